@@ -0,0 +1,415 @@
+use crate::gb::SessionConf;
+use crate::gb::cascade::CascadePlatform;
+use crate::gb::sip::message::{GB_XML_CONTENT_TYPE, target_uri};
+use crate::gb::sip::native_runtime::NativeSipRuntimeHandle;
+use crate::gb::sip::runtime_cache::{SipResponseResult, SipRuntimeCache, recv_with_timeout};
+use crate::gb::sip::xml;
+use crate::service::stream_close;
+use base::dashmap::DashMap;
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult};
+use base::log::{error, info, warn};
+use base::tokio;
+use base::tokio::select;
+use base::tokio::sync::oneshot;
+use base::tokio::time::Instant;
+use base::tokio_util::sync::CancellationToken;
+use gmv_pjsip::{
+    AuthAlgorithm, AuthCredential, CredentialKind, SipOutboundMessage, SipOutboundRegister,
+    SipTransportProtocol,
+};
+use std::future::Future;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 运行中的上级平台：server_id -> 客户端
+static CLIENTS: OnceLock<DashMap<String, Arc<CascadeClient>>> = OnceLock::new();
+
+fn clients() -> &'static DashMap<String, Arc<CascadeClient>> {
+    CLIENTS.get_or_init(DashMap::new)
+}
+
+/// 与单个上级平台的注册会话；注册失效后整体重建
+pub struct CascadeClient {
+    pub(super) platform: CascadePlatform,
+    pub(super) local_id: String,
+    local_domain: String,
+    local_ip: Ipv4Addr,
+    local_port: u16,
+    register_call_id: String,
+    sn: AtomicU32,
+    /// 上级点播会话 Call-ID -> stream_id
+    pub(super) dialogs: DashMap<String, String>,
+}
+
+/// 平台任务：注册失败或心跳超限重注册失败后按间隔重试
+pub async fn run_platform(platform: CascadePlatform, cancel_token: CancellationToken) {
+    let retry = Duration::from_secs(platform.retry_sec.max(1) as u64);
+    let conf = SessionConf::get_session_by_conf();
+    loop {
+        let client = Arc::new(CascadeClient::new(platform.clone(), &conf));
+        clients().insert(platform.server_id.clone(), client.clone());
+        client.serve(cancel_token.clone()).await;
+        clients().remove_if(&platform.server_id, |_, current| {
+            Arc::ptr_eq(current, &client)
+        });
+        client.close_dialogs();
+        select! {
+            _ = cancel_token.cancelled() => break,
+            _ = tokio::time::sleep(retry) => {}
+        }
+    }
+}
+
+impl CascadeClient {
+    pub(super) fn new(platform: CascadePlatform, conf: &SessionConf) -> Self {
+        CascadeClient {
+            platform,
+            local_id: conf.domain_id.clone(),
+            local_domain: conf.domain.clone(),
+            local_ip: conf.wan_ip,
+            local_port: conf.wan_port,
+            register_call_id: uuid::Uuid::new_v4().simple().to_string(),
+            sn: AtomicU32::new(1),
+            dialogs: DashMap::new(),
+        }
+    }
+
+    pub(super) fn get(server_id: &str) -> Option<Arc<Self>> {
+        clients().get(server_id).map(|entry| entry.value().clone())
+    }
+
+    async fn serve(&self, cancel_token: CancellationToken) {
+        let server_id = self.platform.server_id.clone();
+        let mut expires = match self.register(self.platform.expires).await {
+            Ok(expires) => expires,
+            Err(err) => {
+                warn!("cascade register failed: server_id={server_id}, err={err}");
+                return;
+            }
+        };
+        info!("cascade registered: server_id={server_id}, expires={expires}");
+        let mut refresh_at = Instant::now() + refresh_after(expires);
+        let mut keepalive =
+            tokio::time::interval(Duration::from_secs(self.platform.keepalive_sec as u64));
+        keepalive.tick().await;
+        let mut misses = KeepaliveMisses::new(self.platform.keepalive_max_miss);
+        loop {
+            select! {
+                _ = cancel_token.cancelled() => {
+                    let _ = self.register(0).await;
+                    info!("cascade unregistered: server_id={server_id}");
+                    return;
+                }
+                _ = tokio::time::sleep_until(refresh_at) => {}
+                _ = keepalive.tick() => {
+                    let res = self.keepalive().await;
+                    let reregister = misses.record(res.is_ok());
+                    if let Err(err) = res {
+                        warn!("cascade keepalive failed: server_id={server_id}, err={err}");
+                    }
+                    if !reregister {
+                        continue;
+                    }
+                    warn!("cascade keepalive lost, re-register: server_id={server_id}");
+                }
+            }
+            match self.register(self.platform.expires).await {
+                Ok(value) => {
+                    expires = value;
+                    refresh_at = Instant::now() + refresh_after(expires);
+                }
+                Err(err) => {
+                    warn!("cascade re-register failed: server_id={server_id}, err={err}");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 注册或注销（expires=0），返回上级确认的有效期
+    async fn register(&self, expires: u32) -> GlobalResult<u32> {
+        register_flow(&self.platform.server_id, expires, |with_auth| {
+            self.send_register(expires, with_auth)
+        })
+        .await
+    }
+
+    async fn send_register(
+        &self,
+        expires: u32,
+        with_auth: bool,
+    ) -> GlobalResult<(u16, Option<u32>)> {
+        let runtime = NativeSipRuntimeHandle::global()?;
+        let operation_id = runtime.next_operation_id();
+        let rx = SipRuntimeCache::global()
+            .insert_native_response_waiter(operation_id, TRANSACTION_TIMEOUT);
+        let register = SipOutboundRegister {
+            operation_id,
+            association_id: 0,
+            protocol: self.protocol(),
+            registrar_uri: self.server_uri(),
+            from_uri: self.local_uri(),
+            contact_uri: self.contact(),
+            call_id: Some(self.register_call_id.clone()),
+            expires,
+            credential: with_auth.then(|| self.credential()),
+        };
+        if let Err(err) = runtime.send_register(register) {
+            SipRuntimeCache::global().remove_native_response_waiter(operation_id);
+            return Err(err);
+        }
+        let resp = self.wait_response(operation_id, rx).await?;
+        Ok((resp.status, resp.metadata.expires))
+    }
+
+    /// 摘要由gmv_pjsip按上级挑战计算
+    fn credential(&self) -> AuthCredential {
+        AuthCredential {
+            username: self
+                .platform
+                .username
+                .clone()
+                .unwrap_or_else(|| self.local_id.clone()),
+            realm: self.platform.server_domain.clone(),
+            secret: self.platform.password.clone(),
+            kind: CredentialKind::PlainPassword,
+            algorithm: AuthAlgorithm::Md5,
+        }
+    }
+
+    async fn keepalive(&self) -> GlobalResult<()> {
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"GB2312\"?>\r\n\
+<Notify>\r\n\
+<CmdType>Keepalive</CmdType>\r\n\
+<SN>{}</SN>\r\n\
+<DeviceID>{}</DeviceID>\r\n\
+<Status>OK</Status>\r\n\
+</Notify>\r\n",
+            self.next_sn(),
+            xml::escape(&self.local_id)
+        );
+        self.send_manscdp(&xml).await
+    }
+
+    /// 向上级发送MANSCDP消息并等待2xx应答
+    pub(super) async fn send_manscdp(&self, xml: &str) -> GlobalResult<()> {
+        let runtime = NativeSipRuntimeHandle::global()?;
+        let operation_id = runtime.next_operation_id();
+        let rx = SipRuntimeCache::global()
+            .insert_native_response_waiter(operation_id, TRANSACTION_TIMEOUT);
+        let message = SipOutboundMessage {
+            operation_id,
+            association_id: 0,
+            protocol: self.protocol(),
+            target_uri: self.server_uri(),
+            from_uri: self.local_uri(),
+            content_type: GB_XML_CONTENT_TYPE.to_string(),
+            body: xml::encode_document(xml, None).to_vec(),
+        };
+        if let Err(err) = runtime.send_upstream_message(message) {
+            SipRuntimeCache::global().remove_native_response_waiter(operation_id);
+            return Err(err);
+        }
+        let resp = self.wait_response(operation_id, rx).await?;
+        if (200..300).contains(&resp.status) {
+            return Ok(());
+        }
+        Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidState.code(),
+            "cascade message rejected",
+            |msg| {
+                error!(
+                    "{msg}: server_id={}, status={}",
+                    self.platform.server_id, resp.status
+                )
+            },
+        ))
+    }
+
+    async fn wait_response(
+        &self,
+        operation_id: u64,
+        rx: oneshot::Receiver<SipResponseResult>,
+    ) -> GlobalResult<SipResponseResult> {
+        recv_with_timeout(rx, TRANSACTION_TIMEOUT)
+            .await
+            .map_err(|reason| {
+                SipRuntimeCache::global().remove_native_response_waiter(operation_id);
+                GlobalError::new_biz_error(
+                    BaseErrorCode::Timeout.code(),
+                    "cascade SIP response timeout",
+                    |msg| {
+                        warn!(
+                            "{msg}: server_id={}, operation_id={operation_id}, reason={reason}",
+                            self.platform.server_id
+                        )
+                    },
+                )
+            })
+    }
+
+    pub(super) fn take_dialog(&self, call_id: &str) -> Option<String> {
+        self.dialogs.remove(call_id).map(|(_, stream_id)| stream_id)
+    }
+
+    fn close_dialogs(&self) {
+        let stream_ids: Vec<String> = self.dialogs.iter().map(|e| e.value().clone()).collect();
+        self.dialogs.clear();
+        for stream_id in stream_ids {
+            stream_close::begin(stream_id);
+        }
+    }
+
+    fn protocol(&self) -> SipTransportProtocol {
+        if self.platform.is_tcp() {
+            SipTransportProtocol::Tcp
+        } else {
+            SipTransportProtocol::Udp
+        }
+    }
+
+    fn server_uri(&self) -> String {
+        target_uri(
+            &self.platform.server_id,
+            &self.platform.server_ip.to_string(),
+            self.platform.server_port,
+            self.protocol(),
+        )
+    }
+
+    fn local_uri(&self) -> String {
+        format!("<sip:{}@{}>", self.local_id, self.local_domain)
+    }
+
+    fn contact(&self) -> String {
+        format!(
+            "<sip:{}@{}:{}>",
+            self.local_id, self.local_ip, self.local_port
+        )
+    }
+
+    pub(super) fn next_sn(&self) -> u32 {
+        self.sn.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// 注册事务：首次不带凭据，上级以401/407挑战时携带凭据重试一次
+async fn register_flow<F, Fut>(server_id: &str, expires: u32, mut send: F) -> GlobalResult<u32>
+where
+    F: FnMut(bool) -> Fut,
+    Fut: Future<Output = GlobalResult<(u16, Option<u32>)>>,
+{
+    let (mut status, mut granted) = send(false).await?;
+    if matches!(status, 401 | 407) {
+        (status, granted) = send(true).await?;
+    }
+    if (200..300).contains(&status) {
+        return Ok(granted.unwrap_or(expires));
+    }
+    Err(GlobalError::new_biz_error(
+        BaseErrorCode::InvalidState.code(),
+        "cascade register rejected",
+        |msg| error!("{msg}: server_id={server_id}, status={status}"),
+    ))
+}
+
+/// 心跳连续失败计数，达到上限需重新注册
+struct KeepaliveMisses {
+    count: u32,
+    max: u32,
+}
+
+impl KeepaliveMisses {
+    fn new(max: u32) -> Self {
+        Self {
+            count: 0,
+            max: max.max(1),
+        }
+    }
+
+    /// 记录一次心跳结果，返回是否需要重新注册
+    fn record(&mut self, ok: bool) -> bool {
+        if ok {
+            self.count = 0;
+            return false;
+        }
+        self.count += 1;
+        if self.count < self.max {
+            return false;
+        }
+        self.count = 0;
+        true
+    }
+}
+
+// 过期前三分之一刷新注册
+fn refresh_after(expires: u32) -> Duration {
+    Duration::from_secs((expires as u64 * 2 / 3).max(30))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::tokio::runtime::Runtime;
+    use std::sync::Mutex;
+
+    #[test]
+    fn register_retries_once_with_credential_after_challenge() {
+        let sent = Mutex::new(Vec::new());
+        let rt = Runtime::new().unwrap();
+        let granted = rt
+            .block_on(register_flow("34020000002000000001", 3600, |with_auth| {
+                sent.lock().unwrap().push(with_auth);
+                async move {
+                    Ok(if with_auth {
+                        (200, Some(1800))
+                    } else {
+                        (401, None)
+                    })
+                }
+            }))
+            .unwrap();
+        assert_eq!(granted, 1800);
+        assert_eq!(*sent.lock().unwrap(), vec![false, true]);
+
+        // 凭据仍被拒绝时不再重试
+        sent.lock().unwrap().clear();
+        let res = rt.block_on(register_flow("34020000002000000001", 3600, |with_auth| {
+            sent.lock().unwrap().push(with_auth);
+            async { Ok((401, None)) }
+        }));
+        assert!(res.is_err());
+        assert_eq!(*sent.lock().unwrap(), vec![false, true]);
+
+        // 无需认证时沿用请求的有效期
+        let granted = rt
+            .block_on(register_flow("34020000002000000001", 3600, |_| async {
+                Ok((200, None))
+            }))
+            .unwrap();
+        assert_eq!(granted, 3600);
+    }
+
+    #[test]
+    fn keepalive_misses_trigger_reregister_at_limit() {
+        let mut misses = KeepaliveMisses::new(3);
+        assert!(!misses.record(false));
+        assert!(!misses.record(false));
+        // 成功心跳清零计数
+        assert!(!misses.record(true));
+        assert!(!misses.record(false));
+        assert!(!misses.record(false));
+        assert!(misses.record(false));
+        // 重新注册后重新计数
+        assert!(!misses.record(false));
+
+        let mut misses = KeepaliveMisses::new(0);
+        assert!(misses.record(false));
+    }
+}
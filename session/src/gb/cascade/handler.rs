@@ -0,0 +1,651 @@
+//! 上级平台请求处理：目录/设备信息/状态查询应答与点播
+
+use crate::gb::cascade::client::CascadeClient;
+use crate::gb::sip::GbIncomingInviteEvent;
+use crate::gb::sip::native_runtime::NativeSipRuntimeHandle;
+use crate::gb::sip::runtime_cache::sip_user;
+use crate::gb::sip::xml;
+use crate::register::core::Register;
+use crate::service::{api_serv, stream_close};
+use crate::state::StreamConf;
+use crate::state::model::CustomMediaConfig;
+use crate::state::session::{AccessMode, Cache};
+use crate::storage::entity::{CHANNEL_STATUS_ON, GmvDeviceChannel, GmvDeviceChannelQo};
+use base::chrono::Local;
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult};
+use base::log::{debug, error, info, warn};
+use gmv_pjsip::SipInviteResponse;
+use shared::info::format::RtpPs;
+use shared::info::output::{Gb28181PsOutput, OutputKind, RtpPushTarget, RtpTransport};
+
+// 单条目录应答携带的通道数，避免UDP分片
+const CATALOG_ITEMS_PER_MESSAGE: usize = 10;
+const CHANNEL_PAGE_SIZE: u32 = 500;
+const PS_PAYLOAD_TYPE: u8 = 96;
+
+/// 上级MESSAGE已由运行时应答200，此处仅处理查询
+pub(super) async fn on_message(client: &CascadeClient, items: Vec<(String, String)>) {
+    if !items.iter().any(|(key, _)| key.starts_with("Query,")) {
+        return;
+    }
+    let cmd_type = xml::value(&items, "Query,CmdType").unwrap_or_default();
+    let sn = xml::value(&items, "Query,SN").unwrap_or("0").to_string();
+    let device_id = xml::value(&items, "Query,DeviceID")
+        .unwrap_or_default()
+        .to_string();
+    let res = match cmd_type {
+        "Catalog" => answer_catalog(client, &sn).await,
+        "DeviceInfo" => answer_device_info(client, &sn, &device_id).await,
+        "DeviceStatus" => answer_device_status(client, &sn, &device_id).await,
+        _ => {
+            debug!(
+                "cascade query not supported: server_id={}, cmd_type={cmd_type}",
+                client.platform.server_id
+            );
+            Ok(())
+        }
+    };
+    if let Err(err) = res {
+        warn!(
+            "cascade answer {cmd_type} failed: server_id={}, err={err}",
+            client.platform.server_id
+        );
+    }
+}
+
+/// 分页读取全部通道，附带在线状态
+async fn load_channels() -> GlobalResult<Vec<(GmvDeviceChannel, bool)>> {
+    let session_ids = Register::session_device_ids();
+    let mut qo = GmvDeviceChannelQo {
+        limit: CHANNEL_PAGE_SIZE,
+        ..Default::default()
+    };
+    let mut channels = Vec::new();
+    loop {
+        let (total, page) = GmvDeviceChannel::query_gmv_device_channel_page(&qo).await?;
+        let len = page.len() as u32;
+        channels.extend(page.into_iter().map(|dc| {
+            let online = channel_online(&dc, &session_ids);
+            (dc, online)
+        }));
+        qo.offset += len;
+        if len == 0 || qo.offset as i64 >= total {
+            break;
+        }
+    }
+    Ok(channels)
+}
+
+fn channel_online(dc: &GmvDeviceChannel, session_ids: &[String]) -> bool {
+    session_ids.contains(&dc.device_id)
+        && CHANNEL_STATUS_ON
+            .iter()
+            .any(|s| dc.status.eq_ignore_ascii_case(s))
+}
+
+async fn answer_catalog(client: &CascadeClient, sn: &str) -> GlobalResult<()> {
+    let channels = load_channels().await?;
+    for xml in catalog_responses(sn, &client.local_id, &channels) {
+        client.send_manscdp(&xml).await?;
+    }
+    info!(
+        "cascade catalog answered: server_id={}, sum_num={}",
+        client.platform.server_id,
+        channels.len()
+    );
+    Ok(())
+}
+
+/// 按页拆分目录应答，每页携带总数；无通道时仍应答一条空列表
+fn catalog_responses(
+    sn: &str,
+    local_id: &str,
+    channels: &[(GmvDeviceChannel, bool)],
+) -> Vec<String> {
+    if channels.is_empty() {
+        return vec![build_catalog_response(sn, local_id, 0, &[])];
+    }
+    channels
+        .chunks(CATALOG_ITEMS_PER_MESSAGE)
+        .map(|chunk| build_catalog_response(sn, local_id, channels.len(), chunk))
+        .collect()
+}
+
+fn build_catalog_response(
+    sn: &str,
+    local_id: &str,
+    sum_num: usize,
+    channels: &[(GmvDeviceChannel, bool)],
+) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"GB2312\"?>\r\n\
+<Response>\r\n\
+<CmdType>Catalog</CmdType>\r\n\
+<SN>{}</SN>\r\n\
+<DeviceID>{}</DeviceID>\r\n\
+<SumNum>{sum_num}</SumNum>\r\n\
+<DeviceList Num=\"{}\">\r\n",
+        xml::escape(sn),
+        xml::escape(local_id),
+        channels.len()
+    );
+    for (dc, online) in channels {
+        let opt = |v: &Option<String>| xml::escape(v.as_deref().unwrap_or_default());
+        xml.push_str("<Item>\r\n");
+        xml.push_str(&format!(
+            "<DeviceID>{}</DeviceID>\r\n",
+            xml::escape(&dc.channel_id)
+        ));
+        xml.push_str(&format!("<Name>{}</Name>\r\n", opt(&dc.name)));
+        xml.push_str(&format!(
+            "<Manufacturer>{}</Manufacturer>\r\n",
+            opt(&dc.manufacturer)
+        ));
+        xml.push_str(&format!("<Model>{}</Model>\r\n", opt(&dc.model)));
+        xml.push_str(&format!("<Owner>{}</Owner>\r\n", opt(&dc.owner)));
+        xml.push_str(&format!(
+            "<CivilCode>{}</CivilCode>\r\n",
+            opt(&dc.civil_code)
+        ));
+        xml.push_str(&format!("<Address>{}</Address>\r\n", opt(&dc.address)));
+        xml.push_str(&format!(
+            "<Parental>{}</Parental>\r\n",
+            dc.parental.unwrap_or(0)
+        ));
+        // 通道统一挂在本级平台下
+        xml.push_str(&format!(
+            "<ParentID>{}</ParentID>\r\n",
+            xml::escape(local_id)
+        ));
+        xml.push_str(
+            "<SafetyWay>0</SafetyWay>\r\n<RegisterWay>1</RegisterWay>\r\n<Secrecy>0</Secrecy>\r\n",
+        );
+        xml.push_str(&format!(
+            "<Status>{}</Status>\r\n",
+            if *online { "ON" } else { "OFF" }
+        ));
+        if let (Some(lng), Some(lat)) = (dc.longitude, dc.latitude) {
+            xml.push_str(&format!(
+                "<Longitude>{lng}</Longitude>\r\n<Latitude>{lat}</Latitude>\r\n"
+            ));
+        }
+        if let Some(ptz_type) = dc.ptz_type {
+            xml.push_str(&format!(
+                "<Info>\r\n<PTZType>{ptz_type}</PTZType>\r\n</Info>\r\n"
+            ));
+        }
+        xml.push_str("</Item>\r\n");
+    }
+    xml.push_str("</DeviceList>\r\n</Response>\r\n");
+    xml
+}
+
+async fn answer_device_info(client: &CascadeClient, sn: &str, device_id: &str) -> GlobalResult<()> {
+    let body = if device_id == client.local_id {
+        let (total, _) =
+            GmvDeviceChannel::query_gmv_device_channel_page(&GmvDeviceChannelQo::default()).await?;
+        format!(
+            "<DeviceName>GMV</DeviceName>\r\n\
+<Result>OK</Result>\r\n\
+<Manufacturer>GMV</Manufacturer>\r\n\
+<Model>GMV-SESSION</Model>\r\n\
+<Firmware>{}</Firmware>\r\n\
+<Channel>{total}</Channel>\r\n",
+            env!("CARGO_PKG_VERSION")
+        )
+    } else {
+        match GmvDeviceChannel::query_gmv_device_channel_by_channel_id(device_id).await? {
+            Some(dc) => format!(
+                "<DeviceName>{}</DeviceName>\r\n\
+<Result>OK</Result>\r\n\
+<Manufacturer>{}</Manufacturer>\r\n\
+<Model>{}</Model>\r\n\
+<Channel>1</Channel>\r\n",
+                xml::escape(dc.name.as_deref().unwrap_or_default()),
+                xml::escape(dc.manufacturer.as_deref().unwrap_or_default()),
+                xml::escape(dc.model.as_deref().unwrap_or_default()),
+            ),
+            None => "<Result>ERROR</Result>\r\n".to_string(),
+        }
+    };
+    client
+        .send_manscdp(&build_response("DeviceInfo", sn, device_id, &body))
+        .await
+}
+
+async fn answer_device_status(
+    client: &CascadeClient,
+    sn: &str,
+    device_id: &str,
+) -> GlobalResult<()> {
+    let online = if device_id == client.local_id {
+        Some(true)
+    } else {
+        GmvDeviceChannel::query_gmv_device_channel_by_channel_id(device_id)
+            .await?
+            .map(|dc| channel_online(&dc, &Register::session_device_ids()))
+    };
+    let body = match online {
+        Some(online) => format!(
+            "<Result>OK</Result>\r\n\
+<Online>{}</Online>\r\n\
+<Status>OK</Status>\r\n\
+<Encode>ON</Encode>\r\n\
+<Record>OFF</Record>\r\n\
+<DeviceTime>{}</DeviceTime>\r\n",
+            if online { "ONLINE" } else { "OFFLINE" },
+            Local::now().format("%Y-%m-%dT%H:%M:%S")
+        ),
+        None => "<Result>ERROR</Result>\r\n".to_string(),
+    };
+    client
+        .send_manscdp(&build_response("DeviceStatus", sn, device_id, &body))
+        .await
+}
+
+fn build_response(cmd_type: &str, sn: &str, device_id: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"GB2312\"?>\r\n\
+<Response>\r\n\
+<CmdType>{cmd_type}</CmdType>\r\n\
+<SN>{}</SN>\r\n\
+<DeviceID>{}</DeviceID>\r\n\
+{body}</Response>\r\n",
+        xml::escape(sn),
+        xml::escape(device_id),
+    )
+}
+
+/// 上级点播邀约中的媒体参数
+struct InviteOffer {
+    session_name: String,
+    am: AccessMode,
+    ip: String,
+    port: u16,
+    ssrc: u32,
+    transport: RtpTransport,
+    st: u32,
+    et: u32,
+}
+
+fn parse_offer(sdp: &[u8]) -> GlobalResult<InviteOffer> {
+    let invalid = |reason: &str| {
+        GlobalError::new_biz_error(
+            BaseErrorCode::InvalidRequest.code(),
+            "cascade invite sdp invalid",
+            |msg| error!("{msg}: {reason}"),
+        )
+    };
+    let sdp = String::from_utf8_lossy(sdp);
+    let mut session_name = None;
+    let mut ip = None;
+    let mut media = None;
+    let mut ssrc = None;
+    let (mut st, mut et) = (0, 0);
+    let mut setup_active = false;
+    for line in sdp.lines().map(str::trim) {
+        let Some((kind, value)) = line.split_once('=') else {
+            continue;
+        };
+        match kind {
+            "s" => session_name = Some(value.trim().to_string()),
+            "c" => ip = value.split_whitespace().nth(2).map(str::to_string),
+            "t" => {
+                let mut parts = value.split_whitespace();
+                st = parts.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+                et = parts.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+            }
+            "m" if media.is_none() && value.starts_with("video") => {
+                let mut parts = value.split_whitespace().skip(1);
+                let port = parts.next().and_then(|v| v.parse::<u16>().ok());
+                let proto = parts.next().unwrap_or_default().to_ascii_uppercase();
+                media = port.map(|port| (port, proto.starts_with("TCP")));
+            }
+            "a" if value.trim().eq_ignore_ascii_case("setup:active") => setup_active = true,
+            "y" => ssrc = value.trim().parse::<u32>().ok(),
+            _ => {}
+        }
+    }
+    let session_name = session_name.ok_or_else(|| invalid("missing s="))?;
+    let am = match session_name.as_str() {
+        "Play" => AccessMode::Live,
+        "Playback" => AccessMode::Back,
+        "Download" => AccessMode::Down,
+        _ => return Err(invalid("unsupported session name")),
+    };
+    let ip = ip.ok_or_else(|| invalid("missing c="))?;
+    let (port, tcp) = media.ok_or_else(|| invalid("missing video m="))?;
+    let ssrc = ssrc.ok_or_else(|| invalid("missing y="))?;
+    // 仅支持由本级主动连接上级的TCP方式
+    if tcp && setup_active {
+        return Err(invalid("tcp setup:active not supported"));
+    }
+    if !matches!(am, AccessMode::Live) && (st == 0 || et <= st) {
+        return Err(invalid("invalid playback range"));
+    }
+    Ok(InviteOffer {
+        session_name,
+        am,
+        ip,
+        port,
+        ssrc,
+        transport: if tcp {
+            RtpTransport::Tcp
+        } else {
+            RtpTransport::Udp
+        },
+        st,
+        et,
+    })
+}
+
+fn build_answer(channel_id: &str, media_ip: &str, media_port: u16, offer: &InviteOffer) -> String {
+    let mut sdp = String::with_capacity(256);
+    sdp.push_str("v=0\r\n");
+    sdp.push_str(&format!("o={channel_id} 0 0 IN IP4 {media_ip}\r\n"));
+    sdp.push_str(&format!("s={}\r\n", offer.session_name));
+    sdp.push_str(&format!("c=IN IP4 {media_ip}\r\n"));
+    sdp.push_str(&format!("t={} {}\r\n", offer.st, offer.et));
+    match offer.transport {
+        RtpTransport::Udp => sdp.push_str(&format!(
+            "m=video {media_port} RTP/AVP {PS_PAYLOAD_TYPE}\r\n"
+        )),
+        RtpTransport::Tcp => {
+            sdp.push_str(&format!(
+                "m=video {media_port} TCP/RTP/AVP {PS_PAYLOAD_TYPE}\r\n"
+            ));
+            sdp.push_str("a=setup:active\r\n");
+            sdp.push_str("a=connection:new\r\n");
+        }
+    }
+    sdp.push_str("a=sendonly\r\n");
+    sdp.push_str(&format!("a=rtpmap:{PS_PAYLOAD_TYPE} PS/90000\r\n"));
+    sdp.push_str(&format!("y={:010}\r\n", offer.ssrc));
+    sdp
+}
+
+pub(super) async fn on_invite(
+    client: &CascadeClient,
+    runtime: &NativeSipRuntimeHandle,
+    invite: GbIncomingInviteEvent,
+) {
+    let call_id = invite.call_id.clone();
+    let res = accept_invite(client, &invite).await;
+    let response = invite_response(&call_id, &res);
+    if let Err(err) = runtime.respond_invite(response) {
+        warn!(
+            "cascade invite response failed: server_id={}, call_id={call_id}, err={err}",
+            client.platform.server_id
+        );
+        if let Ok((stream_id, _)) = res {
+            stream_close::begin(stream_id);
+        }
+        return;
+    }
+    if let Ok((stream_id, _)) = res {
+        client.dialogs.insert(call_id, stream_id);
+    }
+}
+
+fn invite_response(
+    call_id: &str,
+    res: &Result<(String, String), (u16, &'static str)>,
+) -> SipInviteResponse {
+    match res {
+        Ok((_, sdp)) => SipInviteResponse {
+            call_id: call_id.to_string(),
+            status_code: 200,
+            reason: Some("OK".into()),
+            content_type: Some("application/sdp".into()),
+            body: sdp.clone().into_bytes(),
+        },
+        Err((code, reason)) => SipInviteResponse {
+            call_id: call_id.to_string(),
+            status_code: *code,
+            reason: Some((*reason).into()),
+            content_type: None,
+            body: Vec::new(),
+        },
+    }
+}
+
+async fn accept_invite(
+    client: &CascadeClient,
+    invite: &GbIncomingInviteEvent,
+) -> Result<(String, String), (u16, &'static str)> {
+    // 上级点播的通道编码位于To头
+    let channel_id = sip_user(&invite.to).ok_or((400, "Bad Request"))?;
+    let offer =
+        parse_offer(invite.remote_sdp.as_bytes()).map_err(|_| (488, "Not Acceptable Here"))?;
+    let dc = GmvDeviceChannel::query_gmv_device_channel_by_channel_id(&channel_id)
+        .await
+        .map_err(|_| (500, "Server Internal Error"))?
+        .ok_or((404, "Not Found"))?;
+    let device_id = dc.device_id;
+    let media_config = CustomMediaConfig {
+        output: OutputKind::Gb28181Ps(Gb28181PsOutput {
+            fmt: RtpPs::default(),
+            target: RtpPushTarget {
+                ip: offer.ip.clone(),
+                port: offer.port,
+                ssrc: offer.ssrc,
+                transport: offer.transport,
+                payload_type: Some(PS_PAYLOAD_TYPE),
+            },
+        }),
+        codec: None,
+        filter: Default::default(),
+    };
+    let setup_lock = Cache::stream_setup_lock(&device_id, &channel_id, offer.am);
+    let _setup_guard = setup_lock.lock().await;
+    let (stream_id, node_name, _) = api_serv::start_invite_stream(
+        &device_id,
+        &channel_id,
+        &String::new(),
+        offer.am,
+        offer.st,
+        offer.et,
+        None,
+        Some(media_config),
+    )
+    .await
+    .map_err(|err| {
+        warn!(
+            "cascade invite failed: server_id={}, channel_id={channel_id}, err={err}",
+            client.platform.server_id
+        );
+        (500, "Server Internal Error")
+    })?;
+    let Some(node) = StreamConf::get_stream_conf().node_map.get(&node_name) else {
+        stream_close::begin(stream_id);
+        return Err((500, "Server Internal Error"));
+    };
+    info!(
+        "cascade invite accepted: server_id={}, channel_id={channel_id}, stream_id={stream_id}, target={}:{}",
+        client.platform.server_id, offer.ip, offer.port
+    );
+    let sdp = build_answer(&channel_id, &node.pub_ip.to_string(), node.pub_port, &offer);
+    Ok((stream_id, sdp))
+}
+
+/// BYE已由运行时在对话内应答
+pub(super) fn on_bye(client: &CascadeClient, call_id: &str) {
+    if let Some(stream_id) = client.take_dialog(call_id) {
+        info!(
+            "cascade bye: server_id={}, stream_id={stream_id}",
+            client.platform.server_id
+        );
+        stream_close::begin(stream_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gb::SessionConf;
+    use crate::gb::cascade::CascadePlatform;
+    use base::tokio::runtime::Runtime;
+    use gmv_pjsip::{SipAssociation, SipDialogSnapshot, SipTransportProtocol};
+
+    fn test_client() -> CascadeClient {
+        let platform = CascadePlatform {
+            enable: true,
+            server_id: "34020000002000000001".into(),
+            server_domain: "3402000000".into(),
+            server_ip: "10.1.1.1".parse().unwrap(),
+            server_port: 5060,
+            transport: "udp".into(),
+            username: None,
+            password: "12345678".into(),
+            expires: 3600,
+            keepalive_sec: 60,
+            keepalive_max_miss: 3,
+            retry_sec: 30,
+        };
+        let conf = SessionConf {
+            domain: "5101000000".into(),
+            domain_id: "51010000002000000001".into(),
+            http_source: String::new(),
+            lan_ip: "192.168.0.22".parse().unwrap(),
+            wan_ip: "192.168.0.22".parse().unwrap(),
+            lan_port: 15060,
+            wan_port: 15060,
+        };
+        CascadeClient::new(platform, &conf)
+    }
+
+    fn upper_invite(to: &str, sdp: &str) -> GbIncomingInviteEvent {
+        let local_addr = "192.168.0.22:15060".parse().unwrap();
+        let remote_addr = "10.1.1.1:5060".parse().unwrap();
+        GbIncomingInviteEvent {
+            call_id: "cascade-call".into(),
+            cseq: 1,
+            association: SipAssociation {
+                local_addr,
+                remote_addr,
+                protocol: SipTransportProtocol::Udp,
+            },
+            dialog_snapshot: SipDialogSnapshot {
+                call_id: "cascade-call".into(),
+                local_uri: "sip:51010000002000000001@5101000000".into(),
+                remote_uri: "sip:34020000002000000001@3402000000".into(),
+                local_tag: "local".into(),
+                remote_tag: "remote".into(),
+                local_cseq: 1,
+                remote_target: "sip:34020000002000000001@10.1.1.1:5060".into(),
+                route_set: Vec::new(),
+                protocol: SipTransportProtocol::Udp,
+                association_id: 0,
+                local_addr,
+                remote_addr,
+            },
+            remote_sdp: sdp.into(),
+            from: "<sip:34020000002000000001@3402000000>;tag=remote".into(),
+            to: to.into(),
+            subject: None,
+        }
+    }
+
+    fn channel(index: usize) -> (GmvDeviceChannel, bool) {
+        let dc = GmvDeviceChannel {
+            device_id: "34020000001110000001".into(),
+            channel_id: format!("3402000000132{index:07}"),
+            status: "ON".into(),
+            ..Default::default()
+        };
+        (dc, index % 2 == 0)
+    }
+
+    #[test]
+    fn catalog_responses_page_with_total_sum_num() {
+        let channels: Vec<_> = (0..23).map(channel).collect();
+        let pages = catalog_responses("17", "51010000002000000001", &channels);
+        assert_eq!(pages.len(), 3);
+        for page in &pages {
+            assert!(page.contains("<SN>17</SN>"));
+            assert!(page.contains("<SumNum>23</SumNum>"));
+        }
+        assert!(pages[0].contains("<DeviceList Num=\"10\">"));
+        assert!(pages[2].contains("<DeviceList Num=\"3\">"));
+        assert_eq!(pages[0].matches("<Item>").count(), 10);
+        assert!(pages[2].contains("<DeviceID>34020000001320000022</DeviceID>"));
+        assert!(pages[0].contains("<Status>ON</Status>"));
+        assert!(pages[0].contains("<Status>OFF</Status>"));
+
+        let empty = catalog_responses("18", "51010000002000000001", &[]);
+        assert_eq!(empty.len(), 1);
+        assert!(empty[0].contains("<SumNum>0</SumNum>"));
+        assert!(empty[0].contains("<DeviceList Num=\"0\">"));
+    }
+
+    #[test]
+    fn invite_answer_and_bye_track_cascade_dialog() {
+        let client = test_client();
+        let rt = Runtime::new().unwrap();
+        // 请求校验失败在查库前直接拒绝
+        let invalid_to = upper_invite("tel:110", "");
+        assert_eq!(
+            rt.block_on(accept_invite(&client, &invalid_to)),
+            Err((400, "Bad Request"))
+        );
+        let invalid_sdp = upper_invite("<sip:34020000001320000001@3402000000>", "v=0\r\n");
+        assert_eq!(
+            rt.block_on(accept_invite(&client, &invalid_sdp)),
+            Err((488, "Not Acceptable Here"))
+        );
+
+        let rejected = invite_response("cascade-call", &Err((488, "Not Acceptable Here")));
+        assert_eq!(rejected.status_code, 488);
+        assert!(rejected.content_type.is_none());
+        assert!(rejected.body.is_empty());
+
+        let accepted = invite_response(
+            "cascade-call",
+            &Ok(("stream-1".into(), "v=0\r\ny=0100000001\r\n".into())),
+        );
+        assert_eq!(accepted.call_id, "cascade-call");
+        assert_eq!(accepted.status_code, 200);
+        assert_eq!(accepted.content_type.as_deref(), Some("application/sdp"));
+        assert_eq!(accepted.body, b"v=0\r\ny=0100000001\r\n");
+
+        client
+            .dialogs
+            .insert("cascade-call".into(), "stream-1".into());
+        on_bye(&client, "other-call");
+        assert!(client.dialogs.contains_key("cascade-call"));
+        on_bye(&client, "cascade-call");
+        assert!(client.dialogs.is_empty());
+        on_bye(&client, "cascade-call");
+    }
+
+    #[test]
+    fn playback_offer_maps_to_ps_push_target() {
+        let sdp = "v=0\r\n\
+o=34020000001320000001 0 0 IN IP4 10.1.1.2\r\n\
+s=Playback\r\n\
+u=34020000001320000001:0\r\n\
+c=IN IP4 10.1.1.2\r\n\
+t=1700000000 1700003600\r\n\
+m=video 30000 TCP/RTP/AVP 96 98\r\n\
+a=recvonly\r\n\
+a=setup:passive\r\n\
+a=rtpmap:96 PS/90000\r\n\
+y=1100000001\r\n";
+        let offer = parse_offer(sdp.as_bytes()).unwrap();
+        assert!(matches!(offer.am, AccessMode::Back));
+        assert_eq!(offer.ip, "10.1.1.2");
+        assert_eq!(offer.port, 30000);
+        assert_eq!(offer.ssrc, 1_100_000_001);
+        assert_eq!(offer.transport, RtpTransport::Tcp);
+        assert_eq!((offer.st, offer.et), (1_700_000_000, 1_700_003_600));
+
+        let answer = build_answer("34020000001320000001", "192.168.0.22", 18568, &offer);
+        assert!(answer.contains("m=video 18568 TCP/RTP/AVP 96\r\n"));
+        assert!(answer.contains("a=setup:active\r\n"));
+        assert!(answer.contains("y=1100000001\r\n"));
+
+        let active = sdp.replace("setup:passive", "setup:active");
+        assert!(parse_offer(active.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! GB28181 级联：向上级平台注册、保活，并应答其目录查询与点播
+//!
+//! 信令统一经由 `gmv_pjsip` 运行时收发，按 From 用户区分上级平台与下级设备
+
+use crate::gb::sip::native_runtime::NativeSipRuntimeHandle;
+use crate::gb::sip::runtime_cache::sip_user;
+use crate::gb::sip::{GbIncomingInviteEvent, xml};
+use base::cfg_lib::conf;
+use base::cfg_lib::conf::{CheckFromConf, FieldCheckError};
+use base::log::warn;
+use base::serde::Deserialize;
+use base::serde_default;
+use base::tokio::runtime::Handle;
+use base::tokio_util::sync::CancellationToken;
+use gmv_pjsip::{SipRuntimeEvent, SipRuntimeEventKind};
+use regex::Regex;
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+
+pub mod client;
+mod handler;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(crate = "base::serde")]
+#[conf(prefix = "server.cascade", check)]
+pub struct CascadeConf {
+    #[serde(default = "default_platforms")]
+    pub platforms: Vec<CascadePlatform>,
+}
+serde_default!(default_platforms, Vec<CascadePlatform>, Vec::new());
+
+/// 上级平台
+#[derive(Debug, Deserialize, Clone)]
+#[serde(crate = "base::serde")]
+pub struct CascadePlatform {
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+    /// 上级SIP服务器编码
+    pub server_id: String,
+    /// 上级SIP域
+    pub server_domain: String,
+    pub server_ip: Ipv4Addr,
+    pub server_port: u16,
+    /// udp|tcp
+    #[serde(default = "default_transport")]
+    pub transport: String,
+    /// 注册用户名，缺省为本级domain_id
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_expires")]
+    pub expires: u32,
+    #[serde(default = "default_keepalive_sec")]
+    pub keepalive_sec: u32,
+    /// 心跳连续失败次数达到后重新注册
+    #[serde(default = "default_keepalive_max_miss")]
+    pub keepalive_max_miss: u32,
+    /// 注册失败或断线后的重试间隔
+    #[serde(default = "default_retry_sec")]
+    pub retry_sec: u32,
+}
+serde_default!(default_enable, bool, true);
+serde_default!(default_transport, String, "udp".to_string());
+serde_default!(default_expires, u32, 3600);
+serde_default!(default_keepalive_sec, u32, 60);
+serde_default!(default_keepalive_max_miss, u32, 3);
+serde_default!(default_retry_sec, u32, 30);
+
+impl CascadePlatform {
+    pub fn is_tcp(&self) -> bool {
+        self.transport.eq_ignore_ascii_case("tcp")
+    }
+}
+
+impl CheckFromConf for CascadeConf {
+    fn _field_check(&self) -> Result<(), FieldCheckError> {
+        let re = Regex::new(r"^\d{20}$").unwrap();
+        let mut server_ids = HashSet::new();
+        for platform in self.platforms.iter().filter(|p| p.enable) {
+            if !re.is_match(&platform.server_id) {
+                return Err(FieldCheckError::BizError(format!(
+                    "server.cascade.platforms.server_id must be 20 digits: {}",
+                    platform.server_id
+                )));
+            }
+            if !matches!(&*platform.transport.to_ascii_lowercase(), "udp" | "tcp") {
+                return Err(FieldCheckError::BizError(format!(
+                    "server.cascade.platforms.transport必须为udp|tcp: {}",
+                    platform.transport
+                )));
+            }
+            // 上级请求按 server_id 路由，不能重复
+            if !server_ids.insert(platform.server_id.as_str()) {
+                return Err(FieldCheckError::BizError(format!(
+                    "server.cascade.platforms.server_id重复: {}",
+                    platform.server_id
+                )));
+            }
+            if platform.expires < 60 || platform.keepalive_sec == 0 {
+                return Err(FieldCheckError::BizError(format!(
+                    "server.cascade.platforms expires需不小于60且keepalive_sec大于0: {}",
+                    platform.server_id
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CascadeConf {
+    pub fn get_cascade_conf() -> Self {
+        CascadeConf::conf()
+    }
+
+    /// 为每个启用的上级平台启动注册与信令任务
+    pub fn run(cancel_token: CancellationToken) {
+        let handle = Handle::current();
+        for platform in Self::get_cascade_conf()
+            .platforms
+            .into_iter()
+            .filter(|p| p.enable)
+        {
+            handle.spawn(client::run_platform(platform, cancel_token.child_token()));
+        }
+    }
+}
+
+/// 运行时事件分流：来自已启用上级平台的请求交由级联处理，返回是否已消费
+pub fn dispatch_native(event: &SipRuntimeEvent, runtime: &NativeSipRuntimeHandle) -> bool {
+    if !matches!(
+        event.kind,
+        SipRuntimeEventKind::RequestReceived | SipRuntimeEventKind::IncomingInvite
+    ) {
+        return false;
+    }
+    let Some(client) = event
+        .from_header
+        .as_deref()
+        .and_then(sip_user)
+        .and_then(|server_id| client::CascadeClient::get(&server_id))
+    else {
+        return false;
+    };
+    if event.kind == SipRuntimeEventKind::IncomingInvite {
+        match GbIncomingInviteEvent::from_native(event) {
+            Some(invite) => {
+                let runtime = runtime.clone();
+                base::tokio::spawn(
+                    async move { handler::on_invite(&client, &runtime, invite).await },
+                );
+            }
+            None => warn!(
+                "cascade invite missing dialog identity: server_id={}",
+                client.platform.server_id
+            ),
+        }
+        return true;
+    }
+    match event
+        .method
+        .as_deref()
+        .map(str::to_ascii_uppercase)
+        .as_deref()
+    {
+        Some("MESSAGE") => match xml::parse_items(&event.body) {
+            Ok(items) => {
+                base::tokio::spawn(async move { handler::on_message(&client, items).await });
+            }
+            Err(err) => warn!(
+                "cascade message invalid: server_id={}, err={err}",
+                client.platform.server_id
+            ),
+        },
+        Some("BYE") => {
+            if let Some(call_id) = event.call_id.as_deref() {
+                handler::on_bye(&client, call_id);
+            }
+        }
+        // 应答由运行时完成，ACK无需业务处理
+        Some("ACK") => {}
+        _ => return false,
+    }
+    true
+}
@@ -17,6 +17,7 @@ use std::net::{Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
 use std::str::FromStr;
 use std::sync::Arc;
 
+pub mod cascade;
 pub mod sip;
 
 #[derive(Clone, Debug, Deserialize)]
@@ -98,6 +99,7 @@ impl SessionConf {
         cancel_token: CancellationToken,
     ) -> GlobalResult<()> {
         db_task::init(cancel_token.child_token());
+        crate::service::webhook_serv::init(cancel_token.child_token());
         let session_conf = SessionConf::get_session_by_conf();
         crate::storage::ssrc_sequence::SsrcSequence::initialize(&session_conf.domain_id).await?;
         let auth_cache = sip::auth::init_global().await?;
@@ -122,6 +124,7 @@ impl SessionConf {
         handle.spawn(SessionConf::heart_server());
         handle.spawn(sip::auth::run_cleanup_task(cancel_token.child_token()));
         handle.spawn(sip::run_cleanup_task(cancel_token.child_token()));
+        cascade::CascadeConf::run(cancel_token.child_token());
         let native_shutdown = cancel_token.child_token();
         handle.spawn(async move {
             native_shutdown.cancelled().await;
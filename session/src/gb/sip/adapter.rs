@@ -2,24 +2,25 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use base::chrono::{Duration as TimeDelta, Local};
-use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use base::exception::{GlobalError, GlobalResult};
 use base::log::{debug, error, info, warn};
 use base::net::state::{Association, Protocol};
 use gmv_pjsip::{SipAssociation, SipMethod, SipTransportProtocol};
 
 use crate::register::core::{DeviceSession, Register};
-use crate::service::{api_serv, stream_close};
-use crate::state::session::Cache as GeneralCache;
-use crate::state::{AlarmConf, model::AlarmInfo};
+use crate::service::lifecycle_serv::{self, DeviceLifecycle};
+use crate::service::{alarm_serv, api_serv, catalog_serv, stream_close};
+use crate::state::AlarmConf;
+use crate::state::model::{PresetItem, RecordInfoItem};
+use crate::state::session::{Cache as GeneralCache, SubscriptionKind};
 use crate::storage::db_task::{self, DbTask};
-use crate::storage::entity::GmvDevice;
+use crate::storage::entity::{GmvDevice, GmvMobilePosition};
 
 use super::bye::GbByeEvent;
 use super::invite::GbIncomingInviteEvent;
 use super::message::{GbMessageEvent, GbMessageKind};
 use super::register::GbRegisterEvent;
 use super::runtime_cache::SipRuntimeCache;
-use super::xml::KV2Model;
 
 #[derive(Clone, Debug)]
 pub enum GbSipEvent {
@@ -101,7 +102,13 @@ fn apply_bye_event(event: &GbByeEvent) -> GlobalResult<()> {
 fn apply_register_event(event: &GbRegisterEvent) -> GlobalResult<()> {
     let device_id: Arc<str> = Arc::from(event.device_id.as_str());
     if event.is_unregister() {
-        Register::remove_device(&device_id);
+        if let Some(session) = Register::remove_device(&device_id) {
+            lifecycle_serv::record(
+                &event.device_id,
+                DeviceLifecycle::Unregistered,
+                Some(&session.association),
+            );
+        }
         GeneralCache::reset_device_state(&event.device_id);
         db_task::submit(DbTask::ExpireDeviceOnline {
             device_id: event.device_id.clone(),
@@ -132,7 +139,7 @@ fn apply_register_event(event: &GbRegisterEvent) -> GlobalResult<()> {
         session.enable_lr();
     }
     Register::register_device(device_id, session)?;
-    GeneralCache::catalog_subscription_remove(&event.device_id, None);
+    GeneralCache::subscription_remove_device(&event.device_id);
 
     let now = Local::now().naive_local();
     db_task::submit(DbTask::UpsertDevice(GmvDevice {
@@ -176,6 +183,25 @@ fn apply_register_event(event: &GbRegisterEvent) -> GlobalResult<()> {
                 "subscribe catalog after register failed: device_id={query_device_id}, err={err}"
             );
         }
+        if AlarmConf::get_alarm_conf().subscribe {
+            if let Err(err) = super::subscription::subscribe_alarm(&query_device_id, expires).await
+            {
+                warn!(
+                    "subscribe alarm after register failed: device_id={query_device_id}, err={err}"
+                );
+            }
+        }
+        // 恢复重新注册前由业务发起的移动位置订阅
+        if let Some(interval) = GeneralCache::mobile_position_interval(&query_device_id) {
+            if let Err(err) =
+                super::subscription::subscribe_mobile_position(&query_device_id, interval, expires)
+                    .await
+            {
+                warn!(
+                    "resume mobile position subscription failed: device_id={query_device_id}, err={err}"
+                );
+            }
+        }
     });
     Ok(())
 }
@@ -206,7 +232,11 @@ fn apply_message_event(mut event: GbMessageEvent) -> GlobalResult<()> {
         GbMessageKind::Catalog => {
             if let Some(device_id) = device_id {
                 if matches!(event.method.as_ref(), Some(SipMethod::Notify))
-                    && !super::subscription::accept_catalog_notify(&event, device_id)
+                    && !super::subscription::accept_notify(
+                        &event,
+                        device_id,
+                        SubscriptionKind::Catalog,
+                    )
                 {
                     warn!(
                         "ignore catalog NOTIFY outside active subscription: \
@@ -215,15 +245,13 @@ fn apply_message_event(mut event: GbMessageEvent) -> GlobalResult<()> {
                     );
                     return Ok(());
                 }
-                db_task::submit(DbTask::InsertDeviceCatalog {
-                    device_id: device_id.to_string(),
-                    items: std::mem::take(&mut event.items),
-                });
+                catalog_serv::receive(device_id, std::mem::take(&mut event.items));
             } else {
                 warn!("catalog MESSAGE missing device id");
             }
         }
-        GbMessageKind::Alarm => dispatch_alarm(device_id, std::mem::take(&mut event.items))?,
+        GbMessageKind::Alarm => dispatch_alarm(&mut event, device_id)?,
+        GbMessageKind::MobilePosition => dispatch_mobile_position(&event, device_id),
         GbMessageKind::MediaStatus => {
             let channel_id = super::xml::value(&event.items, super::xml::NOTIFY_DEVICE_ID);
             let notify_type = super::xml::value(&event.items, super::xml::NOTIFY_TYPE);
@@ -255,6 +283,52 @@ fn apply_message_event(mut event: GbMessageEvent) -> GlobalResult<()> {
                 );
             }
         }
+        GbMessageKind::RecordInfo => {
+            let (Some(device_id), Some(sn)) = (device_id, event.xml_sn.as_deref()) else {
+                warn!("record info MESSAGE missing device id or SN");
+                return Ok(());
+            };
+            let (sum_num, records) = RecordInfoItem::from_response_items(&event.items);
+            if !SipRuntimeCache::global()
+                .record_info_waiters()
+                .append(sn, device_id, sum_num, records)
+            {
+                debug!("record info response without waiter: device_id={device_id}, sn={sn}");
+            }
+        }
+        GbMessageKind::PresetQuery => {
+            let (Some(device_id), Some(sn)) = (device_id, event.xml_sn.as_deref()) else {
+                warn!("preset query MESSAGE missing device id or SN");
+                return Ok(());
+            };
+            let (sum_num, presets) = PresetItem::from_response_items(&event.items);
+            if !SipRuntimeCache::global()
+                .preset_waiters()
+                .append(sn, device_id, sum_num, presets)
+            {
+                debug!("preset query response without waiter: device_id={device_id}, sn={sn}");
+            }
+        }
+        GbMessageKind::DeviceStatus
+        | GbMessageKind::PtzPosition
+        | GbMessageKind::ConfigDownload
+        | GbMessageKind::DeviceConfig
+        | GbMessageKind::DeviceControl => {
+            let (Some(device_id), Some(sn)) = (device_id, event.xml_sn.as_deref()) else {
+                warn!("query response MESSAGE missing device id or SN");
+                return Ok(());
+            };
+            let items = std::mem::take(&mut event.items);
+            if !SipRuntimeCache::global()
+                .query_waiters()
+                .append(sn, device_id, None, items)
+            {
+                debug!(
+                    "query response without waiter: kind={:?}, device_id={device_id}, sn={sn}",
+                    event.kind
+                );
+            }
+        }
         GbMessageKind::UploadSnapshotFinished | GbMessageKind::Notify => {
             if let Some(session_id) = event.snapshot_session_id.as_deref() {
                 let key = crate::service::edge_serv::rebuild_snapshot_wait_key(session_id);
@@ -268,35 +342,40 @@ fn apply_message_event(mut event: GbMessageEvent) -> GlobalResult<()> {
     Ok(())
 }
 
-fn dispatch_alarm(device_id: Option<&str>, items: Vec<(String, String)>) -> GlobalResult<()> {
+fn dispatch_mobile_position(event: &GbMessageEvent, device_id: Option<&str>) {
+    let Some(device_id) = device_id else {
+        warn!("mobile position MESSAGE missing device id");
+        return;
+    };
+    if matches!(event.method.as_ref(), Some(SipMethod::Notify))
+        && !super::subscription::accept_notify(event, device_id, SubscriptionKind::MobilePosition)
+    {
+        warn!(
+            "ignore mobile position NOTIFY outside active subscription: \
+             device_id={device_id}, call_id={:?}",
+            event.call_id
+        );
+        return;
+    }
+    match GmvMobilePosition::from_notify_items(device_id, &event.items) {
+        Some(position) => db_task::submit(DbTask::InsertMobilePosition(position)),
+        None => warn!("mobile position missing longitude or latitude: device_id={device_id}"),
+    }
+}
+
+fn dispatch_alarm(event: &mut GbMessageEvent, device_id: Option<&str>) -> GlobalResult<()> {
     let Some(device_id) = device_id else {
         warn!("alarm MESSAGE missing device id");
         return Ok(());
     };
-    let conf = AlarmConf::get_alarm_conf();
-    if !conf.enable {
+    if matches!(event.method.as_ref(), Some(SipMethod::Notify))
+        && !super::subscription::accept_notify(event, device_id, SubscriptionKind::Alarm)
+    {
+        warn!(
+            "ignore alarm NOTIFY outside active subscription: device_id={device_id}, call_id={:?}",
+            event.call_id
+        );
         return Ok(());
     }
-    let mut alarm = AlarmInfo::kv_to_model(items)?;
-    alarm.deviceId = device_id.to_string();
-    let Some(push_url) = conf.push_url.clone() else {
-        return Ok(());
-    };
-    base::tokio::spawn(async move {
-        use crate::http::client::HttpBiz;
-
-        let result = async {
-            let client = crate::http::client::HttpClient::template(&push_url)?;
-            client
-                .call_alarm_info(&alarm)
-                .await
-                .hand_log(|msg| error!("{msg}"))?;
-            GlobalResult::<()>::Ok(())
-        }
-        .await;
-        if let Err(err) = result {
-            error!("push alarm failed: device_id={}, err={err}", alarm.deviceId);
-        }
-    });
-    Ok(())
+    alarm_serv::receive(device_id, std::mem::take(&mut event.items))
 }
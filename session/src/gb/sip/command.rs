@@ -23,7 +23,10 @@ use shared::info::media_info_ext::MediaExt;
 
 use crate::gb::SessionConf;
 use crate::register::core::Register;
-use crate::state::model::{PtzControlModel, TransMode};
+use crate::state::model::{
+    DeviceConfigInfo, DeviceControlCmd, DeviceStatusInfo, DragZoom, PresetItem, PtzCmd,
+    PtzCmdModel, PtzControlModel, PtzPositionInfo, RecordInfoItem, TransMode,
+};
 use crate::state::session::Cache as GeneralCache;
 use crate::storage::dialog_session::{
     DialogSessionType, DialogState, DialogTransport, EstablishedDialogFields, SipDialogSession,
@@ -31,19 +34,23 @@ use crate::storage::dialog_session::{
 };
 
 use super::adapter::pjsip_protocol_from_base;
+use super::config::{ConfigType, DeviceConfigSet};
 use super::invite::{
     AcceptBroadcastInviteRequest, GbInviteAcceptedEvent, InvitePlayRequest, InviteStopRequest,
 };
 use super::message::{CreateDeviceMessageRequest, target_uri};
 use super::native_runtime::NativeSipRuntimeHandle;
 use super::runtime_cache::{
-    BroadcastResponseKey, NativeInviteMetadata, SipRuntimeCache, recv_with_timeout,
+    BroadcastResponseKey, NativeInviteMetadata, SipRuntimeCache, XmlQueryItem, XmlQueryKey,
+    XmlQueryWaiters, recv_with_timeout,
 };
 use super::{sdp, xml};
 
 const INVITE_WAIT_TIMEOUT: Duration = Duration::from_secs(15);
 const BYE_WAIT_TIMEOUT: Duration = Duration::from_secs(8);
 const REQUEST_WAIT_TIMEOUT: Duration = Duration::from_secs(8);
+const RECORD_INFO_WAIT_TIMEOUT: Duration = Duration::from_secs(15);
+const PRESET_WAIT_TIMEOUT: Duration = Duration::from_secs(8);
 const DIALOG_EXPIRE_HOURS: i64 = 8;
 
 struct DurableDialogReservation {
@@ -538,13 +545,282 @@ pub async fn query_record_info(
     end_time: &str,
 ) -> GlobalResult<()> {
     send_native_message_and_wait(CreateDeviceMessageRequest::record_info_query(
-        device_id, sn, start_time, end_time,
+        device_id, device_id, sn, start_time, end_time,
     ))
     .await
 }
 
-pub async fn query_preset(device_id: &str) -> GlobalResult<()> {
-    send_native_message_and_wait(CreateDeviceMessageRequest::preset_query(device_id)).await
+/// 查询通道录像并等待全部应答：按 SN 聚合多条 MESSAGE，按时间排序
+pub async fn query_record_info_and_wait(
+    device_id: &str,
+    channel_id: &str,
+    start_time: &str,
+    end_time: &str,
+) -> GlobalResult<Vec<RecordInfoItem>> {
+    let sn = super::sequence::next_sn();
+    let request = CreateDeviceMessageRequest::record_info_query(
+        device_id, channel_id, sn, start_time, end_time,
+    );
+    let mut records = query_xml_and_wait(
+        SipRuntimeCache::global().record_info_waiters(),
+        device_id,
+        sn,
+        request,
+        RECORD_INFO_WAIT_TIMEOUT,
+    )
+    .await?;
+    records.sort_by(|a, b| (&a.start_time, &a.end_time).cmp(&(&b.start_time, &b.end_time)));
+    Ok(records)
+}
+
+pub async fn query_preset(device_id: &str, sn: u32) -> GlobalResult<()> {
+    send_native_message_and_wait(CreateDeviceMessageRequest::preset_query(
+        device_id, device_id, sn,
+    ))
+    .await
+}
+
+/// 查询通道预置位并等待全部应答：按 SN 聚合多条 MESSAGE，结果写入通道缓存
+pub async fn query_preset_and_wait(
+    device_id: &str,
+    channel_id: &str,
+) -> GlobalResult<Vec<PresetItem>> {
+    let sn = super::sequence::next_sn();
+    let request = CreateDeviceMessageRequest::preset_query(device_id, channel_id, sn);
+    let mut presets = query_xml_and_wait(
+        SipRuntimeCache::global().preset_waiters(),
+        device_id,
+        sn,
+        request,
+        PRESET_WAIT_TIMEOUT,
+    )
+    .await?;
+    presets.sort_by_key(|item| item.preset_id.parse::<u32>().unwrap_or(u32::MAX));
+    SipRuntimeCache::global().cache_presets(device_id, channel_id, presets.clone());
+    Ok(presets)
+}
+
+/// 发送 MANSCDP 查询并等待同 SN 的 Response；超时时返回已收到的部分分页
+async fn query_xml_and_wait<T: XmlQueryItem>(
+    waiters: &XmlQueryWaiters<T>,
+    device_id: &str,
+    sn: u32,
+    request: CreateDeviceMessageRequest,
+    timeout: Duration,
+) -> GlobalResult<Vec<T>> {
+    let key = XmlQueryKey {
+        sn: sn.to_string(),
+        device_id: device_id.to_string(),
+    };
+    let rx = waiters.insert(key.clone(), timeout);
+    if let Err(err) = send_native_message_and_wait(request).await {
+        waiters.remove(&key);
+        return Err(err);
+    }
+    match recv_with_timeout(rx, timeout).await {
+        Ok(items) => Ok(items),
+        Err(reason) => match waiters.take(&key) {
+            // 设备未发满 SumNum 条时返回已收到的部分
+            Some(items) if !items.is_empty() => {
+                warn!(
+                    "device_id={device_id}; sn={sn}; response incomplete, return {} items; reason={reason}",
+                    items.len()
+                );
+                Ok(items)
+            }
+            _ => Err(GlobalError::new_biz_error(
+                BaseErrorCode::Timeout.code(),
+                "device query response timeout",
+                |msg| error!("device_id={device_id}; sn={sn}; {msg}; reason={reason}"),
+            )),
+        },
+    }
+}
+
+/// 单包应答的查询/控制：直接返回应答 XML 条目
+async fn query_items_and_wait(
+    device_id: &str,
+    sn: u32,
+    request: CreateDeviceMessageRequest,
+) -> GlobalResult<Vec<(String, String)>> {
+    query_xml_and_wait(
+        SipRuntimeCache::global().query_waiters(),
+        device_id,
+        sn,
+        request,
+        REQUEST_WAIT_TIMEOUT,
+    )
+    .await
+}
+
+pub async fn query_device_status_and_wait(
+    device_id: &str,
+    channel_id: &str,
+) -> GlobalResult<DeviceStatusInfo> {
+    let sn = super::sequence::next_sn();
+    let request = CreateDeviceMessageRequest::device_status_query(device_id, channel_id, sn);
+    let items = query_items_and_wait(device_id, sn, request).await?;
+    Ok(DeviceStatusInfo::from_response_items(&items))
+}
+
+pub async fn query_ptz_position_and_wait(
+    device_id: &str,
+    channel_id: &str,
+) -> GlobalResult<PtzPositionInfo> {
+    let sn = super::sequence::next_sn();
+    let request = CreateDeviceMessageRequest::ptz_position_query(device_id, channel_id, sn);
+    let items = query_items_and_wait(device_id, sn, request).await?;
+    Ok(PtzPositionInfo::from_response_items(&items))
+}
+
+/// 设备应答 Result 为 ERROR 或缺失时视为拒绝
+fn ensure_result_ok(
+    items: &[(String, String)],
+    device_id: &str,
+    channel_id: &str,
+) -> GlobalResult<()> {
+    match xml::value(items, "Response,Result") {
+        Some(result) if result.eq_ignore_ascii_case("OK") => Ok(()),
+        result => Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidState.code(),
+            "device rejected request",
+            |msg| {
+                error!("device_id={device_id}; channel_id={channel_id}; result={result:?}; {msg}")
+            },
+        )),
+    }
+}
+
+/// 查询设备配置：多个 ConfigType 以单条 ConfigDownload 请求
+pub async fn query_config_and_wait(
+    device_id: &str,
+    channel_id: &str,
+    config_types: &[ConfigType],
+) -> GlobalResult<DeviceConfigInfo> {
+    let sn = super::sequence::next_sn();
+    let request = CreateDeviceMessageRequest::config_download_query(
+        device_id,
+        channel_id,
+        sn,
+        &ConfigType::join(config_types),
+    );
+    let items = query_items_and_wait(device_id, sn, request).await?;
+    ensure_result_ok(&items, device_id, channel_id)?;
+    Ok(DeviceConfigInfo::from_response_items(&items))
+}
+
+/// 下发设备配置并等待设备 Response 的 Result
+pub async fn device_config_and_wait(
+    device_id: &str,
+    channel_id: &str,
+    config: &DeviceConfigSet,
+) -> GlobalResult<()> {
+    let sn = super::sequence::next_sn();
+    let request = CreateDeviceMessageRequest::device_config(device_id, channel_id, sn, config);
+    let items = query_items_and_wait(device_id, sn, request).await?;
+    ensure_result_ok(&items, device_id, channel_id)
+}
+
+/// 下发设备控制命令，需应答的命令等待设备 Response 的 Result
+pub async fn device_control_and_wait(
+    device_id: &str,
+    channel_id: &str,
+    cmd: &DeviceControlCmd,
+) -> GlobalResult<()> {
+    let sn = super::sequence::next_sn();
+    let fragment = build_device_control_fragment(cmd)?;
+    let request = CreateDeviceMessageRequest::device_control(device_id, channel_id, sn, &fragment);
+    if !cmd.expects_response() {
+        return send_native_message_and_wait(request).await;
+    }
+    let items = query_items_and_wait(device_id, sn, request).await?;
+    ensure_result_ok(&items, device_id, channel_id)
+}
+
+fn build_device_control_fragment(cmd: &DeviceControlCmd) -> GlobalResult<String> {
+    let invalid = |reason: &'static str| {
+        GlobalError::new_biz_error(BaseErrorCode::InvalidRequest.code(), reason, |msg| {
+            error!("{msg}: cmd={cmd:?}")
+        })
+    };
+    let drag_zoom = |tag: &str, zoom: &DragZoom| {
+        if zoom.length == 0
+            || zoom.width == 0
+            || zoom.mid_point_x > zoom.length
+            || zoom.mid_point_y > zoom.width
+            || zoom.length_x == 0
+            || zoom.length_y == 0
+        {
+            return Err(invalid(
+                "drag zoom rectangle must lie inside the play window",
+            ));
+        }
+        Ok(format!(
+            "<{tag}>\r\n\
+<Length>{}</Length>\r\n\
+<Width>{}</Width>\r\n\
+<MidPointX>{}</MidPointX>\r\n\
+<MidPointY>{}</MidPointY>\r\n\
+<LengthX>{}</LengthX>\r\n\
+<LengthY>{}</LengthY>\r\n\
+</{tag}>\r\n",
+            zoom.length,
+            zoom.width,
+            zoom.mid_point_x,
+            zoom.mid_point_y,
+            zoom.length_x,
+            zoom.length_y,
+        ))
+    };
+    let fragment = match cmd {
+        DeviceControlCmd::TeleBoot => "<TeleBoot>Boot</TeleBoot>\r\n".to_string(),
+        DeviceControlCmd::RecordStart => "<RecordCmd>Record</RecordCmd>\r\n".to_string(),
+        DeviceControlCmd::RecordStop => "<RecordCmd>StopRecord</RecordCmd>\r\n".to_string(),
+        DeviceControlCmd::SetGuard => "<GuardCmd>SetGuard</GuardCmd>\r\n".to_string(),
+        DeviceControlCmd::ResetGuard => "<GuardCmd>ResetGuard</GuardCmd>\r\n".to_string(),
+        DeviceControlCmd::ResetAlarm {
+            alarm_method,
+            alarm_type,
+        } => {
+            let info = match (alarm_method, alarm_type) {
+                (None, None) => String::new(),
+                (Some(method), None) => {
+                    format!("<Info>\r\n<AlarmMethod>{method}</AlarmMethod>\r\n</Info>\r\n")
+                }
+                (Some(method), Some(alarm_type)) => format!(
+                    "<Info>\r\n<AlarmMethod>{method}</AlarmMethod>\r\n\
+<AlarmType>{alarm_type}</AlarmType>\r\n</Info>\r\n"
+                ),
+                (None, Some(_)) => return Err(invalid("alarm type requires alarm method")),
+            };
+            format!("<AlarmCmd>ResetAlarm</AlarmCmd>\r\n{info}")
+        }
+        DeviceControlCmd::IFrame => "<IFameCmd>Send</IFameCmd>\r\n".to_string(),
+        DeviceControlCmd::HomePosition { enabled: false, .. } => {
+            "<HomePosition>\r\n<Enabled>0</Enabled>\r\n</HomePosition>\r\n".to_string()
+        }
+        DeviceControlCmd::HomePosition {
+            enabled: true,
+            reset_time,
+            preset_index,
+        } => {
+            let (Some(reset_time), Some(preset_index)) = (reset_time, preset_index) else {
+                return Err(invalid(
+                    "home position requires reset time and preset index",
+                ));
+            };
+            format!(
+                "<HomePosition>\r\n\
+<Enabled>1</Enabled>\r\n\
+<ResetTime>{reset_time}</ResetTime>\r\n\
+<PresetIndex>{preset_index}</PresetIndex>\r\n\
+</HomePosition>\r\n"
+            )
+        }
+        DeviceControlCmd::DragZoomIn(zoom) => drag_zoom("DragZoomIn", zoom)?,
+        DeviceControlCmd::DragZoomOut(zoom) => drag_zoom("DragZoomOut", zoom)?,
+    };
+    Ok(fragment)
 }
 
 pub async fn send_xml_message(device_id: &str, body: String) -> GlobalResult<()> {
@@ -572,27 +848,111 @@ fn build_ptz_command(model: &PtzControlModel) -> GlobalResult<String> {
         ));
     }
 
-    let mut bytes = [0xA5, 0x0F, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00];
-    bytes[3] |= match model.leftRight {
+    let mut code = 0x00;
+    code |= match model.leftRight {
         1 => 0x02,
         2 => 0x01,
         _ => 0,
     };
-    bytes[3] |= match model.upDown {
+    code |= match model.upDown {
         1 => 0x08,
         2 => 0x04,
         _ => 0,
     };
-    bytes[3] |= match model.inOut {
+    code |= match model.inOut {
         1 => 0x20,
         2 => 0x10,
         _ => 0,
     };
-    bytes[4] = model.horizonSpeed;
-    bytes[5] = model.verticalSpeed;
-    bytes[6] = model.zoomSpeed << 4;
+    Ok(ptz_frame(
+        code,
+        model.horizonSpeed,
+        model.verticalSpeed,
+        model.zoomSpeed,
+    ))
+}
+
+pub async fn control_ptz_cmd(model: &PtzCmdModel) -> GlobalResult<()> {
+    let sn = super::sequence::next_sn();
+    let command = build_ptz_instruction(&model.cmd)?;
+    let body = xml::build_ptz_control(sn, &model.channelId, &command);
+    send_xml_message(&model.deviceId, body).await
+}
+
+fn build_ptz_instruction(cmd: &PtzCmd) -> GlobalResult<String> {
+    let invalid = |reason: &'static str| {
+        GlobalError::new_biz_error(BaseErrorCode::InvalidRequest.code(), reason, |msg| {
+            error!("{msg}: cmd={cmd:?}")
+        })
+    };
+    let check_preset = |preset: u8| match preset {
+        0 => Err(invalid("PTZ preset must be in 1-255")),
+        value => Ok(value),
+    };
+    // 巡航/扫描速度与停留时间为 12 位：低 8 位在字节 6，高 4 位在字节 7 高半字节
+    let wide = |value: u16| match value {
+        1..=0x0FFF => Ok(((value & 0xFF) as u8, (value >> 8) as u8)),
+        _ => Err(invalid("PTZ speed or dwell must be in 1-4095")),
+    };
+    let (code, data1, data2, data3) = match *cmd {
+        PtzCmd::Stop => (0x00, 0, 0, 0),
+        PtzCmd::PresetSet { preset } => (0x81, 0, check_preset(preset)?, 0),
+        PtzCmd::PresetCall { preset } => (0x82, 0, check_preset(preset)?, 0),
+        PtzCmd::PresetDelete { preset } => (0x83, 0, check_preset(preset)?, 0),
+        PtzCmd::CruiseAdd { group, preset } => (0x84, group, check_preset(preset)?, 0),
+        PtzCmd::CruiseRemove { group, preset } => (0x85, group, preset, 0),
+        PtzCmd::CruiseSpeed { group, speed } => {
+            let (low, high) = wide(speed)?;
+            (0x86, group, low, high)
+        }
+        PtzCmd::CruiseDwell { group, seconds } => {
+            let (low, high) = wide(seconds)?;
+            (0x87, group, low, high)
+        }
+        PtzCmd::CruiseStart { group } => (0x88, group, 0, 0),
+        PtzCmd::ScanStart { group } => (0x89, group, 0x00, 0),
+        PtzCmd::ScanLeftBound { group } => (0x89, group, 0x01, 0),
+        PtzCmd::ScanRightBound { group } => (0x89, group, 0x02, 0),
+        PtzCmd::ScanSpeed { group, speed } => {
+            let (low, high) = wide(speed)?;
+            (0x8A, group, low, high)
+        }
+        PtzCmd::Fi {
+            focus,
+            iris,
+            focusSpeed,
+            irisSpeed,
+        } => {
+            if focus > 2 || iris > 2 {
+                return Err(invalid("invalid PTZ focus or iris direction"));
+            }
+            let mut code = 0x40;
+            code |= match iris {
+                1 => 0x04,
+                2 => 0x08,
+                _ => 0,
+            };
+            code |= match focus {
+                1 => 0x02,
+                2 => 0x01,
+                _ => 0,
+            };
+            (code, focusSpeed, irisSpeed, 0)
+        }
+        PtzCmd::AuxOn { aux } | PtzCmd::AuxOff { aux } if aux == 0 => {
+            return Err(invalid("PTZ auxiliary switch must be in 1-255"));
+        }
+        PtzCmd::AuxOn { aux } => (0x8C, aux, 0, 0),
+        PtzCmd::AuxOff { aux } => (0x8D, aux, 0, 0),
+    };
+    Ok(ptz_frame(code, data1, data2, data3))
+}
+
+/// A50F01 指令帧：字节 7 高 4 位为 data3，低 4 位为地址高位(固定 0)，字节 8 为校验和
+fn ptz_frame(code: u8, data1: u8, data2: u8, data3: u8) -> String {
+    let mut bytes = [0xA5, 0x0F, 0x01, code, data1, data2, data3 << 4, 0x00];
     bytes[7] = (bytes.iter().map(|value| u16::from(*value)).sum::<u16>() % 256) as u8;
-    Ok(bytes.iter().map(|value| format!("{value:02X}")).collect())
+    bytes.iter().map(|value| format!("{value:02X}")).collect()
 }
 
 pub async fn snapshot_image_call(
@@ -1325,8 +1685,11 @@ async fn reserve_durable_dialog_request(
 
 #[cfg(test)]
 mod tests {
-    use super::{build_ptz_command, invite_subject, normalize_gb_ssrc};
-    use crate::state::model::PtzControlModel;
+    use super::{
+        build_device_control_fragment, build_ptz_command, build_ptz_instruction, invite_subject,
+        normalize_gb_ssrc,
+    };
+    use crate::state::model::{DeviceControlCmd, DragZoom, PtzCmd, PtzControlModel};
 
     #[test]
     fn builds_gb28181_ptz_hex_command() {
@@ -1344,6 +1707,159 @@ mod tests {
         assert_eq!(build_ptz_command(&model).unwrap(), "A50F011A2010302F");
     }
 
+    #[test]
+    fn builds_gb28181_annex_a_instructions() {
+        for (cmd, expected) in [
+            (PtzCmd::Stop, "A50F0100000000B5"),
+            (PtzCmd::PresetSet { preset: 3 }, "A50F018100030039"),
+            (PtzCmd::PresetCall { preset: 3 }, "A50F01820003003A"),
+            (PtzCmd::PresetDelete { preset: 255 }, "A50F018300FF0037"),
+            (
+                PtzCmd::CruiseAdd {
+                    group: 1,
+                    preset: 2,
+                },
+                "A50F01840102003C",
+            ),
+            (
+                PtzCmd::CruiseRemove {
+                    group: 1,
+                    preset: 0,
+                },
+                "A50F01850100003B",
+            ),
+            (
+                PtzCmd::CruiseSpeed {
+                    group: 1,
+                    speed: 0x123,
+                },
+                "A50F01860123106F",
+            ),
+            (
+                PtzCmd::CruiseDwell {
+                    group: 1,
+                    seconds: 30,
+                },
+                "A50F0187011E005B",
+            ),
+            (PtzCmd::CruiseStart { group: 1 }, "A50F01880100003E"),
+            (PtzCmd::ScanStart { group: 1 }, "A50F01890100003F"),
+            (PtzCmd::ScanLeftBound { group: 1 }, "A50F018901010040"),
+            (PtzCmd::ScanRightBound { group: 1 }, "A50F018901020041"),
+            (
+                PtzCmd::ScanSpeed {
+                    group: 1,
+                    speed: 0xFFF,
+                },
+                "A50F018A01FFF02F",
+            ),
+            (
+                PtzCmd::Fi {
+                    focus: 1,
+                    iris: 2,
+                    focusSpeed: 0x80,
+                    irisSpeed: 0x40,
+                },
+                "A50F014A804000BF",
+            ),
+            (PtzCmd::AuxOn { aux: 1 }, "A50F018C01000042"),
+            (PtzCmd::AuxOff { aux: 1 }, "A50F018D01000043"),
+        ] {
+            assert_eq!(build_ptz_instruction(&cmd).unwrap(), expected, "{cmd:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_ptz_instructions() {
+        for cmd in [
+            PtzCmd::PresetCall { preset: 0 },
+            PtzCmd::CruiseAdd {
+                group: 1,
+                preset: 0,
+            },
+            PtzCmd::CruiseSpeed { group: 1, speed: 0 },
+            PtzCmd::CruiseDwell {
+                group: 1,
+                seconds: 0x1000,
+            },
+            PtzCmd::ScanSpeed {
+                group: 1,
+                speed: 0x1000,
+            },
+            PtzCmd::Fi {
+                focus: 3,
+                iris: 0,
+                focusSpeed: 1,
+                irisSpeed: 1,
+            },
+            PtzCmd::AuxOff { aux: 0 },
+        ] {
+            assert!(build_ptz_instruction(&cmd).is_err(), "{cmd:?}");
+        }
+    }
+
+    #[test]
+    fn builds_device_control_fragments() {
+        let zoom = DragZoom {
+            length: 1920,
+            width: 1080,
+            mid_point_x: 960,
+            mid_point_y: 540,
+            length_x: 200,
+            length_y: 100,
+        };
+        for (cmd, expected) in [
+            (DeviceControlCmd::TeleBoot, "<TeleBoot>Boot</TeleBoot>"),
+            (
+                DeviceControlCmd::RecordStop,
+                "<RecordCmd>StopRecord</RecordCmd>",
+            ),
+            (DeviceControlCmd::SetGuard, "<GuardCmd>SetGuard</GuardCmd>"),
+            (
+                DeviceControlCmd::ResetAlarm {
+                    alarm_method: Some(2),
+                    alarm_type: Some(1),
+                },
+                "<AlarmMethod>2</AlarmMethod>\r\n<AlarmType>1</AlarmType>",
+            ),
+            (DeviceControlCmd::IFrame, "<IFameCmd>Send</IFameCmd>"),
+            (
+                DeviceControlCmd::HomePosition {
+                    enabled: true,
+                    reset_time: Some(30),
+                    preset_index: Some(1),
+                },
+                "<ResetTime>30</ResetTime>\r\n<PresetIndex>1</PresetIndex>",
+            ),
+            (
+                DeviceControlCmd::DragZoomOut(zoom),
+                "<DragZoomOut>\r\n<Length>1920</Length>",
+            ),
+        ] {
+            let fragment = build_device_control_fragment(&cmd).expect("device control fragment");
+            assert!(fragment.contains(expected), "{cmd:?}: {fragment}");
+        }
+        for cmd in [
+            DeviceControlCmd::ResetAlarm {
+                alarm_method: None,
+                alarm_type: Some(1),
+            },
+            DeviceControlCmd::HomePosition {
+                enabled: true,
+                reset_time: None,
+                preset_index: Some(1),
+            },
+            DeviceControlCmd::DragZoomIn(DragZoom {
+                mid_point_x: 2000,
+                ..zoom
+            }),
+        ] {
+            assert!(build_device_control_fragment(&cmd).is_err(), "{cmd:?}");
+        }
+        assert!(!DeviceControlCmd::IFrame.expects_response());
+        assert!(DeviceControlCmd::RecordStart.expects_response());
+    }
+
     #[test]
     fn invite_subject_keeps_gb28181_receiver_leg_zero() {
         assert_eq!(
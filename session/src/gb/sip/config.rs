@@ -0,0 +1,828 @@
+//! GB28181 device configuration models.
+//!
+//! `ConfigDownload` answers and `DeviceConfig` requests share the same
+//! per-ConfigType XML sections; this module maps them to typed models and
+//! back. Parsing works on the flattened `xml::parse_items` paths, so repeated
+//! list items are split on their leading field like `RecordInfoItem`.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use base::serde::{Deserialize, Serialize};
+
+use super::xml::escape;
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "base::serde")]
+pub enum ConfigType {
+    BasicParam,
+    VideoParamOpt,
+    VideoParamAttribute,
+    SVACEncodeConfig,
+    SVACDecodeConfig,
+    OSDConfig,
+    VideoRecordPlan,
+    VideoAlarmRecord,
+}
+
+impl ConfigType {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            ConfigType::BasicParam => "BasicParam",
+            ConfigType::VideoParamOpt => "VideoParamOpt",
+            ConfigType::VideoParamAttribute => "VideoParamAttribute",
+            ConfigType::SVACEncodeConfig => "SVACEncodeConfig",
+            ConfigType::SVACDecodeConfig => "SVACDecodeConfig",
+            ConfigType::OSDConfig => "OSDConfig",
+            ConfigType::VideoRecordPlan => "VideoRecordPlan",
+            ConfigType::VideoAlarmRecord => "VideoAlarmRecord",
+        }
+    }
+
+    /// ConfigDownload 的 ConfigType 以 / 分隔多个配置
+    pub fn join(types: &[ConfigType]) -> String {
+        types
+            .iter()
+            .map(ConfigType::tag)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// 设备配置集合：ConfigDownload 应答或 DeviceConfig 请求，未携带的配置为 None
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct DeviceConfigSet {
+    pub basic_param: Option<BasicParam>,
+    /// 仅可查询
+    pub video_param_opt: Option<VideoParamOpt>,
+    pub video_param_attribute: Option<Vec<VideoParamAttributeItem>>,
+    pub svac_encode_config: Option<SvacEncodeConfig>,
+    pub svac_decode_config: Option<SvacDecodeConfig>,
+    pub osd_config: Option<OsdConfig>,
+    pub video_record_plan: Option<VideoRecordPlan>,
+    pub video_alarm_record: Option<VideoAlarmRecord>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct BasicParam {
+    /// 设备名称
+    pub name: Option<String>,
+    /// 注册过期时间：秒
+    pub expiration: Option<u32>,
+    /// 心跳间隔：秒
+    pub heartbeat_interval: Option<u32>,
+    /// 心跳超时次数
+    pub heartbeat_count: Option<u32>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct VideoParamOpt {
+    /// 可选下载倍速：如 1/2/4
+    pub download_speed: Option<String>,
+    /// 摄像机支持的分辨率：如 5/6
+    pub resolution: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct VideoParamAttributeItem {
+    /// 码流名称：如 Stream1
+    pub stream_name: Option<String>,
+    /// 视频编码格式
+    pub video_format: Option<String>,
+    /// 分辨率
+    pub resolution: Option<String>,
+    /// 帧率
+    pub frame_rate: Option<String>,
+    /// 码率类型：1固定码率 2可变码率
+    pub bit_rate_type: Option<String>,
+    /// 视频码率：kbps
+    pub video_bit_rate: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct SvacEncodeConfig {
+    pub roi_param: Option<RoiParam>,
+    pub svc_param: Option<SvcEncodeParam>,
+    pub surveillance_param: Option<SurveillanceEncodeParam>,
+    pub encrypt_param: Option<EncryptParam>,
+    pub audio_param: Option<AudioParam>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct RoiParam {
+    /// 感兴趣区域开关：0关闭 1打开
+    pub roi_flag: Option<u8>,
+    /// 感兴趣区域数量：0-16
+    pub roi_number: Option<u8>,
+    pub items: Vec<RoiItem>,
+    /// 背景区域编码质量等级
+    pub back_ground_qp: Option<u8>,
+    /// 背景跳过开关
+    pub back_ground_skip_flag: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct RoiItem {
+    /// 感兴趣区域编号：1-16
+    pub roi_seq: Option<u8>,
+    /// 左上角坐标：宏块序号
+    pub top_left: Option<u32>,
+    /// 右下角坐标：宏块序号
+    pub bottom_right: Option<u32>,
+    /// 编码质量等级
+    pub roi_qp: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct SvcEncodeParam {
+    /// 空域编码方式：0不使用 1:1级增强 2:2级增强 3:3级增强
+    pub svc_space_domain_mode: Option<u8>,
+    /// 时域编码方式
+    pub svc_time_domain_mode: Option<u8>,
+    /// 空域编码能力(只读)
+    pub svc_space_support_mode: Option<u8>,
+    /// 时域编码能力(只读)
+    pub svc_time_support_mode: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct SurveillanceEncodeParam {
+    /// 绝对时间信息开关
+    pub time_flag: Option<u8>,
+    /// 监控事件信息开关
+    pub event_flag: Option<u8>,
+    /// 报警信息开关
+    pub alert_flag: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct EncryptParam {
+    /// 加密开关
+    pub encryption_flag: Option<u8>,
+    /// 认证开关
+    pub authentication_flag: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct AudioParam {
+    /// 声音识别特征参数开关
+    pub audio_recognition_flag: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct SvacDecodeConfig {
+    /// 码流显示模式：0基本层 1:1级增强 2:2级增强 3:3级增强
+    pub svc_stm_mode: Option<u8>,
+    /// 绝对时间信息显示开关
+    pub time_show_flag: Option<u8>,
+    /// 监控事件信息显示开关
+    pub event_show_flag: Option<u8>,
+    /// 报警信息显示开关
+    pub alert_show_flag: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct OsdConfig {
+    /// 配置窗口长度：像素
+    pub length: Option<u32>,
+    /// 配置窗口宽度：像素
+    pub width: Option<u32>,
+    /// 时间 X 坐标
+    pub time_x: Option<u32>,
+    /// 时间 Y 坐标
+    pub time_y: Option<u32>,
+    /// 显示时间开关
+    pub time_enable: Option<u8>,
+    /// 时间显示类型：0 YYYY-MM-DD HH:MM:SS 1 YYYY年MM月DD日 HH:MM:SS
+    pub time_type: Option<u8>,
+    /// 显示文字开关
+    pub text_enable: Option<u8>,
+    pub items: Vec<OsdText>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct OsdText {
+    pub text: Option<String>,
+    pub x: Option<u32>,
+    pub y: Option<u32>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct VideoRecordPlan {
+    /// 是否启用录像计划
+    pub record_enable: Option<u8>,
+    /// 码流类型：0主码流 1子码流1 2子码流2
+    pub stream_number: Option<u8>,
+    pub schedules: Vec<RecordSchedule>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct RecordSchedule {
+    /// 周几：1-7
+    pub week_day_num: Option<u8>,
+    pub segments: Vec<TimeSegment>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct TimeSegment {
+    pub start_hour: Option<u8>,
+    pub start_min: Option<u8>,
+    pub start_sec: Option<u8>,
+    pub stop_hour: Option<u8>,
+    pub stop_min: Option<u8>,
+    pub stop_sec: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct VideoAlarmRecord {
+    /// 是否启用报警录像
+    pub record_enable: Option<u8>,
+    /// 录像延时时间：秒
+    pub record_time: Option<u32>,
+    /// 预录时间：秒
+    pub pre_record_time: Option<u32>,
+    /// 码流类型
+    pub stream_number: Option<u8>,
+}
+
+fn num<T: FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+/// 列表字段：首字段出现或列表为空时开始新条目
+fn item<T: Default>(list: &mut Vec<T>, start: bool) -> &mut T {
+    if start || list.is_empty() {
+        list.push(T::default());
+    }
+    list.last_mut().expect("list item")
+}
+
+fn tag<T: Display>(out: &mut String, name: &str, value: &Option<T>) {
+    if let Some(value) = value {
+        out.push_str(&format!(
+            "<{name}>{}</{name}>\r\n",
+            escape(&value.to_string())
+        ));
+    }
+}
+
+fn section(out: &mut String, name: &str, body: String) {
+    out.push_str(&format!("<{name}>\r\n{body}</{name}>\r\n"));
+}
+
+impl DeviceConfigSet {
+    pub fn is_empty(&self) -> bool {
+        *self == DeviceConfigSet::default()
+    }
+
+    pub fn from_response_items(arr: &[(String, String)]) -> Self {
+        let mut config = DeviceConfigSet::default();
+        for (k, v) in arr {
+            let Some((name, path)) = k
+                .strip_prefix("Response,")
+                .and_then(|rest| rest.split_once(','))
+            else {
+                continue;
+            };
+            let v = v.as_str();
+            match name {
+                "BasicParam" => {
+                    let basic = config.basic_param.get_or_insert_default();
+                    match path {
+                        "Name" => basic.name = Some(v.to_string()),
+                        "Expiration" => basic.expiration = num(v),
+                        "HeartBeatInterval" => basic.heartbeat_interval = num(v),
+                        "HeartBeatCount" => basic.heartbeat_count = num(v),
+                        _ => {}
+                    }
+                }
+                "VideoParamOpt" => {
+                    let opt = config.video_param_opt.get_or_insert_default();
+                    match path {
+                        "DownloadSpeed" => opt.download_speed = Some(v.to_string()),
+                        "Resolution" => opt.resolution = Some(v.to_string()),
+                        _ => {}
+                    }
+                }
+                "VideoParamAttribute" => {
+                    let list = config.video_param_attribute.get_or_insert_default();
+                    let Some(field) = path.strip_prefix("Item,") else {
+                        continue;
+                    };
+                    let attr = item(list, field == "StreamName");
+                    let value = Some(v.to_string());
+                    match field {
+                        "StreamName" => attr.stream_name = value,
+                        "VideoFormat" => attr.video_format = value,
+                        "Resolution" => attr.resolution = value,
+                        "FrameRate" => attr.frame_rate = value,
+                        "BitRateType" => attr.bit_rate_type = value,
+                        "VideoBitRate" => attr.video_bit_rate = value,
+                        _ => {}
+                    }
+                }
+                "SVACEncodeConfig" => config
+                    .svac_encode_config
+                    .get_or_insert_default()
+                    .set(path, v),
+                "SVACDecodeConfig" => {
+                    let decode = config.svac_decode_config.get_or_insert_default();
+                    match path {
+                        "SVCParam,SVCSTMMode" => decode.svc_stm_mode = num(v),
+                        "SurveillanceParam,TimeShowFlag" => decode.time_show_flag = num(v),
+                        "SurveillanceParam,EventShowFlag" => decode.event_show_flag = num(v),
+                        // 标准原文拼写为 AlerShowtFlag
+                        "SurveillanceParam,AlerShowtFlag" | "SurveillanceParam,AlertShowFlag" => {
+                            decode.alert_show_flag = num(v)
+                        }
+                        _ => {}
+                    }
+                }
+                "OSDConfig" => config.osd_config.get_or_insert_default().set(path, v),
+                "VideoRecordPlan" => config
+                    .video_record_plan
+                    .get_or_insert_default()
+                    .set(path, v),
+                "VideoAlarmRecord" => {
+                    let alarm = config.video_alarm_record.get_or_insert_default();
+                    match path {
+                        "RecordEnable" => alarm.record_enable = num(v),
+                        "RecordTime" => alarm.record_time = num(v),
+                        "PreRecordTime" => alarm.pre_record_time = num(v),
+                        "StreamNumber" => alarm.stream_number = num(v),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        config
+    }
+
+    /// DeviceConfig 请求体中的配置段
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+        if let Some(basic) = &self.basic_param {
+            let mut body = String::new();
+            tag(&mut body, "Name", &basic.name);
+            tag(&mut body, "Expiration", &basic.expiration);
+            tag(&mut body, "HeartBeatInterval", &basic.heartbeat_interval);
+            tag(&mut body, "HeartBeatCount", &basic.heartbeat_count);
+            section(&mut out, "BasicParam", body);
+        }
+        if let Some(opt) = &self.video_param_opt {
+            let mut body = String::new();
+            tag(&mut body, "DownloadSpeed", &opt.download_speed);
+            tag(&mut body, "Resolution", &opt.resolution);
+            section(&mut out, "VideoParamOpt", body);
+        }
+        if let Some(list) = &self.video_param_attribute {
+            let mut body = String::new();
+            for attr in list {
+                let mut fields = String::new();
+                tag(&mut fields, "StreamName", &attr.stream_name);
+                tag(&mut fields, "VideoFormat", &attr.video_format);
+                tag(&mut fields, "Resolution", &attr.resolution);
+                tag(&mut fields, "FrameRate", &attr.frame_rate);
+                tag(&mut fields, "BitRateType", &attr.bit_rate_type);
+                tag(&mut fields, "VideoBitRate", &attr.video_bit_rate);
+                section(&mut body, "Item", fields);
+            }
+            section(&mut out, "VideoParamAttribute", body);
+        }
+        if let Some(encode) = &self.svac_encode_config {
+            section(&mut out, "SVACEncodeConfig", encode.to_xml());
+        }
+        if let Some(decode) = &self.svac_decode_config {
+            let mut body = String::new();
+            if decode.svc_stm_mode.is_some() {
+                let mut svc = String::new();
+                tag(&mut svc, "SVCSTMMode", &decode.svc_stm_mode);
+                section(&mut body, "SVCParam", svc);
+            }
+            let mut surveillance = String::new();
+            tag(&mut surveillance, "TimeShowFlag", &decode.time_show_flag);
+            tag(&mut surveillance, "EventShowFlag", &decode.event_show_flag);
+            tag(&mut surveillance, "AlerShowtFlag", &decode.alert_show_flag);
+            if !surveillance.is_empty() {
+                section(&mut body, "SurveillanceParam", surveillance);
+            }
+            section(&mut out, "SVACDecodeConfig", body);
+        }
+        if let Some(osd) = &self.osd_config {
+            section(&mut out, "OSDConfig", osd.to_xml());
+        }
+        if let Some(plan) = &self.video_record_plan {
+            section(&mut out, "VideoRecordPlan", plan.to_xml());
+        }
+        if let Some(alarm) = &self.video_alarm_record {
+            let mut body = String::new();
+            tag(&mut body, "RecordEnable", &alarm.record_enable);
+            tag(&mut body, "RecordTime", &alarm.record_time);
+            tag(&mut body, "PreRecordTime", &alarm.pre_record_time);
+            tag(&mut body, "StreamNumber", &alarm.stream_number);
+            section(&mut out, "VideoAlarmRecord", body);
+        }
+        out
+    }
+}
+
+impl SvacEncodeConfig {
+    fn set(&mut self, path: &str, v: &str) {
+        let Some((group, field)) = path.split_once(',') else {
+            return;
+        };
+        match group {
+            "ROIParam" => {
+                let roi = self.roi_param.get_or_insert_default();
+                match field {
+                    "ROIFlag" => roi.roi_flag = num(v),
+                    "ROINumber" => roi.roi_number = num(v),
+                    "BackGroundQP" => roi.back_ground_qp = num(v),
+                    "BackGroundSkipFlag" => roi.back_ground_skip_flag = num(v),
+                    _ => {
+                        let Some(field) = field.strip_prefix("Item,") else {
+                            return;
+                        };
+                        let area = item(&mut roi.items, field == "ROISeq");
+                        match field {
+                            "ROISeq" => area.roi_seq = num(v),
+                            "TopLeft" => area.top_left = num(v),
+                            "BottomRight" => area.bottom_right = num(v),
+                            "ROIQP" => area.roi_qp = num(v),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            "SVCParam" => {
+                let svc = self.svc_param.get_or_insert_default();
+                match field {
+                    "SVCSpaceDomainMode" => svc.svc_space_domain_mode = num(v),
+                    "SVCTimeDomainMode" => svc.svc_time_domain_mode = num(v),
+                    "SVCSpaceSupportMode" => svc.svc_space_support_mode = num(v),
+                    "SVCTimeSupportMode" => svc.svc_time_support_mode = num(v),
+                    _ => {}
+                }
+            }
+            "SurveillanceParam" => {
+                let surveillance = self.surveillance_param.get_or_insert_default();
+                match field {
+                    "TimeFlag" => surveillance.time_flag = num(v),
+                    "EventFlag" => surveillance.event_flag = num(v),
+                    "AlertFlag" => surveillance.alert_flag = num(v),
+                    _ => {}
+                }
+            }
+            "EncryptParam" => {
+                let encrypt = self.encrypt_param.get_or_insert_default();
+                match field {
+                    "EncryptionFlag" => encrypt.encryption_flag = num(v),
+                    "AuthenticationFlag" => encrypt.authentication_flag = num(v),
+                    _ => {}
+                }
+            }
+            "AudioParam" if field == "AudioRecognitionFlag" => {
+                self.audio_param
+                    .get_or_insert_default()
+                    .audio_recognition_flag = num(v);
+            }
+            _ => {}
+        }
+    }
+
+    fn to_xml(&self) -> String {
+        let mut out = String::new();
+        if let Some(roi) = &self.roi_param {
+            let mut body = String::new();
+            tag(&mut body, "ROIFlag", &roi.roi_flag);
+            tag(&mut body, "ROINumber", &roi.roi_number);
+            for area in &roi.items {
+                let mut fields = String::new();
+                tag(&mut fields, "ROISeq", &area.roi_seq);
+                tag(&mut fields, "TopLeft", &area.top_left);
+                tag(&mut fields, "BottomRight", &area.bottom_right);
+                tag(&mut fields, "ROIQP", &area.roi_qp);
+                section(&mut body, "Item", fields);
+            }
+            tag(&mut body, "BackGroundQP", &roi.back_ground_qp);
+            tag(&mut body, "BackGroundSkipFlag", &roi.back_ground_skip_flag);
+            section(&mut out, "ROIParam", body);
+        }
+        if let Some(svc) = &self.svc_param {
+            let mut body = String::new();
+            tag(&mut body, "SVCSpaceDomainMode", &svc.svc_space_domain_mode);
+            tag(&mut body, "SVCTimeDomainMode", &svc.svc_time_domain_mode);
+            section(&mut out, "SVCParam", body);
+        }
+        if let Some(surveillance) = &self.surveillance_param {
+            let mut body = String::new();
+            tag(&mut body, "TimeFlag", &surveillance.time_flag);
+            tag(&mut body, "EventFlag", &surveillance.event_flag);
+            tag(&mut body, "AlertFlag", &surveillance.alert_flag);
+            section(&mut out, "SurveillanceParam", body);
+        }
+        if let Some(encrypt) = &self.encrypt_param {
+            let mut body = String::new();
+            tag(&mut body, "EncryptionFlag", &encrypt.encryption_flag);
+            tag(
+                &mut body,
+                "AuthenticationFlag",
+                &encrypt.authentication_flag,
+            );
+            section(&mut out, "EncryptParam", body);
+        }
+        if let Some(audio) = &self.audio_param {
+            let mut body = String::new();
+            tag(
+                &mut body,
+                "AudioRecognitionFlag",
+                &audio.audio_recognition_flag,
+            );
+            section(&mut out, "AudioParam", body);
+        }
+        out
+    }
+}
+
+impl OsdConfig {
+    fn set(&mut self, path: &str, v: &str) {
+        match path {
+            "Length" => self.length = num(v),
+            "Width" => self.width = num(v),
+            "TimeX" => self.time_x = num(v),
+            "TimeY" => self.time_y = num(v),
+            "TimeEnable" => self.time_enable = num(v),
+            "TimeType" => self.time_type = num(v),
+            "TextEnable" => self.text_enable = num(v),
+            _ => {
+                let Some(field) = path.strip_prefix("Item,") else {
+                    return;
+                };
+                let text = item(&mut self.items, field == "Text");
+                match field {
+                    "Text" => text.text = Some(v.to_string()),
+                    "X" => text.x = num(v),
+                    "Y" => text.y = num(v),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn to_xml(&self) -> String {
+        let mut out = String::new();
+        tag(&mut out, "Length", &self.length);
+        tag(&mut out, "Width", &self.width);
+        tag(&mut out, "TimeX", &self.time_x);
+        tag(&mut out, "TimeY", &self.time_y);
+        tag(&mut out, "TimeEnable", &self.time_enable);
+        tag(&mut out, "TimeType", &self.time_type);
+        tag(&mut out, "TextEnable", &self.text_enable);
+        tag(&mut out, "SumNum", &Some(self.items.len()));
+        for text in &self.items {
+            let mut fields = String::new();
+            tag(&mut fields, "Text", &text.text);
+            tag(&mut fields, "X", &text.x);
+            tag(&mut fields, "Y", &text.y);
+            section(&mut out, "Item", fields);
+        }
+        out
+    }
+}
+
+impl VideoRecordPlan {
+    fn set(&mut self, path: &str, v: &str) {
+        match path {
+            "RecordEnable" => self.record_enable = num(v),
+            "StreamNumber" => self.stream_number = num(v),
+            "RecordSchedule,WeekDayNum" => item(&mut self.schedules, true).week_day_num = num(v),
+            _ => {
+                let Some(field) = path.strip_prefix("RecordSchedule,TimeSegment,") else {
+                    return;
+                };
+                let schedule = item(&mut self.schedules, false);
+                let segment = item(&mut schedule.segments, field == "StartHour");
+                match field {
+                    "StartHour" => segment.start_hour = num(v),
+                    "StartMin" => segment.start_min = num(v),
+                    "StartSec" => segment.start_sec = num(v),
+                    "StopHour" => segment.stop_hour = num(v),
+                    "StopMin" => segment.stop_min = num(v),
+                    "StopSec" => segment.stop_sec = num(v),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn to_xml(&self) -> String {
+        let mut out = String::new();
+        tag(&mut out, "RecordEnable", &self.record_enable);
+        tag(
+            &mut out,
+            "RecordScheduleSumNum",
+            &Some(self.schedules.len()),
+        );
+        for schedule in &self.schedules {
+            let mut body = String::new();
+            tag(&mut body, "WeekDayNum", &schedule.week_day_num);
+            tag(
+                &mut body,
+                "TimeSegmentSumNum",
+                &Some(schedule.segments.len()),
+            );
+            for segment in &schedule.segments {
+                let mut fields = String::new();
+                tag(&mut fields, "StartHour", &segment.start_hour);
+                tag(&mut fields, "StartMin", &segment.start_min);
+                tag(&mut fields, "StartSec", &segment.start_sec);
+                tag(&mut fields, "StopHour", &segment.stop_hour);
+                tag(&mut fields, "StopMin", &segment.stop_min);
+                tag(&mut fields, "StopSec", &segment.stop_sec);
+                section(&mut body, "TimeSegment", fields);
+            }
+            section(&mut out, "RecordSchedule", body);
+        }
+        tag(&mut out, "StreamNumber", &self.stream_number);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(config: &DeviceConfigSet) -> DeviceConfigSet {
+        let xml = format!(
+            "<?xml version=\"1.0\"?>\r\n<Response>\r\n<CmdType>ConfigDownload</CmdType>\r\n\
+             <SN>1</SN>\r\n<DeviceID>34020000001320000001</DeviceID>\r\n\
+             <Result>OK</Result>\r\n{}</Response>\r\n",
+            config.to_xml()
+        );
+        let items = super::super::xml::parse_items(xml.as_bytes()).expect("parse config");
+        DeviceConfigSet::from_response_items(&items)
+    }
+
+    #[test]
+    fn device_config_sections_round_trip() {
+        let segment = |start_hour, stop_hour| TimeSegment {
+            start_hour: Some(start_hour),
+            start_min: Some(0),
+            start_sec: Some(0),
+            stop_hour: Some(stop_hour),
+            stop_min: Some(59),
+            stop_sec: Some(59),
+        };
+        let config = DeviceConfigSet {
+            basic_param: Some(BasicParam {
+                name: Some("gate <east>".into()),
+                expiration: Some(3600),
+                heartbeat_interval: Some(60),
+                heartbeat_count: Some(3),
+            }),
+            video_param_opt: None,
+            video_param_attribute: Some(vec![
+                VideoParamAttributeItem {
+                    stream_name: Some("Stream1".into()),
+                    video_format: Some("2".into()),
+                    resolution: Some("6".into()),
+                    frame_rate: Some("25".into()),
+                    bit_rate_type: Some("1".into()),
+                    video_bit_rate: Some("4096".into()),
+                },
+                VideoParamAttributeItem {
+                    stream_name: Some("Stream2".into()),
+                    resolution: Some("5".into()),
+                    ..Default::default()
+                },
+            ]),
+            svac_encode_config: Some(SvacEncodeConfig {
+                roi_param: Some(RoiParam {
+                    roi_flag: Some(1),
+                    roi_number: Some(2),
+                    items: vec![
+                        RoiItem {
+                            roi_seq: Some(1),
+                            top_left: Some(10),
+                            bottom_right: Some(200),
+                            roi_qp: Some(3),
+                        },
+                        RoiItem {
+                            roi_seq: Some(2),
+                            top_left: Some(300),
+                            bottom_right: Some(500),
+                            roi_qp: Some(2),
+                        },
+                    ],
+                    back_ground_qp: Some(1),
+                    back_ground_skip_flag: Some(0),
+                }),
+                svc_param: Some(SvcEncodeParam {
+                    svc_space_domain_mode: Some(1),
+                    svc_time_domain_mode: Some(0),
+                    ..Default::default()
+                }),
+                surveillance_param: Some(SurveillanceEncodeParam {
+                    time_flag: Some(1),
+                    event_flag: Some(1),
+                    alert_flag: Some(0),
+                }),
+                encrypt_param: None,
+                audio_param: Some(AudioParam {
+                    audio_recognition_flag: Some(1),
+                }),
+            }),
+            svac_decode_config: Some(SvacDecodeConfig {
+                svc_stm_mode: Some(1),
+                time_show_flag: Some(1),
+                event_show_flag: Some(0),
+                alert_show_flag: Some(1),
+            }),
+            osd_config: Some(OsdConfig {
+                length: Some(1920),
+                width: Some(1080),
+                time_x: Some(10),
+                time_y: Some(10),
+                time_enable: Some(1),
+                time_type: Some(0),
+                text_enable: Some(1),
+                items: vec![
+                    OsdText {
+                        text: Some("东门".into()),
+                        x: Some(10),
+                        y: Some(1000),
+                    },
+                    OsdText {
+                        text: Some("B1".into()),
+                        x: Some(1800),
+                        y: Some(1000),
+                    },
+                ],
+            }),
+            video_record_plan: Some(VideoRecordPlan {
+                record_enable: Some(1),
+                stream_number: Some(0),
+                schedules: vec![
+                    RecordSchedule {
+                        week_day_num: Some(1),
+                        segments: vec![segment(0, 7), segment(18, 23)],
+                    },
+                    RecordSchedule {
+                        week_day_num: Some(6),
+                        segments: vec![segment(0, 23)],
+                    },
+                ],
+            }),
+            video_alarm_record: Some(VideoAlarmRecord {
+                record_enable: Some(1),
+                record_time: Some(30),
+                pre_record_time: Some(5),
+                stream_number: Some(0),
+            }),
+        };
+        assert!(config.to_xml().contains("<Name>gate &lt;east&gt;</Name>"));
+        assert_eq!(round_trip(&config), config);
+        assert_eq!(
+            ConfigType::join(&[ConfigType::BasicParam, ConfigType::OSDConfig]),
+            "BasicParam/OSDConfig"
+        );
+    }
+}
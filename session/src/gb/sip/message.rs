@@ -5,6 +5,7 @@ use gmv_pjsip::{
     SipAssociation, SipMethod, SipRuntimeEvent, SipRuntimeEventKind, SipTransportProtocol,
 };
 
+use super::config::DeviceConfigSet;
 use super::xml;
 
 pub const GB_XML_CONTENT_TYPE: &str = "Application/MANSCDP+xml";
@@ -15,6 +16,7 @@ pub enum GbMessageKind {
     Catalog,
     DeviceInfo,
     Alarm,
+    MobilePosition,
     RecordInfo,
     MediaStatus,
     DeviceStatus,
@@ -82,6 +84,7 @@ impl GbMessageEvent {
             Some("Catalog") => GbMessageKind::Catalog,
             Some("DeviceInfo") => GbMessageKind::DeviceInfo,
             Some("Alarm") => GbMessageKind::Alarm,
+            Some("MobilePosition") => GbMessageKind::MobilePosition,
             Some("RecordInfo") => GbMessageKind::RecordInfo,
             Some("MediaStatus") => GbMessageKind::MediaStatus,
             Some("DeviceStatus") => GbMessageKind::DeviceStatus,
@@ -183,21 +186,43 @@ impl CreateDeviceMessageRequest {
         Self::xml(device_id, body)
     }
 
-    pub fn device_status_query(device_id: impl Into<String>, sn: u32) -> Self {
-        let device_id = device_id.into();
-        let body = xml::build_device_status_query(sn, &device_id);
+    pub fn device_status_query(device_id: impl Into<String>, channel_id: &str, sn: u32) -> Self {
+        let body = xml::build_device_status_query(sn, channel_id);
         Self::xml(device_id, body)
     }
 
-    pub fn config_download_query(device_id: impl Into<String>, sn: u32, config_type: &str) -> Self {
-        let device_id = device_id.into();
-        let body = xml::build_config_download_query(sn, &device_id, config_type);
+    pub fn config_download_query(
+        device_id: impl Into<String>,
+        channel_id: &str,
+        sn: u32,
+        config_type: &str,
+    ) -> Self {
+        let body = xml::build_config_download_query(sn, channel_id, config_type);
         Self::xml(device_id, body)
     }
 
-    pub fn ptz_position_query(device_id: impl Into<String>, sn: u32) -> Self {
-        let device_id = device_id.into();
-        let body = xml::build_ptz_position_query(sn, &device_id);
+    pub fn device_control(
+        device_id: impl Into<String>,
+        channel_id: &str,
+        sn: u32,
+        fragment: &str,
+    ) -> Self {
+        let body = xml::build_device_control_xml(sn, channel_id, fragment);
+        Self::xml(device_id, body)
+    }
+
+    pub fn device_config(
+        device_id: impl Into<String>,
+        channel_id: &str,
+        sn: u32,
+        config: &DeviceConfigSet,
+    ) -> Self {
+        let body = xml::build_device_config_xml(sn, channel_id, &config.to_xml());
+        Self::xml(device_id, body)
+    }
+
+    pub fn ptz_position_query(device_id: impl Into<String>, channel_id: &str, sn: u32) -> Self {
+        let body = xml::build_ptz_position_query(sn, channel_id);
         Self::xml(device_id, body)
     }
 
@@ -221,18 +246,17 @@ impl CreateDeviceMessageRequest {
 
     pub fn record_info_query(
         device_id: impl Into<String>,
+        channel_id: &str,
         sn: u32,
         start_time: &str,
         end_time: &str,
     ) -> Self {
-        let device_id = device_id.into();
-        let body = xml::build_record_info_query(sn, &device_id, start_time, end_time);
+        let body = xml::build_record_info_query(sn, channel_id, start_time, end_time);
         Self::xml(device_id, body)
     }
 
-    pub fn preset_query(device_id: impl Into<String>) -> Self {
-        let device_id = device_id.into();
-        let body = xml::build_preset_query_xml(&device_id);
+    pub fn preset_query(device_id: impl Into<String>, channel_id: &str, sn: u32) -> Self {
+        let body = xml::build_preset_query_xml(sn, channel_id);
         Self::xml(device_id, body)
     }
 
@@ -4,6 +4,7 @@ pub mod adapter;
 pub mod auth;
 pub mod bye;
 pub mod command;
+pub mod config;
 pub mod invite;
 pub mod message;
 pub mod native_runtime;
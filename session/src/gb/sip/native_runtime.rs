@@ -16,9 +16,9 @@ use base::tokio::time;
 use base::tokio_util::sync::CancellationToken;
 use gmv_pjsip::{
     AuthAlgorithm, AuthCredential, CredentialKind, SipAuthLookupResult, SipDialogRequest,
-    SipInviteResponse, SipOutboundInvite, SipOutboundMessage, SipOutboundSubscribe,
-    SipRestoredDialogRequest, SipRuntime, SipRuntimeConfig, SipRuntimeEvent, SipRuntimeEventKind,
-    SipRuntimeSockets, SipTransportProtocol,
+    SipInviteResponse, SipOutboundInvite, SipOutboundMessage, SipOutboundRegister,
+    SipOutboundSubscribe, SipRestoredDialogRequest, SipRuntime, SipRuntimeConfig, SipRuntimeEvent,
+    SipRuntimeEventKind, SipRuntimeSockets, SipTransportProtocol,
 };
 
 use super::adapter::{GbSipEvent, apply_business_event};
@@ -28,6 +28,7 @@ use super::invite::GbIncomingInviteEvent;
 use super::message::GbMessageEvent;
 use super::register::GbRegisterEvent;
 use super::runtime_cache::SipRuntimeCache;
+use crate::gb::cascade;
 use crate::register::core::Register;
 
 const AUTH_BATCH_WINDOW: Duration = Duration::from_millis(5);
@@ -62,6 +63,7 @@ enum RuntimeCommand {
     SendRestoredDialog(SipRestoredDialogRequest),
     RespondInvite(SipInviteResponse),
     SendSubscribe(SipOutboundSubscribe),
+    SendRegister(SipOutboundRegister),
     CloseTransport { association_id: u64, status: i32 },
 }
 
@@ -200,6 +202,18 @@ impl NativeSipRuntimeHandle {
         self.try_send(RuntimeCommand::SendSubscribe(subscribe))
     }
 
+    /// Upstream platforms are not bound to a device association; the runtime
+    /// resolves or opens the transport from the target URI.
+    pub fn send_upstream_message(&self, mut message: SipOutboundMessage) -> GlobalResult<()> {
+        message.association_id = 0;
+        self.try_send(RuntimeCommand::SendMessage(message))
+    }
+
+    pub fn send_register(&self, mut register: SipOutboundRegister) -> GlobalResult<()> {
+        register.association_id = 0;
+        self.try_send(RuntimeCommand::SendRegister(register))
+    }
+
     pub fn close_transport(&self, association: &Association, status: i32) {
         if !matches!(association.protocol, Protocol::TCP) {
             return;
@@ -412,6 +426,20 @@ impl NativeSipRuntimeService {
                                     }
                                 }
                             }
+                            RuntimeCommand::SendRegister(register) => {
+                                if let Err(err) = runtime.send_register(&register) {
+                                    warn!(
+                                        "send native SIP REGISTER failed: operation_id={}, \
+                                         err={err}",
+                                        register.operation_id
+                                    );
+                                    SipRuntimeCache::global().complete_native_response(
+                                        register.operation_id,
+                                        503,
+                                        Default::default(),
+                                    );
+                                }
+                            }
                             RuntimeCommand::CloseTransport {
                                 association_id,
                                 status,
@@ -631,6 +659,9 @@ async fn run_native_business_events(
                 _ => {}
             }
         }
+        if cascade::dispatch_native(&event, &runtime) {
+            continue;
+        }
         let message_event = match GbMessageEvent::from_native(&event) {
             Ok(event) => event,
             Err(err) => {
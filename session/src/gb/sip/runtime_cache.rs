@@ -18,7 +18,8 @@ use gmv_pjsip::message::{extract_tag, extract_uri};
 
 use super::bye::GbByeEvent;
 use super::invite::{GbIncomingInviteEvent, GbInviteAcceptedEvent};
-use crate::state::session::Cache;
+use crate::state::model::{PresetItem, RecordInfoItem};
+use crate::state::session::{Cache, SubscriptionKind};
 
 static SIP_RUNTIME_CACHE: Lazy<SipRuntimeCache> = Lazy::new(SipRuntimeCache::default);
 
@@ -32,6 +33,11 @@ pub struct SipRuntimeCache {
     native_subscription_waiters: DashMap<u64, NativeSubscriptionWaiter>,
     broadcast_response_waiters: DashMap<BroadcastResponseKey, BroadcastResponseWaiter>,
     broadcast_invite_waiters: DashMap<String, BroadcastInviteWaiter>,
+    record_info_waiters: XmlQueryWaiters<RecordInfoItem>,
+    preset_waiters: XmlQueryWaiters<PresetItem>,
+    query_waiters: XmlQueryWaiters<(String, String)>,
+    /// 通道预置位缓存：(设备ID, 通道ID)
+    presets: DashMap<(String, String), Vec<PresetItem>>,
     call_stream_index: DashMap<String, String>,
 }
 
@@ -46,6 +52,136 @@ struct BroadcastResponseWaiter {
     tx: oneshot::Sender<bool>,
 }
 
+/// 设备 MANSCDP 应答按 SN 与设备关联
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct XmlQueryKey {
+    pub sn: String,
+    pub device_id: String,
+}
+
+/// 分页应答条目：设备重发的分页按 `duplicates` 去重后再与 SumNum 比较
+pub trait XmlQueryItem {
+    fn duplicates(&self, _other: &Self) -> bool {
+        false
+    }
+}
+
+impl XmlQueryItem for RecordInfoItem {
+    fn duplicates(&self, other: &Self) -> bool {
+        self.start_time == other.start_time
+            && self.end_time == other.end_time
+            && self.file_path == other.file_path
+    }
+}
+
+impl XmlQueryItem for PresetItem {
+    fn duplicates(&self, other: &Self) -> bool {
+        self.preset_id == other.preset_id
+    }
+}
+
+/// 单包应答的查询/控制：整包 XML 条目作为一页，不去重
+impl XmlQueryItem for (String, String) {}
+
+struct XmlQueryWaiter<T> {
+    deadline: Instant,
+    sum_num: Option<usize>,
+    items: Vec<T>,
+    tx: oneshot::Sender<Vec<T>>,
+}
+
+/// 按 SN 聚合的 MANSCDP 应答等待：RecordInfo/PresetQuery 可能分多条 MESSAGE 返回，
+/// 累计条数达到 SumNum 时完成；未携带 SumNum 视为单包应答
+pub struct XmlQueryWaiters<T> {
+    waiters: DashMap<XmlQueryKey, XmlQueryWaiter<T>>,
+}
+
+impl<T> Default for XmlQueryWaiters<T> {
+    fn default() -> Self {
+        Self {
+            waiters: DashMap::new(),
+        }
+    }
+}
+
+impl<T: XmlQueryItem> XmlQueryWaiters<T> {
+    pub fn insert(&self, key: XmlQueryKey, ttl: Duration) -> oneshot::Receiver<Vec<T>> {
+        let (tx, rx) = oneshot::channel();
+        self.waiters.insert(
+            key,
+            XmlQueryWaiter {
+                deadline: Instant::now() + ttl,
+                sum_num: None,
+                items: Vec::new(),
+                tx,
+            },
+        );
+        rx
+    }
+
+    /// 追加一页应答，无对应等待时返回 false
+    pub fn append(&self, sn: &str, device_id: &str, sum_num: Option<usize>, items: Vec<T>) -> bool {
+        let key = XmlQueryKey {
+            sn: sn.to_string(),
+            device_id: device_id.to_string(),
+        };
+        let completed = {
+            let Some(mut waiter) = self.waiters.get_mut(&key) else {
+                return false;
+            };
+            if sum_num.is_some() {
+                waiter.sum_num = sum_num;
+            }
+            for item in items {
+                if !waiter.items.iter().any(|exist| exist.duplicates(&item)) {
+                    waiter.items.push(item);
+                }
+            }
+            waiter
+                .sum_num
+                .is_none_or(|sum_num| waiter.items.len() >= sum_num)
+        };
+        if completed {
+            if let Some((_, waiter)) = self.waiters.remove(&key) {
+                let _ = waiter.tx.send(waiter.items);
+            }
+        }
+        true
+    }
+
+    /// 移除等待并取出已收到的条目，用于超时时返回部分结果
+    pub fn take(&self, key: &XmlQueryKey) -> Option<Vec<T>> {
+        self.waiters.remove(key).map(|(_, waiter)| waiter.items)
+    }
+
+    pub fn remove(&self, key: &XmlQueryKey) {
+        self.waiters.remove(key);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiters.is_empty()
+    }
+
+    /// 过期时交付已收到的分页；无任何条目时丢弃等待，调用方按超时处理
+    fn cleanup_expired(&self, now: Instant) -> usize {
+        let expired = self
+            .waiters
+            .iter()
+            .filter_map(|item| (item.deadline <= now).then(|| item.key().clone()))
+            .collect::<Vec<_>>();
+        let mut count = 0;
+        for key in expired {
+            if let Some((_, waiter)) = self.waiters.remove(&key) {
+                if !waiter.items.is_empty() {
+                    let _ = waiter.tx.send(waiter.items);
+                }
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
 struct BroadcastInviteWaiter {
     deadline: Instant,
     source_id: String,
@@ -124,6 +260,7 @@ struct NativeInviteWaiter {
 #[derive(Clone, Debug)]
 pub struct NativeSubscriptionMetadata {
     pub device_id: String,
+    pub kind: SubscriptionKind,
     pub event: String,
     pub expires: u32,
     pub remote_target: String,
@@ -170,6 +307,35 @@ impl SipRuntimeCache {
         self.broadcast_response_waiters.remove(key);
     }
 
+    pub fn record_info_waiters(&self) -> &XmlQueryWaiters<RecordInfoItem> {
+        &self.record_info_waiters
+    }
+
+    pub fn preset_waiters(&self) -> &XmlQueryWaiters<PresetItem> {
+        &self.preset_waiters
+    }
+
+    /// 单包应答的查询/控制(DeviceStatus/PtzPosition/ConfigDownload 等)
+    pub fn query_waiters(&self) -> &XmlQueryWaiters<(String, String)> {
+        &self.query_waiters
+    }
+
+    pub fn cache_presets(&self, device_id: &str, channel_id: &str, presets: Vec<PresetItem>) {
+        self.presets
+            .insert((device_id.to_string(), channel_id.to_string()), presets);
+    }
+
+    pub fn cached_presets(&self, device_id: &str, channel_id: &str) -> Option<Vec<PresetItem>> {
+        self.presets
+            .get(&(device_id.to_string(), channel_id.to_string()))
+            .map(|item| item.value().clone())
+    }
+
+    pub fn invalidate_presets(&self, device_id: &str, channel_id: &str) {
+        self.presets
+            .remove(&(device_id.to_string(), channel_id.to_string()));
+    }
+
     pub fn insert_broadcast_invite_waiter(
         &self,
         target_id: String,
@@ -452,8 +618,9 @@ impl SipRuntimeCache {
             .as_deref()
             .and_then(extract_uri)
             .unwrap_or_else(|| pending.remote_target.clone());
-        let Some(generation) = Cache::catalog_subscription_begin(
+        let Some(generation) = Cache::subscription_begin(
             pending.device_id.clone(),
+            pending.kind,
             call_id,
             cseq,
             pending.event.clone(),
@@ -465,8 +632,9 @@ impl SipRuntimeCache {
         ) else {
             return true;
         };
-        let completed = Cache::catalog_subscription_complete(
+        let completed = Cache::subscription_complete(
             &pending.device_id,
+            pending.kind,
             generation,
             remote_target,
             Vec::new(),
@@ -476,14 +644,20 @@ impl SipRuntimeCache {
         );
         if completed {
             let expires = response.expires.unwrap_or(pending.expires).max(1);
-            Cache::catalog_subscription_update_expires(&pending.device_id, generation, expires);
-            super::subscription::schedule_catalog_refresh(
+            Cache::subscription_update_expires(
+                &pending.device_id,
+                pending.kind,
+                generation,
+                expires,
+            );
+            super::subscription::schedule_refresh(
                 pending.device_id.clone().into(),
+                pending.kind,
                 generation,
                 expires,
             );
         } else {
-            Cache::catalog_subscription_remove(&pending.device_id, Some(generation));
+            Cache::subscription_remove(&pending.device_id, pending.kind, Some(generation));
         }
         completed
     }
@@ -728,6 +902,10 @@ impl SipRuntimeCache {
             self.broadcast_invite_waiters.remove(&key);
         }
 
+        response_waiters += self.record_info_waiters.cleanup_expired(now);
+        response_waiters += self.preset_waiters.cleanup_expired(now);
+        response_waiters += self.query_waiters.cleanup_expired(now);
+
         RuntimeCleanupReport {
             invite_waiters,
             bye_waiters,
@@ -737,7 +915,7 @@ impl SipRuntimeCache {
     }
 }
 
-fn sip_user(header: &str) -> Option<String> {
+pub(crate) fn sip_user(header: &str) -> Option<String> {
     let uri = extract_uri(header)?;
     let value = uri
         .strip_prefix("sip:")
@@ -891,4 +1069,168 @@ mod tests {
             503
         );
     }
+
+    #[test]
+    fn record_info_waiter_merges_paged_responses_by_sn() {
+        let cache = SipRuntimeCache::default();
+        let key = XmlQueryKey {
+            sn: "40".into(),
+            device_id: "34020000001110000009".into(),
+        };
+        let mut rx = cache
+            .record_info_waiters
+            .insert(key, Duration::from_secs(1));
+        let page = |start: &str, end: &str| {
+            let xml = format!(
+                "<?xml version=\"1.0\"?><Response><CmdType>RecordInfo</CmdType><SN>40</SN>\
+                 <DeviceID>34020000001320000102</DeviceID><SumNum>3</SumNum>\
+                 <RecordList Num=\"1\"><Item><DeviceID>34020000001320000102</DeviceID>\
+                 <StartTime>{start}</StartTime><EndTime>{end}</EndTime>\
+                 <Secrecy>0</Secrecy><Type>time</Type></Item></RecordList></Response>"
+            );
+            let items = super::super::xml::parse_items(xml.as_bytes()).expect("parse record info");
+            RecordInfoItem::from_response_items(&items)
+        };
+
+        let (sum_num, records) = page("2026-06-13T00:30:00", "2026-06-13T01:00:00");
+        assert!(
+            cache
+                .record_info_waiters
+                .append("40", "34020000001110000009", sum_num, records)
+        );
+        assert!(!cache.record_info_waiters.append(
+            "41",
+            "34020000001110000009",
+            sum_num,
+            Vec::new()
+        ));
+        // 设备重发的分页不计入 SumNum
+        let (sum_num, records) = page("2026-06-13T00:30:00", "2026-06-13T01:00:00");
+        cache
+            .record_info_waiters
+            .append("40", "34020000001110000009", sum_num, records);
+        assert!(rx.try_recv().is_err());
+        let (sum_num, records) = page("2026-06-13T00:00:00", "2026-06-13T00:30:00");
+        cache
+            .record_info_waiters
+            .append("40", "34020000001110000009", sum_num, records);
+        assert!(rx.try_recv().is_err());
+        let (sum_num, records) = page("2026-06-13T01:00:00", "2026-06-13T01:30:00");
+        cache
+            .record_info_waiters
+            .append("40", "34020000001110000009", sum_num, records);
+
+        let records = rx.try_recv().expect("record info completion");
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].record_type.as_deref(), Some("time"));
+        assert!(cache.record_info_waiters.is_empty());
+    }
+
+    #[test]
+    fn record_info_waiter_keeps_partial_records_on_timeout() {
+        let cache = SipRuntimeCache::default();
+        let key = XmlQueryKey {
+            sn: "43".into(),
+            device_id: "34020000001110000009".into(),
+        };
+        let record = |start: &str| RecordInfoItem {
+            device_id: "34020000001320000102".into(),
+            start_time: start.into(),
+            end_time: "2026-06-13T01:00:00".into(),
+            ..Default::default()
+        };
+
+        let _rx = cache
+            .record_info_waiters
+            .insert(key.clone(), Duration::from_secs(1));
+        cache.record_info_waiters.append(
+            "43",
+            "34020000001110000009",
+            Some(3),
+            vec![record("2026-06-13T00:00:00")],
+        );
+        let records = cache
+            .record_info_waiters
+            .take(&key)
+            .expect("partial records");
+        assert_eq!(records.len(), 1);
+        assert!(cache.record_info_waiters.take(&key).is_none());
+
+        let mut rx = cache.record_info_waiters.insert(key, Duration::ZERO);
+        cache.record_info_waiters.append(
+            "43",
+            "34020000001110000009",
+            Some(3),
+            vec![record("2026-06-13T00:00:00"), record("2026-06-13T00:30:00")],
+        );
+        assert_eq!(cache.cleanup_expired().response_waiters, 1);
+        assert_eq!(rx.try_recv().expect("expired partial records").len(), 2);
+    }
+
+    #[test]
+    fn preset_waiter_merges_paged_responses_by_sn() {
+        let cache = SipRuntimeCache::default();
+        let key = XmlQueryKey {
+            sn: "42".into(),
+            device_id: "34020000001110000009".into(),
+        };
+        let mut rx = cache
+            .preset_waiters
+            .insert(key.clone(), Duration::from_secs(1));
+        let page = |id: u8| {
+            let xml = format!(
+                "<?xml version=\"1.0\"?><Response><CmdType>PresetQuery</CmdType><SN>42</SN>\
+                 <DeviceID>34020000001320000102</DeviceID><SumNum>2</SumNum>\
+                 <PresetList Num=\"1\"><Item><PresetID>{id}</PresetID>\
+                 <PresetName>p{id}</PresetName></Item></PresetList></Response>"
+            );
+            let items = super::super::xml::parse_items(xml.as_bytes()).expect("parse presets");
+            PresetItem::from_response_items(&items)
+        };
+
+        let (sum_num, presets) = page(1);
+        assert!(
+            cache
+                .preset_waiters
+                .append("42", "34020000001110000009", sum_num, presets)
+        );
+        assert!(rx.try_recv().is_err());
+        let (sum_num, presets) = page(2);
+        cache
+            .preset_waiters
+            .append("42", "34020000001110000009", sum_num, presets);
+        let presets = rx.try_recv().expect("preset completion");
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets[1].preset_name.as_deref(), Some("p2"));
+        assert!(cache.preset_waiters.is_empty());
+
+        // 未携带 SumNum 的设备单包完成
+        let mut rx = cache.preset_waiters.insert(key, Duration::from_secs(1));
+        cache
+            .preset_waiters
+            .append("42", "34020000001110000009", None, Vec::new());
+        assert!(rx.try_recv().expect("preset completion").is_empty());
+    }
+
+    #[test]
+    fn query_waiter_completes_on_single_response() {
+        let cache = SipRuntimeCache::default();
+        let key = XmlQueryKey {
+            sn: "44".into(),
+            device_id: "34020000001110000009".into(),
+        };
+        let mut rx = cache.query_waiters.insert(key, Duration::from_secs(1));
+        let items = vec![
+            ("Response,Item,Status".to_string(), "ON".to_string()),
+            ("Response,Item,Status".to_string(), "ON".to_string()),
+        ];
+        assert!(
+            cache
+                .query_waiters
+                .append("44", "34020000001110000009", None, items)
+        );
+        // 单包应答不去重
+        assert_eq!(rx.try_recv().expect("query completion").len(), 2);
+        assert!(cache.query_waiters.is_empty());
+    }
 }
@@ -10,7 +10,8 @@ use gmv_pjsip::message::extract_uri;
 
 use crate::gb::SessionConf;
 use crate::register::core::{Register, TimeScheduleKey};
-use crate::state::session::{Cache, CatalogSubscriptionCommand};
+use crate::state::AlarmConf;
+use crate::state::session::{Cache, SubscriptionCommand, SubscriptionKind};
 
 use super::adapter::pjsip_protocol_from_base;
 use super::message::{GB_XML_CONTENT_TYPE, GbMessageEvent, target_uri};
@@ -22,19 +23,108 @@ use super::xml;
 
 const SUBSCRIBE_WAIT_TIMEOUT: Duration = Duration::from_secs(8);
 const CATALOG_EVENT: &str = "Catalog";
+// 报警与移动位置订阅沿用 presence 事件包，由 XML CmdType 区分
+const PRESENCE_EVENT: &str = "presence";
+pub const DEFAULT_POSITION_INTERVAL: u32 = 5;
 
 pub async fn subscribe_catalog(device_id: &str, expires: u32) -> GlobalResult<()> {
+    subscribe(device_id, SubscriptionKind::Catalog, expires).await
+}
+
+pub async fn subscribe_alarm(device_id: &str, expires: u32) -> GlobalResult<()> {
+    subscribe(device_id, SubscriptionKind::Alarm, expires).await
+}
+
+/// 订阅移动位置：已有订阅时先取消，再按新的上报间隔重新订阅
+pub async fn subscribe_mobile_position(
+    device_id: &str,
+    interval: u32,
+    expires: u32,
+) -> GlobalResult<()> {
+    if Cache::mobile_position_interval(device_id).is_some() {
+        let _ = unsubscribe(device_id, SubscriptionKind::MobilePosition).await;
+    }
+    Cache::mobile_position_interval_set(device_id, interval.max(1));
+    subscribe(device_id, SubscriptionKind::MobilePosition, expires).await
+}
+
+/// 取消订阅：在原对话内发送 Expires=0 的 SUBSCRIBE
+pub async fn unsubscribe(device_id: &str, kind: SubscriptionKind) -> GlobalResult<()> {
+    if kind == SubscriptionKind::MobilePosition {
+        Cache::mobile_position_interval_remove(device_id);
+    }
+    let Some(command) = Cache::subscription_take_cancel(device_id, kind) else {
+        return Ok(());
+    };
+    let Some(session) = Register::get_connected_device_session(device_id) else {
+        return Ok(());
+    };
+    let runtime = NativeSipRuntimeHandle::global()?;
+    let operation_id = runtime.next_operation_id();
+    let rx = SipRuntimeCache::global()
+        .insert_native_response_waiter(operation_id, SUBSCRIBE_WAIT_TIMEOUT);
+    let request = SipOutboundSubscribe {
+        operation_id,
+        association_id: 0,
+        protocol: pjsip_protocol_from_base(session.association.protocol),
+        target_uri: String::new(),
+        from_uri: String::new(),
+        contact_uri: String::new(),
+        call_id: Some(command.call_id),
+        event: command.event,
+        expires: 0,
+        content_type: GB_XML_CONTENT_TYPE.to_string(),
+        body: xml::encode_document(
+            &subscription_body(device_id, kind, 0),
+            session.gb_version.as_deref(),
+        )
+        .to_vec(),
+    };
+    if let Err(err) = runtime.send_subscribe(&session.association, request) {
+        SipRuntimeCache::global().remove_native_response_waiter(operation_id);
+        return Err(err);
+    }
+    let response = recv_with_timeout(rx, SUBSCRIBE_WAIT_TIMEOUT)
+        .await
+        .map_err(|reason| {
+            SipRuntimeCache::global().remove_native_response_waiter(operation_id);
+            subscription_timeout(device_id, operation_id, reason)
+        })?;
+    // 481 表示设备侧对话已不存在，同样视为取消成功
+    if (200..300).contains(&response.status) || response.status == 481 {
+        Ok(())
+    } else {
+        Err(subscription_rejected(device_id, kind, response.status))
+    }
+}
+
+async fn subscribe(device_id: &str, kind: SubscriptionKind, expires: u32) -> GlobalResult<()> {
     let expires = expires.max(1);
-    match subscribe_catalog_once(device_id, expires).await {
+    match subscribe_once(device_id, kind, expires).await {
         Ok(()) => Ok(()),
         Err(err) => {
-            retry_new_catalog_subscription(device_id.to_string(), expires);
+            retry_new_subscription(device_id.to_string(), kind, expires);
             Err(err)
         }
     }
 }
 
-async fn subscribe_catalog_once(device_id: &str, expires: u32) -> GlobalResult<()> {
+fn event_name(kind: SubscriptionKind) -> &'static str {
+    match kind {
+        SubscriptionKind::Catalog => CATALOG_EVENT,
+        SubscriptionKind::Alarm | SubscriptionKind::MobilePosition => PRESENCE_EVENT,
+    }
+}
+
+/// 目标订阅是否仍需保持：移动位置订阅被取消后不再重建
+fn subscription_wanted(device_id: &str, kind: SubscriptionKind) -> bool {
+    match kind {
+        SubscriptionKind::MobilePosition => Cache::mobile_position_interval(device_id).is_some(),
+        SubscriptionKind::Catalog | SubscriptionKind::Alarm => true,
+    }
+}
+
+async fn subscribe_once(device_id: &str, kind: SubscriptionKind, expires: u32) -> GlobalResult<()> {
     let Some(session) = Register::get_connected_device_session(device_id) else {
         return Err(device_not_connected(device_id));
     };
@@ -52,7 +142,8 @@ async fn subscribe_catalog_once(device_id: &str, expires: u32) -> GlobalResult<(
         operation_id,
         NativeSubscriptionMetadata {
             device_id: device_id.to_string(),
-            event: CATALOG_EVENT.to_string(),
+            kind,
+            event: event_name(kind).to_string(),
             expires,
             remote_target: remote_target.clone(),
         },
@@ -75,11 +166,11 @@ async fn subscribe_catalog_once(device_id: &str, expires: u32) -> GlobalResult<(
             )
         ),
         call_id: None,
-        event: CATALOG_EVENT.to_string(),
+        event: event_name(kind).to_string(),
         expires,
         content_type: GB_XML_CONTENT_TYPE.to_string(),
         body: xml::encode_document(
-            &catalog_subscription_body(device_id, expires),
+            &subscription_body(device_id, kind, expires),
             session.gb_version.as_deref(),
         )
         .to_vec(),
@@ -97,20 +188,21 @@ async fn subscribe_catalog_once(device_id: &str, expires: u32) -> GlobalResult<(
     if (200..300).contains(&response.status) {
         Ok(())
     } else {
-        Err(subscription_rejected(device_id, response.status))
+        Err(subscription_rejected(device_id, kind, response.status))
     }
 }
 
-pub async fn refresh_catalog_subscription(
+pub async fn refresh_subscription(
     device_id: Arc<str>,
+    kind: SubscriptionKind,
     generation: u64,
 ) -> GlobalResult<()> {
-    let Some(command) = Cache::catalog_subscription_take_refresh(device_id.as_ref(), generation)
+    let Some(command) = Cache::subscription_take_refresh(device_id.as_ref(), kind, generation)
     else {
         return Ok(());
     };
     let Some(session) = Register::get_connected_device_session(device_id.as_ref()) else {
-        Cache::catalog_subscription_mark_failed(device_id.as_ref(), generation);
+        Cache::subscription_mark_failed(device_id.as_ref(), kind, generation);
         return Err(device_not_connected(device_id.as_ref()));
     };
     let runtime = NativeSipRuntimeHandle::global()?;
@@ -129,36 +221,38 @@ pub async fn refresh_catalog_subscription(
         expires: command.expires,
         content_type: GB_XML_CONTENT_TYPE.to_string(),
         body: xml::encode_document(
-            &catalog_subscription_body(device_id.as_ref(), command.expires),
+            &subscription_body(device_id.as_ref(), kind, command.expires),
             session.gb_version.as_deref(),
         )
         .to_vec(),
     };
     if let Err(err) = runtime.send_subscribe(&session.association, request) {
         SipRuntimeCache::global().remove_native_response_waiter(operation_id);
-        Cache::catalog_subscription_mark_failed(device_id.as_ref(), generation);
+        Cache::subscription_mark_failed(device_id.as_ref(), kind, generation);
         return Err(err);
     }
     let response = recv_with_timeout(rx, SUBSCRIBE_WAIT_TIMEOUT)
         .await
         .map_err(|reason| {
             SipRuntimeCache::global().remove_native_response_waiter(operation_id);
-            Cache::catalog_subscription_mark_failed(device_id.as_ref(), generation);
-            schedule_catalog_retry(device_id.clone(), generation, command.expires);
+            Cache::subscription_mark_failed(device_id.as_ref(), kind, generation);
+            schedule_retry(device_id.clone(), kind, generation, command.expires);
             subscription_timeout(device_id.as_ref(), operation_id, reason)
         })?;
-    complete_refresh(device_id, command, response)
+    complete_refresh(device_id, kind, command, response)
 }
 
 fn complete_refresh(
     device_id: Arc<str>,
-    command: CatalogSubscriptionCommand,
+    kind: SubscriptionKind,
+    command: SubscriptionCommand,
     response: SipResponseResult,
 ) -> GlobalResult<()> {
     let generation = command.generation;
     if (200..300).contains(&response.status) {
-        match complete_catalog_subscription(
+        match complete_subscription(
             device_id.as_ref(),
+            kind,
             generation,
             &command.remote_target,
             &command.from_header,
@@ -167,33 +261,38 @@ fn complete_refresh(
             response,
         ) {
             Ok(expires) => {
-                schedule_catalog_refresh(device_id, generation, expires);
+                schedule_refresh(device_id, kind, generation, expires);
                 Ok(())
             }
             Err(err) => {
-                Cache::catalog_subscription_mark_failed(device_id.as_ref(), generation);
-                schedule_catalog_retry(device_id, generation, command.expires);
+                Cache::subscription_mark_failed(device_id.as_ref(), kind, generation);
+                schedule_retry(device_id, kind, generation, command.expires);
                 Err(err)
             }
         }
     } else if response.status == 481 {
-        Cache::catalog_subscription_remove(device_id.as_ref(), Some(generation));
-        retry_new_catalog_subscription(device_id.to_string(), command.expires);
+        Cache::subscription_remove(device_id.as_ref(), kind, Some(generation));
+        retry_new_subscription(device_id.to_string(), kind, command.expires);
         Ok(())
     } else {
-        Cache::catalog_subscription_mark_failed(device_id.as_ref(), generation);
-        schedule_catalog_retry(device_id.clone(), generation, command.expires);
-        Err(subscription_rejected(device_id.as_ref(), response.status))
+        Cache::subscription_mark_failed(device_id.as_ref(), kind, generation);
+        schedule_retry(device_id.clone(), kind, generation, command.expires);
+        Err(subscription_rejected(
+            device_id.as_ref(),
+            kind,
+            response.status,
+        ))
     }
 }
 
-pub fn accept_catalog_notify(event: &GbMessageEvent, device_id: &str) -> bool {
+pub fn accept_notify(event: &GbMessageEvent, device_id: &str, kind: SubscriptionKind) -> bool {
     let (Some(call_id), Some(event_header)) = (event.call_id.as_deref(), event.event.as_deref())
     else {
         return false;
     };
-    let Some(generation) = Cache::catalog_subscription_validate_notify(
+    let Some(generation) = Cache::subscription_validate_notify(
         device_id,
+        kind,
         call_id,
         event_header,
         event.from_tag.as_deref(),
@@ -205,18 +304,20 @@ pub fn accept_catalog_notify(event: &GbMessageEvent, device_id: &str) -> bool {
     if let Some(state) = event.subscription_state.as_deref() {
         let (state, expires) = parse_subscription_state(state);
         if state.eq_ignore_ascii_case("terminated") {
-            terminate_catalog_subscription(device_id, generation);
+            terminate_subscription(device_id, kind, generation);
         } else if let Some(expires) = expires {
             let expires = expires.max(1);
-            Cache::catalog_subscription_update_expires(device_id, generation, expires);
-            schedule_catalog_refresh(Arc::from(device_id), generation, expires);
+            Cache::subscription_update_expires(device_id, kind, generation, expires);
+            schedule_refresh(Arc::from(device_id), kind, generation, expires);
         }
     }
     true
 }
 
-fn complete_catalog_subscription(
+#[allow(clippy::too_many_arguments)]
+fn complete_subscription(
     device_id: &str,
+    kind: SubscriptionKind,
     generation: u64,
     fallback_remote_target: &str,
     fallback_from_header: &str,
@@ -237,8 +338,9 @@ fn complete_catalog_subscription(
         .to_header
         .unwrap_or_else(|| fallback_to_header.to_string());
     let remote_tag = metadata.to_tag.unwrap_or_default();
-    if !Cache::catalog_subscription_complete(
+    if !Cache::subscription_complete(
         device_id,
+        kind,
         generation,
         remote_target,
         Vec::new(),
@@ -247,75 +349,104 @@ fn complete_catalog_subscription(
         remote_tag,
     ) {
         return Err(invalid_subscription(
-            "catalog subscription state changed before response",
+            "subscription state changed before response",
         ));
     }
     let expires = metadata.expires.unwrap_or(requested_expires).max(1);
-    Cache::catalog_subscription_update_expires(device_id, generation, expires);
+    Cache::subscription_update_expires(device_id, kind, generation, expires);
     Ok(expires)
 }
 
 #[test]
 fn test_catalog() {
-    let body = catalog_subscription_body("asf", 3600);
+    let body = subscription_body("asf", SubscriptionKind::Catalog, 3600);
     println!("{}", body);
 }
 
-fn catalog_subscription_body(device_id: &str, expires: u32) -> String {
+fn subscription_body(device_id: &str, kind: SubscriptionKind, expires: u32) -> String {
     let now = Local::now();
     let end = now + TimeDelta::seconds(i64::from(expires));
     let sn = super::sequence::next_sn();
-    xml::build_catalog_subscription(
-        sn,
-        device_id,
-        &now.format("%Y-%m-%dT%H:%M:%S").to_string(),
-        &end.format("%Y-%m-%dT%H:%M:%S").to_string(),
-    )
+    let start_time = now.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let end_time = end.format("%Y-%m-%dT%H:%M:%S").to_string();
+    match kind {
+        SubscriptionKind::Catalog => {
+            xml::build_catalog_subscription(sn, device_id, &start_time, &end_time)
+        }
+        SubscriptionKind::Alarm => xml::build_alarm_subscription(
+            sn,
+            device_id,
+            AlarmConf::get_alarm_conf().priority,
+            &start_time,
+            &end_time,
+        ),
+        SubscriptionKind::MobilePosition => xml::build_mobile_position_subscription(
+            sn,
+            device_id,
+            Cache::mobile_position_interval(device_id).unwrap_or(DEFAULT_POSITION_INTERVAL),
+        ),
+    }
 }
 
-pub(super) fn schedule_catalog_refresh(device_id: Arc<str>, generation: u64, expires: u32) {
-    let key = TimeScheduleKey::CatalogSubscription(device_id, generation);
+pub(super) fn schedule_refresh(
+    device_id: Arc<str>,
+    kind: SubscriptionKind,
+    generation: u64,
+    expires: u32,
+) {
+    let key = TimeScheduleKey::Subscription(device_id, kind, generation);
     let _ = Register::scheduler().remove_register(&key);
-    if let Err(err) = Register::scheduler().insert_register(key, catalog_refresh_delay(expires)) {
-        warn!("schedule catalog subscription refresh failed: {err}");
+    if let Err(err) = Register::scheduler().insert_register(key, refresh_delay(expires)) {
+        warn!(
+            "schedule {} subscription refresh failed: {err}",
+            kind.as_str()
+        );
     }
 }
 
-fn catalog_refresh_delay(expires: u32) -> Duration {
+fn refresh_delay(expires: u32) -> Duration {
     let advance = (expires / 10).clamp(1, 30);
     Duration::from_secs(u64::from(expires.saturating_sub(advance).max(1)))
 }
 
-fn schedule_catalog_retry(device_id: Arc<str>, generation: u64, expires: u32) {
-    let key = TimeScheduleKey::CatalogSubscription(device_id, generation);
+fn schedule_retry(device_id: Arc<str>, kind: SubscriptionKind, generation: u64, expires: u32) {
+    let key = TimeScheduleKey::Subscription(device_id, kind, generation);
     let _ = Register::scheduler().remove_register(&key);
     let delay = Duration::from_secs(u64::from(expires.clamp(1, 30)));
     if let Err(err) = Register::scheduler().insert_register(key, delay) {
-        warn!("schedule catalog subscription retry failed: {err}");
+        warn!(
+            "schedule {} subscription retry failed: {err}",
+            kind.as_str()
+        );
     }
 }
 
-fn terminate_catalog_subscription(device_id: &str, generation: u64) {
-    let expires = Cache::catalog_subscription_expires(device_id, generation);
-    if Cache::catalog_subscription_remove(device_id, Some(generation)) {
+fn terminate_subscription(device_id: &str, kind: SubscriptionKind, generation: u64) {
+    let expires = Cache::subscription_expires(device_id, kind, generation);
+    if Cache::subscription_remove(device_id, kind, Some(generation)) {
         if let Some(expires) = expires {
-            retry_new_catalog_subscription(device_id.to_string(), expires);
+            retry_new_subscription(device_id.to_string(), kind, expires);
         }
     }
 }
 
-fn retry_new_catalog_subscription(device_id: String, expires: u32) {
+fn retry_new_subscription(device_id: String, kind: SubscriptionKind, expires: u32) {
     base::tokio::spawn(async move {
         let mut delay = Duration::from_secs(5);
         loop {
             base::tokio::time::sleep(delay).await;
-            if Register::get_connected_device_session(&device_id).is_none() {
+            if Register::get_connected_device_session(&device_id).is_none()
+                || !subscription_wanted(&device_id, kind)
+            {
                 break;
             }
-            match subscribe_catalog_once(&device_id, expires).await {
+            match subscribe_once(&device_id, kind, expires).await {
                 Ok(()) => break,
                 Err(err) => {
-                    warn!("retry catalog subscription failed: device_id={device_id}, err={err}");
+                    warn!(
+                        "retry {} subscription failed: device_id={device_id}, err={err}",
+                        kind.as_str()
+                    );
                     delay = Duration::from_secs(30);
                 }
             }
@@ -343,12 +474,15 @@ fn subscription_timeout(device_id: &str, operation_id: u64, reason: &str) -> Glo
     )
 }
 
-fn subscription_rejected(device_id: &str, status: u16) -> GlobalError {
-    GlobalError::new_biz_error(
-        BaseErrorCode::InvalidState.code(),
-        "device rejected catalog subscription",
-        |msg| error!("device_id={device_id}; status={status}; {msg}"),
-    )
+fn subscription_rejected(device_id: &str, kind: SubscriptionKind, status: u16) -> GlobalError {
+    let message = match kind {
+        SubscriptionKind::Catalog => "device rejected catalog subscription",
+        SubscriptionKind::Alarm => "device rejected alarm subscription",
+        SubscriptionKind::MobilePosition => "device rejected mobile position subscription",
+    };
+    GlobalError::new_biz_error(BaseErrorCode::InvalidState.code(), message, |msg| {
+        error!("device_id={device_id}; status={status}; {msg}")
+    })
 }
 
 fn device_not_connected(device_id: &str) -> GlobalError {
@@ -369,20 +503,20 @@ fn invalid_subscription(message: &'static str) -> GlobalError {
 mod tests {
     use std::time::Duration;
 
-    use super::{catalog_refresh_delay, parse_subscription_state};
+    use super::{SubscriptionKind, parse_subscription_state, refresh_delay, subscription_body};
 
     #[test]
     fn schedules_catalog_refresh_before_native_refresh() {
-        assert_eq!(catalog_refresh_delay(3_600), Duration::from_secs(3_570));
-        assert_eq!(catalog_refresh_delay(300), Duration::from_secs(270));
-        assert_eq!(catalog_refresh_delay(5), Duration::from_secs(4));
-        assert_eq!(catalog_refresh_delay(1), Duration::from_secs(1));
+        assert_eq!(refresh_delay(3_600), Duration::from_secs(3_570));
+        assert_eq!(refresh_delay(300), Duration::from_secs(270));
+        assert_eq!(refresh_delay(5), Duration::from_secs(4));
+        assert_eq!(refresh_delay(1), Duration::from_secs(1));
     }
 
     #[test]
     fn catalog_refresh_builds_a_new_body() {
-        let first = super::catalog_subscription_body("device", 3_600);
-        let second = super::catalog_subscription_body("device", 3_600);
+        let first = subscription_body("device", SubscriptionKind::Catalog, 3_600);
+        let second = subscription_body("device", SubscriptionKind::Catalog, 3_600);
 
         assert_ne!(first, second);
     }
@@ -182,6 +182,41 @@ pub fn build_catalog_subscription(
     )
 }
 
+/// 报警订阅：订阅 1 ~ priority 级的全部报警方式
+pub fn build_alarm_subscription(
+    sn: u32,
+    device_id: &str,
+    priority: u8,
+    start_time: &str,
+    end_time: &str,
+) -> String {
+    build_simple_query(
+        "Alarm",
+        sn,
+        device_id,
+        &format!(
+            "<StartAlarmPriority>1</StartAlarmPriority>\r\n\
+<EndAlarmPriority>{}</EndAlarmPriority>\r\n\
+<AlarmMethod>0</AlarmMethod>\r\n\
+<StartAlarmTime>{}</StartAlarmTime>\r\n\
+<EndAlarmTime>{}</EndAlarmTime>\r\n",
+            priority,
+            escape(start_time),
+            escape(end_time),
+        ),
+    )
+}
+
+/// 移动位置订阅：Interval 为上报间隔，单位秒
+pub fn build_mobile_position_subscription(sn: u32, device_id: &str, interval: u32) -> String {
+    build_simple_query(
+        "MobilePosition",
+        sn,
+        device_id,
+        &format!("<Interval>{interval}</Interval>\r\n"),
+    )
+}
+
 pub fn build_device_info_query(sn: u32, device_id: &str) -> String {
     format!(
         "<?xml version=\"1.0\" encoding=\"GB2312\"?>\r\n\
@@ -223,6 +258,36 @@ pub fn build_config_download_query(sn: u32, device_id: &str, config_type: &str)
     )
 }
 
+/// DeviceControl 请求：fragment 为已转义的控制命令段
+pub fn build_device_control_xml(sn: u32, device_id: &str, fragment: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"GB2312\"?>\r\n\
+<Control>\r\n\
+<CmdType>DeviceControl</CmdType>\r\n\
+<SN>{}</SN>\r\n\
+<DeviceID>{}</DeviceID>\r\n\
+{}</Control>\r\n",
+        sn,
+        escape(device_id),
+        fragment,
+    )
+}
+
+/// DeviceConfig 请求：fragment 为已转义的配置段
+pub fn build_device_config_xml(sn: u32, device_id: &str, fragment: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"GB2312\"?>\r\n\
+<Control>\r\n\
+<CmdType>DeviceConfig</CmdType>\r\n\
+<SN>{}</SN>\r\n\
+<DeviceID>{}</DeviceID>\r\n\
+{}</Control>\r\n",
+        sn,
+        escape(device_id),
+        fragment,
+    )
+}
+
 pub fn build_ptz_position_query(sn: u32, device_id: &str) -> String {
     build_simple_query("PTZPosition", sn, device_id, "")
 }
@@ -287,6 +352,14 @@ pub const NOTIFY_ALARM_PRIORITY: &str = "Notify,AlarmPriority";
 pub const NOTIFY_ALARM_TIME: &str = "Notify,AlarmTime";
 pub const NOTIFY_ALARM_METHOD: &str = "Notify,AlarmMethod";
 pub const NOTIFY_INFO_ALARM_TYPE: &str = "Notify,Info,AlarmType";
+pub const NOTIFY_ALARM_DESCRIPTION: &str = "Notify,AlarmDescription";
+pub const NOTIFY_LONGITUDE: &str = "Notify,Longitude";
+pub const NOTIFY_LATITUDE: &str = "Notify,Latitude";
+pub const NOTIFY_INFO_EVENT_TYPE: &str = "Notify,Info,AlarmTypeParam,EventType";
+pub const NOTIFY_TIME: &str = "Notify,Time";
+pub const NOTIFY_SPEED: &str = "Notify,Speed";
+pub const NOTIFY_DIRECTION: &str = "Notify,Direction";
+pub const NOTIFY_ALTITUDE: &str = "Notify,Altitude";
 
 pub trait KV2Model: Sized {
     fn kv_to_model(arr: Vec<(String, String)>) -> base::exception::GlobalResult<Self>;
@@ -336,18 +409,26 @@ pub const RESPONSE_DEVICE_LIST_ITEM_IP_ADDRESS: &str = "Response,DeviceList,Item
 pub const RESPONSE_DEVICE_LIST_ITEM_PORT: &str = "Response,DeviceList,Item,Port";
 pub const RESPONSE_DEVICE_LIST_ITEM_PASSWORD: &str = "Response,DeviceList,Item,Password";
 pub const RESPONSE_DEVICE_LIST_ITEM_STATUS: &str = "Response,DeviceList,Item,Status";
+/// 目录变更事件：ON/OFF/VLOST/DEFECT/ADD/DEL/UPDATE，仅出现在 Catalog NOTIFY
+pub const RESPONSE_DEVICE_LIST_ITEM_EVENT: &str = "Response,DeviceList,Item,Event";
 pub const SPLIT_CLASS: &str = "?<-0_0->?";
 
-pub fn build_preset_query_xml(device_id: &str) -> String {
-    format!(
-        "<?xml version=\"1.0\" encoding=\"GB2312\"?>\r\n\
-<Query>\r\n\
-<CmdType>PresetQuery</CmdType>\r\n\
-<SN>1</SN>\r\n\
-<DeviceID>{}</DeviceID>\r\n\
-</Query>\r\n",
-        escape(device_id)
-    )
+pub const RESPONSE_SUM_NUM: &str = "Response,SumNum";
+pub const RESPONSE_RECORD_LIST_ITEM_DEVICE_ID: &str = "Response,RecordList,Item,DeviceID";
+pub const RESPONSE_RECORD_LIST_ITEM_NAME: &str = "Response,RecordList,Item,Name";
+pub const RESPONSE_RECORD_LIST_ITEM_FILE_PATH: &str = "Response,RecordList,Item,FilePath";
+pub const RESPONSE_RECORD_LIST_ITEM_ADDRESS: &str = "Response,RecordList,Item,Address";
+pub const RESPONSE_RECORD_LIST_ITEM_START_TIME: &str = "Response,RecordList,Item,StartTime";
+pub const RESPONSE_RECORD_LIST_ITEM_END_TIME: &str = "Response,RecordList,Item,EndTime";
+pub const RESPONSE_RECORD_LIST_ITEM_SECRECY: &str = "Response,RecordList,Item,Secrecy";
+pub const RESPONSE_RECORD_LIST_ITEM_TYPE: &str = "Response,RecordList,Item,Type";
+pub const RESPONSE_RECORD_LIST_ITEM_RECORDER_ID: &str = "Response,RecordList,Item,RecorderID";
+pub const RESPONSE_RECORD_LIST_ITEM_FILE_SIZE: &str = "Response,RecordList,Item,FileSize";
+pub const RESPONSE_PRESET_LIST_ITEM_PRESET_ID: &str = "Response,PresetList,Item,PresetID";
+pub const RESPONSE_PRESET_LIST_ITEM_PRESET_NAME: &str = "Response,PresetList,Item,PresetName";
+
+pub fn build_preset_query_xml(sn: u32, device_id: &str) -> String {
+    build_simple_query("PresetQuery", sn, device_id, "")
 }
 
 pub fn build_snapshot_control_xml(
@@ -381,9 +462,9 @@ pub fn build_snapshot_control_xml(
 #[cfg(test)]
 mod tests {
     use super::{
-        RESPONSE_DEVICE_LIST_ITEM_DEVICE_ID, SPLIT_CLASS, build_broadcast_notify,
-        build_catalog_subscription, build_config_download_query, build_cruise_track_query,
-        build_snapshot_control_xml, encode_document, parse_items,
+        RESPONSE_DEVICE_LIST_ITEM_DEVICE_ID, SPLIT_CLASS, build_alarm_subscription,
+        build_broadcast_notify, build_catalog_subscription, build_config_download_query,
+        build_cruise_track_query, build_snapshot_control_xml, encode_document, parse_items,
     };
     use encoding_rs::{GB18030, GBK};
 
@@ -422,6 +503,28 @@ mod tests {
         assert!(xml.contains("<EndTime>2026-06-11T13:00:00</EndTime>"));
     }
 
+    #[test]
+    fn builds_alarm_subscription_priority_window() {
+        let xml = build_alarm_subscription(
+            8,
+            "34020000001320000001",
+            3,
+            "2026-06-11T12:00:00",
+            "2026-06-11T13:00:00",
+        );
+        let items = parse_items(xml.as_bytes()).unwrap();
+        let value = |key: &str| {
+            items
+                .iter()
+                .find_map(|(k, v)| (k == key).then_some(v.as_str()))
+        };
+        assert_eq!(value("Query,CmdType"), Some("Alarm"));
+        assert_eq!(value("Query,StartAlarmPriority"), Some("1"));
+        assert_eq!(value("Query,EndAlarmPriority"), Some("3"));
+        assert_eq!(value("Query,AlarmMethod"), Some("0"));
+        assert_eq!(value("Query,EndAlarmTime"), Some("2026-06-11T13:00:00"));
+    }
+
     #[test]
     fn encodes_document_using_declared_gb2312() {
         let xml = "<?xml version=\"1.0\" encoding=\"GB2312\"?>\
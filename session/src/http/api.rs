@@ -1,16 +1,30 @@
 use crate::http::{get_gmv_token, res_by_error};
-use crate::service::{api_serv, edge_serv};
+use crate::service::{
+    alarm_serv, api_serv, device_serv, edge_serv, lifecycle_serv, oauth_serv, position_serv,
+};
 use crate::state::model::{
-    PlayBackModel, PlayLiveModel, PlaySeekModel, PlaySpeedModel, PtzControlModel, StreamInfo,
-    StreamQo,
+    AlarmAckModel, AlarmItem, AlarmQueryModel, ChannelItem, ChannelQueryModel, ChannelTreeNode,
+    ConfigDownloadModel, DeviceConfigInfo, DeviceConfigModel, DeviceControlModel, DeviceItem,
+    DeviceOauthCreateModel, DeviceOauthHeartbeatModel, DeviceOauthItem, DeviceOauthPwdModel,
+    DeviceOauthQueryModel, DeviceOauthStatusModel, DeviceQueryModel, DeviceStatusInfo,
+    DeviceTargetModel, DeviceTimelineInfo, DeviceTimelineModel, MobilePositionItem, PageData,
+    PlayBackModel, PlayLiveModel, PlayOsdModel, PlayOutputCloseModel, PlayOutputOpenModel,
+    PlaySeekModel, PlaySnapshotModel, PlaySpeedModel, PositionSubscribeModel, PositionTrackModel,
+    PresetItem, PresetQueryModel, PtzCmdModel, PtzControlModel, PtzPositionInfo, RecordInfoItem,
+    RecordQueryModel, StreamInfo, StreamQo,
 };
-use axum::http::HeaderMap;
+use axum::http::{HeaderMap, header};
+use axum::response::{IntoResponse, Response};
 use axum::{Json, Router};
 use base::log::info;
 use shared::info::obj::{
-    CONTROL_PTZ, DOWNING_INFO, DOWNLOAD_MP4, DOWNLOAD_STOP, PLAY_BACK, PLAY_LIVING, PLAY_SEEK,
-    PLAY_SPEED, RM_FILE, SingleParam, StreamRecordInfo, TALK_START, TALK_STOP, TalkInfo,
-    TalkStartModel, TalkStopModel,
+    ALARM_ACK, ALARM_PAGE, CHANNEL_PAGE, CHANNEL_TREE, CONFIG_DOWNLOAD, CONTROL_PTZ,
+    CONTROL_PTZ_CMD, DEVICE_CONFIG, DEVICE_CONTROL, DEVICE_PAGE, DEVICE_STATUS, DEVICE_TIMELINE,
+    DOWNING_INFO, DOWNLOAD_MP4, DOWNLOAD_STOP, OAUTH_CREATE, OAUTH_DELETE, OAUTH_HEARTBEAT,
+    OAUTH_PAGE, OAUTH_PWD, OAUTH_STATUS, PLAY_BACK, PLAY_LIVING, PLAY_OSD, PLAY_OUTPUT_CLOSE,
+    PLAY_OUTPUT_OPEN, PLAY_SEEK, PLAY_SNAPSHOT, PLAY_SPEED, POSITION_LATEST, POSITION_SUBSCRIBE,
+    POSITION_TRACK, POSITION_UNSUBSCRIBE, PRESET_LIST, PTZ_POSITION, RECORD_LIST, RM_FILE,
+    SingleParam, StreamRecordInfo, TALK_START, TALK_STOP, TalkInfo, TalkStartModel, TalkStopModel,
 };
 use shared::info::res::{EmptyResponse, Resp};
 
@@ -20,13 +34,44 @@ pub fn routes() -> Router {
         .route(PLAY_BACK, axum::routing::post(play_back))
         .route(PLAY_SEEK, axum::routing::post(play_seek))
         .route(PLAY_SPEED, axum::routing::post(play_speed))
+        .route(PLAY_SNAPSHOT, axum::routing::post(play_snapshot))
+        .route(PLAY_OUTPUT_OPEN, axum::routing::post(play_output_open))
+        .route(PLAY_OUTPUT_CLOSE, axum::routing::post(play_output_close))
+        .route(PLAY_OSD, axum::routing::post(play_osd))
         .route(CONTROL_PTZ, axum::routing::post(control_ptz))
+        .route(CONTROL_PTZ_CMD, axum::routing::post(control_ptz_cmd))
+        .route(PRESET_LIST, axum::routing::post(preset_list))
+        .route(PTZ_POSITION, axum::routing::post(ptz_position))
+        .route(DEVICE_STATUS, axum::routing::post(device_status))
+        .route(CONFIG_DOWNLOAD, axum::routing::post(config_download))
+        .route(DEVICE_CONFIG, axum::routing::post(device_config))
+        .route(DEVICE_CONTROL, axum::routing::post(device_control))
+        .route(RECORD_LIST, axum::routing::post(record_list))
         .route(DOWNLOAD_MP4, axum::routing::post(download_mp4))
         .route(DOWNLOAD_STOP, axum::routing::post(download_stop))
         .route(DOWNING_INFO, axum::routing::post(downing_info))
         .route(RM_FILE, axum::routing::post(rm_file))
         .route(TALK_START, axum::routing::post(talk_start))
         .route(TALK_STOP, axum::routing::post(talk_stop))
+        .route(DEVICE_PAGE, axum::routing::post(device_page))
+        .route(CHANNEL_PAGE, axum::routing::post(channel_page))
+        .route(CHANNEL_TREE, axum::routing::post(channel_tree))
+        .route(DEVICE_TIMELINE, axum::routing::post(device_timeline))
+        .route(ALARM_PAGE, axum::routing::post(alarm_page))
+        .route(ALARM_ACK, axum::routing::post(alarm_ack))
+        .route(POSITION_SUBSCRIBE, axum::routing::post(position_subscribe))
+        .route(
+            POSITION_UNSUBSCRIBE,
+            axum::routing::post(position_unsubscribe),
+        )
+        .route(POSITION_LATEST, axum::routing::post(position_latest))
+        .route(POSITION_TRACK, axum::routing::post(position_track))
+        .route(OAUTH_PAGE, axum::routing::post(oauth_page))
+        .route(OAUTH_CREATE, axum::routing::post(oauth_create))
+        .route(OAUTH_STATUS, axum::routing::post(oauth_status))
+        .route(OAUTH_PWD, axum::routing::post(oauth_pwd))
+        .route(OAUTH_HEARTBEAT, axum::routing::post(oauth_heartbeat))
+        .route(OAUTH_DELETE, axum::routing::post(oauth_delete))
 }
 
 #[cfg_attr(debug_assertions, utoipa::path(
@@ -132,6 +177,115 @@ async fn play_speed(headers: HeaderMap, Json(info): Json<PlaySpeedModel>) -> Jso
         Err(err) => Json(res_by_error(err)),
     }
 }
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/play/snapshot",
+    request_body = PlaySnapshotModel,
+    responses(
+        (status = 200, description = "抓拍成功，返回图片二进制(image/jpeg、image/png)；失败返回json", body = Resp<EmptyResponse>),
+        (status = 401, description = "Token无效", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 实时流抓拍：由媒体节点解码最近关键帧
+async fn play_snapshot(headers: HeaderMap, Json(info): Json<PlaySnapshotModel>) -> Response {
+    info!("play_snapshot: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::snapshot(info, token).await {
+            Ok((format, image)) => {
+                ([(header::CONTENT_TYPE, format.content_type())], image).into_response()
+            }
+            Err(err) => Json(res_by_error::<()>(err)).into_response(),
+        },
+        Err(err) => Json(res_by_error::<()>(err)).into_response(),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/play/output/open",
+    request_body = PlayOutputOpenModel,
+    responses(
+        (status = 200, description = "追加输出成功，false表示输出已存在", body = Resp<bool>),
+        (status = 401, description = "Token无效", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<bool>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 运行中的流追加输出：如开始本地录制、rtmp推流，无需重新点播
+async fn play_output_open(
+    headers: HeaderMap,
+    Json(info): Json<PlayOutputOpenModel>,
+) -> Json<Resp<bool>> {
+    info!("play_output_open: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::output_open(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/play/output/close",
+    request_body = PlayOutputCloseModel,
+    responses(
+        (status = 200, description = "移除输出成功，false表示输出不存在", body = Resp<bool>),
+        (status = 401, description = "Token无效", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<bool>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 运行中的流移除输出：如停止本地录制、rtmp推流
+async fn play_output_close(
+    headers: HeaderMap,
+    Json(info): Json<PlayOutputCloseModel>,
+) -> Json<Resp<bool>> {
+    info!("play_output_close: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::output_close(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/play/osd",
+    request_body = PlayOsdModel,
+    responses(
+        (status = 200, description = "水印更新成功", body = Resp<bool>),
+        (status = 401, description = "Token无效", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<bool>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 运行中的流更新水印：osd为空时移除
+async fn play_osd(headers: HeaderMap, Json(info): Json<PlayOsdModel>) -> Json<Resp<bool>> {
+    info!("play_osd: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::osd(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
 #[cfg_attr(debug_assertions, utoipa::path(
     post,
     path = "/api/control/ptz",
@@ -157,6 +311,227 @@ async fn control_ptz(headers: HeaderMap, Json(info): Json<PtzControlModel>) -> J
         Err(err) => Json(res_by_error(err)),
     }
 }
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/control/ptz/cmd",
+    request_body = PtzCmdModel,
+    responses(
+        (status = 200, description = "云台指令下发成功", body = Resp<bool>),
+        (status = 401, description = "Token无效", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<bool>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 摄像机云台扩展指令：预置位、巡航、扫描、聚焦/光圈、辅助开关
+async fn control_ptz_cmd(headers: HeaderMap, Json(info): Json<PtzCmdModel>) -> Json<Resp<bool>> {
+    info!("control_ptz_cmd: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::ptz_cmd(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/control/preset/list",
+    request_body = PresetQueryModel,
+    responses(
+        (status = 200, description = "预置位查询成功", body = Resp<Vec<PresetItem>>),
+        (status = 401, description = "Token无效", body = Resp<Vec<PresetItem>>),
+        (status = 500, description = "服务器内部错误", body = Resp<Vec<PresetItem>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 通道预置位列表：默认读取缓存，refresh 为 true 时重新向设备查询
+async fn preset_list(
+    headers: HeaderMap,
+    Json(info): Json<PresetQueryModel>,
+) -> Json<Resp<Vec<PresetItem>>> {
+    info!("preset_list: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::preset_list(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/control/ptz/position",
+    request_body = DeviceTargetModel,
+    responses(
+        (status = 200, description = "云台位置查询成功", body = Resp<PtzPositionInfo>),
+        (status = 401, description = "Token无效", body = Resp<PtzPositionInfo>),
+        (status = 500, description = "服务器内部错误", body = Resp<PtzPositionInfo>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 云台当前位置：水平/俯仰角、变倍与视场角
+async fn ptz_position(
+    headers: HeaderMap,
+    Json(info): Json<DeviceTargetModel>,
+) -> Json<Resp<PtzPositionInfo>> {
+    info!("ptz_position: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::ptz_position(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/status",
+    request_body = DeviceTargetModel,
+    responses(
+        (status = 200, description = "设备状态查询成功", body = Resp<DeviceStatusInfo>),
+        (status = 401, description = "Token无效", body = Resp<DeviceStatusInfo>),
+        (status = 500, description = "服务器内部错误", body = Resp<DeviceStatusInfo>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 设备/通道运行状态：在线、编码、录像、报警布防与设备时间
+async fn device_status(
+    headers: HeaderMap,
+    Json(info): Json<DeviceTargetModel>,
+) -> Json<Resp<DeviceStatusInfo>> {
+    info!("device_status: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::device_status(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/config/download",
+    request_body = ConfigDownloadModel,
+    responses(
+        (status = 200, description = "设备配置查询成功", body = Resp<DeviceConfigInfo>),
+        (status = 401, description = "Token无效", body = Resp<DeviceConfigInfo>),
+        (status = 500, description = "服务器内部错误", body = Resp<DeviceConfigInfo>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 设备配置查询：基本参数、视频参数、SVAC、OSD 与录像计划
+async fn config_download(
+    headers: HeaderMap,
+    Json(info): Json<ConfigDownloadModel>,
+) -> Json<Resp<DeviceConfigInfo>> {
+    info!("config_download: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::config_download(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/config",
+    request_body = DeviceConfigModel,
+    responses(
+        (status = 200, description = "设备配置成功", body = Resp<bool>),
+        (status = 401, description = "Token无效", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<bool>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 设备配置下发：等待设备应答 Result
+async fn device_config(
+    headers: HeaderMap,
+    Json(info): Json<DeviceConfigModel>,
+) -> Json<Resp<bool>> {
+    info!("device_config: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::device_config(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/control",
+    request_body = DeviceControlModel,
+    responses(
+        (status = 200, description = "设备控制成功", body = Resp<bool>),
+        (status = 401, description = "Token无效", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<bool>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 设备控制：远程启动、录像、布撤防、报警复位、强制关键帧、看守位与拉框缩放
+async fn device_control(
+    headers: HeaderMap,
+    Json(info): Json<DeviceControlModel>,
+) -> Json<Resp<bool>> {
+    info!("device_control: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::device_control(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/record/list",
+    request_body = RecordQueryModel,
+    responses(
+        (status = 200, description = "设备录像查询成功", body = Resp<Vec<RecordInfoItem>>),
+        (status = 401, description = "Token无效", body = Resp<Vec<RecordInfoItem>>),
+        (status = 500, description = "服务器内部错误", body = Resp<Vec<RecordInfoItem>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备媒体流操作API"
+))]
+/// 查询设备端录像：合并多包应答，按时间排序
+async fn record_list(
+    headers: HeaderMap,
+    Json(info): Json<RecordQueryModel>,
+) -> Json<Resp<Vec<RecordInfoItem>>> {
+    info!("record_list: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(token) => match api_serv::record_info(info, token).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
 //let recommendations = [
 //     ("短视频平台", "1-10分钟，<500MB"),
 //     ("在线课程", "15-45分钟，<1GB"),
@@ -169,7 +544,7 @@ async fn control_ptz(headers: HeaderMap, Json(info): Json<PtzControlModel>) -> J
     path = "/api/download/mp4",
     request_body = PlayBackModel,
     responses(
-        (status = 200, description = "MP4下载任务创建成功", body = Resp<String>),
+        (status = 200, description = "下载任务创建成功", body = Resp<String>),
         (status = 401, description = "Token无效", body = Resp<String>),
         (status = 500, description = "服务器内部错误", body = Resp<String>)
     ),
@@ -178,7 +553,7 @@ async fn control_ptz(headers: HeaderMap, Json(info): Json<PtzControlModel>) -> J
     ),
     tag = "设备媒体流操作API"
 ))]
-/// 历史视频mp4录制
+/// 历史视频录制：默认mp4，media_type为LocalTs时录制为ts
 async fn download_mp4(headers: HeaderMap, Json(info): Json<PlayBackModel>) -> Json<Resp<String>> {
     info!("download_mp4: body = {:?}", &info);
     match get_gmv_token(headers) {
@@ -292,3 +667,461 @@ async fn talk_stop(headers: HeaderMap, Json(info): Json<TalkStopModel>) -> Json<
         Err(err) => Json(res_by_error(err)),
     }
 }
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/page",
+    request_body = DeviceQueryModel,
+    responses(
+        (status = 200, description = "查询设备列表成功", body = Resp<PageData<DeviceItem>>),
+        (status = 401, description = "Token无效", body = Resp<PageData<DeviceItem>>),
+        (status = 500, description = "服务器内部错误", body = Resp<PageData<DeviceItem>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备目录API"
+))]
+/// 分页查询设备：在线状态、行政区划、名称、厂商过滤
+async fn device_page(
+    headers: HeaderMap,
+    Json(info): Json<DeviceQueryModel>,
+) -> Json<Resp<PageData<DeviceItem>>> {
+    info!("device_page: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match device_serv::device_page(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/channel/page",
+    request_body = ChannelQueryModel,
+    responses(
+        (status = 200, description = "查询通道列表成功", body = Resp<PageData<ChannelItem>>),
+        (status = 401, description = "Token无效", body = Resp<PageData<ChannelItem>>),
+        (status = 500, description = "服务器内部错误", body = Resp<PageData<ChannelItem>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备目录API"
+))]
+/// 分页查询通道：所属设备、父节点、在线状态、行政区划、名称、厂商过滤
+async fn channel_page(
+    headers: HeaderMap,
+    Json(info): Json<ChannelQueryModel>,
+) -> Json<Resp<PageData<ChannelItem>>> {
+    info!("channel_page: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match device_serv::channel_page(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/channel/tree",
+    request_body = SingleParam<String>,
+    responses(
+        (status = 200, description = "查询通道树成功", body = Resp<Vec<ChannelTreeNode>>),
+        (status = 401, description = "Token无效", body = Resp<Vec<ChannelTreeNode>>),
+        (status = 500, description = "服务器内部错误", body = Resp<Vec<ChannelTreeNode>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备目录API"
+))]
+/// 查询设备通道树：参数为设备ID
+async fn channel_tree(
+    headers: HeaderMap,
+    Json(info): Json<SingleParam<String>>,
+) -> Json<Resp<Vec<ChannelTreeNode>>> {
+    info!("channel_tree: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match device_serv::channel_tree(info.param).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/timeline",
+    request_body = DeviceTimelineModel,
+    responses(
+        (status = 200, description = "查询设备在线时间线成功", body = Resp<DeviceTimelineInfo>),
+        (status = 401, description = "Token无效", body = Resp<DeviceTimelineInfo>),
+        (status = 500, description = "服务器内部错误", body = Resp<DeviceTimelineInfo>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备目录API"
+))]
+/// 查询设备在线时间线：默认最近24小时，范围不超过31天
+async fn device_timeline(
+    headers: HeaderMap,
+    Json(info): Json<DeviceTimelineModel>,
+) -> Json<Resp<DeviceTimelineInfo>> {
+    info!("device_timeline: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match lifecycle_serv::timeline(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/alarm/page",
+    request_body = AlarmQueryModel,
+    responses(
+        (status = 200, description = "查询报警记录成功", body = Resp<PageData<AlarmItem>>),
+        (status = 401, description = "Token无效", body = Resp<PageData<AlarmItem>>),
+        (status = 500, description = "服务器内部错误", body = Resp<PageData<AlarmItem>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备报警API"
+))]
+/// 分页查询报警记录：设备、通道、级别、方式、类型、时间范围、确认状态过滤
+async fn alarm_page(
+    headers: HeaderMap,
+    Json(info): Json<AlarmQueryModel>,
+) -> Json<Resp<PageData<AlarmItem>>> {
+    info!("alarm_page: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match alarm_serv::alarm_page(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/alarm/ack",
+    request_body = AlarmAckModel,
+    responses(
+        (status = 200, description = "确认报警成功，返回确认条数", body = Resp<u64>),
+        (status = 401, description = "Token无效", body = Resp<u64>),
+        (status = 500, description = "服务器内部错误", body = Resp<u64>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备报警API"
+))]
+/// 确认报警：已确认的记录不重复确认
+async fn alarm_ack(headers: HeaderMap, Json(info): Json<AlarmAckModel>) -> Json<Resp<u64>> {
+    info!("alarm_ack: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match alarm_serv::alarm_ack(info).await {
+            Ok(count) => Json(Resp::build_success_data(count)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/position/subscribe",
+    request_body = PositionSubscribeModel,
+    responses(
+        (status = 200, description = "订阅移动位置成功", body = Resp<EmptyResponse>),
+        (status = 401, description = "Token无效", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "移动位置API"
+))]
+/// 订阅移动设备位置：设备按间隔通过 NOTIFY 上报，重新订阅会替换原上报间隔
+async fn position_subscribe(
+    headers: HeaderMap,
+    Json(info): Json<PositionSubscribeModel>,
+) -> Json<Resp<()>> {
+    info!("position_subscribe: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match position_serv::subscribe(info).await {
+            Ok(()) => Json(Resp::build_success()),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/position/unsubscribe",
+    request_body = SingleParam<String>,
+    responses(
+        (status = 200, description = "取消移动位置订阅成功", body = Resp<EmptyResponse>),
+        (status = 401, description = "Token无效", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "移动位置API"
+))]
+/// 取消移动设备位置订阅：参数为设备ID
+async fn position_unsubscribe(
+    headers: HeaderMap,
+    Json(info): Json<SingleParam<String>>,
+) -> Json<Resp<()>> {
+    info!("position_unsubscribe: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match position_serv::unsubscribe(info.param).await {
+            Ok(()) => Json(Resp::build_success()),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/position/latest",
+    request_body = DeviceTargetModel,
+    responses(
+        (status = 200, description = "查询最新位置成功，无上报时返回空", body = Resp<MobilePositionItem>),
+        (status = 401, description = "Token无效", body = Resp<MobilePositionItem>),
+        (status = 500, description = "服务器内部错误", body = Resp<MobilePositionItem>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "移动位置API"
+))]
+/// 查询设备最新位置：指定通道时仅取该通道上报
+async fn position_latest(
+    headers: HeaderMap,
+    Json(info): Json<DeviceTargetModel>,
+) -> Json<Resp<Option<MobilePositionItem>>> {
+    info!("position_latest: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match position_serv::latest(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/position/track",
+    request_body = PositionTrackModel,
+    responses(
+        (status = 200, description = "查询轨迹成功", body = Resp<Vec<MobilePositionItem>>),
+        (status = 401, description = "Token无效", body = Resp<Vec<MobilePositionItem>>),
+        (status = 500, description = "服务器内部错误", body = Resp<Vec<MobilePositionItem>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "移动位置API"
+))]
+/// 按时间范围查询轨迹：按定位时间升序返回
+async fn position_track(
+    headers: HeaderMap,
+    Json(info): Json<PositionTrackModel>,
+) -> Json<Resp<Vec<MobilePositionItem>>> {
+    info!("position_track: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match position_serv::track(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/oauth/page",
+    request_body = DeviceOauthQueryModel,
+    responses(
+        (status = 200, description = "分页查询设备接入记录成功", body = Resp<PageData<DeviceOauthItem>>),
+        (status = 401, description = "Token无效", body = Resp<PageData<DeviceOauthItem>>),
+        (status = 500, description = "服务器内部错误", body = Resp<PageData<DeviceOauthItem>>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备接入API"
+))]
+/// 分页查询设备接入记录：不返回密码，含停用记录
+async fn oauth_page(
+    headers: HeaderMap,
+    Json(info): Json<DeviceOauthQueryModel>,
+) -> Json<Resp<PageData<DeviceOauthItem>>> {
+    info!("oauth_page: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match oauth_serv::oauth_page(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/oauth/create",
+    request_body = DeviceOauthCreateModel,
+    responses(
+        (status = 200, description = "新增设备接入成功", body = Resp<EmptyResponse>),
+        (status = 401, description = "Token无效", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备接入API"
+))]
+/// 新增设备接入：域ID与认证域默认取本信令服务配置
+async fn oauth_create(
+    headers: HeaderMap,
+    Json(info): Json<DeviceOauthCreateModel>,
+) -> Json<Resp<()>> {
+    info!("oauth_create: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match oauth_serv::oauth_create(info).await {
+            Ok(()) => Json(Resp::build_success()),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/oauth/status",
+    request_body = DeviceOauthStatusModel,
+    responses(
+        (status = 200, description = "设置设备接入状态成功", body = Resp<EmptyResponse>),
+        (status = 401, description = "Token无效", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备接入API"
+))]
+/// 启用或停用设备接入：停用时踢下线在线会话
+async fn oauth_status(
+    headers: HeaderMap,
+    Json(info): Json<DeviceOauthStatusModel>,
+) -> Json<Resp<()>> {
+    info!("oauth_status: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match oauth_serv::oauth_status(info).await {
+            Ok(()) => Json(Resp::build_success()),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/oauth/pwd",
+    request_body = DeviceOauthPwdModel,
+    responses(
+        (status = 200, description = "轮换注册密码成功，返回新密码", body = Resp<String>),
+        (status = 401, description = "Token无效", body = Resp<String>),
+        (status = 500, description = "服务器内部错误", body = Resp<String>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备接入API"
+))]
+/// 轮换设备注册密码：不传新密码时随机生成，设备下次注册时生效
+async fn oauth_pwd(
+    headers: HeaderMap,
+    Json(info): Json<DeviceOauthPwdModel>,
+) -> Json<Resp<String>> {
+    info!("oauth_pwd: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match oauth_serv::oauth_rotate_pwd(info).await {
+            Ok(data) => Json(Resp::build_success_data(data)),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/oauth/heartbeat",
+    request_body = DeviceOauthHeartbeatModel,
+    responses(
+        (status = 200, description = "设置心跳周期成功", body = Resp<EmptyResponse>),
+        (status = 401, description = "Token无效", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备接入API"
+))]
+/// 设置设备心跳周期：设备下次注册后生效
+async fn oauth_heartbeat(
+    headers: HeaderMap,
+    Json(info): Json<DeviceOauthHeartbeatModel>,
+) -> Json<Resp<()>> {
+    info!("oauth_heartbeat: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match oauth_serv::oauth_heartbeat(info).await {
+            Ok(()) => Json(Resp::build_success()),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/api/device/oauth/delete",
+    request_body = SingleParam<String>,
+    responses(
+        (status = 200, description = "删除设备接入成功", body = Resp<EmptyResponse>),
+        (status = 401, description = "Token无效", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    security(
+        ("gmv_token" = [])
+    ),
+    tag = "设备接入API"
+))]
+/// 删除设备接入：参数为设备ID，软删除并踢下线在线会话
+async fn oauth_delete(headers: HeaderMap, Json(info): Json<SingleParam<String>>) -> Json<Resp<()>> {
+    info!("oauth_delete: body = {:?}", &info);
+    match get_gmv_token(headers) {
+        Ok(_token) => match oauth_serv::oauth_delete(info.param).await {
+            Ok(()) => Json(Resp::build_success()),
+            Err(err) => Json(res_by_error(err)),
+        },
+        Err(err) => Json(res_by_error(err)),
+    }
+}
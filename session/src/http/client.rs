@@ -1,9 +1,11 @@
 use crate::register::core::DEFAULT_EXPIRES;
-use crate::state::model::AlarmInfo;
+use crate::state::model::{AlarmInfo, WebhookEvent};
+use base::bytes::Bytes;
 use base::dashmap;
 use base::dashmap::DashMap;
-use base::exception::{GlobalResult, GlobalResultExt};
-use base::log::info;
+use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use base::log::{error, info};
+use base::serde_json;
 use pretend::interceptor::NoopRequestInterceptor;
 use pretend::resolver::UrlResolver;
 use pretend::{Json, Url};
@@ -11,8 +13,8 @@ use pretend::{Pretend, Result, pretend};
 use shared::info::media_info::MediaConfig;
 use shared::info::media_info_ext::MediaMap;
 use shared::info::obj::{
-    StreamInfoQo, StreamKey, StreamRecordInfo, TalkAnswerReq, TalkCloseReq, TalkOpenReq,
-    TalkOpenResp,
+    FilterStreamQo, OutputStreamQo, STREAM_SNAPSHOT, SingleParam, StreamInfoQo, StreamKey,
+    StreamRecordInfo, StreamSnapshotQo, TalkAnswerReq, TalkCloseReq, TalkOpenReq, TalkOpenResp,
 };
 use shared::info::res::Resp;
 use std::str::FromStr;
@@ -21,6 +23,7 @@ use std::time::Duration;
 
 type HttpTemplate = Arc<Pretend<pretend_reqwest::Client, UrlResolver, NoopRequestInterceptor>>;
 static CLIENT_POOL: OnceLock<DashMap<String, HttpTemplate>> = OnceLock::new();
+static RAW: OnceLock<reqwest::Client> = OnceLock::new();
 pub struct HttpClient;
 impl HttpClient {
     fn pool() -> &'static DashMap<String, HttpTemplate> {
@@ -59,6 +62,39 @@ impl HttpClient {
         let url = format!("http://{}:{}", local_ip, local_port);
         Self::template(&url)
     }
+
+    /// 流媒体节点抓拍：成功返回图片二进制，失败返回 json 错误
+    pub async fn stream_snapshot(
+        local_ip: &String,
+        local_port: u16,
+        qo: &StreamSnapshotQo,
+    ) -> GlobalResult<Bytes> {
+        let client = RAW.get_or_init(reqwest::Client::new);
+        let res = client
+            .post(format!(
+                "http://{}:{}{}",
+                local_ip, local_port, STREAM_SNAPSHOT
+            ))
+            .timeout(DEFAULT_EXPIRES)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(qo).hand_log(|msg| error!("{msg}"))?)
+            .send()
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        let is_image = res
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("image/"));
+        let body = res.bytes().await.hand_log(|msg| error!("{msg}"))?;
+        if is_image {
+            return Ok(body);
+        }
+        let resp = serde_json::from_slice::<Resp<()>>(&body).hand_log(|msg| error!("{msg}"))?;
+        Err(GlobalError::new_biz_error(resp.code, &resp.msg, |msg| {
+            error!("stream snapshot failed: {msg}, ssrc={}", qo.ssrc)
+        }))
+    }
 }
 
 #[pretend]
@@ -73,6 +109,14 @@ pub trait HttpStream {
     async fn record_info(&self, json: &StreamInfoQo) -> Result<Json<Resp<StreamRecordInfo>>>;
     #[request(method = "POST", path = "/close/output")]
     async fn close_output(&self, json: &StreamInfoQo) -> Result<Json<Resp<()>>>;
+    #[request(method = "POST", path = "/open/output/stream")]
+    async fn open_output_stream(&self, json: &OutputStreamQo) -> Result<Json<Resp<bool>>>;
+    #[request(method = "POST", path = "/close/output/stream")]
+    async fn close_output_stream(&self, json: &StreamInfoQo) -> Result<Json<Resp<bool>>>;
+    #[request(method = "POST", path = "/open/filter/stream")]
+    async fn open_filter_stream(&self, json: &FilterStreamQo) -> Result<Json<Resp<()>>>;
+    #[request(method = "POST", path = "/close/filter/stream")]
+    async fn close_filter_stream(&self, json: &SingleParam<u32>) -> Result<Json<Resp<()>>>;
     #[request(method = "POST", path = "/talk/open")]
     async fn talk_open(&self, json: &TalkOpenReq) -> Result<Json<Resp<TalkOpenResp>>>;
     #[request(method = "POST", path = "/talk/answer")]
@@ -87,4 +131,6 @@ pub trait HttpStream {
 pub trait HttpBiz {
     #[request(method = "POST", path = "")]
     async fn call_alarm_info(&self, json: &AlarmInfo) -> Result<Json<Resp<bool>>>;
+    #[request(method = "POST", path = "")]
+    async fn call_webhook(&self, json: &WebhookEvent) -> Result<Json<Resp<bool>>>;
 }
@@ -1,9 +1,11 @@
 use utoipa::OpenApi;
 // 安全配置
+use crate::gb::sip::config::*;
 use crate::http::api;
 use crate::http::edge;
 use crate::http::hook;
 use crate::state::model::*;
+use shared::info::filter::ImageFormat;
 use shared::info::obj::*;
 use utoipa::Modify;
 use utoipa::openapi::security::ApiKeyValue;
@@ -30,11 +32,39 @@ impl Modify for SecurityAddon {
         api::play_back,
         api::play_seek,
         api::play_speed,
+        api::play_snapshot,
+        api::play_output_open,
+        api::play_output_close,
+        api::play_osd,
         api::control_ptz,
+        api::control_ptz_cmd,
+        api::preset_list,
+        api::ptz_position,
+        api::device_status,
+        api::config_download,
+        api::device_config,
+        api::device_control,
+        api::record_list,
         api::download_mp4,
         api::download_stop,
         api::downing_info,
         api::rm_file,
+        api::device_page,
+        api::channel_page,
+        api::channel_tree,
+        api::device_timeline,
+        api::alarm_page,
+        api::alarm_ack,
+        api::position_subscribe,
+        api::position_unsubscribe,
+        api::position_latest,
+        api::position_track,
+        api::oauth_page,
+        api::oauth_create,
+        api::oauth_status,
+        api::oauth_pwd,
+        api::oauth_heartbeat,
+        api::oauth_delete,
         hook::stream_register,
         hook::stream_input_timeout,
         hook::on_play,
@@ -42,6 +72,8 @@ impl Modify for SecurityAddon {
         hook::stream_idle,
         hook::end_record,
         hook::talk_closed,
+        hook::push_state,
+        hook::stream_capture,
         edge::upload_picture,
         edge::snapshot_image
     ),
@@ -51,7 +83,47 @@ impl Modify for SecurityAddon {
             PlayBackModel,
             PlaySeekModel,
             PlaySpeedModel,
+            PlaySnapshotModel,
+            PlayOutputOpenModel,
+            PlayOutputCloseModel,
+            PlayOsdModel,
+            ImageFormat,
             PtzControlModel,
+            PtzCmdModel,
+            PtzCmd,
+            PresetQueryModel,
+            PresetItem,
+            DeviceTargetModel,
+            PtzPositionInfo,
+            DeviceStatusInfo,
+            AlarmStatusItem,
+            ConfigDownloadModel,
+            DeviceConfigModel,
+            DeviceConfigInfo,
+            ConfigType,
+            DeviceConfigSet,
+            BasicParam,
+            VideoParamOpt,
+            VideoParamAttributeItem,
+            SvacEncodeConfig,
+            RoiParam,
+            RoiItem,
+            SvcEncodeParam,
+            SurveillanceEncodeParam,
+            EncryptParam,
+            AudioParam,
+            SvacDecodeConfig,
+            OsdConfig,
+            OsdText,
+            VideoRecordPlan,
+            RecordSchedule,
+            TimeSegment,
+            VideoAlarmRecord,
+            DeviceControlModel,
+            DeviceControlCmd,
+            DragZoom,
+            RecordQueryModel,
+            RecordInfoItem,
             StreamInfo,
             StreamQo,
             StreamRecordInfo,
@@ -59,12 +131,39 @@ impl Modify for SecurityAddon {
             StreamPlayInfo,
             StreamState,
             TalkClosedEvent,
-            SnapshotImage
+            PushStateInfo,
+            PushState,
+            SnapshotImage,
+            DeviceQueryModel,
+            DeviceItem,
+            ChannelQueryModel,
+            ChannelItem,
+            ChannelTreeNode,
+            DeviceTimelineModel,
+            DeviceTimelineInfo,
+            DeviceUptimeSegment,
+            DeviceLifecycleItem,
+            AlarmQueryModel,
+            AlarmItem,
+            AlarmAckModel,
+            PositionSubscribeModel,
+            PositionTrackModel,
+            MobilePositionItem,
+            DeviceOauthQueryModel,
+            DeviceOauthItem,
+            DeviceOauthCreateModel,
+            DeviceOauthStatusModel,
+            DeviceOauthPwdModel,
+            DeviceOauthHeartbeatModel
         ),
     ),
     modifiers(&SecurityAddon),
     tags(
         (name = "设备媒体流操作API", description = "设备媒体流播放和控制相关接口"),
+        (name = "设备目录API", description = "设备与通道目录查询相关接口"),
+        (name = "设备报警API", description = "设备报警记录查询与确认相关接口"),
+        (name = "移动位置API", description = "移动设备位置订阅与轨迹查询相关接口"),
+        (name = "设备接入API", description = "设备接入凭据与启停管理相关接口"),
         (name = "流媒体服务回调接口", description = "流媒体服务回调相关接口"),
         (name = "图片采集", description = "图片采集上传相关接口")
     )
@@ -1,15 +1,20 @@
+use axum::extract::{DefaultBodyLimit, Path};
 use axum::{Json, Router};
+use base::bytes::Bytes;
 use base::log::info;
 use shared::info::obj::{
     END_RECORD, INPUT_TIMEOUT, InTimeoutEventRes, OFF_PLAY, ON_PLAY, OutputEventRes,
-    OutputStreamInfo, RegisterStreamInfo, STREAM_IDLE, STREAM_REGISTER, STREAM_UNKNOWN,
-    StreamPlayInfo, StreamRecordInfo, StreamState, TALK_CLOSED, TalkClosedEvent,
-    UnknownStreamEvent,
+    OutputStreamInfo, PUSH_STATE, PushStateInfo, RegisterStreamInfo, STREAM_CAPTURE, STREAM_IDLE,
+    STREAM_REGISTER, STREAM_UNKNOWN, StreamPlayInfo, StreamRecordInfo, StreamState, TALK_CLOSED,
+    TalkClosedEvent, UnknownStreamEvent,
 };
 use shared::info::res::{EmptyResponse, Resp};
 
 use crate::service::hook_serv;
 
+//抽图原图(png)可能超出默认 2MB 限制
+const CAPTURE_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
 pub fn routes() -> Router {
     Router::new()
         .route(STREAM_REGISTER, axum::routing::post(stream_register))
@@ -20,6 +25,11 @@ pub fn routes() -> Router {
         .route(OFF_PLAY, axum::routing::post(off_play))
         .route(END_RECORD, axum::routing::post(end_record))
         .route(TALK_CLOSED, axum::routing::post(talk_closed))
+        .route(PUSH_STATE, axum::routing::post(push_state))
+        .route(
+            STREAM_CAPTURE,
+            axum::routing::post(stream_capture).layer(DefaultBodyLimit::max(CAPTURE_BODY_LIMIT)),
+        )
 }
 
 #[cfg_attr(debug_assertions, utoipa::path(
@@ -146,3 +156,42 @@ async fn talk_closed(Json(info): Json<TalkClosedEvent>) -> Json<Resp<bool>> {
     info!("talk_closed = {:?}", &info);
     Json(Resp::build_success_data(hook_serv::talk_closed(info).await))
 }
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/hook/push/state",
+    request_body = PushStateInfo,
+    responses(
+        (status = 200, description = "回调处理成功", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    tag = "流媒体服务回调接口"
+))]
+async fn push_state(Json(info): Json<PushStateInfo>) -> Json<Resp<()>> {
+    info!("push_state = {:?}", &info);
+    hook_serv::push_state(info);
+    Json(Resp::build_success())
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/hook/stream/capture/{stream_id}",
+    params(("stream_id" = String, Path, description = "媒体流ID")),
+    request_body(content = Vec<u8>, content_type = "image/*"),
+    responses(
+        (status = 200, description = "回调处理成功", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    tag = "流媒体服务回调接口"
+))]
+async fn stream_capture(Path(stream_id): Path<String>, bytes: Bytes) -> Json<Resp<()>> {
+    info!(
+        "stream_capture: stream_id = {}, size = {}",
+        &stream_id,
+        bytes.len()
+    );
+    match hook_serv::stream_capture(stream_id, bytes).await {
+        Ok(()) => Json(Resp::build_success()),
+        Err(err) => Json(crate::http::res_by_error(err)),
+    }
+}
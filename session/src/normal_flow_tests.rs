@@ -426,6 +426,81 @@ y={ssrc}\r\n"
             )
             .await;
         }
+        if request.starts_with("MESSAGE ") && request.contains("<CmdType>PresetQuery</CmdType>") {
+            let sn = extract_xml_value_lossy(&request, "SN").expect("preset query SN");
+            // 分两包应答，验证按 SumNum 聚合
+            for (index, (preset_id, preset_name)) in [(2, "yard"), (1, "gate")].iter().enumerate() {
+                device_cseq += 1;
+                let response_body = format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n\
+<Response>\r\n\
+<CmdType>PresetQuery</CmdType>\r\n\
+<SN>{sn}</SN>\r\n\
+<DeviceID>{DEVICE_ID}</DeviceID>\r\n\
+<SumNum>2</SumNum>\r\n\
+<PresetList Num=\"1\">\r\n\
+<Item>\r\n\
+<PresetID>{preset_id}</PresetID>\r\n\
+<PresetName>{preset_name}</PresetName>\r\n\
+</Item>\r\n\
+</PresetList>\r\n\
+</Response>\r\n"
+                );
+                inject(
+                    &socket,
+                    runtime_addr,
+                    device_message(
+                        &format!("preset-response-{index}"),
+                        device_cseq,
+                        &response_body,
+                    ),
+                )
+                .await;
+            }
+        }
+        let query_answer = [
+            (
+                "DeviceStatus",
+                "<Result>OK</Result>\r\n<Online>ONLINE</Online>\r\n<Status>OK</Status>\r\n\
+<Encode>ON</Encode>\r\n<Record>OFF</Record>\r\n\
+<DeviceTime>2026-06-13T00:00:00</DeviceTime>\r\n",
+            ),
+            (
+                "PTZPosition",
+                "<Info>\r\n<Pan>120.5</Pan>\r\n<Tilt>-10</Tilt>\r\n<Zoom>4</Zoom>\r\n</Info>\r\n",
+            ),
+            (
+                "ConfigDownload",
+                "<Result>OK</Result>\r\n<BasicParam>\r\n<Name>gate</Name>\r\n\
+<Expiration>3600</Expiration>\r\n<HeartBeatInterval>60</HeartBeatInterval>\r\n\
+<HeartBeatCount>3</HeartBeatCount>\r\n</BasicParam>\r\n",
+            ),
+            ("DeviceConfig", "<Result>OK</Result>\r\n"),
+            ("DeviceControl", "<Result>OK</Result>\r\n"),
+        ]
+        .into_iter()
+        .find(|(cmd_type, _)| {
+            request.starts_with("MESSAGE ")
+                && request.contains(&format!("<CmdType>{cmd_type}</CmdType>"))
+        });
+        if let Some((cmd_type, fields)) = query_answer {
+            let sn = extract_xml_value_lossy(&request, "SN").expect("query SN");
+            device_cseq += 1;
+            let response_body = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n\
+<Response>\r\n\
+<CmdType>{cmd_type}</CmdType>\r\n\
+<SN>{sn}</SN>\r\n\
+<DeviceID>{DEVICE_ID}</DeviceID>\r\n\
+{fields}</Response>\r\n"
+            );
+            inject(
+                &socket,
+                runtime_addr,
+                device_message(&format!("query-response-{sn}"), device_cseq, &response_body),
+            )
+            .await;
+        }
         if let Some(session_id) = snapshot_session_id {
             device_cseq += 1;
             let body = format!(
@@ -611,7 +686,7 @@ fn all_business_http_apis_complete_the_normal_signaling_flow() {
         command::query_record_info(DEVICE_ID, 40, "2026-06-13T00:00:00", "2026-06-13T01:00:00")
             .await
             .expect("query record info");
-        command::query_preset(DEVICE_ID)
+        command::query_preset(DEVICE_ID, 41)
             .await
             .expect("query preset");
 
@@ -742,6 +817,283 @@ fn all_business_http_apis_complete_the_normal_signaling_flow() {
         )
         .await;
         assert_success(&ptz, "/api/control/ptz");
+        let presets = post_json(
+            &app,
+            "/api/control/preset/list",
+            &json!({
+                "device_id": DEVICE_ID,
+                "channel_id": null,
+                "refresh": true
+            }),
+            true,
+        )
+        .await;
+        assert_success(&presets, "/api/control/preset/list");
+        assert_eq!(presets["data"][0]["preset_id"], "1");
+        assert_eq!(presets["data"][1]["preset_name"], "yard");
+        let status = post_json(
+            &app,
+            "/api/device/status",
+            &json!({"device_id": DEVICE_ID, "channel_id": null}),
+            true,
+        )
+        .await;
+        assert_success(&status, "/api/device/status");
+        assert_eq!(status["data"]["online"], true);
+        assert_eq!(status["data"]["record"], false);
+        let position = post_json(
+            &app,
+            "/api/control/ptz/position",
+            &json!({"device_id": DEVICE_ID, "channel_id": CHANNEL_ID}),
+            true,
+        )
+        .await;
+        assert_success(&position, "/api/control/ptz/position");
+        assert_eq!(position["data"]["pan"], 120.5);
+        let config = post_json(
+            &app,
+            "/api/device/config/download",
+            &json!({"device_id": DEVICE_ID, "channel_id": null, "config_types": ["BasicParam"]}),
+            true,
+        )
+        .await;
+        assert_success(&config, "/api/device/config/download");
+        assert_eq!(
+            config["data"]["config"]["basic_param"]["heartbeat_interval"],
+            60
+        );
+        let configured = post_json(
+            &app,
+            "/api/device/config",
+            &json!({
+                "device_id": DEVICE_ID,
+                "channel_id": null,
+                "config": {"basic_param": {"name": "gate", "heartbeat_interval": 30}}
+            }),
+            true,
+        )
+        .await;
+        assert_success(&configured, "/api/device/config");
+        let guarded = post_json(
+            &app,
+            "/api/device/control",
+            &json!({"device_id": DEVICE_ID, "channel_id": null, "cmd": "SetGuard"}),
+            true,
+        )
+        .await;
+        assert_success(&guarded, "/api/device/control");
+        inject(
+            &device_socket,
+            runtime_addr,
+            device_message(
+                "alarm-notify",
+                900,
+                &format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n\
+<Notify>\r\n\
+<CmdType>Alarm</CmdType>\r\n\
+<SN>900</SN>\r\n\
+<DeviceID>{CHANNEL_ID}</DeviceID>\r\n\
+<AlarmPriority>1</AlarmPriority>\r\n\
+<AlarmMethod>5</AlarmMethod>\r\n\
+<AlarmTime>2026-06-13T00:30:00</AlarmTime>\r\n\
+<AlarmDescription>intrusion</AlarmDescription>\r\n\
+<Info>\r\n<AlarmType>6</AlarmType>\r\n\
+<AlarmTypeParam>\r\n<EventType>1</EventType>\r\n</AlarmTypeParam>\r\n</Info>\r\n\
+</Notify>\r\n"
+                ),
+            ),
+        )
+        .await;
+        let alarm_query = json!({"device_id": DEVICE_ID, "method": 5, "ack": false});
+        let alarms = timeout(Duration::from_secs(3), async {
+            loop {
+                let value = post_json(&app, "/api/alarm/page", &alarm_query, true).await;
+                if value["data"]["total"] == 1 {
+                    break value;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("alarm stored");
+        assert_eq!(alarms["data"]["list"][0]["channel_id"], CHANNEL_ID);
+        assert_eq!(alarms["data"]["list"][0]["event_type"], 1);
+        let alarm_ack = post_json(
+            &app,
+            "/api/alarm/ack",
+            &json!({"ids": [alarms["data"]["list"][0]["id"]], "user": "operator"}),
+            true,
+        )
+        .await;
+        assert_success(&alarm_ack, "/api/alarm/ack");
+        assert_eq!(alarm_ack["data"], 1);
+        let unacked = post_json(&app, "/api/alarm/page", &alarm_query, true).await;
+        assert_eq!(unacked["data"]["total"], 0);
+        inject(
+            &device_socket,
+            runtime_addr,
+            device_message(
+                "position-notify",
+                901,
+                &format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n\
+<Notify>\r\n\
+<CmdType>MobilePosition</CmdType>\r\n\
+<SN>901</SN>\r\n\
+<DeviceID>{CHANNEL_ID}</DeviceID>\r\n\
+<Time>2026-06-13T00:30:05</Time>\r\n\
+<Longitude>116.397</Longitude>\r\n\
+<Latitude>39.908</Latitude>\r\n\
+<Speed>12.5</Speed>\r\n\
+</Notify>\r\n"
+                ),
+            ),
+        )
+        .await;
+        let position_query = json!({"device_id": DEVICE_ID, "channel_id": CHANNEL_ID});
+        let latest = timeout(Duration::from_secs(3), async {
+            loop {
+                let value = post_json(&app, "/api/position/latest", &position_query, true).await;
+                if !value["data"].is_null() {
+                    break value;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("mobile position stored");
+        assert_eq!(latest["data"]["time"], "2026-06-13 00:30:05");
+        assert_eq!(latest["data"]["longitude"], 116.397);
+        let track = post_json(
+            &app,
+            "/api/position/track",
+            &json!({
+                "device_id": DEVICE_ID,
+                "channel_id": CHANNEL_ID,
+                "start_time": "2026-06-13 00:00:00",
+                "end_time": "2026-06-13 01:00:00"
+            }),
+            true,
+        )
+        .await;
+        assert_success(&track, "/api/position/track");
+        assert_eq!(track["data"].as_array().map(Vec::len), Some(1));
+        let catalog_item = |channel_id: &str, extra: &str| {
+            format!(
+                "<Item>\r\n<DeviceID>{channel_id}</DeviceID>\r\n{extra}\
+<Info>\r\n<PTZType>1</PTZType>\r\n</Info>\r\n</Item>\r\n"
+            )
+        };
+        let catalog_body = |root: &str, sn: u32, num: usize, items: String| {
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n\
+<{root}>\r\n\
+<CmdType>Catalog</CmdType>\r\n\
+<SN>{sn}</SN>\r\n\
+<DeviceID>{DEVICE_ID}</DeviceID>\r\n\
+<SumNum>{num}</SumNum>\r\n\
+<DeviceList Num=\"{num}\">\r\n{items}</DeviceList>\r\n\
+</{root}>\r\n"
+            )
+        };
+        let removed_channel = "34020000001320000099";
+        let added_channel = "34020000001320000098";
+        inject(
+            &device_socket,
+            runtime_addr,
+            device_message(
+                "catalog-response",
+                902,
+                &catalog_body(
+                    "Response",
+                    902,
+                    2,
+                    catalog_item(CHANNEL_ID, "<Name>camera</Name>\r\n<Status>ON</Status>\r\n")
+                        + &catalog_item(removed_channel, "<Status>ON</Status>\r\n"),
+                ),
+            ),
+        )
+        .await;
+        inject(
+            &device_socket,
+            runtime_addr,
+            device_message(
+                "catalog-event",
+                903,
+                &catalog_body(
+                    "Notify",
+                    903,
+                    3,
+                    catalog_item(CHANNEL_ID, "<Event>VLOST</Event>\r\n")
+                        + &catalog_item(removed_channel, "<Event>DEL</Event>\r\n")
+                        + &catalog_item(
+                            added_channel,
+                            "<Name>added</Name>\r\n<Status>ON</Status>\r\n<Event>ADD</Event>\r\n",
+                        ),
+                ),
+            ),
+        )
+        .await;
+        let channel_query = json!({"device_id": DEVICE_ID});
+        let channels = timeout(Duration::from_secs(3), async {
+            loop {
+                let value = post_json(&app, "/api/device/channel/page", &channel_query, true).await;
+                let list = value["data"]["list"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                if list.iter().any(|c| c["channel_id"] == added_channel) {
+                    break list;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("catalog events applied");
+        assert_eq!(channels.len(), 2);
+        assert!(channels.iter().all(|c| c["channel_id"] != removed_channel));
+        let lost = channels
+            .iter()
+            .find(|c| c["channel_id"] == CHANNEL_ID)
+            .expect("status changed channel kept");
+        assert_eq!(lost["status"], "VLOST");
+        assert_eq!(lost["name"], "camera");
+        let timeline = timeout(Duration::from_secs(3), async {
+            loop {
+                let value = post_json(
+                    &app,
+                    "/api/device/timeline",
+                    &json!({"device_id": DEVICE_ID}),
+                    true,
+                )
+                .await;
+                if value["data"]["events"]
+                    .as_array()
+                    .is_some_and(|events| !events.is_empty())
+                {
+                    break value;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("device lifecycle recorded");
+        assert_eq!(timeline["data"]["events"][0]["event"], "device_registered");
+        assert_eq!(timeline["data"]["events"][0]["online"], true);
+        assert!(timeline["data"]["online_seconds"].as_i64().is_some());
+        let ptz_cmd = post_json(
+            &app,
+            "/api/control/ptz/cmd",
+            &json!({
+                "deviceId": DEVICE_ID,
+                "channelId": CHANNEL_ID,
+                "cmd": {"PresetCall": {"preset": 3}}
+            }),
+            true,
+        )
+        .await;
+        assert_success(&ptz_cmd, "/api/control/ptz/cmd");
 
         let download = post_json(
             &app,
@@ -986,6 +1338,45 @@ fn all_business_http_apis_complete_the_normal_signaling_flow() {
             second_tcp
         );
 
+        let disable = post_json(
+            &app,
+            "/api/device/oauth/status",
+            &json!({"device_id": DEVICE_ID, "enable": false}),
+            true,
+        )
+        .await;
+        assert_success(&disable, "/api/device/oauth/status");
+        assert!(!Register::has_session(DEVICE_ID));
+        let oauths = post_json(
+            &app,
+            "/api/device/oauth/page",
+            &json!({"device_id": DEVICE_ID}),
+            true,
+        )
+        .await;
+        assert_success(&oauths, "/api/device/oauth/page");
+        assert_eq!(oauths["data"]["list"][0]["enable"], false);
+        assert_eq!(oauths["data"]["list"][0]["online"], false);
+        Register::recover_device_on_keepalive(Arc::from(DEVICE_ID), second_tcp.clone())
+            .expect("schedule disabled keepalive recovery");
+        sleep(Duration::from_millis(100)).await;
+        assert!(
+            !Register::has_session(DEVICE_ID),
+            "disabled device must not recover from keepalive"
+        );
+        let events = post_json(
+            &app,
+            "/api/device/timeline",
+            &json!({"device_id": DEVICE_ID}),
+            true,
+        )
+        .await;
+        assert!(
+            events["data"]["events"]
+                .as_array()
+                .is_some_and(|events| events.iter().any(|e| e["event"] == "device_disabled"))
+        );
+
         sleep(Duration::from_millis(100)).await;
         cancel.cancel();
         device_task.await.expect("stop device simulator");
@@ -17,8 +17,9 @@ use crate::gb::sip::NativeSipRuntimeHandle;
 use crate::register::event::{self, Event};
 pub(crate) use crate::register::network::{DeviceSession, Network};
 use crate::register::schedule::TimeScheduler;
+use crate::service::lifecycle_serv::{self, DeviceLifecycle};
 use crate::service::{stream_close, talk_close};
-use crate::state::session::Cache as GeneralCache;
+use crate::state::session::{Cache as GeneralCache, SubscriptionKind};
 use crate::storage::db_task::{self, DbTask};
 use crate::storage::entity::{GmvDevice, GmvOauth};
 
@@ -36,7 +37,7 @@ pub enum TimeScheduleKey {
     DeviceReconnect(Arc<str>, u64),
     StreamClosing(Arc<str>, u64),
     TalkClosing(Arc<str>, u64),
-    CatalogSubscription(Arc<str>, u64),
+    Subscription(Arc<str>, SubscriptionKind, u64),
     OutSession(u64),
     ServerHeart(Arc<str>),
 }
@@ -135,6 +136,9 @@ impl Register {
             Self::scheduler()
                 .refresh_register(&TimeScheduleKey::Device3Heart(device_id.clone()))?;
         }
+        if reconnected {
+            lifecycle_serv::record(device_id, DeviceLifecycle::Reconnected, Some(&association));
+        }
         if reconnected || previous_session.association != association {
             stream_close::retry_device(device_id);
             talk_close::retry_device(device_id);
@@ -270,6 +274,7 @@ impl Register {
 
         let heartbeat_sec = ds.heartbeat_sec;
         let registration_duration = ds.registration_duration;
+        let association = ds.association.clone();
         let new_generation = arc
             .io_map
             .session
@@ -296,6 +301,11 @@ impl Register {
                             registration_duration,
                         )
                         .hand_log(|e| error!("insert device registration timer failed: {e}"))?;
+                    lifecycle_serv::record(
+                        &device_id,
+                        DeviceLifecycle::Reconnected,
+                        Some(&association),
+                    );
                     stream_close::retry_device(device_id.as_ref());
                     talk_close::retry_device(device_id.as_ref());
                     return Ok(());
@@ -305,6 +315,15 @@ impl Register {
         };
 
         let previous_session = Self::remove_device_by_inner(&device_id, &arc);
+        let lifecycle = match previous_session {
+            None => Some(DeviceLifecycle::Registered),
+            Some(ref previous_session)
+                if new_generation || previous_session.association != ds.association =>
+            {
+                Some(DeviceLifecycle::Reregistered)
+            }
+            Some(_) => None,
+        };
         let association_changed = previous_session
             .as_ref()
             .is_some_and(|previous_session| previous_session.association != ds.association);
@@ -334,6 +353,9 @@ impl Register {
             .hand_log(|e| error!("insert device registration timer failed: {e}"))?;
 
         arc.io_map.insert(device_id.clone(), ds);
+        if let Some(lifecycle) = lifecycle {
+            lifecycle_serv::record(&device_id, lifecycle, Some(&association));
+        }
         if !new_generation {
             stream_close::retry_device(device_id.as_ref());
             talk_close::retry_device(device_id.as_ref());
@@ -361,10 +383,11 @@ impl Register {
         None
     }
 
-    pub fn remove_device(device_id: &Arc<str>) {
+    pub fn remove_device(device_id: &Arc<str>) -> Option<DeviceSession> {
         let inner = &Self::get().inner;
-        Self::remove_device_by_inner(device_id, inner);
+        let session = Self::remove_device_by_inner(device_id, inner);
         GeneralCache::reset_device_state(device_id.as_ref());
+        session
     }
 
     pub fn detach_device_association(association: &Association) -> bool {
@@ -378,6 +401,11 @@ impl Register {
 
         let _ = Self::scheduler()
             .remove_register(&TimeScheduleKey::Device3Heart(detached.device_id.clone()));
+        lifecycle_serv::record(
+            &detached.device_id,
+            DeviceLifecycle::Disconnected,
+            Some(association),
+        );
         let key = TimeScheduleKey::DeviceReconnect(detached.device_id.clone(), detached.generation);
         if let Err(err) = Self::scheduler().insert_register(key, detached.timeout) {
             error!(
@@ -404,6 +432,11 @@ impl Register {
             generation,
         ));
         GeneralCache::reset_device_state(device_id.as_ref());
+        lifecycle_serv::record(
+            device_id,
+            DeviceLifecycle::ReconnectTimeout,
+            Some(&session.association),
+        );
         let _ = inner
             .event_tx
             .try_send(Event::DeviceOffline(device_id.clone()))
@@ -429,6 +462,17 @@ impl Register {
             .map(|item| item.snapshot())
     }
 
+    /// 当前持有注册会话的设备ID
+    pub fn session_device_ids() -> Vec<String> {
+        Self::get()
+            .inner
+            .io_map
+            .session
+            .iter()
+            .map(|item| item.key().to_string())
+            .collect()
+    }
+
     pub fn get_connected_device_session(device_id: &str) -> Option<DeviceSession> {
         Self::get().inner.io_map.connected_session(device_id)
     }
@@ -11,7 +11,8 @@ use base::tokio_util::sync::CancellationToken;
 use crate::gb::sip::subscription;
 use crate::register::core::{Inner, Register, TimeScheduleKey};
 use crate::register::schedule::ScheduleKey;
-use crate::state::session::Cache as GeneralCache;
+use crate::service::lifecycle_serv::{self, DeviceLifecycle};
+use crate::state::session::{Cache as GeneralCache, SubscriptionKind};
 use crate::storage::db_task::{self, DbTask};
 
 const MAX_WORKER_POOL: usize = 128;
@@ -20,7 +21,7 @@ const MAX_WORKER_POOL: usize = 128;
 pub enum Event {
     DeviceOffline(Arc<str>),
     ServerHeart(Arc<str>),
-    RefreshCatalogSubscription(Arc<str>, u64),
+    RefreshSubscription(Arc<str>, SubscriptionKind, u64),
     OutSession(u64),
 }
 
@@ -78,15 +79,28 @@ async fn hand_event(event: Event) {
         Event::ServerHeart(domain_id) => {
             let _ = Register::server_keep_heart_update_db(domain_id).await;
         }
-        Event::RefreshCatalogSubscription(device_id, generation) => {
-            let _ = subscription::refresh_catalog_subscription(device_id, generation)
+        Event::RefreshSubscription(device_id, kind, generation) => {
+            let _ = subscription::refresh_subscription(device_id, kind, generation)
                 .await
-                .hand_log(|msg| warn!("refresh catalog subscription failed: {msg}"));
+                .hand_log(|msg| warn!("refresh {} subscription failed: {msg}", kind.as_str()));
         }
         Event::OutSession(_) => {}
     }
 }
 
+fn expire_device(inner: &Inner, device_id: Arc<str>, lifecycle: DeviceLifecycle) {
+    warn!("device {} expired, removing session", device_id);
+    if let Some(session) = Register::remove_device_by_inner(&device_id, inner) {
+        GeneralCache::reset_device_state(device_id.as_ref());
+        Register::close_tcp_if_needed(&session);
+        lifecycle_serv::record(&device_id, lifecycle, Some(&session.association));
+        let _ = inner
+            .event_tx
+            .try_send(Event::DeviceOffline(device_id))
+            .hand_log(|msg| error!("{msg}"));
+    }
+}
+
 async fn on_time_schedule(
     inner: &Inner,
     batch: Vec<crate::register::schedule::ScheduleEvent<ScheduleKey>>,
@@ -95,17 +109,11 @@ async fn on_time_schedule(
 
     for event in batch {
         match event.key {
-            ScheduleKey::Register(TimeScheduleKey::Device3Heart(device_id))
-            | ScheduleKey::Register(TimeScheduleKey::DeviceRegistration(device_id)) => {
-                warn!("device {} expired, removing session", device_id);
-                if let Some(session) = Register::remove_device_by_inner(&device_id, inner) {
-                    GeneralCache::reset_device_state(device_id.as_ref());
-                    Register::close_tcp_if_needed(&session);
-                    let _ = inner
-                        .event_tx
-                        .try_send(Event::DeviceOffline(device_id))
-                        .hand_log(|msg| error!("{msg}"));
-                }
+            ScheduleKey::Register(TimeScheduleKey::Device3Heart(device_id)) => {
+                expire_device(inner, device_id, DeviceLifecycle::KeepaliveTimeout);
+            }
+            ScheduleKey::Register(TimeScheduleKey::DeviceRegistration(device_id)) => {
+                expire_device(inner, device_id, DeviceLifecycle::RegistrationExpired);
             }
             ScheduleKey::Register(TimeScheduleKey::DeviceReconnect(device_id, generation)) => {
                 if Register::expire_disconnected_by_inner(&device_id, generation, inner).is_some() {
@@ -165,10 +173,10 @@ async fn on_time_schedule(
                     );
                 }
             }
-            ScheduleKey::Register(TimeScheduleKey::CatalogSubscription(device_id, generation)) => {
+            ScheduleKey::Register(TimeScheduleKey::Subscription(device_id, kind, generation)) => {
                 let _ = inner
                     .event_tx
-                    .try_send(Event::RefreshCatalogSubscription(device_id, generation))
+                    .try_send(Event::RefreshSubscription(device_id, kind, generation))
                     .hand_log(|msg| error!("{msg}"));
             }
             ScheduleKey::Register(TimeScheduleKey::ServerHeart(domain_id)) => {
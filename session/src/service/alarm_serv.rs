@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use base::chrono::{Local, NaiveDateTime};
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use base::log::{error, warn};
+
+use crate::gb::sip::xml::KV2Model;
+use crate::service::device_serv::{DATE_TIME_FMT, fmt_time, non_blank, page_range};
+use crate::state::AlarmConf;
+use crate::state::model::{AlarmAckModel, AlarmInfo, AlarmItem, AlarmQueryModel, PageData};
+use crate::storage::entity::{
+    ALARM_PUSH_DONE, ALARM_PUSH_FAILED, ALARM_PUSH_PENDING, ALARM_PUSH_SKIPPED, GmvAlarm,
+    GmvAlarmQo,
+};
+
+const PUSH_MAX_ATTEMPTS: u32 = 5;
+const PUSH_RETRY_BASE: Duration = Duration::from_secs(2);
+const PUSH_RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// 接收设备报警：先入库再推送，推送失败按退避重试，最终结果回写推送状态
+pub fn receive(device_id: &str, items: Vec<(String, String)>) -> GlobalResult<()> {
+    let mut alarm = AlarmInfo::kv_to_model(items)?;
+    alarm.deviceId = device_id.to_string();
+    let conf = AlarmConf::get_alarm_conf();
+    let push_url = conf
+        .push_url
+        .clone()
+        .filter(|_| should_push(conf.enable, conf.priority, alarm.priority));
+    let push_state = if push_url.is_some() {
+        ALARM_PUSH_PENDING
+    } else {
+        ALARM_PUSH_SKIPPED
+    };
+    let record = alarm_record(&alarm, push_state, Local::now().naive_local());
+    base::tokio::spawn(async move {
+        match record.insert_gmv_alarm().await {
+            Ok(id) => alarm.alarmId = Some(id),
+            Err(err) => error!(
+                "insert alarm failed: device_id={}, err={err}",
+                alarm.deviceId
+            ),
+        }
+        let Some(push_url) = push_url else {
+            return;
+        };
+        let push_state = if push_with_retry(&push_url, &alarm).await {
+            ALARM_PUSH_DONE
+        } else {
+            ALARM_PUSH_FAILED
+        };
+        if let Some(id) = alarm.alarmId {
+            if let Err(err) = GmvAlarm::update_gmv_alarm_push_state(id, push_state).await {
+                error!("update alarm push state failed: id={id}, err={err}");
+            }
+        }
+    });
+    Ok(())
+}
+
+pub async fn alarm_page(model: AlarmQueryModel) -> GlobalResult<PageData<AlarmItem>> {
+    let (offset, limit) = page_range(model.page, model.size);
+    let qo = GmvAlarmQo {
+        device_id: non_blank(model.device_id),
+        channel_id: non_blank(model.channel_id),
+        priority: model.priority,
+        method: model.method,
+        alarm_type: model.alarm_type,
+        start_time: parse_query_time(model.start_time)?,
+        end_time: parse_query_time(model.end_time)?,
+        ack: model.ack,
+        offset,
+        limit,
+    };
+    let (total, rows) = GmvAlarm::query_gmv_alarm_page(&qo).await?;
+    let list = rows
+        .into_iter()
+        .map(|alarm| AlarmItem {
+            id: alarm.id,
+            device_id: alarm.device_id,
+            channel_id: alarm.channel_id,
+            priority: alarm.priority,
+            method: alarm.method,
+            alarm_time: alarm.alarm_time.as_ref().map(fmt_time),
+            alarm_type: alarm.alarm_type,
+            event_type: alarm.event_type,
+            description: alarm.description,
+            longitude: alarm.longitude,
+            latitude: alarm.latitude,
+            ack: alarm.ack == 1,
+            ack_time: alarm.ack_time.as_ref().map(fmt_time),
+            ack_user: alarm.ack_user,
+            push_state: alarm.push_state,
+            create_time: fmt_time(&alarm.create_time),
+        })
+        .collect();
+    Ok(PageData { total, list })
+}
+
+/// 确认报警，返回本次由未确认变为已确认的条数
+pub async fn alarm_ack(model: AlarmAckModel) -> GlobalResult<u64> {
+    let mut ids = model.ids;
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.is_empty() {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidRequest.code(),
+            "alarm ids must not be empty",
+            |msg| error!("{msg}"),
+        ));
+    }
+    let user = non_blank(model.user);
+    GmvAlarm::ack_gmv_alarm(&ids, user.as_deref(), Local::now().naive_local()).await
+}
+
+/// 级别 1 最高：仅推送不低于配置级别的报警
+fn should_push(enable: bool, limit: u8, priority: u8) -> bool {
+    enable && priority <= limit
+}
+
+fn alarm_record(alarm: &AlarmInfo, push_state: u8, now: NaiveDateTime) -> GmvAlarm {
+    GmvAlarm {
+        id: 0,
+        device_id: alarm.deviceId.clone(),
+        channel_id: alarm.channelId.clone(),
+        priority: alarm.priority,
+        method: alarm.method,
+        alarm_time: parse_alarm_time(&alarm.timeStr),
+        alarm_type: (alarm.alarmType != 0).then_some(alarm.alarmType),
+        event_type: alarm.eventType,
+        description: alarm.description.clone(),
+        longitude: alarm.longitude,
+        latitude: alarm.latitude,
+        ack: 0,
+        ack_time: None,
+        ack_user: None,
+        push_state,
+        create_time: now,
+    }
+}
+
+/// 设备报警时间：标准为 ISO 格式，兼容部分设备使用空格分隔
+fn parse_alarm_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, DATE_TIME_FMT))
+        .ok()
+}
+
+fn parse_query_time(value: Option<String>) -> GlobalResult<Option<NaiveDateTime>> {
+    let Some(value) = non_blank(value) else {
+        return Ok(None);
+    };
+    NaiveDateTime::parse_from_str(&value, DATE_TIME_FMT)
+        .map(Some)
+        .map_err(|_| {
+            GlobalError::new_biz_error(
+                BaseErrorCode::InvalidRequest.code(),
+                "alarm time must be yyyy-MM-dd HH:mm:ss",
+                |msg| error!("{msg}: {value}"),
+            )
+        })
+}
+
+/// 推送失败的退避间隔：2 秒起按次翻倍，最长 60 秒
+pub(crate) fn retry_delay(attempt: u32) -> Duration {
+    PUSH_RETRY_BASE
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(PUSH_RETRY_MAX)
+}
+
+async fn push_with_retry(push_url: &str, alarm: &AlarmInfo) -> bool {
+    use crate::http::client::{HttpBiz, HttpClient};
+
+    for attempt in 1..=PUSH_MAX_ATTEMPTS {
+        let result = async {
+            let client = HttpClient::template(push_url)?;
+            client
+                .call_alarm_info(alarm)
+                .await
+                .hand_log(|msg| error!("{msg}"))?;
+            GlobalResult::<()>::Ok(())
+        }
+        .await;
+        match result {
+            Ok(()) => return true,
+            Err(err) => warn!(
+                "push alarm failed: device_id={}, attempt={attempt}, err={err}",
+                alarm.deviceId
+            ),
+        }
+        if attempt < PUSH_MAX_ATTEMPTS {
+            base::tokio::time::sleep(retry_delay(attempt)).await;
+        }
+    }
+    error!(
+        "push alarm gave up after {PUSH_MAX_ATTEMPTS} attempts: device_id={}, alarm_id={:?}",
+        alarm.deviceId, alarm.alarmId
+    );
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_alarm_time, parse_query_time, retry_delay, should_push};
+
+    #[test]
+    fn filters_push_by_configured_priority() {
+        assert!(should_push(true, 4, 1));
+        assert!(should_push(true, 2, 2));
+        assert!(!should_push(true, 2, 3));
+        assert!(!should_push(false, 4, 1));
+    }
+
+    #[test]
+    fn backs_off_push_retries() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(2), Duration::from_secs(4));
+        assert_eq!(retry_delay(4), Duration::from_secs(16));
+        assert_eq!(retry_delay(10), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn parses_alarm_and_query_times() {
+        let iso = parse_alarm_time("2026-06-13T08:00:00").unwrap();
+        assert_eq!(parse_alarm_time("2026-06-13 08:00:00"), Some(iso));
+        assert_eq!(parse_alarm_time(""), None);
+        assert_eq!(
+            parse_query_time(Some("2026-06-13 08:00:00".to_string())).unwrap(),
+            Some(iso)
+        );
+        assert_eq!(parse_query_time(Some(" ".to_string())).unwrap(), None);
+        assert!(parse_query_time(Some("2026-06-13T08:00:00".to_string())).is_err());
+    }
+}
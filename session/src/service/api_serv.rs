@@ -2,23 +2,29 @@ use std::fs;
 use std::path::Path;
 use std::time::Duration;
 
+use base::bytes::Bytes;
 use base::chrono::{Local, TimeZone};
 use base::err::BaseErrorCode;
 use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
 use base::log::{error, warn};
 use base::tokio::sync::mpsc;
 use base::tokio::time::{Instant, sleep};
-use shared::info::format::{CMaf, Mp4};
+use shared::info::filter::ImageFormat;
+use shared::info::format::{CMaf, Mp4, Ts};
 use shared::info::media_info::MediaConfig;
 use shared::info::media_info_ext::MediaMap;
-use shared::info::obj::{BaseStreamInfo, StreamInfoQo, StreamKey, StreamRecordInfo};
+use shared::info::obj::{
+    BaseStreamInfo, FilterStreamQo, OutputStreamQo, SingleParam, StreamInfoQo, StreamKey,
+    StreamRecordInfo, StreamSnapshotQo,
+};
 use shared::info::obj::{TalkAnswerReq, TalkInfo, TalkOpenReq, TalkStartModel, TalkStopModel};
-use shared::info::output::{DashFmp4Output, LocalMp4Output, OutputEnum, OutputKind};
+use shared::info::output::{DashFmp4Output, LocalMp4Output, LocalTsOutput, OutputEnum, OutputKind};
 use shared::info::res::Resp;
 
 use crate::gb::SessionConf;
 use crate::gb::sip::command as sip_command;
 use crate::gb::sip::invite::AcceptBroadcastInviteRequest;
+use crate::gb::sip::runtime_cache::SipRuntimeCache;
 use crate::http::client::{HttpClient, HttpStream};
 use crate::register::core::Register;
 use crate::service::talk::{
@@ -28,14 +34,18 @@ use crate::service::talk::{
 use crate::service::{EXPIRES, KEY_STREAM_IN, stream_close, talk_close};
 use crate::state;
 use crate::state::model::{
-    CustomMediaConfig, PlayBackModel, PlayLiveModel, PlaySeekModel, PlaySpeedModel,
-    PtzControlModel, StreamInfo, StreamQo, TransMode,
+    ConfigDownloadModel, CustomMediaConfig, DeviceConfigInfo, DeviceConfigModel,
+    DeviceControlModel, DeviceStatusInfo, DeviceTargetModel, PlayBackModel, PlayLiveModel,
+    PlayOsdModel, PlayOutputCloseModel, PlayOutputOpenModel, PlaySeekModel, PlaySnapshotModel,
+    PlaySpeedModel, PresetItem, PresetQueryModel, PtzCmd, PtzCmdModel, PtzControlModel,
+    PtzPositionInfo, RecordInfoItem, RecordQueryModel, StreamInfo, StreamQo, TransMode,
 };
 use crate::state::session::AccessMode;
 use crate::state::session::TalkSessionState;
 use crate::state::{DownloadConf, StreamConf, session};
 use crate::storage::dialog_session::{DialogState, SipDialogSessionRepository};
 use crate::storage::entity::GmvRecord;
+use crate::utils::date_time::TimeFormatter;
 use crate::utils::id_builder;
 
 pub async fn play_live(play_live_model: PlayLiveModel, token: String) -> GlobalResult<StreamInfo> {
@@ -145,11 +155,11 @@ pub async fn download_stop(stream_id: String, _token: String) -> GlobalResult<bo
             }
             Some(node) => {
                 let p = HttpClient::template_ip_port(&node.local_ip.to_string(), node.local_port)?;
+                //重启恢复的下载任务无格式记录，按默认mp4关闭
+                let output_enum = session::Cache::stream_download_output(&stream_id)
+                    .unwrap_or(OutputEnum::LocalMp4);
                 let json_obj = p
-                    .close_output(&StreamInfoQo {
-                        ssrc,
-                        output_enum: OutputEnum::LocalMp4,
-                    })
+                    .close_output(&StreamInfoQo { ssrc, output_enum })
                     .await
                     .hand_log(|msg| error!("{msg}"))?;
                 let value = json_obj.value();
@@ -191,6 +201,14 @@ pub async fn download(play_back_model: PlayBackModel, token: String) -> GlobalRe
     let st = play_back_model.st;
     let et = play_back_model.et;
     validate_playback_range(st, et)?;
+    let media_type = play_back_model.media_type.unwrap_or(OutputEnum::LocalMp4);
+    if !matches!(media_type, OutputEnum::LocalMp4 | OutputEnum::LocalTs) {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidRequest.code(),
+            "下载格式仅支持LocalMp4、LocalTs",
+            |msg| error!("{msg}: {:?}", media_type),
+        ));
+    }
 
     let storage_path = DownloadConf::get_download_conf().storage_path;
     let date_str = Local::now().format("%Y%m%d").to_string();
@@ -207,13 +225,11 @@ pub async fn download(play_back_model: PlayBackModel, token: String) -> GlobalRe
         .custom_media_config
         .clone()
         .unwrap_or_else(|| CustomMediaConfig {
-            output: OutputKind::LocalMp4(LocalMp4Output {
-                fmt: Mp4::default(),
-                path: abs_path.clone(),
-            }),
+            output: download_output(media_type, abs_path.clone()),
             codec: None,
             filter: Default::default(),
         });
+    let download_output = down_conf.output.output_enum();
     let (stream_id, node_name, _proxy_addr) = start_invite_stream(
         device_id,
         channel_id,
@@ -226,6 +242,7 @@ pub async fn download(play_back_model: PlayBackModel, token: String) -> GlobalRe
     )
     .await?;
     state::session::Cache::stream_map_insert_token(stream_id.clone(), token);
+    state::session::Cache::stream_map_set_download_output(&stream_id, download_output);
     let record = GmvRecord {
         biz_id: stream_id.clone(),
         device_id: device_id.to_string(),
@@ -254,6 +271,19 @@ pub async fn download(play_back_model: PlayBackModel, token: String) -> GlobalRe
     Ok(stream_id)
 }
 
+fn download_output(media_type: OutputEnum, path: String) -> OutputKind {
+    match media_type {
+        OutputEnum::LocalTs => OutputKind::LocalTs(LocalTsOutput {
+            fmt: Ts::default(),
+            path,
+        }),
+        _ => OutputKind::LocalMp4(LocalMp4Output {
+            fmt: Mp4::default(),
+            path,
+        }),
+    }
+}
+
 pub async fn play_back(play_back_model: PlayBackModel, token: String) -> GlobalResult<StreamInfo> {
     let device_id = &play_back_model.device_id;
     if !Register::has_session(device_id) {
@@ -267,6 +297,13 @@ pub async fn play_back(play_back_model: PlayBackModel, token: String) -> GlobalR
     let st = play_back_model.st;
     let et = play_back_model.et;
     validate_playback_range(st, et)?;
+    if let Some(media_type) = play_back_model.media_type {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidRequest.code(),
+            "media_type仅用于录像下载",
+            |msg| error!("{msg}: {:?}", media_type),
+        ));
+    }
     let am = AccessMode::Back;
     let output = play_back_model
         .custom_media_config
@@ -294,6 +331,39 @@ pub async fn play_back(play_back_model: PlayBackModel, token: String) -> GlobalR
     StreamInfo::build(stream_id, proxy_addr, output)
 }
 
+pub async fn record_info(
+    record_query_model: RecordQueryModel,
+    _token: String,
+) -> GlobalResult<Vec<RecordInfoItem>> {
+    let device_id = &record_query_model.device_id;
+    if !Register::has_session(device_id) {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::Network.code(),
+            "设备已离线",
+            |msg| error!("{msg}"),
+        ));
+    }
+    let channel_id = record_query_model.channel_id.as_ref().unwrap_or(device_id);
+    let st = record_query_model.st;
+    let et = record_query_model.et;
+    validate_playback_range(st, et)?;
+    let fmt_time = |ts: u32| {
+        Local
+            .timestamp_opt(ts as i64, 0)
+            .single()
+            .map(TimeFormatter::local_time_ios_format)
+            .ok_or_else(|| {
+                GlobalError::new_biz_error(
+                    BaseErrorCode::InvalidRequest.code(),
+                    "invalid record query time",
+                    |msg| error!("{msg}: {ts}"),
+                )
+            })
+    };
+    sip_command::query_record_info_and_wait(device_id, channel_id, &fmt_time(st)?, &fmt_time(et)?)
+        .await
+}
+
 pub async fn seek(seek_mode: PlaySeekModel, _token: String) -> GlobalResult<bool> {
     let device_id = playback_stream_device(&seek_mode.streamId)?;
     sip_command::play_seek(&device_id, &seek_mode.streamId, seek_mode.seekSecond).await?;
@@ -313,6 +383,102 @@ pub async fn speed(speed_mode: PlaySpeedModel, _token: String) -> GlobalResult<b
     Ok(true)
 }
 
+/// 媒体节点解码最近关键帧抓拍，适用于所有可播放的设备
+pub async fn snapshot(
+    model: PlaySnapshotModel,
+    _token: String,
+) -> GlobalResult<(ImageFormat, Bytes)> {
+    let (stream_server, ssrc) = session::Cache::stream_map_query_node_ssrc(&model.stream_id)
+        .ok_or_else(|| {
+            GlobalError::new_biz_error(
+                BaseErrorCode::InvalidRequest.code(),
+                "无效的媒体流ID",
+                |msg| error!("{msg}"),
+            )
+        })?;
+    let conf = StreamConf::get_stream_conf();
+    let node = conf.node_map.get(&stream_server).ok_or_else(|| {
+        GlobalError::new_biz_error(
+            BaseErrorCode::NotFound.code(),
+            "stream server not found",
+            |msg| error!("{msg}: stream_id={}, node={stream_server}", model.stream_id),
+        )
+    })?;
+    let format = model.format.unwrap_or_default();
+    let image = HttpClient::stream_snapshot(
+        &node.local_ip.to_string(),
+        node.local_port,
+        &StreamSnapshotQo {
+            ssrc,
+            format: Some(format),
+        },
+    )
+    .await?;
+    Ok((format, image))
+}
+
+//运行中的流追加输出，无需重新点播；返回false表示输出已存在
+pub async fn output_open(model: PlayOutputOpenModel, _token: String) -> GlobalResult<bool> {
+    let (local_ip, local_port, ssrc) = stream_node_ssrc(&model.stream_id)?;
+    let p = HttpClient::template_ip_port(&local_ip, local_port)?;
+    let json_obj = p
+        .open_output_stream(&OutputStreamQo {
+            ssrc,
+            output: model.output,
+        })
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+    stream_resp_data(json_obj.value(), "open output stream")
+}
+
+//运行中的流移除输出；返回false表示输出不存在
+pub async fn output_close(model: PlayOutputCloseModel, _token: String) -> GlobalResult<bool> {
+    let (local_ip, local_port, ssrc) = stream_node_ssrc(&model.stream_id)?;
+    let p = HttpClient::template_ip_port(&local_ip, local_port)?;
+    let json_obj = p
+        .close_output_stream(&StreamInfoQo {
+            ssrc,
+            output_enum: model.output_enum,
+        })
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+    stream_resp_data(json_obj.value(), "close output stream")
+}
+
+//运行中的流更新水印，osd为空时移除
+pub async fn osd(model: PlayOsdModel, _token: String) -> GlobalResult<bool> {
+    let (local_ip, local_port, ssrc) = stream_node_ssrc(&model.stream_id)?;
+    let p = HttpClient::template_ip_port(&local_ip, local_port)?;
+    let json_obj = match model.osd {
+        Some(osd) => p.open_filter_stream(&FilterStreamQo { ssrc, osd }).await,
+        None => p.close_filter_stream(&SingleParam { param: ssrc }).await,
+    }
+    .hand_log(|msg| error!("{msg}"))?;
+    stream_resp_unit(json_obj.value(), "update stream osd")?;
+    Ok(true)
+}
+
+//查询流所在节点：(local_ip, local_port, ssrc)
+fn stream_node_ssrc(stream_id: &str) -> GlobalResult<(String, u16, u32)> {
+    let (stream_server, ssrc) = session::Cache::stream_map_query_node_ssrc(&stream_id.to_string())
+        .ok_or_else(|| {
+            GlobalError::new_biz_error(
+                BaseErrorCode::InvalidRequest.code(),
+                "无效的媒体流ID",
+                |msg| error!("{msg}"),
+            )
+        })?;
+    let conf = StreamConf::get_stream_conf();
+    let node = conf.node_map.get(&stream_server).ok_or_else(|| {
+        GlobalError::new_biz_error(
+            BaseErrorCode::NotFound.code(),
+            "stream server not found",
+            |msg| error!("{msg}: stream_id={stream_id}, node={stream_server}"),
+        )
+    })?;
+    Ok((node.local_ip.to_string(), node.local_port, ssrc))
+}
+
 pub async fn ptz(ptz_control_model: PtzControlModel, _token: String) -> GlobalResult<bool> {
     sip_command::control_ptz(&ptz_control_model).await?;
     let mut model = PtzControlModel::default();
@@ -323,6 +489,100 @@ pub async fn ptz(ptz_control_model: PtzControlModel, _token: String) -> GlobalRe
     Ok(true)
 }
 
+pub async fn ptz_cmd(mut model: PtzCmdModel, _token: String) -> GlobalResult<bool> {
+    sip_command::control_ptz_cmd(&model).await?;
+    if matches!(
+        model.cmd,
+        PtzCmd::PresetSet { .. } | PtzCmd::PresetDelete { .. }
+    ) {
+        SipRuntimeCache::global().invalidate_presets(&model.deviceId, &model.channelId);
+    }
+    if model.cmd.is_motion() {
+        sleep(Duration::from_millis(1000)).await;
+        model.cmd = PtzCmd::Stop;
+        sip_command::control_ptz_cmd(&model).await?;
+    }
+    Ok(true)
+}
+
+pub async fn preset_list(model: PresetQueryModel, _token: String) -> GlobalResult<Vec<PresetItem>> {
+    let device_id = &model.device_id;
+    let channel_id = model.channel_id.as_ref().unwrap_or(device_id);
+    if !model.refresh.unwrap_or(false) {
+        if let Some(presets) = SipRuntimeCache::global().cached_presets(device_id, channel_id) {
+            return Ok(presets);
+        }
+    }
+    online_device(device_id)?;
+    sip_command::query_preset_and_wait(device_id, channel_id).await
+}
+
+pub async fn ptz_position(
+    model: DeviceTargetModel,
+    _token: String,
+) -> GlobalResult<PtzPositionInfo> {
+    let device_id = online_device(&model.device_id)?;
+    let channel_id = model.channel_id.as_deref().unwrap_or(device_id);
+    sip_command::query_ptz_position_and_wait(device_id, channel_id).await
+}
+
+pub async fn device_status(
+    model: DeviceTargetModel,
+    _token: String,
+) -> GlobalResult<DeviceStatusInfo> {
+    let device_id = online_device(&model.device_id)?;
+    let channel_id = model.channel_id.as_deref().unwrap_or(device_id);
+    sip_command::query_device_status_and_wait(device_id, channel_id).await
+}
+
+pub async fn config_download(
+    model: ConfigDownloadModel,
+    _token: String,
+) -> GlobalResult<DeviceConfigInfo> {
+    if model.config_types.is_empty() {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidRequest.code(),
+            "配置类型不能为空",
+            |msg| error!("{msg}: device_id={}", model.device_id),
+        ));
+    }
+    let device_id = online_device(&model.device_id)?;
+    let channel_id = model.channel_id.as_deref().unwrap_or(device_id);
+    sip_command::query_config_and_wait(device_id, channel_id, &model.config_types).await
+}
+
+pub async fn device_config(model: DeviceConfigModel, _token: String) -> GlobalResult<bool> {
+    if model.config.is_empty() || model.config.video_param_opt.is_some() {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidRequest.code(),
+            "配置为空或包含只读配置 VideoParamOpt",
+            |msg| error!("{msg}: device_id={}", model.device_id),
+        ));
+    }
+    let device_id = online_device(&model.device_id)?;
+    let channel_id = model.channel_id.as_deref().unwrap_or(device_id);
+    sip_command::device_config_and_wait(device_id, channel_id, &model.config).await?;
+    Ok(true)
+}
+
+pub async fn device_control(model: DeviceControlModel, _token: String) -> GlobalResult<bool> {
+    let device_id = online_device(&model.device_id)?;
+    let channel_id = model.channel_id.as_deref().unwrap_or(device_id);
+    sip_command::device_control_and_wait(device_id, channel_id, &model.cmd).await?;
+    Ok(true)
+}
+
+pub(crate) fn online_device(device_id: &str) -> GlobalResult<&str> {
+    if !Register::has_session(device_id) {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::Network.code(),
+            "设备已离线",
+            |msg| error!("{msg}: device_id={device_id}"),
+        ));
+    }
+    Ok(device_id)
+}
+
 pub async fn talk_start(model: TalkStartModel, token: String) -> GlobalResult<TalkInfo> {
     let device_id = &model.device_id;
     if !Register::has_session(device_id) {
@@ -660,7 +920,7 @@ fn playback_stream_device(stream_id: &String) -> GlobalResult<String> {
     })
 }
 
-async fn start_invite_stream(
+pub(crate) async fn start_invite_stream(
     device_id: &String,
     channel_id: &String,
     _token: &String,
@@ -908,28 +1168,11 @@ async fn listen_stream_by_stream_id(stream_id: &String, secs: u64) -> Option<Bas
     res
 }
 
-fn output_kind_to_enum(output: &OutputKind) -> OutputEnum {
-    match output {
-        OutputKind::Rtmp(_) => OutputEnum::Rtmp,
-        OutputKind::DashFmp4(_) => OutputEnum::DashFmp4,
-        OutputKind::DashMp4(_) => OutputEnum::DashMp4,
-        OutputKind::HlsFmp4(_) => OutputEnum::HlsFmp4,
-        OutputKind::HlsTs(_) => OutputEnum::HlsTs,
-        OutputKind::Rtsp(_) => OutputEnum::Rtsp,
-        OutputKind::Gb28181Frame(_) => OutputEnum::Gb28181Frame,
-        OutputKind::Gb28181Ps(_) => OutputEnum::Gb28181Ps,
-        OutputKind::WebRtc(_) => OutputEnum::WebRtc,
-        OutputKind::LocalMp4(_) => OutputEnum::LocalMp4,
-        OutputKind::LocalTs(_) => OutputEnum::LocalTs,
-        OutputKind::HttpFlv(_) => OutputEnum::HttpFlv,
-    }
-}
-
 async fn cleanup_stream_init(client: &impl HttpStream, ssrc: u32, output: &OutputKind) {
     let _ = client
         .close_output(&StreamInfoQo {
             ssrc,
-            output_enum: output_kind_to_enum(output),
+            output_enum: output.output_enum(),
         })
         .await;
 }
@@ -0,0 +1,221 @@
+use base::exception::GlobalResult;
+use base::log::debug;
+
+use crate::service::device_serv::to_channel_item;
+use crate::service::webhook_serv::{
+    self, CHANNEL_ADDED, CHANNEL_REMOVED, CHANNEL_STATUS_CHANGED, CHANNEL_UPDATED,
+};
+use crate::state::model::WebhookEvent;
+use crate::storage::db_task::{self, DbTask};
+use crate::storage::entity::{CatalogEvent, GmvDeviceChannel};
+
+/// 接收目录应答或目录 NOTIFY，经 DB 队列按序落库
+pub fn receive(device_id: &str, items: Vec<(String, String)>) {
+    let channels = GmvDeviceChannel::build_catalog(device_id, items);
+    if channels.is_empty() {
+        debug!("catalog without channel item: device_id={device_id}");
+        return;
+    }
+    db_task::submit(DbTask::ApplyDeviceCatalog {
+        device_id: device_id.to_string(),
+        channels,
+    });
+}
+
+/// 应用目录变更：无事件的条目整体更新且不推送；
+/// 有事件的条目按新增/更新、状态、删除顺序落库，成功后推送对应事件
+pub async fn apply(
+    device_id: &str,
+    channels: Vec<(GmvDeviceChannel, Option<CatalogEvent>)>,
+) -> GlobalResult<()> {
+    let changes = classify(device_id, channels);
+    GmvDeviceChannel::upsert_gmv_device_channels(&changes.upserts).await?;
+    for (channel_id, status) in changes.statuses {
+        GmvDeviceChannel::update_gmv_device_channel_status(device_id, &channel_id, status).await?;
+    }
+    GmvDeviceChannel::delete_gmv_device_channels(device_id, &changes.removed).await?;
+    for event in changes.events {
+        webhook_serv::emit(event);
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct CatalogChanges {
+    upserts: Vec<GmvDeviceChannel>,
+    statuses: Vec<(String, &'static str)>,
+    removed: Vec<String>,
+    events: Vec<WebhookEvent>,
+}
+
+fn classify(
+    device_id: &str,
+    channels: Vec<(GmvDeviceChannel, Option<CatalogEvent>)>,
+) -> CatalogChanges {
+    let mut changes = CatalogChanges::default();
+    for (dc, event) in channels {
+        match event {
+            None => changes.upserts.push(dc),
+            Some(CatalogEvent::Add) => {
+                changes.events.push(channel_event(CHANNEL_ADDED, &dc));
+                changes.upserts.push(dc);
+            }
+            Some(CatalogEvent::Update) => {
+                changes.events.push(channel_event(CHANNEL_UPDATED, &dc));
+                changes.upserts.push(dc);
+            }
+            Some(CatalogEvent::Del) => {
+                let mut webhook = webhook_serv::event(CHANNEL_REMOVED, device_id);
+                webhook.channel_id = Some(dc.channel_id.clone());
+                changes.events.push(webhook);
+                changes.removed.push(dc.channel_id);
+            }
+            Some(event) => {
+                let Some(status) = event.status() else {
+                    continue;
+                };
+                let mut webhook = webhook_serv::event(CHANNEL_STATUS_CHANGED, device_id);
+                webhook.channel_id = Some(dc.channel_id.clone());
+                webhook.status = Some(status.to_string());
+                changes.events.push(webhook);
+                changes.statuses.push((dc.channel_id, status));
+            }
+        }
+    }
+    changes
+}
+
+fn channel_event(kind: &str, dc: &GmvDeviceChannel) -> WebhookEvent {
+    let mut event = webhook_serv::event(kind, &dc.device_id);
+    event.channel_id = Some(dc.channel_id.clone());
+    event.status = Some(dc.status.clone()).filter(|status| !status.is_empty());
+    // 目录 NOTIFY 随设备会话上报，设备此时在线
+    event.channel = Some(to_channel_item(dc.clone(), true));
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, classify};
+    use crate::gb::sip::native_runtime::RUNTIME_TEST_LOCK;
+    use crate::storage::entity::{CatalogEvent, GmvDeviceChannel, GmvOauth, enable_test_storage};
+
+    const DEVICE_ID: &str = "34020000001320000001";
+
+    fn channel(channel_id: &str, status: &str) -> GmvDeviceChannel {
+        GmvDeviceChannel {
+            device_id: DEVICE_ID.to_string(),
+            channel_id: channel_id.to_string(),
+            status: status.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn classifies_catalog_events() {
+        let changes = classify(
+            DEVICE_ID,
+            vec![
+                (channel("34020000001320000011", "ON"), None),
+                (
+                    channel("34020000001320000012", "ON"),
+                    Some(CatalogEvent::Add),
+                ),
+                (
+                    channel("34020000001320000013", ""),
+                    Some(CatalogEvent::Update),
+                ),
+                (channel("34020000001320000014", ""), Some(CatalogEvent::Off)),
+                (channel("34020000001320000015", ""), Some(CatalogEvent::Del)),
+            ],
+        );
+        let upserts = changes
+            .upserts
+            .iter()
+            .map(|dc| dc.channel_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            upserts,
+            [
+                "34020000001320000011",
+                "34020000001320000012",
+                "34020000001320000013"
+            ]
+        );
+        assert_eq!(
+            changes.statuses,
+            [("34020000001320000014".to_string(), "OFF")]
+        );
+        assert_eq!(changes.removed, ["34020000001320000015"]);
+        let events = changes
+            .events
+            .iter()
+            .map(|e| {
+                (
+                    e.event.as_str(),
+                    e.channel_id.as_deref(),
+                    e.status.as_deref(),
+                    e.channel.is_some(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                (
+                    "channel_added",
+                    Some("34020000001320000012"),
+                    Some("ON"),
+                    true
+                ),
+                ("channel_updated", Some("34020000001320000013"), None, true),
+                (
+                    "channel_status_changed",
+                    Some("34020000001320000014"),
+                    Some("OFF"),
+                    false
+                ),
+                ("channel_removed", Some("34020000001320000015"), None, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_updates_stored_channels() {
+        let _runtime_guard = RUNTIME_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let runtime = base::tokio::runtime::Runtime::new().expect("create Tokio runtime");
+        runtime.block_on(async {
+            let _storage = enable_test_storage(GmvOauth::default());
+            apply(
+                DEVICE_ID,
+                vec![
+                    (channel("34020000001320000011", "ON"), None),
+                    (channel("34020000001320000012", "ON"), None),
+                ],
+            )
+            .await
+            .unwrap();
+            apply(
+                DEVICE_ID,
+                vec![
+                    (channel("34020000001320000011", ""), Some(CatalogEvent::Off)),
+                    (channel("34020000001320000012", ""), Some(CatalogEvent::Del)),
+                ],
+            )
+            .await
+            .unwrap();
+            let stored = GmvDeviceChannel::query_gmv_device_channel_by_device_id(DEVICE_ID)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|dc| (dc.channel_id, dc.status))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                stored,
+                [("34020000001320000011".to_string(), "OFF".to_string())]
+            );
+        });
+    }
+}
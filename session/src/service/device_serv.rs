@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use base::chrono::NaiveDateTime;
+use base::exception::GlobalResult;
+
+use crate::register::core::Register;
+use crate::state::model::{
+    ChannelItem, ChannelQueryModel, ChannelTreeNode, DeviceItem, DeviceQueryModel, PageData,
+};
+use crate::storage::entity::{
+    CHANNEL_STATUS_ON, GmvDeviceChannel, GmvDeviceChannelQo, GmvDeviceInfo, GmvDeviceQo,
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 200;
+pub(crate) const DATE_TIME_FMT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub async fn device_page(model: DeviceQueryModel) -> GlobalResult<PageData<DeviceItem>> {
+    let (offset, limit) = page_range(model.page, model.size);
+    let session_ids = Register::session_device_ids();
+    let qo = GmvDeviceQo {
+        online: model.online,
+        session_ids,
+        civil_code: non_blank(model.civil_code),
+        name: non_blank(model.name),
+        manufacturer: non_blank(model.manufacturer),
+        offset,
+        limit,
+    };
+    let (total, rows) = GmvDeviceInfo::query_gmv_device_page(&qo).await?;
+    let list = rows
+        .into_iter()
+        .map(|info| {
+            let online = qo.session_ids.contains(&info.device_id);
+            DeviceItem {
+                device_id: info.device_id,
+                domain_id: info.domain_id,
+                name: info.alias,
+                transport: info.transport,
+                register_time: fmt_time(&info.register_time),
+                online_expire_time: info.online_expire_time.as_ref().map(fmt_time),
+                local_addr: info.local_addr,
+                contact_uri: info.contact_uri,
+                gb_version: info.gb_version,
+                device_type: info.device_type,
+                manufacturer: info.manufacturer,
+                model: info.model,
+                firmware: info.firmware,
+                max_camera: info.max_camera,
+                channel_count: info.channel_count,
+                online,
+            }
+        })
+        .collect();
+    Ok(PageData { total, list })
+}
+
+pub async fn channel_page(model: ChannelQueryModel) -> GlobalResult<PageData<ChannelItem>> {
+    let (offset, limit) = page_range(model.page, model.size);
+    let qo = GmvDeviceChannelQo {
+        device_id: non_blank(model.device_id),
+        parent_id: non_blank(model.parent_id),
+        online: model.online,
+        session_ids: Register::session_device_ids(),
+        civil_code: non_blank(model.civil_code),
+        name: non_blank(model.name),
+        manufacturer: non_blank(model.manufacturer),
+        offset,
+        limit,
+    };
+    let (total, rows) = GmvDeviceChannel::query_gmv_device_channel_page(&qo).await?;
+    let list = rows
+        .into_iter()
+        .map(|dc| {
+            let device_online = qo.session_ids.contains(&dc.device_id);
+            to_channel_item(dc, device_online)
+        })
+        .collect();
+    Ok(PageData { total, list })
+}
+
+/// 设备下的通道树：按 parent_id 挂载，父节点不在该设备通道中的作为根节点
+pub async fn channel_tree(device_id: String) -> GlobalResult<Vec<ChannelTreeNode>> {
+    let rows = GmvDeviceChannel::query_gmv_device_channel_by_device_id(&device_id).await?;
+    let device_online = Register::get_device_session(&device_id).is_some();
+    let items = rows
+        .into_iter()
+        .map(|dc| to_channel_item(dc, device_online))
+        .collect();
+    Ok(build_channel_tree(items))
+}
+
+fn build_channel_tree(items: Vec<ChannelItem>) -> Vec<ChannelTreeNode> {
+    let ids: HashSet<String> = items.iter().map(|item| item.channel_id.clone()).collect();
+    let mut roots = Vec::new();
+    let mut children: HashMap<String, Vec<ChannelItem>> = HashMap::new();
+    for item in items {
+        match item
+            .parent_id
+            .as_ref()
+            .filter(|pid| **pid != item.channel_id && ids.contains(*pid))
+            .cloned()
+        {
+            Some(pid) => children.entry(pid).or_default().push(item),
+            None => roots.push(item),
+        }
+    }
+    // 成环的节点无法从根到达，统一挂为根节点，避免数据丢失
+    let mut nodes: Vec<ChannelTreeNode> = roots
+        .into_iter()
+        .map(|item| attach_children(item, &mut children))
+        .collect();
+    while let Some(pid) = children.keys().next().cloned() {
+        if let Some(ls) = children.remove(&pid) {
+            for item in ls {
+                nodes.push(attach_children(item, &mut children));
+            }
+        }
+    }
+    nodes
+}
+
+fn attach_children(
+    item: ChannelItem,
+    children: &mut HashMap<String, Vec<ChannelItem>>,
+) -> ChannelTreeNode {
+    let sub = children.remove(&item.channel_id).unwrap_or_default();
+    ChannelTreeNode {
+        channel: item,
+        children: sub
+            .into_iter()
+            .map(|child| attach_children(child, children))
+            .collect(),
+    }
+}
+
+pub(crate) fn to_channel_item(dc: GmvDeviceChannel, device_online: bool) -> ChannelItem {
+    let online = device_online
+        && CHANNEL_STATUS_ON
+            .iter()
+            .any(|s| dc.status.eq_ignore_ascii_case(s));
+    ChannelItem {
+        device_id: dc.device_id,
+        channel_id: dc.channel_id,
+        name: dc.name,
+        manufacturer: dc.manufacturer,
+        model: dc.model,
+        owner: dc.owner,
+        status: dc.status,
+        civil_code: dc.civil_code,
+        address: dc.address,
+        parental: dc.parental,
+        block: dc.block,
+        parent_id: dc.parent_id,
+        ip_address: dc.ip_address,
+        port: dc.port,
+        longitude: dc.longitude,
+        latitude: dc.latitude,
+        ptz_type: dc.ptz_type,
+        online,
+    }
+}
+
+pub(crate) fn page_range(page: Option<u32>, size: Option<u32>) -> (u32, u32) {
+    let page = page.unwrap_or(1).max(1);
+    let size = size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    ((page - 1).saturating_mul(size), size)
+}
+
+pub(crate) fn non_blank(v: Option<String>) -> Option<String> {
+    v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+pub(crate) fn fmt_time(time: &NaiveDateTime) -> String {
+    time.format(DATE_TIME_FMT).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(channel_id: &str, parent_id: Option<&str>) -> ChannelItem {
+        let dc = GmvDeviceChannel {
+            device_id: "34020000001110000001".to_string(),
+            channel_id: channel_id.to_string(),
+            parent_id: parent_id.map(str::to_string),
+            status: "ON".to_string(),
+            ..Default::default()
+        };
+        to_channel_item(dc, true)
+    }
+
+    #[test]
+    fn channel_tree_mounts_by_parent_id() {
+        let tree = build_channel_tree(vec![
+            item("34020000002150000001", Some("34020000001110000001")),
+            item("34020000001320000001", Some("34020000002150000001")),
+            item("34020000001320000002", Some("34020000002150000001")),
+            item("34020000001320000003", None),
+        ]);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].channel.channel_id, "34020000002150000001");
+        assert_eq!(tree[0].children.len(), 2);
+        assert!(tree[0].children.iter().all(|n| n.channel.online));
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn channel_tree_keeps_cycle_nodes() {
+        let tree = build_channel_tree(vec![
+            item("34020000001320000001", Some("34020000001320000002")),
+            item("34020000001320000002", Some("34020000001320000001")),
+        ]);
+        let count: usize = tree.iter().map(|n| 1 + n.children.len()).sum();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn page_range_clamps_size() {
+        assert_eq!(page_range(None, None), (0, DEFAULT_PAGE_SIZE));
+        assert_eq!(page_range(Some(0), Some(0)), (0, 1));
+        assert_eq!(page_range(Some(3), Some(1000)), (400, MAX_PAGE_SIZE));
+    }
+}
@@ -85,7 +85,24 @@ pub async fn upload(
         None => edge_token::build_file_name(&device_id, &channel_id)?,
         Some(id) => id.to_string(),
     };
+    save_picture(
+        bytes,
+        device_id,
+        channel_id,
+        session_id.to_string(),
+        file_name,
+    )
+    .await
+}
 
+/// 按图片存储配置转存并入库，biz_id 为设备抓拍会话或媒体流ID
+pub async fn save_picture(
+    bytes: Bytes,
+    device_id: String,
+    channel_id: String,
+    biz_id: String,
+    file_name: String,
+) -> GlobalResult<()> {
     let mut info = GmvFileInfo::default();
     let now = Local::now().naive_local();
     info.biz_time = Some(now);
@@ -94,7 +111,7 @@ pub async fn upload(
     info.is_del = Some(0);
     info.device_id = device_id;
     info.channel_id = channel_id;
-    info.biz_id = session_id.to_string();
+    info.biz_id = biz_id;
 
     let pics_conf = Pics::get_pics_by_conf();
     let relative_path = Path::new(&pics_conf.storage_path);
@@ -4,19 +4,21 @@ use std::path::Path;
 use base::bytes::Bytes;
 use base::chrono::{Local, TimeZone};
 use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
-use base::log::{error, warn};
+use base::log::{error, info, warn};
 use base::serde_json;
 use shared::info::obj::{
-    InTimeoutEventRes, OutputEventRes, OutputStreamInfo, RegisterStreamInfo, StreamPlayInfo,
-    StreamRecordInfo, StreamState, TalkClosedEvent, UnknownStreamEvent,
+    InTimeoutEventRes, OutputEventRes, OutputStreamInfo, PushState, PushStateInfo,
+    RegisterStreamInfo, StreamPlayInfo, StreamRecordInfo, StreamState, TalkClosedEvent,
+    UnknownStreamEvent,
 };
 
 use crate::gb::SessionConf;
-use crate::service::{KEY_STREAM_IN, dialog_recovery, stream_close, talk_close};
+use crate::service::{KEY_STREAM_IN, dialog_recovery, edge_serv, stream_close, talk_close};
 use crate::state;
 use crate::state::DownloadConf;
 use crate::storage::dialog_session::SipDialogSessionRepository;
 use crate::storage::entity::{GmvFileInfo, GmvRecord};
+use crate::utils::{edge_token, id_builder};
 
 pub async fn stream_register(register_stream_info: RegisterStreamInfo) {
     let key_stream_in_id = format!(
@@ -259,6 +261,31 @@ pub async fn talk_closed(event: TalkClosedEvent) -> bool {
     closed
 }
 
+/// 主动推流状态仅做记录，推流重连由流媒体节点自行处理
+pub fn push_state(info: PushStateInfo) {
+    match info.state {
+        PushState::Connecting | PushState::Pushing | PushState::Stopped => info!(
+            "push state: stream_id={}, output={:?}, target={}, state={:?}",
+            info.stream_id, info.output, info.target, info.state
+        ),
+        PushState::Retrying => warn!(
+            "push retrying: stream_id={}, output={:?}, target={}, retry={}, reason={:?}",
+            info.stream_id, info.output, info.target, info.retry, info.reason
+        ),
+        PushState::Failed => error!(
+            "push failed: stream_id={}, output={:?}, target={}, retry={}, reason={:?}",
+            info.stream_id, info.output, info.target, info.retry, info.reason
+        ),
+    }
+}
+
+/// 媒体节点定时抽图入库，按 stream_id 归属设备通道
+pub async fn stream_capture(stream_id: String, bytes: Bytes) -> GlobalResult<()> {
+    let (device_id, channel_id, _) = id_builder::de_stream_id(&stream_id)?;
+    let file_name = edge_token::build_file_name(&device_id, &channel_id)?;
+    edge_serv::save_picture(bytes, device_id, channel_id, stream_id, file_name).await
+}
+
 fn get_path(path_file_name: &str) -> GlobalResult<(String, String, String, String)> {
     let path = Path::new(path_file_name);
     let biz_id = path
@@ -0,0 +1,252 @@
+use base::chrono::{Duration as TimeDelta, Local, NaiveDateTime};
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult};
+use base::log::{error, info};
+use base::net::state::Association;
+
+use crate::service::device_serv::{DATE_TIME_FMT, fmt_time, non_blank};
+use crate::service::webhook_serv;
+use crate::state::model::{
+    DeviceLifecycleItem, DeviceTimelineInfo, DeviceTimelineModel, DeviceUptimeSegment,
+};
+use crate::storage::db_task::{self, DbTask};
+use crate::storage::entity::GmvDeviceStatusHistory;
+
+const DEFAULT_TIMELINE_HOURS: i64 = 24;
+const MAX_TIMELINE_DAYS: i64 = 31;
+const MAX_TIMELINE_EVENTS: u32 = 10_000;
+
+/// 设备生命周期事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceLifecycle {
+    /// 首次注册或会话清理后重新注册
+    Registered,
+    /// 会话存续期间以新的网络地址或新的注册代际重新注册
+    Reregistered,
+    /// 连接断开后在重连窗口内恢复
+    Reconnected,
+    /// 连接断开，等待设备重连
+    Disconnected,
+    KeepaliveTimeout,
+    RegistrationExpired,
+    /// 连接断开后未在重连窗口内恢复
+    ReconnectTimeout,
+    Unregistered,
+    /// 接入被停用或删除，会话被踢下线
+    Disabled,
+}
+
+impl DeviceLifecycle {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Registered => "device_registered",
+            Self::Reregistered => "device_reregistered",
+            Self::Reconnected => "device_reconnected",
+            Self::Disconnected => "device_disconnected",
+            Self::KeepaliveTimeout => "device_keepalive_timeout",
+            Self::RegistrationExpired => "device_registration_expired",
+            Self::ReconnectTimeout => "device_reconnect_timeout",
+            Self::Unregistered => "device_unregistered",
+            Self::Disabled => "device_disabled",
+        }
+    }
+
+    /// 事件发生后设备是否可达
+    pub fn online(self) -> bool {
+        matches!(
+            self,
+            Self::Registered | Self::Reregistered | Self::Reconnected
+        )
+    }
+}
+
+/// 记录设备生命周期事件：写入状态历史并推送给业务系统
+pub fn record(device_id: &str, lifecycle: DeviceLifecycle, association: Option<&Association>) {
+    let address = association.map(|association| association.remote_addr.to_string());
+    info!(
+        "device lifecycle: device_id={device_id}, event={}, address={:?}",
+        lifecycle.as_str(),
+        address
+    );
+    db_task::submit(DbTask::InsertDeviceStatusHistory(GmvDeviceStatusHistory {
+        device_id: device_id.to_string(),
+        event: lifecycle.as_str().to_string(),
+        online: u8::from(lifecycle.online()),
+        address: address.clone(),
+        event_time: Local::now().naive_local(),
+    }));
+    let mut event = webhook_serv::event(lifecycle.as_str(), device_id);
+    event.status = Some(if lifecycle.online() { "ON" } else { "OFF" }.to_string());
+    event.address = address;
+    webhook_serv::emit(event);
+}
+
+/// 设备在线时间线：起点状态取范围前最后一条记录，无记录视为离线
+pub async fn timeline(model: DeviceTimelineModel) -> GlobalResult<DeviceTimelineInfo> {
+    let now = Local::now().naive_local();
+    let end_time = parse_time(model.end_time)?.unwrap_or(now).min(now);
+    let start_time = parse_time(model.start_time)?
+        .unwrap_or(end_time - TimeDelta::hours(DEFAULT_TIMELINE_HOURS));
+    if start_time >= end_time {
+        return Err(invalid_request("start_time must be earlier than end_time"));
+    }
+    if end_time - start_time > TimeDelta::days(MAX_TIMELINE_DAYS) {
+        return Err(invalid_request("timeline range must not exceed 31 days"));
+    }
+    let initial =
+        GmvDeviceStatusHistory::query_last_gmv_device_status_before(&model.device_id, start_time)
+            .await?
+            .is_some_and(|history| history.online == 1);
+    let history = GmvDeviceStatusHistory::query_gmv_device_status_history(
+        &model.device_id,
+        start_time,
+        end_time,
+        MAX_TIMELINE_EVENTS,
+    )
+    .await?;
+    let transitions = history
+        .iter()
+        .map(|history| (history.event_time, history.online == 1))
+        .collect::<Vec<_>>();
+    let segments = uptime_segments(initial, &transitions, start_time, end_time);
+    let online_seconds = segments
+        .iter()
+        .filter(|segment| segment.online)
+        .map(|segment| segment.seconds)
+        .sum::<i64>();
+    let events = history
+        .into_iter()
+        .map(|history| DeviceLifecycleItem {
+            event: history.event,
+            online: history.online == 1,
+            address: history.address,
+            time: fmt_time(&history.event_time),
+        })
+        .collect();
+    Ok(DeviceTimelineInfo {
+        device_id: model.device_id,
+        start_time: fmt_time(&start_time),
+        end_time: fmt_time(&end_time),
+        online_seconds,
+        offline_seconds: (end_time - start_time).num_seconds() - online_seconds,
+        segments,
+        events,
+    })
+}
+
+/// 按状态变化切分 [start, end) 区间，相邻同状态区间合并
+fn uptime_segments(
+    initial: bool,
+    transitions: &[(NaiveDateTime, bool)],
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Vec<DeviceUptimeSegment> {
+    let mut segments = Vec::<DeviceUptimeSegment>::new();
+    let mut push = |online: bool, from: NaiveDateTime, to: NaiveDateTime| {
+        if to <= from {
+            return;
+        }
+        if let Some(last) = segments.last_mut().filter(|last| last.online == online) {
+            last.end_time = fmt_time(&to);
+            last.seconds += (to - from).num_seconds();
+            return;
+        }
+        segments.push(DeviceUptimeSegment {
+            online,
+            start_time: fmt_time(&from),
+            end_time: fmt_time(&to),
+            seconds: (to - from).num_seconds(),
+        });
+    };
+    let (mut since, mut online) = (start, initial);
+    for &(time, next) in transitions {
+        if next == online {
+            continue;
+        }
+        let time = time.clamp(start, end);
+        push(online, since, time);
+        since = time;
+        online = next;
+    }
+    push(online, since, end);
+    segments
+}
+
+fn parse_time(value: Option<String>) -> GlobalResult<Option<NaiveDateTime>> {
+    let Some(value) = non_blank(value) else {
+        return Ok(None);
+    };
+    NaiveDateTime::parse_from_str(&value, DATE_TIME_FMT)
+        .map(Some)
+        .map_err(|_| invalid_request("time must be yyyy-MM-dd HH:mm:ss"))
+}
+
+fn invalid_request(message: &'static str) -> GlobalError {
+    GlobalError::new_biz_error(BaseErrorCode::InvalidRequest.code(), message, |msg| {
+        error!("{msg}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use base::chrono::NaiveDateTime;
+
+    use super::{DeviceLifecycle, uptime_segments};
+
+    fn time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn splits_uptime_by_online_transitions() {
+        let start = time("2026-06-13 00:00:00");
+        let end = time("2026-06-13 01:00:00");
+        let transitions = [
+            (time("2026-06-13 00:10:00"), true),
+            (time("2026-06-13 00:20:00"), true),
+            (time("2026-06-13 00:30:00"), false),
+            (time("2026-06-13 00:30:00"), true),
+            (time("2026-06-13 00:45:00"), false),
+        ];
+        let segments = uptime_segments(false, &transitions, start, end);
+        let summary = segments
+            .iter()
+            .map(|s| {
+                (
+                    s.online,
+                    s.start_time.as_str(),
+                    s.end_time.as_str(),
+                    s.seconds,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (false, "2026-06-13 00:00:00", "2026-06-13 00:10:00", 600),
+                (true, "2026-06-13 00:10:00", "2026-06-13 00:45:00", 2100),
+                (false, "2026-06-13 00:45:00", "2026-06-13 01:00:00", 900),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_initial_state_without_transitions() {
+        let start = time("2026-06-13 00:00:00");
+        let end = time("2026-06-13 00:00:30");
+        let segments = uptime_segments(true, &[], start, end);
+        assert_eq!(segments.len(), 1);
+        assert!(segments[0].online);
+        assert_eq!(segments[0].seconds, 30);
+    }
+
+    #[test]
+    fn lifecycle_online_state() {
+        assert!(DeviceLifecycle::Reconnected.online());
+        assert!(!DeviceLifecycle::Disconnected.online());
+        assert_eq!(
+            DeviceLifecycle::KeepaliveTimeout.as_str(),
+            "device_keepalive_timeout"
+        );
+    }
+}
@@ -1,10 +1,17 @@
+pub mod alarm_serv;
 pub mod api_serv;
+pub mod catalog_serv;
+pub mod device_serv;
 pub mod dialog_recovery;
 pub mod edge_serv;
 pub mod hook_serv;
+pub mod lifecycle_serv;
+pub mod oauth_serv;
+pub mod position_serv;
 pub mod stream_close;
 mod talk;
 pub mod talk_close;
+pub mod webhook_serv;
 
 pub const EXPIRES: u64 = 8;
 pub const SNAPSHOT_IDLE_EXPIRES: u64 = 20;
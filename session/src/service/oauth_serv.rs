@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult};
+use base::log::{error, info};
+use regex::Regex;
+
+use crate::gb::SessionConf;
+use crate::gb::sip::auth;
+use crate::register::core::Register;
+use crate::service::device_serv::{non_blank, page_range};
+use crate::service::lifecycle_serv::{self, DeviceLifecycle};
+use crate::state::model::{
+    DeviceOauthCreateModel, DeviceOauthHeartbeatModel, DeviceOauthItem, DeviceOauthPwdModel,
+    DeviceOauthQueryModel, DeviceOauthStatusModel, PageData,
+};
+use crate::storage::db_task::{self, DbTask};
+use crate::storage::entity::{GmvOauth, GmvOauthQo};
+
+const DEFAULT_HEARTBEAT_SEC: u8 = 60;
+const MIN_HEARTBEAT_SEC: u8 = 5;
+const GENERATED_PWD_LEN: usize = 16;
+
+pub async fn oauth_page(model: DeviceOauthQueryModel) -> GlobalResult<PageData<DeviceOauthItem>> {
+    let (offset, limit) = page_range(model.page, model.size);
+    let qo = GmvOauthQo {
+        device_id: non_blank(model.device_id),
+        status: model.enable.map(u8::from),
+        alias: non_blank(model.alias),
+        offset,
+        limit,
+    };
+    let (total, rows) = GmvOauth::query_gmv_oauth_page(&qo).await?;
+    let list = rows
+        .into_iter()
+        .map(|oauth| {
+            let online = Register::has_session(&oauth.device_id);
+            DeviceOauthItem {
+                has_pwd: oauth.pwd.as_deref().is_some_and(|pwd| !pwd.is_empty()),
+                pwd_check: oauth.pwd_check == 1,
+                enable: oauth.status == 1,
+                device_id: oauth.device_id,
+                domain_id: oauth.domain_id,
+                domain: oauth.domain,
+                alias: oauth.alias,
+                heartbeat_sec: oauth.heartbeat_sec,
+                online,
+            }
+        })
+        .collect();
+    Ok(PageData { total, list })
+}
+
+pub async fn oauth_create(model: DeviceOauthCreateModel) -> GlobalResult<()> {
+    let device_id = model.device_id.trim().to_string();
+    check_gb_id("device_id", &device_id)?;
+    if GmvOauth::query_gmv_oauth_by_device_id(&device_id)
+        .await?
+        .is_some()
+    {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::AlreadyExists.code(),
+            "device oauth already exists",
+            |msg| error!("device_id={device_id}; {msg}"),
+        ));
+    }
+    let conf = SessionConf::get_session_by_conf();
+    let domain_id = non_blank(model.domain_id).unwrap_or(conf.domain_id);
+    check_gb_id("domain_id", &domain_id)?;
+    let pwd = non_blank(model.pwd);
+    let pwd_check = model.pwd_check.unwrap_or(pwd.is_some());
+    if pwd_check && pwd.is_none() {
+        return Err(invalid_request("pwd is required when pwd_check is enabled"));
+    }
+    let oauth = GmvOauth {
+        device_id,
+        domain_id,
+        domain: non_blank(model.domain).unwrap_or(conf.domain),
+        pwd,
+        pwd_check: u8::from(pwd_check),
+        alias: non_blank(model.alias),
+        status: u8::from(model.enable.unwrap_or(true)),
+        heartbeat_sec: check_heartbeat(model.heartbeat_sec.unwrap_or(DEFAULT_HEARTBEAT_SEC))?,
+    };
+    oauth.insert_gmv_oauth().await?;
+    // 清除未接入前缓存的拒绝结果
+    auth::invalidate_device(&oauth.device_id);
+    info!("device oauth created: device_id={}", oauth.device_id);
+    Ok(())
+}
+
+pub async fn oauth_status(model: DeviceOauthStatusModel) -> GlobalResult<()> {
+    let mut oauth = require_oauth(&model.device_id).await?;
+    oauth.status = u8::from(model.enable);
+    oauth.update_gmv_oauth_access().await?;
+    auth::invalidate_device(&oauth.device_id);
+    info!(
+        "device oauth status changed: device_id={}, enable={}",
+        oauth.device_id, model.enable
+    );
+    if !model.enable {
+        kick_device(&oauth.device_id);
+    }
+    Ok(())
+}
+
+/// 轮换注册密码：返回生效的新密码；在线会话保持，设备下次注册时使用新密码认证
+pub async fn oauth_rotate_pwd(model: DeviceOauthPwdModel) -> GlobalResult<String> {
+    let mut oauth = require_oauth(&model.device_id).await?;
+    let pwd = non_blank(model.pwd).unwrap_or_else(|| {
+        uuid::Uuid::new_v4().simple().to_string()[..GENERATED_PWD_LEN].to_string()
+    });
+    if let Some(pwd_check) = model.pwd_check {
+        oauth.pwd_check = u8::from(pwd_check);
+    }
+    oauth.pwd = Some(pwd.clone());
+    oauth.update_gmv_oauth_access().await?;
+    auth::invalidate_device(&oauth.device_id);
+    info!(
+        "device oauth password rotated: device_id={}",
+        oauth.device_id
+    );
+    Ok(pwd)
+}
+
+/// 设置心跳周期：设备下次注册后生效
+pub async fn oauth_heartbeat(model: DeviceOauthHeartbeatModel) -> GlobalResult<()> {
+    let mut oauth = require_oauth(&model.device_id).await?;
+    oauth.heartbeat_sec = check_heartbeat(model.heartbeat_sec)?;
+    oauth.update_gmv_oauth_access().await?;
+    auth::invalidate_device(&oauth.device_id);
+    Ok(())
+}
+
+/// 软删除接入记录，并踢下线在线会话
+pub async fn oauth_delete(device_id: String) -> GlobalResult<()> {
+    let device_id = device_id.trim();
+    if GmvOauth::delete_gmv_oauth(device_id).await? == 0 {
+        return Err(not_found(device_id));
+    }
+    auth::invalidate_device(device_id);
+    info!("device oauth deleted: device_id={device_id}");
+    kick_device(device_id);
+    Ok(())
+}
+
+/// 移除注册会话并关闭 TCP 连接，设备再次注册时将被拒绝
+fn kick_device(device_id: &str) {
+    let Some(session) = Register::remove_device(&Arc::from(device_id)) else {
+        return;
+    };
+    Register::close_tcp_if_needed(&session);
+    lifecycle_serv::record(
+        device_id,
+        DeviceLifecycle::Disabled,
+        Some(&session.association),
+    );
+    db_task::submit(DbTask::ExpireDeviceOnline {
+        device_id: device_id.to_string(),
+    });
+}
+
+async fn require_oauth(device_id: &str) -> GlobalResult<GmvOauth> {
+    let device_id = device_id.trim();
+    GmvOauth::query_gmv_oauth_by_device_id(device_id)
+        .await?
+        .ok_or_else(|| not_found(device_id))
+}
+
+fn check_gb_id(field: &str, value: &str) -> GlobalResult<()> {
+    let re = Regex::new(r"^\d{20}$").unwrap();
+    if re.is_match(value) {
+        return Ok(());
+    }
+    Err(GlobalError::new_biz_error(
+        BaseErrorCode::InvalidRequest.code(),
+        &format!("{field} must be 20 digits"),
+        |msg| error!("{msg}: {value}"),
+    ))
+}
+
+fn check_heartbeat(heartbeat_sec: u8) -> GlobalResult<u8> {
+    if heartbeat_sec < MIN_HEARTBEAT_SEC {
+        return Err(invalid_request("heartbeat_sec must be at least 5 seconds"));
+    }
+    Ok(heartbeat_sec)
+}
+
+fn not_found(device_id: &str) -> GlobalError {
+    GlobalError::new_biz_error(
+        BaseErrorCode::NotFound.code(),
+        "device oauth not found",
+        |msg| error!("device_id={device_id}; {msg}"),
+    )
+}
+
+fn invalid_request(message: &'static str) -> GlobalError {
+    GlobalError::new_biz_error(BaseErrorCode::InvalidRequest.code(), message, |msg| {
+        error!("{msg}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_gb_id, check_heartbeat, oauth_heartbeat, oauth_rotate_pwd};
+    use crate::gb::sip::native_runtime::RUNTIME_TEST_LOCK;
+    use crate::state::model::{DeviceOauthHeartbeatModel, DeviceOauthPwdModel};
+    use crate::storage::entity::{GmvOauth, enable_test_storage};
+
+    const DEVICE_ID: &str = "34020000001320000001";
+
+    #[test]
+    fn gb_id_requires_twenty_digits() {
+        assert!(check_gb_id("device_id", DEVICE_ID).is_ok());
+        assert!(check_gb_id("device_id", "3402000000132000000").is_err());
+        assert!(check_gb_id("device_id", "3402000000132000000a").is_err());
+        assert!(check_gb_id("device_id", " 34020000001320000001").is_err());
+    }
+
+    #[test]
+    fn heartbeat_has_lower_bound() {
+        assert_eq!(check_heartbeat(5).unwrap(), 5);
+        assert_eq!(check_heartbeat(60).unwrap(), 60);
+        assert!(check_heartbeat(4).is_err());
+    }
+
+    #[test]
+    fn rotate_pwd_and_heartbeat_update_stored_oauth() {
+        let _runtime_guard = RUNTIME_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let runtime = base::tokio::runtime::Runtime::new().expect("create Tokio runtime");
+        runtime.block_on(async {
+            let _storage = enable_test_storage(GmvOauth {
+                device_id: DEVICE_ID.to_string(),
+                pwd: Some("old".to_string()),
+                pwd_check: 0,
+                status: 1,
+                heartbeat_sec: 60,
+                ..Default::default()
+            });
+            let pwd = oauth_rotate_pwd(DeviceOauthPwdModel {
+                device_id: format!(" {DEVICE_ID} "),
+                pwd: None,
+                pwd_check: Some(true),
+            })
+            .await
+            .unwrap();
+            assert_eq!(pwd.len(), 16);
+            oauth_heartbeat(DeviceOauthHeartbeatModel {
+                device_id: DEVICE_ID.to_string(),
+                heartbeat_sec: 30,
+            })
+            .await
+            .unwrap();
+            let oauth = GmvOauth::query_gmv_oauth_by_device_id(DEVICE_ID)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(oauth.pwd.as_deref(), Some(pwd.as_str()));
+            assert_eq!(oauth.pwd_check, 1);
+            assert_eq!(oauth.heartbeat_sec, 30);
+
+            assert!(
+                oauth_heartbeat(DeviceOauthHeartbeatModel {
+                    device_id: DEVICE_ID.to_string(),
+                    heartbeat_sec: 3,
+                })
+                .await
+                .is_err()
+            );
+            assert!(
+                oauth_rotate_pwd(DeviceOauthPwdModel {
+                    device_id: "34020000001320000002".to_string(),
+                    pwd: None,
+                    pwd_check: None,
+                })
+                .await
+                .is_err()
+            );
+        });
+    }
+}
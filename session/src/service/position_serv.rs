@@ -0,0 +1,178 @@
+use base::chrono::NaiveDateTime;
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult};
+use base::log::error;
+
+use crate::gb::sip::subscription::{self, DEFAULT_POSITION_INTERVAL};
+use crate::service::api_serv::online_device;
+use crate::service::device_serv::{DATE_TIME_FMT, fmt_time, non_blank};
+use crate::state::model::{
+    DeviceTargetModel, MobilePositionItem, PositionSubscribeModel, PositionTrackModel,
+};
+use crate::state::session::SubscriptionKind;
+use crate::storage::entity::GmvMobilePosition;
+
+const DEFAULT_POSITION_EXPIRES: u32 = 3600;
+const MAX_POSITION_INTERVAL: u32 = 3600;
+const MAX_TRACK_POINTS: u32 = 10_000;
+
+pub async fn subscribe(model: PositionSubscribeModel) -> GlobalResult<()> {
+    let device_id = online_device(&model.device_id)?;
+    let interval = model
+        .interval
+        .unwrap_or(DEFAULT_POSITION_INTERVAL)
+        .clamp(1, MAX_POSITION_INTERVAL);
+    let expires = model.expires.unwrap_or(DEFAULT_POSITION_EXPIRES).max(60);
+    subscription::subscribe_mobile_position(device_id, interval, expires).await
+}
+
+pub async fn unsubscribe(device_id: String) -> GlobalResult<()> {
+    subscription::unsubscribe(&device_id, SubscriptionKind::MobilePosition).await
+}
+
+pub async fn latest(model: DeviceTargetModel) -> GlobalResult<Option<MobilePositionItem>> {
+    let channel_id = non_blank(model.channel_id);
+    let position = GmvMobilePosition::query_latest_gmv_mobile_position(
+        &model.device_id,
+        channel_id.as_deref(),
+    )
+    .await?;
+    Ok(position.map(to_item))
+}
+
+pub async fn track(model: PositionTrackModel) -> GlobalResult<Vec<MobilePositionItem>> {
+    let start_time = parse_time(&model.start_time)?;
+    let end_time = parse_time(&model.end_time)?;
+    if start_time > end_time {
+        return Err(invalid_request(
+            "start_time must not be later than end_time",
+        ));
+    }
+    let channel_id = non_blank(model.channel_id);
+    let track = GmvMobilePosition::query_gmv_mobile_position_track(
+        &model.device_id,
+        channel_id.as_deref(),
+        start_time,
+        end_time,
+        MAX_TRACK_POINTS,
+    )
+    .await?;
+    Ok(track.into_iter().map(to_item).collect())
+}
+
+fn to_item(position: GmvMobilePosition) -> MobilePositionItem {
+    MobilePositionItem {
+        device_id: position.device_id,
+        channel_id: position.channel_id,
+        time: fmt_time(&position.time),
+        longitude: position.longitude,
+        latitude: position.latitude,
+        speed: position.speed,
+        direction: position.direction,
+        altitude: position.altitude,
+    }
+}
+
+fn parse_time(value: &str) -> GlobalResult<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), DATE_TIME_FMT)
+        .map_err(|_| invalid_request("time must be yyyy-MM-dd HH:mm:ss"))
+}
+
+fn invalid_request(message: &'static str) -> GlobalError {
+    GlobalError::new_biz_error(BaseErrorCode::InvalidRequest.code(), message, |msg| {
+        error!("{msg}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use base::chrono::NaiveDateTime;
+
+    use super::{parse_time, to_item, track};
+    use crate::gb::sip::native_runtime::RUNTIME_TEST_LOCK;
+    use crate::state::model::PositionTrackModel;
+    use crate::storage::entity::{GmvMobilePosition, GmvOauth, enable_test_storage};
+
+    const DEVICE_ID: &str = "34020000001320000001";
+
+    fn time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn position(channel_id: &str, at: &str, longitude: f64) -> GmvMobilePosition {
+        GmvMobilePosition {
+            device_id: DEVICE_ID.to_string(),
+            channel_id: channel_id.to_string(),
+            time: time(at),
+            longitude,
+            latitude: 30.5,
+            speed: Some(12.5),
+            direction: None,
+            altitude: None,
+            create_time: time(at),
+        }
+    }
+
+    fn track_model(start_time: &str, end_time: &str) -> PositionTrackModel {
+        PositionTrackModel {
+            device_id: DEVICE_ID.to_string(),
+            channel_id: Some("34020000001320000011".to_string()),
+            start_time: start_time.to_string(),
+            end_time: end_time.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_trimmed_time_and_rejects_other_formats() {
+        assert_eq!(
+            parse_time(" 2026-06-13 08:00:00 ").unwrap(),
+            time("2026-06-13 08:00:00")
+        );
+        assert!(parse_time("2026-06-13T08:00:00").is_err());
+        assert!(parse_time("").is_err());
+    }
+
+    #[test]
+    fn item_formats_position_time() {
+        let item = to_item(position(
+            "34020000001320000011",
+            "2026-06-13 08:00:05",
+            120.1,
+        ));
+        assert_eq!(item.device_id, DEVICE_ID);
+        assert_eq!(item.channel_id, "34020000001320000011");
+        assert_eq!(item.time, "2026-06-13 08:00:05");
+        assert_eq!(item.longitude, 120.1);
+        assert_eq!(item.speed, Some(12.5));
+    }
+
+    #[test]
+    fn track_filters_channel_and_range_in_time_order() {
+        let _runtime_guard = RUNTIME_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let runtime = base::tokio::runtime::Runtime::new().expect("create Tokio runtime");
+        runtime.block_on(async {
+            let _storage = enable_test_storage(GmvOauth::default());
+            for p in [
+                position("34020000001320000011", "2026-06-13 08:02:00", 120.3),
+                position("34020000001320000011", "2026-06-13 08:00:00", 120.1),
+                position("34020000001320000012", "2026-06-13 08:01:00", 121.0),
+                position("34020000001320000011", "2026-06-13 09:00:00", 120.9),
+            ] {
+                p.insert_gmv_mobile_position().await.unwrap();
+            }
+            let items = track(track_model("2026-06-13 08:00:00", "2026-06-13 08:30:00"))
+                .await
+                .unwrap();
+            let times = items.iter().map(|i| i.time.as_str()).collect::<Vec<_>>();
+            assert_eq!(times, ["2026-06-13 08:00:00", "2026-06-13 08:02:00"]);
+
+            assert!(
+                track(track_model("2026-06-13 09:00:00", "2026-06-13 08:00:00"))
+                    .await
+                    .is_err()
+            );
+        });
+    }
+}
@@ -0,0 +1,119 @@
+use base::chrono::Local;
+use base::exception::{GlobalResult, GlobalResultExt};
+use base::log::{error, warn};
+use base::once_cell::sync::OnceCell;
+use base::tokio::select;
+use base::tokio::sync::mpsc::error::TrySendError;
+use base::tokio::sync::mpsc::{self, Receiver, Sender};
+use base::tokio_util::sync::CancellationToken;
+
+use crate::service::alarm_serv::retry_delay;
+use crate::service::device_serv::DATE_TIME_FMT;
+use crate::state::WebhookConf;
+use crate::state::model::WebhookEvent;
+
+pub const CHANNEL_ADDED: &str = "channel_added";
+pub const CHANNEL_UPDATED: &str = "channel_updated";
+pub const CHANNEL_REMOVED: &str = "channel_removed";
+pub const CHANNEL_STATUS_CHANGED: &str = "channel_status_changed";
+
+const WEBHOOK_QUEUE_SIZE: usize = 4096;
+
+static WEBHOOK_TX: OnceCell<Sender<WebhookEvent>> = OnceCell::new();
+
+/// 开启推送时启动推送队列：单协程顺序推送，保证同一设备的事件先后有序
+pub fn init(cancel: CancellationToken) {
+    let conf = WebhookConf::get_webhook_conf();
+    let Some(push_url) = conf.push_url.clone().filter(|_| conf.enable) else {
+        return;
+    };
+    if WEBHOOK_TX.get().is_some() {
+        return;
+    }
+    let (tx, rx) = mpsc::channel(WEBHOOK_QUEUE_SIZE);
+    if WEBHOOK_TX.set(tx).is_err() {
+        return;
+    }
+    base::tokio::spawn(run(rx, push_url, conf.max_attempts, cancel));
+}
+
+pub fn event(kind: &str, device_id: &str) -> WebhookEvent {
+    WebhookEvent {
+        event: kind.to_string(),
+        device_id: device_id.to_string(),
+        channel_id: None,
+        status: None,
+        address: None,
+        channel: None,
+        time: Local::now().format(DATE_TIME_FMT).to_string(),
+    }
+}
+
+/// 投递事件：未开启推送时直接忽略
+pub fn emit(event: WebhookEvent) {
+    let Some(tx) = WEBHOOK_TX.get() else {
+        return;
+    };
+    match tx.try_send(event) {
+        Ok(_) => {}
+        Err(TrySendError::Full(event)) => {
+            error!("webhook queue is full; event dropped: {event:?}");
+        }
+        Err(TrySendError::Closed(event)) => {
+            error!("webhook queue is closed; event dropped: {event:?}");
+        }
+    }
+}
+
+async fn run(
+    mut rx: Receiver<WebhookEvent>,
+    push_url: String,
+    max_attempts: u32,
+    cancel: CancellationToken,
+) {
+    loop {
+        select! {
+            item = rx.recv() => {
+                let Some(event) = item else {
+                    warn!("webhook worker exiting because event queue closed");
+                    break;
+                };
+                push_with_retry(&push_url, max_attempts, &event).await;
+            }
+            _ = cancel.cancelled() => {
+                warn!("webhook worker exiting after cancellation");
+                break;
+            },
+        }
+    }
+}
+
+async fn push_with_retry(push_url: &str, max_attempts: u32, event: &WebhookEvent) {
+    use crate::http::client::{HttpBiz, HttpClient};
+
+    for attempt in 1..=max_attempts {
+        let result = async {
+            let client = HttpClient::template(push_url)?;
+            client
+                .call_webhook(event)
+                .await
+                .hand_log(|msg| error!("{msg}"))?;
+            GlobalResult::<()>::Ok(())
+        }
+        .await;
+        match result {
+            Ok(()) => return,
+            Err(err) => warn!(
+                "push webhook failed: event={}, device_id={}, attempt={attempt}, err={err}",
+                event.event, event.device_id
+            ),
+        }
+        if attempt < max_attempts {
+            base::tokio::time::sleep(retry_delay(attempt)).await;
+        }
+    }
+    error!(
+        "push webhook gave up after {max_attempts} attempts: event={}, device_id={}, channel_id={:?}",
+        event.event, event.device_id, event.channel_id
+    );
+}
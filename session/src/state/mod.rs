@@ -20,6 +20,9 @@ pub struct AlarmConf {
     pub push_url: Option<String>,
     #[serde(default = "default_priority")]
     pub priority: u8,
+    /// 设备注册后是否下发报警订阅
+    #[serde(default)]
+    pub subscribe: bool,
 }
 serde_default!(default_priority, u8, 4);
 static ALARM_CONF: OnceLock<AlarmConf> = OnceLock::new();
@@ -55,6 +58,48 @@ impl CheckFromConf for AlarmConf {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(crate = "base::serde")]
+#[conf(prefix = "server.webhook", check)]
+pub struct WebhookConf {
+    pub enable: bool,
+    pub push_url: Option<String>,
+    /// 单个事件最多推送次数，含首次
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+}
+serde_default!(default_max_attempts, u32, 5);
+static WEBHOOK_CONF: OnceLock<WebhookConf> = OnceLock::new();
+
+impl WebhookConf {
+    pub fn get_webhook_conf() -> &'static Self {
+        WEBHOOK_CONF.get_or_init(|| WebhookConf::conf())
+    }
+}
+
+impl CheckFromConf for WebhookConf {
+    fn _field_check(&self) -> Result<(), FieldCheckError> {
+        if self.enable {
+            let Some(push_url) = self.push_url.as_ref().filter(|url| !url.is_empty()) else {
+                return Err(FieldCheckError::BizError(
+                    "server.webhook.push_url不能为空".to_string(),
+                ));
+            };
+            if Url::parse(push_url).is_err() {
+                return Err(FieldCheckError::BizError(
+                    "server.webhook.push_url非有效的url地址".to_string(),
+                ));
+            }
+        }
+        if self.max_attempts == 0 {
+            return Err(FieldCheckError::BizError(
+                "server.webhook.max_attempts必须大于0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(crate = "base::serde")]
 #[conf(prefix = "server.stream")]
@@ -1,13 +1,14 @@
 use base::serde::{Deserialize, Serialize};
 
+use crate::gb::sip::config::{ConfigType, DeviceConfigSet};
 use crate::gb::sip::xml::KV2Model;
 use base::constructor::New;
 use base::err::BaseErrorCode;
 use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
 use base::log::error;
 use base::serde_json;
-use shared::info::codec::Codec;
-use shared::info::filter::Filter;
+use shared::info::codec::Transcode;
+use shared::info::filter::{Filter, ImageFormat, Osd};
 use shared::info::output::{HttpFlvOutput, OutputEnum, OutputKind};
 
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
@@ -20,6 +21,46 @@ pub struct StreamQo {
     pub media_type: Option<OutputEnum>,
 }
 
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "base::serde")]
+pub struct PlaySnapshotModel {
+    /// 媒体流ID
+    pub stream_id: String,
+    /// 图片格式，默认jpeg
+    pub format: Option<ImageFormat>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "base::serde")]
+pub struct PlayOutputOpenModel {
+    /// 媒体流ID
+    pub stream_id: String,
+    /// 追加的输出，如本地录制、rtmp推流
+    pub output: OutputKind,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "base::serde")]
+pub struct PlayOutputCloseModel {
+    /// 媒体流ID
+    pub stream_id: String,
+    /// 移除的输出类型
+    pub output_enum: OutputEnum,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "base::serde")]
+pub struct PlayOsdModel {
+    /// 媒体流ID
+    pub stream_id: String,
+    /// 水印，为空时移除；需在开流时已开启视频滤镜
+    pub osd: Option<Osd>,
+}
+
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
 #[serde(crate = "base::serde")]
@@ -36,8 +77,8 @@ pub struct CustomMediaConfig {
     /// 媒体流输出信息
     pub output: OutputKind,
     /// 媒体流转码信息
-    pub codec: Option<Codec>,
-    /// 媒体流过滤信息
+    pub codec: Option<Transcode>,
+    /// 媒体流过滤信息：作用于该流全部输出，仅建流时生效
     pub filter: Filter,
 }
 
@@ -71,6 +112,8 @@ pub struct PlayBackModel {
     pub st: u32,
     /// 历史视频回放结束时间
     pub et: u32,
+    /// 下载文件格式：LocalMp4(默认)、LocalTs；仅下载接口使用，回放接口传入时拒绝
+    pub media_type: Option<OutputEnum>,
 }
 
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
@@ -118,6 +161,143 @@ pub struct PtzControlModel {
     pub zoomSpeed: u8,
 }
 
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+#[allow(non_snake_case)]
+pub struct PtzCmdModel {
+    /// 设备ID
+    pub deviceId: String,
+    /// 通道ID
+    pub channelId: String,
+    /// PTZ 指令
+    pub cmd: PtzCmd,
+}
+
+/// GB/T 28181 附录 A 前端设备控制指令
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "base::serde")]
+#[allow(non_snake_case)]
+pub enum PtzCmd {
+    /// 停止全部动作
+    Stop,
+    /// 设置预置位：1-255
+    PresetSet { preset: u8 },
+    /// 调用预置位：1-255
+    PresetCall { preset: u8 },
+    /// 删除预置位：1-255
+    PresetDelete { preset: u8 },
+    /// 加入巡航点
+    CruiseAdd { group: u8, preset: u8 },
+    /// 删除巡航点，preset 为 0 时删除整条巡航
+    CruiseRemove { group: u8, preset: u8 },
+    /// 设置巡航速度：1-4095
+    CruiseSpeed { group: u8, speed: u16 },
+    /// 设置巡航停留时间(秒)：1-4095
+    CruiseDwell { group: u8, seconds: u16 },
+    /// 开始巡航
+    CruiseStart { group: u8 },
+    /// 开始自动扫描
+    ScanStart { group: u8 },
+    /// 设置自动扫描左边界
+    ScanLeftBound { group: u8 },
+    /// 设置自动扫描右边界
+    ScanRightBound { group: u8 },
+    /// 设置自动扫描速度：1-4095
+    ScanSpeed { group: u8, speed: u16 },
+    /// 聚焦/光圈控制，focus 0:停止 1:近 2:远；iris 0:停止 1:缩小 2:放大
+    Fi {
+        focus: u8,
+        iris: u8,
+        focusSpeed: u8,
+        irisSpeed: u8,
+    },
+    /// 辅助开关开，1:雨刷
+    AuxOn { aux: u8 },
+    /// 辅助开关关
+    AuxOff { aux: u8 },
+}
+
+impl PtzCmd {
+    /// 持续动作类指令，需要随后下发停止
+    pub fn is_motion(&self) -> bool {
+        matches!(self, PtzCmd::Fi { focus, iris, .. } if *focus != 0 || *iris != 0)
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceControlModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 通道ID：默认设备ID
+    pub channel_id: Option<String>,
+    /// 控制命令
+    pub cmd: DeviceControlCmd,
+}
+
+/// DeviceControl 中 PTZ 以外的控制命令
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(crate = "base::serde")]
+pub enum DeviceControlCmd {
+    /// 远程启动
+    TeleBoot,
+    /// 开始手动录像
+    RecordStart,
+    /// 停止手动录像
+    RecordStop,
+    /// 布防
+    SetGuard,
+    /// 撤防
+    ResetGuard,
+    /// 报警复位：报警方式与类型为空时复位全部报警
+    ResetAlarm {
+        alarm_method: Option<u8>,
+        alarm_type: Option<u8>,
+    },
+    /// 强制关键帧
+    IFrame,
+    /// 看守位：开启时需指定自动归位时间(秒)与预置位编号
+    HomePosition {
+        enabled: bool,
+        reset_time: Option<u32>,
+        preset_index: Option<u8>,
+    },
+    /// 拉框放大
+    DragZoomIn(DragZoom),
+    /// 拉框缩小
+    DragZoomOut(DragZoom),
+}
+
+/// 拉框区域：播放窗口长宽与框中心、框长宽，单位像素
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "base::serde")]
+pub struct DragZoom {
+    pub length: u32,
+    pub width: u32,
+    pub mid_point_x: u32,
+    pub mid_point_y: u32,
+    pub length_x: u32,
+    pub length_y: u32,
+}
+
+impl DeviceControlCmd {
+    /// 远程启动、强制关键帧与拉框设备不回 Response
+    pub fn expects_response(&self) -> bool {
+        !matches!(
+            self,
+            DeviceControlCmd::TeleBoot
+                | DeviceControlCmd::IFrame
+                | DeviceControlCmd::DragZoomIn(_)
+                | DeviceControlCmd::DragZoomOut(_)
+        )
+    }
+}
+
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "base::serde")]
@@ -163,12 +343,20 @@ impl StreamInfo {
 #[serde(crate = "base::serde")]
 #[allow(non_snake_case)]
 pub struct AlarmInfo {
+    /// 报警记录ID：入库后回填，用于确认报警
+    pub alarmId: Option<i64>,
     pub priority: u8,
     pub method: u8,
     pub alarmType: u8,
     pub timeStr: String,
     pub deviceId: String,
     pub channelId: String,
+    /// 报警描述
+    pub description: Option<String>,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    /// 报警类型扩展参数：入侵检测时 1=进入区域，2=离开区域
+    pub eventType: Option<u8>,
 }
 
 impl KV2Model for AlarmInfo {
@@ -192,6 +380,18 @@ impl KV2Model for AlarmInfo {
                 NOTIFY_INFO_ALARM_TYPE => {
                     model.alarmType = v.parse::<u8>().hand_log(|msg| error!("{msg}"))?;
                 }
+                NOTIFY_ALARM_DESCRIPTION => {
+                    model.description = Some(v).filter(|v| !v.is_empty());
+                }
+                NOTIFY_LONGITUDE => {
+                    model.longitude = v.parse::<f64>().ok();
+                }
+                NOTIFY_LATITUDE => {
+                    model.latitude = v.parse::<f64>().ok();
+                }
+                NOTIFY_INFO_EVENT_TYPE => {
+                    model.eventType = v.parse::<u8>().ok();
+                }
                 &_ => {}
             }
         }
@@ -199,6 +399,326 @@ impl KV2Model for AlarmInfo {
     }
 }
 
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct RecordQueryModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 通道ID：默认设备ID
+    pub channel_id: Option<String>,
+    /// 查询开始时间：秒级时间戳
+    pub st: u32,
+    /// 查询结束时间：秒级时间戳
+    pub et: u32,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct RecordInfoItem {
+    /// 通道ID
+    pub device_id: String,
+    pub name: Option<String>,
+    /// 文件路径
+    pub file_path: Option<String>,
+    pub address: Option<String>,
+    /// 录像开始时间：2026-06-13T00:00:00
+    pub start_time: String,
+    /// 录像结束时间
+    pub end_time: String,
+    /// 保密属性：0不涉密，1涉密
+    pub secrecy: u8,
+    /// 录像类型：time/alarm/manual/all
+    pub record_type: Option<String>,
+    /// 录像触发者ID
+    pub recorder_id: Option<String>,
+    /// 文件大小：字节
+    pub file_size: Option<u64>,
+}
+
+impl RecordInfoItem {
+    /// 解析单条 RecordInfo 应答：返回 (SumNum, 本条消息携带的录像)
+    pub fn from_response_items(arr: &[(String, String)]) -> (Option<usize>, Vec<Self>) {
+        use crate::gb::sip::xml::*;
+        let sum_num = value(arr, RESPONSE_SUM_NUM).and_then(|v| v.trim().parse::<usize>().ok());
+        let mut records = Vec::new();
+        let mut record = RecordInfoItem::default();
+        let mut fields = Vec::<&str>::new();
+        for (k, v) in arr {
+            let key = match &k[..] {
+                RESPONSE_RECORD_LIST_ITEM_DEVICE_ID
+                | RESPONSE_RECORD_LIST_ITEM_NAME
+                | RESPONSE_RECORD_LIST_ITEM_FILE_PATH
+                | RESPONSE_RECORD_LIST_ITEM_ADDRESS
+                | RESPONSE_RECORD_LIST_ITEM_START_TIME
+                | RESPONSE_RECORD_LIST_ITEM_END_TIME
+                | RESPONSE_RECORD_LIST_ITEM_SECRECY
+                | RESPONSE_RECORD_LIST_ITEM_TYPE
+                | RESPONSE_RECORD_LIST_ITEM_RECORDER_ID
+                | RESPONSE_RECORD_LIST_ITEM_FILE_SIZE => k.as_str(),
+                _ => continue,
+            };
+            // 同层 Item 之间无分隔标记：字段重复出现即为下一条录像
+            if fields.contains(&key) {
+                records.push(std::mem::take(&mut record));
+                fields.clear();
+            }
+            fields.push(key);
+            match key {
+                RESPONSE_RECORD_LIST_ITEM_DEVICE_ID => record.device_id = v.to_string(),
+                RESPONSE_RECORD_LIST_ITEM_NAME => record.name = Some(v.to_string()),
+                RESPONSE_RECORD_LIST_ITEM_FILE_PATH => record.file_path = Some(v.to_string()),
+                RESPONSE_RECORD_LIST_ITEM_ADDRESS => record.address = Some(v.to_string()),
+                RESPONSE_RECORD_LIST_ITEM_START_TIME => record.start_time = v.to_string(),
+                RESPONSE_RECORD_LIST_ITEM_END_TIME => record.end_time = v.to_string(),
+                RESPONSE_RECORD_LIST_ITEM_SECRECY => {
+                    record.secrecy = v.trim().parse::<u8>().unwrap_or_default()
+                }
+                RESPONSE_RECORD_LIST_ITEM_TYPE => record.record_type = Some(v.to_string()),
+                RESPONSE_RECORD_LIST_ITEM_RECORDER_ID => record.recorder_id = Some(v.to_string()),
+                _ => record.file_size = v.trim().parse::<u64>().ok(),
+            }
+        }
+        if !fields.is_empty() {
+            records.push(record);
+        }
+        (sum_num, records)
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct PresetQueryModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 通道ID：默认设备ID
+    pub channel_id: Option<String>,
+    /// 忽略缓存重新向设备查询
+    pub refresh: Option<bool>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct PresetItem {
+    /// 预置位编号：1-255
+    pub preset_id: String,
+    /// 预置位名称
+    pub preset_name: Option<String>,
+}
+
+impl PresetItem {
+    /// 解析单条 PresetQuery 应答：返回 (SumNum, 本条消息携带的预置位)
+    pub fn from_response_items(arr: &[(String, String)]) -> (Option<usize>, Vec<Self>) {
+        use crate::gb::sip::xml::*;
+        let sum_num = value(arr, RESPONSE_SUM_NUM).and_then(|v| v.trim().parse::<usize>().ok());
+        let mut presets = Vec::new();
+        let mut preset = PresetItem::default();
+        let mut fields = Vec::<&str>::new();
+        for (k, v) in arr {
+            let key = match &k[..] {
+                RESPONSE_PRESET_LIST_ITEM_PRESET_ID | RESPONSE_PRESET_LIST_ITEM_PRESET_NAME => {
+                    k.as_str()
+                }
+                _ => continue,
+            };
+            if fields.contains(&key) {
+                presets.push(std::mem::take(&mut preset));
+                fields.clear();
+            }
+            fields.push(key);
+            match key {
+                RESPONSE_PRESET_LIST_ITEM_PRESET_ID => preset.preset_id = v.trim().to_string(),
+                _ => preset.preset_name = Some(v.to_string()),
+            }
+        }
+        if !fields.is_empty() {
+            presets.push(preset);
+        }
+        (sum_num, presets)
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceTargetModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 通道ID：默认设备ID
+    pub channel_id: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct DeviceStatusInfo {
+    /// 查询目标ID
+    pub device_id: String,
+    /// 查询结果：OK/ERROR
+    pub result: Option<String>,
+    /// 是否在线
+    pub online: Option<bool>,
+    /// 是否正常工作：OK/ERROR
+    pub status: Option<String>,
+    /// 不正常工作原因
+    pub reason: Option<String>,
+    /// 是否编码
+    pub encode: Option<bool>,
+    /// 是否录像
+    pub record: Option<bool>,
+    /// 设备时间：2026-06-13T00:00:00
+    pub device_time: Option<String>,
+    /// 报警设备状态列表
+    pub alarm_status: Vec<AlarmStatusItem>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct AlarmStatusItem {
+    /// 报警设备ID
+    pub device_id: String,
+    /// 布防状态：ONDUTY/OFFDUTY/ALARM
+    pub duty_status: String,
+}
+
+impl DeviceStatusInfo {
+    pub fn from_response_items(arr: &[(String, String)]) -> Self {
+        let switch = |v: &str| v.trim().eq_ignore_ascii_case("ON");
+        let mut info = DeviceStatusInfo::default();
+        let mut alarm = AlarmStatusItem::default();
+        for (k, v) in arr {
+            // 标准写作 Alarmstatus，兼容设备大小写差异
+            let alarm_field = k
+                .split_once(',')
+                .and_then(|(_, rest)| rest.split_once(','))
+                .filter(|(list, _)| list.eq_ignore_ascii_case("Alarmstatus"))
+                .map(|(_, field)| field);
+            match (alarm_field, &k[..]) {
+                (Some("Item,DeviceID"), _) => {
+                    if !alarm.device_id.is_empty() {
+                        info.alarm_status.push(std::mem::take(&mut alarm));
+                    }
+                    alarm.device_id = v.to_string();
+                }
+                (Some("Item,DutyStatus"), _) => alarm.duty_status = v.to_string(),
+                (Some(_), _) => {}
+                (None, "Response,DeviceID") => info.device_id = v.to_string(),
+                (None, "Response,Result") => info.result = Some(v.to_string()),
+                (None, "Response,Online") => {
+                    info.online = Some(v.trim().eq_ignore_ascii_case("ONLINE"))
+                }
+                (None, "Response,Status") => info.status = Some(v.to_string()),
+                (None, "Response,Reason") => info.reason = Some(v.to_string()),
+                (None, "Response,Encode") => info.encode = Some(switch(v)),
+                (None, "Response,Record") => info.record = Some(switch(v)),
+                (None, "Response,DeviceTime") => info.device_time = Some(v.to_string()),
+                _ => {}
+            }
+        }
+        if !alarm.device_id.is_empty() {
+            info.alarm_status.push(alarm);
+        }
+        info
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct PtzPositionInfo {
+    /// 查询目标ID
+    pub device_id: String,
+    /// 水平方位角：0-360
+    pub pan: Option<f64>,
+    /// 俯仰角：-30-90
+    pub tilt: Option<f64>,
+    /// 变倍倍数
+    pub zoom: Option<f64>,
+    /// 水平视场角
+    pub horizontal_field_angle: Option<f64>,
+    /// 垂直视场角
+    pub vertical_field_angle: Option<f64>,
+    /// 最大可视距离：米
+    pub max_view_distance: Option<f64>,
+}
+
+impl PtzPositionInfo {
+    pub fn from_response_items(arr: &[(String, String)]) -> Self {
+        use crate::gb::sip::xml::value_by_tag;
+        let number = |tag: &str| value_by_tag(arr, tag).and_then(|v| v.trim().parse::<f64>().ok());
+        PtzPositionInfo {
+            device_id: value_by_tag(arr, "DeviceID")
+                .unwrap_or_default()
+                .to_string(),
+            pan: number("Pan"),
+            tilt: number("Tilt"),
+            zoom: number("Zoom"),
+            horizontal_field_angle: number("HorizontalFieldAngle"),
+            vertical_field_angle: number("VerticalFieldAngle"),
+            max_view_distance: number("MaxViewDistance"),
+        }
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct ConfigDownloadModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 通道ID：默认设备ID
+    pub channel_id: Option<String>,
+    /// 查询的配置类型
+    pub config_types: Vec<ConfigType>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceConfigModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 通道ID：默认设备ID
+    pub channel_id: Option<String>,
+    /// 待下发配置：仅下发非空配置段
+    pub config: DeviceConfigSet,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct DeviceConfigInfo {
+    /// 应答目标ID
+    pub device_id: String,
+    /// 执行结果：OK/ERROR
+    pub result: Option<String>,
+    /// 设备返回的配置
+    pub config: DeviceConfigSet,
+}
+
+impl DeviceConfigInfo {
+    pub fn from_response_items(arr: &[(String, String)]) -> Self {
+        use crate::gb::sip::xml::value;
+        DeviceConfigInfo {
+            device_id: value(arr, "Response,DeviceID")
+                .unwrap_or_default()
+                .to_string(),
+            result: value(arr, "Response,Result").map(str::to_string),
+            config: DeviceConfigSet::from_response_items(arr),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.result
+            .as_deref()
+            .is_some_and(|result| result.eq_ignore_ascii_case("OK"))
+    }
+}
+
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(New, Serialize, Deserialize, Debug)]
 #[serde(crate = "base::serde")]
@@ -216,6 +736,391 @@ pub struct SnapshotImage {
     pub count: Option<u8>,
 }
 
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct PageData<T> {
+    /// 总条数
+    pub total: i64,
+    /// 当前页数据
+    pub list: Vec<T>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(crate = "base::serde")]
+pub struct DeviceQueryModel {
+    /// 页码：从1开始，默认1
+    pub page: Option<u32>,
+    /// 每页条数：默认20，最大200
+    pub size: Option<u32>,
+    /// 在线状态：true在线，false离线，不传则不过滤
+    pub online: Option<bool>,
+    /// 行政区划编码：按设备编码前缀匹配
+    pub civil_code: Option<String>,
+    /// 设备名称(别名)：模糊匹配
+    pub name: Option<String>,
+    /// 设备厂商：模糊匹配
+    pub manufacturer: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceItem {
+    /// 设备ID
+    pub device_id: String,
+    /// 所属域ID
+    pub domain_id: String,
+    /// 设备名称(别名)
+    pub name: Option<String>,
+    /// 信令传输协议：UDP/TCP
+    pub transport: String,
+    /// 最近注册时间
+    pub register_time: String,
+    /// 在线有效期截止时间
+    pub online_expire_time: Option<String>,
+    /// 设备信令地址
+    pub local_addr: String,
+    pub contact_uri: String,
+    /// GB28181 版本
+    pub gb_version: Option<String>,
+    pub device_type: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub max_camera: Option<u8>,
+    /// 通道数量
+    pub channel_count: i64,
+    /// 实时在线状态：信令会话是否存在
+    pub online: bool,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(crate = "base::serde")]
+pub struct ChannelQueryModel {
+    /// 页码：从1开始，默认1
+    pub page: Option<u32>,
+    /// 每页条数：默认20，最大200
+    pub size: Option<u32>,
+    /// 所属设备ID
+    pub device_id: Option<String>,
+    /// 父节点ID：设备、业务分组或虚拟组织
+    pub parent_id: Option<String>,
+    /// 在线状态：true在线，false离线，不传则不过滤
+    pub online: Option<bool>,
+    /// 行政区划编码：前缀匹配
+    pub civil_code: Option<String>,
+    /// 通道名称：模糊匹配
+    pub name: Option<String>,
+    /// 通道厂商：模糊匹配
+    pub manufacturer: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "base::serde")]
+pub struct ChannelItem {
+    /// 所属设备ID
+    pub device_id: String,
+    /// 通道ID
+    pub channel_id: String,
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub owner: Option<String>,
+    /// 设备上报的通道状态：ON/OFF/VLOST/DEFECT
+    pub status: String,
+    pub civil_code: Option<String>,
+    pub address: Option<String>,
+    /// 是否有子设备：1有，0没有
+    pub parental: Option<u8>,
+    pub block: Option<String>,
+    pub parent_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub port: Option<u16>,
+    pub longitude: Option<f32>,
+    pub latitude: Option<f32>,
+    /// 摄像机类型：1球机，2半球，3固定枪机，4遥控枪机
+    pub ptz_type: Option<u8>,
+    /// 实时在线状态：所属设备在线且通道上报ON
+    pub online: bool,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct ChannelTreeNode {
+    pub channel: ChannelItem,
+    /// 以 parent_id 挂载的下级节点
+    #[cfg_attr(debug_assertions, schema(no_recursion))]
+    pub children: Vec<ChannelTreeNode>,
+}
+
+/// 推送给业务系统的变更事件
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(crate = "base::serde")]
+pub struct WebhookEvent {
+    /// 事件类型：channel_added/channel_updated/channel_removed/channel_status_changed，
+    /// 设备生命周期事件以 device_ 开头
+    pub event: String,
+    pub device_id: String,
+    pub channel_id: Option<String>,
+    /// 通道或设备状态：ON/OFF/VLOST/DEFECT
+    pub status: Option<String>,
+    /// 设备信令地址：设备生命周期事件携带
+    pub address: Option<String>,
+    /// 新增或更新通道时携带的通道信息
+    pub channel: Option<ChannelItem>,
+    /// 事件时间：yyyy-MM-dd HH:mm:ss
+    pub time: String,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceTimelineModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 开始时间：yyyy-MM-dd HH:mm:ss，默认结束时间前24小时
+    pub start_time: Option<String>,
+    /// 结束时间：yyyy-MM-dd HH:mm:ss，默认当前时间
+    pub end_time: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct DeviceUptimeSegment {
+    pub online: bool,
+    pub start_time: String,
+    pub end_time: String,
+    /// 持续秒数
+    pub seconds: i64,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceLifecycleItem {
+    /// 生命周期事件：device_registered/device_reregistered/device_reconnected/device_disconnected/
+    /// device_keepalive_timeout/device_registration_expired/device_reconnect_timeout/device_unregistered/
+    /// device_disabled
+    pub event: String,
+    /// 事件后设备是否在线
+    pub online: bool,
+    pub address: Option<String>,
+    pub time: String,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceTimelineInfo {
+    pub device_id: String,
+    pub start_time: String,
+    pub end_time: String,
+    /// 时间范围内在线总秒数
+    pub online_seconds: i64,
+    /// 时间范围内离线总秒数，含无记录的时段
+    pub offline_seconds: i64,
+    /// 按时间升序的在线/离线区间
+    pub segments: Vec<DeviceUptimeSegment>,
+    /// 时间范围内的生命周期事件
+    pub events: Vec<DeviceLifecycleItem>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(crate = "base::serde")]
+pub struct DeviceOauthQueryModel {
+    /// 页码：从1开始，默认1
+    pub page: Option<u32>,
+    /// 每页条数：默认20，最大200
+    pub size: Option<u32>,
+    /// 设备ID：按前缀匹配
+    pub device_id: Option<String>,
+    /// 启用状态：true启用，false停用，不传则不过滤
+    pub enable: Option<bool>,
+    /// 设备别名：模糊匹配
+    pub alias: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceOauthItem {
+    pub device_id: String,
+    pub domain_id: String,
+    pub domain: String,
+    /// 是否校验注册密码
+    pub pwd_check: bool,
+    /// 是否已设置密码，密码本身不返回
+    pub has_pwd: bool,
+    pub alias: Option<String>,
+    pub enable: bool,
+    /// 心跳周期：秒
+    pub heartbeat_sec: u8,
+    /// 当前是否持有注册会话
+    pub online: bool,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceOauthCreateModel {
+    /// 设备ID：20位国标编码
+    pub device_id: String,
+    /// 所属域ID：默认本信令服务域ID
+    pub domain_id: Option<String>,
+    /// 认证域：默认本信令服务域
+    pub domain: Option<String>,
+    /// 注册密码
+    pub pwd: Option<String>,
+    /// 是否校验注册密码：默认设置了密码即校验
+    pub pwd_check: Option<bool>,
+    pub alias: Option<String>,
+    /// 是否启用：默认启用
+    pub enable: Option<bool>,
+    /// 心跳周期：秒，默认60
+    pub heartbeat_sec: Option<u8>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceOauthStatusModel {
+    pub device_id: String,
+    /// 停用时立即踢下线在线会话
+    pub enable: bool,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceOauthPwdModel {
+    pub device_id: String,
+    /// 新密码：不传则随机生成
+    pub pwd: Option<String>,
+    /// 是否校验注册密码：不传则保持不变
+    pub pwd_check: Option<bool>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct DeviceOauthHeartbeatModel {
+    pub device_id: String,
+    /// 心跳周期：秒，设备下次注册后生效
+    pub heartbeat_sec: u8,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct PositionSubscribeModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 上报间隔：单位秒，默认5
+    pub interval: Option<u32>,
+    /// 订阅有效期：单位秒，默认3600，到期前自动刷新
+    pub expires: Option<u32>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct PositionTrackModel {
+    /// 设备ID
+    pub device_id: String,
+    /// 通道ID：不传则返回设备下全部通道的轨迹点
+    pub channel_id: Option<String>,
+    /// 开始时间：yyyy-MM-dd HH:mm:ss
+    pub start_time: String,
+    /// 结束时间：yyyy-MM-dd HH:mm:ss
+    pub end_time: String,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct MobilePositionItem {
+    pub device_id: String,
+    pub channel_id: String,
+    /// 定位时间
+    pub time: String,
+    pub longitude: f64,
+    pub latitude: f64,
+    /// 速度：km/h
+    pub speed: Option<f64>,
+    /// 方向：正北顺时针夹角
+    pub direction: Option<f64>,
+    /// 海拔高度：m
+    pub altitude: Option<f64>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(crate = "base::serde")]
+pub struct AlarmQueryModel {
+    /// 页码：从1开始，默认1
+    pub page: Option<u32>,
+    /// 每页条数：默认20，最大200
+    pub size: Option<u32>,
+    pub device_id: Option<String>,
+    /// 报警源通道ID
+    pub channel_id: Option<String>,
+    /// 报警级别：1=一级警情 ~ 4=四级警情
+    pub priority: Option<u8>,
+    /// 报警方式：1=电话，2=设备，3=短信，4=GPS，5=视频，6=设备故障，7=其他
+    pub method: Option<u8>,
+    /// 报警类型
+    pub alarm_type: Option<u8>,
+    /// 报警时间下限：yyyy-MM-dd HH:mm:ss
+    pub start_time: Option<String>,
+    /// 报警时间上限：yyyy-MM-dd HH:mm:ss
+    pub end_time: Option<String>,
+    /// 确认状态：true已确认，false未确认，不传则不过滤
+    pub ack: Option<bool>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct AlarmItem {
+    pub id: i64,
+    pub device_id: String,
+    pub channel_id: String,
+    pub priority: u8,
+    pub method: u8,
+    /// 设备上报的报警时间
+    pub alarm_time: Option<String>,
+    pub alarm_type: Option<u8>,
+    /// 报警类型扩展参数
+    pub event_type: Option<u8>,
+    pub description: Option<String>,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    pub ack: bool,
+    pub ack_time: Option<String>,
+    pub ack_user: Option<String>,
+    /// 推送状态：0=待推送，1=已推送，2=推送失败，3=无需推送
+    pub push_state: u8,
+    /// 平台接收时间
+    pub create_time: String,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "base::serde")]
+pub struct AlarmAckModel {
+    /// 待确认的报警记录ID
+    pub ids: Vec<i64>,
+    /// 确认人
+    pub user: Option<String>,
+}
+
 #[test]
 fn test1() {
     let a = PlayLiveModel {
@@ -233,3 +1138,90 @@ fn test1() {
     let json = serde_json::to_string(&a).unwrap();
     println!("{}", json);
 }
+
+#[test]
+fn record_info_items_split_on_repeated_fields() {
+    let xml = "<?xml version=\"1.0\"?><Response><CmdType>RecordInfo</CmdType><SN>7</SN>\
+        <DeviceID>34020000001320000001</DeviceID><SumNum>2</SumNum><RecordList Num=\"2\">\
+        <Item><DeviceID>34020000001320000001</DeviceID><FilePath>/a.ps</FilePath>\
+        <StartTime>2026-06-13T00:00:00</StartTime><EndTime>2026-06-13T00:30:00</EndTime>\
+        <Secrecy>1</Secrecy><Type>alarm</Type><RecorderID>34020000001320000009</RecorderID></Item>\
+        <Item><DeviceID>34020000001320000001</DeviceID><FilePath>/b.ps</FilePath>\
+        <StartTime>2026-06-13T00:30:00</StartTime><EndTime>2026-06-13T01:00:00</EndTime>\
+        <Secrecy>0</Secrecy><Type>time</Type></Item></RecordList></Response>";
+    let items = crate::gb::sip::xml::parse_items(xml.as_bytes()).unwrap();
+    let (sum_num, records) = RecordInfoItem::from_response_items(&items);
+    assert_eq!(sum_num, Some(2));
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].file_path.as_deref(), Some("/a.ps"));
+    assert_eq!(records[0].secrecy, 1);
+    assert_eq!(
+        records[0].recorder_id.as_deref(),
+        Some("34020000001320000009")
+    );
+    assert_eq!(records[1].record_type.as_deref(), Some("time"));
+    assert_eq!(records[1].recorder_id, None);
+}
+
+#[test]
+fn preset_items_split_on_repeated_fields() {
+    let xml = "<?xml version=\"1.0\"?><Response><CmdType>PresetQuery</CmdType><SN>9</SN>\
+        <DeviceID>34020000001320000001</DeviceID><SumNum>3</SumNum><PresetList Num=\"2\">\
+        <Item><PresetID>1</PresetID><PresetName>gate</PresetName></Item>\
+        <Item><PresetID>2</PresetID></Item></PresetList></Response>";
+    let items = crate::gb::sip::xml::parse_items(xml.as_bytes()).unwrap();
+    let (sum_num, presets) = PresetItem::from_response_items(&items);
+    assert_eq!(sum_num, Some(3));
+    assert_eq!(presets.len(), 2);
+    assert_eq!(presets[0].preset_id, "1");
+    assert_eq!(presets[0].preset_name.as_deref(), Some("gate"));
+    assert_eq!(presets[1].preset_id, "2");
+    assert_eq!(presets[1].preset_name, None);
+}
+
+#[test]
+fn device_status_and_ptz_position_from_response() {
+    let xml = "<?xml version=\"1.0\"?><Response><CmdType>DeviceStatus</CmdType><SN>5</SN>\
+        <DeviceID>34020000001320000001</DeviceID><Result>OK</Result><Online>ONLINE</Online>\
+        <Status>OK</Status><Encode>ON</Encode><Record>OFF</Record>\
+        <DeviceTime>2026-06-13T00:00:00</DeviceTime><Alarmstatus Num=\"2\">\
+        <Item><DeviceID>34020000001340000001</DeviceID><DutyStatus>ONDUTY</DutyStatus></Item>\
+        <Item><DeviceID>34020000001340000002</DeviceID><DutyStatus>ALARM</DutyStatus></Item>\
+        </Alarmstatus></Response>";
+    let items = crate::gb::sip::xml::parse_items(xml.as_bytes()).unwrap();
+    let status = DeviceStatusInfo::from_response_items(&items);
+    assert_eq!(status.device_id, "34020000001320000001");
+    assert_eq!(status.online, Some(true));
+    assert_eq!(status.encode, Some(true));
+    assert_eq!(status.record, Some(false));
+    assert_eq!(status.alarm_status.len(), 2);
+    assert_eq!(status.alarm_status[1].duty_status, "ALARM");
+
+    let xml = "<?xml version=\"1.0\"?><Response><CmdType>PTZPosition</CmdType><SN>6</SN>\
+        <DeviceID>34020000001320000001</DeviceID><Info><Pan>120.5</Pan><Tilt>-10</Tilt>\
+        <Zoom>4</Zoom><HorizontalFieldAngle>58.2</HorizontalFieldAngle></Info></Response>";
+    let items = crate::gb::sip::xml::parse_items(xml.as_bytes()).unwrap();
+    let position = PtzPositionInfo::from_response_items(&items);
+    assert_eq!(position.pan, Some(120.5));
+    assert_eq!(position.tilt, Some(-10.0));
+    assert_eq!(position.horizontal_field_angle, Some(58.2));
+    assert_eq!(position.max_view_distance, None);
+}
+
+#[test]
+fn alarm_info_reads_extended_notify_fields() {
+    let xml = "<?xml version=\"1.0\"?><Notify><CmdType>Alarm</CmdType><SN>9</SN>\
+        <DeviceID>34020000001340000001</DeviceID><AlarmPriority>2</AlarmPriority>\
+        <AlarmMethod>5</AlarmMethod><AlarmTime>2026-06-13T08:00:00</AlarmTime>\
+        <AlarmDescription>区域入侵</AlarmDescription><Longitude>116.397</Longitude>\
+        <Latitude>39.908</Latitude><Info><AlarmType>6</AlarmType>\
+        <AlarmTypeParam><EventType>1</EventType></AlarmTypeParam></Info></Notify>";
+    let items = crate::gb::sip::xml::parse_items(xml.as_bytes()).unwrap();
+    let alarm = AlarmInfo::kv_to_model(items).unwrap();
+    assert_eq!(alarm.channelId, "34020000001340000001");
+    assert_eq!((alarm.priority, alarm.method, alarm.alarmType), (2, 5, 6));
+    assert_eq!(alarm.description.as_deref(), Some("区域入侵"));
+    assert_eq!(alarm.longitude, Some(116.397));
+    assert_eq!(alarm.latitude, Some(39.908));
+    assert_eq!(alarm.eventType, Some(1));
+}
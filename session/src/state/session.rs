@@ -18,11 +18,12 @@ use base::tokio::sync::mpsc::Sender;
 use base::tokio::time::Instant;
 use shared::info::media_info::MediaConfig;
 use shared::info::obj::BaseStreamInfo;
+use shared::info::output::OutputEnum;
 
 static GENERAL_CACHE: Lazy<Cache> = Lazy::new(Cache::init);
 static STREAM_CLOSE_GENERATION: AtomicU64 = AtomicU64::new(1);
 static TALK_CLOSE_GENERATION: AtomicU64 = AtomicU64::new(1);
-static CATALOG_SUBSCRIPTION_GENERATION: AtomicU64 = AtomicU64::new(1);
+static SUBSCRIPTION_GENERATION: AtomicU64 = AtomicU64::new(1);
 
 pub struct Cache {
     shared: Arc<Shared>,
@@ -53,8 +54,32 @@ pub struct StreamCloseInfo {
     pub last_error: Option<String>,
 }
 
+/// 设备事件订阅类型：每个设备每种类型仅保持一个订阅对话
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    Catalog,
+    Alarm,
+    MobilePosition,
+}
+
+impl SubscriptionKind {
+    pub const ALL: [SubscriptionKind; 3] = [
+        SubscriptionKind::Catalog,
+        SubscriptionKind::Alarm,
+        SubscriptionKind::MobilePosition,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionKind::Catalog => "catalog",
+            SubscriptionKind::Alarm => "alarm",
+            SubscriptionKind::MobilePosition => "mobile position",
+        }
+    }
+}
+
 #[derive(Clone)]
-pub struct CatalogSubscriptionCommand {
+pub struct SubscriptionCommand {
     pub generation: u64,
     pub call_id: String,
     pub seq: u32,
@@ -66,7 +91,7 @@ pub struct CatalogSubscriptionCommand {
     pub to_header: String,
 }
 
-struct CatalogSubscriptionState {
+struct SubscriptionState {
     generation: u64,
     call_id: String,
     seq: u32,
@@ -230,6 +255,7 @@ impl Cache {
                     am,
                     ssrc,
                     restored,
+                    download_output: None,
                     lifecycle: StreamLifecycle::Playing,
                 });
                 true
@@ -253,6 +279,24 @@ impl Cache {
             .map(|stream| stream.call_id.clone())
     }
 
+    pub fn stream_map_set_download_output(stream_id: &str, output: OutputEnum) -> bool {
+        match GENERAL_CACHE.shared.stream_map.get_mut(stream_id) {
+            Some(mut stream) => {
+                stream.download_output = Some(output);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn stream_download_output(stream_id: &str) -> Option<OutputEnum> {
+        GENERAL_CACHE
+            .shared
+            .stream_map
+            .get(stream_id)
+            .and_then(|stream| stream.download_output)
+    }
+
     pub fn stream_is_restored(stream_id: &str) -> bool {
         GENERAL_CACHE
             .shared
@@ -787,8 +831,9 @@ impl Cache {
     }
 
     #[allow(clippy::too_many_arguments)]
-    pub fn catalog_subscription_begin(
+    pub fn subscription_begin(
         device_id: String,
+        kind: SubscriptionKind,
         call_id: String,
         seq: u32,
         event: String,
@@ -798,11 +843,11 @@ impl Cache {
         to_header: String,
         local_tag: String,
     ) -> Option<u64> {
-        match GENERAL_CACHE.shared.catalog_subscriptions.entry(device_id) {
+        match GENERAL_CACHE.shared.subscriptions.entry((device_id, kind)) {
             Entry::Occupied(_) => None,
             Entry::Vacant(entry) => {
-                let generation = CATALOG_SUBSCRIPTION_GENERATION.fetch_add(1, Ordering::Relaxed);
-                entry.insert(CatalogSubscriptionState {
+                let generation = SUBSCRIPTION_GENERATION.fetch_add(1, Ordering::Relaxed);
+                entry.insert(SubscriptionState {
                     generation,
                     call_id,
                     seq,
@@ -822,8 +867,9 @@ impl Cache {
     }
 
     #[allow(clippy::too_many_arguments)]
-    pub fn catalog_subscription_complete(
+    pub fn subscription_complete(
         device_id: &str,
+        kind: SubscriptionKind,
         generation: u64,
         remote_target: String,
         route_set: Vec<String>,
@@ -833,8 +879,8 @@ impl Cache {
     ) -> bool {
         GENERAL_CACHE
             .shared
-            .catalog_subscriptions
-            .get_mut(device_id)
+            .subscriptions
+            .get_mut(&(device_id.to_string(), kind))
             .is_some_and(|mut subscription| {
                 if subscription.generation != generation {
                     return false;
@@ -849,20 +895,21 @@ impl Cache {
             })
     }
 
-    pub fn catalog_subscription_take_refresh(
+    pub fn subscription_take_refresh(
         device_id: &str,
+        kind: SubscriptionKind,
         generation: u64,
-    ) -> Option<CatalogSubscriptionCommand> {
+    ) -> Option<SubscriptionCommand> {
         let mut subscription = GENERAL_CACHE
             .shared
-            .catalog_subscriptions
-            .get_mut(device_id)?;
+            .subscriptions
+            .get_mut(&(device_id.to_string(), kind))?;
         if subscription.generation != generation || subscription.inflight {
             return None;
         }
         subscription.seq = subscription.seq.saturating_add(1);
         subscription.inflight = true;
-        Some(CatalogSubscriptionCommand {
+        Some(SubscriptionCommand {
             generation,
             call_id: subscription.call_id.clone(),
             seq: subscription.seq,
@@ -875,11 +922,15 @@ impl Cache {
         })
     }
 
-    pub fn catalog_subscription_mark_failed(device_id: &str, generation: u64) -> bool {
+    pub fn subscription_mark_failed(
+        device_id: &str,
+        kind: SubscriptionKind,
+        generation: u64,
+    ) -> bool {
         GENERAL_CACHE
             .shared
-            .catalog_subscriptions
-            .get_mut(device_id)
+            .subscriptions
+            .get_mut(&(device_id.to_string(), kind))
             .is_some_and(|mut subscription| {
                 if subscription.generation != generation {
                     return false;
@@ -889,15 +940,16 @@ impl Cache {
             })
     }
 
-    pub fn catalog_subscription_update_expires(
+    pub fn subscription_update_expires(
         device_id: &str,
+        kind: SubscriptionKind,
         generation: u64,
         expires: u32,
     ) -> bool {
         GENERAL_CACHE
             .shared
-            .catalog_subscriptions
-            .get_mut(device_id)
+            .subscriptions
+            .get_mut(&(device_id.to_string(), kind))
             .is_some_and(|mut subscription| {
                 if subscription.generation != generation {
                     return false;
@@ -907,18 +959,23 @@ impl Cache {
             })
     }
 
-    pub fn catalog_subscription_expires(device_id: &str, generation: u64) -> Option<u32> {
+    pub fn subscription_expires(
+        device_id: &str,
+        kind: SubscriptionKind,
+        generation: u64,
+    ) -> Option<u32> {
         GENERAL_CACHE
             .shared
-            .catalog_subscriptions
-            .get(device_id)
+            .subscriptions
+            .get(&(device_id.to_string(), kind))
             .and_then(|subscription| {
                 (subscription.generation == generation).then_some(subscription.expires)
             })
     }
 
-    pub fn catalog_subscription_validate_notify(
+    pub fn subscription_validate_notify(
         device_id: &str,
+        kind: SubscriptionKind,
         call_id: &str,
         event: &str,
         remote_tag: Option<&str>,
@@ -926,10 +983,10 @@ impl Cache {
     ) -> Option<u64> {
         let mut subscription = GENERAL_CACHE
             .shared
-            .catalog_subscriptions
-            .get_mut(device_id)?;
+            .subscriptions
+            .get_mut(&(device_id.to_string(), kind))?;
         if subscription.call_id != call_id
-            || !catalog_event_matches(&subscription.event, event)
+            || !subscription_event_matches(&subscription.event, event)
             || local_tag != Some(subscription.local_tag.as_str())
         {
             return None;
@@ -944,19 +1001,25 @@ impl Cache {
         Some(subscription.generation)
     }
 
-    pub fn catalog_subscription_remove(device_id: &str, generation: Option<u64>) -> bool {
+    pub fn subscription_remove(
+        device_id: &str,
+        kind: SubscriptionKind,
+        generation: Option<u64>,
+    ) -> bool {
+        let key = (device_id.to_string(), kind);
         let removed = match generation {
             Some(generation) => GENERAL_CACHE
                 .shared
-                .catalog_subscriptions
-                .remove_if(device_id, |_, state| state.generation == generation),
-            None => GENERAL_CACHE.shared.catalog_subscriptions.remove(device_id),
+                .subscriptions
+                .remove_if(&key, |_, state| state.generation == generation),
+            None => GENERAL_CACHE.shared.subscriptions.remove(&key),
         };
         if let Some((_, subscription)) = &removed {
             if let Some(scheduler) = crate::register::schedule::TimeScheduler::try_global() {
                 let _ = scheduler.remove_register(
-                    &crate::register::core::TimeScheduleKey::CatalogSubscription(
+                    &crate::register::core::TimeScheduleKey::Subscription(
                         Arc::from(device_id),
+                        kind,
                         subscription.generation,
                     ),
                 );
@@ -965,6 +1028,68 @@ impl Cache {
         removed.is_some()
     }
 
+    /// 取消订阅：移除对话状态并返回发送 Expires=0 所需的对话参数
+    pub fn subscription_take_cancel(
+        device_id: &str,
+        kind: SubscriptionKind,
+    ) -> Option<SubscriptionCommand> {
+        let (_, mut subscription) = GENERAL_CACHE
+            .shared
+            .subscriptions
+            .remove(&(device_id.to_string(), kind))?;
+        if let Some(scheduler) = crate::register::schedule::TimeScheduler::try_global() {
+            let _ =
+                scheduler.remove_register(&crate::register::core::TimeScheduleKey::Subscription(
+                    Arc::from(device_id),
+                    kind,
+                    subscription.generation,
+                ));
+        }
+        subscription.seq = subscription.seq.saturating_add(1);
+        Some(SubscriptionCommand {
+            generation: subscription.generation,
+            call_id: subscription.call_id,
+            seq: subscription.seq,
+            event: subscription.event,
+            expires: 0,
+            remote_target: subscription.remote_target,
+            route_set: subscription.route_set,
+            from_header: subscription.from_header,
+            to_header: subscription.to_header,
+        })
+    }
+
+    /// 移动位置订阅的上报间隔：设备重新注册后据此恢复订阅，取消订阅时清除
+    pub fn mobile_position_interval_set(device_id: &str, interval: u32) {
+        GENERAL_CACHE
+            .shared
+            .position_intervals
+            .insert(device_id.to_string(), interval);
+    }
+
+    pub fn mobile_position_interval(device_id: &str) -> Option<u32> {
+        GENERAL_CACHE
+            .shared
+            .position_intervals
+            .get(device_id)
+            .map(|interval| *interval)
+    }
+
+    pub fn mobile_position_interval_remove(device_id: &str) -> Option<u32> {
+        GENERAL_CACHE
+            .shared
+            .position_intervals
+            .remove(device_id)
+            .map(|(_, interval)| interval)
+    }
+
+    /// 移除设备的全部订阅：重新注册或重置设备状态时调用
+    pub fn subscription_remove_device(device_id: &str) {
+        for kind in SubscriptionKind::ALL {
+            Self::subscription_remove(device_id, kind, None);
+        }
+    }
+
     pub fn reset_device_state(device_id: &str) {
         if let Some((_, entries)) = GENERAL_CACHE.shared.device_map.remove(device_id) {
             for entry in entries {
@@ -1034,7 +1159,7 @@ impl Cache {
             .shared
             .stream_setup_locks
             .retain(|key, _| !key.starts_with(&setup_lock_prefix));
-        Self::catalog_subscription_remove(device_id, None);
+        Self::subscription_remove_device(device_id);
     }
 
     fn upsert_state(
@@ -1170,7 +1295,8 @@ impl Cache {
                 }),
                 stream_map: Default::default(),
                 talk_map: Default::default(),
-                catalog_subscriptions: Default::default(),
+                subscriptions: Default::default(),
+                position_intervals: Default::default(),
                 device_map: Default::default(),
                 stream_setup_locks: Default::default(),
             }),
@@ -1186,7 +1312,7 @@ struct State {
     entities: HashMap<String, StateEntry>,
 }
 
-fn catalog_event_matches(expected: &str, actual: &str) -> bool {
+fn subscription_event_matches(expected: &str, actual: &str) -> bool {
     fn parts(value: &str) -> (&str, Option<&str>) {
         let mut parts = value.split(';').map(str::trim);
         let package = parts.next().unwrap_or_default();
@@ -1261,6 +1387,8 @@ struct StreamTable {
     am: AccessMode,
     ssrc: u32,
     restored: bool,
+    //下载任务的录制输出类型
+    download_output: Option<OutputEnum>,
     lifecycle: StreamLifecycle,
 }
 
@@ -1364,7 +1492,8 @@ struct Shared {
     state: Mutex<State>,
     stream_map: DashMap<String, StreamTable>,
     talk_map: DashMap<String, TalkSessionState>,
-    catalog_subscriptions: DashMap<String, CatalogSubscriptionState>,
+    subscriptions: DashMap<(String, SubscriptionKind), SubscriptionState>,
+    position_intervals: DashMap<String, u32>,
     device_map: DashMap<String, Vec<DeviceTable>>,
     stream_setup_locks: DashMap<String, Arc<AsyncMutex<()>>>,
 }
@@ -1419,7 +1548,9 @@ impl AccessMode {
 
 #[cfg(test)]
 mod tests {
-    use crate::state::session::{AccessMode, Cache, GENERAL_CACHE, StreamLifecycle, StreamTable};
+    use crate::state::session::{
+        AccessMode, Cache, GENERAL_CACHE, StreamLifecycle, StreamTable, SubscriptionKind,
+    };
 
     fn stream_table() -> StreamTable {
         StreamTable {
@@ -1579,9 +1710,10 @@ mod tests {
     #[test]
     fn catalog_subscription_is_singleton_and_refreshes_cseq() {
         let device_id = "catalog-device";
-        Cache::catalog_subscription_remove(device_id, None);
-        let generation = Cache::catalog_subscription_begin(
+        Cache::subscription_remove_device(device_id);
+        let generation = Cache::subscription_begin(
             device_id.to_string(),
+            SubscriptionKind::Catalog,
             "catalog-call-id".to_string(),
             20,
             "Catalog;id=123".to_string(),
@@ -1594,8 +1726,9 @@ mod tests {
         .unwrap();
 
         assert!(
-            Cache::catalog_subscription_begin(
+            Cache::subscription_begin(
                 device_id.to_string(),
+                SubscriptionKind::Catalog,
                 "other-call-id".to_string(),
                 1,
                 "Catalog;id=456".to_string(),
@@ -1608,8 +1741,9 @@ mod tests {
             .is_none()
         );
 
-        Cache::catalog_subscription_complete(
+        Cache::subscription_complete(
             device_id,
+            SubscriptionKind::Catalog,
             generation,
             "sip:device@192.0.2.10:5080".to_string(),
             vec!["<sip:proxy.example.com;lr>".to_string()],
@@ -1617,20 +1751,103 @@ mod tests {
             "<sip:device@example.com>;tag=remote-tag".to_string(),
             "remote-tag".to_string(),
         );
-        let command = Cache::catalog_subscription_take_refresh(device_id, generation).unwrap();
+        let command =
+            Cache::subscription_take_refresh(device_id, SubscriptionKind::Catalog, generation)
+                .unwrap();
 
         assert_eq!(command.seq, 21);
         assert_eq!(command.call_id, "catalog-call-id");
         assert_eq!(command.remote_target, "sip:device@192.0.2.10:5080");
-        Cache::catalog_subscription_remove(device_id, Some(generation));
+        Cache::subscription_remove(device_id, SubscriptionKind::Catalog, Some(generation));
+    }
+
+    #[test]
+    fn alarm_and_catalog_subscriptions_are_independent() {
+        let device_id = "alarm-subscription-device";
+        Cache::subscription_remove_device(device_id);
+        let begin = |kind, call_id: &str| {
+            Cache::subscription_begin(
+                device_id.to_string(),
+                kind,
+                call_id.to_string(),
+                1,
+                "presence".to_string(),
+                300,
+                "sip:device@192.0.2.30:5060".to_string(),
+                "<sip:platform@example.com>;tag=local-tag".to_string(),
+                "<sip:device@example.com>".to_string(),
+                "local-tag".to_string(),
+            )
+        };
+        let catalog = begin(SubscriptionKind::Catalog, "catalog-call-id").unwrap();
+        let alarm = begin(SubscriptionKind::Alarm, "alarm-call-id").unwrap();
+        assert_ne!(catalog, alarm);
+        assert!(begin(SubscriptionKind::Alarm, "other-call-id").is_none());
+        assert!(
+            Cache::subscription_validate_notify(
+                device_id,
+                SubscriptionKind::Alarm,
+                "catalog-call-id",
+                "presence",
+                Some("remote-tag"),
+                Some("local-tag"),
+            )
+            .is_none()
+        );
+
+        Cache::subscription_remove_device(device_id);
+        assert_eq!(
+            Cache::subscription_expires(device_id, SubscriptionKind::Catalog, catalog),
+            None
+        );
+        assert_eq!(
+            Cache::subscription_expires(device_id, SubscriptionKind::Alarm, alarm),
+            None
+        );
+    }
+
+    #[test]
+    fn cancel_mobile_position_subscription_keeps_dialog_and_bumps_seq() {
+        let device_id = "position-subscription-device";
+        Cache::subscription_remove_device(device_id);
+        Cache::mobile_position_interval_set(device_id, 5);
+        let generation = Cache::subscription_begin(
+            device_id.to_string(),
+            SubscriptionKind::MobilePosition,
+            "position-call-id".to_string(),
+            7,
+            "presence".to_string(),
+            3600,
+            "sip:device@192.0.2.31:5060".to_string(),
+            "<sip:platform@example.com>;tag=local-tag".to_string(),
+            "<sip:device@example.com>".to_string(),
+            "local-tag".to_string(),
+        )
+        .unwrap();
+
+        let cancel =
+            Cache::subscription_take_cancel(device_id, SubscriptionKind::MobilePosition).unwrap();
+        assert_eq!(cancel.generation, generation);
+        assert_eq!(cancel.call_id, "position-call-id");
+        assert_eq!(cancel.seq, 8);
+        assert_eq!(cancel.expires, 0);
+        assert!(
+            Cache::subscription_take_cancel(device_id, SubscriptionKind::MobilePosition).is_none()
+        );
+
+        Cache::subscription_remove_device(device_id);
+        assert_eq!(Cache::mobile_position_interval(device_id), Some(5));
+        assert_eq!(Cache::mobile_position_interval_remove(device_id), Some(5));
+        assert_eq!(Cache::mobile_position_interval(device_id), None);
     }
 
     #[test]
     fn catalog_notify_requires_matching_dialog() {
         let device_id = "catalog-notify-device";
-        Cache::catalog_subscription_remove(device_id, None);
-        let generation = Cache::catalog_subscription_begin(
+        Cache::subscription_remove_device(device_id);
+        let generation = Cache::subscription_begin(
             device_id.to_string(),
+            SubscriptionKind::Catalog,
             "notify-call-id".to_string(),
             30,
             "Catalog;id=789".to_string(),
@@ -1641,8 +1858,9 @@ mod tests {
             "local-tag".to_string(),
         )
         .unwrap();
-        Cache::catalog_subscription_complete(
+        Cache::subscription_complete(
             device_id,
+            SubscriptionKind::Catalog,
             generation,
             "sip:device@192.0.2.20:5060".to_string(),
             Vec::new(),
@@ -1652,8 +1870,9 @@ mod tests {
         );
 
         assert_eq!(
-            Cache::catalog_subscription_validate_notify(
+            Cache::subscription_validate_notify(
                 device_id,
+                SubscriptionKind::Catalog,
                 "notify-call-id",
                 "Catalog;id=789",
                 Some("remote-tag"),
@@ -1662,8 +1881,9 @@ mod tests {
             Some(generation)
         );
         assert!(
-            Cache::catalog_subscription_validate_notify(
+            Cache::subscription_validate_notify(
                 device_id,
+                SubscriptionKind::Catalog,
                 "other-call-id",
                 "Catalog;id=789",
                 Some("remote-tag"),
@@ -1672,8 +1892,9 @@ mod tests {
             .is_none()
         );
         assert!(
-            Cache::catalog_subscription_validate_notify(
+            Cache::subscription_validate_notify(
                 device_id,
+                SubscriptionKind::Catalog,
                 "notify-call-id",
                 "Catalog;id=999",
                 Some("remote-tag"),
@@ -1681,7 +1902,7 @@ mod tests {
             )
             .is_none()
         );
-        Cache::catalog_subscription_remove(device_id, Some(generation));
+        Cache::subscription_remove(device_id, SubscriptionKind::Catalog, Some(generation));
     }
 
     #[test]
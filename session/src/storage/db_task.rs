@@ -5,7 +5,11 @@ use base::tokio::sync::mpsc::error::TrySendError;
 use base::tokio::sync::mpsc::{self, Receiver, Sender};
 use base::tokio_util::sync::CancellationToken;
 
-use crate::storage::entity::{GmvDevice, GmvDeviceChannel, GmvDeviceExt};
+use crate::service::catalog_serv;
+use crate::storage::entity::{
+    CatalogEvent, GmvDevice, GmvDeviceChannel, GmvDeviceExt, GmvDeviceStatusHistory,
+    GmvMobilePosition,
+};
 
 const DB_TASK_QUEUE_SIZE: usize = 8192;
 
@@ -21,10 +25,12 @@ pub enum DbTask {
         device_id: String,
     },
     UpdateDeviceExtInfo(Vec<(String, String)>),
-    InsertDeviceCatalog {
+    ApplyDeviceCatalog {
         device_id: String,
-        items: Vec<(String, String)>,
+        channels: Vec<(GmvDeviceChannel, Option<CatalogEvent>)>,
     },
+    InsertMobilePosition(GmvMobilePosition),
+    InsertDeviceStatusHistory(GmvDeviceStatusHistory),
 }
 
 pub fn init(cancel: CancellationToken) {
@@ -99,9 +105,28 @@ async fn handle_task(task: DbTask) {
                 error!("update gmv device ext info failed: {err:?}");
             }
         }
-        DbTask::InsertDeviceCatalog { device_id, items } => {
-            if let Err(err) = GmvDeviceChannel::insert_gmv_device_channel(&device_id, items).await {
-                error!("insert gmv device channel failed: device_id={device_id}, err={err:?}");
+        DbTask::ApplyDeviceCatalog {
+            device_id,
+            channels,
+        } => {
+            if let Err(err) = catalog_serv::apply(&device_id, channels).await {
+                error!("apply gmv device catalog failed: device_id={device_id}, err={err:?}");
+            }
+        }
+        DbTask::InsertMobilePosition(position) => {
+            if let Err(err) = position.insert_gmv_mobile_position().await {
+                error!(
+                    "insert gmv mobile position failed: device_id={}, err={err:?}",
+                    position.device_id
+                );
+            }
+        }
+        DbTask::InsertDeviceStatusHistory(history) => {
+            if let Err(err) = history.insert_gmv_device_status_history().await {
+                error!(
+                    "insert gmv device status history failed: device_id={}, event={}, err={err:?}",
+                    history.device_id, history.event
+                );
             }
         }
     }
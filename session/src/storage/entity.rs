@@ -31,6 +31,9 @@ struct TestStorage {
     records: HashMap<String, GmvRecord>,
     files: HashMap<i64, GmvFileInfo>,
     channels: Vec<GmvDeviceChannel>,
+    alarms: Vec<GmvAlarm>,
+    positions: Vec<GmvMobilePosition>,
+    status_history: Vec<GmvDeviceStatusHistory>,
 }
 
 #[cfg(test)]
@@ -260,6 +263,7 @@ impl GmvOauth {
                 .unwrap_or_else(|poisoned| poisoned.into_inner())
                 .oauths
                 .get(device_id)
+                .filter(|oauth| oauth.status == 1)
                 .cloned());
         }
         let pool = get_conn_by_pool();
@@ -282,7 +286,9 @@ impl GmvOauth {
                 .unwrap_or_else(|poisoned| poisoned.into_inner());
             return Ok(device_ids
                 .iter()
-                .filter_map(|device_id| storage.oauths.get(device_id).cloned())
+                .filter_map(|device_id| storage.oauths.get(device_id))
+                .filter(|oauth| oauth.status == 1)
+                .cloned()
                 .collect());
         }
 
@@ -303,6 +309,201 @@ impl GmvOauth {
             .hand_log(|msg| error!("{msg}"))?;
         Ok(rows)
     }
+
+    /// 管理端查询：包含停用记录，不含已删除记录
+    pub async fn query_gmv_oauth_by_device_id(device_id: &str) -> GlobalResult<Option<GmvOauth>> {
+        #[cfg(test)]
+        if use_test_storage() {
+            return Ok(test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .oauths
+                .get(device_id)
+                .cloned());
+        }
+        let pool = get_conn_by_pool();
+        let res = sqlx::query_as::<_, GmvOauth>(
+            "select device_id,domain_id,domain,pwd,pwd_check,alias,status,heartbeat_sec \
+             from GMV_OAUTH where device_id=? and DEL=0",
+        )
+        .bind(device_id)
+        .fetch_optional(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(res)
+    }
+
+    pub async fn query_gmv_oauth_page(qo: &GmvOauthQo) -> GlobalResult<(i64, Vec<GmvOauth>)> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut ls = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .oauths
+                .values()
+                .filter(|o| {
+                    qo.device_id
+                        .as_ref()
+                        .is_none_or(|v| o.device_id.starts_with(v.as_str()))
+                        && qo.status.is_none_or(|v| o.status == v)
+                        && qo.alias.as_ref().is_none_or(|v| {
+                            o.alias
+                                .as_ref()
+                                .is_some_and(|alias| alias.contains(v.as_str()))
+                        })
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            ls.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+            let total = ls.len() as i64;
+            let page = ls
+                .into_iter()
+                .skip(qo.offset as usize)
+                .take(qo.limit as usize)
+                .collect();
+            return Ok((total, page));
+        }
+        let pool = get_conn_by_pool();
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select count(1) from GMV_OAUTH o where o.DEL=0",
+        );
+        Self::push_oauth_condition(&mut builder, qo);
+        let total = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        if total == 0 {
+            return Ok((0, Vec::new()));
+        }
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select device_id,domain_id,domain,pwd,pwd_check,alias,status,heartbeat_sec \
+             from GMV_OAUTH o where o.DEL=0",
+        );
+        Self::push_oauth_condition(&mut builder, qo);
+        builder
+            .push(" order by o.device_id limit ")
+            .push_bind(qo.limit)
+            .push(" offset ")
+            .push_bind(qo.offset);
+        let rows = builder
+            .build_query_as::<GmvOauth>()
+            .fetch_all(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok((total, rows))
+    }
+
+    fn push_oauth_condition<'a>(
+        builder: &mut sqlx::query_builder::QueryBuilder<'a, sqlx::MySql>,
+        qo: &'a GmvOauthQo,
+    ) {
+        if let Some(device_id) = &qo.device_id {
+            builder
+                .push(" and o.device_id like concat(")
+                .push_bind(device_id)
+                .push(",'%')");
+        }
+        if let Some(status) = qo.status {
+            builder.push(" and o.status=").push_bind(status);
+        }
+        if let Some(alias) = &qo.alias {
+            builder
+                .push(" and o.alias like concat('%',")
+                .push_bind(alias)
+                .push(",'%')");
+        }
+    }
+
+    /// 新增接入记录；已软删除的同编码记录被覆盖并恢复
+    pub async fn insert_gmv_oauth(&self) -> GlobalResult<()> {
+        #[cfg(test)]
+        if use_test_storage() {
+            test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .oauths
+                .insert(self.device_id.clone(), self.clone());
+            return Ok(());
+        }
+        let pool = get_conn_by_pool();
+        sqlx::query(r#"insert into GMV_OAUTH (device_id,domain_id,domain,pwd,pwd_check,alias,status,heartbeat_sec,DEL)
+        values (?,?,?,?,?,?,?,?,0)
+        ON DUPLICATE KEY UPDATE domain_id=VALUES(domain_id),domain=VALUES(domain),pwd=VALUES(pwd),pwd_check=VALUES(pwd_check),
+        alias=VALUES(alias),status=VALUES(status),heartbeat_sec=VALUES(heartbeat_sec),DEL=0"#)
+            .bind(&self.device_id)
+            .bind(&self.domain_id)
+            .bind(&self.domain)
+            .bind(&self.pwd)
+            .bind(self.pwd_check)
+            .bind(&self.alias)
+            .bind(self.status)
+            .bind(self.heartbeat_sec)
+            .execute(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok(())
+    }
+
+    /// 更新状态、密码与心跳周期，仅作用于未删除记录
+    pub async fn update_gmv_oauth_access(&self) -> GlobalResult<()> {
+        #[cfg(test)]
+        if use_test_storage() {
+            if let Some(oauth) = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .oauths
+                .get_mut(&self.device_id)
+            {
+                *oauth = self.clone();
+            }
+            return Ok(());
+        }
+        let pool = get_conn_by_pool();
+        sqlx::query(
+            "update GMV_OAUTH set pwd=?,pwd_check=?,status=?,heartbeat_sec=? where device_id=? and DEL=0",
+        )
+        .bind(&self.pwd)
+        .bind(self.pwd_check)
+        .bind(self.status)
+        .bind(self.heartbeat_sec)
+        .bind(&self.device_id)
+        .execute(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(())
+    }
+
+    /// 软删除接入记录
+    pub async fn delete_gmv_oauth(device_id: &str) -> GlobalResult<u64> {
+        #[cfg(test)]
+        if use_test_storage() {
+            return Ok(test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .oauths
+                .remove(device_id)
+                .map_or(0, |_| 1));
+        }
+        let pool = get_conn_by_pool();
+        let res = sqlx::query("update GMV_OAUTH set DEL=1 where device_id=? and DEL=0")
+            .bind(device_id)
+            .execute(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok(res.rows_affected())
+    }
+}
+
+/// 接入记录分页查询条件
+#[derive(Debug, Default)]
+pub struct GmvOauthQo {
+    /// 设备编码前缀
+    pub device_id: Option<String>,
+    pub status: Option<u8>,
+    pub alias: Option<String>,
+    pub offset: u32,
+    pub limit: u32,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq, New, FromRow)]
@@ -426,6 +627,180 @@ impl GmvDevice {
     }
 }
 
+/// 设备分页查询条件
+#[derive(Debug, Default, Clone)]
+pub struct GmvDeviceQo {
+    /// 在线过滤：Some(true)仅在线，Some(false)仅离线
+    pub online: Option<bool>,
+    /// 当前持有会话的设备ID，配合 online 使用
+    pub session_ids: Vec<String>,
+    /// 行政区划：按设备编码前缀匹配
+    pub civil_code: Option<String>,
+    /// 设备别名：模糊匹配
+    pub name: Option<String>,
+    /// 厂商：模糊匹配
+    pub manufacturer: Option<String>,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+/// 设备列表：GMV_DEVICE 关联 GMV_OAUTH 及通道数量
+#[derive(Default, Debug, Clone, FromRow)]
+pub struct GmvDeviceInfo {
+    pub device_id: String,
+    pub domain_id: String,
+    pub alias: Option<String>,
+    pub transport: String,
+    pub register_time: NaiveDateTime,
+    pub online_expire_time: Option<NaiveDateTime>,
+    pub local_addr: String,
+    pub contact_uri: String,
+    pub gb_version: Option<String>,
+    pub device_type: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub max_camera: Option<u8>,
+    pub channel_count: i64,
+}
+
+impl GmvDeviceInfo {
+    /// 分页查询设备，返回 (总数, 当前页)
+    pub async fn query_gmv_device_page(qo: &GmvDeviceQo) -> GlobalResult<(i64, Vec<Self>)> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let storage = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut ls = storage
+                .devices
+                .values()
+                .filter_map(|device| {
+                    let oauth = storage.oauths.get(&device.device_id)?;
+                    let channel_count = storage
+                        .channels
+                        .iter()
+                        .filter(|c| c.device_id == device.device_id)
+                        .count() as i64;
+                    Some(GmvDeviceInfo {
+                        device_id: device.device_id.clone(),
+                        domain_id: oauth.domain_id.clone(),
+                        alias: oauth.alias.clone(),
+                        transport: device.transport.clone(),
+                        register_time: device.register_time,
+                        online_expire_time: device.online_expire_time,
+                        local_addr: device.local_addr.clone(),
+                        contact_uri: device.contact_uri.clone(),
+                        gb_version: device.gb_version.clone(),
+                        channel_count,
+                        ..Default::default()
+                    })
+                })
+                .filter(|info| {
+                    let in_session = qo.session_ids.contains(&info.device_id);
+                    qo.online.is_none_or(|online| online == in_session)
+                        && qo
+                            .civil_code
+                            .as_ref()
+                            .is_none_or(|code| info.device_id.starts_with(code.as_str()))
+                        && qo.name.as_ref().is_none_or(|name| {
+                            info.alias
+                                .as_ref()
+                                .is_some_and(|alias| alias.contains(name.as_str()))
+                        })
+                        && qo.manufacturer.is_none()
+                })
+                .collect::<Vec<_>>();
+            ls.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+            let total = ls.len() as i64;
+            let page = ls
+                .into_iter()
+                .skip(qo.offset as usize)
+                .take(qo.limit as usize)
+                .collect();
+            return Ok((total, page));
+        }
+        // 仅查在线但当前无会话：无需访问数据库
+        if qo.online == Some(true) && qo.session_ids.is_empty() {
+            return Ok((0, Vec::new()));
+        }
+        let pool = get_conn_by_pool();
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select count(1) from GMV_DEVICE d inner join GMV_OAUTH o on o.DEVICE_ID=d.DEVICE_ID where o.DEL=0",
+        );
+        Self::push_device_condition(&mut builder, qo);
+        let total = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        if total == 0 {
+            return Ok((0, Vec::new()));
+        }
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select d.device_id,o.domain_id,o.alias,d.transport,d.register_time,d.online_expire_time,\
+             d.local_addr,d.contact_uri,d.gb_version,d.device_type,d.manufacturer,d.model,d.firmware,d.max_camera,\
+             (select count(1) from GMV_DEVICE_CHANNEL c where c.DEVICE_ID=d.DEVICE_ID) channel_count \
+             from GMV_DEVICE d inner join GMV_OAUTH o on o.DEVICE_ID=d.DEVICE_ID where o.DEL=0",
+        );
+        Self::push_device_condition(&mut builder, qo);
+        builder
+            .push(" order by d.device_id limit ")
+            .push_bind(qo.limit)
+            .push(" offset ")
+            .push_bind(qo.offset);
+        let rows = builder
+            .build_query_as::<Self>()
+            .fetch_all(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok((total, rows))
+    }
+
+    fn push_device_condition<'a>(
+        builder: &mut sqlx::query_builder::QueryBuilder<'a, sqlx::MySql>,
+        qo: &'a GmvDeviceQo,
+    ) {
+        match qo.online {
+            Some(true) => {
+                builder.push(" and d.device_id in (");
+                let mut separated = builder.separated(", ");
+                for device_id in &qo.session_ids {
+                    separated.push_bind(device_id);
+                }
+                separated.push_unseparated(")");
+            }
+            Some(false) if !qo.session_ids.is_empty() => {
+                builder.push(" and d.device_id not in (");
+                let mut separated = builder.separated(", ");
+                for device_id in &qo.session_ids {
+                    separated.push_bind(device_id);
+                }
+                separated.push_unseparated(")");
+            }
+            _ => {}
+        }
+        if let Some(civil_code) = &qo.civil_code {
+            builder
+                .push(" and d.device_id like concat(")
+                .push_bind(civil_code)
+                .push(",'%')");
+        }
+        if let Some(name) = &qo.name {
+            builder
+                .push(" and o.alias like concat('%',")
+                .push_bind(name)
+                .push(",'%')");
+        }
+        if let Some(manufacturer) = &qo.manufacturer {
+            builder
+                .push(" and d.manufacturer like concat('%',")
+                .push_bind(manufacturer)
+                .push(",'%')");
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, FromRow)]
 pub struct GmvDeviceExt {
     pub device_id: String,
@@ -511,20 +886,87 @@ pub struct GmvDeviceChannel {
     pub supply_light_type: Option<u8>,
 }
 
+/// 通道上报的在线状态取值
+pub const CHANNEL_STATUS_ON: [&str; 3] = ["OK", "ON", "ONLINE"];
+
+/// 目录 NOTIFY 携带的通道变更事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogEvent {
+    On,
+    Off,
+    VideoLost,
+    Defect,
+    Add,
+    Del,
+    Update,
+}
+
+impl CatalogEvent {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "ON" => Some(Self::On),
+            "OFF" => Some(Self::Off),
+            "VLOST" => Some(Self::VideoLost),
+            "DEFECT" => Some(Self::Defect),
+            "ADD" => Some(Self::Add),
+            "DEL" => Some(Self::Del),
+            "UPDATE" => Some(Self::Update),
+            _ => None,
+        }
+    }
+
+    /// 状态类事件写入通道的状态值
+    pub fn status(self) -> Option<&'static str> {
+        match self {
+            Self::On => Some("ON"),
+            Self::Off => Some("OFF"),
+            Self::VideoLost => Some("VLOST"),
+            Self::Defect => Some("DEFECT"),
+            Self::Add | Self::Del | Self::Update => None,
+        }
+    }
+}
+
+/// 通道分页查询条件
+#[derive(Debug, Default, Clone)]
+pub struct GmvDeviceChannelQo {
+    /// 所属设备
+    pub device_id: Option<String>,
+    /// 父节点：设备、业务分组或虚拟组织
+    pub parent_id: Option<String>,
+    /// 在线过滤：所属设备持有会话且通道上报在线
+    pub online: Option<bool>,
+    /// 当前持有会话的设备ID，配合 online 使用
+    pub session_ids: Vec<String>,
+    /// 行政区划：前缀匹配
+    pub civil_code: Option<String>,
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub offset: u32,
+    pub limit: u32,
+}
+
 impl GmvDeviceChannel {
-    pub async fn insert_gmv_device_channel(
-        device_id: &str,
-        vs: Vec<(String, String)>,
-    ) -> GlobalResult<Vec<GmvDeviceChannel>> {
-        let dc_ls = Self::build(device_id, vs);
+    pub async fn upsert_gmv_device_channels(dc_ls: &[GmvDeviceChannel]) -> GlobalResult<()> {
+        if dc_ls.is_empty() {
+            return Ok(());
+        }
         #[cfg(test)]
         if use_test_storage() {
-            test_storage()
+            let mut storage = test_storage()
                 .lock()
-                .unwrap_or_else(|poisoned| poisoned.into_inner())
-                .channels
-                .extend(dc_ls.clone());
-            return Ok(dc_ls);
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            for dc in dc_ls {
+                match storage
+                    .channels
+                    .iter_mut()
+                    .find(|c| c.device_id == dc.device_id && c.channel_id == dc.channel_id)
+                {
+                    Some(c) => *c = dc.clone(),
+                    None => storage.channels.push(dc.clone()),
+                }
+            }
+            return Ok(());
         }
         let pool = get_conn_by_pool();
         let mut builder = sqlx::query_builder::QueryBuilder::new("INSERT INTO GMV_DEVICE_CHANNEL (device_id, channel_id, name, manufacturer,
@@ -561,39 +1003,326 @@ impl GmvDeviceChannel {
             .execute(pool)
             .await
             .hand_log(|msg| error!("{msg}"))?;
-        Self::insert_gmv_device_channel_conf(&dc_ls).await?;
-        Ok(dc_ls)
+        Self::insert_gmv_device_channel_conf(dc_ls).await?;
+        Ok(())
+    }
+
+    /// 更新通道上报状态，返回受影响行数
+    pub async fn update_gmv_device_channel_status(
+        device_id: &str,
+        channel_id: &str,
+        status: &str,
+    ) -> GlobalResult<u64> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut storage = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut affected = 0;
+            for c in storage
+                .channels
+                .iter_mut()
+                .filter(|c| c.device_id == device_id && c.channel_id == channel_id)
+            {
+                c.status = status.to_string();
+                affected += 1;
+            }
+            return Ok(affected);
+        }
+        let pool = get_conn_by_pool();
+        let res = sqlx::query(
+            "UPDATE GMV_DEVICE_CHANNEL SET status=? WHERE device_id=? AND channel_id=?",
+        )
+        .bind(status)
+        .bind(device_id)
+        .bind(channel_id)
+        .execute(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(res.rows_affected())
+    }
+
+    /// 删除通道及其配置，返回删除的通道数
+    pub async fn delete_gmv_device_channels(
+        device_id: &str,
+        channel_ids: &[String],
+    ) -> GlobalResult<u64> {
+        if channel_ids.is_empty() {
+            return Ok(0);
+        }
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut storage = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let before = storage.channels.len();
+            storage
+                .channels
+                .retain(|c| c.device_id != device_id || !channel_ids.contains(&c.channel_id));
+            return Ok((before - storage.channels.len()) as u64);
+        }
+        let pool = get_conn_by_pool();
+        let mut res = 0;
+        for table in ["GMV_DEVICE_CHANNEL_CONF", "GMV_DEVICE_CHANNEL"] {
+            let mut builder = sqlx::query_builder::QueryBuilder::new(format!(
+                "DELETE FROM {table} WHERE device_id="
+            ));
+            builder.push_bind(device_id).push(" AND channel_id IN (");
+            let mut separated = builder.separated(", ");
+            for channel_id in channel_ids {
+                separated.push_bind(channel_id);
+            }
+            separated.push_unseparated(")");
+            res = builder
+                .build()
+                .execute(pool)
+                .await
+                .hand_log(|msg| error!("{msg}"))?
+                .rows_affected();
+        }
+        Ok(res)
+    }
+
+    async fn insert_gmv_device_channel_conf(dc_ls: &[GmvDeviceChannel]) -> GlobalResult<()> {
+        if dc_ls.is_empty() {
+            return Ok(());
+        }
+        let pool = get_conn_by_pool();
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "INSERT IGNORE INTO GMV_DEVICE_CHANNEL_CONF (device_id, channel_id) ",
+        );
+        builder.push_values(dc_ls, |mut b, dc| {
+            b.push_bind(&dc.device_id).push_bind(&dc.channel_id);
+        });
+        builder
+            .build()
+            .execute(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok(())
+    }
+
+    /// 分页查询通道，返回 (总数, 当前页)
+    pub async fn query_gmv_device_channel_page(
+        qo: &GmvDeviceChannelQo,
+    ) -> GlobalResult<(i64, Vec<GmvDeviceChannel>)> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let like = |field: &Option<String>, v: &Option<String>| {
+                v.as_ref().is_none_or(|v| {
+                    field
+                        .as_ref()
+                        .is_some_and(|field| field.contains(v.as_str()))
+                })
+            };
+            let mut ls = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .channels
+                .iter()
+                .filter(|c| {
+                    qo.device_id.as_ref().is_none_or(|v| &c.device_id == v)
+                        && qo
+                            .parent_id
+                            .as_ref()
+                            .is_none_or(|v| c.parent_id.as_ref() == Some(v))
+                        && qo.online.is_none_or(|online| {
+                            online
+                                == (qo.session_ids.contains(&c.device_id)
+                                    && CHANNEL_STATUS_ON
+                                        .iter()
+                                        .any(|s| c.status.eq_ignore_ascii_case(s)))
+                        })
+                        && qo.civil_code.as_ref().is_none_or(|v| {
+                            c.civil_code
+                                .as_ref()
+                                .is_some_and(|code| code.starts_with(v.as_str()))
+                        })
+                        && like(&c.name, &qo.name)
+                        && like(&c.manufacturer, &qo.manufacturer)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            ls.sort_by(|a, b| (&a.device_id, &a.channel_id).cmp(&(&b.device_id, &b.channel_id)));
+            let total = ls.len() as i64;
+            let page = ls
+                .into_iter()
+                .skip(qo.offset as usize)
+                .take(qo.limit as usize)
+                .collect();
+            return Ok((total, page));
+        }
+        if qo.online == Some(true) && qo.session_ids.is_empty() {
+            return Ok((0, Vec::new()));
+        }
+        let pool = get_conn_by_pool();
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select count(1) from GMV_DEVICE_CHANNEL c where 1=1",
+        );
+        Self::push_channel_condition(&mut builder, qo);
+        let total = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        if total == 0 {
+            return Ok((0, Vec::new()));
+        }
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select device_id, channel_id, name, manufacturer, model, owner, status, civil_code, address, parental, \
+             block, parent_id, ip_address, port, password, longitude, latitude, ptz_type, supply_light_type \
+             from GMV_DEVICE_CHANNEL c where 1=1",
+        );
+        Self::push_channel_condition(&mut builder, qo);
+        builder
+            .push(" order by c.device_id, c.channel_id limit ")
+            .push_bind(qo.limit)
+            .push(" offset ")
+            .push_bind(qo.offset);
+        let rows = builder
+            .build_query_as::<GmvDeviceChannel>()
+            .fetch_all(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok((total, rows))
+    }
+
+    pub async fn query_gmv_device_channel_by_device_id(
+        device_id: &str,
+    ) -> GlobalResult<Vec<GmvDeviceChannel>> {
+        #[cfg(test)]
+        if use_test_storage() {
+            return Ok(test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .channels
+                .iter()
+                .filter(|c| c.device_id == device_id)
+                .cloned()
+                .collect());
+        }
+        let pool = get_conn_by_pool();
+        let rows = sqlx::query_as::<_, GmvDeviceChannel>(
+            "select device_id, channel_id, name, manufacturer, model, owner, status, civil_code, address, parental, \
+             block, parent_id, ip_address, port, password, longitude, latitude, ptz_type, supply_light_type \
+             from GMV_DEVICE_CHANNEL where device_id=? order by channel_id",
+        )
+        .bind(device_id)
+        .fetch_all(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(rows)
+    }
+
+    /// 按通道编码查询所属设备的通道，编码重复时取首个
+    pub async fn query_gmv_device_channel_by_channel_id(
+        channel_id: &str,
+    ) -> GlobalResult<Option<GmvDeviceChannel>> {
+        #[cfg(test)]
+        if use_test_storage() {
+            return Ok(test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .channels
+                .iter()
+                .filter(|c| c.channel_id == channel_id)
+                .min_by(|a, b| a.device_id.cmp(&b.device_id))
+                .cloned());
+        }
+        let pool = get_conn_by_pool();
+        let row = sqlx::query_as::<_, GmvDeviceChannel>(
+            "select device_id, channel_id, name, manufacturer, model, owner, status, civil_code, address, parental, \
+             block, parent_id, ip_address, port, password, longitude, latitude, ptz_type, supply_light_type \
+             from GMV_DEVICE_CHANNEL where channel_id=? order by device_id limit 1",
+        )
+        .bind(channel_id)
+        .fetch_optional(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(row)
     }
 
-    async fn insert_gmv_device_channel_conf(dc_ls: &[GmvDeviceChannel]) -> GlobalResult<()> {
-        if dc_ls.is_empty() {
-            return Ok(());
+    fn push_channel_condition<'a>(
+        builder: &mut sqlx::query_builder::QueryBuilder<'a, sqlx::MySql>,
+        qo: &'a GmvDeviceChannelQo,
+    ) {
+        if let Some(device_id) = &qo.device_id {
+            builder.push(" and c.device_id=").push_bind(device_id);
+        }
+        if let Some(parent_id) = &qo.parent_id {
+            builder.push(" and c.parent_id=").push_bind(parent_id);
+        }
+        match qo.online {
+            Some(true) => {
+                builder.push(" and upper(c.status) in ('OK','ON','ONLINE') and c.device_id in (");
+                let mut separated = builder.separated(", ");
+                for device_id in &qo.session_ids {
+                    separated.push_bind(device_id);
+                }
+                separated.push_unseparated(")");
+            }
+            Some(false) if !qo.session_ids.is_empty() => {
+                builder.push(
+                    " and (upper(ifnull(c.status,'')) not in ('OK','ON','ONLINE') or c.device_id not in (",
+                );
+                let mut separated = builder.separated(", ");
+                for device_id in &qo.session_ids {
+                    separated.push_bind(device_id);
+                }
+                separated.push_unseparated("))");
+            }
+            _ => {}
+        }
+        if let Some(civil_code) = &qo.civil_code {
+            builder
+                .push(" and c.civil_code like concat(")
+                .push_bind(civil_code)
+                .push(",'%')");
+        }
+        if let Some(name) = &qo.name {
+            builder
+                .push(" and c.name like concat('%',")
+                .push_bind(name)
+                .push(",'%')");
+        }
+        if let Some(manufacturer) = &qo.manufacturer {
+            builder
+                .push(" and c.manufacturer like concat('%',")
+                .push_bind(manufacturer)
+                .push(",'%')");
         }
-        let pool = get_conn_by_pool();
-        let mut builder = sqlx::query_builder::QueryBuilder::new(
-            "INSERT IGNORE INTO GMV_DEVICE_CHANNEL_CONF (device_id, channel_id) ",
-        );
-        builder.push_values(dc_ls, |mut b, dc| {
-            b.push_bind(&dc.device_id).push_bind(&dc.channel_id);
-        });
-        builder
-            .build()
-            .execute(pool)
-            .await
-            .hand_log(|msg| error!("{msg}"))?;
-        Ok(())
     }
 
-    fn build(parent_device_id: &str, vs: Vec<(String, String)>) -> Vec<GmvDeviceChannel> {
+    /// 解析目录应答或目录 NOTIFY：以 Item 的 DeviceID 切分通道，并带出变更事件
+    pub fn build_catalog(
+        parent_device_id: &str,
+        vs: Vec<(String, String)>,
+    ) -> Vec<(GmvDeviceChannel, Option<CatalogEvent>)> {
         use crate::gb::sip::xml::*;
         let mut dc = GmvDeviceChannel::default();
         dc.device_id = parent_device_id.to_string();
-        let mut dcs: Vec<GmvDeviceChannel> = Vec::new();
+        let mut event = None;
+        let mut dcs = Vec::new();
         for (k, v) in vs {
+            // NOTIFY 与应答的 DeviceList 结构一致，仅根节点不同
+            let k = match k.strip_prefix("Notify,") {
+                Some(rest) => format!("Response,{rest}"),
+                None => k,
+            };
             match &k[..] {
                 RESPONSE_DEVICE_LIST_ITEM_DEVICE_ID => {
+                    if !dc.channel_id.is_empty() {
+                        let next = GmvDeviceChannel {
+                            device_id: parent_device_id.to_string(),
+                            ..Default::default()
+                        };
+                        dcs.push((std::mem::replace(&mut dc, next), event.take()));
+                    }
                     dc.channel_id = v.to_string();
                 }
+                RESPONSE_DEVICE_LIST_ITEM_EVENT => {
+                    event = CatalogEvent::parse(&v);
+                }
                 RESPONSE_DEVICE_LIST_ITEM_NAME => {
                     dc.name = v.parse::<String>().ok();
                 }
@@ -645,17 +1374,12 @@ impl GmvDeviceChannel {
                 RESPONSE_DEVICE_LIST_ITEM_STATUS => {
                     dc.status = v.to_string();
                 }
-                SPLIT_CLASS if "4".eq(&v) => {
-                    if !dc.channel_id.is_empty() {
-                        dcs.push(dc.clone());
-                        dc = GmvDeviceChannel::default();
-                        dc.device_id = parent_device_id.to_string();
-                    }
-                }
                 &_ => {}
             }
         }
-        dcs.push(dc);
+        if !dc.channel_id.is_empty() {
+            dcs.push((dc, event));
+        }
         dcs
     }
 }
@@ -769,6 +1493,552 @@ impl GmvFileInfo {
         Ok(())
     }
 }
+//CREATE TABLE `GMV_ALARM` (
+//   `ID` bigint NOT NULL AUTO_INCREMENT,
+//   `DEVICE_ID` varchar(20) NOT NULL COMMENT '设备编号',
+//   `CHANNEL_ID` varchar(20) NOT NULL COMMENT '报警源编号',
+//   `PRIORITY` tinyint NOT NULL COMMENT '报警级别：1=一级警情，2=二级，3=三级，4=四级',
+//   `METHOD` tinyint NOT NULL COMMENT '报警方式：1=电话，2=设备，3=短信，4=GPS，5=视频，6=设备故障，7=其他',
+//   `ALARM_TIME` datetime DEFAULT NULL COMMENT '设备上报的报警时间',
+//   `ALARM_TYPE` tinyint DEFAULT NULL COMMENT '报警类型',
+//   `EVENT_TYPE` tinyint DEFAULT NULL COMMENT '报警类型扩展参数',
+//   `DESCRIPTION` varchar(256) DEFAULT NULL COMMENT '报警描述',
+//   `LONGITUDE` double DEFAULT NULL COMMENT '经度',
+//   `LATITUDE` double DEFAULT NULL COMMENT '纬度',
+//   `ACK` tinyint NOT NULL DEFAULT '0' COMMENT '确认状态：0=未确认，1=已确认',
+//   `ACK_TIME` datetime DEFAULT NULL COMMENT '确认时间',
+//   `ACK_USER` varchar(64) DEFAULT NULL COMMENT '确认人',
+//   `PUSH_STATE` tinyint NOT NULL DEFAULT '0' COMMENT '推送状态：0=待推送，1=已推送，2=推送失败，3=无需推送',
+//   `CREATE_TIME` datetime NOT NULL COMMENT '平台接收时间',
+//   PRIMARY KEY (`ID`),
+//   KEY `IDX_ALARM_DEVICE_TIME` (`DEVICE_ID`,`ALARM_TIME`)
+// ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci COMMENT='设备报警';
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct GmvAlarm {
+    pub id: i64,
+    pub device_id: String,
+    pub channel_id: String,
+    pub priority: u8,
+    pub method: u8,
+    pub alarm_time: Option<NaiveDateTime>,
+    pub alarm_type: Option<u8>,
+    pub event_type: Option<u8>,
+    pub description: Option<String>,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    pub ack: u8,
+    pub ack_time: Option<NaiveDateTime>,
+    pub ack_user: Option<String>,
+    pub push_state: u8,
+    pub create_time: NaiveDateTime,
+}
+
+pub const ALARM_PUSH_PENDING: u8 = 0;
+pub const ALARM_PUSH_DONE: u8 = 1;
+pub const ALARM_PUSH_FAILED: u8 = 2;
+pub const ALARM_PUSH_SKIPPED: u8 = 3;
+
+/// 报警分页查询条件
+#[derive(Debug, Default, Clone)]
+pub struct GmvAlarmQo {
+    pub device_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub priority: Option<u8>,
+    pub method: Option<u8>,
+    pub alarm_type: Option<u8>,
+    pub start_time: Option<NaiveDateTime>,
+    pub end_time: Option<NaiveDateTime>,
+    pub ack: Option<bool>,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+impl GmvAlarm {
+    /// 写入报警记录，返回自增ID
+    pub async fn insert_gmv_alarm(&self) -> GlobalResult<i64> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut storage = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let id = storage.alarms.len() as i64 + 1;
+            storage.alarms.push(GmvAlarm { id, ..self.clone() });
+            return Ok(id);
+        }
+        let pool = get_conn_by_pool();
+        let res = sqlx::query(
+            "INSERT INTO GMV_ALARM (DEVICE_ID, CHANNEL_ID, PRIORITY, METHOD, ALARM_TIME, ALARM_TYPE, EVENT_TYPE, \
+             DESCRIPTION, LONGITUDE, LATITUDE, ACK, PUSH_STATE, CREATE_TIME) VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?)",
+        )
+        .bind(&self.device_id)
+        .bind(&self.channel_id)
+        .bind(self.priority)
+        .bind(self.method)
+        .bind(self.alarm_time)
+        .bind(self.alarm_type)
+        .bind(self.event_type)
+        .bind(&self.description)
+        .bind(self.longitude)
+        .bind(self.latitude)
+        .bind(self.ack)
+        .bind(self.push_state)
+        .bind(self.create_time)
+        .execute(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(res.last_insert_id() as i64)
+    }
+
+    pub async fn update_gmv_alarm_push_state(id: i64, push_state: u8) -> GlobalResult<()> {
+        #[cfg(test)]
+        if use_test_storage() {
+            if let Some(alarm) = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .alarms
+                .iter_mut()
+                .find(|alarm| alarm.id == id)
+            {
+                alarm.push_state = push_state;
+            }
+            return Ok(());
+        }
+        let pool = get_conn_by_pool();
+        sqlx::query("update GMV_ALARM set PUSH_STATE=? where ID=?")
+            .bind(push_state)
+            .bind(id)
+            .execute(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok(())
+    }
+
+    /// 确认未确认的报警，返回实际确认条数
+    pub async fn ack_gmv_alarm(
+        ids: &[i64],
+        ack_user: Option<&str>,
+        ack_time: NaiveDateTime,
+    ) -> GlobalResult<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut count = 0;
+            for alarm in test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .alarms
+                .iter_mut()
+                .filter(|alarm| alarm.ack == 0 && ids.contains(&alarm.id))
+            {
+                alarm.ack = 1;
+                alarm.ack_time = Some(ack_time);
+                alarm.ack_user = ack_user.map(str::to_string);
+                count += 1;
+            }
+            return Ok(count);
+        }
+        let pool = get_conn_by_pool();
+        let mut builder =
+            sqlx::query_builder::QueryBuilder::new("update GMV_ALARM set ACK=1, ACK_TIME=");
+        builder
+            .push_bind(ack_time)
+            .push(", ACK_USER=")
+            .push_bind(ack_user)
+            .push(" where ACK=0 and ID in (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+        let res = builder
+            .build()
+            .execute(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok(res.rows_affected())
+    }
+
+    /// 分页查询报警，按报警时间倒序，返回 (总数, 当前页)
+    pub async fn query_gmv_alarm_page(qo: &GmvAlarmQo) -> GlobalResult<(i64, Vec<GmvAlarm>)> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut ls = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .alarms
+                .iter()
+                .filter(|a| {
+                    qo.device_id.as_ref().is_none_or(|v| &a.device_id == v)
+                        && qo.channel_id.as_ref().is_none_or(|v| &a.channel_id == v)
+                        && qo.priority.is_none_or(|v| a.priority == v)
+                        && qo.method.is_none_or(|v| a.method == v)
+                        && qo.alarm_type.is_none_or(|v| a.alarm_type == Some(v))
+                        && qo
+                            .start_time
+                            .is_none_or(|v| a.alarm_time.is_some_and(|t| t >= v))
+                        && qo
+                            .end_time
+                            .is_none_or(|v| a.alarm_time.is_some_and(|t| t <= v))
+                        && qo.ack.is_none_or(|v| v == (a.ack == 1))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            ls.sort_by(|a, b| (b.alarm_time, b.id).cmp(&(a.alarm_time, a.id)));
+            let total = ls.len() as i64;
+            let page = ls
+                .into_iter()
+                .skip(qo.offset as usize)
+                .take(qo.limit as usize)
+                .collect();
+            return Ok((total, page));
+        }
+        let pool = get_conn_by_pool();
+        let mut builder =
+            sqlx::query_builder::QueryBuilder::new("select count(1) from GMV_ALARM a where 1=1");
+        Self::push_alarm_condition(&mut builder, qo);
+        let total = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        if total == 0 {
+            return Ok((0, Vec::new()));
+        }
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select id, device_id, channel_id, priority, method, alarm_time, alarm_type, event_type, description, \
+             longitude, latitude, ack, ack_time, ack_user, push_state, create_time from GMV_ALARM a where 1=1",
+        );
+        Self::push_alarm_condition(&mut builder, qo);
+        builder
+            .push(" order by a.alarm_time desc, a.id desc limit ")
+            .push_bind(qo.limit)
+            .push(" offset ")
+            .push_bind(qo.offset);
+        let rows = builder
+            .build_query_as::<GmvAlarm>()
+            .fetch_all(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok((total, rows))
+    }
+
+    fn push_alarm_condition<'a>(
+        builder: &mut sqlx::query_builder::QueryBuilder<'a, sqlx::MySql>,
+        qo: &'a GmvAlarmQo,
+    ) {
+        if let Some(device_id) = &qo.device_id {
+            builder.push(" and a.device_id=").push_bind(device_id);
+        }
+        if let Some(channel_id) = &qo.channel_id {
+            builder.push(" and a.channel_id=").push_bind(channel_id);
+        }
+        if let Some(priority) = qo.priority {
+            builder.push(" and a.priority=").push_bind(priority);
+        }
+        if let Some(method) = qo.method {
+            builder.push(" and a.method=").push_bind(method);
+        }
+        if let Some(alarm_type) = qo.alarm_type {
+            builder.push(" and a.alarm_type=").push_bind(alarm_type);
+        }
+        if let Some(start_time) = qo.start_time {
+            builder.push(" and a.alarm_time>=").push_bind(start_time);
+        }
+        if let Some(end_time) = qo.end_time {
+            builder.push(" and a.alarm_time<=").push_bind(end_time);
+        }
+        if let Some(ack) = qo.ack {
+            builder.push(" and a.ack=").push_bind(u8::from(ack));
+        }
+    }
+}
+
+//CREATE TABLE `GMV_MOBILE_POSITION` (
+//   `ID` bigint NOT NULL AUTO_INCREMENT,
+//   `DEVICE_ID` varchar(20) NOT NULL COMMENT '设备编号',
+//   `CHANNEL_ID` varchar(20) NOT NULL COMMENT '上报位置的通道编号',
+//   `TIME` datetime NOT NULL COMMENT '定位时间',
+//   `LONGITUDE` double NOT NULL COMMENT '经度',
+//   `LATITUDE` double NOT NULL COMMENT '纬度',
+//   `SPEED` double DEFAULT NULL COMMENT '速度：km/h',
+//   `DIRECTION` double DEFAULT NULL COMMENT '方向：正北顺时针夹角，0~360',
+//   `ALTITUDE` double DEFAULT NULL COMMENT '海拔高度：m',
+//   `CREATE_TIME` datetime NOT NULL COMMENT '平台接收时间',
+//   PRIMARY KEY (`ID`),
+//   KEY `IDX_POSITION_CHANNEL_TIME` (`DEVICE_ID`,`CHANNEL_ID`,`TIME`)
+// ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci COMMENT='移动设备位置';
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct GmvMobilePosition {
+    pub device_id: String,
+    pub channel_id: String,
+    pub time: NaiveDateTime,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub speed: Option<f64>,
+    pub direction: Option<f64>,
+    pub altitude: Option<f64>,
+    pub create_time: NaiveDateTime,
+}
+
+impl GmvMobilePosition {
+    pub async fn insert_gmv_mobile_position(&self) -> GlobalResult<()> {
+        #[cfg(test)]
+        if use_test_storage() {
+            test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .positions
+                .push(self.clone());
+            return Ok(());
+        }
+        let pool = get_conn_by_pool();
+        sqlx::query(
+            "INSERT INTO GMV_MOBILE_POSITION (DEVICE_ID, CHANNEL_ID, TIME, LONGITUDE, LATITUDE, SPEED, DIRECTION, \
+             ALTITUDE, CREATE_TIME) VALUES (?,?,?,?,?,?,?,?,?)",
+        )
+        .bind(&self.device_id)
+        .bind(&self.channel_id)
+        .bind(self.time)
+        .bind(self.longitude)
+        .bind(self.latitude)
+        .bind(self.speed)
+        .bind(self.direction)
+        .bind(self.altitude)
+        .bind(self.create_time)
+        .execute(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(())
+    }
+
+    /// 查询设备(或指定通道)最新位置
+    pub async fn query_latest_gmv_mobile_position(
+        device_id: &str,
+        channel_id: Option<&str>,
+    ) -> GlobalResult<Option<GmvMobilePosition>> {
+        #[cfg(test)]
+        if use_test_storage() {
+            return Ok(test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .positions
+                .iter()
+                .filter(|p| {
+                    p.device_id == device_id && channel_id.is_none_or(|v| p.channel_id == v)
+                })
+                .max_by_key(|p| p.time)
+                .cloned());
+        }
+        let pool = get_conn_by_pool();
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select device_id, channel_id, time, longitude, latitude, speed, direction, altitude, create_time \
+             from GMV_MOBILE_POSITION where device_id=",
+        );
+        builder.push_bind(device_id);
+        if let Some(channel_id) = channel_id {
+            builder.push(" and channel_id=").push_bind(channel_id);
+        }
+        builder.push(" order by time desc limit 1");
+        let row = builder
+            .build_query_as::<GmvMobilePosition>()
+            .fetch_optional(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok(row)
+    }
+
+    /// 按时间范围查询轨迹点，按定位时间升序，最多 limit 条
+    pub async fn query_gmv_mobile_position_track(
+        device_id: &str,
+        channel_id: Option<&str>,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        limit: u32,
+    ) -> GlobalResult<Vec<GmvMobilePosition>> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut ls = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .positions
+                .iter()
+                .filter(|p| {
+                    p.device_id == device_id
+                        && channel_id.is_none_or(|v| p.channel_id == v)
+                        && p.time >= start_time
+                        && p.time <= end_time
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            ls.sort_by_key(|p| p.time);
+            ls.truncate(limit as usize);
+            return Ok(ls);
+        }
+        let pool = get_conn_by_pool();
+        let mut builder = sqlx::query_builder::QueryBuilder::new(
+            "select device_id, channel_id, time, longitude, latitude, speed, direction, altitude, create_time \
+             from GMV_MOBILE_POSITION where device_id=",
+        );
+        builder.push_bind(device_id);
+        if let Some(channel_id) = channel_id {
+            builder.push(" and channel_id=").push_bind(channel_id);
+        }
+        builder
+            .push(" and time>=")
+            .push_bind(start_time)
+            .push(" and time<=")
+            .push_bind(end_time)
+            .push(" order by time limit ")
+            .push_bind(limit);
+        let rows = builder
+            .build_query_as::<GmvMobilePosition>()
+            .fetch_all(pool)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        Ok(rows)
+    }
+
+    /// 解析 MobilePosition 通知：经纬度缺失时丢弃，定位时间缺失时取接收时间
+    pub fn from_notify_items(device_id: &str, items: &[(String, String)]) -> Option<Self> {
+        use crate::gb::sip::xml::*;
+        let now = Local::now().naive_local();
+        let number = |key: &str| value(items, key).and_then(|v| v.trim().parse::<f64>().ok());
+        Some(GmvMobilePosition {
+            device_id: device_id.to_string(),
+            channel_id: value(items, NOTIFY_DEVICE_ID)
+                .unwrap_or(device_id)
+                .to_string(),
+            time: value(items, NOTIFY_TIME)
+                .and_then(|v| NaiveDateTime::parse_from_str(v.trim(), "%Y-%m-%dT%H:%M:%S").ok())
+                .unwrap_or(now),
+            longitude: number(NOTIFY_LONGITUDE)?,
+            latitude: number(NOTIFY_LATITUDE)?,
+            speed: number(NOTIFY_SPEED),
+            direction: number(NOTIFY_DIRECTION),
+            altitude: number(NOTIFY_ALTITUDE),
+            create_time: now,
+        })
+    }
+}
+
+//CREATE TABLE `GMV_DEVICE_STATUS_HISTORY` (
+//   `ID` bigint NOT NULL AUTO_INCREMENT,
+//   `DEVICE_ID` varchar(20) NOT NULL COMMENT '设备编号',
+//   `EVENT` varchar(32) NOT NULL COMMENT '生命周期事件：device_registered/device_unregistered等',
+//   `ONLINE` tinyint NOT NULL COMMENT '事件后设备是否在线：0-离线，1-在线',
+//   `ADDRESS` varchar(64) DEFAULT NULL COMMENT '设备信令地址',
+//   `EVENT_TIME` datetime NOT NULL COMMENT '事件时间',
+//   PRIMARY KEY (`ID`),
+//   KEY `IDX_STATUS_DEVICE_TIME` (`DEVICE_ID`,`EVENT_TIME`)
+// ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci COMMENT='设备在线状态变更记录';
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct GmvDeviceStatusHistory {
+    pub device_id: String,
+    pub event: String,
+    pub online: u8,
+    pub address: Option<String>,
+    pub event_time: NaiveDateTime,
+}
+
+impl GmvDeviceStatusHistory {
+    pub async fn insert_gmv_device_status_history(&self) -> GlobalResult<()> {
+        #[cfg(test)]
+        if use_test_storage() {
+            test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .status_history
+                .push(self.clone());
+            return Ok(());
+        }
+        let pool = get_conn_by_pool();
+        sqlx::query(
+            "INSERT INTO GMV_DEVICE_STATUS_HISTORY (DEVICE_ID, EVENT, ONLINE, ADDRESS, EVENT_TIME) \
+             VALUES (?,?,?,?,?)",
+        )
+        .bind(&self.device_id)
+        .bind(&self.event)
+        .bind(self.online)
+        .bind(&self.address)
+        .bind(self.event_time)
+        .execute(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(())
+    }
+
+    /// 查询时间点之前的最后一条记录，用于确定时间范围起点的在线状态
+    pub async fn query_last_gmv_device_status_before(
+        device_id: &str,
+        time: NaiveDateTime,
+    ) -> GlobalResult<Option<GmvDeviceStatusHistory>> {
+        #[cfg(test)]
+        if use_test_storage() {
+            return Ok(test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .status_history
+                .iter()
+                .filter(|h| h.device_id == device_id && h.event_time < time)
+                .max_by_key(|h| h.event_time)
+                .cloned());
+        }
+        let pool = get_conn_by_pool();
+        let row = sqlx::query_as::<_, GmvDeviceStatusHistory>(
+            "select device_id, event, online, address, event_time from GMV_DEVICE_STATUS_HISTORY \
+             where device_id=? and event_time<? order by event_time desc, id desc limit 1",
+        )
+        .bind(device_id)
+        .bind(time)
+        .fetch_optional(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(row)
+    }
+
+    /// 按时间范围查询状态记录，按事件时间升序，最多 limit 条
+    pub async fn query_gmv_device_status_history(
+        device_id: &str,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        limit: u32,
+    ) -> GlobalResult<Vec<GmvDeviceStatusHistory>> {
+        #[cfg(test)]
+        if use_test_storage() {
+            let mut ls = test_storage()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .status_history
+                .iter()
+                .filter(|h| {
+                    h.device_id == device_id
+                        && h.event_time >= start_time
+                        && h.event_time <= end_time
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            ls.sort_by_key(|h| h.event_time);
+            ls.truncate(limit as usize);
+            return Ok(ls);
+        }
+        let pool = get_conn_by_pool();
+        let rows = sqlx::query_as::<_, GmvDeviceStatusHistory>(
+            "select device_id, event, online, address, event_time from GMV_DEVICE_STATUS_HISTORY \
+             where device_id=? and event_time>=? and event_time<=? order by event_time, id limit ?",
+        )
+        .bind(device_id)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+        Ok(rows)
+    }
+}
+
 #[derive(Debug, FromRow, Default)]
 pub struct DeviceStatus {
     pub heartbeat: u8,
@@ -1014,4 +2284,59 @@ mod tests {
         let time_str2 = now.naive_local().format("%Y-%m-%d %H:%M:%S").to_string();
         println!("{}", time_str2);
     }
+
+    #[test]
+    fn mobile_position_reads_notify_items() {
+        let xml = "<?xml version=\"1.0\"?><Notify><CmdType>MobilePosition</CmdType><SN>3</SN>\
+            <DeviceID>34020000001320000001</DeviceID><Time>2026-06-13T08:00:05</Time>\
+            <Longitude>116.397</Longitude><Latitude>39.908</Latitude><Speed>12.5</Speed>\
+            <Direction>90</Direction><Altitude>45</Altitude></Notify>";
+        let items = crate::gb::sip::xml::parse_items(xml.as_bytes()).unwrap();
+        let position =
+            GmvMobilePosition::from_notify_items("34020000001110000001", &items).unwrap();
+        assert_eq!(position.device_id, "34020000001110000001");
+        assert_eq!(position.channel_id, "34020000001320000001");
+        assert_eq!(position.time.to_string(), "2026-06-13 08:00:05");
+        assert_eq!((position.longitude, position.latitude), (116.397, 39.908));
+        assert_eq!(position.speed, Some(12.5));
+        assert_eq!(position.direction, Some(90.0));
+        assert_eq!(position.altitude, Some(45.0));
+
+        let missing = "<?xml version=\"1.0\"?><Notify><CmdType>MobilePosition</CmdType>\
+            <SN>4</SN><DeviceID>34020000001320000001</DeviceID><Longitude>116.397</Longitude></Notify>";
+        let items = crate::gb::sip::xml::parse_items(missing.as_bytes()).unwrap();
+        assert!(GmvMobilePosition::from_notify_items("34020000001110000001", &items).is_none());
+    }
+
+    #[test]
+    fn catalog_notify_splits_items_with_events() {
+        let xml = "<?xml version=\"1.0\"?><Notify><CmdType>Catalog</CmdType><SN>5</SN>\
+            <DeviceID>34020000001110000001</DeviceID><SumNum>3</SumNum><DeviceList Num=\"3\">\
+            <Item><DeviceID>34020000001320000001</DeviceID><Event>vlost</Event></Item>\
+            <Item><DeviceID>34020000001320000002</DeviceID><Event>DEL</Event></Item>\
+            <Item><DeviceID>34020000001320000003</DeviceID><Name>added</Name>\
+            <Info><PTZType>1</PTZType></Info><Status>ON</Status><Event>ADD</Event></Item>\
+            </DeviceList></Notify>";
+        let items = crate::gb::sip::xml::parse_items(xml.as_bytes()).unwrap();
+        let channels = GmvDeviceChannel::build_catalog("34020000001110000001", items);
+        let summary = channels
+            .iter()
+            .map(|(dc, event)| (dc.channel_id.as_str(), *event))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("34020000001320000001", Some(CatalogEvent::VideoLost)),
+                ("34020000001320000002", Some(CatalogEvent::Del)),
+                ("34020000001320000003", Some(CatalogEvent::Add)),
+            ]
+        );
+        let added = &channels[2].0;
+        assert_eq!(added.device_id, "34020000001110000001");
+        assert_eq!(added.name.as_deref(), Some("added"));
+        assert_eq!(added.ptz_type, Some(1));
+        assert_eq!(added.status, "ON");
+        assert_eq!(CatalogEvent::VideoLost.status(), Some("VLOST"));
+        assert_eq!(CatalogEvent::Add.status(), None);
+    }
 }
@@ -389,7 +389,7 @@ fn packet_assets() -> Vec<PacketAsset> {
         ),
         (
             "preset-normal",
-            &[][..],
+            &["/api/control/preset/list"][..],
             xml("Query", "PresetQuery", 13, CHANNEL_ID, ""),
         ),
         (
@@ -419,6 +419,142 @@ fn packet_assets() -> Vec<PacketAsset> {
 </SnapShotConfig>\r\n",
             ),
         ),
+        (
+            "ptz-preset-normal",
+            &["/api/control/ptz/cmd"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                16,
+                CHANNEL_ID,
+                "<PTZCmd>A50F01820003003A</PTZCmd>\r\n",
+            ),
+        ),
+        (
+            "ptz-cruise-normal",
+            &["/api/control/ptz/cmd"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                17,
+                CHANNEL_ID,
+                "<PTZCmd>A50F01860123106F</PTZCmd>\r\n",
+            ),
+        ),
+        (
+            "ptz-scan-normal",
+            &["/api/control/ptz/cmd"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                18,
+                CHANNEL_ID,
+                "<PTZCmd>A50F01890100003F</PTZCmd>\r\n",
+            ),
+        ),
+        (
+            "ptz-fi-normal",
+            &["/api/control/ptz/cmd"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                19,
+                CHANNEL_ID,
+                "<PTZCmd>A50F014A804000BF</PTZCmd>\r\n",
+            ),
+        ),
+        (
+            "ptz-aux-normal",
+            &["/api/control/ptz/cmd"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                20,
+                CHANNEL_ID,
+                "<PTZCmd>A50F018C01000042</PTZCmd>\r\n",
+            ),
+        ),
+        (
+            "device-status-normal",
+            &["/api/device/status"][..],
+            xml("Query", "DeviceStatus", 21, CHANNEL_ID, ""),
+        ),
+        (
+            "ptz-position-normal",
+            &["/api/control/ptz/position"][..],
+            xml("Query", "PTZPosition", 22, CHANNEL_ID, ""),
+        ),
+        (
+            "config-download-normal",
+            &["/api/device/config/download"][..],
+            xml(
+                "Query",
+                "ConfigDownload",
+                23,
+                CHANNEL_ID,
+                "<ConfigType>BasicParam/VideoRecordPlan</ConfigType>\r\n",
+            ),
+        ),
+        (
+            "device-config-normal",
+            &["/api/device/config"][..],
+            xml(
+                "Control",
+                "DeviceConfig",
+                24,
+                CHANNEL_ID,
+                "<BasicParam>\r\n\
+<Name>gate</Name>\r\n\
+<Expiration>3600</Expiration>\r\n\
+<HeartBeatInterval>60</HeartBeatInterval>\r\n\
+<HeartBeatCount>3</HeartBeatCount>\r\n\
+</BasicParam>\r\n",
+            ),
+        ),
+        (
+            "device-control-record-normal",
+            &["/api/device/control"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                25,
+                CHANNEL_ID,
+                "<RecordCmd>Record</RecordCmd>\r\n",
+            ),
+        ),
+        (
+            "device-control-home-position-normal",
+            &["/api/device/control"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                26,
+                CHANNEL_ID,
+                "<HomePosition>\r\n\
+<Enabled>1</Enabled>\r\n\
+<ResetTime>30</ResetTime>\r\n\
+<PresetIndex>1</PresetIndex>\r\n\
+</HomePosition>\r\n",
+            ),
+        ),
+        (
+            "device-control-drag-zoom-normal",
+            &["/api/device/control"][..],
+            xml(
+                "Control",
+                "DeviceControl",
+                27,
+                CHANNEL_ID,
+                "<DragZoomIn>\r\n\
+<Length>1920</Length>\r\n\
+<Width>1080</Width>\r\n\
+<MidPointX>960</MidPointX>\r\n\
+<MidPointY>540</MidPointY>\r\n\
+<LengthX>200</LengthX>\r\n\
+<LengthY>100</LengthY>\r\n\
+</DragZoomIn>\r\n",
+            ),
+        ),
     ];
     for (index, (scenario_id, apis, body)) in message_scenarios.into_iter().enumerate() {
         let cseq = 10 + u32::try_from(index).expect("message index fits u32");
@@ -433,6 +569,130 @@ fn packet_assets() -> Vec<PacketAsset> {
         push_exchange(&mut assets, scenario_id, apis, "MESSAGE", cseq, packet);
     }
 
+    let device_responses = [
+        (
+            "preset-response-normal",
+            xml(
+                "Response",
+                "PresetQuery",
+                13,
+                CHANNEL_ID,
+                "<SumNum>2</SumNum>\r\n\
+<PresetList Num=\"2\">\r\n\
+<Item>\r\n\
+<PresetID>1</PresetID>\r\n\
+<PresetName>gate</PresetName>\r\n\
+</Item>\r\n\
+<Item>\r\n\
+<PresetID>2</PresetID>\r\n\
+<PresetName>yard</PresetName>\r\n\
+</Item>\r\n\
+</PresetList>\r\n",
+            ),
+        ),
+        (
+            "device-status-response-normal",
+            xml(
+                "Response",
+                "DeviceStatus",
+                21,
+                CHANNEL_ID,
+                "<Result>OK</Result>\r\n\
+<Online>ONLINE</Online>\r\n\
+<Status>OK</Status>\r\n\
+<Encode>ON</Encode>\r\n\
+<Record>OFF</Record>\r\n\
+<DeviceTime>2026-06-13T00:00:00</DeviceTime>\r\n\
+<Alarmstatus Num=\"1\">\r\n\
+<Item>\r\n\
+<DeviceID>34020000001340000001</DeviceID>\r\n\
+<DutyStatus>ONDUTY</DutyStatus>\r\n\
+</Item>\r\n\
+</Alarmstatus>\r\n",
+            ),
+        ),
+        (
+            "ptz-position-response-normal",
+            xml(
+                "Response",
+                "PTZPosition",
+                22,
+                CHANNEL_ID,
+                "<Info>\r\n\
+<Pan>120.5</Pan>\r\n\
+<Tilt>-10</Tilt>\r\n\
+<Zoom>4</Zoom>\r\n\
+<HorizontalFieldAngle>58.2</HorizontalFieldAngle>\r\n\
+</Info>\r\n",
+            ),
+        ),
+        (
+            "config-download-response-normal",
+            xml(
+                "Response",
+                "ConfigDownload",
+                23,
+                CHANNEL_ID,
+                "<Result>OK</Result>\r\n\
+<BasicParam>\r\n\
+<Name>gate</Name>\r\n\
+<Expiration>3600</Expiration>\r\n\
+<HeartBeatInterval>60</HeartBeatInterval>\r\n\
+<HeartBeatCount>3</HeartBeatCount>\r\n\
+</BasicParam>\r\n\
+<VideoRecordPlan>\r\n\
+<RecordEnable>1</RecordEnable>\r\n\
+<RecordScheduleSumNum>1</RecordScheduleSumNum>\r\n\
+<RecordSchedule>\r\n\
+<WeekDayNum>1</WeekDayNum>\r\n\
+<TimeSegmentSumNum>1</TimeSegmentSumNum>\r\n\
+<TimeSegment>\r\n\
+<StartHour>0</StartHour>\r\n\
+<StartMin>0</StartMin>\r\n\
+<StartSec>0</StartSec>\r\n\
+<StopHour>23</StopHour>\r\n\
+<StopMin>59</StopMin>\r\n\
+<StopSec>59</StopSec>\r\n\
+</TimeSegment>\r\n\
+</RecordSchedule>\r\n\
+<StreamNumber>0</StreamNumber>\r\n\
+</VideoRecordPlan>\r\n",
+            ),
+        ),
+        (
+            "device-config-response-normal",
+            xml(
+                "Response",
+                "DeviceConfig",
+                24,
+                CHANNEL_ID,
+                "<Result>OK</Result>\r\n",
+            ),
+        ),
+        (
+            "device-control-response-normal",
+            xml(
+                "Response",
+                "DeviceControl",
+                25,
+                CHANNEL_ID,
+                "<Result>OK</Result>\r\n",
+            ),
+        ),
+    ];
+    for (index, (scenario_id, body)) in device_responses.into_iter().enumerate() {
+        let cseq = 4 + u32::try_from(index).expect("response index fits u32");
+        let packet = device_request(
+            "MESSAGE",
+            scenario_id,
+            cseq,
+            Some("Application/MANSCDP+xml"),
+            &body,
+            &[],
+        );
+        push_device_exchange(&mut assets, scenario_id, "MESSAGE", cseq, packet);
+    }
+
     let answer_video = format!(
         "v=0\r\n\
 o={DEVICE_ID} 0 0 IN IP4 198.51.100.20\r\n\
@@ -652,6 +912,144 @@ y=0200008202\r\n"
         bytes: response_to_device("subscribe-normal", 28, "NOTIFY", 200, "OK", &[]),
     });
 
+    let alarm_subscribe_body = xml(
+        "Query",
+        "Alarm",
+        29,
+        DEVICE_ID,
+        "<StartAlarmPriority>1</StartAlarmPriority>\r\n\
+<EndAlarmPriority>4</EndAlarmPriority>\r\n\
+<AlarmMethod>0</AlarmMethod>\r\n\
+<StartAlarmTime>2026-06-13T00:00:00</StartAlarmTime>\r\n\
+<EndAlarmTime>2026-06-13T00:05:00</EndAlarmTime>\r\n",
+    );
+    push_exchange(
+        &mut assets,
+        "alarm-subscribe-normal",
+        &[],
+        "SUBSCRIBE",
+        29,
+        request(
+            "SUBSCRIBE",
+            "alarm-subscribe-normal",
+            29,
+            Some("Application/MANSCDP+xml"),
+            &alarm_subscribe_body,
+            &[("Event", "presence"), ("Expires", "300")],
+        ),
+    );
+    let alarm_notify_body = xml(
+        "Notify",
+        "Alarm",
+        30,
+        CHANNEL_ID,
+        "<AlarmPriority>1</AlarmPriority>\r\n\
+<AlarmMethod>5</AlarmMethod>\r\n\
+<AlarmTime>2026-06-13T00:30:00</AlarmTime>\r\n\
+<AlarmDescription>intrusion</AlarmDescription>\r\n\
+<Longitude>116.397</Longitude>\r\n\
+<Latitude>39.908</Latitude>\r\n\
+<Info>\r\n\
+<AlarmType>6</AlarmType>\r\n\
+<AlarmTypeParam>\r\n\
+<EventType>1</EventType>\r\n\
+</AlarmTypeParam>\r\n\
+</Info>\r\n",
+    );
+    let alarm_notify = device_request(
+        "NOTIFY",
+        "alarm-subscribe-normal",
+        30,
+        Some("Application/MANSCDP+xml"),
+        &alarm_notify_body,
+        &[
+            ("Event", "presence"),
+            ("Subscription-State", "active;expires=299"),
+        ],
+    );
+    assets.push(PacketAsset {
+        scenario_id: "alarm-notify-normal",
+        business_apis: &["/api/alarm/page", "/api/alarm/ack"],
+        file_name: "alarm-notify-normal-01-request.sip".into(),
+        direction: "device-to-platform",
+        sip_method: "NOTIFY",
+        expected_status: None,
+        bytes: alarm_notify,
+    });
+    assets.push(PacketAsset {
+        scenario_id: "alarm-notify-normal",
+        business_apis: &[],
+        file_name: "alarm-notify-normal-02-200.sip".into(),
+        direction: "platform-to-device",
+        sip_method: "NOTIFY",
+        expected_status: Some(200),
+        bytes: response_to_device("alarm-subscribe-normal", 30, "NOTIFY", 200, "OK", &[]),
+    });
+
+    let position_subscribe_body = xml(
+        "Query",
+        "MobilePosition",
+        31,
+        DEVICE_ID,
+        "<Interval>5</Interval>\r\n",
+    );
+    push_exchange(
+        &mut assets,
+        "position-subscribe-normal",
+        &["/api/position/subscribe"],
+        "SUBSCRIBE",
+        31,
+        request(
+            "SUBSCRIBE",
+            "position-subscribe-normal",
+            31,
+            Some("Application/MANSCDP+xml"),
+            &position_subscribe_body,
+            &[("Event", "presence"), ("Expires", "3600")],
+        ),
+    );
+    let position_notify_body = xml(
+        "Notify",
+        "MobilePosition",
+        32,
+        CHANNEL_ID,
+        "<Time>2026-06-13T00:30:05</Time>\r\n\
+<Longitude>116.397</Longitude>\r\n\
+<Latitude>39.908</Latitude>\r\n\
+<Speed>12.5</Speed>\r\n\
+<Direction>90</Direction>\r\n\
+<Altitude>45</Altitude>\r\n",
+    );
+    let position_notify = device_request(
+        "NOTIFY",
+        "position-subscribe-normal",
+        32,
+        Some("Application/MANSCDP+xml"),
+        &position_notify_body,
+        &[
+            ("Event", "presence"),
+            ("Subscription-State", "active;expires=3599"),
+        ],
+    );
+    assets.push(PacketAsset {
+        scenario_id: "position-notify-normal",
+        business_apis: &["/api/position/latest", "/api/position/track"],
+        file_name: "position-notify-normal-01-request.sip".into(),
+        direction: "device-to-platform",
+        sip_method: "NOTIFY",
+        expected_status: None,
+        bytes: position_notify,
+    });
+    assets.push(PacketAsset {
+        scenario_id: "position-notify-normal",
+        business_apis: &[],
+        file_name: "position-notify-normal-02-200.sip".into(),
+        direction: "platform-to-device",
+        sip_method: "NOTIFY",
+        expected_status: Some(200),
+        bytes: response_to_device("position-subscribe-normal", 32, "NOTIFY", 200, "OK", &[]),
+    });
+
     assets
 }
 
@@ -1206,6 +1604,18 @@ fn generated_sip_corpus_is_current_and_complete() {
         "/api/play/back/seek",
         "/api/play/back/speed",
         "/api/control/ptz",
+        "/api/control/ptz/cmd",
+        "/api/control/preset/list",
+        "/api/control/ptz/position",
+        "/api/device/status",
+        "/api/device/config/download",
+        "/api/device/config",
+        "/api/device/control",
+        "/api/alarm/page",
+        "/api/alarm/ack",
+        "/api/position/subscribe",
+        "/api/position/latest",
+        "/api/position/track",
         "/api/download/mp4",
         "/api/download/stop",
         "/api/downing/info",
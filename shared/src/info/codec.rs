@@ -23,3 +23,36 @@ impl Default for Codec {
         Self::H264
     }
 }
+
+/// 转码配置：音视频分别指定目标编码，未指定或与输入编码一致的轨道直通
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(crate = "base::serde")]
+pub struct Transcode {
+    pub video: Option<VideoTranscode>,
+    pub audio: Option<AudioTranscode>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "base::serde")]
+pub struct VideoTranscode {
+    /// 目标视频编码：H264/H265/Mpeg4
+    pub codec: Codec,
+    /// 码率：kbps，默认2048
+    pub bitrate: Option<u32>,
+    /// 关键帧间隔：帧数，默认50
+    pub gop: Option<u32>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "base::serde")]
+pub struct AudioTranscode {
+    /// 目标音频编码：Aac/G711a/G711u；输入支持 G.711/G.722.1/AAC
+    pub codec: Codec,
+    /// 码率：kbps，默认64
+    pub bitrate: Option<u32>,
+    /// 采样率：默认沿用输入采样率
+    pub sample_rate: Option<u32>,
+}
@@ -3,7 +3,33 @@ use base::serde::{Deserialize, Serialize};
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "base::serde")]
-pub struct Capture {}
+pub struct Capture {
+    /// 定时抽图间隔(秒)，按间隔后的首个关键帧抽取并入库；为空或0时仅支持按需抓拍
+    pub interval: Option<u32>,
+    /// 图片格式，默认jpeg
+    pub format: Option<ImageFormat>,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(crate = "base::serde")]
+pub enum ImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+        }
+    }
+}
+
+/// 滤镜作用于整条媒体流而非单个输出：开启视频滤镜后该流的全部输出均为滤镜后的重编码画面；
+/// 以建流时的配置为准，流运行中追加输出携带的滤镜不生效
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(crate = "base::serde")]
@@ -11,11 +37,83 @@ pub struct Filter {
     //抽图
     pub capture: Option<Capture>,
     //缩放
-    // pub scale: Option<Scale>,
+    pub scale: Option<Scale>,
     //裁剪
-    // pub crop: Option<Crop>,
+    pub crop: Option<Crop>,
     //旋转
-    // pub rotate: Option<Rotate>,
+    pub rotate: Option<Rotate>,
     //镜像
-    // pub mirror: Option<Mirror>,
+    pub mirror: Option<Mirror>,
+    //文字/时间水印
+    pub osd: Option<Osd>,
+}
+
+impl Filter {
+    /// 是否包含需要解码重编码的视频滤镜
+    pub fn has_video_filter(&self) -> bool {
+        self.scale.is_some()
+            || self.crop.is_some()
+            || self.rotate.is_some()
+            || self.mirror.is_some()
+            || self.osd.is_some()
+    }
+}
+
+/// 缩放：宽高为0时按另一边等比缩放
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "base::serde")]
+pub struct Scale {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 裁剪：以左上角为原点的矩形区域
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "base::serde")]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 顺时针旋转
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "base::serde")]
+pub enum Rotate {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "base::serde")]
+pub enum Mirror {
+    //左右镜像
+    Horizontal,
+    //上下镜像
+    Vertical,
+}
+
+/// 水印：text 与 timestamp 至少开启一项
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(crate = "base::serde")]
+pub struct Osd {
+    /// 水印文字
+    pub text: Option<String>,
+    /// 叠加本地时间：yyyy-MM-dd HH:mm:ss
+    #[serde(default)]
+    pub timestamp: bool,
+    /// 左上角坐标，默认(10,10)
+    pub x: Option<u32>,
+    pub y: Option<u32>,
+    /// 字号，默认24
+    pub font_size: Option<u32>,
+    /// 字体文件路径；FFmpeg 未启用 fontconfig 时必填，中文需指定支持中文的字体
+    pub font_file: Option<String>,
 }
@@ -1,4 +1,4 @@
-use crate::info::codec::Codec;
+use crate::info::codec::Transcode;
 use crate::info::filter::Filter;
 use crate::info::output::OutputKind;
 use base::serde::{Deserialize, Serialize};
@@ -16,7 +16,7 @@ pub struct MediaConfig {
     ///   out_idle_timeout: 6 #u8 单位秒；输出流闲置超时,0：立即关闭,建议：2-8；
     pub in_wait_timeout: Option<u8>,
     pub out_idle_timeout: Option<u8>,
-    pub codec: Option<Codec>,
+    pub codec: Option<Transcode>,
     pub filter: Filter,
     pub output: OutputKind,
 }
@@ -1,4 +1,5 @@
-use crate::info::output::OutputEnum;
+use crate::info::filter::{ImageFormat, Osd};
+use crate::info::output::{OutputEnum, OutputKind};
 use base::constructor::New;
 use base::serde::{Deserialize, Serialize};
 
@@ -8,12 +9,40 @@ pub const PLAY_BACK: &str = "/play/back/stream";
 pub const PLAY_SEEK: &str = "/play/back/seek";
 pub const PLAY_SPEED: &str = "/play/back/speed";
 pub const CONTROL_PTZ: &str = "/control/ptz";
+pub const CONTROL_PTZ_CMD: &str = "/control/ptz/cmd";
+pub const PRESET_LIST: &str = "/control/preset/list";
+pub const PTZ_POSITION: &str = "/control/ptz/position";
+pub const DEVICE_STATUS: &str = "/device/status";
+pub const CONFIG_DOWNLOAD: &str = "/device/config/download";
+pub const DEVICE_CONFIG: &str = "/device/config";
+pub const DEVICE_CONTROL: &str = "/device/control";
 pub const DOWNLOAD_MP4: &str = "/download/mp4";
 pub const DOWNLOAD_STOP: &str = "/download/stop";
 pub const DOWNING_INFO: &str = "/downing/info";
 pub const RM_FILE: &str = "/rm/file";
 pub const TALK_START: &str = "/talk/start";
 pub const TALK_STOP: &str = "/talk/stop";
+pub const DEVICE_PAGE: &str = "/device/page";
+pub const CHANNEL_PAGE: &str = "/device/channel/page";
+pub const CHANNEL_TREE: &str = "/device/channel/tree";
+pub const DEVICE_TIMELINE: &str = "/device/timeline";
+pub const OAUTH_PAGE: &str = "/device/oauth/page";
+pub const OAUTH_CREATE: &str = "/device/oauth/create";
+pub const OAUTH_STATUS: &str = "/device/oauth/status";
+pub const OAUTH_PWD: &str = "/device/oauth/pwd";
+pub const OAUTH_HEARTBEAT: &str = "/device/oauth/heartbeat";
+pub const OAUTH_DELETE: &str = "/device/oauth/delete";
+pub const RECORD_LIST: &str = "/record/list";
+pub const ALARM_PAGE: &str = "/alarm/page";
+pub const ALARM_ACK: &str = "/alarm/ack";
+pub const POSITION_SUBSCRIBE: &str = "/position/subscribe";
+pub const POSITION_UNSUBSCRIBE: &str = "/position/unsubscribe";
+pub const POSITION_LATEST: &str = "/position/latest";
+pub const POSITION_TRACK: &str = "/position/track";
+pub const PLAY_SNAPSHOT: &str = "/play/snapshot";
+pub const PLAY_OUTPUT_OPEN: &str = "/play/output/open";
+pub const PLAY_OUTPUT_CLOSE: &str = "/play/output/close";
+pub const PLAY_OSD: &str = "/play/osd";
 
 pub const STREAM_REGISTER: &str = "/stream/register";
 pub const INPUT_TIMEOUT: &str = "/stream/input/timeout";
@@ -23,12 +52,16 @@ pub const STREAM_IDLE: &str = "/stream/idle";
 pub const STREAM_UNKNOWN: &str = "/stream/unknown";
 pub const END_RECORD: &str = "/end/record";
 pub const TALK_CLOSED: &str = "/talk/closed";
+pub const PUSH_STATE: &str = "/push/state";
+pub const STREAM_CAPTURE: &str = "/stream/capture/{stream_id}";
 
 //stream
 pub const LISTEN_MEDIA: &str = "/listen/media";
 pub const SDP_MEDIA: &str = "/sdp/media";
 pub const STREAM_ONLINE: &str = "/stream/online";
 pub const PLAY_PATH: &str = "/play/{stream_id}";
+pub const WHEP_PATH: &str = "/whep/{stream_id}";
+pub const WHEP_SESSION_PATH: &str = "/whep/{stream_id}/{session_id}";
 pub const RECORD_INFO: &str = "/record/info";
 pub const CLOSE_OUTPUT: &str = "/close/output";
 pub const TALK_OPEN: &str = "/talk/open";
@@ -37,6 +70,12 @@ pub const TALK_CLOSE: &str = "/talk/close";
 pub const TALK_ONLINE: &str = "/talk/online";
 pub const TALK_INPUT_PREFIX: &str = "/talk/input";
 pub const TALK_INPUT_PATH: &str = "/talk/input/{talk_id}";
+pub const STREAM_SNAPSHOT: &str = "/stream/snapshot";
+pub const OPEN_OUTPUT_STREAM: &str = "/open/output/stream";
+pub const CLOSE_OUTPUT_STREAM: &str = "/close/output/stream";
+pub const OPEN_FILTER_STREAM: &str = "/open/filter/stream";
+pub const CLOSE_FILTER_STREAM: &str = "/close/filter/stream";
+pub const RTCP_STATS: &str = "/rtcp/stats";
 
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug)]
@@ -52,6 +91,53 @@ pub struct StreamInfoQo {
     pub output_enum: OutputEnum,
 }
 
+/// 抓拍：解码最近关键帧，返回图片二进制
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "base::serde")]
+pub struct StreamSnapshotQo {
+    pub ssrc: u32,
+    /// 默认jpeg
+    pub format: Option<ImageFormat>,
+}
+
+/// 运行中的流追加输出
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "base::serde")]
+pub struct OutputStreamQo {
+    pub ssrc: u32,
+    pub output: OutputKind,
+}
+
+/// 运行中的流更新水印；需在开流时已开启视频滤镜
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "base::serde")]
+pub struct FilterStreamQo {
+    pub ssrc: u32,
+    pub osd: Osd,
+}
+
+/// 输入流 RTCP 接收统计
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "base::serde")]
+pub struct RtcpStats {
+    /// 按序号推算的期望包数
+    pub expected: u32,
+    pub received: u32,
+    /// 累计丢包，重复包可使其为负
+    pub cumulative_lost: i32,
+    /// 到达间隔抖动：RTP时间戳单位
+    pub jitter: u32,
+    pub clock_rate: u32,
+    /// 最近RTP包对应的设备端时间(unix ms)，由 SR 的 NTP/RTP 映射换算，未收到 SR 时为空
+    pub sender_unix_ms: Option<u64>,
+    /// 已学习的对端 RTCP 地址，为空时不发送 RR
+    pub peer: Option<String>,
+}
+
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(New, Serialize, Deserialize, Debug)]
 #[serde(crate = "base::serde")]
@@ -155,6 +241,34 @@ pub struct StreamKey {
     pub stream_id: Option<String>,
 }
 
+/// 主动推流状态
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(crate = "base::serde")]
+pub enum PushState {
+    Connecting,
+    Pushing,
+    //断线等待重连
+    Retrying,
+    //超过重连上限
+    Failed,
+    //源流结束
+    Stopped,
+}
+
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "base::serde")]
+pub struct PushStateInfo {
+    pub stream_id: String,
+    pub output: OutputEnum,
+    pub target: String,
+    pub state: PushState,
+    ///当前连续重连次数
+    pub retry: u32,
+    pub reason: Option<String>,
+}
+
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "base::serde")]
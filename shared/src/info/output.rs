@@ -37,6 +37,25 @@ pub enum OutputKind {
     LocalTs(LocalTsOutput),
 }
 
+impl OutputKind {
+    pub fn output_enum(&self) -> OutputEnum {
+        match self {
+            OutputKind::HttpFlv(_) => OutputEnum::HttpFlv,
+            OutputKind::Rtmp(_) => OutputEnum::Rtmp,
+            OutputKind::DashFmp4(_) => OutputEnum::DashFmp4,
+            OutputKind::DashMp4(_) => OutputEnum::DashMp4,
+            OutputKind::HlsFmp4(_) => OutputEnum::HlsFmp4,
+            OutputKind::HlsTs(_) => OutputEnum::HlsTs,
+            OutputKind::Rtsp(_) => OutputEnum::Rtsp,
+            OutputKind::Gb28181Frame(_) => OutputEnum::Gb28181Frame,
+            OutputKind::Gb28181Ps(_) => OutputEnum::Gb28181Ps,
+            OutputKind::WebRtc(_) => OutputEnum::WebRtc,
+            OutputKind::LocalMp4(_) => OutputEnum::LocalMp4,
+            OutputKind::LocalTs(_) => OutputEnum::LocalTs,
+        }
+    }
+}
+
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "base::serde")]
@@ -62,6 +81,10 @@ pub struct HttpFlvOutput {
 #[serde(crate = "base::serde")]
 pub struct RtmpOutput {
     pub fmt: Flv,
+    /// 推流地址 rtmp://host[:port]/app/stream
+    pub url: String,
+    /// 断线重连次数上限，缺省持续重连直至源流结束
+    pub max_retry: Option<u32>,
 }
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -100,12 +123,34 @@ pub struct RtspOutput {
 #[serde(crate = "base::serde")]
 pub struct Gb28181FrameOutput {
     pub fmt: RtpFrame,
+    pub target: RtpPushTarget,
 }
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "base::serde")]
 pub struct Gb28181PsOutput {
     pub fmt: RtpPs,
+    pub target: RtpPushTarget,
+}
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(crate = "base::serde")]
+pub enum RtpTransport {
+    Udp,
+    //RFC4571：每个RTP包前置2字节长度
+    Tcp,
+}
+/// RTP 主动推流目标
+#[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "base::serde")]
+pub struct RtpPushTarget {
+    pub ip: String,
+    pub port: u16,
+    pub ssrc: u32,
+    pub transport: RtpTransport,
+    //缺省96
+    pub payload_type: Option<u8>,
 }
 #[cfg_attr(debug_assertions, derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -145,6 +145,7 @@ echo "[5/7] configure"
   --enable-decoder=aac \
   --enable-decoder=pcm_alaw \
   --enable-decoder=pcm_mulaw \
+  --enable-decoder=siren \
   \
   --enable-encoder=aac \
   --enable-encoder=pcm_alaw \
@@ -1,5 +1,5 @@
 use crate::general::cfg::ServerConf;
-use crate::io::{http, rtp_handler};
+use crate::io::{http, rtcp_handler, rtp_handler, rtsp, webrtc};
 use crate::media;
 use crate::state::register::Register;
 use base::cfg_lib::{CliBasic, default_cli_basic};
@@ -18,6 +18,9 @@ pub struct App {
 impl
     Daemon<(
         std::net::TcpListener,
+        Option<std::net::TcpListener>,
+        Option<UdpSocket>,
+        Option<UdpSocket>,
         (Option<std::net::TcpListener>, Option<UdpSocket>),
     )> for App
 {
@@ -29,6 +32,9 @@ impl
         Self,
         (
             std::net::TcpListener,
+            Option<std::net::TcpListener>,
+            Option<UdpSocket>,
+            Option<UdpSocket>,
             (Option<std::net::TcpListener>, Option<UdpSocket>),
         ),
     )>
@@ -43,20 +49,38 @@ impl
         let http_listener = http::listen_http_server(http_port)?;
         let rtp_port = app.conf.rtp_port;
         let tu = rtp_handler::listen_media_server(rtp_port)?;
-        banner(Self::cli_basic().version, http_port, rtp_port, |msg| {
-            info!("{msg}")
-        });
-        Ok((app, (http_listener, tu)))
+        let rtsp_port = app.conf.rtsp_port;
+        let rtsp_listener = rtsp::listen_rtsp_server(rtsp_port)?;
+        let rtc_port = app.conf.rtc_port;
+        let rtc_socket = webrtc::listen_rtc_server(rtc_port)?;
+        let rtcp_port = app.conf.rtcp_port;
+        let rtcp_socket = rtcp_handler::listen_rtcp_server(rtcp_port)?;
+        banner(
+            Self::cli_basic().version,
+            http_port,
+            rtp_port,
+            rtcp_port,
+            rtsp_port,
+            rtc_port,
+            |msg| info!("{msg}"),
+        );
+        Ok((
+            app,
+            (http_listener, rtsp_listener, rtc_socket, rtcp_socket, tu),
+        ))
     }
 
     fn run_app(
         self,
         t: (
             std::net::TcpListener,
+            Option<std::net::TcpListener>,
+            Option<UdpSocket>,
+            Option<UdpSocket>,
             (Option<std::net::TcpListener>, Option<UdpSocket>),
         ),
     ) -> GlobalResult<()> {
-        let (http_listener, tu) = t;
+        let (http_listener, rtsp_listener, rtc_socket, rtcp_socket, tu) = t;
         let (tx, rx) = mpsc::channel(100);
         Register::init()?;
 
@@ -64,10 +88,19 @@ impl
         {
             let _enter = network_rt.rt_handle.enter();
             rtp_handler::run(tu, network_rt.cancel.clone())?;
+            rtcp_handler::run(rtcp_socket, network_rt.cancel.clone())?;
+            if let Some(rtc_socket) = rtc_socket {
+                webrtc::init(rtc_socket, self.conf.rtc_public_ip.clone())?;
+            }
         }
         network_rt
             .rt_handle
             .spawn(http::run(http_listener, tx, network_rt.cancel.clone()));
+        if let Some(rtsp_listener) = rtsp_listener {
+            network_rt
+                .rt_handle
+                .spawn(rtsp::run(rtsp_listener, network_rt.cancel.clone()));
+        }
 
         let compute_rt = GlobalRuntime::register_default(RuntimeType::CommonCompute)?;
         compute_rt.rt_handle.spawn(media::handle_process(rx));
@@ -80,7 +113,15 @@ impl
     }
 }
 
-fn banner<F: FnOnce(String)>(version: &str, http_port: u16, rtp_port: u16, f: F) {
+fn banner<F: FnOnce(String)>(
+    version: &str,
+    http_port: u16,
+    rtp_port: u16,
+    rtcp_port: u16,
+    rtsp_port: u16,
+    rtc_port: u16,
+    f: F,
+) {
     let msg = format!(
         r#"
             ___   __  __  __   __    _      ___    _____    ___    ___    ___    __  __
@@ -95,8 +136,32 @@ fn banner<F: FnOnce(String)>(version: &str, http_port: u16, rtp_port: u16, f: F)
 ├──────────────────┼──────────────────┼──────────────┼──────────────┤
 │ HTTP Server      │ 0.0.0.0:{:<5}    │ HTTP         │ 🟢 Ready     │
 │ RTP Media Stream │ 0.0.0.0:{:<5}    │ TCP, UDP     │ 🟢 Listening │
+│ RTCP Report      │ 0.0.0.0:{:<5}    │ UDP, MUX     │ {} │
+│ RTSP Server      │ 0.0.0.0:{:<5}    │ RTSP         │ {} │
+│ WebRTC Media     │ 0.0.0.0:{:<5}    │ UDP(WHEP)    │ {} │
 └──────────────────┴──────────────────┴──────────────┴──────────────┘"#,
-        "Version", version, http_port, rtp_port
+        "Version",
+        version,
+        http_port,
+        rtp_port,
+        rtcp_port,
+        if rtcp_port == 0 {
+            "⚪ Mux Only  "
+        } else {
+            "🟢 Listening"
+        },
+        rtsp_port,
+        if rtsp_port == 0 {
+            "⚪ Disabled  "
+        } else {
+            "🟢 Listening"
+        },
+        rtc_port,
+        if rtc_port == 0 {
+            "⚪ Disabled  "
+        } else {
+            "🟢 Listening"
+        }
     );
     f(msg);
 }
@@ -42,6 +42,15 @@ pub struct ServerConf {
     pub rtcp_port: u16,
     #[serde(default = "default_http_port")]
     pub http_port: u16,
+    //0-关闭RTSP服务
+    #[serde(default = "default_rtsp_port")]
+    pub rtsp_port: u16,
+    //WebRTC(WHEP) 媒体 UDP 端口,0-关闭
+    #[serde(default = "default_rtc_port")]
+    pub rtc_port: u16,
+    //WebRTC 对外公布的 ICE 候选地址,NAT 部署时配置
+    #[serde(default)]
+    pub rtc_public_ip: Option<String>,
     #[serde(default = "default_hook_uri")]
     pub hook_uri: String,
     #[serde(default = "default_proxy_addr")]
@@ -51,6 +60,8 @@ serde_default!(default_name, String, "stream-node-1".to_string());
 serde_default!(default_rtp_port, u16, 18568);
 serde_default!(default_rtcp_port, u16, 18569);
 serde_default!(default_http_port, u16, 18570);
+serde_default!(default_rtsp_port, u16, 18554);
+serde_default!(default_rtc_port, u16, 18572);
 serde_default!(
     default_hook_uri,
     String,
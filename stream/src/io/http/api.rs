@@ -1,10 +1,15 @@
 use crate::general::util::dump;
 use crate::io::http::{res_by_code, res_by_error};
 use crate::io::local::mp4::Mp4OutputInnerEvent;
+use crate::io::local::ts::TsOutputInnerEvent;
 use crate::io::talk::TalkManager;
+use crate::media::context::event::ContextEvent;
+use crate::media::context::event::filter::FilterEvent;
+use crate::media::context::event::inner::InnerEvent;
 use crate::state::register::Register;
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::extract::{Path, Query};
+use axum::http::header;
 use axum::response::{IntoResponse, Response};
 use axum::{Extension, Json, Router};
 use base::err::BaseErrorCode;
@@ -17,9 +22,11 @@ use futures_util::StreamExt;
 use shared::info::media_info::MediaConfig;
 use shared::info::media_info_ext::MediaMap;
 use shared::info::obj::{
-    CLOSE_OUTPUT, LISTEN_MEDIA, RECORD_INFO, SDP_MEDIA, STREAM_ONLINE, StreamInfoQo, StreamKey,
-    StreamRecordInfo, TALK_ANSWER, TALK_CLOSE, TALK_INPUT_PATH, TALK_ONLINE, TALK_OPEN,
-    TalkAnswerReq, TalkCloseReq, TalkOpenReq, TalkOpenResp,
+    CLOSE_FILTER_STREAM, CLOSE_OUTPUT, CLOSE_OUTPUT_STREAM, FilterStreamQo, LISTEN_MEDIA,
+    OPEN_FILTER_STREAM, OPEN_OUTPUT_STREAM, OutputStreamQo, RECORD_INFO, RTCP_STATS, RtcpStats,
+    SDP_MEDIA, STREAM_ONLINE, STREAM_SNAPSHOT, SingleParam, StreamInfoQo, StreamKey,
+    StreamRecordInfo, StreamSnapshotQo, TALK_ANSWER, TALK_CLOSE, TALK_INPUT_PATH, TALK_ONLINE,
+    TALK_OPEN, TalkAnswerReq, TalkCloseReq, TalkOpenReq, TalkOpenResp,
 };
 use shared::info::output::OutputEnum;
 use shared::info::res::{EmptyResponse, Resp};
@@ -35,6 +42,18 @@ pub fn routes(tx: Sender<u32>) -> Router {
         .route(STREAM_ONLINE, axum::routing::post(stream_online))
         .route(RECORD_INFO, axum::routing::post(record_info))
         .route(CLOSE_OUTPUT, axum::routing::post(close_output))
+        .route(STREAM_SNAPSHOT, axum::routing::post(stream_snapshot))
+        .route(RTCP_STATS, axum::routing::post(rtcp_stats))
+        .route(OPEN_OUTPUT_STREAM, axum::routing::post(open_output_stream))
+        .route(
+            CLOSE_OUTPUT_STREAM,
+            axum::routing::post(close_output_stream),
+        )
+        .route(OPEN_FILTER_STREAM, axum::routing::post(open_filter_stream))
+        .route(
+            CLOSE_FILTER_STREAM,
+            axum::routing::post(close_filter_stream),
+        )
         .route(TALK_OPEN, axum::routing::post(talk_open))
         .route(TALK_ANSWER, axum::routing::post(talk_answer))
         .route(TALK_CLOSE, axum::routing::post(talk_close))
@@ -141,7 +160,19 @@ async fn record_info(Json(info): Json<StreamInfoQo>) -> Json<Resp<StreamRecordIn
                 }
             }
         }
-        OutputEnum::LocalTs => {}
+        OutputEnum::LocalTs => {
+            let (tx, rx) = oneshot::channel();
+            if let Ok(_) = Register::try_publish_mpsc::<TsOutputInnerEvent>(
+                info.ssrc,
+                TsOutputInnerEvent::StoreInfo(tx),
+            ) {
+                if let Ok(record) = rx.await {
+                    let json = Json(Resp::<StreamRecordInfo>::build_success_data(record));
+                    info!("record_info response: {:?}", &json);
+                    return json;
+                }
+            }
+        }
         _ => {}
     }
     let json = Json(res_by_code::<StreamRecordInfo>(BaseErrorCode::NotFound));
@@ -169,7 +200,12 @@ async fn close_output(Json(output): Json<StreamInfoQo>) -> Json<Resp<()>> {
                 Mp4OutputInnerEvent::Close,
             );
         }
-        OutputEnum::LocalTs => {}
+        OutputEnum::LocalTs => {
+            let _ = Register::try_publish_mpsc::<TsOutputInnerEvent>(
+                output.ssrc,
+                TsOutputInnerEvent::Close,
+            );
+        }
         _ => {}
     }
     let json = Json(Resp::<()>::build_success());
@@ -177,6 +213,57 @@ async fn close_output(Json(output): Json<StreamInfoQo>) -> Json<Resp<()>> {
     json
 }
 
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/stream/snapshot",
+    request_body = StreamSnapshotQo,
+    responses(
+        (status = 200, description = "抓拍成功，返回图片二进制(image/jpeg、image/png)；失败返回json", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    tag = "媒体流操作"
+))]
+///抓拍：解码最近关键帧
+async fn stream_snapshot(Json(qo): Json<StreamSnapshotQo>) -> Response {
+    info!("stream_snapshot: {:?}", &qo);
+    let format = qo.format.unwrap_or_default();
+    let (tx, rx) = oneshot::channel();
+    if Register::try_publish_mpsc(
+        qo.ssrc,
+        ContextEvent::Inner(InnerEvent::Snapshot(format, tx)),
+    )
+    .is_ok()
+    {
+        match rx.await {
+            Ok(Ok(image)) => {
+                return ([(header::CONTENT_TYPE, format.content_type())], image).into_response();
+            }
+            Ok(Err(err)) => return Json(res_by_error::<()>(err)).into_response(),
+            Err(_) => {}
+        }
+    }
+    Json(res_by_code::<()>(BaseErrorCode::NotFound)).into_response()
+}
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/rtcp/stats",
+    request_body = SingleParam<u32>,
+    responses(
+        (status = 200, description = "输入流RTCP接收统计；流不存在或尚未收到RTP时返回NotFound", body = Resp<RtcpStats>),
+        (status = 500, description = "服务器内部错误", body = Resp<RtcpStats>)
+    ),
+    tag = "媒体流操作"
+))]
+/// 查看输入流RTCP接收统计：丢包、抖动及SR换算的设备端时间
+async fn rtcp_stats(Json(ssrc): Json<SingleParam<u32>>) -> Json<Resp<RtcpStats>> {
+    debug!("rtcp_stats: {:?}", &ssrc);
+    match Register::rtcp_session(ssrc.param).and_then(|session| session.stats()) {
+        Some(stats) => Json(Resp::build_success_data(stats)),
+        None => Json(res_by_code::<RtcpStats>(BaseErrorCode::NotFound)),
+    }
+}
+
 async fn talk_open(Json(req): Json<TalkOpenReq>) -> Json<Resp<TalkOpenResp>> {
     info!("talk_open: {:?}", &req);
     let json = match TalkManager::open(req).await {
@@ -258,28 +345,90 @@ async fn handle_talk_socket(talk_id: String, mut socket: WebSocket) {
     info!("talk websocket closed: talk_id={}", talk_id);
 }
 
-async fn open_output_stream(
-    Extension(tx): Extension<Sender<u32>>,
-    Json(ssrc): Json<u32>,
-) -> Resp<()> {
-    unimplemented!()
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/open/output/stream",
+    request_body = OutputStreamQo,
+    responses(
+        (status = 200, description = "追加输出：data=false 表示输出已存在", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    tag = "媒体流操作"
+))]
+///运行中的流追加输出，如开始本地录制、rtmp推流
+async fn open_output_stream(Json(qo): Json<OutputStreamQo>) -> Json<Resp<bool>> {
+    info!("open_output_stream: {:?}", &qo);
+    let json = match Register::open_output(qo.ssrc, qo.output) {
+        Ok(opened) => Resp::build_success_data(opened),
+        Err(err) => res_by_error(err),
+    };
+    info!("open_output_stream response: {:?}", &json);
+    Json(json)
 }
-async fn close_output_stream(
-    Extension(tx): Extension<Sender<u32>>,
-    Json(ssrc): Json<u32>,
-) -> Resp<()> {
-    unimplemented!()
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/close/output/stream",
+    request_body = StreamInfoQo,
+    responses(
+        (status = 200, description = "移除输出：data=false 表示输出不存在", body = Resp<bool>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    tag = "媒体流操作"
+))]
+///运行中的流移除输出，muxer无输出使用时一并关闭
+async fn close_output_stream(Json(qo): Json<StreamInfoQo>) -> Json<Resp<bool>> {
+    info!("close_output_stream: {:?}", &qo);
+    let json = match Register::close_output(qo.ssrc, qo.output_enum) {
+        Ok(closed) => Resp::build_success_data(closed),
+        Err(err) => res_by_error(err),
+    };
+    info!("close_output_stream response: {:?}", &json);
+    Json(json)
 }
 
-async fn open_filter_stream(
-    Extension(tx): Extension<Sender<u32>>,
-    Json(ssrc): Json<u32>,
-) -> Resp<()> {
-    unimplemented!()
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/open/filter/stream",
+    request_body = FilterStreamQo,
+    responses(
+        (status = 200, description = "更新水印成功", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    tag = "媒体流操作"
+))]
+///运行中的流设置水印
+async fn open_filter_stream(Json(qo): Json<FilterStreamQo>) -> Json<Resp<()>> {
+    info!("open_filter_stream: {:?}", &qo);
+    let json = match Register::try_publish_mpsc(
+        qo.ssrc,
+        ContextEvent::Filter(FilterEvent::Osd(Some(qo.osd))),
+    ) {
+        Ok(_) => Resp::<()>::build_success(),
+        Err(err) => res_by_error(err),
+    };
+    info!("open_filter_stream response: {:?}", &json);
+    Json(json)
 }
-async fn close_filter_stream(
-    Extension(tx): Extension<Sender<u32>>,
-    Json(ssrc): Json<u32>,
-) -> Resp<()> {
-    unimplemented!()
+
+#[cfg_attr(debug_assertions, utoipa::path(
+    post,
+    path = "/close/filter/stream",
+    request_body = SingleParam<u32>,
+    responses(
+        (status = 200, description = "移除水印成功", body = Resp<EmptyResponse>),
+        (status = 500, description = "服务器内部错误", body = Resp<EmptyResponse>)
+    ),
+    tag = "媒体流操作"
+))]
+///运行中的流移除水印；param为ssrc
+async fn close_filter_stream(Json(qo): Json<SingleParam<u32>>) -> Json<Resp<()>> {
+    info!("close_filter_stream: {:?}", &qo);
+    let json =
+        match Register::try_publish_mpsc(qo.param, ContextEvent::Filter(FilterEvent::Osd(None))) {
+            Ok(_) => Resp::<()>::build_success(),
+            Err(err) => res_by_error(err),
+        };
+    info!("close_filter_stream response: {:?}", &json);
+    Json(json)
 }
@@ -1,13 +1,16 @@
 use crate::state::register::{DEFAULT_EXPIRES, Register};
-use base::exception::{GlobalResult, GlobalResultExt};
-use base::log::info;
+use base::bytes::Bytes;
+use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use base::log::{error, info};
 use pretend::interceptor::NoopRequestInterceptor;
 use pretend::resolver::UrlResolver;
 use pretend::{Json, Url};
 use pretend::{Pretend, Result, pretend};
+use shared::info::filter::ImageFormat;
 use shared::info::obj::{
-    BaseStreamInfo, InTimeoutEventRes, OutputEventRes, OutputStreamInfo, RegisterStreamInfo,
-    StreamPlayInfo, StreamRecordInfo, StreamState, TalkClosedEvent, UnknownStreamEvent,
+    BaseStreamInfo, InTimeoutEventRes, OutputEventRes, OutputStreamInfo, PushStateInfo,
+    RegisterStreamInfo, STREAM_CAPTURE, StreamPlayInfo, StreamRecordInfo, StreamState,
+    TalkClosedEvent, UnknownStreamEvent,
 };
 use shared::info::res::Resp;
 use std::str::FromStr;
@@ -17,6 +20,7 @@ use std::time::Duration;
 pub struct HttpClient;
 pub type HttpTemplate = Arc<Pretend<pretend_reqwest::Client, UrlResolver, NoopRequestInterceptor>>;
 static HTTP: OnceLock<HttpTemplate> = OnceLock::new();
+static RAW: OnceLock<reqwest::Client> = OnceLock::new();
 impl HttpClient {
     fn init(url: &str) -> GlobalResult<HttpTemplate> {
         let url = Url::from_str(url).hand_log(|msg| info!("{msg}"))?;
@@ -36,6 +40,47 @@ impl HttpClient {
         let _ = HTTP.set(client.clone());
         Ok(client)
     }
+
+    /// 定时抽图上报：图片以二进制请求体提交，不经 json 编码
+    pub async fn stream_capture(
+        stream_id: &str,
+        format: ImageFormat,
+        bytes: Bytes,
+    ) -> GlobalResult<()> {
+        let client = match RAW.get() {
+            Some(client) => client,
+            None => {
+                let client = reqwest::Client::builder()
+                    .timeout(DEFAULT_EXPIRES)
+                    .build()
+                    .hand_log(|msg| error!("{msg}"))?;
+                RAW.get_or_init(|| client)
+            }
+        };
+        //与回调接口一致，按绝对路径拼接
+        let url = Url::from_str(&Register::get_server_conf().hook_uri)
+            .and_then(|url| {
+                url.join(&format!(
+                    "/hook{}",
+                    STREAM_CAPTURE.replace("{stream_id}", stream_id)
+                ))
+            })
+            .hand_log(|msg| error!("{msg}"))?;
+        let res = client
+            .post(url.as_str())
+            .header("Content-Type", format.content_type())
+            .body(bytes)
+            .send()
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        if !res.status().is_success() {
+            return Err(GlobalError::new_sys_error(
+                &format!("stream_capture returned {}", res.status()),
+                |msg| error!("stream_id: {stream_id}; {msg}"),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[pretend]
@@ -59,4 +104,6 @@ pub trait HttpSession {
     async fn end_record(&self, json: &StreamRecordInfo) -> Result<Json<Resp<()>>>;
     #[request(method = "POST", path = "/hook/talk/closed")]
     async fn talk_closed(&self, json: &TalkClosedEvent) -> Result<Json<Resp<bool>>>;
+    #[request(method = "POST", path = "/hook/push/state")]
+    async fn push_state(&self, json: &PushStateInfo) -> Result<Json<Resp<()>>>;
 }
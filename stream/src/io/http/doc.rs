@@ -1,5 +1,6 @@
 use crate::io::http::api;
 use crate::io::http::out;
+use shared::info::filter::ImageFormat;
 use shared::info::media_info::MediaConfig;
 use shared::info::media_info_ext::MediaMap;
 use shared::info::obj::*;
@@ -13,7 +14,15 @@ use utoipa::OpenApi;
         api::sdp_media,
         api::stream_online,
         api::record_info,
+        api::stream_snapshot,
+        api::rtcp_stats,
+        api::open_output_stream,
+        api::close_output_stream,
+        api::open_filter_stream,
+        api::close_filter_stream,
         out::handler,
+        out::whep::offer,
+        out::whep::delete,
     ),
     components(
         schemas(
@@ -22,6 +31,11 @@ use utoipa::OpenApi;
             StreamKey,
             StreamInfoQo,
             StreamRecordInfo,
+            StreamSnapshotQo,
+            RtcpStats,
+            ImageFormat,
+            OutputStreamQo,
+            FilterStreamQo,
         ),
     ),
     tags(
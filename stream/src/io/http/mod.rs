@@ -16,7 +16,7 @@ mod api;
 pub mod call;
 #[cfg(debug_assertions)]
 mod doc;
-mod out;
+pub(crate) mod out;
 
 pub fn listen_http_server(port: u16) -> GlobalResult<std::net::TcpListener> {
     let listener =
@@ -1,13 +1,580 @@
+use crate::io::http::out::{OutPlayKind, stream_user_token_check};
+use crate::io::http::{res_401, res_404};
+use crate::media::context::event::ContextEvent;
+use crate::media::context::event::inner::InnerEvent;
+use crate::media::context::format::MuxPacket;
+use crate::media::context::format::hls_ts::HlsTsSegment;
+use crate::media::context::format::hlsfmp4::HlsMp4Segment;
+use crate::media::context::format::muxer::MuxerEnum;
+use crate::state::register::{DEFAULT_EXPIRES, DEFAULT_OFFSET_SECOND, Register};
 use axum::body::Body;
 use axum::response::Response;
+use base::bytes::{Bytes, BytesMut};
+use base::exception::{GlobalResult, GlobalResultExt};
+use base::log::error;
+use base::tokio::sync::{broadcast, oneshot};
+use base::tokio::time::timeout;
+use shared::info::output::OutputEnum;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
 
-pub async fn m3u8_handler() -> Response<Body> {
-    unimplemented!()
+/// HLS 播放列表：`{stream_id}.ll.m3u8` 返回LL-HLS(CMAF)列表，`{stream_id}.m3u8` 返回HLS-TS列表
+/// hls_msn/hls_part 为LL-HLS阻塞刷新参数(_HLS_msn/_HLS_part)
+pub async fn m3u8_handler(
+    stream_id: Arc<str>,
+    token: Arc<str>,
+    addr: SocketAddr,
+    low_latency: bool,
+    hls_msn: Option<String>,
+    hls_part: Option<String>,
+) -> Response<Body> {
+    match Register::get_base_stream_info_by_stream_id(stream_id.clone()) {
+        None => res_404(),
+        Some(bsi) => {
+            let ssrc = bsi.rtp_info.ssrc;
+            let output = playlist_output(
+                low_latency,
+                Register::get_muxer_rx(&ssrc, MuxerEnum::HlsTs).is_ok(),
+                Register::get_muxer_rx(&ssrc, MuxerEnum::HlsMp4).is_ok(),
+            );
+            if output == OutputEnum::HlsFmp4 {
+                let msn = hls_msn.and_then(|v| v.parse::<usize>().ok());
+                let part = hls_part.and_then(|v| v.parse::<usize>().ok());
+                return match stream_user_token_check(
+                    OutputEnum::HlsFmp4,
+                    bsi,
+                    stream_id.clone(),
+                    token.clone(),
+                    addr,
+                )
+                .await
+                {
+                    OutPlayKind::Play => {
+                        Register::listen_output_timeout(
+                            stream_id.clone(),
+                            OutputEnum::HlsFmp4,
+                            token.clone(),
+                            addr,
+                            DEFAULT_OFFSET_SECOND,
+                        );
+                        // 阻塞刷新：直到列表包含请求的msn/part
+                        let playlist = match msn {
+                            None => {
+                                wait_mp4_playlist(ssrc, |list| {
+                                    list.iter().any(|s| !s.parts.is_empty())
+                                })
+                                .await
+                            }
+                            Some(msn) => {
+                                wait_mp4_playlist(ssrc, |list| playlist_contains(list, msn, part))
+                                    .await
+                            }
+                        };
+                        match playlist {
+                            Some(list) => Response::builder()
+                                .header("Content-Type", "application/vnd.apple.mpegurl")
+                                .header("Cache-Control", "no-cache")
+                                .body(Body::from(generate_ll_m3u8(&stream_id, &token, &list)))
+                                .unwrap(),
+                            None => res_404(),
+                        }
+                    }
+                    OutPlayKind::Forbid => res_401(),
+                    OutPlayKind::Notfound => res_404(),
+                };
+            }
+            match stream_user_token_check(
+                OutputEnum::HlsTs,
+                bsi,
+                stream_id.clone(),
+                token.clone(),
+                addr,
+            )
+            .await
+            {
+                OutPlayKind::Play => {
+                    // playlist 按轮询拉取，每次请求续期
+                    Register::listen_output_timeout(
+                        stream_id.clone(),
+                        OutputEnum::HlsTs,
+                        token.clone(),
+                        addr,
+                        DEFAULT_OFFSET_SECOND,
+                    );
+                    let mut segments = get_ts_segments(ssrc).await.unwrap_or_default();
+                    if segments.is_empty() {
+                        if let Ok(mut rx) = Register::get_muxer_rx(&ssrc, MuxerEnum::HlsTs) {
+                            let _ = timeout(DEFAULT_EXPIRES, wait_segment(&mut rx, 1)).await;
+                        }
+                        segments = get_ts_segments(ssrc).await.unwrap_or_default();
+                    }
+                    if segments.is_empty() {
+                        return res_404();
+                    }
+                    Response::builder()
+                        .header("Content-Type", "application/vnd.apple.mpegurl")
+                        .header("Cache-Control", "no-cache")
+                        .body(Body::from(generate_m3u8(&stream_id, &token, &segments)))
+                        .unwrap()
+                }
+                OutPlayKind::Forbid => res_401(),
+                OutPlayKind::Notfound => res_404(),
+            }
+        }
+    }
 }
 
-pub async fn segment_ts_handler() -> Response<Body> {
-    unimplemented!()
+/// HLS-TS 切片：seg为切片序号，请求尚未生成的下一切片时阻塞等待
+pub async fn segment_ts_handler(
+    stream_id: Arc<str>,
+    token: Arc<str>,
+    addr: SocketAddr,
+    seg: Option<String>,
+) -> Response<Body> {
+    let Some(seq) = seg.and_then(|seg| seg.parse::<usize>().ok()) else {
+        return res_404();
+    };
+    match Register::get_base_stream_info_by_stream_id(stream_id.clone()) {
+        None => res_404(),
+        Some(bsi) => {
+            let ssrc = bsi.rtp_info.ssrc;
+            match stream_user_token_check(
+                OutputEnum::HlsTs,
+                bsi,
+                stream_id.clone(),
+                token.clone(),
+                addr,
+            )
+            .await
+            {
+                OutPlayKind::Play => {}
+                OutPlayKind::Forbid => return res_401(),
+                OutPlayKind::Notfound => return res_404(),
+            }
+            Register::listen_output_timeout(
+                stream_id,
+                OutputEnum::HlsTs,
+                token,
+                addr,
+                DEFAULT_OFFSET_SECOND,
+            );
+            let mut rx = match Register::get_muxer_rx(&ssrc, MuxerEnum::HlsTs) {
+                Ok(rx) => rx,
+                Err(_) => return res_404(),
+            };
+            let segments = get_ts_segments(ssrc).await.unwrap_or_default();
+            if let Some(segment) = segments.iter().find(|s| s.seq == seq) {
+                return ts_response(segment.data.clone());
+            }
+            let latest = segments.last().map(|s| s.seq).unwrap_or(0);
+            // 仅等待紧邻的下一切片，过期或过远的序号直接404
+            if seq != latest + 1 {
+                return res_404();
+            }
+            match timeout(DEFAULT_EXPIRES, wait_segment(&mut rx, seq)).await {
+                Ok(Some(data)) => ts_response(data),
+                _ => res_404(),
+            }
+        }
+    }
 }
-pub async fn segment_mp4_handler() -> Response<Body> {
-    unimplemented!()
+
+/// LL-HLS 资源：seg=init 为EXT-X-MAP初始化段；msn+part为part；仅msn为完整segment
+/// 请求预加载提示(preload hint)的part时阻塞等待其生成
+pub async fn segment_mp4_handler(
+    stream_id: Arc<str>,
+    token: Arc<str>,
+    addr: SocketAddr,
+    seg: Option<String>,
+    msn: Option<String>,
+    part: Option<String>,
+) -> Response<Body> {
+    match Register::get_base_stream_info_by_stream_id(stream_id.clone()) {
+        None => res_404(),
+        Some(bsi) => {
+            let ssrc = bsi.rtp_info.ssrc;
+            match stream_user_token_check(
+                OutputEnum::HlsFmp4,
+                bsi,
+                stream_id.clone(),
+                token.clone(),
+                addr,
+            )
+            .await
+            {
+                OutPlayKind::Play => {}
+                OutPlayKind::Forbid => return res_401(),
+                OutPlayKind::Notfound => return res_404(),
+            }
+            Register::listen_output_timeout(
+                stream_id,
+                OutputEnum::HlsFmp4,
+                token,
+                addr,
+                DEFAULT_OFFSET_SECOND,
+            );
+            if seg.as_deref() == Some("init") {
+                return match get_hls_mp4_init(ssrc).await {
+                    Ok(init) => Response::builder()
+                        .header("Content-Type", "video/mp4")
+                        .header("Cache-Control", "max-age=3600")
+                        .body(Body::from(init))
+                        .unwrap(),
+                    Err(_) => res_404(),
+                };
+            }
+            let Some(msn) = msn.and_then(|v| v.parse::<usize>().ok()) else {
+                return res_404();
+            };
+            let part = part.and_then(|v| v.parse::<usize>().ok());
+            let Some(list) =
+                wait_mp4_playlist(ssrc, |list| playlist_contains(list, msn, part)).await
+            else {
+                return res_404();
+            };
+            let Some(segment) = list.iter().find(|s| s.msn == msn) else {
+                return res_404();
+            };
+            let data = match part {
+                Some(part) => match segment.parts.get(part) {
+                    Some(p) => p.data.clone(),
+                    None => return res_404(),
+                },
+                None => {
+                    let mut out = BytesMut::new();
+                    for p in &segment.parts {
+                        out.extend_from_slice(&p.data);
+                    }
+                    out.freeze()
+                }
+            };
+            Response::builder()
+                .header("Content-Type", "video/mp4")
+                .header("Cache-Control", "max-age=60")
+                .body(Body::from(data))
+                .unwrap()
+        }
+    }
+}
+
+/// 按请求的列表选择输出：仅开启HlsFmp4时 `.m3u8` 回退为LL-HLS，兼容旧播放地址
+fn playlist_output(low_latency: bool, ts_active: bool, mp4_active: bool) -> OutputEnum {
+    if low_latency || (mp4_active && !ts_active) {
+        OutputEnum::HlsFmp4
+    } else {
+        OutputEnum::HlsTs
+    }
+}
+
+fn ts_response(data: Bytes) -> Response<Body> {
+    Response::builder()
+        .header("Content-Type", "video/mp2t")
+        .header("Cache-Control", "max-age=60")
+        .body(Body::from(data))
+        .unwrap()
+}
+
+async fn wait_segment(rx: &mut broadcast::Receiver<Arc<MuxPacket>>, seq: usize) -> Option<Bytes> {
+    loop {
+        match rx.recv().await {
+            Ok(pkt) if pkt.seq == seq => return Some(pkt.data.clone()),
+            Ok(pkt) if pkt.seq > seq => return None,
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+//msn(及part)是否已生成：后续segment存在即表示该segment已完整
+fn playlist_contains(list: &[HlsMp4Segment], msn: usize, part: Option<usize>) -> bool {
+    list.iter().any(|s| {
+        s.msn > msn
+            || match part {
+                Some(part) => s.msn == msn && s.parts.len() > part,
+                None => false,
+            }
+    })
+}
+
+async fn wait_mp4_playlist<F>(ssrc: u32, pred: F) -> Option<Vec<HlsMp4Segment>>
+where
+    F: Fn(&[HlsMp4Segment]) -> bool,
+{
+    // 先订阅再取列表，避免错过两者之间生成的part
+    let mut rx = Register::get_muxer_rx(&ssrc, MuxerEnum::HlsMp4).ok()?;
+    timeout(DEFAULT_EXPIRES, async {
+        loop {
+            let list = get_mp4_playlist(ssrc).await.ok()?;
+            if pred(&list) {
+                return Some(list);
+            }
+            match rx.recv().await {
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+async fn get_mp4_playlist(ssrc: u32) -> GlobalResult<Vec<HlsMp4Segment>> {
+    let (tx, rx) = oneshot::channel();
+    Register::try_publish_mpsc(ssrc, ContextEvent::Inner(InnerEvent::HlsMp4Playlist(tx)))?;
+    Ok(rx.await.hand_log(|msg| error!("{msg}"))?)
+}
+
+async fn get_hls_mp4_init(ssrc: u32) -> GlobalResult<Bytes> {
+    let (tx, rx) = oneshot::channel();
+    Register::try_publish_mpsc(ssrc, ContextEvent::Inner(InnerEvent::HlsMp4Header(tx)))?;
+    Ok(rx.await.hand_log(|msg| error!("{msg}"))?)
+}
+
+fn generate_ll_m3u8(stream_id: &str, token: &str, list: &[HlsMp4Segment]) -> String {
+    let segments: Vec<&HlsMp4Segment> = list.iter().filter(|s| !s.parts.is_empty()).collect();
+    let part_target = segments
+        .iter()
+        .flat_map(|s| s.parts.iter())
+        .map(|p| p.duration)
+        .fold(0.5f64, f64::max);
+    let target = segments
+        .iter()
+        .map(|s| s.duration.ceil() as u64)
+        .max()
+        .unwrap_or(2)
+        .max(1);
+    let mut m3u8 = String::new();
+    m3u8.push_str("#EXTM3U\n#EXT-X-VERSION:6\n");
+    let _ = writeln!(m3u8, "#EXT-X-TARGETDURATION:{}", target);
+    let _ = writeln!(
+        m3u8,
+        "#EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES,PART-HOLD-BACK={:.3}",
+        part_target * 3.0
+    );
+    let _ = writeln!(m3u8, "#EXT-X-PART-INF:PART-TARGET={:.3}", part_target);
+    let first_msn = segments
+        .first()
+        .map(|s| s.msn)
+        .or_else(|| list.last().map(|s| s.msn))
+        .unwrap_or(0);
+    let _ = writeln!(m3u8, "#EXT-X-MEDIA-SEQUENCE:{}", first_msn);
+    let _ = writeln!(
+        m3u8,
+        "#EXT-X-MAP:URI=\"{}.hmp4?seg=init&gmv-token={}\"",
+        stream_id, token
+    );
+    // 仅最近的segment列出part，较早的segment只保留完整分段
+    let part_from = segments.len().saturating_sub(3);
+    let mut epoch = segments.first().map(|s| s.epoch);
+    for (i, segment) in segments.iter().enumerate() {
+        if epoch != Some(segment.epoch) {
+            epoch = Some(segment.epoch);
+            m3u8.push_str("#EXT-X-DISCONTINUITY\n");
+        }
+        if i >= part_from {
+            for (j, part) in segment.parts.iter().enumerate() {
+                let _ = writeln!(
+                    m3u8,
+                    "#EXT-X-PART:DURATION={:.3},URI=\"{}.hmp4?msn={}&part={}&gmv-token={}\"{}",
+                    part.duration,
+                    stream_id,
+                    segment.msn,
+                    j,
+                    token,
+                    if part.independent {
+                        ",INDEPENDENT=YES"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+        if segment.complete {
+            let _ = writeln!(m3u8, "#EXTINF:{:.3},", segment.duration);
+            let _ = writeln!(
+                m3u8,
+                "{}.hmp4?msn={}&gmv-token={}",
+                stream_id, segment.msn, token
+            );
+        }
+    }
+    if let Some(current) = list.last().filter(|s| !s.complete) {
+        let _ = writeln!(
+            m3u8,
+            "#EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"{}.hmp4?msn={}&part={}&gmv-token={}\"",
+            stream_id,
+            current.msn,
+            current.parts.len(),
+            token
+        );
+    }
+    m3u8
+}
+
+async fn get_ts_segments(ssrc: u32) -> GlobalResult<Vec<Arc<HlsTsSegment>>> {
+    let (tx, rx) = oneshot::channel();
+    Register::try_publish_mpsc(ssrc, ContextEvent::Inner(InnerEvent::HlsTsSegments(tx)))?;
+    Ok(rx.await.hand_log(|msg| error!("{msg}"))?)
+}
+
+fn generate_m3u8(stream_id: &str, token: &str, segments: &[Arc<HlsTsSegment>]) -> String {
+    let target = segments
+        .iter()
+        .map(|s| s.duration.ceil() as u64)
+        .max()
+        .unwrap_or(2);
+    let mut m3u8 = String::new();
+    m3u8.push_str("#EXTM3U\n#EXT-X-VERSION:3\n");
+    let _ = writeln!(m3u8, "#EXT-X-TARGETDURATION:{}", target);
+    let _ = writeln!(m3u8, "#EXT-X-MEDIA-SEQUENCE:{}", segments[0].seq);
+    let mut epoch = segments[0].epoch;
+    for segment in segments {
+        if segment.epoch != epoch {
+            epoch = segment.epoch;
+            m3u8.push_str("#EXT-X-DISCONTINUITY\n");
+        }
+        let _ = writeln!(m3u8, "#EXTINF:{:.3},", segment.duration);
+        let _ = writeln!(
+            m3u8,
+            "{}.ts?seg={}&gmv-token={}",
+            stream_id, segment.seq, token
+        );
+    }
+    m3u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::context::format::hlsfmp4::HlsPart;
+    use std::time::Instant;
+
+    fn ts_segment(seq: usize, epoch: Instant) -> Arc<HlsTsSegment> {
+        Arc::new(HlsTsSegment {
+            seq,
+            duration: 2.0,
+            data: Bytes::new(),
+            epoch,
+        })
+    }
+
+    #[test]
+    fn m3u8_media_sequence_follows_first_segment() {
+        let epoch = Instant::now();
+        let segments: Vec<_> = (5..=7).map(|seq| ts_segment(seq, epoch)).collect();
+        let m3u8 = generate_m3u8("s1", "t1", &segments);
+        assert!(m3u8.contains("#EXT-X-MEDIA-SEQUENCE:5\n"));
+        assert!(m3u8.contains("#EXT-X-TARGETDURATION:2\n"));
+        let uris: Vec<&str> = m3u8.lines().filter(|l| l.starts_with("s1.ts")).collect();
+        assert_eq!(
+            uris,
+            vec![
+                "s1.ts?seg=5&gmv-token=t1",
+                "s1.ts?seg=6&gmv-token=t1",
+                "s1.ts?seg=7&gmv-token=t1"
+            ]
+        );
+        assert!(!m3u8.contains("#EXT-X-DISCONTINUITY"));
+    }
+
+    #[test]
+    fn m3u8_marks_discontinuity_on_epoch_change() {
+        let first = Instant::now();
+        let second = first + std::time::Duration::from_secs(1);
+        let segments = vec![ts_segment(1, first), ts_segment(2, second)];
+        let m3u8 = generate_m3u8("s1", "t1", &segments);
+        let lines: Vec<&str> = m3u8.lines().collect();
+        let pos = lines
+            .iter()
+            .position(|l| *l == "#EXT-X-DISCONTINUITY")
+            .unwrap();
+        assert_eq!(lines[pos + 2], "s1.ts?seg=2&gmv-token=t1");
+    }
+
+    fn mp4_segment(msn: usize, parts: usize, complete: bool, epoch: Instant) -> HlsMp4Segment {
+        HlsMp4Segment {
+            msn,
+            duration: parts as f64,
+            parts: (0..parts)
+                .map(|i| {
+                    Arc::new(HlsPart {
+                        duration: 1.0,
+                        independent: i == 0,
+                        data: Bytes::new(),
+                    })
+                })
+                .collect(),
+            epoch,
+            complete,
+        }
+    }
+
+    #[test]
+    fn ll_m3u8_lists_parts_for_recent_segments() {
+        let epoch = Instant::now();
+        let mut list: Vec<_> = (3..8).map(|msn| mp4_segment(msn, 2, true, epoch)).collect();
+        list.push(mp4_segment(8, 1, false, epoch));
+        let m3u8 = generate_ll_m3u8("s1", "t1", &list);
+        assert!(m3u8.contains("#EXT-X-MEDIA-SEQUENCE:3\n"));
+        assert!(m3u8.contains("#EXT-X-PART-INF:PART-TARGET=1.000\n"));
+        // 仅最近3个segment列出part
+        for msn in 3..6 {
+            assert!(!m3u8.contains(&format!("msn={msn}&part=")));
+        }
+        assert!(m3u8.contains(
+            "#EXT-X-PART:DURATION=1.000,URI=\"s1.hmp4?msn=6&part=0&gmv-token=t1\",INDEPENDENT=YES"
+        ));
+        assert!(
+            m3u8.contains("#EXT-X-PART:DURATION=1.000,URI=\"s1.hmp4?msn=7&part=1&gmv-token=t1\"\n")
+        );
+        assert!(m3u8.contains("s1.hmp4?msn=8&part=0&gmv-token=t1"));
+        // 未完成的segment不输出EXTINF
+        assert!(m3u8.contains("s1.hmp4?msn=7&gmv-token=t1"));
+        assert!(!m3u8.contains("s1.hmp4?msn=8&gmv-token=t1"));
+        assert!(m3u8.ends_with(
+            "#EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"s1.hmp4?msn=8&part=1&gmv-token=t1\"\n"
+        ));
+    }
+
+    #[test]
+    fn ll_m3u8_omits_preload_hint_without_open_segment() {
+        let epoch = Instant::now();
+        let list = vec![mp4_segment(1, 2, true, epoch)];
+        let m3u8 = generate_ll_m3u8("s1", "t1", &list);
+        assert!(!m3u8.contains("#EXT-X-PRELOAD-HINT"));
+    }
+
+    #[test]
+    fn playlist_routes_by_requested_output() {
+        // 两种muxer同时开启：各自的列表互不抢占
+        assert_eq!(playlist_output(false, true, true), OutputEnum::HlsTs);
+        assert_eq!(playlist_output(true, true, true), OutputEnum::HlsFmp4);
+        // 仅开启其一
+        assert_eq!(playlist_output(false, true, false), OutputEnum::HlsTs);
+        assert_eq!(playlist_output(false, false, true), OutputEnum::HlsFmp4);
+        assert_eq!(playlist_output(true, true, false), OutputEnum::HlsFmp4);
+        // 均未开启时按 HLS-TS 鉴权按需拉起
+        assert_eq!(playlist_output(false, false, false), OutputEnum::HlsTs);
+    }
+
+    #[test]
+    fn blocking_reload_waits_for_requested_part() {
+        let epoch = Instant::now();
+        let list = vec![
+            mp4_segment(4, 2, true, epoch),
+            mp4_segment(5, 1, false, epoch),
+        ];
+        // 已生成的part
+        assert!(playlist_contains(&list, 5, Some(0)));
+        // 预加载提示的part尚未生成
+        assert!(!playlist_contains(&list, 5, Some(1)));
+        // 完整segment：存在后续segment即已完成
+        assert!(playlist_contains(&list, 4, None));
+        assert!(playlist_contains(&list, 4, Some(3)));
+        // 当前segment尚未完成
+        assert!(!playlist_contains(&list, 5, None));
+        assert!(!playlist_contains(&list, 6, Some(0)));
+    }
 }
@@ -9,7 +9,7 @@ use base::bytes::Bytes;
 use base::log::{debug, info, warn};
 use base::tokio::sync::oneshot;
 use futures_core::Stream;
-use shared::info::obj::{BaseStreamInfo, PLAY_PATH, StreamPlayInfo};
+use shared::info::obj::{BaseStreamInfo, PLAY_PATH, StreamPlayInfo, WHEP_PATH, WHEP_SESSION_PATH};
 use shared::info::output::OutputEnum;
 use std::collections::HashMap;
 use std::net::SocketAddr;
@@ -22,9 +22,13 @@ use std::task::{Context, Poll};
 mod dash;
 mod flv;
 mod hls;
+pub(crate) mod whep;
 //收到流-》media 长期阻塞 ——》无输出流
 pub fn routes() -> Router {
-    Router::new().route(PLAY_PATH, axum::routing::get(handler))
+    Router::new()
+        .route(PLAY_PATH, axum::routing::get(handler))
+        .route(WHEP_PATH, axum::routing::post(whep::offer))
+        .route(WHEP_SESSION_PATH, axum::routing::delete(whep::delete))
 }
 
 #[cfg_attr(
@@ -62,15 +66,37 @@ async fn handler(
     match stream_id.rsplit_once('.') {
         None => res_404(),
         Some((id, tp)) => {
-            let id = Arc::from(id);
+            let id: Arc<str> = Arc::from(id);
             match tp {
                 "flv" => {
                     info!("flv stream play:stream_id: {}, param: {:?}", stream_id, map);
                     flv::handler(id, token, addr).await
                 }
-                "m3u8" => hls::m3u8_handler().await,
-                "hmp4" => hls::segment_mp4_handler().await,
-                "ts" => hls::segment_ts_handler().await,
+                "m3u8" => {
+                    debug!(
+                        "hls playlist play:stream_id: {}, param: {:?}",
+                        stream_id, map
+                    );
+                    let msn = map.remove("_HLS_msn");
+                    let part = map.remove("_HLS_part");
+                    // {stream_id}.ll.m3u8 为LL-HLS列表
+                    let (id, low_latency) = match id.strip_suffix(".ll") {
+                        Some(id) => (Arc::from(id), true),
+                        None => (id, false),
+                    };
+                    hls::m3u8_handler(id, token, addr, low_latency, msn, part).await
+                }
+                "hmp4" => {
+                    debug!(
+                        "ll-hls resource play:stream_id: {}, param: {:?}",
+                        stream_id, map
+                    );
+                    let seg = map.remove("seg");
+                    let msn = map.remove("msn");
+                    let part = map.remove("part");
+                    hls::segment_mp4_handler(id, token, addr, seg, msn, part).await
+                }
+                "ts" => hls::segment_ts_handler(id, token, addr, map.remove("seg")).await,
                 "mpd" => {
                     debug!(
                         "mpeg dash mpd stream play:stream_id: {}, param: {:?}",
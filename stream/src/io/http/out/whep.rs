@@ -0,0 +1,189 @@
+use crate::io::http::out::{OutPlayKind, stream_user_token_check};
+use crate::io::http::{res_401, res_404};
+use crate::io::webrtc::{self, WhepPeer};
+use crate::media::context::event::ContextEvent;
+use crate::media::context::event::inner::InnerEvent;
+use crate::media::context::format::muxer::MuxerEnum;
+use crate::media::context::format::rtp::RtpEncTracks;
+use crate::state::register::{DEFAULT_EXPIRES, Register};
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Path, Query};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::Response;
+use base::err::{BaseErrorCode, CodeOutErr};
+use base::exception::{BizError, GlobalError, GlobalResult, GlobalResultExt};
+use base::log::{error, info};
+use base::tokio::sync::oneshot;
+use base::tokio::time::timeout;
+use shared::info::output::OutputEnum;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        post,
+        path = "/whep/{stream_id}",
+        request_body(content = String, content_type = "application/sdp", description = "SDP offer"),
+        params(
+            ("stream_id" = String, Path, description = "流 ID"),
+            ("gmv-token" = Option<String>, Query, description = "认证 token；也可通过 Authorization: Bearer 传递", example = "tkn_xyz789")
+        ),
+        responses(
+            (status = 201, description = "协商成功，Location 为会话地址", body = String, content_type = "application/sdp"),
+            (status = 400, description = "SDP offer 无效"),
+            (status = 401, description = "gmv-token 无效"),
+            (status = 404, description = "流未找到"),
+            (status = 406, description = "播放端不支持流的视频编码，或源音频非 G.711"),
+            (status = 503, description = "WebRTC 服务未开启")
+        ),
+        tag = "HTTP播放音视频"
+    )
+)]
+/// WHEP 播放：提交 SDP offer，返回 answer
+pub async fn offer(
+    Path(stream_id): Path<String>,
+    Query(map): Query<HashMap<String, String>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: String,
+) -> Response<Body> {
+    if !webrtc::enabled() {
+        return Response::builder()
+            .header(header::CONTENT_TYPE, "text/plain")
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(Body::from("503 Service Unavailable"))
+            .unwrap();
+    }
+    let Some(token) = request_token(&map, &headers) else {
+        return res_401();
+    };
+    let stream_id: Arc<str> = Arc::from(stream_id);
+    let token: Arc<str> = Arc::from(token);
+    let Some(bsi) = Register::get_base_stream_info_by_stream_id(stream_id.clone()) else {
+        return res_404();
+    };
+    let ssrc = bsi.rtp_info.ssrc;
+    match stream_user_token_check(
+        OutputEnum::WebRtc,
+        bsi,
+        stream_id.clone(),
+        token.clone(),
+        addr,
+    )
+    .await
+    {
+        OutPlayKind::Play => {}
+        OutPlayKind::Forbid => return res_401(),
+        OutPlayKind::Notfound => return res_404(),
+    }
+    let peer = WhepPeer {
+        stream_id: stream_id.clone(),
+        token: token.clone(),
+        addr,
+    };
+    let prepared = match Register::get_muxer_rx(&ssrc, MuxerEnum::RtpEnc) {
+        Ok(rx) => get_tracks(ssrc).await.map(|tracks| (rx, tracks)),
+        Err(err) => Err(err),
+    };
+    let res = match prepared {
+        Ok((rx, tracks)) => webrtc::answer(peer, tracks, body, rx).await,
+        Err(err) => Err(err),
+    };
+    match res {
+        Ok((session_id, sdp)) => Response::builder()
+            .status(StatusCode::CREATED)
+            .header(header::CONTENT_TYPE, "application/sdp")
+            .header(header::LOCATION, format!("/whep/{stream_id}/{session_id}"))
+            .body(Body::from(sdp))
+            .unwrap(),
+        Err(err) => {
+            //未建立会话，按断开处理
+            Register::listen_output_timeout(stream_id, OutputEnum::WebRtc, token, addr, 0);
+            res_by_error(err)
+        }
+    }
+}
+
+#[cfg_attr(
+    debug_assertions,
+    utoipa::path(
+        delete,
+        path = "/whep/{stream_id}/{session_id}",
+        params(
+            ("stream_id" = String, Path, description = "流 ID"),
+            ("session_id" = String, Path, description = "WHEP 会话 ID"),
+            ("gmv-token" = Option<String>, Query, description = "认证 token；也可通过 Authorization: Bearer 传递", example = "tkn_xyz789")
+        ),
+        responses(
+            (status = 200, description = "会话已关闭"),
+            (status = 401, description = "缺少 gmv-token"),
+            (status = 404, description = "会话不存在")
+        ),
+        tag = "HTTP播放音视频"
+    )
+)]
+/// WHEP 结束播放
+pub async fn delete(
+    Path((stream_id, session_id)): Path<(String, String)>,
+    Query(map): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response<Body> {
+    let Some(token) = request_token(&map, &headers) else {
+        return res_401();
+    };
+    if webrtc::close(&stream_id, &session_id, &token) {
+        info!(
+            "whep session {} delete: stream_id={}",
+            session_id, stream_id
+        );
+        Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap()
+    } else {
+        res_404()
+    }
+}
+
+/// 优先取 gmv-token 参数，其次为 WHEP 约定的 Bearer token
+fn request_token(map: &HashMap<String, String>, headers: &HeaderMap) -> Option<String> {
+    map.get("gmv-token").cloned().or_else(|| {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|v| v.trim().to_string())
+    })
+}
+
+async fn get_tracks(ssrc: u32) -> GlobalResult<RtpEncTracks> {
+    let (tx, rx) = oneshot::channel();
+    Register::try_publish_mpsc(ssrc, ContextEvent::Inner(InnerEvent::RtpEncTracks(tx)))?;
+    let tracks = timeout(DEFAULT_EXPIRES, rx)
+        .await
+        .hand_log(|msg| error!("{msg}"))?
+        .hand_log(|msg| error!("{msg}"))?;
+    Ok(tracks)
+}
+
+fn res_by_error(err: GlobalError) -> Response<Body> {
+    let status = match &err {
+        GlobalError::BizErr(BizError { code, .. }) => {
+            if *code == BaseErrorCode::InvalidRequest.code() {
+                StatusCode::BAD_REQUEST
+            } else if *code == BaseErrorCode::Unsupported.code() {
+                StatusCode::NOT_ACCEPTABLE
+            } else {
+                StatusCode::NOT_FOUND
+            }
+        }
+        GlobalError::SysErr(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/plain")
+        .status(status)
+        .body(Body::from(err.out_err().into_owned()))
+        .unwrap()
+}
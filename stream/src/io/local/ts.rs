@@ -1 +1,271 @@
+use crate::media::context::event::ContextEvent;
+use crate::media::context::event::inner::InnerEvent;
+use crate::media::context::format::MuxPacket;
+use crate::state::event::{Event, EventRes, OutEvent};
+use crate::state::register::Register;
+use base::bus::mpsc::TypedReceiver;
+use base::exception::{GlobalResult, GlobalResultExt};
+use base::log::{error, warn};
+use base::tokio;
+use base::tokio::fs;
+use base::tokio::fs::File;
+use base::tokio::io::AsyncWriteExt;
+use base::tokio::sync::{broadcast, mpsc, oneshot};
+use shared::enums::OptAction;
+use shared::info::obj::StreamRecordInfo;
+use shared::info::output::OutputEnum;
+use std::path::Path;
+use std::sync::Arc;
 
+pub enum TsOutputInnerEvent {
+    StoreInfo(oneshot::Sender<StreamRecordInfo>), //获取当前录制信息
+    Close,                                        //用于主动关闭录制
+}
+
+/// TS 逐包落盘，进程异常退出时已写入部分仍可播放
+pub struct LocalStoreTsContext {
+    pub path: String,
+    pub ssrc: u32,
+
+    pub file_name: Arc<str>,                         //stream_id
+    pub pkt_rx: broadcast::Receiver<Arc<MuxPacket>>, //数据接收端，当发送端drop，即录制完成
+    pub record_event_tx: mpsc::Sender<(Event, Option<oneshot::Sender<EventRes>>)>, //用于主动发送录制报错、录制结束
+    pub inner_event_rx: TypedReceiver<TsOutputInnerEvent>, //获取当前录制信息
+    pub file_size: usize,
+    pub ts: u64,   //second
+    pub state: u8, //录制状态，0=进行，1=完成，2=录制部分，3=失败
+}
+
+impl LocalStoreTsContext {
+    pub fn store(mut self) {
+        tokio::spawn(async move {
+            Register::handle_stream_metadata_map_output(
+                OptAction::Insert,
+                &self.file_name,
+                OutputEnum::LocalTs,
+            );
+            let path_file_name = Some(format!("{}/ts/{}.ts", self.path, self.file_name));
+            let info = match self.run().await {
+                Ok(_) => StreamRecordInfo {
+                    path_file_name,
+                    file_size: self.file_size as u64,
+                    timestamp: self.ts as u32,
+                    state: 1,
+                },
+                Err(_) => StreamRecordInfo {
+                    path_file_name,
+                    state: 3,
+                    ..Default::default()
+                },
+            };
+            let _ = self
+                .record_event_tx
+                .send((Event::Out(OutEvent::EndRecord(info)), None))
+                .await
+                .hand_log(|msg| error!("{msg}"));
+            Register::handle_stream_metadata_map_output(
+                OptAction::Remove,
+                &self.file_name,
+                OutputEnum::LocalTs,
+            );
+        });
+    }
+
+    async fn run(&mut self) -> GlobalResult<()> {
+        // 1. 创建目录
+        let dir_path = Path::new(&self.path).join("ts");
+        fs::create_dir_all(&dir_path)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+
+        // 2. 创建文件
+        let file_path = dir_path.join(self.file_name.as_ref()).with_extension("ts");
+        let mut file = fs::File::create(&file_path)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+
+        // 3. 处理第一个关键帧,并写入 PAT/PMT
+        if !self.handle_first_key_frame(&mut file).await? {
+            return Ok(());
+        }
+
+        // 4. 持续接收数据包写入 + 监听录制过程信息获取事件
+        loop {
+            tokio::select! {
+                pkt_opt = self.pkt_rx.recv() => {
+                    match pkt_opt {
+                        Ok(pkt) => self.write_pkt(&mut file, &pkt).await?,
+                        //TS 可从后续包自行同步，丢包不中断录制
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("local ts store lagged: stream_id={}, skipped={n}", self.file_name);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,//发送端drop，录制结束
+                    }
+                }
+                inner_event_res = self.inner_event_rx.recv() => {
+                    if let Ok(inner_event) = inner_event_res {
+                        if !self.handle_inner_event(inner_event) {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        file.flush().await.hand_log(|msg| error!("{msg}"))?;
+        Ok(())
+    }
+
+    /// 返回 false 表示录制在首个关键帧前结束
+    async fn handle_first_key_frame(&mut self, file: &mut File) -> GlobalResult<bool> {
+        loop {
+            tokio::select! {
+                pkt_opt = self.pkt_rx.recv() => {
+                    match pkt_opt {
+                        Ok(pkt) => {
+                            if pkt.is_key {
+                                let (tx, rx) = oneshot::channel();
+                                Register::try_publish_mpsc(self.ssrc, ContextEvent::Inner(InnerEvent::TsHeader(tx)))?;
+                                let header = rx.await.hand_log(|msg| error!("{msg}"))?;
+                                file.write_all(&header).await.hand_log(|msg| error!("{msg}"))?;
+                                self.file_size += header.len();
+                                self.write_pkt(file, &pkt).await?;
+                                return Ok(true);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return Ok(false),
+                    }
+                }
+                inner_event_res = self.inner_event_rx.recv() => {
+                    if let Ok(inner_event) = inner_event_res {
+                        if !self.handle_inner_event(inner_event) {
+                            return Ok(false);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn write_pkt(&mut self, file: &mut File, pkt: &MuxPacket) -> GlobalResult<()> {
+        file.write_all(&pkt.data)
+            .await
+            .hand_log(|msg| error!("{msg}"))?;
+        //trailer 包不携带时间戳
+        if pkt.timestamp > 0 {
+            self.ts = pkt.timestamp;
+        }
+        self.file_size += pkt.data.len();
+        Ok(())
+    }
+
+    /// 返回 false 表示关闭录制
+    fn handle_inner_event(&self, inner_event: TsOutputInnerEvent) -> bool {
+        match inner_event {
+            TsOutputInnerEvent::StoreInfo(record_info_tx) => {
+                let info = StreamRecordInfo {
+                    path_file_name: None,
+                    file_size: self.file_size as u64,
+                    timestamp: self.ts as u32,
+                    state: self.state,
+                };
+                let _ = record_info_tx.send(info);
+                true
+            }
+            TsOutputInnerEvent::Close => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::bus::mpsc::TypedMessageBus;
+    use base::bytes::Bytes;
+    use std::time::Instant;
+
+    fn context(path: &Path) -> (LocalStoreTsContext, broadcast::Sender<Arc<MuxPacket>>) {
+        let (pkt_tx, pkt_rx) = broadcast::channel(16);
+        let (record_event_tx, _record_event_rx) = mpsc::channel(1);
+        let inner_event_rx = TypedMessageBus::new()
+            .sub_type_channel::<TsOutputInnerEvent>()
+            .unwrap();
+        let context = LocalStoreTsContext {
+            path: path.to_string_lossy().to_string(),
+            ssrc: 1,
+            file_name: Arc::from("ts-test"),
+            pkt_rx,
+            record_event_tx,
+            inner_event_rx,
+            file_size: 0,
+            ts: 0,
+            state: 0,
+        };
+        (context, pkt_tx)
+    }
+
+    fn packet(len: usize, timestamp: u64) -> MuxPacket {
+        MuxPacket {
+            data: Bytes::from(vec![0x47; len]),
+            is_key: false,
+            timestamp,
+            epoch: Instant::now(),
+            seq: 0,
+        }
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("gmv-local-ts-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn write_pkt_tracks_size_and_keeps_last_timestamp() {
+        let dir = temp_dir("write");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            fs::create_dir_all(&dir).await.unwrap();
+            let (mut ctx, _pkt_tx) = context(&dir);
+            let file_path = dir.join("out.ts");
+            let mut file = File::create(&file_path).await.unwrap();
+            ctx.write_pkt(&mut file, &packet(188, 40)).await.unwrap();
+            ctx.write_pkt(&mut file, &packet(376, 80)).await.unwrap();
+            //trailer
+            ctx.write_pkt(&mut file, &packet(188, 0)).await.unwrap();
+            file.flush().await.unwrap();
+            assert_eq!(ctx.file_size, 752);
+            assert_eq!(ctx.ts, 80);
+            assert_eq!(fs::metadata(&file_path).await.unwrap().len(), 752);
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn inner_event_reports_progress_and_closes() {
+        let dir = temp_dir("event");
+        let (mut ctx, _pkt_tx) = context(&dir);
+        ctx.file_size = 1024;
+        ctx.ts = 12;
+        let (tx, mut rx) = oneshot::channel();
+        assert!(ctx.handle_inner_event(TsOutputInnerEvent::StoreInfo(tx)));
+        let info = rx.try_recv().unwrap();
+        assert_eq!(info.file_size, 1024);
+        assert_eq!(info.timestamp, 12);
+        assert_eq!(info.state, 0);
+        assert!(!ctx.handle_inner_event(TsOutputInnerEvent::Close));
+    }
+
+    #[test]
+    fn run_ends_when_stream_closes_before_key_frame() {
+        let dir = temp_dir("close");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (mut ctx, pkt_tx) = context(&dir);
+            pkt_tx.send(Arc::new(packet(188, 40))).unwrap();
+            drop(pkt_tx);
+            ctx.run().await.unwrap();
+            assert_eq!(ctx.file_size, 0);
+            assert!(dir.join("ts").join("ts-test.ts").exists());
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,5 +1,9 @@
 pub mod http;
 pub mod local;
+pub mod push;
+pub mod rtcp_handler;
 pub mod rtp_handler;
+pub mod rtsp;
 pub mod splitter;
 pub mod talk;
+pub mod webrtc;
@@ -0,0 +1,301 @@
+use crate::io::talk::build_rtp_packet;
+use crate::media::context::event::ContextEvent;
+use crate::media::context::event::inner::InnerEvent;
+use crate::media::context::format::MuxPacket;
+use crate::media::context::format::rtp::split_payloads;
+use crate::state::event::{Event, EventRes, OutEvent};
+use crate::state::register::Register;
+use base::bytes::{BufMut, Bytes, BytesMut};
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use base::log::{error, info, warn};
+use base::tokio;
+use base::tokio::io::AsyncWriteExt;
+use base::tokio::net::{TcpStream, UdpSocket};
+use base::tokio::sync::broadcast::error::RecvError;
+use base::tokio::sync::{broadcast, mpsc, oneshot};
+use base::tokio_util::sync::CancellationToken;
+use shared::enums::OptAction;
+use shared::info::obj::{PushState, PushStateInfo};
+use shared::info::output::{OutputEnum, RtpPushTarget, RtpTransport};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_PAYLOAD_TYPE: u8 = 96;
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+enum RtpSink {
+    Udp(UdpSocket, SocketAddr),
+    Tcp(TcpStream),
+}
+
+impl RtpSink {
+    async fn connect(addr: SocketAddr, transport: RtpTransport) -> GlobalResult<Self> {
+        match transport {
+            RtpTransport::Udp => {
+                let local = match addr.ip() {
+                    IpAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+                    IpAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+                };
+                let socket = UdpSocket::bind(local)
+                    .await
+                    .hand_log(|msg| error!("{msg}"))?;
+                Ok(RtpSink::Udp(socket, addr))
+            }
+            RtpTransport::Tcp => {
+                let stream = tokio::time::timeout(TCP_CONNECT_TIMEOUT, TcpStream::connect(addr))
+                    .await
+                    .map_err(|_| {
+                        GlobalError::new_biz_error(
+                            BaseErrorCode::Timeout.code(),
+                            "rtp push tcp connect timeout",
+                            |msg| error!("{msg}: target={addr}"),
+                        )
+                    })?
+                    .hand_log(|msg| error!("{msg}"))?;
+                let _ = stream.set_nodelay(true);
+                Ok(RtpSink::Tcp(stream))
+            }
+        }
+    }
+
+    /// 一帧的RTP包：UDP逐包发送，TCP按RFC4571加长度前缀后合并写入
+    async fn send_frame(&mut self, packets: Vec<Vec<u8>>) -> GlobalResult<()> {
+        match self {
+            RtpSink::Udp(socket, addr) => {
+                for packet in packets {
+                    socket
+                        .send_to(&packet, *addr)
+                        .await
+                        .hand_log(|msg| error!("{msg}"))?;
+                }
+            }
+            RtpSink::Tcp(stream) => {
+                let buf = rfc4571_frame(&packets);
+                stream
+                    .write_all(&buf)
+                    .await
+                    .hand_log(|msg| error!("{msg}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 一帧负载打包为RTP：同帧共用时间戳，末包置marker，seq跨帧连续递增
+fn packetize_frame(
+    ssrc: u32,
+    seq: &mut u16,
+    timestamp: u32,
+    payload_type: u8,
+    payloads: &[Bytes],
+) -> Vec<Vec<u8>> {
+    let last = payloads.len().saturating_sub(1);
+    payloads
+        .iter()
+        .enumerate()
+        .map(|(i, payload)| {
+            let packet = build_rtp_packet(ssrc, *seq, timestamp, i == last, payload_type, payload);
+            *seq = seq.wrapping_add(1);
+            packet
+        })
+        .collect()
+}
+
+fn rfc4571_frame(packets: &[Vec<u8>]) -> BytesMut {
+    let len = packets.iter().map(|p| p.len() + 2).sum();
+    let mut buf = BytesMut::with_capacity(len);
+    for packet in packets {
+        buf.put_u16(packet.len() as u16);
+        buf.put_slice(packet);
+    }
+    buf
+}
+
+/// GB28181 RTP 主动推流：PS模式或帧模式
+pub struct Gb28181PushContext {
+    pub stream_id: Arc<str>,
+    pub ssrc: u32,
+    pub output: OutputEnum,
+    pub target: RtpPushTarget,
+    pub pkt_rx: broadcast::Receiver<Arc<MuxPacket>>,
+    pub event_tx: mpsc::Sender<(Event, Option<oneshot::Sender<EventRes>>)>,
+    //运行时移除输出时取消推流
+    pub cancel: CancellationToken,
+}
+
+impl Gb28181PushContext {
+    pub fn push(mut self) {
+        tokio::spawn(async move {
+            Register::handle_stream_metadata_map_output(
+                OptAction::Insert,
+                &self.stream_id,
+                self.output,
+            );
+            let cancel = self.cancel.clone();
+            let res = tokio::select! {
+                res = self.run() => res,
+                _ = cancel.cancelled() => Ok(()),
+            };
+            match res {
+                Ok(_) => {
+                    info!(
+                        "gb28181 push end: stream_id={}, target={}:{}, ssrc={}",
+                        self.stream_id, self.target.ip, self.target.port, self.target.ssrc
+                    );
+                    self.report(PushState::Stopped, None);
+                }
+                Err(err) => {
+                    warn!(
+                        "gb28181 push failed: stream_id={}, target={}:{}, err={}",
+                        self.stream_id, self.target.ip, self.target.port, err
+                    );
+                    self.report(PushState::Failed, Some(err.to_string()));
+                }
+            }
+            Register::handle_stream_metadata_map_output(
+                OptAction::Remove,
+                &self.stream_id,
+                self.output,
+            );
+        });
+    }
+
+    async fn run(&mut self) -> GlobalResult<()> {
+        let ip: IpAddr = self.target.ip.parse().map_err(|_| {
+            GlobalError::new_biz_error(
+                BaseErrorCode::InvalidRequest.code(),
+                "invalid rtp push target ip",
+                |msg| error!("{msg}: ip={}", self.target.ip),
+            )
+        })?;
+        let addr = SocketAddr::new(ip, self.target.port);
+        self.report(PushState::Connecting, None);
+        let mut sink = RtpSink::connect(addr, self.target.transport).await?;
+        info!(
+            "gb28181 push start: stream_id={}, target={}, transport={:?}, ssrc={}",
+            self.stream_id, addr, self.target.transport, self.target.ssrc
+        );
+        // 帧模式单路RTP仅承载视频，音频丢弃时随推流状态上报
+        let reason = match self.output {
+            OutputEnum::Gb28181Frame => skipped_audio(self.ssrc)
+                .await
+                .map(|codec| format!("audio {codec} dropped in frame mode")),
+            _ => None,
+        };
+        self.report(PushState::Pushing, reason);
+        let ssrc = self.target.ssrc;
+        let payload_type = self.target.payload_type.unwrap_or(DEFAULT_PAYLOAD_TYPE);
+        let mut seq = 0u16;
+        let mut wait_key = false;
+        loop {
+            match self.pkt_rx.recv().await {
+                Ok(pkt) => {
+                    // 丢包后从下一个关键帧恢复，避免接收端花屏
+                    if wait_key {
+                        if !pkt.is_key {
+                            continue;
+                        }
+                        wait_key = false;
+                    }
+                    let payloads = split_payloads(&pkt.data);
+                    let packets = packetize_frame(
+                        ssrc,
+                        &mut seq,
+                        pkt.timestamp as u32,
+                        payload_type,
+                        &payloads,
+                    );
+                    sink.send_frame(packets).await?;
+                }
+                Err(RecvError::Lagged(n)) => {
+                    warn!(
+                        "gb28181 push lagged {n} frames: stream_id={}",
+                        self.stream_id
+                    );
+                    wait_key = true;
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn report(&self, state: PushState, reason: Option<String>) {
+        let info = PushStateInfo {
+            stream_id: self.stream_id.to_string(),
+            output: self.output,
+            target: format!("{}:{}", self.target.ip, self.target.port),
+            state,
+            retry: 0,
+            reason,
+        };
+        let _ = self
+            .event_tx
+            .try_send((Event::Out(OutEvent::PushState(info)), None))
+            .hand_log(|msg| warn!("{msg}"));
+    }
+}
+
+async fn skipped_audio(ssrc: u32) -> Option<String> {
+    let (tx, rx) = oneshot::channel();
+    Register::try_publish_mpsc(
+        ssrc,
+        ContextEvent::Inner(InnerEvent::RtpFrameSkippedAudio(tx)),
+    )
+    .ok()?;
+    rx.await.ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tcp_frame_prefixes_each_packet_length() {
+        let packets = vec![
+            build_rtp_packet(1, 7, 3600, false, 96, &[0xAA; 3]),
+            build_rtp_packet(1, 8, 3600, true, 96, &[0xBB; 1]),
+        ];
+        let buf = rfc4571_frame(&packets);
+        assert_eq!(&buf[..2], &15u16.to_be_bytes());
+        assert_eq!(buf[2], 0x80);
+        assert_eq!(buf[3], 96);
+        let second = 2 + 15;
+        assert_eq!(&buf[second..second + 2], &13u16.to_be_bytes());
+        assert_eq!(buf[second + 3], 0x80 | 96);
+        assert_eq!(&buf[second + 4..second + 6], &8u16.to_be_bytes());
+        assert_eq!(buf.len(), second + 2 + 13);
+    }
+
+    #[test]
+    fn frame_packets_mark_last_and_keep_seq_continuous() {
+        let frame = vec![
+            Bytes::from_static(&[1; 4]),
+            Bytes::from_static(&[2; 4]),
+            Bytes::from_static(&[3; 4]),
+        ];
+        let mut seq = u16::MAX - 1;
+        let first = packetize_frame(9, &mut seq, 3600, 96, &frame);
+        let second = packetize_frame(9, &mut seq, 7200, 96, &frame[..1]);
+        let seqs: Vec<u16> = first
+            .iter()
+            .chain(second.iter())
+            .map(|p| u16::from_be_bytes([p[2], p[3]]))
+            .collect();
+        // 跨帧连续且回绕
+        assert_eq!(seqs, vec![u16::MAX - 1, u16::MAX, 0, 1]);
+        assert_eq!(seq, 2);
+        let markers: Vec<bool> = first.iter().map(|p| p[1] & 0x80 != 0).collect();
+        assert_eq!(markers, vec![false, false, true]);
+        assert!(second[0][1] & 0x80 != 0);
+        // 同帧共用时间戳
+        assert!(
+            first
+                .iter()
+                .all(|p| p[4..8] == 3600u32.to_be_bytes() && p[8..12] == 9u32.to_be_bytes())
+        );
+        assert_eq!(&second[0][4..8], &7200u32.to_be_bytes());
+    }
+}
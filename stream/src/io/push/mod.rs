@@ -0,0 +1,2 @@
+pub mod gb28181;
+pub mod rtmp;
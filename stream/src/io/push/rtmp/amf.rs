@@ -0,0 +1,208 @@
+use base::bytes::{Buf, BufMut, BytesMut};
+
+const AMF0_NUMBER: u8 = 0x00;
+const AMF0_BOOLEAN: u8 = 0x01;
+const AMF0_STRING: u8 = 0x02;
+const AMF0_OBJECT: u8 = 0x03;
+const AMF0_NULL: u8 = 0x05;
+const AMF0_UNDEFINED: u8 = 0x06;
+const AMF0_ECMA_ARRAY: u8 = 0x08;
+const AMF0_OBJECT_END: u8 = 0x09;
+const AMF0_STRICT_ARRAY: u8 = 0x0A;
+const AMF0_DATE: u8 = 0x0B;
+const AMF0_LONG_STRING: u8 = 0x0C;
+
+/// RTMP 命令所需的 AMF0 子集
+#[derive(Debug, Clone, PartialEq)]
+pub enum Amf0 {
+    Number(f64),
+    Bool(bool),
+    String(String),
+    Object(Vec<(String, Amf0)>),
+    Null,
+    Undefined,
+    EcmaArray(Vec<(String, Amf0)>),
+    StrictArray(Vec<Amf0>),
+}
+
+impl Amf0 {
+    pub fn str(value: &str) -> Self {
+        Amf0::String(value.to_string())
+    }
+
+    pub fn object(props: Vec<(&str, Amf0)>) -> Self {
+        Amf0::Object(
+            props
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn encode(&self, buf: &mut BytesMut) {
+        match self {
+            Amf0::Number(n) => {
+                buf.put_u8(AMF0_NUMBER);
+                buf.put_f64(*n);
+            }
+            Amf0::Bool(b) => {
+                buf.put_u8(AMF0_BOOLEAN);
+                buf.put_u8(*b as u8);
+            }
+            Amf0::String(s) if s.len() > u16::MAX as usize => {
+                buf.put_u8(AMF0_LONG_STRING);
+                buf.put_u32(s.len() as u32);
+                buf.put_slice(s.as_bytes());
+            }
+            Amf0::String(s) => {
+                buf.put_u8(AMF0_STRING);
+                put_key(buf, s);
+            }
+            Amf0::Object(props) => {
+                buf.put_u8(AMF0_OBJECT);
+                put_props(buf, props);
+            }
+            Amf0::Null => buf.put_u8(AMF0_NULL),
+            Amf0::Undefined => buf.put_u8(AMF0_UNDEFINED),
+            Amf0::EcmaArray(props) => {
+                buf.put_u8(AMF0_ECMA_ARRAY);
+                buf.put_u32(props.len() as u32);
+                put_props(buf, props);
+            }
+            Amf0::StrictArray(values) => {
+                buf.put_u8(AMF0_STRICT_ARRAY);
+                buf.put_u32(values.len() as u32);
+                for value in values {
+                    value.encode(buf);
+                }
+            }
+        }
+    }
+
+    /// 依次解码全部值，遇到不支持的类型时停止
+    pub fn decode_all(mut data: &[u8]) -> Vec<Amf0> {
+        let mut values = Vec::new();
+        while data.has_remaining() {
+            match Self::decode(&mut data) {
+                Some(value) => values.push(value),
+                None => break,
+            }
+        }
+        values
+    }
+
+    fn decode(data: &mut &[u8]) -> Option<Amf0> {
+        if !data.has_remaining() {
+            return None;
+        }
+        let value = match data.get_u8() {
+            AMF0_NUMBER => {
+                need(data, 8)?;
+                Amf0::Number(data.get_f64())
+            }
+            AMF0_BOOLEAN => {
+                need(data, 1)?;
+                Amf0::Bool(data.get_u8() != 0)
+            }
+            AMF0_STRING => Amf0::String(get_key(data)?),
+            AMF0_LONG_STRING => {
+                need(data, 4)?;
+                let len = data.get_u32() as usize;
+                need(data, len)?;
+                let s = String::from_utf8_lossy(&data[..len]).into_owned();
+                data.advance(len);
+                Amf0::String(s)
+            }
+            AMF0_OBJECT => Amf0::Object(get_props(data)?),
+            AMF0_NULL => Amf0::Null,
+            AMF0_UNDEFINED => Amf0::Undefined,
+            AMF0_ECMA_ARRAY => {
+                need(data, 4)?;
+                data.advance(4);
+                Amf0::EcmaArray(get_props(data)?)
+            }
+            AMF0_STRICT_ARRAY => {
+                need(data, 4)?;
+                let count = data.get_u32();
+                let mut values = Vec::new();
+                for _ in 0..count {
+                    values.push(Self::decode(data)?);
+                }
+                Amf0::StrictArray(values)
+            }
+            AMF0_DATE => {
+                need(data, 10)?;
+                data.advance(10);
+                Amf0::Undefined
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Amf0> {
+        match self {
+            Amf0::Object(props) | Amf0::EcmaArray(props) => {
+                props.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Amf0::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Amf0::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+fn need(data: &&[u8], len: usize) -> Option<()> {
+    (data.remaining() >= len).then_some(())
+}
+
+fn put_key(buf: &mut BytesMut, key: &str) {
+    buf.put_u16(key.len() as u16);
+    buf.put_slice(key.as_bytes());
+}
+
+fn get_key(data: &mut &[u8]) -> Option<String> {
+    need(data, 2)?;
+    let len = data.get_u16() as usize;
+    need(data, len)?;
+    let key = String::from_utf8_lossy(&data[..len]).into_owned();
+    data.advance(len);
+    Some(key)
+}
+
+fn put_props(buf: &mut BytesMut, props: &[(String, Amf0)]) {
+    for (key, value) in props {
+        put_key(buf, key);
+        value.encode(buf);
+    }
+    buf.put_u16(0);
+    buf.put_u8(AMF0_OBJECT_END);
+}
+
+fn get_props(data: &mut &[u8]) -> Option<Vec<(String, Amf0)>> {
+    let mut props = Vec::new();
+    loop {
+        let key = get_key(data)?;
+        if key.is_empty() {
+            need(data, 1)?;
+            if data[0] == AMF0_OBJECT_END {
+                data.advance(1);
+                return Some(props);
+            }
+        }
+        let value = Amf0::decode(data)?;
+        props.push((key, value));
+    }
+}
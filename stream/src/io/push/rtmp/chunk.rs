@@ -0,0 +1,374 @@
+use base::bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::collections::HashMap;
+
+pub const DEFAULT_RTMP_PORT: u16 = 1935;
+/// 协议默认块大小
+pub const DEFAULT_CHUNK_SIZE: usize = 128;
+/// 本端发送块大小，连接后通过 SetChunkSize 通告
+pub const OUT_CHUNK_SIZE: usize = 4096;
+
+pub const MSG_SET_CHUNK_SIZE: u8 = 1;
+pub const MSG_USER_CONTROL: u8 = 4;
+pub const MSG_WINDOW_ACK_SIZE: u8 = 5;
+pub const MSG_SET_PEER_BANDWIDTH: u8 = 6;
+pub const MSG_AUDIO: u8 = 8;
+pub const MSG_VIDEO: u8 = 9;
+pub const MSG_DATA_AMF0: u8 = 18;
+pub const MSG_COMMAND_AMF0: u8 = 20;
+
+pub const CSID_CONTROL: u32 = 2;
+pub const CSID_COMMAND: u32 = 3;
+pub const CSID_AUDIO: u32 = 4;
+pub const CSID_DATA: u32 = 5;
+pub const CSID_VIDEO: u32 = 6;
+
+const FLV_CODEC_H265: u8 = 12;
+const EX_HEADER: u8 = 0x80;
+const PACKET_TYPE_SEQUENCE_START: u8 = 0;
+const PACKET_TYPE_CODED_FRAMES: u8 = 1;
+const PACKET_TYPE_CODED_FRAMES_X: u8 = 3;
+const FOURCC_HEVC: &[u8; 4] = b"hvc1";
+
+/// rtmp://host[:port]/app[/...]/stream[?query]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtmpUrl {
+    pub host: String,
+    pub port: u16,
+    pub app: String,
+    pub stream: String,
+    pub tc_url: String,
+}
+
+impl RtmpUrl {
+    pub fn parse(url: &str) -> Option<Self> {
+        let rest = url.strip_prefix("rtmp://")?;
+        let (authority, path) = rest.split_once('/')?;
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, DEFAULT_RTMP_PORT),
+        };
+        if host.is_empty() {
+            return None;
+        }
+        // 查询串属于流名（常用于鉴权），app 取最后一个 '/' 之前的路径
+        let (path_part, query) = match path.split_once('?') {
+            Some((p, q)) => (p, Some(q)),
+            None => (path, None),
+        };
+        let (app, stream) = path_part.rsplit_once('/')?;
+        if app.is_empty() || stream.is_empty() {
+            return None;
+        }
+        let stream = match query {
+            Some(q) => format!("{stream}?{q}"),
+            None => stream.to_string(),
+        };
+        Some(RtmpUrl {
+            host: host.to_string(),
+            port,
+            app: app.to_string(),
+            stream,
+            tc_url: format!("rtmp://{authority}/{app}"),
+        })
+    }
+}
+
+pub struct RtmpMessage {
+    pub type_id: u8,
+    pub stream_id: u32,
+    pub timestamp: u32,
+    pub payload: Bytes,
+}
+
+/// 按块大小切分消息：首块 fmt0，后续块 fmt3
+pub fn write_chunks(out: &mut BytesMut, csid: u32, msg: &RtmpMessage, chunk_size: usize) {
+    let extended = msg.timestamp >= 0xFF_FFFF;
+    let ts_field = if extended { 0xFF_FFFF } else { msg.timestamp };
+    put_basic_header(out, 0, csid);
+    out.put_uint(ts_field as u64, 3);
+    out.put_uint(msg.payload.len() as u64, 3);
+    out.put_u8(msg.type_id);
+    out.put_u32_le(msg.stream_id);
+    if extended {
+        out.put_u32(msg.timestamp);
+    }
+    let mut chunks = msg.payload.chunks(chunk_size).peekable();
+    while let Some(chunk) = chunks.next() {
+        out.put_slice(chunk);
+        if chunks.peek().is_some() {
+            put_basic_header(out, 3, csid);
+            if extended {
+                out.put_u32(msg.timestamp);
+            }
+        }
+    }
+}
+
+fn put_basic_header(out: &mut BytesMut, fmt: u8, csid: u32) {
+    match csid {
+        2..=63 => out.put_u8((fmt << 6) | csid as u8),
+        64..=319 => {
+            out.put_u8(fmt << 6);
+            out.put_u8((csid - 64) as u8);
+        }
+        _ => {
+            out.put_u8((fmt << 6) | 1);
+            out.put_u16_le((csid - 64) as u16);
+        }
+    }
+}
+
+#[derive(Default)]
+struct ChunkStream {
+    timestamp: u32,
+    delta: u32,
+    length: usize,
+    type_id: u8,
+    stream_id: u32,
+    extended: bool,
+    buf: BytesMut,
+}
+
+/// 解析服务端下发的块流，按 csid 重组消息
+pub struct ChunkReader {
+    chunk_size: usize,
+    streams: HashMap<u32, ChunkStream>,
+}
+
+impl Default for ChunkReader {
+    fn default() -> Self {
+        ChunkReader {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            streams: HashMap::new(),
+        }
+    }
+}
+
+impl ChunkReader {
+    pub fn set_chunk_size(&mut self, size: usize) {
+        self.chunk_size = size.clamp(1, 0x7FFF_FFFF);
+    }
+
+    /// 数据不足时返回 None 且不消费输入
+    pub fn read(&mut self, input: &mut BytesMut) -> Option<RtmpMessage> {
+        loop {
+            let (msg, used) = self.try_chunk(input)?;
+            input.advance(used);
+            if msg.is_some() {
+                return msg;
+            }
+        }
+    }
+
+    fn try_chunk(&mut self, input: &[u8]) -> Option<(Option<RtmpMessage>, usize)> {
+        let mut cur = input;
+        if cur.remaining() < 1 {
+            return None;
+        }
+        let first = cur.get_u8();
+        let fmt = first >> 6;
+        let csid = match first & 0x3F {
+            0 => {
+                if cur.remaining() < 1 {
+                    return None;
+                }
+                cur.get_u8() as u32 + 64
+            }
+            1 => {
+                if cur.remaining() < 2 {
+                    return None;
+                }
+                cur.get_u16_le() as u32 + 64
+            }
+            n => n as u32,
+        };
+        let header_len = [11, 7, 3, 0][fmt as usize];
+        if cur.remaining() < header_len {
+            return None;
+        }
+        let state = self.streams.entry(csid).or_default();
+        let mut ts_field = None;
+        if fmt <= 2 {
+            ts_field = Some(cur.get_uint(3) as u32);
+        }
+        let (mut length, mut type_id, mut stream_id) =
+            (state.length, state.type_id, state.stream_id);
+        if fmt <= 1 {
+            length = cur.get_uint(3) as usize;
+            type_id = cur.get_u8();
+        }
+        if fmt == 0 {
+            stream_id = cur.get_u32_le();
+        }
+        let extended = match ts_field {
+            Some(ts) => ts == 0xFF_FFFF,
+            None => state.extended,
+        };
+        let mut ext_ts = None;
+        if extended {
+            if cur.remaining() < 4 {
+                return None;
+            }
+            ext_ts = Some(cur.get_u32());
+        }
+        let starting = state.buf.is_empty();
+        let need = (length - state.buf.len().min(length)).min(self.chunk_size);
+        if cur.remaining() < need {
+            return None;
+        }
+        // 头部完整，更新块流状态
+        if let Some(ts) = ts_field {
+            let value = ext_ts.unwrap_or(ts);
+            if fmt == 0 {
+                state.timestamp = value;
+                state.delta = 0;
+            } else {
+                state.delta = value;
+                state.timestamp = state.timestamp.wrapping_add(value);
+            }
+        } else if starting {
+            state.timestamp = state.timestamp.wrapping_add(state.delta);
+        }
+        state.extended = extended;
+        state.length = length;
+        state.type_id = type_id;
+        state.stream_id = stream_id;
+        state.buf.extend_from_slice(&cur[..need]);
+        cur.advance(need);
+        let used = input.len() - cur.len();
+        if state.buf.len() < length {
+            return Some((None, used));
+        }
+        let msg = RtmpMessage {
+            type_id,
+            stream_id,
+            timestamp: state.timestamp,
+            payload: state.buf.split().freeze(),
+        };
+        Some((Some(msg), used))
+    }
+}
+
+pub struct FlvTag {
+    pub tag_type: u8,
+    pub timestamp: u32,
+    pub body: Bytes,
+}
+
+/// 拆分 FLV 数据为标签，跳过文件头与 PreviousTagSize
+pub fn split_flv_tags(data: &Bytes) -> Vec<FlvTag> {
+    let mut tags = Vec::new();
+    let mut pos = 0;
+    if data.len() >= 13 && &data[..3] == b"FLV" {
+        let offset = u32::from_be_bytes([data[5], data[6], data[7], data[8]]) as usize;
+        pos = offset + 4;
+    }
+    while pos + 11 <= data.len() {
+        let head = &data[pos..pos + 11];
+        let size = u32::from_be_bytes([0, head[1], head[2], head[3]]) as usize;
+        let timestamp = u32::from_be_bytes([head[7], head[4], head[5], head[6]]);
+        let end = pos + 11 + size;
+        if end > data.len() {
+            break;
+        }
+        tags.push(FlvTag {
+            tag_type: head[0],
+            timestamp,
+            body: data.slice(pos + 11..end),
+        });
+        pos = end + 4;
+    }
+    tags
+}
+
+pub fn is_legacy_hevc(body: &[u8]) -> bool {
+    body.first().is_some_and(|b| b & 0x0F == FLV_CODEC_H265)
+}
+
+/// 旧式 codec id 12 的 HEVC 标签转换为增强 RTMP（FourCC hvc1）
+pub fn to_enhanced_hevc(body: &Bytes) -> Option<Bytes> {
+    if body.len() < 5 || !is_legacy_hevc(body) {
+        return None;
+    }
+    let frame_type = (body[0] >> 4) & 0x07;
+    let cts = &body[2..5];
+    let mut out = BytesMut::with_capacity(body.len() + 3);
+    match body[1] {
+        0 => {
+            out.put_u8(EX_HEADER | (frame_type << 4) | PACKET_TYPE_SEQUENCE_START);
+            out.put_slice(FOURCC_HEVC);
+        }
+        1 if cts == [0, 0, 0] => {
+            out.put_u8(EX_HEADER | (frame_type << 4) | PACKET_TYPE_CODED_FRAMES_X);
+            out.put_slice(FOURCC_HEVC);
+        }
+        1 => {
+            out.put_u8(EX_HEADER | (frame_type << 4) | PACKET_TYPE_CODED_FRAMES);
+            out.put_slice(FOURCC_HEVC);
+            out.put_slice(cts);
+        }
+        _ => return None,
+    }
+    out.put_slice(&body[5..]);
+    Some(out.freeze())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_splits_app_and_stream_with_query() {
+        let url = RtmpUrl::parse("rtmp://10.0.0.8/live/sub/cam01?token=abc").unwrap();
+        assert_eq!(url.host, "10.0.0.8");
+        assert_eq!(url.port, DEFAULT_RTMP_PORT);
+        assert_eq!(url.app, "live/sub");
+        assert_eq!(url.stream, "cam01?token=abc");
+        assert_eq!(url.tc_url, "rtmp://10.0.0.8/live/sub");
+        assert_eq!(
+            RtmpUrl::parse("rtmp://host:19350/app/s").unwrap().port,
+            19350
+        );
+        assert!(RtmpUrl::parse("rtmp://host/stream").is_none());
+        assert!(RtmpUrl::parse("http://host/app/s").is_none());
+    }
+
+    #[test]
+    fn chunks_round_trip_through_reader() {
+        let payload = Bytes::from((0..300u32).map(|i| i as u8).collect::<Vec<_>>());
+        let msg = RtmpMessage {
+            type_id: MSG_VIDEO,
+            stream_id: 1,
+            timestamp: 0x0100_0000,
+            payload: payload.clone(),
+        };
+        let mut out = BytesMut::new();
+        write_chunks(&mut out, CSID_VIDEO, &msg, DEFAULT_CHUNK_SIZE);
+        // fmt0 头 12 + 扩展时间戳 4，两个续块各 1 + 4
+        assert_eq!(out.len(), 300 + 16 + 2 * 5);
+        let mut reader = ChunkReader::default();
+        let mut partial = out.split_to(20);
+        assert!(reader.read(&mut partial).is_none());
+        partial.unsplit(out);
+        let read = reader.read(&mut partial).unwrap();
+        assert_eq!(read.type_id, MSG_VIDEO);
+        assert_eq!(read.stream_id, 1);
+        assert_eq!(read.timestamp, 0x0100_0000);
+        assert_eq!(read.payload, payload);
+        assert!(partial.is_empty());
+    }
+
+    #[test]
+    fn legacy_hevc_converts_to_enhanced() {
+        let seq = Bytes::from_static(&[0x1C, 0, 0, 0, 0, 0xAA]);
+        assert_eq!(&to_enhanced_hevc(&seq).unwrap()[..], b"\x90hvc1\xAA");
+        let key = Bytes::from_static(&[0x1C, 1, 0, 0, 0, 0xBB]);
+        assert_eq!(&to_enhanced_hevc(&key).unwrap()[..], b"\x93hvc1\xBB");
+        let inter = Bytes::from_static(&[0x2C, 1, 0, 0, 0x28, 0xCC]);
+        assert_eq!(
+            &to_enhanced_hevc(&inter).unwrap()[..],
+            b"\xA1hvc1\x00\x00\x28\xCC"
+        );
+        assert!(to_enhanced_hevc(&Bytes::from_static(&[0x17, 1, 0, 0, 0])).is_none());
+    }
+}
@@ -0,0 +1,601 @@
+use crate::io::push::rtmp::amf::Amf0;
+use crate::io::push::rtmp::chunk::{
+    CSID_AUDIO, CSID_COMMAND, CSID_CONTROL, CSID_DATA, CSID_VIDEO, ChunkReader, FlvTag, MSG_AUDIO,
+    MSG_COMMAND_AMF0, MSG_DATA_AMF0, MSG_SET_CHUNK_SIZE, MSG_SET_PEER_BANDWIDTH, MSG_USER_CONTROL,
+    MSG_VIDEO, MSG_WINDOW_ACK_SIZE, OUT_CHUNK_SIZE, RtmpMessage, RtmpUrl, is_legacy_hevc,
+    split_flv_tags, to_enhanced_hevc, write_chunks,
+};
+use crate::media::context::event::ContextEvent;
+use crate::media::context::event::inner::InnerEvent;
+use crate::media::context::format::MuxPacket;
+use crate::state::event::{Event, EventRes, OutEvent};
+use crate::state::register::Register;
+use base::bytes::{Buf, BufMut, Bytes, BytesMut};
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use base::log::{error, info, warn};
+use base::tokio;
+use base::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use base::tokio::net::TcpStream;
+use base::tokio::sync::broadcast::error::RecvError;
+use base::tokio::sync::{broadcast, mpsc, oneshot};
+use base::tokio_util::sync::CancellationToken;
+use shared::enums::OptAction;
+use shared::info::obj::{PushState, PushStateInfo};
+use shared::info::output::OutputEnum;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub mod amf;
+pub mod chunk;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const FIRST_KEY_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+//连续推流超过该时长视为恢复，重连计数清零
+const STABLE_PUSHING: Duration = Duration::from_secs(60);
+const HANDSHAKE_SIZE: usize = 1536;
+const WINDOW_ACK_SIZE: u32 = 2_500_000;
+
+/// RTMP 主动推流：复用 FLV/H265-FLV 复用器输出，HEVC 以增强 RTMP 发送
+pub struct RtmpPushContext {
+    pub stream_id: Arc<str>,
+    pub ssrc: u32,
+    pub url: String,
+    pub max_retry: Option<u32>,
+    pub pkt_rx: broadcast::Receiver<Arc<MuxPacket>>,
+    pub event_tx: mpsc::Sender<(Event, Option<oneshot::Sender<EventRes>>)>,
+    //运行时移除输出时取消推流
+    pub cancel: CancellationToken,
+}
+
+impl RtmpPushContext {
+    pub fn push(mut self) {
+        tokio::spawn(async move {
+            Register::handle_stream_metadata_map_output(
+                OptAction::Insert,
+                &self.stream_id,
+                OutputEnum::Rtmp,
+            );
+            let cancel = self.cancel.clone();
+            let cancelled = tokio::select! {
+                _ = self.run() => false,
+                _ = cancel.cancelled() => true,
+            };
+            if cancelled {
+                info!(
+                    "rtmp push cancelled: stream_id={}, url={}",
+                    self.stream_id, self.url
+                );
+                self.report(PushState::Stopped, 0, None);
+            }
+            Register::handle_stream_metadata_map_output(
+                OptAction::Remove,
+                &self.stream_id,
+                OutputEnum::Rtmp,
+            );
+        });
+    }
+
+    async fn run(&mut self) {
+        let Some(url) = RtmpUrl::parse(&self.url) else {
+            warn!(
+                "rtmp push invalid url: stream_id={}, url={}",
+                self.stream_id, self.url
+            );
+            self.report(PushState::Failed, 0, Some("invalid rtmp url".to_string()));
+            return;
+        };
+        let mut retry = 0u32;
+        self.report(PushState::Connecting, retry, None);
+        loop {
+            let mut pushing_since = None;
+            match self.attempt(&url, &mut pushing_since, retry).await {
+                Ok(()) => {
+                    info!(
+                        "rtmp push end: stream_id={}, url={}",
+                        self.stream_id, self.url
+                    );
+                    self.report(PushState::Stopped, retry, None);
+                    return;
+                }
+                Err(err) => {
+                    if pushing_since.is_some_and(|t: Instant| t.elapsed() >= STABLE_PUSHING) {
+                        retry = 0;
+                    }
+                    retry += 1;
+                    let reason = Some(err.to_string());
+                    if self.max_retry.is_some_and(|max| retry > max) {
+                        warn!(
+                            "rtmp push failed: stream_id={}, url={}, retry={}, err={}",
+                            self.stream_id, self.url, retry, err
+                        );
+                        self.report(PushState::Failed, retry - 1, reason);
+                        return;
+                    }
+                    let backoff = backoff(retry);
+                    warn!(
+                        "rtmp push retry in {:?}: stream_id={}, url={}, retry={}, err={}",
+                        backoff, self.stream_id, self.url, retry, err
+                    );
+                    self.report(PushState::Retrying, retry, reason);
+                    tokio::time::sleep(backoff).await;
+                }
+            }
+        }
+    }
+
+    /// Ok 表示源流结束，Err 表示本次连接失败需重连
+    async fn attempt(
+        &mut self,
+        url: &RtmpUrl,
+        pushing_since: &mut Option<Instant>,
+        retry: u32,
+    ) -> GlobalResult<()> {
+        let Some(first_key) = self.wait_key().await? else {
+            return Ok(());
+        };
+        let header = get_header(self.ssrc).await?;
+        let header_tags = split_flv_tags(&header);
+        let hevc = header_tags
+            .iter()
+            .any(|tag| tag.tag_type == MSG_VIDEO && is_legacy_hevc(&tag.body));
+        let mut conn = RtmpConn::connect(url).await?;
+        conn.publish(url, hevc).await?;
+        info!(
+            "rtmp push start: stream_id={}, url={}, hevc={}",
+            self.stream_id, self.url, hevc
+        );
+        *pushing_since = Some(Instant::now());
+        self.report(PushState::Pushing, retry, None);
+
+        for tag in header_tags {
+            conn.send_tag(tag, 0).await?;
+        }
+        let base_ts = split_flv_tags(&first_key.data)
+            .first()
+            .map(|tag| tag.timestamp)
+            .unwrap_or_default();
+        conn.send_packet(&first_key, base_ts).await?;
+        let mut wait_key = false;
+        loop {
+            tokio::select! {
+                res = self.pkt_rx.recv() => match res {
+                    Ok(pkt) => {
+                        // 丢帧后从下一个关键帧恢复
+                        if wait_key {
+                            if !pkt.is_key {
+                                continue;
+                            }
+                            wait_key = false;
+                        }
+                        conn.send_packet(&pkt, base_ts).await?;
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("rtmp push lagged {n} frames: stream_id={}", self.stream_id);
+                        wait_key = true;
+                    }
+                    Err(RecvError::Closed) => {
+                        conn.unpublish(url).await;
+                        return Ok(());
+                    }
+                },
+                res = conn.read_more() => {
+                    res?;
+                    conn.drain_incoming().await?;
+                }
+            }
+        }
+    }
+
+    /// 等待首个关键帧；None 表示源流已结束
+    async fn wait_key(&mut self) -> GlobalResult<Option<Arc<MuxPacket>>> {
+        tokio::time::timeout(FIRST_KEY_TIMEOUT, async {
+            loop {
+                match self.pkt_rx.recv().await {
+                    Ok(pkt) if pkt.is_key => return Some(pkt),
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+        .await
+        .map_err(|_| {
+            GlobalError::new_biz_error(
+                BaseErrorCode::Timeout.code(),
+                "rtmp push wait key frame timeout",
+                |msg| warn!("{msg}: stream_id={}", self.stream_id),
+            )
+        })
+    }
+
+    fn report(&self, state: PushState, retry: u32, reason: Option<String>) {
+        let info = PushStateInfo {
+            stream_id: self.stream_id.to_string(),
+            output: OutputEnum::Rtmp,
+            target: self.url.clone(),
+            state,
+            retry,
+            reason,
+        };
+        let _ = self
+            .event_tx
+            .try_send((Event::Out(OutEvent::PushState(info)), None))
+            .hand_log(|msg| warn!("{msg}"));
+    }
+}
+
+fn backoff(retry: u32) -> Duration {
+    let secs = 1u64 << retry.saturating_sub(1).min(5);
+    Duration::from_secs(secs).min(MAX_BACKOFF)
+}
+
+async fn get_header(ssrc: u32) -> GlobalResult<Bytes> {
+    let (tx, rx) = oneshot::channel();
+    Register::try_publish_mpsc(ssrc, ContextEvent::Inner(InnerEvent::FlvHeader(tx)))?;
+    let header = rx.await.hand_log(|msg| error!("{msg}"))?;
+    Ok(header)
+}
+
+fn rtmp_error(msg: &str) -> GlobalError {
+    GlobalError::new_biz_error(BaseErrorCode::InvalidState.code(), msg, |msg| {
+        warn!("{msg}")
+    })
+}
+
+struct RtmpConn {
+    stream: TcpStream,
+    reader: ChunkReader,
+    in_buf: BytesMut,
+    out_buf: BytesMut,
+    msg_stream_id: u32,
+    hevc: bool,
+}
+
+impl RtmpConn {
+    async fn connect(url: &RtmpUrl) -> GlobalResult<Self> {
+        let addr = format!("{}:{}", url.host, url.port);
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&addr))
+            .await
+            .map_err(|_| {
+                GlobalError::new_biz_error(
+                    BaseErrorCode::Timeout.code(),
+                    "rtmp connect timeout",
+                    |msg| warn!("{msg}: target={addr}"),
+                )
+            })?
+            .hand_log(|msg| warn!("{msg}"))?;
+        let _ = stream.set_nodelay(true);
+        let mut conn = RtmpConn {
+            stream,
+            reader: ChunkReader::default(),
+            in_buf: BytesMut::with_capacity(4096),
+            out_buf: BytesMut::with_capacity(OUT_CHUNK_SIZE * 2),
+            msg_stream_id: 0,
+            hevc: false,
+        };
+        tokio::time::timeout(PUBLISH_TIMEOUT, conn.handshake())
+            .await
+            .map_err(|_| rtmp_error("rtmp handshake timeout"))??;
+        Ok(conn)
+    }
+
+    /// 简单握手：C1 时间戳 + 零 + 随机填充，C2 回显 S1
+    async fn handshake(&mut self) -> GlobalResult<()> {
+        let mut c0c1 = BytesMut::with_capacity(1 + HANDSHAKE_SIZE);
+        c0c1.put_u8(3);
+        c0c1.put_u32(0);
+        c0c1.put_u32(0);
+        let mut seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15)
+            | 1;
+        while c0c1.len() < 1 + HANDSHAKE_SIZE {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            c0c1.put_u8(seed as u8);
+        }
+        self.write(&c0c1).await?;
+        let mut s0s1 = vec![0u8; 1 + HANDSHAKE_SIZE];
+        self.stream
+            .read_exact(&mut s0s1)
+            .await
+            .hand_log(|msg| warn!("{msg}"))?;
+        if s0s1[0] != 3 {
+            return Err(rtmp_error("rtmp handshake unsupported version"));
+        }
+        self.write(&s0s1[1..]).await?;
+        let mut s2 = vec![0u8; HANDSHAKE_SIZE];
+        self.stream
+            .read_exact(&mut s2)
+            .await
+            .hand_log(|msg| warn!("{msg}"))?;
+        Ok(())
+    }
+
+    async fn publish(&mut self, url: &RtmpUrl, hevc: bool) -> GlobalResult<()> {
+        tokio::time::timeout(PUBLISH_TIMEOUT, self.do_publish(url, hevc))
+            .await
+            .map_err(|_| rtmp_error("rtmp publish timeout"))?
+    }
+
+    async fn do_publish(&mut self, url: &RtmpUrl, hevc: bool) -> GlobalResult<()> {
+        self.hevc = hevc;
+        let mut size = BytesMut::new();
+        size.put_u32(OUT_CHUNK_SIZE as u32);
+        self.send(CSID_CONTROL, MSG_SET_CHUNK_SIZE, 0, 0, size.freeze())
+            .await?;
+
+        let mut props = vec![
+            ("app", Amf0::str(&url.app)),
+            ("type", Amf0::str("nonprivate")),
+            ("flashVer", Amf0::str("FMLE/3.0 (compatible; FMSc/1.0)")),
+            ("tcUrl", Amf0::str(&url.tc_url)),
+        ];
+        if hevc {
+            props.push(("fourCcList", Amf0::StrictArray(vec![Amf0::str("hvc1")])));
+        }
+        self.command(&[Amf0::str("connect"), Amf0::Number(1.0), Amf0::object(props)])
+            .await?;
+        self.wait_result(1.0).await?;
+
+        let stream = Amf0::str(&url.stream);
+        self.command(&[
+            Amf0::str("releaseStream"),
+            Amf0::Number(2.0),
+            Amf0::Null,
+            stream.clone(),
+        ])
+        .await?;
+        self.command(&[
+            Amf0::str("FCPublish"),
+            Amf0::Number(3.0),
+            Amf0::Null,
+            stream.clone(),
+        ])
+        .await?;
+        self.command(&[Amf0::str("createStream"), Amf0::Number(4.0), Amf0::Null])
+            .await?;
+        let res = self.wait_result(4.0).await?;
+        self.msg_stream_id = res
+            .get(3)
+            .and_then(Amf0::as_number)
+            .ok_or_else(|| rtmp_error("rtmp createStream without stream id"))?
+            as u32;
+
+        self.command(&[
+            Amf0::str("publish"),
+            Amf0::Number(5.0),
+            Amf0::Null,
+            stream,
+            Amf0::str("live"),
+        ])
+        .await?;
+        loop {
+            let values = self.next_command().await?;
+            if values.first().and_then(Amf0::as_str) == Some("onStatus") {
+                let code = status_code(&values).unwrap_or_default();
+                if code == "NetStream.Publish.Start" {
+                    return Ok(());
+                }
+                if is_status_error(&code) {
+                    return Err(rtmp_error(&format!("rtmp publish rejected: {code}")));
+                }
+            }
+        }
+    }
+
+    async fn unpublish(&mut self, url: &RtmpUrl) {
+        let _ = self
+            .command(&[
+                Amf0::str("FCUnpublish"),
+                Amf0::Number(6.0),
+                Amf0::Null,
+                Amf0::str(&url.stream),
+            ])
+            .await;
+        let _ = self
+            .command(&[
+                Amf0::str("deleteStream"),
+                Amf0::Number(7.0),
+                Amf0::Null,
+                Amf0::Number(self.msg_stream_id as f64),
+            ])
+            .await;
+    }
+
+    async fn wait_result(&mut self, txn: f64) -> GlobalResult<Vec<Amf0>> {
+        loop {
+            let values = self.next_command().await?;
+            let name = values.first().and_then(Amf0::as_str).unwrap_or_default();
+            if values.get(1).and_then(Amf0::as_number) != Some(txn) {
+                continue;
+            }
+            match name {
+                "_result" => return Ok(values),
+                "_error" => {
+                    let code = status_code(&values).unwrap_or_else(|| "_error".to_string());
+                    return Err(rtmp_error(&format!("rtmp command rejected: {code}")));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn next_command(&mut self) -> GlobalResult<Vec<Amf0>> {
+        loop {
+            while let Some(msg) = self.reader.read(&mut self.in_buf) {
+                if let Some(values) = self.on_message(msg).await? {
+                    return Ok(values);
+                }
+            }
+            self.read_more().await?;
+        }
+    }
+
+    /// 推流中处理服务端消息，收到错误状态则断开重连
+    async fn drain_incoming(&mut self) -> GlobalResult<()> {
+        while let Some(msg) = self.reader.read(&mut self.in_buf) {
+            if let Some(values) = self.on_message(msg).await? {
+                if let Some(code) = status_code(&values).filter(|c| is_status_error(c)) {
+                    return Err(rtmp_error(&format!("rtmp server status: {code}")));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_more(&mut self) -> GlobalResult<()> {
+        let n = self
+            .stream
+            .read_buf(&mut self.in_buf)
+            .await
+            .hand_log(|msg| warn!("{msg}"))?;
+        if n == 0 {
+            return Err(rtmp_error("rtmp connection closed by server"));
+        }
+        Ok(())
+    }
+
+    /// 处理协议控制消息，命令消息返回 AMF 值
+    async fn on_message(&mut self, msg: RtmpMessage) -> GlobalResult<Option<Vec<Amf0>>> {
+        let mut payload = msg.payload;
+        match msg.type_id {
+            MSG_SET_CHUNK_SIZE if payload.len() >= 4 => {
+                self.reader
+                    .set_chunk_size((payload.get_u32() & 0x7FFF_FFFF) as usize);
+            }
+            MSG_USER_CONTROL if payload.len() >= 6 => {
+                // PingRequest -> PingResponse
+                if payload.get_u16() == 6 {
+                    let mut pong = BytesMut::with_capacity(6);
+                    pong.put_u16(7);
+                    pong.put_u32(payload.get_u32());
+                    self.send(CSID_CONTROL, MSG_USER_CONTROL, 0, 0, pong.freeze())
+                        .await?;
+                }
+            }
+            MSG_SET_PEER_BANDWIDTH => {
+                let mut ack = BytesMut::with_capacity(4);
+                ack.put_u32(WINDOW_ACK_SIZE);
+                self.send(CSID_CONTROL, MSG_WINDOW_ACK_SIZE, 0, 0, ack.freeze())
+                    .await?;
+            }
+            MSG_COMMAND_AMF0 => return Ok(Some(Amf0::decode_all(&payload))),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    async fn command(&mut self, values: &[Amf0]) -> GlobalResult<()> {
+        let mut payload = BytesMut::new();
+        for value in values {
+            value.encode(&mut payload);
+        }
+        let stream_id = self.msg_stream_id;
+        self.send(
+            CSID_COMMAND,
+            MSG_COMMAND_AMF0,
+            stream_id,
+            0,
+            payload.freeze(),
+        )
+        .await
+    }
+
+    async fn send_packet(&mut self, pkt: &MuxPacket, base_ts: u32) -> GlobalResult<()> {
+        for tag in split_flv_tags(&pkt.data) {
+            let timestamp = tag.timestamp.saturating_sub(base_ts);
+            self.send_tag(tag, timestamp).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_tag(&mut self, tag: FlvTag, timestamp: u32) -> GlobalResult<()> {
+        let stream_id = self.msg_stream_id;
+        match tag.tag_type {
+            MSG_AUDIO => {
+                self.send(CSID_AUDIO, MSG_AUDIO, stream_id, timestamp, tag.body)
+                    .await
+            }
+            MSG_VIDEO => {
+                let body = match self.hevc {
+                    true => to_enhanced_hevc(&tag.body).unwrap_or(tag.body),
+                    false => tag.body,
+                };
+                self.send(CSID_VIDEO, MSG_VIDEO, stream_id, timestamp, body)
+                    .await
+            }
+            MSG_DATA_AMF0 => {
+                let mut payload = BytesMut::with_capacity(16 + tag.body.len());
+                Amf0::str("@setDataFrame").encode(&mut payload);
+                payload.put_slice(&tag.body);
+                self.send(CSID_DATA, MSG_DATA_AMF0, stream_id, 0, payload.freeze())
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn send(
+        &mut self,
+        csid: u32,
+        type_id: u8,
+        stream_id: u32,
+        timestamp: u32,
+        payload: Bytes,
+    ) -> GlobalResult<()> {
+        let msg = RtmpMessage {
+            type_id,
+            stream_id,
+            timestamp,
+            payload,
+        };
+        let mut out = std::mem::take(&mut self.out_buf);
+        write_chunks(&mut out, csid, &msg, OUT_CHUNK_SIZE);
+        let res = self.write(&out).await;
+        out.clear();
+        self.out_buf = out;
+        res
+    }
+
+    async fn write(&mut self, data: &[u8]) -> GlobalResult<()> {
+        tokio::time::timeout(WRITE_TIMEOUT, self.stream.write_all(data))
+            .await
+            .map_err(|_| rtmp_error("rtmp write timeout"))?
+            .hand_log(|msg| warn!("{msg}"))?;
+        Ok(())
+    }
+}
+
+fn status_code(values: &[Amf0]) -> Option<String> {
+    values
+        .iter()
+        .skip(2)
+        .find_map(|v| v.get("code"))
+        .and_then(Amf0::as_str)
+        .map(str::to_string)
+}
+
+fn is_status_error(code: &str) -> bool {
+    ["Error", "Failed", "BadName", "Rejected", "Unpublish"]
+        .iter()
+        .any(|key| code.contains(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(6), MAX_BACKOFF);
+        assert_eq!(backoff(40), MAX_BACKOFF);
+    }
+}
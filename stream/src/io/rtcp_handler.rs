@@ -0,0 +1,144 @@
+use crate::media::rtcp::{self, RtcpPacket, RtcpPeer};
+use crate::state::register::Register;
+use base::bytes::Bytes;
+use base::exception::{GlobalResult, GlobalResultExt};
+use base::log::{debug, error, warn};
+use base::net::rw::{PacketWriter, U16BeLengthPrefixEncoder};
+use base::net::state::Protocol;
+use base::tokio;
+use base::tokio::net::UdpSocket;
+use base::tokio_util::sync::CancellationToken;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+// RFC 3550 建议的最小报告间隔
+const RR_INTERVAL: Duration = Duration::from_secs(5);
+const RTCP_BUF: usize = 1500;
+
+// rtcp-mux：复用 rtp 端口的写通道
+static MUX_WRITER: OnceLock<PacketWriter<U16BeLengthPrefixEncoder>> = OnceLock::new();
+
+pub fn listen_rtcp_server(port: u16) -> GlobalResult<Option<std::net::UdpSocket>> {
+    if port == 0 {
+        return Ok(None);
+    }
+    let socket =
+        std::net::UdpSocket::bind(format!("0.0.0.0:{}", port)).hand_log(|msg| error!("{msg}"))?;
+    Ok(Some(socket))
+}
+
+pub fn init_mux_writer(writer: PacketWriter<U16BeLengthPrefixEncoder>) {
+    let _ = MUX_WRITER.set(writer);
+}
+
+/// 处理收到的 RTCP 复合包：SR 记录时间映射，BYE 提前结束输入流；来源非该流RTP源时忽略
+pub fn dispatch(data: &[u8], remote_addr: SocketAddr, protocol: Protocol, mux: bool) {
+    let peer = RtcpPeer {
+        addr: remote_addr,
+        protocol,
+        mux,
+    };
+    for packet in rtcp::parse_compound(data) {
+        match packet {
+            RtcpPacket::SenderReport {
+                ssrc,
+                ntp_sec,
+                ntp_frac,
+                rtp_ts,
+            } => {
+                if let Some(session) = Register::rtcp_session_from(ssrc, remote_addr) {
+                    session.set_peer(peer);
+                    session.on_sr(ntp_sec, ntp_frac, rtp_ts);
+                }
+            }
+            RtcpPacket::ReceiverReport { ssrc } => {
+                if let Some(session) = Register::rtcp_session_from(ssrc, remote_addr) {
+                    session.set_peer(peer);
+                }
+            }
+            RtcpPacket::Bye { ssrcs } => {
+                for ssrc in ssrcs {
+                    Register::handle_rtcp_bye(ssrc, remote_addr);
+                }
+            }
+        }
+    }
+}
+
+/// 需在 tokio 运行时上下文中调用；未配置独立端口时仅通过 rtcp-mux 收发
+pub fn run(std_socket: Option<std::net::UdpSocket>, cancel: CancellationToken) -> GlobalResult<()> {
+    let socket = match std_socket {
+        None => None,
+        Some(std_socket) => {
+            std_socket
+                .set_nonblocking(true)
+                .hand_log(|msg| error!("{msg}"))?;
+            let socket = UdpSocket::from_std(std_socket).hand_log(|msg| error!("{msg}"))?;
+            Some(Arc::new(socket))
+        }
+    };
+    if let Some(socket) = socket.clone() {
+        tokio::spawn(recv_loop(socket, cancel.clone()));
+    }
+    tokio::spawn(report_loop(socket, cancel));
+    Ok(())
+}
+
+async fn recv_loop(socket: Arc<UdpSocket>, cancel: CancellationToken) {
+    let mut buf = [0u8; RTCP_BUF];
+    loop {
+        tokio::select! {
+            res = socket.recv_from(&mut buf) => {
+                match res {
+                    Ok((len, remote_addr)) => {
+                        if rtcp::is_rtcp(&buf[..len]) {
+                            dispatch(&buf[..len], remote_addr, Protocol::UDP, false);
+                        }
+                    }
+                    Err(err) => warn!("rtcp socket recv failed: {err}"),
+                }
+            }
+            _ = cancel.cancelled() => break,
+        }
+    }
+}
+
+async fn report_loop(socket: Option<Arc<UdpSocket>>, cancel: CancellationToken) {
+    let mut interval = tokio::time::interval(RR_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                for (ssrc, session) in Register::rtcp_sessions() {
+                    //GB28181 设备 RTP 源端口不固定，未收到对端 RTCP 前不发送 RR
+                    let Some(peer) = session.peer() else {
+                        continue;
+                    };
+                    let Some(block) = session.report_block(ssrc) else {
+                        continue;
+                    };
+                    debug!(
+                        "rtcp rr: ssrc={ssrc}, fraction_lost={}, cumulative_lost={}, jitter={}",
+                        block.fraction_lost, block.cumulative_lost, block.jitter
+                    );
+                    send_rr(socket.as_deref(), peer, rtcp::build_rr(ssrc, &block)).await;
+                }
+            }
+            _ = cancel.cancelled() => break,
+        }
+    }
+}
+
+async fn send_rr(socket: Option<&UdpSocket>, peer: RtcpPeer, data: Bytes) {
+    if peer.mux {
+        if let Some(writer) = MUX_WRITER.get() {
+            if let Err(err) = writer.write_to(data, peer.addr, peer.protocol).await {
+                warn!("rtcp mux write failed: peer={}, err={err}", peer.addr);
+            }
+        }
+    } else if let Some(socket) = socket {
+        if let Err(err) = socket.send_to(&data, peer.addr).await {
+            warn!("rtcp socket write failed: peer={}, err={err}", peer.addr);
+        }
+    }
+}
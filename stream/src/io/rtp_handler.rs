@@ -1,5 +1,7 @@
+use crate::io::rtcp_handler;
 use crate::io::talk::TalkManager;
 use crate::media;
+use crate::media::rtcp;
 use crate::state::register::Register;
 use base::bytes::{Bytes, BytesMut};
 use base::err::BaseErrorCode;
@@ -16,6 +18,7 @@ use socket2::Socket;
 use std::net::{SocketAddr, TcpListener, UdpSocket};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
 const RECV_BUF_SIZE: usize = 8 * 1024 * 1024;
 pub fn listen_media_server(port: u16) -> GlobalResult<(Option<TcpListener>, Option<UdpSocket>)> {
     let socket_addr =
@@ -55,6 +58,7 @@ pub fn run(
             Arc::new(U16BeLengthPrefixEncoder),
         )?;
     base::tokio::spawn(write_net(output_rx, writer.clone(), cancel));
+    rtcp_handler::init_mux_writer(writer.clone());
     TalkManager::init_rtp_writer(writer, output_tx, rtp_port)
 }
 
@@ -141,6 +145,7 @@ impl RtpReader {
             marker: pkt.marker_bit(),
             seq: pkt.sequence_number(),
             payload,
+            arrival: Instant::now(),
         };
 
         match rtp_tx.try_send(packet) {
@@ -164,6 +169,11 @@ impl PacketDispatcher for RtpReader {
         remote_addr: SocketAddr,
         protocol: Protocol,
     ) -> GlobalResult<()> {
+        //rtcp-mux：与RTP复用端口的RTCP包
+        if rtcp::is_rtcp(data.as_ref()) {
+            rtcp_handler::dispatch(data.as_ref(), remote_addr, protocol, true);
+            return Ok(());
+        }
         match RtpPacket::parse(data.as_ref()) {
             Ok(pkt) => {
                 let payload_start = pkt.payload_offset();
@@ -0,0 +1,472 @@
+use crate::general::mp::MediaParam;
+use crate::io::http::out::{OutPlayKind, stream_user_token_check};
+use crate::io::rtsp::msg::{
+    Incoming, RtspRequest, RtspResponse, SdpCodec, TransportSpec, build_sdp, codec_from_rfc6381,
+    parse_incoming, parse_play_url, parse_transport,
+};
+use crate::io::talk::build_rtp_packet;
+use crate::media::context::event::ContextEvent;
+use crate::media::context::event::inner::InnerEvent;
+use crate::media::context::format::MuxPacket;
+use crate::media::context::format::muxer::MuxerEnum;
+use crate::media::context::format::rtp::split_payloads;
+use crate::state::register::Register;
+use base::bytes::{BufMut, Bytes, BytesMut};
+use base::exception::{GlobalResult, GlobalResultExt};
+use base::log::{debug, error, info, warn};
+use base::tokio;
+use base::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use base::tokio::net::tcp::OwnedWriteHalf;
+use base::tokio::net::{TcpListener, TcpStream, UdpSocket};
+use base::tokio::sync::broadcast::error::RecvError;
+use base::tokio::sync::{broadcast, mpsc, oneshot};
+use base::tokio_util::sync::CancellationToken;
+use shared::info::output::OutputEnum;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod msg;
+
+const PAYLOAD_TYPE: u8 = 96;
+const SESSION_TIMEOUT_SEC: u64 = 60;
+const WRITE_QUEUE: usize = 512;
+const PUBLIC_METHODS: &str = "OPTIONS, DESCRIBE, SETUP, PLAY, TEARDOWN, GET_PARAMETER";
+
+pub fn listen_rtsp_server(port: u16) -> GlobalResult<Option<std::net::TcpListener>> {
+    if port == 0 {
+        return Ok(None);
+    }
+    let listener =
+        std::net::TcpListener::bind(format!("0.0.0.0:{}", port)).hand_log(|msg| error!("{msg}"))?;
+    Ok(Some(listener))
+}
+
+pub async fn run(
+    std_listener: std::net::TcpListener,
+    cancel_token: CancellationToken,
+) -> GlobalResult<()> {
+    std_listener
+        .set_nonblocking(true)
+        .hand_log(|msg| error!("{msg}"))?;
+    let listener = TcpListener::from_std(std_listener).hand_log(|msg| error!("{msg}"))?;
+    loop {
+        tokio::select! {
+            res = listener.accept() => match res {
+                Ok((stream, addr)) => {
+                    let token = cancel_token.child_token();
+                    tokio::spawn(async move {
+                        RtspConn::serve(stream, addr, token).await;
+                    });
+                }
+                Err(err) => warn!("rtsp accept failed: {err}"),
+            },
+            _ = cancel_token.cancelled() => break,
+        }
+    }
+    Ok(())
+}
+
+/// DESCRIBE 后确定的媒体源
+struct MediaSource {
+    stream_id: Arc<str>,
+    token: Arc<str>,
+    ssrc: u32,
+    muxer: MuxerEnum,
+}
+
+enum RtpSink {
+    Interleaved(mpsc::Sender<Bytes>, u8),
+    Udp(Arc<UdpSocket>, SocketAddr),
+}
+
+impl RtpSink {
+    async fn send(&self, packet: Vec<u8>) -> bool {
+        match self {
+            RtpSink::Interleaved(tx, channel) => {
+                let mut frame = BytesMut::with_capacity(4 + packet.len());
+                frame.put_u8(b'$');
+                frame.put_u8(*channel);
+                frame.put_u16(packet.len() as u16);
+                frame.put_slice(&packet);
+                tx.send(frame.freeze()).await.is_ok()
+            }
+            // UDP 发送失败（如 ICMP 不可达）不中断会话，由会话超时回收
+            RtpSink::Udp(socket, addr) => {
+                let _ = socket.send_to(&packet, *addr).await;
+                true
+            }
+        }
+    }
+}
+
+struct RtspConn {
+    addr: SocketAddr,
+    local_ip: IpAddr,
+    session_id: String,
+    out_tx: mpsc::Sender<Bytes>,
+    source: Option<MediaSource>,
+    sink: Option<RtpSink>,
+    playing: bool,
+    cancel: CancellationToken,
+}
+
+impl RtspConn {
+    async fn serve(stream: TcpStream, addr: SocketAddr, cancel: CancellationToken) {
+        let local_ip = stream
+            .local_addr()
+            .map(|a| a.ip())
+            .unwrap_or(IpAddr::from([127, 0, 0, 1]));
+        let _ = stream.set_nodelay(true);
+        let (mut reader, writer) = stream.into_split();
+        let (out_tx, out_rx) = mpsc::channel(WRITE_QUEUE);
+        tokio::spawn(write_loop(writer, out_rx, cancel.clone()));
+        let mut conn = RtspConn {
+            addr,
+            local_ip,
+            session_id: format!("{:08X}", rand_u32()),
+            out_tx,
+            source: None,
+            sink: None,
+            playing: false,
+            cancel,
+        };
+        debug!("rtsp client connected: {addr}");
+        let mut buf = BytesMut::with_capacity(4096);
+        'conn: loop {
+            loop {
+                match parse_incoming(&mut buf) {
+                    Some(Incoming::Request(req)) => {
+                        if !conn.handle(req).await {
+                            break 'conn;
+                        }
+                    }
+                    Some(Incoming::Interleaved) => {}
+                    None => break,
+                    Some(Incoming::Malformed) => {
+                        warn!("rtsp bad request from {addr}");
+                        break 'conn;
+                    }
+                }
+            }
+            // UDP 模式下依赖控制连接保活，超时未收到任何请求则结束会话
+            let idle = Duration::from_secs(SESSION_TIMEOUT_SEC * 2);
+            tokio::select! {
+                res = tokio::time::timeout(idle, reader.read_buf(&mut buf)) => match res {
+                    Ok(Ok(n)) if n > 0 => {}
+                    Ok(_) => break,
+                    Err(_) if matches!(conn.sink, Some(RtpSink::Udp(..))) => {
+                        info!("rtsp session timeout: addr={addr}");
+                        break;
+                    }
+                    Err(_) => {}
+                },
+                _ = conn.cancel.cancelled() => break,
+            }
+        }
+        conn.close();
+    }
+
+    fn close(&mut self) {
+        self.cancel.cancel();
+        self.close_source();
+    }
+
+    /// 返回 false 表示关闭连接
+    async fn handle(&mut self, req: RtspRequest) -> bool {
+        debug!("rtsp {} {} from {}", req.method, req.url, self.addr);
+        let cseq = req.cseq().to_string();
+        let (res, keep) = match req.method.as_str() {
+            "OPTIONS" => (
+                RtspResponse::ok(&cseq).header("Public", PUBLIC_METHODS),
+                true,
+            ),
+            "DESCRIBE" => (self.describe(&req, &cseq).await, true),
+            "SETUP" => (self.setup(&req, &cseq).await, true),
+            "PLAY" => (self.play(&req, &cseq), true),
+            "GET_PARAMETER" | "SET_PARAMETER" => (self.with_session(RtspResponse::ok(&cseq)), true),
+            "TEARDOWN" => (self.with_session(RtspResponse::ok(&cseq)), false),
+            _ => (RtspResponse::new(501, "Not Implemented", &cseq), true),
+        };
+        self.out_tx.send(res.encode()).await.is_ok() && keep
+    }
+
+    fn with_session(&self, res: RtspResponse) -> RtspResponse {
+        res.header(
+            "Session",
+            format!("{};timeout={}", self.session_id, SESSION_TIMEOUT_SEC),
+        )
+    }
+
+    async fn describe(&mut self, req: &RtspRequest, cseq: &str) -> RtspResponse {
+        let Some((stream_id, token)) = parse_play_url(&req.url) else {
+            return RtspResponse::new(400, "Bad Request", cseq);
+        };
+        let Some(token) = token else {
+            return RtspResponse::new(401, "Unauthorized", cseq);
+        };
+        let stream_id: Arc<str> = Arc::from(stream_id);
+        let Some(bsi) = Register::get_base_stream_info_by_stream_id(stream_id.clone()) else {
+            return RtspResponse::new(404, "Stream Not Found", cseq);
+        };
+        let ssrc = bsi.rtp_info.ssrc;
+        // 优先帧模式（H264/H265），否则以 PS 负载输出
+        let muxer = if Register::get_muxer_rx(&ssrc, MuxerEnum::RtpFrame).is_ok() {
+            MuxerEnum::RtpFrame
+        } else if Register::get_muxer_rx(&ssrc, MuxerEnum::RtpPs).is_ok() {
+            MuxerEnum::RtpPs
+        } else {
+            return RtspResponse::new(404, "Stream Not Found", cseq);
+        };
+        let (codec, rfc6381) = match muxer {
+            MuxerEnum::RtpFrame => {
+                let codec = match get_media_param(ssrc).await {
+                    Ok(MediaParam {
+                        video: Some(video), ..
+                    }) => video.codec,
+                    _ => return RtspResponse::new(503, "Service Unavailable", cseq),
+                };
+                match codec_from_rfc6381(&codec) {
+                    Some(sdp_codec) => (sdp_codec, Some(codec)),
+                    None => return RtspResponse::new(415, "Unsupported Media Type", cseq),
+                }
+            }
+            _ => (SdpCodec::Mp2p, None),
+        };
+        let token: Arc<str> = Arc::from(token);
+        let same_source = self
+            .source
+            .as_ref()
+            .is_some_and(|s| s.stream_id == stream_id && s.token == token);
+        if !same_source {
+            if self.playing {
+                return RtspResponse::new(455, "Method Not Valid in This State", cseq);
+            }
+            match stream_user_token_check(
+                OutputEnum::Rtsp,
+                bsi,
+                stream_id.clone(),
+                token.clone(),
+                self.addr,
+            )
+            .await
+            {
+                OutPlayKind::Play => {}
+                OutPlayKind::Forbid => return RtspResponse::new(401, "Unauthorized", cseq),
+                OutPlayKind::Notfound => return RtspResponse::new(404, "Stream Not Found", cseq),
+            }
+            // 切换播放源：释放上一次的计数
+            self.close_source();
+            info!(
+                "rtsp play authorized: stream_id={}, addr={}",
+                stream_id, self.addr
+            );
+            self.source = Some(MediaSource {
+                stream_id,
+                token,
+                ssrc,
+                muxer,
+            });
+        }
+        let sdp = build_sdp(
+            self.local_ip,
+            &self.session_id,
+            codec,
+            PAYLOAD_TYPE,
+            rfc6381.as_deref(),
+        );
+        let base = req.url.trim_end_matches('/');
+        RtspResponse::ok(cseq)
+            .header("Content-Base", format!("{base}/"))
+            .body("application/sdp", sdp)
+    }
+
+    /// 释放点播计数，由闲置调度触发 off_play 与流闲置检测
+    fn close_source(&mut self) {
+        if let Some(source) = self.source.take() {
+            info!(
+                "rtsp play end: stream_id={}, addr={}",
+                source.stream_id, self.addr
+            );
+            Register::listen_output_timeout(
+                source.stream_id,
+                OutputEnum::Rtsp,
+                source.token,
+                self.addr,
+                0,
+            );
+        }
+    }
+
+    async fn setup(&mut self, req: &RtspRequest, cseq: &str) -> RtspResponse {
+        if self.source.is_none() {
+            return RtspResponse::new(455, "Method Not Valid in This State", cseq);
+        }
+        if self.playing {
+            return RtspResponse::new(455, "Method Not Valid in This State", cseq);
+        }
+        let Some(spec) = req.header("Transport").and_then(parse_transport) else {
+            return RtspResponse::new(461, "Unsupported Transport", cseq);
+        };
+        let transport = match spec {
+            TransportSpec::Tcp {
+                rtp_channel,
+                rtcp_channel,
+            } => {
+                self.sink = Some(RtpSink::Interleaved(self.out_tx.clone(), rtp_channel));
+                format!("RTP/AVP/TCP;unicast;interleaved={rtp_channel}-{rtcp_channel}")
+            }
+            TransportSpec::Udp {
+                rtp_port,
+                rtcp_port,
+            } => {
+                let Ok((rtp, rtcp)) = bind_udp_pair(self.local_ip).await else {
+                    return RtspResponse::new(500, "Internal Server Error", cseq);
+                };
+                let server_rtp = rtp.local_addr().map(|a| a.port()).unwrap_or_default();
+                let server_rtcp = rtcp.local_addr().map(|a| a.port()).unwrap_or_default();
+                let target = SocketAddr::new(self.addr.ip(), rtp_port);
+                self.sink = Some(RtpSink::Udp(Arc::new(rtp), target));
+                // RTCP 套接字仅占位，随会话结束释放
+                let cancel = self.cancel.clone();
+                tokio::spawn(async move {
+                    cancel.cancelled().await;
+                    drop(rtcp);
+                });
+                format!(
+                    "RTP/AVP;unicast;client_port={rtp_port}-{rtcp_port};server_port={server_rtp}-{server_rtcp}"
+                )
+            }
+        };
+        self.with_session(RtspResponse::ok(cseq))
+            .header("Transport", transport)
+    }
+
+    fn play(&mut self, req: &RtspRequest, cseq: &str) -> RtspResponse {
+        let (Some(source), Some(sink)) = (&self.source, self.sink.take()) else {
+            if self.playing {
+                return self.with_session(RtspResponse::ok(cseq));
+            }
+            return RtspResponse::new(455, "Method Not Valid in This State", cseq);
+        };
+        let rx = match Register::get_muxer_rx(&source.ssrc, source.muxer) {
+            Ok(rx) => rx,
+            Err(_) => return RtspResponse::new(404, "Stream Not Found", cseq),
+        };
+        self.playing = true;
+        info!(
+            "rtsp play start: stream_id={}, addr={}, muxer={:?}",
+            source.stream_id, self.addr, source.muxer
+        );
+        tokio::spawn(send_loop(
+            rx,
+            sink,
+            source.ssrc,
+            source.stream_id.clone(),
+            self.cancel.clone(),
+        ));
+        let base = req.url.trim_end_matches('/');
+        self.with_session(RtspResponse::ok(cseq))
+            .header("Range", "npt=0.000-")
+            .header("RTP-Info", format!("url={base}/trackID=0;seq=0"))
+    }
+}
+
+async fn write_loop(
+    mut writer: OwnedWriteHalf,
+    mut out_rx: mpsc::Receiver<Bytes>,
+    cancel: CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            // 优先写完已排队数据，保证 TEARDOWN 响应先于关闭发出
+            biased;
+            data = out_rx.recv() => match data {
+                Some(data) => {
+                    if writer.write_all(&data).await.is_err() {
+                        break;
+                    }
+                }
+                None => break,
+            },
+            _ = cancel.cancelled() => break,
+        }
+    }
+    cancel.cancel();
+    let _ = writer.shutdown().await;
+}
+
+/// 从关键帧起播，丢帧后等待下一个关键帧；源流结束时关闭会话
+async fn send_loop(
+    mut rx: broadcast::Receiver<Arc<MuxPacket>>,
+    sink: RtpSink,
+    ssrc: u32,
+    stream_id: Arc<str>,
+    cancel: CancellationToken,
+) {
+    let mut seq = 0u16;
+    let mut wait_key = true;
+    loop {
+        let pkt = tokio::select! {
+            res = rx.recv() => match res {
+                Ok(pkt) => pkt,
+                Err(RecvError::Lagged(n)) => {
+                    warn!("rtsp send lagged {n} frames: stream_id={stream_id}");
+                    wait_key = true;
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = cancel.cancelled() => return,
+        };
+        if wait_key {
+            if !pkt.is_key {
+                continue;
+            }
+            wait_key = false;
+        }
+        let payloads = split_payloads(&pkt.data);
+        let last = payloads.len().saturating_sub(1);
+        for (i, payload) in payloads.iter().enumerate() {
+            let packet = build_rtp_packet(
+                ssrc,
+                seq,
+                pkt.timestamp as u32,
+                i == last,
+                PAYLOAD_TYPE,
+                payload,
+            );
+            seq = seq.wrapping_add(1);
+            if !sink.send(packet).await {
+                cancel.cancel();
+                return;
+            }
+        }
+    }
+    info!("rtsp source closed: stream_id={stream_id}");
+    cancel.cancel();
+}
+
+async fn get_media_param(ssrc: u32) -> GlobalResult<MediaParam> {
+    let (tx, rx) = oneshot::channel();
+    Register::try_publish_mpsc(ssrc, ContextEvent::Inner(InnerEvent::MediaParam(tx)))?;
+    Ok(rx.await.hand_log(|msg| error!("{msg}"))?)
+}
+
+async fn bind_udp_pair(ip: IpAddr) -> GlobalResult<(UdpSocket, UdpSocket)> {
+    let rtp = UdpSocket::bind(SocketAddr::new(ip, 0))
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+    let rtcp = UdpSocket::bind(SocketAddr::new(ip, 0))
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+    Ok((rtp, rtcp))
+}
+
+fn rand_u32() -> u32 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    nanos ^ std::process::id().rotate_left(16)
+}
@@ -0,0 +1,319 @@
+use base::bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::net::IpAddr;
+
+const MAX_HEADER_LEN: usize = 16 * 1024;
+
+pub struct RtspRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+}
+
+impl RtspRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn cseq(&self) -> &str {
+        self.header("CSeq").unwrap_or("0")
+    }
+}
+
+/// 控制连接上的输入：RTSP 请求或客户端交织数据（如 TCP 模式的 RTCP RR）
+pub enum Incoming {
+    Request(RtspRequest),
+    Interleaved,
+    Malformed,
+}
+
+/// 数据不足返回 None
+pub fn parse_incoming(buf: &mut BytesMut) -> Option<Incoming> {
+    if buf.is_empty() {
+        return None;
+    }
+    if buf[0] == b'$' {
+        if buf.len() < 4 {
+            return None;
+        }
+        let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if buf.len() < 4 + len {
+            return None;
+        }
+        buf.advance(4 + len);
+        return Some(Incoming::Interleaved);
+    }
+    let Some(head_end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+        return (buf.len() > MAX_HEADER_LEN).then_some(Incoming::Malformed);
+    };
+    let Some((method, url, headers)) = std::str::from_utf8(&buf[..head_end])
+        .ok()
+        .and_then(parse_head)
+    else {
+        return Some(Incoming::Malformed);
+    };
+    let body_len = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("Content-Length"))
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    let total = head_end + 4 + body_len;
+    if buf.len() < total {
+        return None;
+    }
+    let mut msg = buf.split_to(total);
+    msg.advance(head_end + 4);
+    Some(Incoming::Request(RtspRequest {
+        method,
+        url,
+        headers,
+        body: msg.freeze(),
+    }))
+}
+
+type RequestHead = (String, String, Vec<(String, String)>);
+
+fn parse_head(head: &str) -> Option<RequestHead> {
+    let mut lines = head.split("\r\n");
+    let mut start = lines.next()?.split_whitespace();
+    let method = start.next()?.to_string();
+    let url = start.next()?.to_string();
+    if !start.next()?.starts_with("RTSP/") {
+        return None;
+    }
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+    Some((method, url, headers))
+}
+
+pub struct RtspResponse {
+    code: u16,
+    reason: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Option<(&'static str, String)>,
+}
+
+impl RtspResponse {
+    pub fn new(code: u16, reason: &'static str, cseq: &str) -> Self {
+        RtspResponse {
+            code,
+            reason,
+            headers: vec![("CSeq", cseq.to_string()), ("Server", "GMV".to_string())],
+            body: None,
+        }
+    }
+
+    pub fn ok(cseq: &str) -> Self {
+        Self::new(200, "OK", cseq)
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub fn body(mut self, content_type: &'static str, body: String) -> Self {
+        self.body = Some((content_type, body));
+        self
+    }
+
+    pub fn encode(&self) -> Bytes {
+        let mut out = BytesMut::with_capacity(256);
+        out.put_slice(format!("RTSP/1.0 {} {}\r\n", self.code, self.reason).as_bytes());
+        for (k, v) in &self.headers {
+            out.put_slice(format!("{k}: {v}\r\n").as_bytes());
+        }
+        match &self.body {
+            Some((content_type, body)) => {
+                out.put_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+                out.put_slice(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes());
+                out.put_slice(body.as_bytes());
+            }
+            None => out.put_slice(b"\r\n"),
+        }
+        out.freeze()
+    }
+}
+
+/// 播放地址：rtsp://host:port/[..]/{stream_id}?gmv-token=xxx
+pub fn parse_play_url(url: &str) -> Option<(String, Option<String>)> {
+    let rest = url.strip_prefix("rtsp://")?;
+    let (_, path) = rest.split_once('/')?;
+    let (path, query) = match path.split_once('?') {
+        Some((p, q)) => (p, Some(q)),
+        None => (path, None),
+    };
+    let stream_id = path.rsplit('/').find(|seg| !seg.is_empty())?;
+    let token = query.and_then(|q| {
+        q.split(['&', '/'])
+            .filter_map(|kv| kv.split_once('='))
+            .find(|(k, _)| *k == "gmv-token")
+            .map(|(_, v)| v.to_string())
+    });
+    Some((stream_id.to_string(), token))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportSpec {
+    Tcp { rtp_channel: u8, rtcp_channel: u8 },
+    Udp { rtp_port: u16, rtcp_port: u16 },
+}
+
+/// 解析 SETUP 的 Transport 头，取第一个可接受的传输方式
+pub fn parse_transport(value: &str) -> Option<TransportSpec> {
+    value.split(',').find_map(|spec| {
+        let mut parts = spec.split(';').map(str::trim);
+        let proto = parts.next()?;
+        let params: Vec<&str> = parts.collect();
+        let pair = |name: &str| -> Option<(u16, u16)> {
+            let v = params.iter().find_map(|p| p.strip_prefix(name))?;
+            match v.split_once('-') {
+                Some((a, b)) => Some((a.parse().ok()?, b.parse().ok()?)),
+                None => {
+                    let a: u16 = v.parse().ok()?;
+                    Some((a, a.checked_add(1)?))
+                }
+            }
+        };
+        if proto.eq_ignore_ascii_case("RTP/AVP/TCP") {
+            let (rtp, rtcp) = pair("interleaved=").unwrap_or((0, 1));
+            Some(TransportSpec::Tcp {
+                rtp_channel: u8::try_from(rtp).ok()?,
+                rtcp_channel: u8::try_from(rtcp).ok()?,
+            })
+        } else if proto.eq_ignore_ascii_case("RTP/AVP") || proto.eq_ignore_ascii_case("RTP/AVP/UDP")
+        {
+            if params.iter().any(|p| p.eq_ignore_ascii_case("multicast")) {
+                return None;
+            }
+            let (rtp_port, rtcp_port) = pair("client_port=")?;
+            Some(TransportSpec::Udp {
+                rtp_port,
+                rtcp_port,
+            })
+        } else {
+            None
+        }
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SdpCodec {
+    H264,
+    H265,
+    //无帧模式复用器时以 PS 负载输出
+    Mp2p,
+}
+
+/// 由 RFC6381 codec 字符串推断编码
+pub fn codec_from_rfc6381(codec: &str) -> Option<SdpCodec> {
+    let prefix = codec.split('.').next().unwrap_or_default();
+    match prefix {
+        "avc1" | "avc3" => Some(SdpCodec::H264),
+        "hvc1" | "hev1" => Some(SdpCodec::H265),
+        _ => None,
+    }
+}
+
+pub fn build_sdp(
+    local_ip: IpAddr,
+    session_id: &str,
+    codec: SdpCodec,
+    payload_type: u8,
+    rfc6381: Option<&str>,
+) -> String {
+    let mut sdp = String::with_capacity(256);
+    sdp.push_str("v=0\r\n");
+    sdp.push_str(&format!("o=- {session_id} 1 IN IP4 {local_ip}\r\n"));
+    sdp.push_str("s=GMV Stream\r\n");
+    sdp.push_str(&format!("c=IN IP4 {local_ip}\r\n"));
+    sdp.push_str("t=0 0\r\n");
+    sdp.push_str("a=control:*\r\n");
+    sdp.push_str("a=range:npt=0-\r\n");
+    sdp.push_str(&format!("m=video 0 RTP/AVP {payload_type}\r\n"));
+    match codec {
+        SdpCodec::H264 => {
+            sdp.push_str(&format!("a=rtpmap:{payload_type} H264/90000\r\n"));
+            let profile = rfc6381
+                .and_then(|c| c.split_once('.'))
+                .map(|(_, p)| p)
+                .filter(|p| p.len() == 6)
+                .map(|p| format!(";profile-level-id={p}"))
+                .unwrap_or_default();
+            sdp.push_str(&format!(
+                "a=fmtp:{payload_type} packetization-mode=1{profile}\r\n"
+            ));
+        }
+        SdpCodec::H265 => sdp.push_str(&format!("a=rtpmap:{payload_type} H265/90000\r\n")),
+        SdpCodec::Mp2p => sdp.push_str(&format!("a=rtpmap:{payload_type} MP2P/90000\r\n")),
+    }
+    sdp.push_str("a=sendonly\r\n");
+    sdp.push_str("a=control:trackID=0\r\n");
+    sdp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_request_and_skip_interleaved() {
+        let mut buf = BytesMut::from(
+            &b"$\x01\x00\x02\xAA\xBBDESCRIBE rtsp://10.0.0.1:18554/s1/play/abc?gmv-token=t1 RTSP/1.0\r\nCSeq: 2\r\nAccept: application/sdp\r\n\r\nOPTIONS"[..],
+        );
+        assert!(matches!(
+            parse_incoming(&mut buf),
+            Some(Incoming::Interleaved)
+        ));
+        let Some(Incoming::Request(req)) = parse_incoming(&mut buf) else {
+            panic!("request expected");
+        };
+        assert_eq!(req.method, "DESCRIBE");
+        assert_eq!(req.cseq(), "2");
+        assert_eq!(req.header("accept"), Some("application/sdp"));
+        assert!(parse_incoming(&mut buf).is_none());
+        assert_eq!(&buf[..], b"OPTIONS");
+        assert_eq!(
+            parse_play_url(&req.url),
+            Some(("abc".to_string(), Some("t1".to_string())))
+        );
+    }
+
+    #[test]
+    fn parse_transport_prefers_first_supported() {
+        assert_eq!(
+            parse_transport("RTP/AVP/TCP;unicast;interleaved=2-3"),
+            Some(TransportSpec::Tcp {
+                rtp_channel: 2,
+                rtcp_channel: 3
+            })
+        );
+        assert_eq!(
+            parse_transport("RTP/AVP;multicast,RTP/AVP;unicast;client_port=5000-5001"),
+            Some(TransportSpec::Udp {
+                rtp_port: 5000,
+                rtcp_port: 5001
+            })
+        );
+        assert_eq!(parse_transport("RTP/SAVP;unicast;client_port=1-2"), None);
+    }
+
+    #[test]
+    fn sdp_carries_codec_and_profile() {
+        let sdp = build_sdp(
+            "10.0.0.1".parse().unwrap(),
+            "1",
+            SdpCodec::H264,
+            96,
+            Some("avc1.4D401F"),
+        );
+        assert!(sdp.contains("a=rtpmap:96 H264/90000\r\n"));
+        assert!(sdp.contains("packetization-mode=1;profile-level-id=4D401F"));
+        assert_eq!(codec_from_rfc6381("hev1.1.6.L93.B0"), Some(SdpCodec::H265));
+    }
+}
@@ -496,7 +496,7 @@ async fn notify_talk_closed(talk_id: &str, reason: &str) {
     }
 }
 
-fn build_rtp_packet(
+pub(crate) fn build_rtp_packet(
     ssrc: u32,
     seq: u16,
     timestamp: u32,
@@ -0,0 +1,544 @@
+use crate::media::context::format::MuxPacket;
+use crate::media::context::format::rtp::{
+    RTP_ENC_AUDIO, RTP_ENC_VIDEO, RtpEncAudio, RtpEncTracks, RtpEncVideo, split_payloads,
+};
+use crate::state::register::Register;
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use base::log::{debug, error, info, warn};
+use base::tokio;
+use base::tokio::net::UdpSocket;
+use base::tokio::sync::broadcast::error::RecvError;
+use base::tokio::sync::{broadcast, watch};
+use base::tokio_util::sync::CancellationToken;
+use parking_lot::Mutex;
+use shared::info::output::OutputEnum;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use uuid::Uuid;
+use webrtc::api::APIBuilder;
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::setting_engine::SettingEngine;
+use webrtc::ice::network_type::NetworkType;
+use webrtc::ice::udp_mux::{UDPMuxDefault, UDPMuxParams};
+use webrtc::ice::udp_network::UDPNetwork;
+use webrtc::ice_transport::ice_candidate_type::RTCIceCandidateType;
+use webrtc::interceptor::registry::Registry;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::rtp::header::Header;
+use webrtc::rtp::packet::Packet;
+use webrtc::rtp_transceiver::rtp_codec::{
+    RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType,
+};
+use webrtc::rtp_transceiver::rtp_sender::RTCRtpSender;
+use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use webrtc::track::track_local::{TrackLocal, TrackLocalWriter};
+
+const MIME_TYPE_H264: &str = "video/H264";
+const MIME_TYPE_H265: &str = "video/H265";
+const MIME_TYPE_PCMA: &str = "audio/PCMA";
+const MIME_TYPE_PCMU: &str = "audio/PCMU";
+// 浏览器常见 offer；H.264 按 profile 严格匹配，解码端实际兼容更高 profile
+const H264_PROFILES: [&str; 4] = ["42e01f", "42001f", "4d001f", "640c1f"];
+const ICE_DISCONNECTED_TIMEOUT: Duration = Duration::from_secs(5);
+const ICE_FAILED_TIMEOUT: Duration = Duration::from_secs(10);
+const ICE_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(2);
+// 播放端未完成 ICE/DTLS 时释放会话
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const RTCP_BUF: usize = 1500;
+
+static RTC_SERVER: OnceLock<RtcServer> = OnceLock::new();
+
+struct RtcServer {
+    udp_mux: Arc<UDPMuxDefault>,
+    public_ips: Vec<String>,
+    sessions: Mutex<HashMap<String, WhepSession>>,
+}
+
+struct WhepSession {
+    stream_id: Arc<str>,
+    token: Arc<str>,
+    cancel: CancellationToken,
+}
+
+/// WHEP 会话的播放端信息
+pub struct WhepPeer {
+    pub stream_id: Arc<str>,
+    pub token: Arc<str>,
+    pub addr: SocketAddr,
+}
+
+pub fn listen_rtc_server(port: u16) -> GlobalResult<Option<std::net::UdpSocket>> {
+    if port == 0 {
+        return Ok(None);
+    }
+    let socket =
+        std::net::UdpSocket::bind(format!("0.0.0.0:{}", port)).hand_log(|msg| error!("{msg}"))?;
+    Ok(Some(socket))
+}
+
+/// 所有 PeerConnection 复用同一 UDP 端口；需在 tokio 运行时上下文中调用
+pub fn init(std_socket: std::net::UdpSocket, public_ip: Option<String>) -> GlobalResult<()> {
+    std_socket
+        .set_nonblocking(true)
+        .hand_log(|msg| error!("{msg}"))?;
+    let socket = UdpSocket::from_std(std_socket).hand_log(|msg| error!("{msg}"))?;
+    let server = RtcServer {
+        udp_mux: UDPMuxDefault::new(UDPMuxParams::new(socket)),
+        public_ips: public_ip.into_iter().collect(),
+        sessions: Mutex::new(HashMap::new()),
+    };
+    let _ = RTC_SERVER.set(server);
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    RTC_SERVER.get().is_some()
+}
+
+/// 协商 SDP 并启动发送；返回 (session_id, answer sdp)
+pub async fn answer(
+    peer: WhepPeer,
+    tracks: RtpEncTracks,
+    offer: String,
+    pkt_rx: broadcast::Receiver<Arc<MuxPacket>>,
+) -> GlobalResult<(String, String)> {
+    let Some(server) = RTC_SERVER.get() else {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::InvalidState.code(),
+            "webrtc 服务未开启",
+            |msg| warn!("{msg}"),
+        ));
+    };
+    check_audio(&tracks)?;
+    let pc = new_peer_connection(server, &tracks).await?;
+    match negotiate(&pc, &peer, &tracks, offer).await {
+        Ok((sdp, video, audio)) => {
+            // 会话 ID 用于 DELETE 资源定位，需不可猜测
+            let session_id = Uuid::new_v4().simple().to_string();
+            let cancel = CancellationToken::new();
+            server.sessions.lock().insert(
+                session_id.clone(),
+                WhepSession {
+                    stream_id: peer.stream_id.clone(),
+                    token: peer.token.clone(),
+                    cancel: cancel.clone(),
+                },
+            );
+            let (state_tx, state_rx) = watch::channel(RTCPeerConnectionState::New);
+            pc.on_peer_connection_state_change(Box::new(move |state| {
+                let _ = state_tx.send(state);
+                Box::pin(async {})
+            }));
+            info!(
+                "whep session {} start: stream_id={}, addr={}",
+                session_id, peer.stream_id, peer.addr
+            );
+            let id = session_id.clone();
+            tokio::spawn(async move {
+                send_loop(video, audio, pkt_rx, state_rx, cancel).await;
+                server.sessions.lock().remove(&id);
+                let _ = pc.close().await;
+                info!("whep session {} closed", id);
+                Register::listen_output_timeout(
+                    peer.stream_id,
+                    OutputEnum::WebRtc,
+                    peer.token,
+                    peer.addr,
+                    0,
+                );
+            });
+            Ok((session_id, sdp))
+        }
+        Err(err) => {
+            let _ = pc.close().await;
+            Err(err)
+        }
+    }
+}
+
+/// WHEP DELETE；会话不存在或 token 不一致时返回 false
+pub fn close(stream_id: &str, session_id: &str, token: &str) -> bool {
+    let Some(server) = RTC_SERVER.get() else {
+        return false;
+    };
+    let mut sessions = server.sessions.lock();
+    match sessions.get(session_id) {
+        Some(session) if &*session.stream_id == stream_id && &*session.token == token => {
+            if let Some(session) = sessions.remove(session_id) {
+                session.cancel.cancel();
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+async fn new_peer_connection(
+    server: &RtcServer,
+    tracks: &RtpEncTracks,
+) -> GlobalResult<RTCPeerConnection> {
+    let mut media_engine = MediaEngine::default();
+    for params in codec_parameters(tracks) {
+        let kind = if params.capability.mime_type.starts_with("video/") {
+            RTPCodecType::Video
+        } else {
+            RTPCodecType::Audio
+        };
+        media_engine
+            .register_codec(params, kind)
+            .hand_log(|msg| error!("{msg}"))?;
+    }
+    let registry = register_default_interceptors(Registry::new(), &mut media_engine)
+        .hand_log(|msg| error!("{msg}"))?;
+    let mut setting_engine = SettingEngine::default();
+    setting_engine.set_lite(true);
+    setting_engine.set_network_types(vec![NetworkType::Udp4]);
+    setting_engine.set_udp_network(UDPNetwork::Muxed(server.udp_mux.clone()));
+    setting_engine.set_ice_timeouts(
+        Some(ICE_DISCONNECTED_TIMEOUT),
+        Some(ICE_FAILED_TIMEOUT),
+        Some(ICE_KEEPALIVE_INTERVAL),
+    );
+    if !server.public_ips.is_empty() {
+        setting_engine.set_nat_1to1_ips(server.public_ips.clone(), RTCIceCandidateType::Host);
+    }
+    let api = APIBuilder::new()
+        .with_media_engine(media_engine)
+        .with_interceptor_registry(registry)
+        .with_setting_engine(setting_engine)
+        .build();
+    api.new_peer_connection(RTCConfiguration::default())
+        .await
+        .hand_log(|msg| error!("{msg}"))
+}
+
+type NegotiatedTracks = (
+    String,
+    Arc<TrackLocalStaticRTP>,
+    Option<Arc<TrackLocalStaticRTP>>,
+);
+
+async fn negotiate(
+    pc: &RTCPeerConnection,
+    peer: &WhepPeer,
+    tracks: &RtpEncTracks,
+    offer: String,
+) -> GlobalResult<NegotiatedTracks> {
+    let video = Arc::new(TrackLocalStaticRTP::new(
+        video_capability(tracks),
+        "video".to_string(),
+        peer.stream_id.to_string(),
+    ));
+    let sender = pc
+        .add_track(Arc::clone(&video) as Arc<dyn TrackLocal + Send + Sync>)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+    drain_rtcp(sender);
+    let audio = match tracks.audio {
+        Some(kind) => {
+            let audio = Arc::new(TrackLocalStaticRTP::new(
+                audio_capability(kind),
+                "audio".to_string(),
+                peer.stream_id.to_string(),
+            ));
+            let sender = pc
+                .add_track(Arc::clone(&audio) as Arc<dyn TrackLocal + Send + Sync>)
+                .await
+                .hand_log(|msg| error!("{msg}"))?;
+            drain_rtcp(sender);
+            Some(audio)
+        }
+        None => None,
+    };
+    let offer = RTCSessionDescription::offer(offer).map_err(|err| {
+        GlobalError::new_biz_error(
+            BaseErrorCode::InvalidRequest.code(),
+            &format!("无效的 SDP offer: {err}"),
+            |msg| warn!("{msg}"),
+        )
+    })?;
+    pc.set_remote_description(offer)
+        .await
+        .hand_log(|msg| warn!("{msg}"))?;
+    let answer = pc
+        .create_answer(None)
+        .await
+        .hand_log(|msg| warn!("{msg}"))?;
+    let mut gathered = pc.gathering_complete_promise().await;
+    pc.set_local_description(answer)
+        .await
+        .hand_log(|msg| error!("{msg}"))?;
+    let _ = gathered.recv().await;
+    let Some(local) = pc.local_description().await else {
+        return Err(GlobalError::new_sys_error(
+            "webrtc local description missing",
+            |msg| error!("{msg}"),
+        ));
+    };
+    check_answer_video(&local.sdp, tracks)?;
+    Ok((local.sdp, video, audio))
+}
+
+/// 源音频无法以 G.711 输出时拒绝协商，避免静默丢弃音频
+fn check_audio(tracks: &RtpEncTracks) -> GlobalResult<()> {
+    match &tracks.unsupported_audio {
+        Some(codec) => Err(GlobalError::new_biz_error(
+            BaseErrorCode::Unsupported.code(),
+            &format!("WebRTC 不支持音频编码: {codec}，请配置音频转码为 G711a/G711u"),
+            |msg| warn!("{msg}"),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// 浏览器不支持对应视频编码时 m-line 被拒绝
+fn check_answer_video(sdp: &str, tracks: &RtpEncTracks) -> GlobalResult<()> {
+    let encoding = video_capability(tracks).mime_type.replace("video/", "");
+    if !sdp.contains(&format!(" {encoding}/90000")) {
+        return Err(GlobalError::new_biz_error(
+            BaseErrorCode::Unsupported.code(),
+            &format!("播放端不支持视频编码: {encoding}"),
+            |msg| warn!("{msg}"),
+        ));
+    }
+    Ok(())
+}
+
+fn codec_parameters(tracks: &RtpEncTracks) -> Vec<RTCRtpCodecParameters> {
+    let mut params = Vec::new();
+    match tracks.video {
+        RtpEncVideo::H264 => {
+            let mut profiles: Vec<&str> = tracks.profile_level_id.as_deref().into_iter().collect();
+            profiles.extend(
+                H264_PROFILES
+                    .iter()
+                    .filter(|p| tracks.profile_level_id.as_deref() != Some(**p)),
+            );
+            for (i, profile) in profiles.into_iter().enumerate() {
+                params.push(RTCRtpCodecParameters {
+                    capability: h264_capability(profile),
+                    payload_type: 96 + i as u8,
+                    ..Default::default()
+                });
+            }
+        }
+        RtpEncVideo::H265 => params.push(RTCRtpCodecParameters {
+            capability: video_capability(tracks),
+            payload_type: 96,
+            ..Default::default()
+        }),
+    }
+    if let Some(kind) = tracks.audio {
+        params.push(RTCRtpCodecParameters {
+            capability: audio_capability(kind),
+            payload_type: match kind {
+                RtpEncAudio::Pcma => 8,
+                RtpEncAudio::Pcmu => 0,
+            },
+            ..Default::default()
+        });
+    }
+    params
+}
+
+fn video_capability(tracks: &RtpEncTracks) -> RTCRtpCodecCapability {
+    match tracks.video {
+        RtpEncVideo::H264 => h264_capability(
+            tracks
+                .profile_level_id
+                .as_deref()
+                .unwrap_or(H264_PROFILES[0]),
+        ),
+        RtpEncVideo::H265 => RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_H265.to_string(),
+            clock_rate: 90000,
+            ..Default::default()
+        },
+    }
+}
+
+fn h264_capability(profile: &str) -> RTCRtpCodecCapability {
+    RTCRtpCodecCapability {
+        mime_type: MIME_TYPE_H264.to_string(),
+        clock_rate: 90000,
+        sdp_fmtp_line: format!(
+            "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id={profile}"
+        ),
+        ..Default::default()
+    }
+}
+
+fn audio_capability(kind: RtpEncAudio) -> RTCRtpCodecCapability {
+    let mime_type = match kind {
+        RtpEncAudio::Pcma => MIME_TYPE_PCMA,
+        RtpEncAudio::Pcmu => MIME_TYPE_PCMU,
+    };
+    RTCRtpCodecCapability {
+        mime_type: mime_type.to_string(),
+        clock_rate: 8000,
+        channels: 1,
+        ..Default::default()
+    }
+}
+
+/// 读取 RTCP 以驱动 NACK/报告等 interceptor
+fn drain_rtcp(sender: Arc<RTCRtpSender>) {
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; RTCP_BUF];
+        while sender.read(&mut buf).await.is_ok() {}
+    });
+}
+
+async fn send_loop(
+    video: Arc<TrackLocalStaticRTP>,
+    audio: Option<Arc<TrackLocalStaticRTP>>,
+    mut rx: broadcast::Receiver<Arc<MuxPacket>>,
+    mut state_rx: watch::Receiver<RTCPeerConnectionState>,
+    cancel: CancellationToken,
+) {
+    let mut connected = false;
+    let mut wait_key = true;
+    let mut video_seq = random_seq();
+    let mut audio_seq = random_seq();
+    let connect_timeout = tokio::time::sleep(CONNECT_TIMEOUT);
+    tokio::pin!(connect_timeout);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = &mut connect_timeout, if !connected => {
+                warn!("whep peer connect timeout");
+                break;
+            }
+            res = state_rx.changed() => {
+                if res.is_err() {
+                    break;
+                }
+                let state = *state_rx.borrow_and_update();
+                debug!("whep peer connection state: {state}");
+                match state {
+                    RTCPeerConnectionState::Connected => connected = true,
+                    RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed => break,
+                    _ => {}
+                }
+            }
+            res = rx.recv() => match res {
+                Ok(pkt) => {
+                    if !connected || pkt.data.is_empty() {
+                        continue;
+                    }
+                    let payloads = split_payloads(&pkt.data.slice(1..));
+                    let (track, seq, frame_marker) = match pkt.data[0] {
+                        RTP_ENC_VIDEO => {
+                            if wait_key && !pkt.is_key {
+                                continue;
+                            }
+                            wait_key = false;
+                            (&video, &mut video_seq, true)
+                        }
+                        RTP_ENC_AUDIO => match &audio {
+                            Some(audio) if !wait_key => (audio, &mut audio_seq, false),
+                            _ => continue,
+                        },
+                        _ => continue,
+                    };
+                    let count = payloads.len();
+                    for (i, payload) in payloads.into_iter().enumerate() {
+                        let packet = Packet {
+                            header: Header {
+                                version: 2,
+                                marker: frame_marker && i + 1 == count,
+                                sequence_number: *seq,
+                                timestamp: pkt.timestamp as u32,
+                                ..Default::default()
+                            },
+                            payload,
+                        };
+                        *seq = seq.wrapping_add(1);
+                        if let Err(err) = track.write_rtp(&packet).await {
+                            debug!("whep write rtp failed: {err}");
+                        }
+                    }
+                }
+                // 丢包后从下一个关键帧恢复
+                Err(RecvError::Lagged(_)) => wait_key = true,
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+// RFC3550：初始序号随机
+fn random_seq() -> u16 {
+    Uuid::new_v4().as_u128() as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracks(video: RtpEncVideo, audio: Option<RtpEncAudio>) -> RtpEncTracks {
+        RtpEncTracks {
+            video,
+            audio,
+            unsupported_audio: None,
+            profile_level_id: None,
+        }
+    }
+
+    #[test]
+    fn h264_params_put_source_profile_first() {
+        let mut tracks = tracks(RtpEncVideo::H264, Some(RtpEncAudio::Pcma));
+        tracks.profile_level_id = Some("4d001f".to_string());
+        let params = codec_parameters(&tracks);
+        let profiles: Vec<&str> = params
+            .iter()
+            .filter_map(|p| p.capability.sdp_fmtp_line.rsplit_once('='))
+            .map(|(_, profile)| profile)
+            .collect();
+        // 源 profile 优先且不重复注册
+        assert_eq!(profiles, vec!["4d001f", "42e01f", "42001f", "640c1f"]);
+        let payload_types: Vec<u8> = params.iter().map(|p| p.payload_type).collect();
+        assert_eq!(payload_types, vec![96, 97, 98, 99, 8]);
+        let audio = params.last().unwrap();
+        assert_eq!(audio.capability.mime_type, MIME_TYPE_PCMA);
+        assert_eq!(audio.capability.clock_rate, 8000);
+    }
+
+    #[test]
+    fn h265_params_with_pcmu() {
+        let params = codec_parameters(&tracks(RtpEncVideo::H265, Some(RtpEncAudio::Pcmu)));
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].capability.mime_type, MIME_TYPE_H265);
+        assert_eq!(params[0].payload_type, 96);
+        assert_eq!(params[1].capability.mime_type, MIME_TYPE_PCMU);
+        assert_eq!(params[1].payload_type, 0);
+
+        let params = codec_parameters(&tracks(RtpEncVideo::H265, None));
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn answer_rejected_without_negotiated_video() {
+        let tracks = tracks(RtpEncVideo::H265, None);
+        let accepted = "m=video 9 UDP/TLS/RTP/SAVPF 96\r\na=rtpmap:96 H265/90000\r\n";
+        assert!(check_answer_video(accepted, &tracks).is_ok());
+        // 浏览器拒绝 m-line 时 answer 端口为 0 且无对应 rtpmap
+        let rejected = "m=video 0 UDP/TLS/RTP/SAVPF 0\r\n";
+        assert!(check_answer_video(rejected, &tracks).is_err());
+    }
+
+    #[test]
+    fn unsupported_audio_fails_negotiation() {
+        let mut tracks = tracks(RtpEncVideo::H264, None);
+        assert!(check_audio(&tracks).is_ok());
+        tracks.unsupported_audio = Some("aac".to_string());
+        assert!(check_audio(&tracks).is_err());
+    }
+}
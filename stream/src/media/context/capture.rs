@@ -0,0 +1,258 @@
+use crate::media::context::codec::{encode, open_decoder};
+use crate::media::context::format::demuxer::DemuxerContext;
+use crate::media::show_ffmpeg_error_msg;
+use crate::state::event::{Event, OutEvent};
+use crate::state::layer::filter_layer::CaptureLayer;
+use crate::state::register::Register;
+use base::bytes::{Bytes, BytesMut};
+use base::err::BaseErrorCode;
+use base::exception::{GlobalError, GlobalResult, GlobalResultExt};
+use log::{error, info};
+use rsmpeg::ffi::{
+    AV_CODEC_FLAG_QSCALE, AV_PKT_FLAG_KEY, AVCodecContext, AVCodecID_AV_CODEC_ID_MJPEG,
+    AVCodecID_AV_CODEC_ID_PNG, AVFrame, AVMediaType_AVMEDIA_TYPE_VIDEO, AVPacket,
+    AVPixelFormat_AV_PIX_FMT_RGB24, AVPixelFormat_AV_PIX_FMT_YUVJ420P, AVRational, FF_QP2LAMBDA,
+    SWS_BILINEAR, av_frame_alloc, av_frame_free, av_frame_get_buffer, av_packet_alloc,
+    av_packet_free, av_packet_ref, av_packet_unref, avcodec_alloc_context3, avcodec_find_encoder,
+    avcodec_free_context, avcodec_open2, avcodec_receive_frame, avcodec_send_packet,
+    sws_freeContext, sws_getContext, sws_scale,
+};
+use shared::info::filter::ImageFormat;
+use std::ffi::c_int;
+use std::ptr;
+use std::sync::Arc;
+use std::time::Instant;
+
+//jpeg 量化参数，越小画质越高
+const JPEG_QSCALE: c_int = 3;
+
+/// 抽图：缓存最近的视频关键帧，按需或按间隔解码为图片；取自转码前的原始流
+pub struct CaptureContext {
+    ssrc: u32,
+    stream_id: Arc<str>,
+    layer: Option<CaptureLayer>,
+    keyframe: *mut AVPacket,
+    last_sample: Option<Instant>,
+}
+
+impl CaptureContext {
+    pub fn init(ssrc: u32, stream_id: Arc<str>, layer: Option<CaptureLayer>) -> CaptureContext {
+        CaptureContext {
+            ssrc,
+            stream_id,
+            layer,
+            keyframe: unsafe { av_packet_alloc() },
+            last_sample: None,
+        }
+    }
+
+    /// 缓存视频关键帧；开启定时抽图时，间隔到期后抽取当前关键帧回调信令入库
+    pub(crate) unsafe fn cache(&mut self, demuxer: &DemuxerContext, pkt: &AVPacket) {
+        if self.keyframe.is_null() || pkt.flags & AV_PKT_FLAG_KEY as c_int == 0 {
+            return;
+        }
+        let fmt_ctx = demuxer.avio.fmt_ctx;
+        if fmt_ctx.is_null() || pkt.stream_index as u32 >= (*fmt_ctx).nb_streams {
+            return;
+        }
+        let st = *(*fmt_ctx).streams.add(pkt.stream_index as usize);
+        if (*(*st).codecpar).codec_type != AVMediaType_AVMEDIA_TYPE_VIDEO {
+            return;
+        }
+        av_packet_unref(self.keyframe);
+        if av_packet_ref(self.keyframe, pkt) < 0 {
+            return;
+        }
+        let Some(CaptureLayer {
+            interval: Some(interval),
+            format,
+        }) = self.layer
+        else {
+            return;
+        };
+        if self
+            .last_sample
+            .is_some_and(|last| last.elapsed() < interval)
+        {
+            return;
+        }
+        self.last_sample = Some(Instant::now());
+        if let Ok(bytes) = self.snapshot(demuxer, format) {
+            let _ = Register::get_event_tx()
+                .try_send((
+                    Event::Out(OutEvent::StreamCapture(
+                        self.stream_id.clone(),
+                        format,
+                        bytes,
+                    )),
+                    None,
+                ))
+                .hand_log(|msg| error!("ssrc: {}; {msg}", self.ssrc));
+        }
+    }
+
+    /// 解码最近关键帧并编码为图片
+    pub fn snapshot(&self, demuxer: &DemuxerContext, format: ImageFormat) -> GlobalResult<Bytes> {
+        unsafe {
+            if self.keyframe.is_null() || (*self.keyframe).data.is_null() {
+                return Err(GlobalError::new_biz_error(
+                    BaseErrorCode::NotFound.code(),
+                    "暂无可用关键帧",
+                    |msg| error!("ssrc: {}; {msg}", self.ssrc),
+                ));
+            }
+            let fmt_ctx = demuxer.avio.fmt_ctx;
+            let st = *(*fmt_ctx)
+                .streams
+                .add((*self.keyframe).stream_index as usize);
+            let mut dec = open_decoder(self.ssrc, st)?;
+            let mut frame = av_frame_alloc();
+            let res = if frame.is_null() {
+                Err(sys_error(self.ssrc, "Failed to alloc frame"))
+            } else {
+                self.decode_keyframe(dec, frame)
+                    .and_then(|_| encode_image(self.ssrc, frame, format))
+            };
+            av_frame_free(&mut frame);
+            avcodec_free_context(&mut dec);
+            if let Ok(bytes) = &res {
+                info!(
+                    "ssrc: {}; snapshot {:?} captured: {} bytes",
+                    self.ssrc,
+                    format,
+                    bytes.len()
+                );
+            }
+            res
+        }
+    }
+
+    /// 送入关键帧后立即冲刷，取出首帧
+    unsafe fn decode_keyframe(
+        &self,
+        dec: *mut AVCodecContext,
+        frame: *mut AVFrame,
+    ) -> GlobalResult<()> {
+        let mut ret = avcodec_send_packet(dec, self.keyframe);
+        if ret >= 0 {
+            ret = avcodec_send_packet(dec, ptr::null());
+        }
+        if ret >= 0 {
+            ret = avcodec_receive_frame(dec, frame);
+        }
+        check(self.ssrc, ret, "Failed to decode keyframe")
+    }
+}
+
+impl Drop for CaptureContext {
+    fn drop(&mut self) {
+        unsafe {
+            av_packet_free(&mut self.keyframe);
+        }
+    }
+}
+
+/// jpeg 使用 mjpeg(yuvj420p)，png 使用 rgb24
+unsafe fn encode_image(ssrc: u32, frame: *mut AVFrame, format: ImageFormat) -> GlobalResult<Bytes> {
+    let (codec_id, pix_fmt) = match format {
+        ImageFormat::Jpeg => (
+            AVCodecID_AV_CODEC_ID_MJPEG,
+            AVPixelFormat_AV_PIX_FMT_YUVJ420P,
+        ),
+        ImageFormat::Png => (AVCodecID_AV_CODEC_ID_PNG, AVPixelFormat_AV_PIX_FMT_RGB24),
+    };
+    let encoder = avcodec_find_encoder(codec_id);
+    if encoder.is_null() {
+        return Err(sys_error(ssrc, "Image encoder not found"));
+    }
+    let mut enc = avcodec_alloc_context3(encoder);
+    if enc.is_null() {
+        return Err(sys_error(ssrc, "Failed to alloc encoder context"));
+    }
+    (*enc).width = (*frame).width;
+    (*enc).height = (*frame).height;
+    (*enc).pix_fmt = pix_fmt;
+    (*enc).time_base = AVRational { num: 1, den: 25 };
+    if format == ImageFormat::Jpeg {
+        (*enc).flags |= AV_CODEC_FLAG_QSCALE as c_int;
+        (*enc).global_quality = FF_QP2LAMBDA as c_int * JPEG_QSCALE;
+    }
+    let mut scaled = av_frame_alloc();
+    let res = (|| {
+        check(
+            ssrc,
+            avcodec_open2(enc, encoder, ptr::null_mut()),
+            "Failed to open image encoder",
+        )?;
+        if scaled.is_null() {
+            return Err(sys_error(ssrc, "Failed to alloc frame"));
+        }
+        (*scaled).format = pix_fmt;
+        (*scaled).width = (*frame).width;
+        (*scaled).height = (*frame).height;
+        check(
+            ssrc,
+            av_frame_get_buffer(scaled, 0),
+            "Failed to alloc frame buffer",
+        )?;
+        let sws = sws_getContext(
+            (*frame).width,
+            (*frame).height,
+            (*frame).format,
+            (*scaled).width,
+            (*scaled).height,
+            pix_fmt,
+            SWS_BILINEAR as c_int,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null(),
+        );
+        if sws.is_null() {
+            return Err(sys_error(ssrc, "Failed to create sws context"));
+        }
+        sws_scale(
+            sws,
+            (*frame).data.as_ptr() as _,
+            (*frame).linesize.as_ptr(),
+            0,
+            (*frame).height,
+            (*scaled).data.as_ptr() as _,
+            (*scaled).linesize.as_ptr(),
+        );
+        sws_freeContext(sws);
+        (*scaled).pts = 0;
+
+        //单帧编码后立即冲刷
+        let mut pkts = Vec::new();
+        let ret = encode(enc, scaled, &mut pkts).and_then(|_| encode(enc, ptr::null(), &mut pkts));
+        let mut buf = BytesMut::new();
+        for mut pkt in pkts {
+            buf.extend_from_slice(std::slice::from_raw_parts(pkt.data, pkt.size as usize));
+            av_packet_unref(&mut pkt);
+        }
+        if let Err(ret) = ret {
+            check(ssrc, ret, "Failed to encode image")?;
+        }
+        if buf.is_empty() {
+            return Err(sys_error(ssrc, "Empty image encoded"));
+        }
+        Ok(buf.freeze())
+    })();
+    av_frame_free(&mut scaled);
+    avcodec_free_context(&mut enc);
+    res
+}
+
+fn check(ssrc: u32, ret: c_int, msg: &str) -> GlobalResult<()> {
+    if ret < 0 {
+        return Err(sys_error(
+            ssrc,
+            &format!("{msg}: {}", show_ffmpeg_error_msg(ret)),
+        ));
+    }
+    Ok(())
+}
+
+fn sys_error(ssrc: u32, msg: &str) -> GlobalError {
+    GlobalError::new_sys_error(msg, |msg| error!("ssrc: {ssrc}; {msg}"))
+}
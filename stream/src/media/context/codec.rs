@@ -1,8 +1,821 @@
-use crate::state::layer::codec_layer::CodecLayer;
+use crate::media::context::filter::FilterContext;
+use crate::media::context::format::demuxer::{AvioResource, DemuxerContext, ParamRepairState};
+use crate::media::show_ffmpeg_error_msg;
+use crate::state::layer::codec_layer::{
+    AudioTranscodeLayer, CodecLayer, TranscodeLayer, VideoTranscodeLayer,
+};
+use base::exception::{GlobalError, GlobalResult};
+use log::{error, info, warn};
+use rsmpeg::ffi::{
+    AV_CODEC_FLAG_GLOBAL_HEADER, AV_NOPTS_VALUE, AVAudioFifo, AVCodecContext, AVCodecID,
+    AVCodecID_AV_CODEC_ID_AAC, AVCodecID_AV_CODEC_ID_H264, AVCodecID_AV_CODEC_ID_HEVC,
+    AVCodecID_AV_CODEC_ID_MPEG4, AVCodecID_AV_CODEC_ID_PCM_ALAW, AVCodecID_AV_CODEC_ID_PCM_MULAW,
+    AVCodecID_AV_CODEC_ID_SIREN, AVDictionary, AVERROR, AVERROR_EOF, AVFrame,
+    AVMediaType_AVMEDIA_TYPE_AUDIO, AVMediaType_AVMEDIA_TYPE_VIDEO, AVPacket,
+    AVPictureType_AV_PICTURE_TYPE_NONE, AVPixelFormat_AV_PIX_FMT_YUV420P, AVRational,
+    AVSampleFormat, AVSampleFormat_AV_SAMPLE_FMT_FLTP, AVSampleFormat_AV_SAMPLE_FMT_S16, AVStream,
+    EAGAIN, EINVAL, ENOMEM, SWS_BILINEAR, SwrContext, SwsContext, av_audio_fifo_alloc,
+    av_audio_fifo_free, av_audio_fifo_read, av_audio_fifo_size, av_audio_fifo_write,
+    av_channel_layout_copy, av_channel_layout_default, av_dict_free, av_dict_set, av_frame_alloc,
+    av_frame_free, av_frame_get_buffer, av_frame_make_writable, av_frame_unref,
+    av_packet_rescale_ts, av_packet_unref, av_rescale_q, avcodec_alloc_context3,
+    avcodec_find_decoder, avcodec_find_encoder, avcodec_free_context, avcodec_get_name,
+    avcodec_open2, avcodec_parameters_copy, avcodec_parameters_from_context,
+    avcodec_parameters_to_context, avcodec_receive_frame, avcodec_receive_packet,
+    avcodec_send_frame, avcodec_send_packet, avformat_alloc_context, avformat_new_stream,
+    swr_alloc_set_opts2, swr_convert, swr_free, swr_get_out_samples, swr_init, sws_freeContext,
+    sws_getCachedContext, sws_scale,
+};
+use std::ffi::{CStr, CString, c_int};
+use std::ptr;
+
+const AVERROR_EAGAIN: c_int = AVERROR(EAGAIN as c_int);
+const DEFAULT_FRAME_RATE: AVRational = AVRational { num: 25, den: 1 };
+//可变帧长编码器(G.711)每帧 20ms
+const PCM_FRAMES_PER_SEC: c_int = 50;
+const G7221_SAMPLE_RATE: c_int = 16000;
+
+/// 实时转码：按轨道解码后重新编码，未配置或无需转码的轨道直通；开启视频滤镜时视频强制重编码
+pub struct CodecContext {
+    ssrc: u32,
+    layer: TranscodeLayer,
+    //转码后的流描述，muxer 以此建立输出流
+    output: Option<DemuxerContext>,
+    tracks: Vec<Option<Transcoder>>,
+}
 
-pub struct CodecContext {}
 impl CodecContext {
-    pub fn init(codec: Option<CodecLayer>) -> Option<CodecContext> {
-        None
+    pub fn init(
+        ssrc: u32,
+        codec: Option<TranscodeLayer>,
+        filter: &FilterContext,
+    ) -> Option<CodecContext> {
+        let layer = match codec {
+            Some(layer) => layer,
+            None if filter.has_video() => TranscodeLayer {
+                video: None,
+                audio: None,
+            },
+            None => return None,
+        };
+        Some(CodecContext {
+            ssrc,
+            layer,
+            output: None,
+            tracks: Vec::new(),
+        })
+    }
+
+    /// 流信息修复后打开编解码器；返回 false 表示所有轨道均无需转码
+    pub fn open(
+        &mut self,
+        demuxer_context: &DemuxerContext,
+        filter: &mut FilterContext,
+    ) -> GlobalResult<bool> {
+        unsafe {
+            let in_fmt = demuxer_context.avio.fmt_ctx;
+            let out_fmt = avformat_alloc_context();
+            if out_fmt.is_null() {
+                return Err(GlobalError::new_sys_error(
+                    "Failed to alloc transcode format context",
+                    |msg| error!("ssrc: {}; {msg}", self.ssrc),
+                ));
+            }
+            let mut output = DemuxerContext {
+                avio: AvioResource {
+                    fmt_ctx: out_fmt,
+                    io_buf: ptr::null_mut(),
+                    avio_ctx: ptr::null_mut(),
+                },
+                params: demuxer_context.params.clone(),
+            };
+            let mut tracks = Vec::with_capacity(demuxer_context.params.len());
+            for i in 0..demuxer_context.params.len() {
+                let in_st = *(*in_fmt).streams.add(i);
+                let out_st = avformat_new_stream(out_fmt, ptr::null());
+                if out_st.is_null() {
+                    return Err(GlobalError::new_sys_error(
+                        "Failed to create transcode stream",
+                        |msg| error!("ssrc: {}; {msg}", self.ssrc),
+                    ));
+                }
+                (*out_st).time_base = (*in_st).time_base;
+                (*out_st).avg_frame_rate = (*in_st).avg_frame_rate;
+                (*out_st).r_frame_rate = (*in_st).r_frame_rate;
+
+                let transcoder = match (*(*in_st).codecpar).codec_type {
+                    AVMediaType_AVMEDIA_TYPE_VIDEO => {
+                        //仅配置滤镜时沿用输入编码
+                        let layer = self.layer.video.clone().or_else(|| {
+                            filter.has_video().then(|| {
+                                VideoTranscodeLayer::with_codec(same_codec(
+                                    (*(*in_st).codecpar).codec_id,
+                                ))
+                            })
+                        });
+                        match layer {
+                            Some(layer) => VideoTranscoder::open(self.ssrc, in_st, &layer, filter)?
+                                .map(Transcoder::Video),
+                            None => None,
+                        }
+                    }
+                    AVMediaType_AVMEDIA_TYPE_AUDIO => match &self.layer.audio {
+                        Some(layer) => {
+                            AudioTranscoder::open(self.ssrc, in_st, layer)?.map(Transcoder::Audio)
+                        }
+                        None => None,
+                    },
+                    _ => None,
+                };
+                let ret = match &transcoder {
+                    Some(transcoder) => {
+                        //编码后的参数集已写入 extradata，无需再修复
+                        output.params[i] = ParamRepairState {
+                            ready: true,
+                            ..Default::default()
+                        };
+                        avcodec_parameters_from_context((*out_st).codecpar, transcoder.enc_ctx())
+                    }
+                    None => avcodec_parameters_copy((*out_st).codecpar, (*in_st).codecpar),
+                };
+                if ret < 0 {
+                    return Err(GlobalError::new_sys_error(
+                        &format!(
+                            "Failed to set transcode stream parameters: {}",
+                            show_ffmpeg_error_msg(ret)
+                        ),
+                        |msg| error!("ssrc: {}; {msg}", self.ssrc),
+                    ));
+                }
+                (*(*out_st).codecpar).codec_tag = 0;
+                tracks.push(transcoder);
+            }
+            if tracks.iter().all(Option::is_none) {
+                return Ok(false);
+            }
+            self.output = Some(output);
+            self.tracks = tracks;
+            Ok(true)
+        }
+    }
+
+    /// 转码后的流描述
+    pub fn output(&self) -> Option<&DemuxerContext> {
+        self.output.as_ref()
+    }
+
+    /// 返回 None 表示该轨道直通；返回的数据包由调用方释放
+    pub fn transcode(
+        &mut self,
+        pkt: &AVPacket,
+        filter: &mut FilterContext,
+    ) -> Option<Vec<AVPacket>> {
+        let idx = pkt.stream_index;
+        let transcoder = self.tracks.get_mut(idx as usize)?.as_mut()?;
+        let mut out = Vec::new();
+        let res = unsafe {
+            match transcoder {
+                Transcoder::Video(video) => video.transcode(pkt, &mut out, filter),
+                Transcoder::Audio(audio) => audio.transcode(pkt, &mut out),
+            }
+        };
+        if let Err(ret) = res {
+            warn!(
+                "ssrc: {}; transcode stream {} failed: {}",
+                self.ssrc,
+                idx,
+                show_ffmpeg_error_msg(ret)
+            );
+        }
+        for pkt in out.iter_mut() {
+            pkt.stream_index = idx;
+        }
+        Some(out)
+    }
+}
+
+enum Transcoder {
+    Video(VideoTranscoder),
+    Audio(AudioTranscoder),
+}
+
+impl Transcoder {
+    fn enc_ctx(&self) -> *const AVCodecContext {
+        match self {
+            Transcoder::Video(video) => video.enc_ctx,
+            Transcoder::Audio(audio) => audio.enc_ctx,
+        }
+    }
+}
+
+struct VideoTranscoder {
+    dec_ctx: *mut AVCodecContext,
+    enc_ctx: *mut AVCodecContext,
+    sws_ctx: *mut SwsContext,
+    frame: *mut AVFrame,
+    scaled: *mut AVFrame,
+    last_pts: i64,
+    filtered: bool,
+}
+
+impl VideoTranscoder {
+    unsafe fn open(
+        ssrc: u32,
+        in_st: *mut AVStream,
+        layer: &VideoTranscodeLayer,
+        filter: &mut FilterContext,
+    ) -> GlobalResult<Option<Self>> {
+        let par = (*in_st).codecpar;
+        let filtered = filter.has_video();
+        let target = match layer.codec {
+            CodecLayer::H264 => AVCodecID_AV_CODEC_ID_H264,
+            CodecLayer::H265 => AVCodecID_AV_CODEC_ID_HEVC,
+            CodecLayer::Mpeg4 => AVCodecID_AV_CODEC_ID_MPEG4,
+            _ => {
+                warn!(
+                    "ssrc: {ssrc}; unsupported video transcode target: {}, passthrough",
+                    layer.codec.to_string()
+                );
+                return Ok(None);
+            }
+        };
+        if (*par).codec_id == target && !filtered {
+            info!(
+                "ssrc: {ssrc}; video already {}, passthrough",
+                layer.codec.to_string()
+            );
+            return Ok(None);
+        }
+        if (*par).width <= 0 || (*par).height <= 0 {
+            return Err(sys_error(
+                ssrc,
+                "Video resolution unknown, cannot transcode",
+            ));
+        }
+        let mut this = VideoTranscoder {
+            dec_ctx: open_decoder(ssrc, in_st)?,
+            enc_ctx: ptr::null_mut(),
+            sws_ctx: ptr::null_mut(),
+            frame: av_frame_alloc(),
+            scaled: av_frame_alloc(),
+            last_pts: AV_NOPTS_VALUE,
+            filtered,
+        };
+        if this.frame.is_null() || this.scaled.is_null() {
+            return Err(sys_error(ssrc, "Failed to alloc video frame"));
+        }
+        let encoder = avcodec_find_encoder(target);
+        if encoder.is_null() {
+            return Err(sys_error(ssrc, "Video encoder not found"));
+        }
+        this.enc_ctx = avcodec_alloc_context3(encoder);
+        if this.enc_ctx.is_null() {
+            return Err(sys_error(ssrc, "Failed to alloc video encoder context"));
+        }
+        //编码尺寸取滤镜输出，yuv420p 要求宽高为偶数
+        let (width, height) = if filtered {
+            filter.open_video(
+                (*par).width,
+                (*par).height,
+                (*par).format,
+                (*in_st).time_base,
+                (*par).sample_aspect_ratio,
+            )?
+        } else {
+            ((*par).width, (*par).height)
+        };
+        let enc = this.enc_ctx;
+        (*enc).width = (width & !1).max(2);
+        (*enc).height = (height & !1).max(2);
+        (*enc).pix_fmt = AVPixelFormat_AV_PIX_FMT_YUV420P;
+        (*enc).time_base = (*in_st).time_base;
+        (*enc).framerate = if (*in_st).avg_frame_rate.num > 0 {
+            (*in_st).avg_frame_rate
+        } else {
+            DEFAULT_FRAME_RATE
+        };
+        (*enc).bit_rate = layer.bit_rate;
+        (*enc).gop_size = layer.gop;
+        (*enc).max_b_frames = 0;
+        (*enc).flags |= AV_CODEC_FLAG_GLOBAL_HEADER as c_int;
+
+        //低延迟；关键帧携带参数集，便于中途接入的播放端解码
+        let mut opts: *mut AVDictionary = ptr::null_mut();
+        match CStr::from_ptr((*encoder).name).to_bytes() {
+            b"libx264" => {
+                dict_set(&mut opts, "preset", "veryfast");
+                dict_set(&mut opts, "tune", "zerolatency");
+                dict_set(&mut opts, "x264-params", "repeat-headers=1");
+            }
+            b"libx265" => {
+                dict_set(&mut opts, "preset", "veryfast");
+                dict_set(&mut opts, "tune", "zerolatency");
+                dict_set(&mut opts, "x265-params", "repeat-headers=1:log-level=error");
+            }
+            _ => {}
+        }
+        let ret = avcodec_open2(enc, encoder, &mut opts);
+        av_dict_free(&mut opts);
+        if ret < 0 {
+            return Err(sys_error(
+                ssrc,
+                &format!(
+                    "Failed to open video encoder: {}",
+                    show_ffmpeg_error_msg(ret)
+                ),
+            ));
+        }
+        info!(
+            "ssrc: {ssrc}; video transcode to {} {}x{} bitrate={} gop={}",
+            layer.codec.to_string(),
+            (*enc).width,
+            (*enc).height,
+            layer.bit_rate,
+            layer.gop
+        );
+        Ok(Some(this))
+    }
+
+    unsafe fn transcode(
+        &mut self,
+        pkt: &AVPacket,
+        out: &mut Vec<AVPacket>,
+        filter: &mut FilterContext,
+    ) -> Result<(), c_int> {
+        let ret = avcodec_send_packet(self.dec_ctx, pkt);
+        if ret < 0 && ret != AVERROR_EAGAIN {
+            return Err(ret);
+        }
+        loop {
+            let ret = avcodec_receive_frame(self.dec_ctx, self.frame);
+            if ret == AVERROR_EAGAIN || ret == AVERROR_EOF {
+                return Ok(());
+            }
+            if ret < 0 {
+                return Err(ret);
+            }
+            let pts = (*self.frame).best_effort_timestamp;
+            //编码器要求 pts 严格递增
+            if pts == AV_NOPTS_VALUE || (self.last_pts != AV_NOPTS_VALUE && pts <= self.last_pts) {
+                av_frame_unref(self.frame);
+                continue;
+            }
+            self.last_pts = pts;
+            let frame = self.frame;
+            (*frame).pts = pts;
+            let res = if self.filtered {
+                filter.filter_video(frame, |filtered| self.encode_frame(filtered, out))
+            } else {
+                self.encode_frame(frame, out)
+            };
+            av_frame_unref(frame);
+            res?;
+        }
+    }
+
+    unsafe fn encode_frame(
+        &mut self,
+        frame: *mut AVFrame,
+        out: &mut Vec<AVPacket>,
+    ) -> Result<(), c_int> {
+        let pts = (*frame).pts;
+        let frame = self.scale(frame)?;
+        (*frame).pts = pts;
+        (*frame).pict_type = AVPictureType_AV_PICTURE_TYPE_NONE;
+        encode(self.enc_ctx, frame, out)
+    }
+
+    /// 像素格式或分辨率与编码器不一致时转换
+    unsafe fn scale(&mut self, frame: *mut AVFrame) -> Result<*mut AVFrame, c_int> {
+        let enc = self.enc_ctx;
+        if (*frame).format == (*enc).pix_fmt
+            && (*frame).width == (*enc).width
+            && (*frame).height == (*enc).height
+        {
+            return Ok(frame);
+        }
+        self.sws_ctx = sws_getCachedContext(
+            self.sws_ctx,
+            (*frame).width,
+            (*frame).height,
+            (*frame).format,
+            (*enc).width,
+            (*enc).height,
+            (*enc).pix_fmt,
+            SWS_BILINEAR as c_int,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null(),
+        );
+        if self.sws_ctx.is_null() {
+            return Err(AVERROR(EINVAL as c_int));
+        }
+        let scaled = self.scaled;
+        if (*scaled).data[0].is_null() {
+            (*scaled).format = (*enc).pix_fmt;
+            (*scaled).width = (*enc).width;
+            (*scaled).height = (*enc).height;
+            check(av_frame_get_buffer(scaled, 0))?;
+        }
+        check(av_frame_make_writable(scaled))?;
+        sws_scale(
+            self.sws_ctx,
+            (*frame).data.as_ptr() as _,
+            (*frame).linesize.as_ptr(),
+            0,
+            (*frame).height,
+            (*scaled).data.as_ptr() as _,
+            (*scaled).linesize.as_ptr(),
+        );
+        Ok(scaled)
+    }
+}
+
+impl Drop for VideoTranscoder {
+    fn drop(&mut self) {
+        unsafe {
+            avcodec_free_context(&mut self.dec_ctx);
+            avcodec_free_context(&mut self.enc_ctx);
+            if !self.sws_ctx.is_null() {
+                sws_freeContext(self.sws_ctx);
+                self.sws_ctx = ptr::null_mut();
+            }
+            av_frame_free(&mut self.frame);
+            av_frame_free(&mut self.scaled);
+        }
+    }
+}
+
+struct AudioTranscoder {
+    dec_ctx: *mut AVCodecContext,
+    enc_ctx: *mut AVCodecContext,
+    swr_ctx: *mut SwrContext,
+    fifo: *mut AVAudioFifo,
+    frame: *mut AVFrame,
+    frame_size: c_int,
+    //输入流时间基，输出包回到该时间基以保持与视频同步
+    time_base: AVRational,
+    //编码器时间基(1/sample_rate)下的下一帧 pts
+    next_pts: i64,
+}
+
+impl AudioTranscoder {
+    unsafe fn open(
+        ssrc: u32,
+        in_st: *mut AVStream,
+        layer: &AudioTranscodeLayer,
+    ) -> GlobalResult<Option<Self>> {
+        let par = (*in_st).codecpar;
+        let (target, sample_fmt): (AVCodecID, AVSampleFormat) = match layer.codec {
+            CodecLayer::Aac => (AVCodecID_AV_CODEC_ID_AAC, AVSampleFormat_AV_SAMPLE_FMT_FLTP),
+            CodecLayer::G711a => (
+                AVCodecID_AV_CODEC_ID_PCM_ALAW,
+                AVSampleFormat_AV_SAMPLE_FMT_S16,
+            ),
+            CodecLayer::G711u => (
+                AVCodecID_AV_CODEC_ID_PCM_MULAW,
+                AVSampleFormat_AV_SAMPLE_FMT_S16,
+            ),
+            _ => {
+                warn!(
+                    "ssrc: {ssrc}; unsupported audio transcode target: {}, passthrough",
+                    layer.codec.to_string()
+                );
+                return Ok(None);
+            }
+        };
+        if (*par).codec_id == target {
+            info!(
+                "ssrc: {ssrc}; audio already {}, passthrough",
+                layer.codec.to_string()
+            );
+            return Ok(None);
+        }
+        let mut this = AudioTranscoder {
+            dec_ctx: open_decoder(ssrc, in_st)?,
+            enc_ctx: ptr::null_mut(),
+            swr_ctx: ptr::null_mut(),
+            fifo: ptr::null_mut(),
+            frame: av_frame_alloc(),
+            frame_size: 0,
+            time_base: (*in_st).time_base,
+            next_pts: AV_NOPTS_VALUE,
+        };
+        if this.frame.is_null() {
+            return Err(sys_error(ssrc, "Failed to alloc audio frame"));
+        }
+        let dec = this.dec_ctx;
+        let encoder = avcodec_find_encoder(target);
+        if encoder.is_null() {
+            return Err(sys_error(ssrc, "Audio encoder not found"));
+        }
+        this.enc_ctx = avcodec_alloc_context3(encoder);
+        if this.enc_ctx.is_null() {
+            return Err(sys_error(ssrc, "Failed to alloc audio encoder context"));
+        }
+        let enc = this.enc_ctx;
+        (*enc).sample_fmt = sample_fmt;
+        (*enc).sample_rate = layer.sample_rate.unwrap_or((*dec).sample_rate);
+        (*enc).time_base = AVRational {
+            num: 1,
+            den: (*enc).sample_rate,
+        };
+        (*enc).bit_rate = layer.bit_rate;
+        (*enc).flags |= AV_CODEC_FLAG_GLOBAL_HEADER as c_int;
+        check(av_channel_layout_copy(
+            &mut (*enc).ch_layout,
+            &(*dec).ch_layout,
+        ))
+        .map_err(|_| sys_error(ssrc, "Failed to copy audio channel layout"))?;
+        let ret = avcodec_open2(enc, encoder, ptr::null_mut());
+        if ret < 0 {
+            return Err(sys_error(
+                ssrc,
+                &format!(
+                    "Failed to open audio encoder: {}",
+                    show_ffmpeg_error_msg(ret)
+                ),
+            ));
+        }
+        this.frame_size = if (*enc).frame_size > 0 {
+            (*enc).frame_size
+        } else {
+            ((*enc).sample_rate / PCM_FRAMES_PER_SEC).max(1)
+        };
+
+        let ret = swr_alloc_set_opts2(
+            &mut this.swr_ctx,
+            &(*enc).ch_layout,
+            (*enc).sample_fmt,
+            (*enc).sample_rate,
+            &(*dec).ch_layout,
+            (*dec).sample_fmt,
+            (*dec).sample_rate,
+            0,
+            ptr::null_mut(),
+        );
+        if ret < 0 || swr_init(this.swr_ctx) < 0 {
+            return Err(sys_error(ssrc, "Failed to init audio resampler"));
+        }
+        this.fifo = av_audio_fifo_alloc(
+            (*enc).sample_fmt,
+            (*enc).ch_layout.nb_channels,
+            this.frame_size,
+        );
+        if this.fifo.is_null() {
+            return Err(sys_error(ssrc, "Failed to alloc audio fifo"));
+        }
+        info!(
+            "ssrc: {ssrc}; audio transcode to {} {}Hz bitrate={}",
+            layer.codec.to_string(),
+            (*enc).sample_rate,
+            layer.bit_rate
+        );
+        Ok(Some(this))
+    }
+
+    unsafe fn transcode(&mut self, pkt: &AVPacket, out: &mut Vec<AVPacket>) -> Result<(), c_int> {
+        let ret = avcodec_send_packet(self.dec_ctx, pkt);
+        if ret < 0 && ret != AVERROR_EAGAIN {
+            return Err(ret);
+        }
+        loop {
+            let ret = avcodec_receive_frame(self.dec_ctx, self.frame);
+            if ret == AVERROR_EAGAIN || ret == AVERROR_EOF {
+                return Ok(());
+            }
+            if ret < 0 {
+                return Err(ret);
+            }
+            if self.next_pts == AV_NOPTS_VALUE {
+                let pts = (*self.frame).best_effort_timestamp;
+                self.next_pts = if pts == AV_NOPTS_VALUE {
+                    0
+                } else {
+                    av_rescale_q(pts, self.time_base, (*self.enc_ctx).time_base)
+                };
+            }
+            let res = self.resample().and_then(|_| self.drain_fifo(out));
+            av_frame_unref(self.frame);
+            res?;
+        }
+    }
+
+    /// 重采样到编码器格式后写入 fifo
+    unsafe fn resample(&mut self) -> Result<(), c_int> {
+        let mut converted = alloc_audio_frame(
+            self.enc_ctx,
+            swr_get_out_samples(self.swr_ctx, (*self.frame).nb_samples),
+        )?;
+        let mut res = check(swr_convert(
+            self.swr_ctx,
+            (*converted).data.as_mut_ptr(),
+            (*converted).nb_samples,
+            (*self.frame).extended_data as _,
+            (*self.frame).nb_samples,
+        ));
+        if let Ok(samples) = res
+            && samples > 0
+        {
+            res = check(av_audio_fifo_write(
+                self.fifo,
+                (*converted).data.as_mut_ptr() as _,
+                samples,
+            ));
+        }
+        av_frame_free(&mut converted);
+        res.map(|_| ())
+    }
+
+    /// 按编码器帧长取出样本编码
+    unsafe fn drain_fifo(&mut self, out: &mut Vec<AVPacket>) -> Result<(), c_int> {
+        while av_audio_fifo_size(self.fifo) >= self.frame_size {
+            let mut frame = alloc_audio_frame(self.enc_ctx, self.frame_size)?;
+            let res = check(av_audio_fifo_read(
+                self.fifo,
+                (*frame).data.as_mut_ptr() as _,
+                self.frame_size,
+            ))
+            .and_then(|_| {
+                (*frame).pts = self.next_pts;
+                self.next_pts += i64::from(self.frame_size);
+                let start = out.len();
+                encode(self.enc_ctx, frame, out)?;
+                for pkt in out[start..].iter_mut() {
+                    av_packet_rescale_ts(pkt, (*self.enc_ctx).time_base, self.time_base);
+                }
+                Ok(())
+            });
+            av_frame_free(&mut frame);
+            res?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AudioTranscoder {
+    fn drop(&mut self) {
+        unsafe {
+            avcodec_free_context(&mut self.dec_ctx);
+            avcodec_free_context(&mut self.enc_ctx);
+            swr_free(&mut self.swr_ctx);
+            if !self.fifo.is_null() {
+                av_audio_fifo_free(self.fifo);
+                self.fifo = ptr::null_mut();
+            }
+            av_frame_free(&mut self.frame);
+        }
+    }
+}
+
+//滤镜需重编码但未指定目标编码时沿用输入编码，不支持编码的格式转为 H264
+fn same_codec(codec_id: AVCodecID) -> CodecLayer {
+    match codec_id {
+        AVCodecID_AV_CODEC_ID_HEVC => CodecLayer::H265,
+        AVCodecID_AV_CODEC_ID_MPEG4 => CodecLayer::Mpeg4,
+        _ => CodecLayer::H264,
+    }
+}
+
+pub(super) unsafe fn open_decoder(
+    ssrc: u32,
+    in_st: *mut AVStream,
+) -> GlobalResult<*mut AVCodecContext> {
+    let par = (*in_st).codecpar;
+    let decoder = avcodec_find_decoder((*par).codec_id);
+    if decoder.is_null() {
+        //链接的 FFmpeg 未启用该解码器(如 G.722.1 需 siren)
+        let name = CStr::from_ptr(avcodec_get_name((*par).codec_id)).to_string_lossy();
+        return Err(sys_error(ssrc, &format!("Decoder not found: {name}")));
+    }
+    let mut dec = avcodec_alloc_context3(decoder);
+    if dec.is_null() {
+        return Err(sys_error(ssrc, "Failed to alloc decoder context"));
+    }
+    let mut ret = avcodec_parameters_to_context(dec, par);
+    if ret >= 0 {
+        (*dec).pkt_timebase = (*in_st).time_base;
+        //G.711 等流可能未携带声道信息，按单声道处理
+        if (*par).codec_type == AVMediaType_AVMEDIA_TYPE_AUDIO && (*dec).ch_layout.nb_channels == 0
+        {
+            av_channel_layout_default(&mut (*dec).ch_layout, 1);
+        }
+        //G.722.1 固定 16kHz，PS 流中通常不携带采样率
+        if (*par).codec_id == AVCodecID_AV_CODEC_ID_SIREN && (*dec).sample_rate <= 0 {
+            (*dec).sample_rate = G7221_SAMPLE_RATE;
+        }
+        ret = avcodec_open2(dec, decoder, ptr::null_mut());
+    }
+    if ret < 0 {
+        avcodec_free_context(&mut dec);
+        return Err(sys_error(
+            ssrc,
+            &format!("Failed to open decoder: {}", show_ffmpeg_error_msg(ret)),
+        ));
+    }
+    Ok(dec)
+}
+
+/// 送入一帧并取出全部已编码的数据包
+pub(super) unsafe fn encode(
+    enc: *mut AVCodecContext,
+    frame: *const AVFrame,
+    out: &mut Vec<AVPacket>,
+) -> Result<(), c_int> {
+    let ret = avcodec_send_frame(enc, frame);
+    if ret < 0 && ret != AVERROR_EAGAIN {
+        return Err(ret);
+    }
+    loop {
+        let mut pkt = std::mem::zeroed::<AVPacket>();
+        let ret = avcodec_receive_packet(enc, &mut pkt);
+        if ret == AVERROR_EAGAIN || ret == AVERROR_EOF {
+            return Ok(());
+        }
+        if ret < 0 {
+            av_packet_unref(&mut pkt);
+            return Err(ret);
+        }
+        out.push(pkt);
+    }
+}
+
+unsafe fn alloc_audio_frame(
+    enc: *const AVCodecContext,
+    nb_samples: c_int,
+) -> Result<*mut AVFrame, c_int> {
+    let mut frame = av_frame_alloc();
+    if frame.is_null() {
+        return Err(AVERROR(ENOMEM as c_int));
+    }
+    (*frame).format = (*enc).sample_fmt;
+    (*frame).sample_rate = (*enc).sample_rate;
+    (*frame).nb_samples = nb_samples.max(1);
+    let mut ret = av_channel_layout_copy(&mut (*frame).ch_layout, &(*enc).ch_layout);
+    if ret >= 0 {
+        ret = av_frame_get_buffer(frame, 0);
+    }
+    if ret < 0 {
+        av_frame_free(&mut frame);
+        return Err(ret);
+    }
+    Ok(frame)
+}
+
+unsafe fn dict_set(opts: *mut *mut AVDictionary, key: &str, value: &str) {
+    let key = CString::new(key).unwrap();
+    let value = CString::new(value).unwrap();
+    av_dict_set(opts, key.as_ptr(), value.as_ptr(), 0);
+}
+
+fn check(ret: c_int) -> Result<c_int, c_int> {
+    if ret < 0 { Err(ret) } else { Ok(ret) }
+}
+
+fn sys_error(ssrc: u32, msg: &str) -> GlobalError {
+    GlobalError::new_sys_error(msg, |msg| error!("ssrc: {ssrc}; {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioTranscoder, G7221_SAMPLE_RATE};
+    use crate::state::layer::codec_layer::{AudioTranscodeLayer, CodecLayer};
+    use rsmpeg::ffi::{
+        AVCodecID_AV_CODEC_ID_SIREN, AVMediaType_AVMEDIA_TYPE_AUDIO, AVRational,
+        av_channel_layout_default, avcodec_find_decoder, avformat_alloc_context,
+        avformat_free_context, avformat_new_stream,
+    };
+    use std::ptr;
+
+    #[test]
+    fn g7221_input_transcode_to_aac() {
+        unsafe {
+            let fmt = avformat_alloc_context();
+            let st = avformat_new_stream(fmt, ptr::null());
+            let par = (*st).codecpar;
+            (*par).codec_type = AVMediaType_AVMEDIA_TYPE_AUDIO;
+            (*par).codec_id = AVCodecID_AV_CODEC_ID_SIREN;
+            (*par).bit_rate = 32000;
+            av_channel_layout_default(&mut (*par).ch_layout, 1);
+            (*st).time_base = AVRational {
+                num: 1,
+                den: G7221_SAMPLE_RATE,
+            };
+            let layer = AudioTranscodeLayer {
+                codec: CodecLayer::Aac,
+                bit_rate: 64000,
+                sample_rate: None,
+            };
+            let res = AudioTranscoder::open(1, st, &layer);
+            if avcodec_find_decoder(AVCodecID_AV_CODEC_ID_SIREN).is_null() {
+                //未启用 siren 解码器时应报错而非静默直通
+                assert!(res.is_err());
+            } else {
+                let transcoder = res.unwrap().expect("siren input should transcode");
+                assert_eq!((*transcoder.dec_ctx).sample_rate, G7221_SAMPLE_RATE);
+                assert_eq!((*transcoder.enc_ctx).sample_rate, G7221_SAMPLE_RATE);
+            }
+            avformat_free_context(fmt);
+        }
     }
 }
@@ -1 +0,0 @@
-pub struct CodecEvent {}
@@ -1 +1,6 @@
-pub struct FilterEvent {}
+use shared::info::filter::Osd;
+
+pub enum FilterEvent {
+    //更新水印：None 移除
+    Osd(Option<Osd>),
+}
@@ -2,18 +2,36 @@ use crate::general::mp::MediaParam;
 use crate::media::context::MediaContext;
 use crate::media::context::format::FmtMuxer;
 use crate::media::context::format::flv::FlvSupperCtx;
+use crate::media::context::format::hls_ts::HlsTsSegment;
+use crate::media::context::format::hlsfmp4::HlsMp4Segment;
+use crate::media::context::format::rtp::RtpEncTracks;
 use crate::media::context::utils::extradata;
 use base::bytes::Bytes;
+use base::exception::GlobalResult;
 use base::log::error;
 use base::tokio::sync::oneshot;
 use log::info;
+use shared::info::filter::ImageFormat;
+use std::sync::Arc;
 
 pub enum InnerEvent {
     FlvHeader(oneshot::Sender<Bytes>),
     Mp4Header(oneshot::Sender<Bytes>),
+    TsHeader(oneshot::Sender<Bytes>),
     Fmp4Header(oneshot::Sender<Bytes>),
     DashMp4Header(oneshot::Sender<Bytes>),
+    HlsMp4Header(oneshot::Sender<Bytes>),
     MediaParam(oneshot::Sender<MediaParam>),
+    //hls-ts 滚动窗口内的切片
+    HlsTsSegments(oneshot::Sender<Vec<Arc<HlsTsSegment>>>),
+    //ll-hls 滚动窗口内的segment/part
+    HlsMp4Playlist(oneshot::Sender<Vec<HlsMp4Segment>>),
+    //webrtc 协商所需的轨道信息
+    RtpEncTracks(oneshot::Sender<RtpEncTracks>),
+    //rtp 帧模式丢弃的音频编码
+    RtpFrameSkippedAudio(oneshot::Sender<Option<String>>),
+    //抓拍：解码最近关键帧
+    Snapshot(ImageFormat, oneshot::Sender<GlobalResult<Bytes>>),
     //...
 }
 impl InnerEvent {
@@ -43,6 +61,16 @@ impl InnerEvent {
                     }
                 }
             },
+            InnerEvent::TsHeader(sender) => match &media_context.muxer_context.ts {
+                None => {
+                    error!("no ts context");
+                }
+                Some(context) => {
+                    if let Err(_) = sender.send(context.get_header()) {
+                        error!("ts_header send to the receiver dropped");
+                    }
+                }
+            },
             InnerEvent::Fmp4Header(sender) => match &media_context.muxer_context.fmp4 {
                 None => {
                     error!("no fmp4 context");
@@ -64,8 +92,68 @@ impl InnerEvent {
                     }
                 }
             },
+            InnerEvent::HlsMp4Header(sender) => match &media_context.muxer_context.hls_mp4 {
+                None => {
+                    error!("no hls mp4 context");
+                }
+                Some(context) => {
+                    if let Err(_) = sender.send(context.get_header()) {
+                        error!("hls_mp4_header send to the receiver dropped");
+                    }
+                }
+            },
+            InnerEvent::HlsMp4Playlist(sender) => match &media_context.muxer_context.hls_mp4 {
+                None => {
+                    error!("no hls mp4 context");
+                }
+                Some(context) => {
+                    if let Err(_) = sender.send(context.get_playlist()) {
+                        error!("hls mp4 playlist send to the receiver dropped");
+                    }
+                }
+            },
+            InnerEvent::HlsTsSegments(sender) => match &media_context.muxer_context.hls_ts {
+                None => {
+                    error!("no hls ts context");
+                }
+                Some(context) => {
+                    if let Err(_) = sender.send(context.get_segments()) {
+                        error!("hls ts segments send to the receiver dropped");
+                    }
+                }
+            },
+            InnerEvent::RtpEncTracks(sender) => match &media_context.muxer_context.rtp_enc {
+                None => {
+                    error!("no rtp enc context");
+                }
+                Some(context) => {
+                    if let Err(_) = sender.send(context.tracks.clone()) {
+                        error!("rtp enc tracks send to the receiver dropped");
+                    }
+                }
+            },
+            InnerEvent::RtpFrameSkippedAudio(sender) => {
+                match &media_context.muxer_context.rtp_frame {
+                    None => {
+                        error!("no rtp frame context");
+                    }
+                    Some(context) => {
+                        if let Err(_) = sender.send(context.skipped_audio.clone()) {
+                            error!("rtp frame skipped audio send to the receiver dropped");
+                        }
+                    }
+                }
+            }
+            InnerEvent::Snapshot(format, sender) => {
+                let image = media_context
+                    .capture_context
+                    .snapshot(&media_context.demuxer_context, format);
+                if let Err(_) = sender.send(image) {
+                    error!("snapshot send to the receiver dropped");
+                }
+            }
             InnerEvent::MediaParam(sender) => {
-                let param = extradata::parse_media_param(&media_context.demuxer_context);
+                let param = extradata::parse_media_param(media_context.output_context());
                 if let Err(_) = sender.send(param) {
                     error!("media params send to the receiver dropped");
                 }
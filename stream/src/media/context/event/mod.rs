@@ -1,11 +1,9 @@
-pub mod codec;
 pub mod filter;
 pub mod inner;
 pub mod muxer;
 pub mod output;
 
 pub enum ContextEvent {
-    Codec(codec::CodecEvent),
     Muxer(muxer::MuxerEvent),
     Filter(filter::FilterEvent),
     Inner(inner::InnerEvent),
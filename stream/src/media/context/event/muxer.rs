@@ -4,12 +4,16 @@ use crate::media::context::format::demuxer::DemuxerContext;
 use crate::media::context::format::flv::{FlvContext, FlvSupperCtx};
 use crate::media::context::format::fmp4::CmafFmp4Context;
 use crate::media::context::format::h265flv::H265FlvContext;
+use crate::media::context::format::hls_ts::HlsTsContext;
 use crate::media::context::format::hlsfmp4::HlsFmp4Context;
-use crate::media::context::format::muxer::{MuxerContext, MuxerEnum};
+use crate::media::context::format::mp4::Mp4Context;
+use crate::media::context::format::muxer::{MuxerContext, MuxerEnum, input_has_hevc_video};
+use crate::media::context::format::rtp::{RtpEncContext, RtpFrameContext, RtpPsContext};
+use crate::media::context::format::ts::TsContext;
 use crate::state::layer::muxer_layer::{
-    CMafLayer, FlvLayer, HlsTsLayer, Mp4Layer, RtpEncLayer, RtpFrameLayer, RtpPsLayer, TsLayer,
+    CMafLayer, FlvLayer, HlsTsLayer, Mp4Layer, MuxerLayer, RtpEncLayer, RtpFrameLayer, RtpPsLayer,
+    TsLayer,
 };
-use rsmpeg::ffi::AVCodecID_AV_CODEC_ID_HEVC;
 
 pub enum MuxerEvent {
     Open(MuxerKind),
@@ -21,7 +25,7 @@ impl MuxerEvent {
             MuxerEvent::Open(open) => match open {
                 MuxerKind::Flv(flv) => unsafe {
                     let in_fmt_ctx = demuxer_context.avio.fmt_ctx;
-                    if (*in_fmt_ctx).video_codec_id == AVCodecID_AV_CODEC_ID_HEVC {
+                    if input_has_hevc_video(in_fmt_ctx) {
                         let _ = H265FlvContext::init_context(demuxer_context, flv.tx).map(
                             |flv_context| {
                                 muxer_context.flv = Some(FlvSupperCtx::H265FlvCtx(flv_context));
@@ -35,10 +39,14 @@ impl MuxerEvent {
                     }
                 },
                 MuxerKind::Ts(ts) => {
-                    unimplemented!()
+                    let _ = TsContext::init_context(demuxer_context, ts.tx).map(|ctx| {
+                        muxer_context.ts = Some(ctx);
+                    });
                 }
                 MuxerKind::Mp4(mp4) => {
-                    unimplemented!()
+                    let _ = Mp4Context::init_context(demuxer_context, mp4.tx).map(|ctx| {
+                        muxer_context.mp4 = Some(ctx);
+                    });
                 }
                 MuxerKind::FMp4(fmp4) => {
                     let _ = CmafFmp4Context::init_context(demuxer_context, fmp4.tx).map(|ctx| {
@@ -46,16 +54,25 @@ impl MuxerEvent {
                     });
                 }
                 MuxerKind::HlsTs(hls_ts) => {
-                    unimplemented!()
+                    let _ = HlsTsContext::init_context(demuxer_context, hls_ts.tx).map(|ctx| {
+                        muxer_context.hls_ts = Some(ctx);
+                    });
                 }
                 MuxerKind::RtpFrame(rtp_frame) => {
-                    unimplemented!()
+                    let _ =
+                        RtpFrameContext::init_context(demuxer_context, rtp_frame.tx).map(|ctx| {
+                            muxer_context.rtp_frame = Some(ctx);
+                        });
                 }
                 MuxerKind::RtpPs(rtp_ps) => {
-                    unimplemented!()
+                    let _ = RtpPsContext::init_context(demuxer_context, rtp_ps.tx).map(|ctx| {
+                        muxer_context.rtp_ps = Some(ctx);
+                    });
                 }
                 MuxerKind::RtpEnc(rtp_enc) => {
-                    unimplemented!()
+                    let _ = RtpEncContext::init_context(demuxer_context, rtp_enc.tx).map(|ctx| {
+                        muxer_context.rtp_enc = Some(ctx);
+                    });
                 }
                 MuxerKind::DashMp4(dash_mp4) => {
                     let _ =
@@ -78,7 +95,12 @@ impl MuxerEvent {
                         muxer_context.mp4 = None;
                     }
                 }
-                MuxerEnum::Ts => muxer_context.ts = None,
+                MuxerEnum::Ts => {
+                    if let Some(ts_ctx) = &mut muxer_context.ts {
+                        ts_ctx.flush();
+                        muxer_context.ts = None;
+                    }
+                }
                 MuxerEnum::FMp4 => muxer_context.fmp4 = None,
                 MuxerEnum::HlsTs => muxer_context.hls_ts = None,
                 MuxerEnum::RtpFrame => muxer_context.rtp_frame = None,
@@ -103,3 +125,20 @@ pub enum MuxerKind {
     RtpPs(RtpPsLayer),
     RtpEnc(RtpEncLayer),
 }
+impl MuxerKind {
+    //从已登记的muxer layer中取出对应类型，用于运行时追加输出
+    pub fn from_layer(layer: &MuxerLayer, muxer_enum: MuxerEnum) -> Option<MuxerKind> {
+        match muxer_enum {
+            MuxerEnum::Flv => layer.flv.clone().map(MuxerKind::Flv),
+            MuxerEnum::Mp4 => layer.mp4.clone().map(MuxerKind::Mp4),
+            MuxerEnum::Ts => layer.ts.clone().map(MuxerKind::Ts),
+            MuxerEnum::FMp4 => layer.fmp4.clone().map(MuxerKind::FMp4),
+            MuxerEnum::HlsMp4 => layer.hls_mp4.clone().map(MuxerKind::HlsMp4),
+            MuxerEnum::DashMp4 => layer.dash_mp4.clone().map(MuxerKind::DashMp4),
+            MuxerEnum::HlsTs => layer.hls_ts.clone().map(MuxerKind::HlsTs),
+            MuxerEnum::RtpFrame => layer.rtp_frame.clone().map(MuxerKind::RtpFrame),
+            MuxerEnum::RtpPs => layer.rtp_ps.clone().map(MuxerKind::RtpPs),
+            MuxerEnum::RtpEnc => layer.rtp_enc.clone().map(MuxerKind::RtpEnc),
+        }
+    }
+}
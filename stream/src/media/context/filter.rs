@@ -1,9 +1,280 @@
-use crate::state::layer::filter_layer::FilterLayer;
+use crate::media::context::event::filter::FilterEvent;
+use crate::media::show_ffmpeg_error_msg;
+use crate::state::layer::filter_layer::{FilterLayer, VideoFilterLayer};
+use base::exception::{GlobalError, GlobalResult};
+use log::{error, info, warn};
+use rsmpeg::ffi::{
+    AV_BUFFERSRC_FLAG_KEEP_REF, AV_OPT_SEARCH_CHILDREN, AVERROR, AVERROR_EOF, AVFilterContext,
+    AVFilterGraph, AVFrame, AVRational, EAGAIN, EINVAL, av_buffersink_get_frame,
+    av_buffersink_get_h, av_buffersink_get_w, av_buffersrc_add_frame_flags, av_frame_alloc,
+    av_frame_free, av_frame_unref, av_opt_set, avfilter_get_by_name, avfilter_graph_alloc,
+    avfilter_graph_alloc_filter, avfilter_graph_config, avfilter_graph_create_filter,
+    avfilter_graph_free, avfilter_init_str, avfilter_link,
+};
+use std::ffi::{CString, c_int};
+use std::ptr;
 
-pub struct FilterContext {}
+/// 视频滤镜：基于 libavfilter，作用于解码后的帧，由转码阶段负责解码与重编码
+pub struct FilterContext {
+    ssrc: u32,
+    video: Option<VideoFilterLayer>,
+    //流时间基，滤镜图重建时沿用
+    time_base: AVRational,
+    graph: Option<VideoFilterGraph>,
+    //参数变更，下一帧重建滤镜图
+    dirty: bool,
+    frame: *mut AVFrame,
+}
 
 impl FilterContext {
-    pub fn init(filter: FilterLayer) -> FilterContext {
-        FilterContext {}
+    pub fn init(ssrc: u32, filter: FilterLayer) -> FilterContext {
+        FilterContext {
+            ssrc,
+            video: filter.video,
+            time_base: AVRational { num: 1, den: 90000 },
+            graph: None,
+            dirty: false,
+            frame: ptr::null_mut(),
+        }
+    }
+
+    /// 是否需要对视频解码重编码
+    pub fn has_video(&self) -> bool {
+        self.video.is_some()
+    }
+
+    /// 以流参数建立滤镜图，返回输出宽高供编码器使用
+    pub(crate) unsafe fn open_video(
+        &mut self,
+        width: c_int,
+        height: c_int,
+        pix_fmt: c_int,
+        time_base: AVRational,
+        sar: AVRational,
+    ) -> GlobalResult<(c_int, c_int)> {
+        let Some(layer) = &self.video else {
+            return Ok((width, height));
+        };
+        if self.frame.is_null() {
+            self.frame = av_frame_alloc();
+            if self.frame.is_null() {
+                return Err(sys_error(self.ssrc, "Failed to alloc filter frame"));
+            }
+        }
+        self.time_base = time_base;
+        let graph =
+            VideoFilterGraph::build(self.ssrc, layer, (width, height, pix_fmt), time_base, sar)?;
+        let size = (
+            av_buffersink_get_w(graph.sink),
+            av_buffersink_get_h(graph.sink),
+        );
+        info!(
+            "ssrc: {}; video filter opened: {}x{} -> {}x{}",
+            self.ssrc, width, height, size.0, size.1
+        );
+        self.graph = Some(graph);
+        self.dirty = false;
+        Ok(size)
+    }
+
+    /// 送入解码帧，逐个回调滤镜输出帧；帧参数变化时重建滤镜图
+    pub(crate) unsafe fn filter_video(
+        &mut self,
+        frame: *mut AVFrame,
+        mut f: impl FnMut(*mut AVFrame) -> Result<(), c_int>,
+    ) -> Result<(), c_int> {
+        let Some(layer) = &self.video else {
+            return f(frame);
+        };
+        let input = ((*frame).width, (*frame).height, (*frame).format);
+        if self.dirty || self.graph.as_ref().is_none_or(|graph| graph.input != input) {
+            self.graph = None;
+            self.dirty = false;
+            let graph = VideoFilterGraph::build(
+                self.ssrc,
+                layer,
+                input,
+                self.time_base,
+                (*frame).sample_aspect_ratio,
+            )
+            .map_err(|_| AVERROR(EINVAL as c_int))?;
+            self.graph = Some(graph);
+        }
+        let graph = self.graph.as_ref().unwrap();
+        let ret =
+            av_buffersrc_add_frame_flags(graph.src, frame, AV_BUFFERSRC_FLAG_KEEP_REF as c_int);
+        if ret < 0 {
+            return Err(ret);
+        }
+        loop {
+            let ret = av_buffersink_get_frame(graph.sink, self.frame);
+            if ret == AVERROR(EAGAIN as c_int) || ret == AVERROR_EOF {
+                return Ok(());
+            }
+            if ret < 0 {
+                return Err(ret);
+            }
+            let res = f(self.frame);
+            av_frame_unref(self.frame);
+            res?;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: FilterEvent) {
+        match event {
+            FilterEvent::Osd(osd) => match &mut self.video {
+                Some(layer) => {
+                    layer.set_osd(osd.as_ref());
+                    self.dirty = true;
+                    info!("ssrc: {}; video osd updated", self.ssrc);
+                }
+                //未开启视频滤镜时流为直通，无法中途叠加水印
+                None => warn!(
+                    "ssrc: {}; video filter not enabled, ignore osd update",
+                    self.ssrc
+                ),
+            },
+        }
+    }
+}
+
+impl Drop for FilterContext {
+    fn drop(&mut self) {
+        self.graph = None;
+        unsafe {
+            av_frame_free(&mut self.frame);
+        }
+    }
+}
+
+struct VideoFilterGraph {
+    graph: *mut AVFilterGraph,
+    src: *mut AVFilterContext,
+    sink: *mut AVFilterContext,
+    //(width, height, pix_fmt)
+    input: (c_int, c_int, c_int),
+}
+
+impl VideoFilterGraph {
+    /// buffer -> 各滤镜步骤 -> buffersink
+    unsafe fn build(
+        ssrc: u32,
+        layer: &VideoFilterLayer,
+        input: (c_int, c_int, c_int),
+        time_base: AVRational,
+        sar: AVRational,
+    ) -> GlobalResult<Self> {
+        let graph = avfilter_graph_alloc();
+        if graph.is_null() {
+            return Err(sys_error(ssrc, "Failed to alloc filter graph"));
+        }
+        //先托管，构建失败时由 Drop 释放
+        let mut this = VideoFilterGraph {
+            graph,
+            src: ptr::null_mut(),
+            sink: ptr::null_mut(),
+            input,
+        };
+        let sar = if sar.num > 0 && sar.den > 0 {
+            sar
+        } else {
+            AVRational { num: 1, den: 1 }
+        };
+        let args = CString::new(format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            input.0, input.1, input.2, time_base.num, time_base.den, sar.num, sar.den
+        ))
+        .unwrap();
+        let ret = avfilter_graph_create_filter(
+            &mut this.src,
+            avfilter_get_by_name(c"buffer".as_ptr()),
+            c"in".as_ptr(),
+            args.as_ptr(),
+            ptr::null_mut(),
+            graph,
+        );
+        check(ssrc, ret, "Failed to create filter source")?;
+        let ret = avfilter_graph_create_filter(
+            &mut this.sink,
+            avfilter_get_by_name(c"buffersink".as_ptr()),
+            c"out".as_ptr(),
+            ptr::null(),
+            ptr::null_mut(),
+            graph,
+        );
+        check(ssrc, ret, "Failed to create filter sink")?;
+
+        let mut prev = this.src;
+        for (i, step) in layer.steps.iter().enumerate() {
+            let name = CString::new(step.name).unwrap();
+            let filter = avfilter_get_by_name(name.as_ptr());
+            if filter.is_null() {
+                return Err(sys_error(
+                    ssrc,
+                    &format!("Filter {} not available in linked FFmpeg", step.name),
+                ));
+            }
+            let inst = CString::new(format!("{}_{i}", step.name)).unwrap();
+            let ctx = avfilter_graph_alloc_filter(graph, filter, inst.as_ptr());
+            if ctx.is_null() {
+                return Err(sys_error(ssrc, "Failed to alloc filter"));
+            }
+            for (key, value) in &step.opts {
+                let key = CString::new(*key).unwrap();
+                let value = CString::new(value.as_str()).unwrap_or_default();
+                let ret = av_opt_set(
+                    ctx as *mut _,
+                    key.as_ptr(),
+                    value.as_ptr(),
+                    AV_OPT_SEARCH_CHILDREN as c_int,
+                );
+                check(ssrc, ret, &format!("Invalid {} option", step.name))?;
+            }
+            check(
+                ssrc,
+                avfilter_init_str(ctx, ptr::null()),
+                "Failed to init filter",
+            )?;
+            check(
+                ssrc,
+                avfilter_link(prev, 0, ctx, 0),
+                "Failed to link filter",
+            )?;
+            prev = ctx;
+        }
+        check(
+            ssrc,
+            avfilter_link(prev, 0, this.sink, 0),
+            "Failed to link filter sink",
+        )?;
+        check(
+            ssrc,
+            avfilter_graph_config(graph, ptr::null_mut()),
+            "Failed to config filter graph",
+        )?;
+        Ok(this)
     }
 }
+
+impl Drop for VideoFilterGraph {
+    fn drop(&mut self) {
+        unsafe {
+            //滤镜实例随图一并释放
+            avfilter_graph_free(&mut self.graph);
+        }
+    }
+}
+
+fn check(ssrc: u32, ret: c_int, msg: &str) -> GlobalResult<()> {
+    if ret < 0 {
+        return Err(sys_error(
+            ssrc,
+            &format!("{msg}: {}", show_ffmpeg_error_msg(ret)),
+        ));
+    }
+    Ok(())
+}
+
+fn sys_error(ssrc: u32, msg: &str) -> GlobalError {
+    GlobalError::new_sys_error(msg, |msg| error!("ssrc: {ssrc}; {msg}"))
+}
@@ -86,7 +86,7 @@ pub struct DemuxerContext {
 //     }
 // }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ParamRepairState {
     pub h264_ps: Option<H264ParameterSets>,
     pub h265_ps: Option<H265ParameterSets>,
@@ -94,13 +94,13 @@ pub struct ParamRepairState {
     pub ready: bool,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct H264ParameterSets {
     pub sps: Option<Vec<u8>>,
     pub pps: Option<Vec<u8>>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct H265ParameterSets {
     pub vps: Option<Vec<u8>>,
     pub sps: Option<Vec<u8>>,
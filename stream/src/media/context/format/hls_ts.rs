@@ -1 +1,186 @@
-pub struct HlsTsContext {}
+use crate::media::context::format::demuxer::DemuxerContext;
+use crate::media::context::format::ts::{TsMuxer, alloc_ts_muxer};
+use crate::media::context::format::{FmtMuxer, MuxPacket};
+use base::bytes::Bytes;
+use base::exception::GlobalResult;
+use base::log::debug;
+use base::tokio::sync::broadcast;
+use rsmpeg::ffi::{AV_NOPTS_VALUE, AV_PKT_FLAG_KEY, AVPacket, AVRational, av_rescale_q};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Instant;
+
+//目标切片时长(ms)：在达到该时长后的首个关键帧处切片
+const TARGET_SEGMENT_MS: i64 = 2000;
+//滚动播放列表保留的切片数量
+const HLS_TS_WINDOW: usize = 6;
+const MS_TIME_BASE: AVRational = AVRational { num: 1, den: 1000 };
+
+pub struct HlsTsSegment {
+    pub seq: usize,
+    //秒
+    pub duration: f64,
+    pub data: Bytes,
+    //时间轴重置(seek等)后的首个切片，playlist需标记EXT-X-DISCONTINUITY
+    pub epoch: Instant,
+}
+
+pub struct HlsTsContext {
+    pub header: Bytes,
+    pub pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    muxer: TsMuxer,
+    started: bool,
+    segment_start_ms: i64,
+    last_ms: i64,
+    segment_timestamp: u64,
+    segments: VecDeque<Arc<HlsTsSegment>>,
+    pub epoch: Instant,
+    pub seq: usize, //hls 片段序号
+}
+
+impl FmtMuxer for HlsTsContext {
+    fn init_context(
+        demuxer_context: &DemuxerContext,
+        pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    ) -> GlobalResult<Self> {
+        let muxer = alloc_ts_muxer(demuxer_context)?;
+        let header = muxer.take_output();
+        Ok(HlsTsContext {
+            header,
+            pkt_tx,
+            muxer,
+            started: false,
+            segment_start_ms: 0,
+            last_ms: 0,
+            segment_timestamp: 0,
+            segments: VecDeque::with_capacity(HLS_TS_WINDOW + 1),
+            epoch: Instant::now(),
+            seq: 0,
+        })
+    }
+
+    fn get_header(&self) -> Bytes {
+        self.header.clone()
+    }
+
+    fn write_packet(&mut self, pkt: &AVPacket, timestamp: u64) -> GlobalResult<()> {
+        if pkt.size == 0 || pkt.data.is_null() {
+            return Ok(());
+        }
+        let Some(in_tb) = self.muxer.in_timebase(pkt.stream_index) else {
+            return Ok(());
+        };
+        let v_idx = self.muxer.v_idx;
+        let is_video = v_idx < 0 || v_idx == pkt.stream_index;
+        let is_key = v_idx < 0 || (is_video && (pkt.flags & AV_PKT_FLAG_KEY as i32) != 0);
+        let pts = if pkt.pts != AV_NOPTS_VALUE {
+            pkt.pts
+        } else {
+            pkt.dts
+        };
+        let cur_ms = unsafe { av_rescale_q(pts, in_tb, MS_TIME_BASE) };
+        if !self.started {
+            // 切片必须以关键帧开始
+            if !is_key {
+                return Ok(());
+            }
+            self.started = true;
+            self.segment_start_ms = cur_ms;
+            self.segment_timestamp = timestamp;
+        } else if is_key && cur_ms - self.segment_start_ms >= TARGET_SEGMENT_MS {
+            // mpegts 在视频关键帧前会重发 PAT/PMT，新切片可独立解码
+            self.flush_segment(cur_ms);
+            self.segment_start_ms = cur_ms;
+            self.segment_timestamp = timestamp;
+        }
+        if is_video && cur_ms > self.last_ms {
+            self.last_ms = cur_ms;
+        }
+        self.muxer.write(pkt);
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.muxer.write_trailer();
+        self.flush_segment(self.last_ms);
+    }
+}
+
+impl HlsTsContext {
+    /// 时间轴重置(seek等)：结束当前切片，等待关键帧重新起片，后续切片标记不连续
+    pub fn reset_epoch(&mut self) {
+        if self.started {
+            self.flush_segment(self.last_ms);
+        }
+        self.started = false;
+        self.last_ms = 0;
+        self.epoch = Instant::now();
+    }
+
+    pub fn get_segments(&self) -> Vec<Arc<HlsTsSegment>> {
+        self.segments.iter().cloned().collect()
+    }
+
+    fn flush_segment(&mut self, end_ms: i64) {
+        self.muxer.flush_io();
+        let data = self.muxer.take_output();
+        if data.is_empty() {
+            return;
+        }
+        let duration = (end_ms - self.segment_start_ms).max(1) as f64 / 1000.0;
+        self.seq += 1;
+        debug!(
+            "Flushing ts segment: seq={}, {} bytes, duration={}",
+            self.seq,
+            data.len(),
+            duration
+        );
+        let segment = Arc::new(HlsTsSegment {
+            seq: self.seq,
+            duration,
+            data: data.clone(),
+            epoch: self.epoch,
+        });
+        push_window(&mut self.segments, segment);
+        let _ = self.pkt_tx.send(Arc::new(MuxPacket {
+            data,
+            is_key: true,
+            timestamp: self.segment_timestamp,
+            epoch: self.epoch,
+            seq: self.seq,
+        }));
+    }
+}
+
+//滚动窗口：超出 HLS_TS_WINDOW 时淘汰最早的切片
+fn push_window(segments: &mut VecDeque<Arc<HlsTsSegment>>, segment: Arc<HlsTsSegment>) {
+    segments.push_back(segment);
+    while segments.len() > HLS_TS_WINDOW {
+        segments.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(seq: usize) -> Arc<HlsTsSegment> {
+        Arc::new(HlsTsSegment {
+            seq,
+            duration: 2.0,
+            data: Bytes::new(),
+            epoch: Instant::now(),
+        })
+    }
+
+    #[test]
+    fn window_keeps_latest_segments() {
+        let mut segments = VecDeque::new();
+        for seq in 1..=HLS_TS_WINDOW + 3 {
+            push_window(&mut segments, segment(seq));
+        }
+        assert_eq!(segments.len(), HLS_TS_WINDOW);
+        assert_eq!(segments.front().unwrap().seq, 4);
+        assert_eq!(segments.back().unwrap().seq, HLS_TS_WINDOW + 3);
+    }
+}
@@ -21,7 +21,7 @@ use rsmpeg::ffi::{
     AVCodecID_AV_CODEC_ID_AAC, AVCodecID_AV_CODEC_ID_H264, AVCodecID_AV_CODEC_ID_HEVC,
 };
 use rtp_types::prelude::PayloadLength;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::ffi::{CStr, CString, c_int, c_uint, c_void};
 use std::ptr;
 use std::sync::Arc;
@@ -29,7 +29,34 @@ use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
 static MP4: Lazy<CString> = Lazy::new(|| CString::new("mp4").unwrap());
+//LL-HLS part 目标时长：每个fMP4分片即一个part
 const MAX_DURATION: Duration = Duration::from_millis(500);
+//segment 目标时长(ms)：累计达到后在下一个独立(关键帧)part处切segment
+const TARGET_SEGMENT_MS: i64 = 2000;
+//滚动播放列表保留的完整segment数量
+const HLS_MP4_WINDOW: usize = 6;
+const MS_TIME_BASE: AVRational = AVRational { num: 1, den: 1000 };
+
+pub struct HlsPart {
+    //秒
+    pub duration: f64,
+    //以关键帧开始
+    pub independent: bool,
+    pub data: Bytes,
+}
+
+#[derive(Clone)]
+pub struct HlsMp4Segment {
+    //media sequence number
+    pub msn: usize,
+    //秒
+    pub duration: f64,
+    pub parts: Vec<Arc<HlsPart>>,
+    pub epoch: Instant,
+    //false-当前正在生成的segment
+    pub complete: bool,
+}
+
 pub struct HlsFmp4Context {
     pub init_segment: Bytes, // CMAF init.mp4
     pub pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
@@ -43,8 +70,12 @@ pub struct HlsFmp4Context {
     v_idx: c_int,
     fragment_started_with_key: bool, // 当前片段是否以关键帧开始
     fragment_start_timestamp: u64,   // 当前片段的第一帧时间戳
-    pub epoch: Instant,              //当由于seek导致dts回退时，重新初始化mux cxt
-    pub seq: usize,                  //hls 片段序号
+    fragment_start_ms: Option<i64>,  // 当前片段的第一帧pts(ms)
+    last_ms: i64,
+    segments: VecDeque<Arc<HlsMp4Segment>>,
+    current: HlsMp4Segment,
+    pub epoch: Instant, //当由于seek导致dts回退时，重新初始化mux cxt
+    pub seq: usize,     //hls 片段序号
 }
 impl Drop for HlsFmp4Context {
     fn drop(&mut self) {
@@ -125,7 +156,7 @@ impl FmtMuxer for HlsFmp4Context {
                 movflags.as_ptr(),
                 0,
             );
-            let frag_duration = CString::new("2000000").unwrap(); // 2s
+            let frag_duration = CString::new(MAX_DURATION.as_micros().to_string()).unwrap(); // 500ms
             av_dict_set(
                 &mut options,
                 CString::new("frag_duration").unwrap().as_ptr(),
@@ -148,6 +179,7 @@ impl FmtMuxer for HlsFmp4Context {
                 ));
             }
 
+            let epoch = Instant::now();
             // === init segment ===
             let init_data = {
                 let buf = &mut *out_buf_ptr;
@@ -165,7 +197,17 @@ impl FmtMuxer for HlsFmp4Context {
                 v_idx,
                 fragment_started_with_key: true,
                 fragment_start_timestamp: 0,
-                epoch: Instant::now(),
+                fragment_start_ms: None,
+                last_ms: 0,
+                segments: VecDeque::with_capacity(HLS_MP4_WINDOW + 1),
+                current: HlsMp4Segment {
+                    msn: 0,
+                    duration: 0.0,
+                    parts: Vec::new(),
+                    epoch,
+                    complete: false,
+                },
+                epoch,
                 seq: 0,
             })
         }
@@ -187,6 +229,15 @@ impl FmtMuxer for HlsFmp4Context {
                     return Ok(());
                 }
                 Some(&in_tb) => {
+                    let pts = if pkt.pts != AV_NOPTS_VALUE {
+                        pkt.pts
+                    } else {
+                        pkt.dts
+                    };
+                    let cur_ms = av_rescale_q(pts, in_tb, MS_TIME_BASE);
+                    if self.fragment_start_ms.is_none() {
+                        self.fragment_start_ms = Some(cur_ms);
+                    }
                     // 写入当前帧
                     av_packet_ref(&mut cloned, pkt);
                     let out_st = *(*self.fmt_ctx).streams.add(pkt.stream_index as usize);
@@ -206,11 +257,16 @@ impl FmtMuxer for HlsFmp4Context {
                     if self.flush_fragment(
                         self.fragment_start_timestamp,
                         self.fragment_started_with_key,
+                        cur_ms,
                     ) {
                         self.fragment_started_with_key = is_keyframe;
                         self.fragment_start_timestamp = timestamp;
+                        self.fragment_start_ms = Some(cur_ms);
                         // self.fragment_frame_count = 1;
                     }
+                    if cur_ms > self.last_ms {
+                        self.last_ms = cur_ms;
+                    }
                 }
             }
         }
@@ -230,13 +286,59 @@ impl FmtMuxer for HlsFmp4Context {
             self.flush_fragment(
                 self.fragment_start_timestamp,
                 self.fragment_started_with_key,
+                self.last_ms,
             );
         }
+        self.close_segment();
     }
 }
 
 impl HlsFmp4Context {
-    fn flush_fragment(&mut self, timestamp: u64, is_key: bool) -> bool {
+    /// 滚动窗口内的segment，末尾为正在生成的segment
+    pub fn get_playlist(&self) -> Vec<HlsMp4Segment> {
+        let mut list: Vec<HlsMp4Segment> = self.segments.iter().map(|s| (**s).clone()).collect();
+        list.push(self.current.clone());
+        list
+    }
+
+    fn close_segment(&mut self) {
+        if self.current.parts.is_empty() {
+            return;
+        }
+        let msn = self.current.msn;
+        let mut closed = std::mem::replace(
+            &mut self.current,
+            HlsMp4Segment {
+                msn: msn + 1,
+                duration: 0.0,
+                parts: Vec::new(),
+                epoch: self.epoch,
+                complete: false,
+            },
+        );
+        closed.complete = true;
+        self.segments.push_back(Arc::new(closed));
+        while self.segments.len() > HLS_MP4_WINDOW {
+            self.segments.pop_front();
+        }
+    }
+
+    fn push_part(&mut self, part: Arc<HlsPart>) {
+        // 独立part且segment时长已达标，或时间轴重置，开始新segment
+        if part.independent
+            && ((self.current.duration * 1000.0) as i64 >= TARGET_SEGMENT_MS
+                || self.current.epoch != self.epoch)
+        {
+            self.close_segment();
+        }
+        if self.current.parts.is_empty() {
+            self.current.epoch = self.epoch;
+        }
+        self.current.duration += part.duration;
+        self.current.parts.push(part);
+    }
+
+    fn flush_fragment(&mut self, timestamp: u64, is_key: bool, end_ms: i64) -> bool {
         unsafe {
             let out_vec = &mut *self.out_buf_ptr;
             if out_vec.is_empty() {
@@ -250,6 +352,12 @@ impl HlsFmp4Context {
             );
             self.seq += 1;
             let data = Bytes::from(std::mem::take(out_vec));
+            let start_ms = self.fragment_start_ms.unwrap_or(end_ms);
+            self.push_part(Arc::new(HlsPart {
+                duration: (end_ms - start_ms).max(1) as f64 / 1000.0,
+                independent: is_key,
+                data: data.clone(),
+            }));
             let _ = self.pkt_tx.send(Arc::new(MuxPacket {
                 data,
                 is_key,
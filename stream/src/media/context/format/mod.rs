@@ -12,7 +12,7 @@ pub mod demuxer;
 pub mod flv;
 pub mod fmp4;
 pub mod h265flv;
-mod hls_ts;
+pub mod hls_ts;
 pub mod hlsfmp4;
 pub mod mp4;
 pub mod muxer;
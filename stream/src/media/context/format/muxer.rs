@@ -14,7 +14,7 @@ use base::serde::{Deserialize, Serialize};
 use rsmpeg::ffi::{AVCodecID_AV_CODEC_ID_HEVC, AVMediaType_AVMEDIA_TYPE_VIDEO};
 use shared::info::output::OutputEnum;
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 #[serde(crate = "base::serde")]
 pub enum MuxerEnum {
     Flv,
@@ -36,7 +36,7 @@ impl MuxerEnum {
             OutputEnum::DashMp4 => MuxerEnum::DashMp4,
             OutputEnum::DashFmp4 => MuxerEnum::FMp4,
             OutputEnum::HlsFmp4 => MuxerEnum::HlsMp4,
-            OutputEnum::HlsTs => MuxerEnum::Ts,
+            OutputEnum::HlsTs => MuxerEnum::HlsTs,
             OutputEnum::Rtsp => MuxerEnum::RtpFrame,
             OutputEnum::Gb28181Frame => MuxerEnum::RtpFrame,
             OutputEnum::Gb28181Ps => MuxerEnum::RtpPs,
@@ -87,19 +87,29 @@ impl MuxerContext {
             });
         }
         if let Some(ts_layer) = muxer.ts {
-            unimplemented!()
+            let _ = TsContext::init_context(demuxer_context, ts_layer.tx).map(|ctx| {
+                context.ts = Some(ctx);
+            });
         }
         if let Some(hls_ts_layer) = muxer.hls_ts {
-            unimplemented!()
+            let _ = HlsTsContext::init_context(demuxer_context, hls_ts_layer.tx).map(|ctx| {
+                context.hls_ts = Some(ctx);
+            });
         }
         if let Some(rtp_frame_layer) = muxer.rtp_frame {
-            unimplemented!()
+            let _ = RtpFrameContext::init_context(demuxer_context, rtp_frame_layer.tx).map(|ctx| {
+                context.rtp_frame = Some(ctx);
+            });
         }
         if let Some(rtp_ps_layer) = muxer.rtp_ps {
-            unimplemented!()
+            let _ = RtpPsContext::init_context(demuxer_context, rtp_ps_layer.tx).map(|ctx| {
+                context.rtp_ps = Some(ctx);
+            });
         }
         if let Some(rtp_enc_layer) = muxer.rtp_enc {
-            unimplemented!()
+            let _ = RtpEncContext::init_context(demuxer_context, rtp_enc_layer.tx).map(|ctx| {
+                context.rtp_enc = Some(ctx);
+            });
         }
         if let Some(fmp4_layer) = muxer.fmp4 {
             let _ = CmafFmp4Context::init_context(demuxer_context, fmp4_layer.tx).map(|ctx| {
@@ -122,7 +132,7 @@ impl MuxerContext {
     }
 }
 
-unsafe fn input_has_hevc_video(in_fmt_ctx: *mut rsmpeg::ffi::AVFormatContext) -> bool {
+pub(crate) unsafe fn input_has_hevc_video(in_fmt_ctx: *mut rsmpeg::ffi::AVFormatContext) -> bool {
     if in_fmt_ctx.is_null() {
         return false;
     }
@@ -1 +1,249 @@
+use base::bytes::{BufMut, Bytes, BytesMut};
 
+const PACK_START_CODE: [u8; 4] = [0x00, 0x00, 0x01, 0xBA];
+const SYSTEM_HEADER_START_CODE: [u8; 4] = [0x00, 0x00, 0x01, 0xBB];
+const PSM_START_CODE: [u8; 4] = [0x00, 0x00, 0x01, 0xBC];
+pub const PS_VIDEO_STREAM_ID: u8 = 0xE0;
+pub const PS_AUDIO_STREAM_ID: u8 = 0xC0;
+// 单位50byte/s
+const PS_MUX_RATE: u32 = 6106;
+// PES_packet_length 16位，扣除固定头与PTS/DTS
+const MAX_PES_PAYLOAD: usize = 0xFFFF - 13;
+
+/// GB28181 PS 流类型
+pub mod stream_type {
+    pub const MPEG4: u8 = 0x10;
+    pub const H264: u8 = 0x1B;
+    pub const H265: u8 = 0x24;
+    pub const AAC: u8 = 0x0F;
+    pub const G711A: u8 = 0x90;
+    pub const G711U: u8 = 0x91;
+    pub const G722: u8 = 0x92;
+    pub const G723: u8 = 0x93;
+    pub const G729: u8 = 0x99;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PsStream {
+    pub stream_id: u8,
+    pub stream_type: u8,
+}
+
+/// GB28181 PS 封装：每帧输出一个 PS 包，关键帧携带系统头与 PSM
+pub struct PsMuxer {
+    streams: Vec<PsStream>,
+    psm_version: u8,
+}
+
+impl PsMuxer {
+    pub fn new(streams: Vec<PsStream>) -> Self {
+        Self {
+            streams,
+            psm_version: 0,
+        }
+    }
+
+    /// pts/dts 为 90kHz 时钟
+    pub fn mux_frame(
+        &self,
+        stream_id: u8,
+        data: &[u8],
+        pts: u64,
+        dts: Option<u64>,
+        is_key: bool,
+    ) -> Bytes {
+        let mut out = BytesMut::with_capacity(data.len() + 64);
+        put_pack_header(&mut out, dts.unwrap_or(pts));
+        if is_key {
+            self.put_system_header(&mut out);
+            self.put_psm(&mut out);
+        }
+        let mut first = true;
+        for chunk in data.chunks(MAX_PES_PAYLOAD) {
+            // 分片时仅首个PES携带时间戳
+            if first {
+                put_pes(
+                    &mut out,
+                    stream_id,
+                    chunk,
+                    Some(pts),
+                    dts.filter(|d| *d != pts),
+                );
+                first = false;
+            } else {
+                put_pes(&mut out, stream_id, chunk, None, None);
+            }
+        }
+        out.freeze()
+    }
+
+    fn put_system_header(&self, out: &mut BytesMut) {
+        let video_bound = self.count(PS_VIDEO_STREAM_ID);
+        let audio_bound = self.count(PS_AUDIO_STREAM_ID);
+        out.put_slice(&SYSTEM_HEADER_START_CODE);
+        out.put_u16((6 + 3 * self.streams.len()) as u16);
+        out.put_u8(0x80 | ((PS_MUX_RATE >> 15) & 0x7F) as u8);
+        out.put_u8(((PS_MUX_RATE >> 7) & 0xFF) as u8);
+        out.put_u8((((PS_MUX_RATE << 1) & 0xFE) | 0x01) as u8);
+        out.put_u8(audio_bound << 2);
+        out.put_u8(0xE0 | (video_bound & 0x1F));
+        out.put_u8(0x7F);
+        for st in &self.streams {
+            out.put_u8(st.stream_id);
+            if st.stream_id == PS_VIDEO_STREAM_ID {
+                // buffer_bound_scale=1,size=400*1024
+                out.put_u8(0xE0 | (400u16 >> 8) as u8);
+                out.put_u8((400u16 & 0xFF) as u8);
+            } else {
+                // buffer_bound_scale=0,size=32*128
+                out.put_u8(0xC0);
+                out.put_u8(0x20);
+            }
+        }
+    }
+
+    fn put_psm(&self, out: &mut BytesMut) {
+        let start = out.len();
+        let es_map_len = 4 * self.streams.len();
+        out.put_slice(&PSM_START_CODE);
+        out.put_u16((10 + es_map_len) as u16);
+        out.put_u8(0xE0 | (self.psm_version & 0x1F));
+        out.put_u8(0xFF);
+        out.put_u16(0);
+        out.put_u16(es_map_len as u16);
+        for st in &self.streams {
+            out.put_u8(st.stream_type);
+            out.put_u8(st.stream_id);
+            out.put_u16(0);
+        }
+        let crc = crc32_mpeg2(&out[start..]);
+        out.put_u32(crc);
+    }
+
+    fn count(&self, stream_id: u8) -> u8 {
+        self.streams
+            .iter()
+            .filter(|st| st.stream_id == stream_id)
+            .count() as u8
+    }
+}
+
+fn put_pack_header(out: &mut BytesMut, scr: u64) {
+    let scr = scr & 0x1_FFFF_FFFF;
+    out.put_slice(&PACK_START_CODE);
+    out.put_u8(0x44 | (((scr >> 27) & 0x38) as u8) | (((scr >> 28) & 0x03) as u8));
+    out.put_u8(((scr >> 20) & 0xFF) as u8);
+    out.put_u8((((scr >> 12) & 0xF8) as u8) | 0x04 | (((scr >> 13) & 0x03) as u8));
+    out.put_u8(((scr >> 5) & 0xFF) as u8);
+    out.put_u8((((scr & 0x1F) << 3) as u8) | 0x04);
+    out.put_u8(0x01);
+    out.put_u8(((PS_MUX_RATE >> 14) & 0xFF) as u8);
+    out.put_u8(((PS_MUX_RATE >> 6) & 0xFF) as u8);
+    out.put_u8((((PS_MUX_RATE << 2) & 0xFC) | 0x03) as u8);
+    out.put_u8(0xF8);
+}
+
+fn put_pes(out: &mut BytesMut, stream_id: u8, payload: &[u8], pts: Option<u64>, dts: Option<u64>) {
+    let (flags, header_len) = match (pts, dts) {
+        (Some(_), Some(_)) => (0xC0, 10),
+        (Some(_), None) => (0x80, 5),
+        _ => (0x00, 0),
+    };
+    out.put_slice(&[0x00, 0x00, 0x01, stream_id]);
+    out.put_u16((3 + header_len + payload.len()) as u16);
+    out.put_u8(0x80);
+    out.put_u8(flags);
+    out.put_u8(header_len as u8);
+    match (pts, dts) {
+        (Some(pts), Some(dts)) => {
+            put_timestamp(out, 0x03, pts);
+            put_timestamp(out, 0x01, dts);
+        }
+        (Some(pts), None) => put_timestamp(out, 0x02, pts),
+        _ => {}
+    }
+    out.put_slice(payload);
+}
+
+fn put_timestamp(out: &mut BytesMut, prefix: u8, ts: u64) {
+    let ts = ts & 0x1_FFFF_FFFF;
+    out.put_u8((prefix << 4) | ((((ts >> 30) & 0x07) as u8) << 1) | 0x01);
+    out.put_u8(((ts >> 22) & 0xFF) as u8);
+    out.put_u8(((((ts >> 15) & 0x7F) as u8) << 1) | 0x01);
+    out.put_u8(((ts >> 7) & 0xFF) as u8);
+    out.put_u8((((ts & 0x7F) as u8) << 1) | 0x01);
+}
+
+fn crc32_mpeg2(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc ^= (b as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_timestamp(b: &[u8]) -> u64 {
+        (((b[0] >> 1) & 0x07) as u64) << 30
+            | (b[1] as u64) << 22
+            | ((b[2] >> 1) as u64) << 15
+            | (b[3] as u64) << 7
+            | (b[4] >> 1) as u64
+    }
+
+    #[test]
+    fn key_frame_carries_system_header_and_psm() {
+        let muxer = PsMuxer::new(vec![
+            PsStream {
+                stream_id: PS_VIDEO_STREAM_ID,
+                stream_type: stream_type::H264,
+            },
+            PsStream {
+                stream_id: PS_AUDIO_STREAM_ID,
+                stream_type: stream_type::G711A,
+            },
+        ]);
+        let frame = [0u8, 0, 0, 1, 0x65, 0x88];
+        let ps = muxer.mux_frame(PS_VIDEO_STREAM_ID, &frame, 3600, None, true);
+        assert_eq!(&ps[..4], &PACK_START_CODE);
+        assert_eq!(&ps[14..18], &SYSTEM_HEADER_START_CODE);
+        let psm = 14 + 6 + 6 + 3 * 2;
+        assert_eq!(&ps[psm..psm + 4], &PSM_START_CODE);
+        let psm_len = u16::from_be_bytes([ps[psm + 4], ps[psm + 5]]) as usize;
+        // CRC 覆盖整个PSM时余数为0
+        assert_eq!(crc32_mpeg2(&ps[psm..psm + 6 + psm_len]), 0);
+        let pes = psm + 6 + psm_len;
+        assert_eq!(&ps[pes..pes + 4], &[0, 0, 1, PS_VIDEO_STREAM_ID]);
+        assert_eq!(read_timestamp(&ps[pes + 9..pes + 14]), 3600);
+        assert!(ps.ends_with(&frame));
+
+        let ps = muxer.mux_frame(PS_VIDEO_STREAM_ID, &frame, 7200, None, false);
+        assert_eq!(&ps[14..18], &[0, 0, 1, PS_VIDEO_STREAM_ID]);
+    }
+
+    #[test]
+    fn large_frame_splits_into_multiple_pes() {
+        let muxer = PsMuxer::new(vec![PsStream {
+            stream_id: PS_VIDEO_STREAM_ID,
+            stream_type: stream_type::H265,
+        }]);
+        let frame = vec![0xABu8; MAX_PES_PAYLOAD + 100];
+        let ps = muxer.mux_frame(PS_VIDEO_STREAM_ID, &frame, 90_000, Some(87_000), false);
+        let first_len = u16::from_be_bytes([ps[18], ps[19]]) as usize;
+        assert_eq!(first_len, 3 + 10 + MAX_PES_PAYLOAD);
+        let second = 14 + 6 + first_len;
+        assert_eq!(&ps[second..second + 4], &[0, 0, 1, PS_VIDEO_STREAM_ID]);
+        assert_eq!(ps[second + 7], 0x00);
+        assert_eq!(ps.len(), second + 9 + 100);
+    }
+}
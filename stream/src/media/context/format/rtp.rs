@@ -1,3 +1,762 @@
-pub struct RtpFrameContext {}
-pub struct RtpPsContext {}
-pub struct RtpEncContext {}
+use crate::media::context::format::demuxer::DemuxerContext;
+use crate::media::context::format::ps::{
+    PS_AUDIO_STREAM_ID, PS_VIDEO_STREAM_ID, PsMuxer, PsStream, stream_type,
+};
+use crate::media::context::format::{FmtMuxer, MuxPacket};
+use base::bytes::{BufMut, Bytes, BytesMut};
+use base::exception::{GlobalError, GlobalResult};
+use base::log::{debug, warn};
+use base::tokio::sync::broadcast;
+use rsmpeg::ffi::{
+    AV_NOPTS_VALUE, AV_PKT_FLAG_KEY, AVCodecID, AVCodecID_AV_CODEC_ID_AAC,
+    AVCodecID_AV_CODEC_ID_ADPCM_G722, AVCodecID_AV_CODEC_ID_G723_1, AVCodecID_AV_CODEC_ID_G729,
+    AVCodecID_AV_CODEC_ID_H264, AVCodecID_AV_CODEC_ID_HEVC, AVCodecID_AV_CODEC_ID_MPEG4,
+    AVCodecID_AV_CODEC_ID_PCM_ALAW, AVCodecID_AV_CODEC_ID_PCM_MULAW,
+    AVMediaType_AVMEDIA_TYPE_AUDIO, AVMediaType_AVMEDIA_TYPE_VIDEO, AVPacket, AVRational,
+    av_rescale_q, avcodec_get_name,
+};
+use std::collections::HashMap;
+use std::ffi::{CStr, c_int};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// 单个RTP负载上限：留出IP/UDP/RTP头，避免分片
+pub const RTP_MAX_PAYLOAD: usize = 1400;
+const RTP_TIME_BASE: AVRational = AVRational { num: 1, den: 90000 };
+const H264_NAL_FU_A: u8 = 28;
+const H265_NAL_FU: u8 = 49;
+
+/// RTP 类 muxer 输出的 MuxPacket：
+/// data 为一帧拆分后的 RTP 负载序列，每个负载前置2字节大端长度；
+/// timestamp 为 90kHz 时钟，由推流端截断为 RTP 时间戳
+pub fn split_payloads(data: &Bytes) -> Vec<Bytes> {
+    let mut payloads = Vec::new();
+    let mut pos = 0;
+    while pos + 2 <= data.len() {
+        let len = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
+        pos += 2;
+        if len == 0 || pos + len > data.len() {
+            break;
+        }
+        payloads.push(data.slice(pos..pos + len));
+        pos += len;
+    }
+    payloads
+}
+
+fn put_payload(out: &mut BytesMut, payload: &[u8]) {
+    out.put_u16(payload.len() as u16);
+    out.put_slice(payload);
+}
+
+#[derive(Clone, Copy)]
+struct RtpTrack {
+    codec_id: AVCodecID,
+    time_base: AVRational,
+    stream_id: u8,
+}
+
+/// 帧模式：视频ES按 RFC6184/RFC7798 打包，仅输出视频
+pub struct RtpFrameContext {
+    pub pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    v_idx: c_int,
+    track: RtpTrack,
+    param_sets: Option<Bytes>,
+    started: bool,
+    //帧模式单路RTP不承载音频，记录被丢弃的音频编码供推流端上报
+    pub skipped_audio: Option<String>,
+    pub epoch: Instant,
+}
+
+impl FmtMuxer for RtpFrameContext {
+    fn init_context(
+        demuxer_context: &DemuxerContext,
+        pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    ) -> GlobalResult<Self> {
+        unsafe {
+            let tracks = collect_tracks(demuxer_context);
+            let Some((&v_idx, &track)) = tracks
+                .iter()
+                .find(|(_, t)| t.stream_id == PS_VIDEO_STREAM_ID)
+            else {
+                return Err(GlobalError::new_sys_error(
+                    "rtp frame muxer requires a video stream",
+                    |msg| warn!("{msg}"),
+                ));
+            };
+            let skipped_audio = tracks
+                .values()
+                .find(|t| t.stream_id == PS_AUDIO_STREAM_ID)
+                .map(|t| {
+                    CStr::from_ptr(avcodec_get_name(t.codec_id))
+                        .to_string_lossy()
+                        .into_owned()
+                });
+            if let Some(codec) = &skipped_audio {
+                warn!("rtp frame muxer outputs video only, audio {codec} dropped");
+            }
+            Ok(RtpFrameContext {
+                pkt_tx,
+                v_idx,
+                track,
+                param_sets: annexb_extradata(demuxer_context, v_idx),
+                started: false,
+                skipped_audio,
+                epoch: Instant::now(),
+            })
+        }
+    }
+
+    fn get_header(&self) -> Bytes {
+        Bytes::new()
+    }
+
+    fn write_packet(&mut self, pkt: &AVPacket, _timestamp: u64) -> GlobalResult<()> {
+        if pkt.stream_index != self.v_idx || pkt.size <= 0 || pkt.data.is_null() {
+            return Ok(());
+        }
+        let is_key = (pkt.flags & AV_PKT_FLAG_KEY as i32) != 0;
+        if !self.started {
+            if !is_key {
+                return Ok(());
+            }
+            self.started = true;
+        }
+        let data = unsafe { std::slice::from_raw_parts(pkt.data, pkt.size as usize) };
+        let mut out = BytesMut::with_capacity(data.len() + 64);
+        if is_key && !has_param_sets(data, self.track.codec_id) {
+            if let Some(param_sets) = &self.param_sets {
+                for nal in annexb_nals(param_sets) {
+                    packetize_nal(&mut out, nal, self.track.codec_id);
+                }
+            }
+        }
+        for nal in annexb_nals(data) {
+            packetize_nal(&mut out, nal, self.track.codec_id);
+        }
+        if out.is_empty() {
+            return Ok(());
+        }
+        let _ = self.pkt_tx.send(Arc::new(MuxPacket {
+            data: out.freeze(),
+            is_key,
+            timestamp: rescale_90k(pkt.pts, pkt.dts, self.track.time_base),
+            epoch: self.epoch,
+            seq: 0,
+        }));
+        Ok(())
+    }
+
+    fn flush(&mut self) {}
+}
+
+/// PS模式：音视频按 GB28181 封装为 PS，再按 RTP_MAX_PAYLOAD 切分
+pub struct RtpPsContext {
+    pub pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    muxer: PsMuxer,
+    tracks: HashMap<c_int, RtpTrack>,
+    v_idx: c_int,
+    param_sets: Option<Bytes>,
+    started: bool,
+    pub epoch: Instant,
+}
+
+impl FmtMuxer for RtpPsContext {
+    fn init_context(
+        demuxer_context: &DemuxerContext,
+        pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    ) -> GlobalResult<Self> {
+        unsafe {
+            let tracks = collect_tracks(demuxer_context);
+            if tracks.is_empty() {
+                return Err(GlobalError::new_sys_error(
+                    "no stream supported by ps muxer",
+                    |msg| warn!("{msg}"),
+                ));
+            }
+            let mut indexes: Vec<&c_int> = tracks.keys().collect();
+            indexes.sort();
+            let streams = indexes
+                .into_iter()
+                .map(|idx| {
+                    let track = &tracks[idx];
+                    PsStream {
+                        stream_id: track.stream_id,
+                        stream_type: ps_stream_type(track.codec_id),
+                    }
+                })
+                .collect();
+            let v_idx = tracks
+                .iter()
+                .find(|(_, t)| t.stream_id == PS_VIDEO_STREAM_ID)
+                .map(|(idx, _)| *idx)
+                .unwrap_or(-1);
+            let param_sets = if v_idx >= 0 {
+                annexb_extradata(demuxer_context, v_idx)
+            } else {
+                None
+            };
+            Ok(RtpPsContext {
+                pkt_tx,
+                muxer: PsMuxer::new(streams),
+                tracks,
+                v_idx,
+                param_sets,
+                started: false,
+                epoch: Instant::now(),
+            })
+        }
+    }
+
+    fn get_header(&self) -> Bytes {
+        Bytes::new()
+    }
+
+    fn write_packet(&mut self, pkt: &AVPacket, _timestamp: u64) -> GlobalResult<()> {
+        if pkt.size <= 0 || pkt.data.is_null() {
+            return Ok(());
+        }
+        let Some(track) = self.tracks.get(&pkt.stream_index).copied() else {
+            return Ok(());
+        };
+        let is_key = self.v_idx == pkt.stream_index && (pkt.flags & AV_PKT_FLAG_KEY as i32) != 0;
+        // 关键帧起播：无视频流时直接开始
+        if !self.started {
+            if self.v_idx >= 0 && !is_key {
+                return Ok(());
+            }
+            self.started = true;
+        }
+        let data = unsafe { std::slice::from_raw_parts(pkt.data, pkt.size as usize) };
+        let pts = rescale_90k(pkt.pts, pkt.dts, track.time_base);
+        let dts =
+            (pkt.dts != AV_NOPTS_VALUE).then(|| rescale_90k(pkt.dts, pkt.dts, track.time_base));
+        let ps = match &self.param_sets {
+            Some(param_sets) if is_key && !has_param_sets(data, track.codec_id) => {
+                let mut frame = BytesMut::with_capacity(param_sets.len() + data.len());
+                frame.put_slice(param_sets);
+                frame.put_slice(data);
+                self.muxer
+                    .mux_frame(track.stream_id, &frame, pts, dts, is_key)
+            }
+            _ => self
+                .muxer
+                .mux_frame(track.stream_id, data, pts, dts, is_key),
+        };
+        let mut out = BytesMut::with_capacity(ps.len() + ps.len() / RTP_MAX_PAYLOAD * 2 + 2);
+        for chunk in ps.chunks(RTP_MAX_PAYLOAD) {
+            put_payload(&mut out, chunk);
+        }
+        let _ = self.pkt_tx.send(Arc::new(MuxPacket {
+            data: out.freeze(),
+            is_key,
+            timestamp: dts.unwrap_or(pts),
+            epoch: self.epoch,
+            seq: 0,
+        }));
+        Ok(())
+    }
+
+    fn flush(&mut self) {}
+}
+
+/// RtpEnc 输出的 MuxPacket：data 首字节为轨道类型，其后同 split_payloads 格式；
+/// timestamp 为对应轨道的 RTP 时钟（视频 90kHz，G.711 8kHz）
+pub const RTP_ENC_VIDEO: u8 = 0;
+pub const RTP_ENC_AUDIO: u8 = 1;
+const G711_CLOCK_RATE: i32 = 8000;
+// G.711 按 20ms 一包
+const G711_SAMPLES_PER_PACKET: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtpEncVideo {
+    H264,
+    H265,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtpEncAudio {
+    Pcma,
+    Pcmu,
+}
+
+/// WebRTC 协商所需的轨道信息
+#[derive(Debug, Clone)]
+pub struct RtpEncTracks {
+    pub video: RtpEncVideo,
+    //浏览器不支持 AAC，仅 G.711 作为音频轨道输出
+    pub audio: Option<RtpEncAudio>,
+    //无法输出的源音频编码，如 aac；协商时拒绝
+    pub unsupported_audio: Option<String>,
+    //H.264 SPS 中的 profile-level-id，如 4d401f
+    pub profile_level_id: Option<String>,
+}
+
+/// WebRTC 模式：视频按 RFC6184/RFC7798 打包，G.711 音频按 RFC3551 打包
+pub struct RtpEncContext {
+    pub pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    v_idx: c_int,
+    video: RtpTrack,
+    audio: Option<(c_int, RtpTrack)>,
+    param_sets: Option<Bytes>,
+    started: bool,
+    pub tracks: RtpEncTracks,
+    pub epoch: Instant,
+}
+
+impl FmtMuxer for RtpEncContext {
+    fn init_context(
+        demuxer_context: &DemuxerContext,
+        pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    ) -> GlobalResult<Self> {
+        unsafe {
+            let tracks = collect_tracks(demuxer_context);
+            let video = tracks
+                .iter()
+                .find(|(_, t)| t.stream_id == PS_VIDEO_STREAM_ID)
+                .and_then(|(idx, t)| {
+                    let kind = match t.codec_id {
+                        AVCodecID_AV_CODEC_ID_H264 => RtpEncVideo::H264,
+                        AVCodecID_AV_CODEC_ID_HEVC => RtpEncVideo::H265,
+                        _ => return None,
+                    };
+                    Some((*idx, *t, kind))
+                });
+            let Some((v_idx, video, video_kind)) = video else {
+                return Err(GlobalError::new_sys_error(
+                    "rtp enc muxer requires h264/h265 video",
+                    |msg| warn!("{msg}"),
+                ));
+            };
+            let mut unsupported_audio = None;
+            let audio = tracks
+                .iter()
+                .find(|(_, t)| t.stream_id == PS_AUDIO_STREAM_ID)
+                .and_then(|(idx, t)| {
+                    let kind = match t.codec_id {
+                        AVCodecID_AV_CODEC_ID_PCM_ALAW => RtpEncAudio::Pcma,
+                        AVCodecID_AV_CODEC_ID_PCM_MULAW => RtpEncAudio::Pcmu,
+                        _ => {
+                            let codec = CStr::from_ptr(avcodec_get_name(t.codec_id))
+                                .to_string_lossy()
+                                .into_owned();
+                            warn!("rtp enc muxer unsupported audio codec: {codec}");
+                            unsupported_audio = Some(codec);
+                            return None;
+                        }
+                    };
+                    Some((*idx, *t, kind))
+                });
+            let param_sets = annexb_extradata(demuxer_context, v_idx);
+            let profile_level_id = match video_kind {
+                RtpEncVideo::H264 => param_sets.as_deref().and_then(h264_profile_level_id),
+                RtpEncVideo::H265 => None,
+            };
+            Ok(RtpEncContext {
+                pkt_tx,
+                v_idx,
+                video,
+                audio: audio.map(|(idx, t, _)| (idx, t)),
+                param_sets,
+                started: false,
+                tracks: RtpEncTracks {
+                    video: video_kind,
+                    audio: audio.map(|(_, _, kind)| kind),
+                    unsupported_audio,
+                    profile_level_id,
+                },
+                epoch: Instant::now(),
+            })
+        }
+    }
+
+    fn get_header(&self) -> Bytes {
+        Bytes::new()
+    }
+
+    fn write_packet(&mut self, pkt: &AVPacket, _timestamp: u64) -> GlobalResult<()> {
+        if pkt.size <= 0 || pkt.data.is_null() {
+            return Ok(());
+        }
+        let data = unsafe { std::slice::from_raw_parts(pkt.data, pkt.size as usize) };
+        if pkt.stream_index == self.v_idx {
+            let is_key = (pkt.flags & AV_PKT_FLAG_KEY as i32) != 0;
+            if !self.started {
+                if !is_key {
+                    return Ok(());
+                }
+                self.started = true;
+            }
+            let mut out = BytesMut::with_capacity(data.len() + 64);
+            out.put_u8(RTP_ENC_VIDEO);
+            if is_key && !has_param_sets(data, self.video.codec_id) {
+                if let Some(param_sets) = &self.param_sets {
+                    for nal in annexb_nals(param_sets) {
+                        packetize_nal(&mut out, nal, self.video.codec_id);
+                    }
+                }
+            }
+            for nal in annexb_nals(data) {
+                packetize_nal(&mut out, nal, self.video.codec_id);
+            }
+            if out.len() > 1 {
+                self.send(
+                    out.freeze(),
+                    is_key,
+                    rescale_90k(pkt.pts, pkt.dts, self.video.time_base),
+                );
+            }
+            return Ok(());
+        }
+        let Some((a_idx, audio)) = self.audio else {
+            return Ok(());
+        };
+        if pkt.stream_index != a_idx || !self.started {
+            return Ok(());
+        }
+        let base = rescale_clock(pkt.pts, pkt.dts, audio.time_base, G711_CLOCK_RATE);
+        for (i, chunk) in data.chunks(G711_SAMPLES_PER_PACKET).enumerate() {
+            let mut out = BytesMut::with_capacity(chunk.len() + 3);
+            out.put_u8(RTP_ENC_AUDIO);
+            put_payload(&mut out, chunk);
+            let ts = base + (i * G711_SAMPLES_PER_PACKET) as u64;
+            self.send(out.freeze(), false, ts);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) {}
+}
+
+impl RtpEncContext {
+    fn send(&self, data: Bytes, is_key: bool, timestamp: u64) {
+        let _ = self.pkt_tx.send(Arc::new(MuxPacket {
+            data,
+            is_key,
+            timestamp,
+            epoch: self.epoch,
+            seq: 0,
+        }));
+    }
+}
+
+unsafe fn collect_tracks(demuxer_context: &DemuxerContext) -> HashMap<c_int, RtpTrack> {
+    unsafe {
+        let mut tracks = HashMap::new();
+        let fmt_ctx = demuxer_context.avio.fmt_ctx;
+        if fmt_ctx.is_null() {
+            return tracks;
+        }
+        let mut has_video = false;
+        let mut has_audio = false;
+        for i in 0..(*fmt_ctx).nb_streams as usize {
+            let st = *(*fmt_ctx).streams.add(i);
+            if st.is_null() || (*st).codecpar.is_null() {
+                continue;
+            }
+            let codecpar = (*st).codecpar;
+            let codec_id = (*codecpar).codec_id;
+            if ps_stream_type(codec_id) == 0 {
+                debug!("ps/rtp muxer skip unsupported codec: {}", codec_id);
+                continue;
+            }
+            // GB28181 仅支持单路视频与单路音频
+            let stream_id = match (*codecpar).codec_type {
+                AVMediaType_AVMEDIA_TYPE_VIDEO if !has_video => {
+                    has_video = true;
+                    PS_VIDEO_STREAM_ID
+                }
+                AVMediaType_AVMEDIA_TYPE_AUDIO if !has_audio => {
+                    has_audio = true;
+                    PS_AUDIO_STREAM_ID
+                }
+                _ => continue,
+            };
+            tracks.insert(
+                i as c_int,
+                RtpTrack {
+                    codec_id,
+                    time_base: (*st).time_base,
+                    stream_id,
+                },
+            );
+        }
+        tracks
+    }
+}
+
+/// 返回 0 表示不支持
+fn ps_stream_type(codec_id: AVCodecID) -> u8 {
+    match codec_id {
+        AVCodecID_AV_CODEC_ID_H264 => stream_type::H264,
+        AVCodecID_AV_CODEC_ID_HEVC => stream_type::H265,
+        AVCodecID_AV_CODEC_ID_MPEG4 => stream_type::MPEG4,
+        // 音频数据按ADTS透传，与输入侧保持一致
+        AVCodecID_AV_CODEC_ID_AAC => stream_type::AAC,
+        AVCodecID_AV_CODEC_ID_PCM_ALAW => stream_type::G711A,
+        AVCodecID_AV_CODEC_ID_PCM_MULAW => stream_type::G711U,
+        AVCodecID_AV_CODEC_ID_ADPCM_G722 => stream_type::G722,
+        AVCodecID_AV_CODEC_ID_G723_1 => stream_type::G723,
+        AVCodecID_AV_CODEC_ID_G729 => stream_type::G729,
+        _ => 0,
+    }
+}
+
+fn rescale_90k(pts: i64, dts: i64, time_base: AVRational) -> u64 {
+    rescale_clock(pts, dts, time_base, RTP_TIME_BASE.den)
+}
+
+fn rescale_clock(pts: i64, dts: i64, time_base: AVRational, clock_rate: i32) -> u64 {
+    let ts = if pts != AV_NOPTS_VALUE { pts } else { dts };
+    if ts == AV_NOPTS_VALUE || ts <= 0 {
+        return 0;
+    }
+    let clock = AVRational {
+        num: 1,
+        den: clock_rate,
+    };
+    unsafe { av_rescale_q(ts, time_base, clock) as u64 }
+}
+
+/// extradata 为 AnnexB 格式时作为关键帧前置的参数集
+unsafe fn annexb_extradata(demuxer_context: &DemuxerContext, idx: c_int) -> Option<Bytes> {
+    unsafe {
+        let fmt_ctx = demuxer_context.avio.fmt_ctx;
+        let st = *(*fmt_ctx).streams.add(idx as usize);
+        let codecpar = (*st).codecpar;
+        if (*codecpar).extradata.is_null() || (*codecpar).extradata_size < 4 {
+            return None;
+        }
+        let extradata =
+            std::slice::from_raw_parts((*codecpar).extradata, (*codecpar).extradata_size as usize);
+        if extradata.starts_with(&[0, 0, 0, 1]) || extradata.starts_with(&[0, 0, 1]) {
+            Some(Bytes::copy_from_slice(extradata))
+        } else {
+            None
+        }
+    }
+}
+
+fn has_param_sets(data: &[u8], codec_id: AVCodecID) -> bool {
+    match codec_id {
+        AVCodecID_AV_CODEC_ID_H264 => annexb_nals(data).any(|nal| nal[0] & 0x1F == 7),
+        AVCodecID_AV_CODEC_ID_HEVC => annexb_nals(data).any(|nal| (nal[0] >> 1) & 0x3F == 33),
+        _ => true,
+    }
+}
+
+/// 取 SPS 的 profile_idc/constraint_flags/level_idc
+fn h264_profile_level_id(param_sets: &[u8]) -> Option<String> {
+    annexb_nals(param_sets)
+        .find(|nal| nal[0] & 0x1F == 7 && nal.len() >= 4)
+        .map(|sps| format!("{:02x}{:02x}{:02x}", sps[1], sps[2], sps[3]))
+}
+
+/// 按起始码拆分 NAL，返回不含起始码的 NAL
+fn annexb_nals(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut pos = find_start_code(data, 0).map(|(_, next)| next);
+    std::iter::from_fn(move || {
+        loop {
+            let start = pos?;
+            let (end, next) = match find_start_code(data, start) {
+                Some((end, next)) => (end, Some(next)),
+                None => (data.len(), None),
+            };
+            pos = next;
+            if end > start {
+                return Some(&data[start..end]);
+            }
+        }
+    })
+}
+
+/// 返回 (起始码位置, 起始码之后的位置)
+fn find_start_code(data: &[u8], from: usize) -> Option<(usize, usize)> {
+    let mut i = from;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 {
+            if data[i + 2] == 1 {
+                return Some((i, i + 3));
+            }
+            if i + 4 <= data.len() && data[i + 2] == 0 && data[i + 3] == 1 {
+                return Some((i, i + 4));
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+fn packetize_nal(out: &mut BytesMut, nal: &[u8], codec_id: AVCodecID) {
+    if nal.len() <= RTP_MAX_PAYLOAD {
+        put_payload(out, nal);
+        return;
+    }
+    match codec_id {
+        AVCodecID_AV_CODEC_ID_H264 => {
+            let indicator = (nal[0] & 0xE0) | H264_NAL_FU_A;
+            let nal_type = nal[0] & 0x1F;
+            put_fragments(out, &nal[1..], &[indicator], nal_type);
+        }
+        AVCodecID_AV_CODEC_ID_HEVC if nal.len() > 2 => {
+            let payload_header = [(nal[0] & 0x81) | (H265_NAL_FU << 1), nal[1]];
+            let nal_type = (nal[0] >> 1) & 0x3F;
+            put_fragments(out, &nal[2..], &payload_header, nal_type);
+        }
+        _ => {
+            for chunk in nal.chunks(RTP_MAX_PAYLOAD) {
+                put_payload(out, chunk);
+            }
+        }
+    }
+}
+
+/// FU 分片：FU header 的 S/E 位标识首尾分片
+fn put_fragments(out: &mut BytesMut, body: &[u8], header: &[u8], nal_type: u8) {
+    let size = RTP_MAX_PAYLOAD - header.len() - 1;
+    let count = body.len().div_ceil(size);
+    for (i, chunk) in body.chunks(size).enumerate() {
+        let mut fu_header = nal_type;
+        if i == 0 {
+            fu_header |= 0x80;
+        }
+        if i + 1 == count {
+            fu_header |= 0x40;
+        }
+        out.put_u16((header.len() + 1 + chunk.len()) as u16);
+        out.put_slice(header);
+        out.put_u8(fu_header);
+        out.put_slice(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn h264_nal_fragments_into_fu_a() {
+        let mut au = vec![0u8, 0, 0, 1, 0x67, 0x42, 0, 0, 1, 0x65];
+        au.extend(std::iter::repeat_n(0x11u8, RTP_MAX_PAYLOAD * 2));
+        let mut out = BytesMut::new();
+        for nal in annexb_nals(&au) {
+            packetize_nal(&mut out, nal, AVCodecID_AV_CODEC_ID_H264);
+        }
+        let payloads = split_payloads(&out.freeze());
+        assert_eq!(payloads.len(), 4);
+        assert_eq!(&payloads[0][..], &[0x67, 0x42]);
+        assert_eq!(payloads[1][0], 0x60 | H264_NAL_FU_A);
+        assert_eq!(payloads[1][1], 0x80 | 0x05);
+        assert_eq!(payloads[2][1], 0x05);
+        assert_eq!(payloads[3][1], 0x40 | 0x05);
+        let body: usize = payloads[1..].iter().map(|p| p.len() - 2).sum();
+        assert_eq!(body, RTP_MAX_PAYLOAD * 2);
+        assert!(payloads.iter().all(|p| p.len() <= RTP_MAX_PAYLOAD));
+    }
+
+    #[test]
+    fn h264_profile_level_id_from_sps() {
+        let param_sets = [
+            0u8, 0, 0, 1, 0x68, 0xCE, 0, 0, 0, 1, 0x67, 0x4D, 0x40, 0x1F, 0x95,
+        ];
+        assert_eq!(
+            h264_profile_level_id(&param_sets),
+            Some("4d401f".to_string())
+        );
+        assert_eq!(h264_profile_level_id(&[0, 0, 1, 0x68, 0xCE]), None);
+    }
+
+    fn ps_context() -> (RtpPsContext, broadcast::Receiver<Arc<MuxPacket>>) {
+        let (pkt_tx, pkt_rx) = broadcast::channel(8);
+        let time_base = RTP_TIME_BASE;
+        let tracks = HashMap::from([
+            (
+                0,
+                RtpTrack {
+                    codec_id: AVCodecID_AV_CODEC_ID_H264,
+                    time_base,
+                    stream_id: PS_VIDEO_STREAM_ID,
+                },
+            ),
+            (
+                1,
+                RtpTrack {
+                    codec_id: AVCodecID_AV_CODEC_ID_PCM_ALAW,
+                    time_base,
+                    stream_id: PS_AUDIO_STREAM_ID,
+                },
+            ),
+        ]);
+        let muxer = PsMuxer::new(vec![
+            PsStream {
+                stream_id: PS_VIDEO_STREAM_ID,
+                stream_type: stream_type::H264,
+            },
+            PsStream {
+                stream_id: PS_AUDIO_STREAM_ID,
+                stream_type: stream_type::G711A,
+            },
+        ]);
+        let ctx = RtpPsContext {
+            pkt_tx,
+            muxer,
+            tracks,
+            v_idx: 0,
+            param_sets: None,
+            started: false,
+            epoch: Instant::now(),
+        };
+        (ctx, pkt_rx)
+    }
+
+    fn av_packet(stream_index: c_int, data: &mut [u8], ts: i64, is_key: bool) -> AVPacket {
+        let mut pkt: AVPacket = unsafe { std::mem::zeroed() };
+        pkt.data = data.as_mut_ptr();
+        pkt.size = data.len() as c_int;
+        pkt.stream_index = stream_index;
+        pkt.pts = ts;
+        pkt.dts = ts;
+        if is_key {
+            pkt.flags = AV_PKT_FLAG_KEY as c_int;
+        }
+        pkt
+    }
+
+    #[test]
+    fn ps_mode_frames_key_frame_into_bounded_payloads() {
+        let (mut ctx, mut rx) = ps_context();
+        let mut delta = vec![0u8, 0, 0, 1, 0x41, 0x9A];
+        ctx.write_packet(&av_packet(0, &mut delta, 3000, false), 0)
+            .unwrap();
+        // 关键帧之前的帧不输出
+        assert!(rx.try_recv().is_err());
+
+        let mut key = vec![0u8, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x65];
+        key.extend(std::iter::repeat_n(0x11u8, RTP_MAX_PAYLOAD * 2));
+        ctx.write_packet(&av_packet(0, &mut key, 3600, true), 0)
+            .unwrap();
+        let pkt = rx.try_recv().expect("key frame");
+        assert!(pkt.is_key);
+        assert_eq!(pkt.timestamp, 3600);
+        let payloads = split_payloads(&pkt.data);
+        assert_eq!(payloads.len(), 3);
+        assert!(payloads.iter().all(|p| p.len() <= RTP_MAX_PAYLOAD));
+        let ps: Vec<u8> = payloads.iter().flat_map(|p| p.iter().copied()).collect();
+        assert_eq!(&ps[..4], &[0, 0, 1, 0xBA]);
+        assert_eq!(&ps[14..18], &[0, 0, 1, 0xBB]);
+        assert!(ps.ends_with(&key[key.len() - 16..]));
+
+        let mut audio = vec![0xD5u8; 160];
+        ctx.write_packet(&av_packet(1, &mut audio, 3960, false), 0)
+            .unwrap();
+        let pkt = rx.try_recv().expect("audio frame");
+        assert!(!pkt.is_key);
+        assert_eq!(pkt.timestamp, 3960);
+        let payloads = split_payloads(&pkt.data);
+        assert_eq!(payloads.len(), 1);
+        // 非关键帧不携带系统头与PSM
+        assert_eq!(&payloads[0][..4], &[0, 0, 1, 0xBA]);
+        assert_eq!(&payloads[0][14..18], &[0, 0, 1, PS_AUDIO_STREAM_ID]);
+        assert!(payloads[0].ends_with(&audio));
+    }
+}
@@ -1 +1,284 @@
-pub struct TsContext {}
+use crate::media::context::format::demuxer::DemuxerContext;
+use crate::media::context::format::hlsfmp4::copy_streams;
+use crate::media::context::format::{FmtMuxer, MuxPacket, write_callback};
+use crate::media::{DEFAULT_IO_BUF_SIZE, show_ffmpeg_error_msg};
+use base::bytes::Bytes;
+use base::exception::{GlobalError, GlobalResult};
+use base::log::warn;
+use base::once_cell::sync::Lazy;
+use base::tokio::sync::broadcast;
+use rsmpeg::ffi::{
+    AV_PKT_FLAG_KEY, AVFMT_FLAG_FLUSH_PACKETS, AVFormatContext, AVIOContext, AVPacket, AVRational,
+    av_free, av_guess_format, av_malloc, av_packet_ref, av_packet_rescale_ts, av_packet_unref,
+    av_write_frame, av_write_trailer, avformat_alloc_context, avformat_free_context,
+    avformat_write_header, avio_alloc_context, avio_context_free, avio_flush,
+};
+use std::collections::HashMap;
+use std::ffi::{CString, c_int, c_void};
+use std::ptr;
+use std::sync::Arc;
+use std::time::Instant;
+
+static MPEGTS: Lazy<CString> = Lazy::new(|| CString::new("mpegts").unwrap());
+
+/// 写入内存的 mpegts 复用器：TsContext 与 HlsTsContext 共用的 FFmpeg 资源
+pub(crate) struct TsMuxer {
+    fmt_ctx: *mut AVFormatContext,
+    avio_ctx: *mut AVIOContext,
+    io_buf: *mut u8,
+    out_buf_ptr: *mut Vec<u8>,
+    in_timebase_map: HashMap<c_int, AVRational>,
+    pub v_idx: c_int,
+}
+
+impl Drop for TsMuxer {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.fmt_ctx.is_null() {
+                avformat_free_context(self.fmt_ctx);
+                self.fmt_ctx = ptr::null_mut();
+            }
+            if !self.avio_ctx.is_null() {
+                avio_context_free(&mut self.avio_ctx);
+                self.avio_ctx = ptr::null_mut();
+            }
+            // io_buf 由 avio_context_free 释放
+            self.io_buf = ptr::null_mut();
+
+            if !self.out_buf_ptr.is_null() {
+                drop(Box::from_raw(self.out_buf_ptr));
+                self.out_buf_ptr = ptr::null_mut();
+            }
+        }
+    }
+}
+
+impl TsMuxer {
+    pub fn in_timebase(&self, stream_index: c_int) -> Option<AVRational> {
+        self.in_timebase_map.get(&stream_index).copied()
+    }
+
+    /// 时基换算后写入一帧，失败返回false
+    pub fn write(&self, pkt: &AVPacket) -> bool {
+        unsafe { write_ts_packet(self.fmt_ctx, &self.in_timebase_map, pkt) }
+    }
+
+    pub fn flush_io(&self) {
+        unsafe {
+            avio_flush((*self.fmt_ctx).pb);
+        }
+    }
+
+    pub fn write_trailer(&self) {
+        unsafe {
+            av_write_trailer(self.fmt_ctx);
+        }
+    }
+
+    /// 取出已写入内存的数据
+    pub fn take_output(&self) -> Bytes {
+        unsafe { Bytes::from(std::mem::take(&mut *self.out_buf_ptr)) }
+    }
+}
+
+/// 连续 MPEG-TS 输出：本地TS录制等按包消费的场景
+pub struct TsContext {
+    pub header: Bytes,
+    pub pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    muxer: TsMuxer,
+    started: bool,
+    pub epoch: Instant,
+}
+
+impl FmtMuxer for TsContext {
+    fn init_context(
+        demuxer_context: &DemuxerContext,
+        pkt_tx: broadcast::Sender<Arc<MuxPacket>>,
+    ) -> GlobalResult<Self> {
+        let muxer = alloc_ts_muxer(demuxer_context)?;
+        let header = muxer.take_output();
+        Ok(TsContext {
+            header,
+            pkt_tx,
+            muxer,
+            started: false,
+            epoch: Instant::now(),
+        })
+    }
+
+    fn get_header(&self) -> Bytes {
+        self.header.clone()
+    }
+
+    fn write_packet(&mut self, pkt: &AVPacket, timestamp: u64) -> GlobalResult<()> {
+        if pkt.size == 0 || pkt.data.is_null() {
+            warn!("Skipping empty or invalid packet");
+            return Ok(());
+        }
+        let v_idx = self.muxer.v_idx;
+        let is_key = v_idx == pkt.stream_index && (pkt.flags & AV_PKT_FLAG_KEY as i32) != 0;
+        // 关键帧起播：无视频流时直接开始
+        if !self.started {
+            if v_idx >= 0 && !is_key {
+                return Ok(());
+            }
+            self.started = true;
+        }
+        if !self.muxer.write(pkt) {
+            return Ok(());
+        }
+        let data = self.muxer.take_output();
+        if data.is_empty() {
+            return Ok(());
+        }
+        let _ = self.pkt_tx.send(Arc::new(MuxPacket {
+            data,
+            is_key,
+            timestamp,
+            epoch: self.epoch,
+            seq: 0,
+        }));
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.muxer.write_trailer();
+        self.muxer.flush_io();
+        let data = self.muxer.take_output();
+        if data.is_empty() {
+            return;
+        }
+        let _ = self.pkt_tx.send(Arc::new(MuxPacket {
+            data,
+            is_key: false,
+            timestamp: 0,
+            epoch: self.epoch,
+            seq: 0,
+        }));
+    }
+}
+
+/// 创建写入内存的 mpegts 复用器，header 已写入输出缓冲
+pub(crate) fn alloc_ts_muxer(demuxer_context: &DemuxerContext) -> GlobalResult<TsMuxer> {
+    unsafe {
+        let io_buf = av_malloc(DEFAULT_IO_BUF_SIZE) as *mut u8;
+        if io_buf.is_null() {
+            return Err(GlobalError::new_sys_error(
+                "Failed to allocate IO buffer",
+                |msg| warn!("{msg}"),
+            ));
+        }
+        let out_buf_ptr = Box::into_raw(Box::new(Vec::<u8>::new()));
+        let mut avio_ctx = avio_alloc_context(
+            io_buf,
+            DEFAULT_IO_BUF_SIZE as c_int,
+            1,
+            out_buf_ptr as *mut c_void,
+            None,
+            Some(write_callback),
+            None,
+        );
+        if avio_ctx.is_null() {
+            av_free(io_buf as *mut c_void);
+            drop(Box::from_raw(out_buf_ptr));
+            return Err(GlobalError::new_sys_error(
+                "Failed to allocate AVIO context",
+                |msg| warn!("{msg}"),
+            ));
+        }
+        let out_fmt_ctx = avformat_alloc_context();
+        if out_fmt_ctx.is_null() {
+            avio_context_free(&mut avio_ctx);
+            drop(Box::from_raw(out_buf_ptr));
+            return Err(GlobalError::new_sys_error(
+                "Failed to alloc format context",
+                |msg| warn!("{msg}"),
+            ));
+        }
+        (*out_fmt_ctx).pb = avio_ctx;
+        (*out_fmt_ctx).oformat = av_guess_format(MPEGTS.as_ptr(), ptr::null(), ptr::null());
+        (*out_fmt_ctx).flags |= AVFMT_FLAG_FLUSH_PACKETS as i32;
+        let release = |mut avio_ctx: *mut AVIOContext| {
+            avio_context_free(&mut avio_ctx);
+            avformat_free_context(out_fmt_ctx);
+            drop(Box::from_raw(out_buf_ptr));
+        };
+        if (*out_fmt_ctx).oformat.is_null() {
+            release(avio_ctx);
+            return Err(GlobalError::new_sys_error(
+                "MPEG-TS format not supported",
+                |msg| warn!("{msg}"),
+            ));
+        }
+
+        let mut in_timebase_map = HashMap::with_capacity(8);
+        let v_idx = match copy_streams(
+            &mut in_timebase_map,
+            demuxer_context.avio.fmt_ctx,
+            out_fmt_ctx,
+        ) {
+            Ok(v_idx) => v_idx,
+            Err(err) => {
+                release(avio_ctx);
+                return Err(err);
+            }
+        };
+        if (*out_fmt_ctx).nb_streams == 0 {
+            release(avio_ctx);
+            return Err(GlobalError::new_sys_error(
+                "No streams added to muxer",
+                |msg| warn!("{msg}"),
+            ));
+        }
+        for i in 0..(*out_fmt_ctx).nb_streams as usize {
+            let out_st = *(*out_fmt_ctx).streams.add(i);
+            (*(*out_st).codecpar).codec_tag = 0;
+        }
+
+        let ret = avformat_write_header(out_fmt_ctx, ptr::null_mut());
+        if ret < 0 {
+            release(avio_ctx);
+            return Err(GlobalError::new_sys_error(
+                &format!("TS header write failed: {}", show_ffmpeg_error_msg(ret)),
+                |msg| warn!("{msg}"),
+            ));
+        }
+        Ok(TsMuxer {
+            fmt_ctx: out_fmt_ctx,
+            avio_ctx,
+            io_buf,
+            out_buf_ptr,
+            in_timebase_map,
+            v_idx,
+        })
+    }
+}
+
+unsafe fn write_ts_packet(
+    fmt_ctx: *mut AVFormatContext,
+    in_timebase_map: &HashMap<c_int, AVRational>,
+    pkt: &AVPacket,
+) -> bool {
+    unsafe {
+        let Some(&in_tb) = in_timebase_map.get(&pkt.stream_index) else {
+            warn!("TS write failed,stream index error: {}", &pkt.stream_index);
+            return false;
+        };
+        let mut cloned = std::mem::zeroed::<AVPacket>();
+        if av_packet_ref(&mut cloned, pkt) < 0 {
+            warn!("Failed to ref packet");
+            return false;
+        }
+        let out_st = *(*fmt_ctx).streams.add(pkt.stream_index as usize);
+        av_packet_rescale_ts(&mut cloned, in_tb, (*out_st).time_base);
+        cloned.pos = -1;
+        // 非交织写入：保证切片边界与输入帧一致
+        let ret = av_write_frame(fmt_ctx, &mut cloned);
+        av_packet_unref(&mut cloned);
+        if ret < 0 {
+            warn!("TS write failed: {}", show_ffmpeg_error_msg(ret));
+            return false;
+        }
+        true
+    }
+}
@@ -1,3 +1,4 @@
+use crate::media::context::capture::CaptureContext;
 use crate::media::context::codec::CodecContext;
 use crate::media::context::event::ContextEvent;
 use crate::media::context::filter::FilterContext;
@@ -33,6 +34,7 @@ use std::ffi::c_int;
 use std::sync::Arc;
 use std::time::Instant;
 
+mod capture;
 mod codec;
 pub mod event;
 mod filter;
@@ -105,6 +107,7 @@ pub struct MediaContext {
     pub media_ext: MediaExt,
     pub codec_context: Option<CodecContext>,
     pub filter_context: FilterContext,
+    pub capture_context: CaptureContext,
     pub muxer_context: MuxerContext,
     pub context_event_rx: TypedReceiver<ContextEvent>,
     pub demuxer_context: DemuxerContext,
@@ -170,12 +173,24 @@ impl MediaContext {
         (false, 0)
     }
 
+    /// muxer 的输入流描述：开启转码时为转码后的流
+    pub fn output_context(&self) -> &DemuxerContext {
+        self.codec_context
+            .as_ref()
+            .and_then(CodecContext::output)
+            .unwrap_or(&self.demuxer_context)
+    }
+
     pub fn init(
         ssrc: u32,
         stream_config: StreamConfig,
     ) -> GlobalResult<(MediaContext, MuxerLayer)> {
-        let rtp_buffer =
-            RtpPacketBuffer::init(ssrc, stream_config.rtp_rx, &stream_config.media_ext)?;
+        let rtp_buffer = RtpPacketBuffer::init(
+            ssrc,
+            stream_config.rtp_rx,
+            &stream_config.media_ext,
+            stream_config.rtcp,
+        )?;
         // Box → raw pointer
         let rtp_state_ptr = Box::into_raw(Box::new(RtpState::new()));
         let demuxer_context = DemuxerContext::start_demuxer(
@@ -184,11 +199,18 @@ impl MediaContext {
             rtp_buffer,
             rtp_state_ptr,
         )?;
-        let converter = stream_config.converter;
+        let mut converter = stream_config.converter;
+        let capture_context = CaptureContext::init(
+            ssrc,
+            stream_config.stream_id,
+            converter.filter.capture.take(),
+        );
+        let filter_context = FilterContext::init(ssrc, converter.filter);
 
         let context = MediaContext {
-            codec_context: CodecContext::init(converter.codec),
-            filter_context: FilterContext::init(converter.filter),
+            codec_context: CodecContext::init(ssrc, converter.codec, &filter_context),
+            filter_context,
+            capture_context,
             ssrc,
             media_ext: stream_config.media_ext,
             context_event_rx: stream_config.context_event_rx,
@@ -313,8 +335,19 @@ impl MediaContext {
                 return Ok(());
             }
             let mut normalizer = &mut cache_info.timeline_normalizer;
+            //打开转码，失败或无需转码时直通
+            if let Some(codec_context) = &mut self.codec_context {
+                match codec_context.open(&self.demuxer_context, &mut self.filter_context) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        info!("ssrc: {}; 无需转码，直通输出", self.ssrc);
+                        self.codec_context = None;
+                    }
+                    Err(_) => self.codec_context = None,
+                }
+            }
             //初始化muxer
-            self.muxer_context = MuxerContext::init(&self.demuxer_context, muxer_layer);
+            self.muxer_context = MuxerContext::init(self.output_context(), muxer_layer);
             //消费缓存数据，以关键帧开始
             while let Some(mut pkt) = cache_info.pkts.pop_front() {
                 match self.context_event_rx.try_recv() {
@@ -370,17 +403,28 @@ impl MediaContext {
         pkt: &mut AVPacket,
     ) -> GlobalResult<()> {
         if let (Some(master_clock_us), res) = normalizer.process(pkt, self.ssrc) {
-            // 暂不实现处理codec
-            // &mut self.codec_context.as_mut().map(|cc|Self::handle_codec(cc));
-            // 暂不实现处理filter
-            // Self::handle_filter(&mut self.filter_context);
-            // 调用 muxer 其中master_clock_us需要转换为秒，供录制进度信息
-            Self::handle_pkt_muxer(self, res, &pkt, (master_clock_us / 1000_000) as u64);
+            // master_clock_us需要转换为秒，供录制进度信息
+            let ts = (master_clock_us / 1000_000) as u64;
+            // 抽图取转码前的原始关键帧
+            self.capture_context.cache(&self.demuxer_context, pkt);
+            // 滤镜作用于转码阶段的解码帧
+            let filter = &mut self.filter_context;
+            match self
+                .codec_context
+                .as_mut()
+                .and_then(|cc| cc.transcode(pkt, filter))
+            {
+                Some(pkts) => {
+                    for mut out in pkts {
+                        Self::handle_pkt_muxer(self, res, &out, ts);
+                        rsmpeg::ffi::av_packet_unref(&mut out);
+                    }
+                }
+                None => Self::handle_pkt_muxer(self, res, &pkt, ts),
+            }
         }
         Ok(())
     }
-    fn handle_codec(codec: &mut CodecContext) {}
-    fn handle_filter(filter: &mut FilterContext) {}
 
     // 1.写入头信息
     // 2.循环写入body
@@ -402,20 +446,23 @@ impl MediaContext {
         if let Some(context) = &mut muxer.mp4 {
             let _ = context.write_packet(pkt, ts);
         }
-        if let Some(context) = &muxer.ts {
-            unimplemented!()
+        if let Some(context) = &mut muxer.ts {
+            let _ = context.write_packet(pkt, ts);
         }
-        if let Some(context) = &muxer.rtp_frame {
-            unimplemented!()
+        if let Some(context) = &mut muxer.rtp_frame {
+            let _ = context.write_packet(pkt, ts);
         }
-        if let Some(context) = &muxer.rtp_ps {
-            unimplemented!()
+        if let Some(context) = &mut muxer.rtp_ps {
+            let _ = context.write_packet(pkt, ts);
         }
-        if let Some(context) = &muxer.rtp_enc {
-            unimplemented!()
+        if let Some(context) = &mut muxer.rtp_enc {
+            let _ = context.write_packet(pkt, ts);
         }
-        if let Some(context) = &muxer.hls_ts {
-            unimplemented!()
+        if let Some(context) = &mut muxer.hls_ts {
+            if epoch == ProcessResult::Discontinuity {
+                context.reset_epoch();
+            }
+            let _ = context.write_packet(pkt, ts);
         }
         if let Some(context) = &mut muxer.fmp4 {
             if epoch == ProcessResult::Discontinuity {
@@ -450,20 +497,20 @@ impl MediaContext {
         if let Some(context) = &mut muxer.mp4 {
             context.flush();
         }
-        if let Some(context) = &muxer.ts {
-            unimplemented!()
+        if let Some(context) = &mut muxer.ts {
+            context.flush();
         }
-        if let Some(context) = &muxer.rtp_frame {
-            unimplemented!()
+        if let Some(context) = &mut muxer.rtp_frame {
+            context.flush();
         }
-        if let Some(context) = &muxer.rtp_ps {
-            unimplemented!()
+        if let Some(context) = &mut muxer.rtp_ps {
+            context.flush();
         }
-        if let Some(context) = &muxer.rtp_enc {
-            unimplemented!()
+        if let Some(context) = &mut muxer.rtp_enc {
+            context.flush();
         }
-        if let Some(context) = &muxer.hls_ts {
-            unimplemented!()
+        if let Some(context) = &mut muxer.hls_ts {
+            context.flush();
         }
         if let Some(context) = &mut muxer.fmp4 {
             context.flush();
@@ -478,15 +525,15 @@ impl MediaContext {
 
     fn handle_event(&mut self, event: ContextEvent) {
         match event {
-            ContextEvent::Codec(_) => {
-                unimplemented!()
-            }
             ContextEvent::Muxer(m_event) => {
-                m_event.handle_event(&mut self.muxer_context, &self.demuxer_context);
-            }
-            ContextEvent::Filter(_) => {
-                unimplemented!()
+                let output = self
+                    .codec_context
+                    .as_ref()
+                    .and_then(CodecContext::output)
+                    .unwrap_or(&self.demuxer_context);
+                m_event.handle_event(&mut self.muxer_context, output);
             }
+            ContextEvent::Filter(f_event) => self.filter_context.handle_event(f_event),
             ContextEvent::Inner(i_event) => {
                 i_event.handle_event(&self);
             }
@@ -18,6 +18,7 @@ use std::ffi::c_int;
 use std::sync::Arc;
 
 pub mod context;
+pub mod rtcp;
 pub mod rtp;
 mod rw;
 
@@ -0,0 +1,457 @@
+use crate::media::rtp::RtpPacket;
+use base::bytes::{BufMut, Bytes, BytesMut};
+use base::net::state::Protocol;
+use parking_lot::Mutex;
+use shared::info::obj::RtcpStats;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+pub const PT_SR: u8 = 200;
+pub const PT_RR: u8 = 201;
+pub const PT_BYE: u8 = 203;
+const RTCP_VERSION: u8 = 2;
+const RTCP_HEADER_LEN: usize = 4;
+const REPORT_BLOCK_LEN: usize = 24;
+const MAX_DROPOUT: u16 = 3000;
+const MAX_MISORDER: u16 = 100;
+const SEQ_MOD: u32 = 1 << 16;
+//NTP 纪元(1900)与 UNIX 纪元(1970)相差秒数
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+//本端报告者SSRC：由媒体SSRC派生，避免与媒体源冲突
+const REPORTER_SSRC_MASK: u32 = 0x5A5A_5A5A;
+
+/// RFC 5761：rtp端口复用时按 PT 区分 RTCP(192..=223)
+pub fn is_rtcp(data: &[u8]) -> bool {
+    data.len() >= RTCP_HEADER_LEN + 4
+        && data[0] >> 6 == RTCP_VERSION
+        && (192..=223).contains(&data[1])
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RtcpPacket {
+    SenderReport {
+        ssrc: u32,
+        ntp_sec: u32,
+        ntp_frac: u32,
+        rtp_ts: u32,
+    },
+    ReceiverReport {
+        ssrc: u32,
+    },
+    Bye {
+        ssrcs: Vec<u32>,
+    },
+}
+
+/// 解析复合包，仅保留 SR/RR/BYE；遇到非法包头即停止
+pub fn parse_compound(data: &[u8]) -> Vec<RtcpPacket> {
+    let mut packets = Vec::new();
+    let mut rest = data;
+    while rest.len() >= RTCP_HEADER_LEN {
+        if rest[0] >> 6 != RTCP_VERSION {
+            break;
+        }
+        let count = (rest[0] & 0x1F) as usize;
+        let pt = rest[1];
+        let len = (u16::from_be_bytes([rest[2], rest[3]]) as usize + 1) * 4;
+        if len > rest.len() {
+            break;
+        }
+        let body = &rest[RTCP_HEADER_LEN..len];
+        match pt {
+            PT_SR if body.len() >= 20 => packets.push(RtcpPacket::SenderReport {
+                ssrc: be_u32(body, 0),
+                ntp_sec: be_u32(body, 4),
+                ntp_frac: be_u32(body, 8),
+                rtp_ts: be_u32(body, 12),
+            }),
+            PT_RR if body.len() >= 4 => packets.push(RtcpPacket::ReceiverReport {
+                ssrc: be_u32(body, 0),
+            }),
+            PT_BYE => {
+                let ssrcs = (0..count)
+                    .filter(|i| body.len() >= i * 4 + 4)
+                    .map(|i| be_u32(body, i * 4))
+                    .collect::<Vec<_>>();
+                if !ssrcs.is_empty() {
+                    packets.push(RtcpPacket::Bye { ssrcs });
+                }
+            }
+            _ => {}
+        }
+        rest = &rest[len..];
+    }
+    packets
+}
+
+fn be_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReportBlock {
+    pub ssrc: u32,
+    pub fraction_lost: u8,
+    //24位有符号
+    pub cumulative_lost: i32,
+    pub ext_highest_seq: u32,
+    pub jitter: u32,
+    pub lsr: u32,
+    pub dlsr: u32,
+}
+
+/// 单个报告块的 RR
+pub fn build_rr(media_ssrc: u32, block: &ReportBlock) -> Bytes {
+    let mut buf = BytesMut::with_capacity(RTCP_HEADER_LEN + 4 + REPORT_BLOCK_LEN);
+    buf.put_u8(RTCP_VERSION << 6 | 1);
+    buf.put_u8(PT_RR);
+    buf.put_u16(((4 + REPORT_BLOCK_LEN) / 4) as u16);
+    buf.put_u32(media_ssrc ^ REPORTER_SSRC_MASK);
+    buf.put_u32(block.ssrc);
+    buf.put_u32((block.fraction_lost as u32) << 24 | (block.cumulative_lost as u32 & 0xFF_FFFF));
+    buf.put_u32(block.ext_highest_seq);
+    buf.put_u32(block.jitter);
+    buf.put_u32(block.lsr);
+    buf.put_u32(block.dlsr);
+    buf.freeze()
+}
+
+/// RFC 3550 A.1/A.3/A.8 接收统计
+struct ReceptionStats {
+    clock_rate: u32,
+    epoch: Instant,
+    initialized: bool,
+    base_seq: u16,
+    max_seq: u16,
+    cycles: u32,
+    received: u32,
+    expected_prior: u32,
+    received_prior: u32,
+    transit: Option<i32>,
+    jitter: f64,
+    last_rtp_ts: u32,
+    //SR：NTP中间32位、接收时刻、NTP(64位)与RTP时间戳映射
+    last_sr: u32,
+    last_sr_at: Option<Instant>,
+    sr_mapping: Option<(u64, u32)>,
+}
+
+impl ReceptionStats {
+    fn new() -> Self {
+        Self {
+            clock_rate: 0,
+            epoch: Instant::now(),
+            initialized: false,
+            base_seq: 0,
+            max_seq: 0,
+            cycles: 0,
+            received: 0,
+            expected_prior: 0,
+            received_prior: 0,
+            transit: None,
+            jitter: 0.0,
+            last_rtp_ts: 0,
+            last_sr: 0,
+            last_sr_at: None,
+            sr_mapping: None,
+        }
+    }
+
+    fn reset_seq(&mut self, seq: u16) {
+        self.base_seq = seq;
+        self.max_seq = seq;
+        self.cycles = 0;
+        self.received = 0;
+        self.expected_prior = 0;
+        self.received_prior = 0;
+    }
+
+    fn on_rtp(&mut self, seq: u16, timestamp: u32, arrival: Instant) {
+        if !self.initialized {
+            self.initialized = true;
+            self.reset_seq(seq);
+        } else {
+            let delta = seq.wrapping_sub(self.max_seq);
+            if delta < MAX_DROPOUT {
+                if seq < self.max_seq {
+                    self.cycles = self.cycles.wrapping_add(SEQ_MOD);
+                }
+                self.max_seq = seq;
+            } else if delta <= u16::MAX - MAX_MISORDER {
+                //序号大跳变视为源重启
+                self.reset_seq(seq);
+                self.transit = None;
+            }
+        }
+        self.received = self.received.wrapping_add(1);
+        self.last_rtp_ts = timestamp;
+        if self.clock_rate > 0 {
+            let elapsed = arrival.saturating_duration_since(self.epoch).as_secs_f64();
+            let arrival_ts = (elapsed * self.clock_rate as f64) as u64 as u32;
+            let transit = arrival_ts.wrapping_sub(timestamp) as i32;
+            if let Some(prev) = self.transit {
+                let d = transit.wrapping_sub(prev).unsigned_abs() as f64;
+                self.jitter += (d - self.jitter) / 16.0;
+            }
+            self.transit = Some(transit);
+        }
+    }
+
+    fn on_sr(&mut self, ntp_sec: u32, ntp_frac: u32, rtp_ts: u32, now: Instant) {
+        self.last_sr = (ntp_sec & 0xFFFF) << 16 | ntp_frac >> 16;
+        self.last_sr_at = Some(now);
+        self.sr_mapping = Some(((ntp_sec as u64) << 32 | ntp_frac as u64, rtp_ts));
+    }
+
+    //(扩展最高序号, 期望包数, 累计丢包)
+    fn totals(&self) -> (u32, u32, i32) {
+        let ext_highest_seq = self.cycles.wrapping_add(self.max_seq as u32);
+        let expected = ext_highest_seq
+            .wrapping_sub(self.base_seq as u32)
+            .wrapping_add(1);
+        let lost = expected as i64 - self.received as i64;
+        (
+            ext_highest_seq,
+            expected,
+            lost.clamp(-0x80_0000, 0x7F_FFFF) as i32,
+        )
+    }
+
+    fn report_block(&mut self, ssrc: u32, now: Instant) -> Option<ReportBlock> {
+        if !self.initialized {
+            return None;
+        }
+        let (ext_highest_seq, expected, cumulative_lost) = self.totals();
+        let expected_interval = expected.wrapping_sub(self.expected_prior);
+        let received_interval = self.received.wrapping_sub(self.received_prior);
+        self.expected_prior = expected;
+        self.received_prior = self.received;
+        let lost_interval = expected_interval as i64 - received_interval as i64;
+        let fraction_lost = if expected_interval == 0 || lost_interval <= 0 {
+            0
+        } else {
+            ((lost_interval << 8) / expected_interval as i64).min(255) as u8
+        };
+        let (lsr, dlsr) = match self.last_sr_at {
+            Some(at) => (
+                self.last_sr,
+                (now.saturating_duration_since(at).as_secs_f64() * 65536.0) as u32,
+            ),
+            None => (0, 0),
+        };
+        Some(ReportBlock {
+            ssrc,
+            fraction_lost,
+            cumulative_lost,
+            ext_highest_seq,
+            jitter: self.jitter as u32,
+            lsr,
+            dlsr,
+        })
+    }
+
+    //按 SR 映射换算 RTP 时间戳对应的发送端时间(unix ms)
+    fn unix_ms(&self, rtp_ts: u32) -> Option<u64> {
+        let (ntp, sr_rtp) = self.sr_mapping?;
+        if self.clock_rate == 0 {
+            return None;
+        }
+        let ntp_ms = (ntp >> 32) * 1000 + ((ntp & 0xFFFF_FFFF) * 1000 >> 32);
+        let diff_ms = rtp_ts.wrapping_sub(sr_rtp) as i32 as i64 * 1000 / self.clock_rate as i64;
+        (ntp_ms as i64 + diff_ms)
+            .checked_sub(NTP_UNIX_OFFSET_SECS as i64 * 1000)
+            .map(|ms| ms as u64)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RtcpPeer {
+    pub addr: SocketAddr,
+    pub protocol: Protocol,
+    //经rtp端口复用收发
+    pub mux: bool,
+}
+
+/// 单路流的 RTCP 状态：RtpPacketBuffer 写入接收统计，RTCP 收发任务读取并回复 RR
+pub struct RtcpSession {
+    stats: Mutex<ReceptionStats>,
+    peer: Mutex<Option<RtcpPeer>>,
+}
+
+impl RtcpSession {
+    pub fn new() -> Self {
+        Self {
+            stats: Mutex::new(ReceptionStats::new()),
+            peer: Mutex::new(None),
+        }
+    }
+
+    pub fn set_clock_rate(&self, clock_rate: u32) {
+        self.stats.lock().clock_rate = clock_rate;
+    }
+
+    pub fn on_rtp(&self, pkt: &RtpPacket) {
+        self.stats
+            .lock()
+            .on_rtp(pkt.seq, pkt.timestamp, pkt.arrival);
+    }
+
+    pub fn on_sr(&self, ntp_sec: u32, ntp_frac: u32, rtp_ts: u32) {
+        self.stats
+            .lock()
+            .on_sr(ntp_sec, ntp_frac, rtp_ts, Instant::now());
+    }
+
+    pub fn set_peer(&self, peer: RtcpPeer) {
+        *self.peer.lock() = Some(peer);
+    }
+
+    pub fn peer(&self) -> Option<RtcpPeer> {
+        *self.peer.lock()
+    }
+
+    pub fn report_block(&self, ssrc: u32) -> Option<ReportBlock> {
+        self.stats.lock().report_block(ssrc, Instant::now())
+    }
+
+    /// 接收统计快照：不影响 RR 的周期丢包计算；未收到 RTP 时为空
+    pub fn stats(&self) -> Option<RtcpStats> {
+        let stats = self.stats.lock();
+        if !stats.initialized {
+            return None;
+        }
+        let (_, expected, cumulative_lost) = stats.totals();
+        Some(RtcpStats {
+            expected,
+            received: stats.received,
+            cumulative_lost,
+            jitter: stats.jitter as u32,
+            clock_rate: stats.clock_rate,
+            sender_unix_ms: stats.unix_ms(stats.last_rtp_ts),
+            peer: self.peer.lock().map(|peer| peer.addr.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_compound_sr_sdes_bye() {
+        let mut buf = BytesMut::new();
+        //SR 无报告块
+        buf.put_u8(0x80);
+        buf.put_u8(PT_SR);
+        buf.put_u16(6);
+        for v in [0x1234_5678u32, 0xE000_0001, 0x8000_0000, 90000, 10, 2000] {
+            buf.put_u32(v);
+        }
+        //SDES 跳过
+        buf.put_u8(0x81);
+        buf.put_u8(202);
+        buf.put_u16(1);
+        buf.put_u32(0x1234_5678);
+        //BYE
+        buf.put_u8(0x81);
+        buf.put_u8(PT_BYE);
+        buf.put_u16(1);
+        buf.put_u32(0x1234_5678);
+        assert!(is_rtcp(&buf));
+        let packets = parse_compound(&buf);
+        assert_eq!(
+            packets,
+            [
+                RtcpPacket::SenderReport {
+                    ssrc: 0x1234_5678,
+                    ntp_sec: 0xE000_0001,
+                    ntp_frac: 0x8000_0000,
+                    rtp_ts: 90000,
+                },
+                RtcpPacket::Bye {
+                    ssrcs: vec![0x1234_5678]
+                },
+            ]
+        );
+        //RTP(PT=96) 不视为 RTCP
+        assert!(!is_rtcp(&[0x80, 0x60, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1]));
+    }
+
+    #[test]
+    fn report_block_counts_loss_and_wrap() {
+        let mut stats = ReceptionStats::new();
+        let now = Instant::now();
+        for seq in [65533u16, 65534, 65535, 1, 2] {
+            stats.on_rtp(seq, 0, now);
+        }
+        let block = stats.report_block(7, now).unwrap();
+        assert_eq!(block.ext_highest_seq, SEQ_MOD + 2);
+        assert_eq!(block.cumulative_lost, 1);
+        assert_eq!(block.fraction_lost, (1 << 8) / 6);
+        assert_eq!(block.lsr, 0);
+        //本周期无新包
+        assert_eq!(stats.report_block(7, now).unwrap().fraction_lost, 0);
+
+        stats.on_sr(0x0001_0002, 0x0003_0004, 0, now);
+        let block = stats.report_block(7, now + Duration::from_secs(1)).unwrap();
+        assert_eq!(block.lsr, 0x0002_0003);
+        assert_eq!(block.dlsr, 65536);
+    }
+
+    #[test]
+    fn rr_layout() {
+        let block = ReportBlock {
+            ssrc: 1,
+            fraction_lost: 2,
+            cumulative_lost: -1,
+            ext_highest_seq: 3,
+            jitter: 4,
+            lsr: 5,
+            dlsr: 6,
+        };
+        let rr = build_rr(1, &block);
+        assert_eq!(rr.len(), 32);
+        assert_eq!(&rr[..4], &[0x81, PT_RR, 0, 7]);
+        assert_eq!(&rr[12..16], &[2, 0xFF, 0xFF, 0xFF]);
+        assert!(is_rtcp(&rr));
+    }
+
+    #[test]
+    fn sr_maps_rtp_to_unix_time() {
+        let mut stats = ReceptionStats::new();
+        stats.clock_rate = 90000;
+        let ntp_sec = (NTP_UNIX_OFFSET_SECS + 1_000) as u32;
+        stats.on_sr(ntp_sec, 0x8000_0000, 1000, Instant::now());
+        assert_eq!(stats.unix_ms(1000 + 90000), Some(1_001_500));
+    }
+
+    #[test]
+    fn stats_snapshot_keeps_rr_interval() {
+        let session = RtcpSession::new();
+        assert!(session.stats().is_none());
+        session.set_clock_rate(90000);
+        let now = Instant::now();
+        {
+            let mut stats = session.stats.lock();
+            for (seq, ts) in [(1u16, 0u32), (2, 3600), (4, 10800)] {
+                stats.on_rtp(seq, ts, now);
+            }
+            stats.on_sr((NTP_UNIX_OFFSET_SECS + 1_000) as u32, 0, 0, now);
+        }
+        let stats = session.stats().unwrap();
+        assert_eq!(
+            (stats.expected, stats.received, stats.cumulative_lost),
+            (4, 3, 1)
+        );
+        assert_eq!(stats.sender_unix_ms, Some(1_000_120));
+        assert!(stats.peer.is_none());
+        //快照不重置 RR 的周期计数
+        assert_eq!(session.report_block(1).unwrap().fraction_lost, (1 << 8) / 4);
+    }
+}
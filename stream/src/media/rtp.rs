@@ -1,4 +1,5 @@
 use crate::media::context::RtpState;
+use crate::media::rtcp::RtcpSession;
 use base::bytes::{Bytes, BytesMut};
 use base::exception::{GlobalError, GlobalResult};
 use base::log::{debug, warn};
@@ -6,6 +7,7 @@ use crossbeam_channel::{Receiver, RecvTimeoutError};
 use shared::info::media_info_ext::MediaExt;
 use std::collections::VecDeque;
 use std::ptr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 pub struct RtpPacket {
@@ -14,6 +16,8 @@ pub struct RtpPacket {
     pub marker: bool,
     pub seq: u16,
     pub payload: Bytes,
+    //到达时刻，用于计算RTCP抖动
+    pub arrival: Instant,
 }
 
 const BUFFER_SIZE: usize = 1024;
@@ -91,6 +95,18 @@ fn max_gap_wait_ms(payload_kind: PayloadKind) -> u64 {
     }
 }
 
+//RTP 时钟频率：优先 SDP，缺省视频 90kHz、G711 8kHz
+fn clock_rate(payload_kind: PayloadKind, media_ext: &MediaExt) -> u32 {
+    if media_ext.clock_rate > 0 {
+        return media_ext.clock_rate as u32;
+    }
+    match payload_kind {
+        PayloadKind::Ps | PayloadKind::H264 | PayloadKind::H265 | PayloadKind::Passthrough => 90000,
+        PayloadKind::G711 => 8000,
+        PayloadKind::Aac => AacAdtsConfig::from_media_ext(media_ext).sample_rate as u32,
+    }
+}
+
 fn matches_codec(codec: &Option<String>, candidates: &[&str]) -> bool {
     codec
         .as_deref()
@@ -165,6 +181,7 @@ pub struct RtpPacketBuffer {
     h264_fu: Option<BytesMut>,
     h265_fu: Option<BytesMut>,
     aac_adts: AacAdtsConfig,
+    rtcp: Arc<RtcpSession>,
 }
 
 impl RtpPacketBuffer {
@@ -172,9 +189,11 @@ impl RtpPacketBuffer {
         ssrc: u32,
         packet_rx: Receiver<RtpPacket>,
         media_ext: &MediaExt,
+        rtcp: Arc<RtcpSession>,
     ) -> GlobalResult<Self> {
         let payload_kind = PayloadKind::from_media_ext(media_ext);
         let queue_window = reorder_window(payload_kind);
+        rtcp.set_clock_rate(clock_rate(payload_kind, media_ext));
         let mut buffer = Self {
             ssrc,
             first_read_rtp_sn: u16::MAX,
@@ -192,6 +211,7 @@ impl RtpPacketBuffer {
             h264_fu: None,
             h265_fu: None,
             aac_adts: AacAdtsConfig::from_media_ext(media_ext),
+            rtcp,
         };
         buffer.calculate_index()?;
         Ok(buffer)
@@ -436,10 +456,14 @@ impl RtpPacketBuffer {
         true
     }
 
+    //所有到达的包（含乱序、重复）计入RTCP接收统计
     fn recv_packet(&self) -> GlobalResult<RtpPacket> {
-        self.packet_rx
+        let pkt = self
+            .packet_rx
             .recv()
-            .map_err(|_| GlobalError::new_sys_error("rtp input channel closed", |_| {}))
+            .map_err(|_| GlobalError::new_sys_error("rtp input channel closed", |_| {}))?;
+        self.rtcp.on_rtp(&pkt);
+        Ok(pkt)
     }
 
     fn enqueue_initial(&mut self, pkt: RtpPacket) {
@@ -618,7 +642,10 @@ impl RtpPacketBuffer {
             }
 
             match self.packet_rx.recv_timeout(deadline.duration_since(now)) {
-                Ok(pkt) => self.enqueue(pkt),
+                Ok(pkt) => {
+                    self.rtcp.on_rtp(&pkt);
+                    self.enqueue(pkt)
+                }
                 Err(RecvTimeoutError::Timeout) => return self.has_packet(expected_seq),
                 Err(RecvTimeoutError::Disconnected) => return false,
             }
@@ -1,7 +1,11 @@
 use crate::io::http::call::{HttpClient, HttpSession, HttpTemplate};
 use crate::io::local::mp4::LocalStoreMp4Context;
+use crate::io::local::ts::LocalStoreTsContext;
+use crate::io::push::gb28181::Gb28181PushContext;
+use crate::io::push::rtmp::RtmpPushContext;
 use crate::state::layer::output_layer::OutputLayer;
 use crate::state::register::{Inner, Register, TimeScheduleKey};
+use base::bytes::Bytes;
 use base::cache::c100k::CacheEvent;
 use base::exception::GlobalResultExt;
 use base::log::{error, info, warn};
@@ -16,9 +20,10 @@ use pretend::Pretend;
 use pretend::interceptor::NoopRequestInterceptor;
 use pretend::resolver::UrlResolver;
 use pretend_reqwest::Client;
+use shared::info::filter::ImageFormat;
 use shared::info::obj::{
-    BaseStreamInfo, InTimeoutEventRes, OutputEventRes, OutputStreamInfo, RegisterStreamInfo,
-    RtpInfo, StreamPlayInfo, StreamRecordInfo, StreamState, UnknownStreamEvent,
+    BaseStreamInfo, InTimeoutEventRes, OutputEventRes, OutputStreamInfo, PushStateInfo,
+    RegisterStreamInfo, RtpInfo, StreamPlayInfo, StreamRecordInfo, StreamState, UnknownStreamEvent,
 };
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -38,11 +43,11 @@ pub enum InnerEvent {
     StreamRegister(u8, Arc<str>, (SocketAddr, Protocol)),
 }
 pub enum ActiveEvent {
-    RtmpPush(u32),
+    RtmpPush(RtmpPushContext),
     LocalStoreMp4(LocalStoreMp4Context),
-    LocalStoreTs(u32),
+    LocalStoreTs(LocalStoreTsContext),
     RtspPush(u32),
-    Gb28181Push(u32),
+    Gb28181Push(Gb28181PushContext),
     WebRtcPush(u32),
 }
 
@@ -63,6 +68,10 @@ pub enum OutEvent {
     OnPlay(StreamPlayInfo),
     OffPlay(StreamPlayInfo),
     EndRecord(StreamRecordInfo),
+    //主动推流状态变更
+    PushState(PushStateInfo),
+    //定时抽图：stream_id,图片格式,图片数据
+    StreamCapture(Arc<str>, ImageFormat, Bytes),
 }
 pub enum EventRes {
     Out(OutEventRes),
@@ -85,6 +94,8 @@ pub enum OutEventRes {
     OffPlay(Option<()>),
     //录像完成事件：响应内容不敏感;some-成功接收;None-未成功接收->查看流是否被使用(观看)->否->调用streamIdle事件
     EndRecord(Option<()>),
+    //推流状态通知：响应内容不敏感
+    PushState(Option<()>),
 }
 
 impl Event {
@@ -135,13 +146,19 @@ impl Event {
 
     fn hand_active(active_event: ActiveEvent, tx: Option<Sender<EventRes>>) {
         match active_event {
-            ActiveEvent::RtmpPush(_) => {}
+            ActiveEvent::RtmpPush(ctx) => {
+                ctx.push();
+            }
             ActiveEvent::LocalStoreMp4(ctx) => {
                 ctx.store();
             }
-            ActiveEvent::LocalStoreTs(_) => {}
+            ActiveEvent::LocalStoreTs(ctx) => {
+                ctx.store();
+            }
             ActiveEvent::RtspPush(_) => {}
-            ActiveEvent::Gb28181Push(_) => {}
+            ActiveEvent::Gb28181Push(ctx) => {
+                ctx.push();
+            }
             ActiveEvent::WebRtcPush(_) => {}
         }
     }
@@ -239,6 +256,20 @@ impl Event {
                 info!("end_record returned: {:?}", res);
                 let _ = res.hand_log(|msg| error!("{msg}"));
             }
+            OutEvent::PushState(info) => {
+                info!("Calling push_state with: {:?}", info);
+                let res = pretend.push_state(&info).await;
+                info!("push_state returned: {:?}", res);
+                let _ = res.hand_log(|msg| error!("{msg}"));
+            }
+            OutEvent::StreamCapture(stream_id, format, bytes) => {
+                info!(
+                    "Calling stream_capture: stream_id={}, size={}",
+                    stream_id,
+                    bytes.len()
+                );
+                let _ = HttpClient::stream_capture(&stream_id, format, bytes).await;
+            }
         }
     }
 }
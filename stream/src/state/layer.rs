@@ -1,9 +1,11 @@
 pub mod output_layer {
+    use crate::media::context::format::muxer::MuxerEnum;
+    use base::tokio_util::sync::CancellationToken;
     use shared::impl_close;
     use shared::info::output::{
         DashFmp4Output, DashMp4Output, Gb28181FrameOutput, Gb28181PsOutput, HlsFmp4Output,
-        HlsTsOutput, HttpFlvOutput, LocalMp4Output, LocalTsOutput, OutputKind, RtmpOutput,
-        RtspOutput, WebRtcOutput,
+        HlsTsOutput, HttpFlvOutput, LocalMp4Output, LocalTsOutput, OutputEnum, OutputKind,
+        RtmpOutput, RtspOutput, WebRtcOutput,
     };
     use shared::paste::paste;
 
@@ -174,6 +176,46 @@ pub mod output_layer {
             }
             false
         }
+
+        //移除输出，存在则返回true；主动推流输出同时取消推流任务
+        pub fn remove(&mut self, output: OutputEnum) -> bool {
+            match output {
+                OutputEnum::HttpFlv => self.http_flv.take().is_some(),
+                OutputEnum::Rtmp => self.rtmp.take().map(|l| l.cancel.cancel()).is_some(),
+                OutputEnum::DashFmp4 => self.dash_fmp4.take().is_some(),
+                OutputEnum::DashMp4 => self.dash_mp4.take().is_some(),
+                OutputEnum::HlsFmp4 => self.hls_fmp4.take().is_some(),
+                OutputEnum::HlsTs => self.hls_ts.take().is_some(),
+                OutputEnum::Rtsp => self.rtsp.take().is_some(),
+                OutputEnum::Gb28181Frame => self
+                    .gb28181_frame
+                    .take()
+                    .map(|l| l.cancel.cancel())
+                    .is_some(),
+                OutputEnum::Gb28181Ps => {
+                    self.gb28181_ps.take().map(|l| l.cancel.cancel()).is_some()
+                }
+                OutputEnum::WebRtc => self.web_rtc.take().is_some(),
+                OutputEnum::LocalMp4 => self.local_mp4.take().is_some(),
+                OutputEnum::LocalTs => self.local_ts.take().is_some(),
+            }
+        }
+
+        //是否仍有输出依赖该muxer
+        pub fn muxer_in_use(&self, muxer: MuxerEnum) -> bool {
+            match muxer {
+                MuxerEnum::Flv => self.http_flv.is_some() || self.rtmp.is_some(),
+                MuxerEnum::Mp4 => self.local_mp4.is_some(),
+                MuxerEnum::Ts => self.local_ts.is_some(),
+                MuxerEnum::FMp4 => self.dash_fmp4.is_some(),
+                MuxerEnum::HlsMp4 => self.hls_fmp4.is_some(),
+                MuxerEnum::DashMp4 => self.dash_mp4.is_some(),
+                MuxerEnum::HlsTs => self.hls_ts.is_some(),
+                MuxerEnum::RtpFrame => self.rtsp.is_some() || self.gb28181_frame.is_some(),
+                MuxerEnum::RtpPs => self.gb28181_ps.is_some(),
+                MuxerEnum::RtpEnc => self.web_rtc.is_some(),
+            }
+        }
     }
 
     pub struct LocalTsLayer {
@@ -219,10 +261,14 @@ pub mod output_layer {
     }
     pub struct RtmpLayer {
         pub rtmp: RtmpOutput,
+        pub cancel: CancellationToken,
     }
     impl RtmpLayer {
         pub fn layer(rtmp: RtmpOutput) -> Self {
-            Self { rtmp }
+            Self {
+                rtmp,
+                cancel: CancellationToken::new(),
+            }
         }
     }
     pub struct RtspLayer {
@@ -251,18 +297,26 @@ pub mod output_layer {
     }
     pub struct Gb28181FrameLayer {
         pub gb28181_frame: Gb28181FrameOutput,
+        pub cancel: CancellationToken,
     }
     impl Gb28181FrameLayer {
         pub fn layer(gb28181_frame: Gb28181FrameOutput) -> Self {
-            Self { gb28181_frame }
+            Self {
+                gb28181_frame,
+                cancel: CancellationToken::new(),
+            }
         }
     }
     pub struct Gb28181PsLayer {
         pub gb28181_ps: Gb28181PsOutput,
+        pub cancel: CancellationToken,
     }
     impl Gb28181PsLayer {
         pub fn layer(gb28181_ps: Gb28181PsOutput) -> Self {
-            Self { gb28181_ps }
+            Self {
+                gb28181_ps,
+                cancel: CancellationToken::new(),
+            }
         }
     }
     pub struct WebRtcLayer {
@@ -273,28 +327,55 @@ pub mod output_layer {
             Self { web_rtc }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::OutputLayer;
+        use crate::media::context::format::muxer::MuxerEnum;
+        use shared::info::output::{HttpFlvOutput, OutputEnum, OutputKind, RtmpOutput};
+
+        #[test]
+        fn remove_output_keeps_shared_muxer() {
+            let mut layer = OutputLayer::new(OutputKind::HttpFlv(HttpFlvOutput {
+                fmt: Default::default(),
+            }));
+            assert!(layer.put_if_absent(OutputKind::Rtmp(RtmpOutput {
+                fmt: Default::default(),
+                url: "rtmp://127.0.0.1/live/test".to_string(),
+                max_retry: None,
+            })));
+            let cancel = layer.rtmp.as_ref().unwrap().cancel.clone();
+            assert!(layer.remove(OutputEnum::Rtmp));
+            assert!(cancel.is_cancelled());
+            assert!(!layer.remove(OutputEnum::Rtmp));
+            //http-flv 仍复用 flv muxer
+            assert!(layer.muxer_in_use(MuxerEnum::Flv));
+            assert!(layer.remove(OutputEnum::HttpFlv));
+            assert!(!layer.muxer_in_use(MuxerEnum::Flv));
+        }
+    }
 }
 
 pub mod converter_layer {
-    use crate::state::layer::codec_layer::CodecLayer;
+    use crate::state::layer::codec_layer::TranscodeLayer;
     use crate::state::layer::filter_layer::FilterLayer;
     use crate::state::layer::muxer_layer::MuxerLayer;
-    use shared::info::codec::Codec;
+    use shared::info::codec::Transcode;
     use shared::info::filter::Filter;
     use shared::info::output::OutputKind;
 
     #[derive(Clone)]
     pub struct ConverterLayer {
-        pub codec: Option<CodecLayer>,
+        pub codec: Option<TranscodeLayer>,
         pub muxer: MuxerLayer,
         pub filter: FilterLayer,
     }
 
     impl ConverterLayer {
-        pub fn new(codec: Option<Codec>, filter: Filter, output: &OutputKind) -> Self {
+        pub fn new(codec: Option<Transcode>, filter: Filter, output: &OutputKind) -> Self {
             let muxer = MuxerLayer::new(output);
             let filter = FilterLayer::new(filter);
-            let codec = codec.map(CodecLayer::new);
+            let codec = codec.and_then(TranscodeLayer::new);
             Self {
                 codec,
                 muxer,
@@ -304,43 +385,272 @@ pub mod converter_layer {
     }
 }
 pub mod filter_layer {
-    use shared::info::filter::{Capture, Filter};
+    use shared::info::filter::{Capture, Crop, Filter, ImageFormat, Mirror, Osd, Rotate, Scale};
+    use std::time::Duration;
+
+    const DEFAULT_OSD_POS: u32 = 10;
+    const DEFAULT_OSD_FONT_SIZE: u32 = 24;
+    //drawtext 展开本地时间，冒号需转义
+    const OSD_TIMESTAMP: &str = r"%{localtime:%Y-%m-%d %H\:%M\:%S}";
 
+    /// 抽图：interval 为空时仅缓存关键帧供按需抓拍
     #[derive(Clone)]
-    pub struct CaptureLayer {}
+    pub struct CaptureLayer {
+        pub interval: Option<Duration>,
+        pub format: ImageFormat,
+    }
     impl CaptureLayer {
         pub fn layer(capture: Capture) -> Self {
-            unimplemented!()
+            CaptureLayer {
+                interval: capture
+                    .interval
+                    .filter(|secs| *secs > 0)
+                    .map(|secs| Duration::from_secs(secs as u64)),
+                format: capture.format.unwrap_or_default(),
+            }
         }
     }
     #[derive(Clone)]
     pub struct FilterLayer {
         //抽图
         pub capture: Option<CaptureLayer>,
-        //缩放
-        // pub scale: Option<Scale>,
-        //裁剪
-        // pub crop: Option<Crop>,
-        //旋转
-        // pub rotate: Option<Rotate>,
-        //镜像
-        // pub mirror: Option<Mirror>,
+        //缩放/裁剪/旋转/镜像/水印
+        pub video: Option<VideoFilterLayer>,
     }
 
     impl FilterLayer {
         pub fn put_if_absent(&mut self, filter: Filter) {
+            if self.video.is_none() {
+                self.video = VideoFilterLayer::new(&filter);
+            }
             if self.capture.is_none() {
-                {
-                    self.capture = filter.capture.map(CaptureLayer::layer);
-                }
+                self.capture = filter.capture.map(CaptureLayer::layer);
             }
         }
         pub fn new(filter: Filter) -> Self {
             FilterLayer {
+                video: VideoFilterLayer::new(&filter),
                 capture: filter.capture.map(CaptureLayer::layer),
             }
         }
     }
+
+    /// libavfilter 滤镜步骤：(滤镜名, 参数)
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct FilterStep {
+        pub name: &'static str,
+        pub opts: Vec<(&'static str, String)>,
+    }
+
+    impl FilterStep {
+        fn new(name: &'static str) -> Self {
+            Self { name, opts: vec![] }
+        }
+        fn opt(mut self, key: &'static str, value: impl ToString) -> Self {
+            self.opts.push((key, value.to_string()));
+            self
+        }
+    }
+
+    /// 按 裁剪->缩放->旋转->镜像->水印 顺序串联
+    #[derive(Clone)]
+    pub struct VideoFilterLayer {
+        pub steps: Vec<FilterStep>,
+    }
+
+    impl VideoFilterLayer {
+        pub fn new(filter: &Filter) -> Option<Self> {
+            let mut steps = Vec::new();
+            if let Some(crop) = &filter.crop {
+                steps.extend(crop_step(crop));
+            }
+            if let Some(scale) = &filter.scale {
+                steps.extend(scale_step(scale));
+            }
+            match filter.rotate {
+                Some(Rotate::Rotate90) => {
+                    steps.push(FilterStep::new("transpose").opt("dir", "clock"))
+                }
+                Some(Rotate::Rotate180) => {
+                    steps.push(FilterStep::new("hflip"));
+                    steps.push(FilterStep::new("vflip"));
+                }
+                Some(Rotate::Rotate270) => {
+                    steps.push(FilterStep::new("transpose").opt("dir", "cclock"))
+                }
+                None => {}
+            }
+            match filter.mirror {
+                Some(Mirror::Horizontal) => steps.push(FilterStep::new("hflip")),
+                Some(Mirror::Vertical) => steps.push(FilterStep::new("vflip")),
+                None => {}
+            }
+            if let Some(osd) = &filter.osd {
+                steps.extend(osd_step(osd));
+            }
+            if steps.is_empty() {
+                return None;
+            }
+            Some(Self { steps })
+        }
+
+        /// 替换水印，水印始终位于最后
+        pub fn set_osd(&mut self, osd: Option<&Osd>) {
+            self.steps.retain(|step| step.name != "drawtext");
+            self.steps.extend(osd.and_then(osd_step));
+        }
+    }
+
+    fn crop_step(crop: &Crop) -> Option<FilterStep> {
+        if crop.width == 0 || crop.height == 0 {
+            return None;
+        }
+        Some(
+            FilterStep::new("crop")
+                .opt("x", crop.x)
+                .opt("y", crop.y)
+                .opt("w", crop.width & !1)
+                .opt("h", crop.height & !1),
+        )
+    }
+
+    fn scale_step(scale: &Scale) -> Option<FilterStep> {
+        //0 表示按比例计算，-2 保证结果为偶数
+        let side = |v: u32| if v == 0 { -2 } else { (v & !1) as i64 };
+        if scale.width == 0 && scale.height == 0 {
+            return None;
+        }
+        Some(
+            FilterStep::new("scale")
+                .opt("w", side(scale.width))
+                .opt("h", side(scale.height)),
+        )
+    }
+
+    fn osd_step(osd: &Osd) -> Option<FilterStep> {
+        let mut text = osd
+            .text
+            .as_deref()
+            .filter(|text| !text.is_empty())
+            .map(escape_osd_text)
+            .unwrap_or_default();
+        if osd.timestamp {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(OSD_TIMESTAMP);
+        }
+        if text.is_empty() {
+            return None;
+        }
+        let mut step = FilterStep::new("drawtext")
+            .opt("text", text)
+            .opt("x", osd.x.unwrap_or(DEFAULT_OSD_POS))
+            .opt("y", osd.y.unwrap_or(DEFAULT_OSD_POS))
+            .opt("fontsize", osd.font_size.unwrap_or(DEFAULT_OSD_FONT_SIZE))
+            .opt("fontcolor", "white")
+            .opt("box", 1)
+            .opt("boxcolor", "black@0.4");
+        if let Some(font_file) = osd.font_file.as_deref().filter(|f| !f.is_empty()) {
+            step = step.opt("fontfile", font_file);
+        }
+        Some(step)
+    }
+
+    //drawtext 文本中 \ 与 % 为展开控制符
+    fn escape_osd_text(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if matches!(c, '\\' | '%') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{CaptureLayer, VideoFilterLayer};
+        use shared::info::filter::{
+            Capture, Crop, Filter, ImageFormat, Mirror, Osd, Rotate, Scale,
+        };
+        use std::time::Duration;
+
+        #[test]
+        fn builds_steps_in_order() {
+            let filter = Filter {
+                scale: Some(Scale {
+                    width: 641,
+                    height: 0,
+                }),
+                crop: Some(Crop {
+                    x: 0,
+                    y: 0,
+                    width: 1280,
+                    height: 720,
+                }),
+                rotate: Some(Rotate::Rotate180),
+                mirror: Some(Mirror::Horizontal),
+                osd: Some(Osd {
+                    text: Some("100%".to_string()),
+                    timestamp: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let layer = VideoFilterLayer::new(&filter).unwrap();
+            let names = layer.steps.iter().map(|s| s.name).collect::<Vec<_>>();
+            assert_eq!(
+                names,
+                ["crop", "scale", "hflip", "vflip", "hflip", "drawtext"]
+            );
+            assert_eq!(
+                layer.steps[1].opts,
+                [("w", "640".to_string()), ("h", "-2".to_string())]
+            );
+            assert!(layer.steps[5].opts[0].1.starts_with(r"100\% %{localtime"));
+        }
+
+        #[test]
+        fn empty_filter_has_no_video_layer() {
+            assert!(VideoFilterLayer::new(&Filter::default()).is_none());
+            let mut layer = VideoFilterLayer::new(&Filter {
+                mirror: Some(Mirror::Vertical),
+                ..Default::default()
+            })
+            .unwrap();
+            layer.set_osd(Some(&Osd::default()));
+            assert_eq!(layer.steps.len(), 1);
+        }
+
+        #[test]
+        fn capture_layer_defaults() {
+            let layer = CaptureLayer::layer(Capture {
+                interval: Some(0),
+                format: None,
+            });
+            assert!(layer.interval.is_none());
+            assert_eq!(layer.format, ImageFormat::Jpeg);
+            let layer = CaptureLayer::layer(Capture {
+                interval: Some(5),
+                format: Some(ImageFormat::Png),
+            });
+            assert_eq!(layer.interval, Some(Duration::from_secs(5)));
+            assert_eq!(layer.format, ImageFormat::Png);
+            //抽图不依赖视频滤镜
+            assert!(
+                !Filter {
+                    capture: Some(Capture {
+                        interval: None,
+                        format: None
+                    }),
+                    ..Default::default()
+                }
+                .has_video_filter()
+            );
+        }
+    }
 }
 pub mod muxer_layer {
     use crate::media::context::format::MuxPacket;
@@ -351,7 +661,7 @@ pub mod muxer_layer {
     use base::log::error;
     use base::tokio::sync::broadcast;
     use shared::info::format::{CMaf, HlsTs, Mp4, RtpEnc, RtpFrame, RtpPs, Ts};
-    use shared::info::output::OutputKind;
+    use shared::info::output::{Gb28181FrameOutput, OutputKind, RtspOutput};
     use std::sync::Arc;
 
     #[derive(Clone, Default)]
@@ -394,7 +704,14 @@ pub mod muxer_layer {
                     Ok(self.mp4.as_ref().unwrap().tx.subscribe())
                 }
                 MuxerEnum::Ts => {
-                    unimplemented!()
+                    if self.ts.is_none() {
+                        Err(GlobalError::new_biz_error(
+                            BaseErrorCode::InvalidState.code(),
+                            &format!("muxer: {:?}未开启", muxer_enum),
+                            |msg| error!("{msg}"),
+                        ))?;
+                    }
+                    Ok(self.ts.as_ref().unwrap().tx.subscribe())
                 }
                 MuxerEnum::FMp4 => {
                     if self.fmp4.is_none() {
@@ -407,16 +724,44 @@ pub mod muxer_layer {
                     Ok(self.fmp4.as_ref().unwrap().tx.subscribe())
                 }
                 MuxerEnum::HlsTs => {
-                    unimplemented!()
+                    if self.hls_ts.is_none() {
+                        Err(GlobalError::new_biz_error(
+                            BaseErrorCode::InvalidState.code(),
+                            &format!("muxer: {:?}未开启", muxer_enum),
+                            |msg| error!("{msg}"),
+                        ))?;
+                    }
+                    Ok(self.hls_ts.as_ref().unwrap().tx.subscribe())
                 }
                 MuxerEnum::RtpFrame => {
-                    unimplemented!()
+                    if self.rtp_frame.is_none() {
+                        Err(GlobalError::new_biz_error(
+                            BaseErrorCode::InvalidState.code(),
+                            &format!("muxer: {:?}未开启", muxer_enum),
+                            |msg| error!("{msg}"),
+                        ))?;
+                    }
+                    Ok(self.rtp_frame.as_ref().unwrap().tx.subscribe())
                 }
                 MuxerEnum::RtpPs => {
-                    unimplemented!()
+                    if self.rtp_ps.is_none() {
+                        Err(GlobalError::new_biz_error(
+                            BaseErrorCode::InvalidState.code(),
+                            &format!("muxer: {:?}未开启", muxer_enum),
+                            |msg| error!("{msg}"),
+                        ))?;
+                    }
+                    Ok(self.rtp_ps.as_ref().unwrap().tx.subscribe())
                 }
                 MuxerEnum::RtpEnc => {
-                    unimplemented!()
+                    if self.rtp_enc.is_none() {
+                        Err(GlobalError::new_biz_error(
+                            BaseErrorCode::InvalidState.code(),
+                            &format!("muxer: {:?}未开启", muxer_enum),
+                            |msg| error!("{msg}"),
+                        ))?;
+                    }
+                    Ok(self.rtp_enc.as_ref().unwrap().tx.subscribe())
                 }
                 MuxerEnum::DashMp4 => {
                     if self.dash_mp4.is_none() {
@@ -458,28 +803,29 @@ pub mod muxer_layer {
                     }
                 }
                 OutputKind::HlsFmp4(_) => {
-                    if self.fmp4.is_none() {
-                        self.fmp4 = Some(CMafLayer::layer(CMaf::default()));
+                    if self.hls_mp4.is_none() {
+                        self.hls_mp4 = Some(CMafLayer::layer(CMaf::default()));
                     }
                 }
                 OutputKind::HlsTs(inner) => {
                     if self.hls_ts.is_none() {
-                        unimplemented!()
+                        self.hls_ts = Some(HlsTsLayer::layer(inner.fmt.clone()));
                     }
                 }
-                OutputKind::Rtsp(_) | OutputKind::Gb28181Frame(_) => {
+                OutputKind::Rtsp(RtspOutput { fmt })
+                | OutputKind::Gb28181Frame(Gb28181FrameOutput { fmt, .. }) => {
                     if self.rtp_frame.is_none() {
-                        unimplemented!()
+                        self.rtp_frame = Some(RtpFrameLayer::layer(fmt.clone()));
                     }
                 }
                 OutputKind::Gb28181Ps(inner) => {
                     if self.rtp_ps.is_none() {
-                        unimplemented!()
+                        self.rtp_ps = Some(RtpPsLayer::layer(inner.fmt.clone()));
                     }
                 }
                 OutputKind::WebRtc(inner) => {
                     if self.rtp_enc.is_none() {
-                        unimplemented!()
+                        self.rtp_enc = Some(RtpEncLayer::layer(inner.fmt.clone()));
                     }
                 }
                 OutputKind::LocalMp4(inner) => {
@@ -489,7 +835,7 @@ pub mod muxer_layer {
                 }
                 OutputKind::LocalTs(inner) => {
                     if self.ts.is_none() {
-                        unimplemented!()
+                        self.ts = Some(TsLayer::layer(inner.fmt.clone()));
                     }
                 }
                 OutputKind::DashMp4(_) => {
@@ -538,10 +884,14 @@ pub mod muxer_layer {
     }
 
     #[derive(Clone)]
-    pub struct TsLayer {}
+    pub struct TsLayer {
+        pub tx: broadcast::Sender<Arc<MuxPacket>>,
+        pub ts: Ts,
+    }
     impl TsLayer {
         pub fn layer(ts: Ts) -> Self {
-            unimplemented!()
+            let (tx, _) = broadcast::channel(FORMAT_BROADCAST_BUFFER);
+            Self { tx, ts }
         }
     }
 
@@ -556,37 +906,113 @@ pub mod muxer_layer {
         }
     }
     #[derive(Clone)]
-    pub struct RtpFrameLayer {}
+    pub struct RtpFrameLayer {
+        pub tx: broadcast::Sender<Arc<MuxPacket>>,
+        pub rtp: RtpFrame,
+    }
     impl RtpFrameLayer {
         pub fn layer(rtp: RtpFrame) -> Self {
-            unimplemented!()
+            let (tx, _) = broadcast::channel(FORMAT_BROADCAST_BUFFER);
+            Self { tx, rtp }
         }
     }
     #[derive(Clone)]
-    pub struct RtpPsLayer {}
+    pub struct RtpPsLayer {
+        pub tx: broadcast::Sender<Arc<MuxPacket>>,
+        pub rtp: RtpPs,
+    }
     impl RtpPsLayer {
         pub fn layer(rtp: RtpPs) -> Self {
-            unimplemented!()
+            let (tx, _) = broadcast::channel(FORMAT_BROADCAST_BUFFER);
+            Self { tx, rtp }
         }
     }
     #[derive(Clone)]
-    pub struct RtpEncLayer {}
+    pub struct RtpEncLayer {
+        pub tx: broadcast::Sender<Arc<MuxPacket>>,
+        pub rtp: RtpEnc,
+    }
     impl RtpEncLayer {
         pub fn layer(rtp: RtpEnc) -> Self {
-            unimplemented!()
+            let (tx, _) = broadcast::channel(FORMAT_BROADCAST_BUFFER);
+            Self { tx, rtp }
         }
     }
 
     #[derive(Clone)]
-    pub struct HlsTsLayer {}
+    pub struct HlsTsLayer {
+        pub tx: broadcast::Sender<Arc<MuxPacket>>,
+    }
     impl HlsTsLayer {
         pub fn layer(hls_ts: HlsTs) -> Self {
-            unimplemented!()
+            let (tx, _) = broadcast::channel(FORMAT_BROADCAST_BUFFER);
+            Self { tx }
         }
     }
 }
 pub mod codec_layer {
-    use shared::info::codec::Codec;
+    use shared::info::codec::{Codec, Transcode};
+
+    const DEFAULT_VIDEO_BITRATE_KBPS: u32 = 2048;
+    const DEFAULT_GOP: u32 = 50;
+    const DEFAULT_AUDIO_BITRATE_KBPS: u32 = 64;
+
+    #[derive(Clone)]
+    pub struct TranscodeLayer {
+        pub video: Option<VideoTranscodeLayer>,
+        pub audio: Option<AudioTranscodeLayer>,
+    }
+
+    #[derive(Clone)]
+    pub struct VideoTranscodeLayer {
+        pub codec: CodecLayer,
+        //bps
+        pub bit_rate: i64,
+        pub gop: i32,
+    }
+
+    #[derive(Clone)]
+    pub struct AudioTranscodeLayer {
+        pub codec: CodecLayer,
+        //bps
+        pub bit_rate: i64,
+        //None:沿用输入采样率
+        pub sample_rate: Option<i32>,
+    }
+
+    impl VideoTranscodeLayer {
+        //仅因滤镜而重编码时使用默认码率与GOP
+        pub fn with_codec(codec: CodecLayer) -> Self {
+            Self {
+                codec,
+                bit_rate: i64::from(DEFAULT_VIDEO_BITRATE_KBPS) * 1000,
+                gop: DEFAULT_GOP as i32,
+            }
+        }
+    }
+
+    impl TranscodeLayer {
+        //音视频均未指定目标编码时不建立转码层
+        pub fn new(transcode: Transcode) -> Option<Self> {
+            let video = transcode.video.map(|video| VideoTranscodeLayer {
+                codec: CodecLayer::new(video.codec),
+                bit_rate: i64::from(video.bitrate.unwrap_or(DEFAULT_VIDEO_BITRATE_KBPS)) * 1000,
+                gop: video.gop.unwrap_or(DEFAULT_GOP).clamp(1, i32::MAX as u32) as i32,
+            });
+            let audio = transcode.audio.map(|audio| AudioTranscodeLayer {
+                codec: CodecLayer::new(audio.codec),
+                bit_rate: i64::from(audio.bitrate.unwrap_or(DEFAULT_AUDIO_BITRATE_KBPS)) * 1000,
+                sample_rate: audio
+                    .sample_rate
+                    .filter(|rate| *rate > 0)
+                    .map(|rate| rate.min(i32::MAX as u32) as i32),
+            });
+            if video.is_none() && audio.is_none() {
+                return None;
+            }
+            Some(Self { video, audio })
+        }
+    }
 
     #[derive(Clone)]
     pub enum CodecLayer {
@@ -636,4 +1062,28 @@ pub mod codec_layer {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::TranscodeLayer;
+        use shared::info::codec::{Codec, Transcode, VideoTranscode};
+
+        #[test]
+        fn transcode_layer_defaults() {
+            assert!(TranscodeLayer::new(Transcode::default()).is_none());
+            let layer = TranscodeLayer::new(Transcode {
+                video: Some(VideoTranscode {
+                    codec: Codec::H264,
+                    bitrate: None,
+                    gop: Some(0),
+                }),
+                audio: None,
+            })
+            .unwrap();
+            let video = layer.video.unwrap();
+            assert_eq!(video.bit_rate, 2_048_000);
+            assert_eq!(video.gop, 1);
+            assert!(layer.audio.is_none());
+        }
+    }
 }
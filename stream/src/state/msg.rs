@@ -1,12 +1,16 @@
 use crate::media::context::event::ContextEvent;
+use crate::media::rtcp::RtcpSession;
 use crate::media::rtp::RtpPacket;
 use crate::state::layer::converter_layer::ConverterLayer;
 use base::bus::mpsc::TypedReceiver;
 use shared::info::media_info_ext::MediaExt;
+use std::sync::Arc;
 
 pub struct StreamConfig {
+    pub stream_id: Arc<str>,
     pub converter: ConverterLayer,
     pub context_event_rx: TypedReceiver<ContextEvent>,
     pub media_ext: MediaExt,
     pub rtp_rx: crossbeam_channel::Receiver<RtpPacket>,
+    pub rtcp: Arc<RtcpSession>,
 }
@@ -2,10 +2,14 @@ use crate::general::cfg;
 use crate::general::cfg::{ServerConf, StreamConf};
 use crate::general::util::Placeholder;
 use crate::io::local::mp4::{LocalStoreMp4Context, Mp4OutputInnerEvent};
+use crate::io::local::ts::{LocalStoreTsContext, TsOutputInnerEvent};
+use crate::io::push::gb28181::Gb28181PushContext;
+use crate::io::push::rtmp::RtmpPushContext;
 use crate::media::context::event::ContextEvent;
-use crate::media::context::event::muxer::MuxerEvent;
+use crate::media::context::event::muxer::{MuxerEvent, MuxerKind};
 use crate::media::context::format::MuxPacket;
 use crate::media::context::format::muxer::MuxerEnum;
+use crate::media::rtcp::RtcpSession;
 use crate::media::rtp::RtpPacket;
 use crate::state::event::{ActiveEvent, Event, EventRes, InnerEvent, OutEvent};
 use crate::state::layer::converter_layer::ConverterLayer;
@@ -26,7 +30,7 @@ use base::tokio::select;
 use base::tokio::sync::oneshot::Sender;
 use base::tokio::sync::{broadcast, mpsc};
 use base::utils::rt::GlobalRuntime;
-use log::{error, info};
+use log::{error, info, warn};
 use shared::enums::OptAction;
 use shared::info::media_info::MediaConfig;
 use shared::info::media_info_ext::MediaExt;
@@ -66,6 +70,7 @@ pub struct RtpChannel {
     pub wait_sign_in: AtomicBool,
     pub stream_id: Arc<str>,
     pub miss_pkt: AtomicUsize,
+    pub rtcp: Arc<RtcpSession>,
 }
 impl RtpChannel {
     fn new(stream_id: Arc<str>) -> RtpChannel {
@@ -77,6 +82,7 @@ impl RtpChannel {
             wait_sign_in: AtomicBool::new(true),
             stream_id,
             miss_pkt: AtomicUsize::new(0),
+            rtcp: Arc::new(RtcpSession::new()),
         }
     }
     fn get_rtp_rx(&self) -> crossbeam_channel::Receiver<RtpPacket> {
@@ -173,6 +179,11 @@ impl UnknownStreamObservation {
         now_ms.saturating_sub(self.last_seen_at_ms)
     }
 }
+//RTCP 可能来自独立端口(UDP源端口+1)或复用RTP连接，按源IP校验；未收到RTP前不信任任何来源
+fn rtcp_from_origin(origin_trans: Option<(SocketAddr, Protocol)>, remote_addr: SocketAddr) -> bool {
+    origin_trans.is_some_and(|(origin, _)| origin.ip() == remote_addr.ip())
+}
+
 pub struct StreamMetadata {
     pub ssrc: u32,
     pub output_count: OutputCount,
@@ -224,24 +235,62 @@ impl StreamMetadata {
     ) -> Option<ActiveEvent> {
         match output_kind {
             OutputKind::HttpFlv(_) => None,
-            OutputKind::Rtmp(_) => {
-                unimplemented!()
+            OutputKind::Rtmp(info) => {
+                let pkt_rx = self
+                    .converter
+                    .muxer
+                    .get_rx(MuxerEnum::Flv)
+                    .hand_log(|msg| error!("{msg}"))
+                    .ok()?;
+                Some(ActiveEvent::RtmpPush(RtmpPushContext {
+                    stream_id: stream_id.clone(),
+                    ssrc,
+                    url: info.url,
+                    max_retry: info.max_retry,
+                    pkt_rx,
+                    event_tx,
+                    cancel: self.output.rtmp.as_ref()?.cancel.clone(),
+                }))
             }
             OutputKind::DashFmp4(_) => None,
             OutputKind::HlsFmp4(_) => None,
             OutputKind::HlsTs(_) => None,
-            OutputKind::Rtsp(_) => {
-                unimplemented!()
-            }
-            OutputKind::Gb28181Frame(_) => {
-                unimplemented!()
-            }
-            OutputKind::Gb28181Ps(_) => {
-                unimplemented!()
+            OutputKind::Rtsp(_) => None,
+            OutputKind::Gb28181Frame(info) => {
+                let pkt_rx = self
+                    .converter
+                    .muxer
+                    .get_rx(MuxerEnum::RtpFrame)
+                    .hand_log(|msg| error!("{msg}"))
+                    .ok()?;
+                Some(ActiveEvent::Gb28181Push(Gb28181PushContext {
+                    stream_id: stream_id.clone(),
+                    ssrc,
+                    output: OutputEnum::Gb28181Frame,
+                    target: info.target,
+                    pkt_rx,
+                    event_tx,
+                    cancel: self.output.gb28181_frame.as_ref()?.cancel.clone(),
+                }))
             }
-            OutputKind::WebRtc(_) => {
-                unimplemented!()
+            OutputKind::Gb28181Ps(info) => {
+                let pkt_rx = self
+                    .converter
+                    .muxer
+                    .get_rx(MuxerEnum::RtpPs)
+                    .hand_log(|msg| error!("{msg}"))
+                    .ok()?;
+                Some(ActiveEvent::Gb28181Push(Gb28181PushContext {
+                    stream_id: stream_id.clone(),
+                    ssrc,
+                    output: OutputEnum::Gb28181Ps,
+                    target: info.target,
+                    pkt_rx,
+                    event_tx,
+                    cancel: self.output.gb28181_ps.as_ref()?.cancel.clone(),
+                }))
             }
+            OutputKind::WebRtc(_) => None,
             OutputKind::LocalMp4(info) => {
                 let context = LocalStoreMp4Context {
                     path: info.path,
@@ -259,8 +308,30 @@ impl StreamMetadata {
                 };
                 Some(ActiveEvent::LocalStoreMp4(context))
             }
-            OutputKind::LocalTs(_) => {
-                unimplemented!()
+            OutputKind::LocalTs(info) => {
+                let pkt_rx = self
+                    .converter
+                    .muxer
+                    .get_rx(MuxerEnum::Ts)
+                    .hand_log(|msg| error!("{msg}"))
+                    .ok()?;
+                let inner_event_rx = self
+                    .mpsc_bus
+                    .sub_type_channel::<TsOutputInnerEvent>()
+                    .hand_log(|msg| error!("{msg}"))
+                    .ok()?;
+                let context = LocalStoreTsContext {
+                    path: info.path,
+                    ssrc,
+                    file_name: stream_id.clone(),
+                    pkt_rx,
+                    record_event_tx: event_tx,
+                    inner_event_rx,
+                    file_size: 0,
+                    ts: 0,
+                    state: 0,
+                };
+                Some(ActiveEvent::LocalStoreTs(context))
             }
             OutputKind::DashMp4(_) => None,
         }
@@ -410,6 +481,54 @@ impl Register {
             }
         }
     }
+    //收到RTCP BYE：视为输入即将超时，立即走输入超时流程；来源须为该流的RTP源
+    pub fn handle_rtcp_bye(ssrc: u32, remote_addr: SocketAddr) {
+        let inner = Self::get().inner.clone();
+        if let Some(rc) = inner.rtp_gateway_map.get(&ssrc) {
+            if let Some(meta) = inner.stream_metadata_map.get(&rc.stream_id) {
+                if !rtcp_from_origin(meta.origin_trans, remote_addr) {
+                    warn!(
+                        "ssrc={ssrc}; rtcp bye from {remote_addr} is not the stream origin, ignored"
+                    );
+                    return;
+                }
+                rc.in_has_timeout
+                    .store(meta.in_wait_timeout.saturating_sub(1), Ordering::Relaxed);
+                let _ = inner
+                    .time_schedule
+                    .insert(TimeScheduleKey::RtpGateway(ssrc), Duration::ZERO)
+                    .hand_log(|msg| error!("{msg}"));
+                info!("ssrc={ssrc}; rtcp bye received");
+            }
+        }
+    }
+    pub fn rtcp_session(ssrc: u32) -> Option<Arc<RtcpSession>> {
+        Self::get()
+            .inner
+            .rtp_gateway_map
+            .get(&ssrc)
+            .map(|rc| rc.rtcp.clone())
+    }
+    //仅返回来源为该流RTP源的会话，用于接收SR/RR时学习对端
+    pub fn rtcp_session_from(ssrc: u32, remote_addr: SocketAddr) -> Option<Arc<RtcpSession>> {
+        let inner = Self::get().inner.clone();
+        let rc = inner.rtp_gateway_map.get(&ssrc)?;
+        let origin_trans = inner
+            .stream_metadata_map
+            .get(&rc.stream_id)
+            .and_then(|meta| meta.origin_trans);
+        rtcp_from_origin(origin_trans, remote_addr).then(|| rc.rtcp.clone())
+    }
+    //已注册的流：(ssrc, rtcp会话)，用于周期发送RR
+    pub fn rtcp_sessions() -> Vec<(u32, Arc<RtcpSession>)> {
+        Self::get()
+            .inner
+            .rtp_gateway_map
+            .iter()
+            .filter(|rc| !rc.wait_sign_in.load(Ordering::Relaxed))
+            .map(|rc| (*rc.key(), rc.rtcp.clone()))
+            .collect()
+    }
     pub fn close_stream_by_input(state: StreamState, res: InTimeoutEventRes) {
         let arc = Self::get().inner.clone();
         match res {
@@ -455,7 +574,7 @@ impl Register {
                         meta.converter.muxer.close_by_muxer_type(muxer_enum);
                         let _ = meta
                             .mpsc_bus
-                            .try_publish(MuxerEvent::Close(muxer_enum))
+                            .try_publish(ContextEvent::Muxer(MuxerEvent::Close(muxer_enum)))
                             .hand_log(|msg| info!("{msg}"));
                     }
                 });
@@ -769,10 +888,10 @@ impl Register {
         if let Some(meta) = arc.stream_metadata_map.get(&stream_id) {
             if let Some(media_ext) = meta.media_ext.as_ref() {
                 if media_ext.type_code == rtp_type {
-                    if let Some(rtp_rx) = arc
+                    if let Some((rtp_rx, rtcp)) = arc
                         .rtp_gateway_map
                         .get(&meta.ssrc)
-                        .map(|rtp_channel| rtp_channel.get_rtp_rx())
+                        .map(|rtp_channel| (rtp_channel.get_rtp_rx(), rtp_channel.rtcp.clone()))
                     {
                         if let Ok(converter_event_rx) = meta
                             .mpsc_bus
@@ -780,10 +899,12 @@ impl Register {
                             .hand_log(|msg| error!("{msg}"))
                         {
                             let stream_config = StreamConfig {
+                                stream_id: stream_id.clone(),
                                 converter: meta.converter.clone(),
                                 media_ext: meta.media_ext.clone().unwrap(),
                                 rtp_rx,
                                 context_event_rx: converter_event_rx,
+                                rtcp,
                             };
                             let _ = meta
                                 .mpsc_bus
@@ -909,6 +1030,110 @@ impl Register {
         }
         Ok(ssrc)
     }
+    //运行中的流追加输出：存在则返回false；muxer未开启时通知媒体上下文开启
+    pub fn open_output(ssrc: u32, output: OutputKind) -> GlobalResult<bool> {
+        let arc = Self::get().inner.clone();
+        let stream_id = Self::get_stream_id(&arc, ssrc)?;
+        let Some(mut meta) = arc.stream_metadata_map.get_mut(&stream_id) else {
+            return Err(GlobalError::new_biz_error(
+                BaseErrorCode::NotFound.code(),
+                "SSRC不存在或已超时丢弃",
+                |msg| error!("ssrc={}; {msg}", ssrc),
+            ));
+        };
+        if !meta.output.put_if_absent(output.clone()) {
+            return Ok(false);
+        }
+        let muxer_enum = MuxerEnum::from_output_enum(output.output_enum());
+        if MuxerKind::from_layer(&meta.converter.muxer, muxer_enum).is_none() {
+            meta.converter.muxer.put_if_absent(&output);
+            if let Some(kind) = MuxerKind::from_layer(&meta.converter.muxer, muxer_enum) {
+                info!(
+                    "ssrc = {},stream id = {} open muxer: {:?}",
+                    ssrc, stream_id, muxer_enum
+                );
+                //媒体上下文未启动时无订阅者，由stream config携带新增的muxer layer
+                let _ = meta
+                    .mpsc_bus
+                    .try_publish(ContextEvent::Muxer(MuxerEvent::Open(kind)))
+                    .hand_log(|msg| info!("{msg}"));
+            }
+        }
+        let event =
+            meta.build_from_output_kind(output, ssrc, stream_id.clone(), arc.event_tx.clone());
+        drop(meta);
+        if let Some(active_event) = event {
+            let _ = arc
+                .event_tx
+                .try_send((Event::Active(active_event), None))
+                .hand_log(|msg| error!("{msg}"));
+        }
+        Ok(true)
+    }
+
+    //运行中的流移除输出：不存在则返回false；muxer已无输出使用时关闭
+    pub fn close_output(ssrc: u32, output_enum: OutputEnum) -> GlobalResult<bool> {
+        let arc = Self::get().inner.clone();
+        let stream_id = Self::get_stream_id(&arc, ssrc)?;
+        let Some(mut meta) = arc.stream_metadata_map.get_mut(&stream_id) else {
+            return Err(GlobalError::new_biz_error(
+                BaseErrorCode::NotFound.code(),
+                "SSRC不存在或已超时丢弃",
+                |msg| error!("ssrc={}; {msg}", ssrc),
+            ));
+        };
+        if !meta.output.remove(output_enum) {
+            return Ok(false);
+        }
+        let muxer_enum = MuxerEnum::from_output_enum(output_enum);
+        if meta.output.muxer_in_use(muxer_enum)
+            || meta.output_count.get_muxer_viewers(muxer_enum, output_enum) > 0
+        {
+            //muxer仍被使用，仅结束本地录制
+            match output_enum {
+                OutputEnum::LocalMp4 => {
+                    let _ = meta
+                        .mpsc_bus
+                        .try_publish(Mp4OutputInnerEvent::Close)
+                        .hand_log(|msg| info!("{msg}"));
+                }
+                OutputEnum::LocalTs => {
+                    let _ = meta
+                        .mpsc_bus
+                        .try_publish(TsOutputInnerEvent::Close)
+                        .hand_log(|msg| info!("{msg}"));
+                }
+                _ => {}
+            }
+        } else {
+            info!(
+                "ssrc = {},stream id = {} close muxer: {:?}",
+                ssrc, stream_id, muxer_enum
+            );
+            //发送端drop后录制/推流随之结束，mp4/ts在关闭时写入尾部
+            meta.converter.muxer.close_by_muxer_type(muxer_enum);
+            let _ = meta
+                .mpsc_bus
+                .try_publish(ContextEvent::Muxer(MuxerEvent::Close(muxer_enum)))
+                .hand_log(|msg| info!("{msg}"));
+        }
+        Ok(true)
+    }
+
+    fn get_stream_id(inner: &Inner, ssrc: u32) -> GlobalResult<Arc<str>> {
+        inner
+            .rtp_gateway_map
+            .get(&ssrc)
+            .map(|rc| rc.stream_id.clone())
+            .ok_or_else(|| {
+                GlobalError::new_biz_error(
+                    BaseErrorCode::NotFound.code(),
+                    "SSRC不存在或已超时丢弃",
+                    |msg| error!("ssrc={}; {msg}", ssrc),
+                )
+            })
+    }
+
     pub fn init() -> GlobalResult<()> {
         if REGISTER.get().is_some() {
             return Ok(());
@@ -992,6 +1217,28 @@ impl OutputCount {
         }
     }
 
+    //同一muxer下其他输出的点播数量，排除@exclude自身
+    fn get_muxer_viewers(&self, muxer: MuxerEnum, exclude: OutputEnum) -> u32 {
+        [
+            OutputEnum::HttpFlv,
+            OutputEnum::Rtmp,
+            OutputEnum::DashFmp4,
+            OutputEnum::DashMp4,
+            OutputEnum::HlsFmp4,
+            OutputEnum::HlsTs,
+            OutputEnum::Rtsp,
+            OutputEnum::Gb28181Frame,
+            OutputEnum::Gb28181Ps,
+            OutputEnum::WebRtc,
+            OutputEnum::LocalMp4,
+            OutputEnum::LocalTs,
+        ]
+        .into_iter()
+        .filter(|output| *output != exclude && MuxerEnum::from_output_enum(*output) == muxer)
+        .map(|output| self.get_muxer_size(output))
+        .sum()
+    }
+
     //增加@OutputEnum点播数量，返回该output的当前点播数量
     fn add(&self, output: OutputEnum) -> u32 {
         (match output {